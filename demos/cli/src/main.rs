@@ -164,6 +164,12 @@ struct ImageSettings {
     /// Scale applied to the model before rendering
     #[clap(long, default_value_t = 1.0)]
     scale: f32,
+
+    /// Comma-separated tile sizes (e.g. `128,32,8`), largest first
+    ///
+    /// By default, tile sizes are selected based on the evaluator flavor
+    #[clap(long)]
+    tile_sizes: Option<fidget::render::TileSizes>,
 }
 
 #[derive(Parser)]
@@ -175,10 +181,16 @@ struct MeshSettings {
     #[clap(short, long)]
     depth: u8,
 
-    /// Name of a `.stl` file to write
+    /// Name of a `.stl` or `.obj` file to write
     #[clap(short, long)]
     out: Option<PathBuf>,
 
+    /// Crease angle (in degrees) for smoothed vertex normals
+    ///
+    /// Normals are only written to `.obj` files
+    #[clap(long)]
+    crease_angle: Option<f32>,
+
     /// Evaluator flavor
     #[clap(short, long, value_enum, default_value_t)]
     eval: EvalMode,
@@ -270,18 +282,18 @@ fn run3d<F: fidget::eval::Function + fidget::render::RenderHints>(
 ) -> Vec<u8> {
     let threads = match settings.threads {
         Some(n) if n.get() == 1 => None,
-        Some(n) => Some(fidget::render::ThreadPool::Custom(
-            rayon::ThreadPoolBuilder::new()
-                .num_threads(n.get())
-                .build()
-                .unwrap(),
-        )),
+        Some(n) => {
+            Some(fidget::render::ThreadPool::with_threads(n.get()).unwrap())
+        }
         None => Some(fidget::render::ThreadPool::Global),
     };
     let threads = threads.as_ref();
     let cfg = fidget::raster::VoxelRenderConfig {
         image_size: fidget::render::VoxelSize::from(settings.size),
-        tile_sizes: F::tile_sizes_3d(),
+        tile_sizes: settings
+            .tile_sizes
+            .clone()
+            .unwrap_or_else(F::tile_sizes_3d),
         threads,
         world_to_model,
         ..Default::default()
@@ -404,7 +416,7 @@ fn run3d<F: fidget::eval::Function + fidget::render::RenderHints>(
 
 ////////////////////////////////////////////////////////////////////////////////
 
-fn run2d<F: fidget::eval::Function + fidget::render::RenderHints>(
+fn run2d<F: fidget::eval::MathFunction + fidget::render::RenderHints>(
     shape: fidget::shape::Shape<F>,
     world_to_model: nalgebra::Matrix3<f32>,
     settings: &ImageSettings,
@@ -438,17 +450,17 @@ fn run2d<F: fidget::eval::Function + fidget::render::RenderHints>(
     } else {
         let threads = match settings.threads {
             Some(n) if n.get() == 1 => None,
-            Some(n) => Some(fidget::render::ThreadPool::Custom(
-                rayon::ThreadPoolBuilder::new()
-                    .num_threads(n.get())
-                    .build()
-                    .unwrap(),
-            )),
+            Some(n) => {
+                Some(fidget::render::ThreadPool::with_threads(n.get()).unwrap())
+            }
             None => Some(fidget::render::ThreadPool::Global),
         };
         let cfg = fidget::raster::ImageRenderConfig {
             image_size: fidget::render::ImageSize::from(settings.size),
-            tile_sizes: F::tile_sizes_2d(),
+            tile_sizes: settings
+                .tile_sizes
+                .clone()
+                .unwrap_or_else(F::tile_sizes_2d),
             threads: threads.as_ref(),
             pixel_perfect: matches!(mode, RenderMode2D::Sdf),
             world_to_model,
@@ -510,12 +522,9 @@ fn run_mesh<F: fidget::eval::Function + fidget::render::RenderHints>(
 
     let threads = match settings.threads {
         Some(n) if n.get() == 1 => None,
-        Some(n) => Some(fidget::render::ThreadPool::Custom(
-            rayon::ThreadPoolBuilder::new()
-                .num_threads(n.get())
-                .build()
-                .unwrap(),
-        )),
+        Some(n) => {
+            Some(fidget::render::ThreadPool::with_threads(n.get()).unwrap())
+        }
         None => Some(fidget::render::ThreadPool::Global),
     };
     let threads = threads.as_ref();
@@ -785,9 +794,19 @@ fn main() -> Result<()> {
                 "  Mesh construction: {:?} ms/iter",
                 mesh_time.as_micros() as f64 / 1000.0 / (settings.n as f64)
             );
+            let mesh = match settings.crease_angle {
+                Some(a) => mesh.with_smooth_normals(a.to_radians()),
+                None => mesh,
+            };
             if let Some(out) = settings.out {
-                info!("Writing STL to {out:?}");
-                mesh.write_stl(&mut std::fs::File::create(out)?)?;
+                let mut f = std::fs::File::create(&out)?;
+                if out.extension().is_some_and(|e| e == "obj") {
+                    info!("Writing OBJ to {out:?}");
+                    mesh.write_obj(&mut f)?;
+                } else {
+                    info!("Writing STL to {out:?}");
+                    mesh.write_stl(&mut f)?;
+                }
             }
         }
     }
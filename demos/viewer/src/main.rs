@@ -12,7 +12,8 @@ use notify::{Event, EventKind, Watcher};
 
 use fidget::{
     gui::{Canvas2, Canvas3, CursorState, DragMode, View2, View3},
-    raster::{GeometryPixel, ImageRenderConfig, VoxelRenderConfig},
+    raster::{GeometryPixel, ImageRenderConfig, RgbaWriter, VoxelRenderConfig},
+    shape::ShapeVars,
 };
 
 use std::{error::Error, path::Path};
@@ -165,7 +166,7 @@ where
     }
 }
 
-fn render_2d<F: fidget::eval::Function + fidget::render::RenderHints>(
+fn render_2d<F: fidget::eval::MathFunction + fidget::render::RenderHints>(
     mode: Mode2D,
     view: View2,
     shape: fidget::shape::Shape<F>,
@@ -182,9 +183,13 @@ fn render_2d<F: fidget::eval::Function + fidget::render::RenderHints>(
 
     let out = match mode {
         Mode2D::Color => {
-            let tmp = config.run(shape).unwrap();
-            let c = [color[0], color[1], color[2], u8::MAX];
-            tmp.map(|p| if p.inside() { c } else { [0u8; 4] })
+            let writer = RgbaWriter {
+                inside: [color[0], color[1], color[2], u8::MAX],
+                outside: [0u8; 4],
+            };
+            config
+                .run_with_writer(shape, &ShapeVars::new(), &writer)
+                .unwrap()
         }
 
         Mode2D::Sdf => {
@@ -160,7 +160,7 @@ mod test {
     #[test]
     fn test_gyroid_sphere() {
         let mut engine = Engine::new();
-        let s = include_str!("../../../models/gyroid-sphere.rhai");
+        let s = include_str!("../../../fidget/models/gyroid-sphere.rhai");
         let out = engine.run(s).unwrap();
         assert_eq!(out.shapes.len(), 1);
         let mut ctx = Context::new();
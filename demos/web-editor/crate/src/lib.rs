@@ -64,8 +64,11 @@ pub fn render_2d(
             threads: Some(&ThreadPool::Global),
             tile_sizes: TileSizes::new(&[64, 16, 8]).unwrap(),
             pixel_perfect: false,
+            anti_alias: 0,
             world_to_model: view.world_to_model(),
+            slice: None,
             cancel,
+            flush_denormals: false,
         };
 
         let tmp = cfg.run(shape)?;
@@ -131,6 +134,7 @@ fn render_3d_inner(
         tile_sizes: TileSizes::new(&[128, 64, 32, 16, 8]).unwrap(),
         world_to_model: view.world_to_model(),
         cancel,
+        flush_denormals: false,
     };
     cfg.run(shape.clone())
 }
@@ -23,7 +23,7 @@ import { RENDER_SIZE, MAX_DEPTH } from "./constants";
 
 import * as fidget from "../../crate/pkg/fidget_wasm_demo";
 
-import GYROID_SCRIPT from "../../../../models/gyroid-sphere.rhai";
+import GYROID_SCRIPT from "../../../../fidget/models/gyroid-sphere.rhai";
 
 async function setup() {
   await fidget.default();
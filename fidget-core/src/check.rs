@@ -0,0 +1,286 @@
+//! Property-based soundness checks for evaluators
+//!
+//! These utilities are meant to be run in tests (e.g. against a library of
+//! shapes or a custom [`Function`] implementation), to catch interval
+//! arithmetic that fails to contain the true result.
+//!
+//! ```
+//! use fidget_core::{check, context::Tree, vm::VmShape};
+//!
+//! let (x, y, z) = Tree::axes();
+//! let shape = VmShape::from((x.square() + y.sin() * z).max(x / 2.0));
+//! check::interval_soundness(&shape, 1000)?;
+//! # Ok::<(), fidget_core::Error>(())
+//! ```
+use crate::{
+    Error,
+    eval::Function,
+    shape::{EzShape, Shape, ShapeVars},
+    types::{Grad, Interval},
+};
+use nalgebra::Point3;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Settings for [`interval_soundness_with`]
+#[derive(Clone, Debug)]
+pub struct SoundnessSettings {
+    /// Number of random boxes to check
+    pub boxes: usize,
+
+    /// Number of random points to check within each box
+    ///
+    /// The box's corners and center are always checked as well.
+    pub points_per_box: usize,
+
+    /// Region in which to generate boxes
+    ///
+    /// Box sizes are log-uniformly distributed between 1/1000th of the region
+    /// and the entire region.
+    pub bounds: [Interval; 3],
+
+    /// Relative tolerance when comparing point values against intervals
+    ///
+    /// Interval arithmetic uses round-to-nearest by default, so bounds may be
+    /// off by an ULP or two; a value `v` is accepted if it's within
+    /// `tolerance * max(1, |bound|)` of the interval.  Set this to zero when
+    /// using the `outward-rounding` feature.
+    pub tolerance: f32,
+
+    /// Seed for the random number generator, for reproducible failures
+    pub seed: u64,
+}
+
+impl Default for SoundnessSettings {
+    fn default() -> Self {
+        Self {
+            boxes: 1000,
+            points_per_box: 16,
+            bounds: [Interval::new(-1.0, 1.0); 3],
+            tolerance: 1e-5,
+            seed: 0,
+        }
+    }
+}
+
+/// Description of a point which falls outside of its interval result
+#[derive(Clone, Debug)]
+pub struct SoundnessViolation {
+    /// Box used for interval evaluation
+    pub region: [Interval; 3],
+    /// Result of interval evaluation
+    pub interval: Interval,
+    /// Point within the box
+    pub point: Point3<f32>,
+    /// Result of evaluating the point
+    pub value: f32,
+    /// Which evaluator (and tape) produced `value`
+    pub evaluator: &'static str,
+}
+
+impl std::fmt::Display for SoundnessViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y, z] = self.region;
+        write!(
+            f,
+            "{} evaluation at ({}, {}, {}) returned {}, \
+             but interval evaluation over [{x}, {y}, {z}] returned {}",
+            self.evaluator,
+            self.point.x,
+            self.point.y,
+            self.point.z,
+            self.value,
+            self.interval,
+        )
+    }
+}
+
+/// Checks that point evaluation is contained by interval evaluation
+///
+/// This evaluates `boxes` random boxes in the `[-1, 1]` cube with default
+/// [`SoundnessSettings`]; see [`interval_soundness_with`] for details.
+pub fn interval_soundness<F: Function, T>(
+    shape: &Shape<F, T>,
+    boxes: usize,
+) -> Result<(), Error> {
+    let settings = SoundnessSettings {
+        boxes,
+        ..Default::default()
+    };
+    interval_soundness_with(shape, &ShapeVars::new(), &settings)
+}
+
+/// Checks that point evaluation is contained by interval evaluation
+///
+/// For each random box, the shape is evaluated with the interval evaluator;
+/// then, points within the box are evaluated with the point, float slice, and
+/// gradient slice evaluators, using both the original tape and the tape
+/// simplified by the interval trace.  Every result must lie within the
+/// interval (or the interval must contain NaN).
+///
+/// The shape is generic over [`Function`], so this should be called once for
+/// each evaluator backend (e.g. VM and JIT).
+///
+/// Returns [`Error::UnsoundInterval`] describing the first failure, or any
+/// error encountered during evaluation.
+pub fn interval_soundness_with<F: Function, T>(
+    shape: &Shape<F, T>,
+    vars: &ShapeVars<f32>,
+    settings: &SoundnessSettings,
+) -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let mut i_eval = Shape::<F, T>::new_interval_eval();
+    let mut p_eval = Shape::<F, T>::new_point_eval();
+    let mut f_eval = Shape::<F, T>::new_float_slice_eval();
+    let mut g_eval = Shape::<F, T>::new_grad_slice_eval();
+    let i_tape = shape.ez_interval_tape();
+
+    let mut points = vec![];
+    for _ in 0..settings.boxes {
+        let region = settings.bounds.map(|b| {
+            let size = b.width() * 10f32.powf(-3.0 * rng.random::<f32>());
+            let lower = b.lower() + (b.width() - size) * rng.random::<f32>();
+            Interval::new(lower, (lower + size).min(b.upper()))
+        });
+        let [x, y, z] = region;
+        let (interval, trace) = i_eval.eval_v(&i_tape, x, y, z, vars)?;
+
+        points.clear();
+        for i in 0..8 {
+            let pick = |r: Interval, bit| {
+                if i & bit == 0 { r.lower() } else { r.upper() }
+            };
+            points.push(Point3::new(pick(x, 1), pick(y, 2), pick(z, 4)));
+        }
+        points.push(Point3::new(x.midpoint(), y.midpoint(), z.midpoint()));
+        for _ in 0..settings.points_per_box {
+            let p = region.map(|r| {
+                r.lerp(rng.random::<f32>()).clamp(r.lower(), r.upper())
+            });
+            points.push(Point3::new(p[0], p[1], p[2]));
+        }
+
+        let check = |point: Point3<f32>, value: f32, evaluator| {
+            if contains(interval, value, settings.tolerance) {
+                Ok(())
+            } else {
+                Err(Error::UnsoundInterval(Box::new(SoundnessViolation {
+                    region,
+                    interval,
+                    point,
+                    value,
+                    evaluator,
+                })))
+            }
+        };
+
+        let simplified = trace.map(|t| shape.ez_simplify(t)).transpose()?;
+        let passes = [
+            (Some(shape), ["point", "float slice", "gradient slice"]),
+            (
+                simplified.as_ref(),
+                [
+                    "simplified point",
+                    "simplified float slice",
+                    "simplified gradient slice",
+                ],
+            ),
+        ];
+        for (s, [p_name, f_name, g_name]) in passes {
+            let Some(s) = s else {
+                continue;
+            };
+
+            let p_tape = s.ez_point_tape();
+            for &p in &points {
+                let (v, _) = p_eval.eval_v(&p_tape, p.x, p.y, p.z, vars)?;
+                check(p, v, p_name)?;
+            }
+
+            let xs: Vec<f32> = points.iter().map(|p| p.x).collect();
+            let ys: Vec<f32> = points.iter().map(|p| p.y).collect();
+            let zs: Vec<f32> = points.iter().map(|p| p.z).collect();
+            let f_tape = s.ez_float_slice_tape();
+            let out = f_eval.eval_v(&f_tape, &xs, &ys, &zs, vars)?;
+            for (&p, &v) in points.iter().zip(out) {
+                check(p, v, f_name)?;
+            }
+
+            let xs: Vec<Grad> = xs.iter().map(|&x| Grad::from(x)).collect();
+            let ys: Vec<Grad> = ys.iter().map(|&y| Grad::from(y)).collect();
+            let zs: Vec<Grad> = zs.iter().map(|&z| Grad::from(z)).collect();
+            let g_tape = s.ez_grad_slice_tape();
+            let out = g_eval.eval_v(&g_tape, &xs, &ys, &zs, vars)?;
+            for (&p, g) in points.iter().zip(out) {
+                check(p, g.v, g_name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether `v` is within `i`, with the given relative tolerance
+///
+/// A NaN interval contains every value; a NaN value is only contained in a
+/// NaN interval.
+fn contains(i: Interval, v: f32, tolerance: f32) -> bool {
+    if i.has_nan() {
+        true
+    } else if v.is_nan() {
+        false
+    } else {
+        let slack = |b: f32| tolerance * b.abs().max(1.0);
+        v >= i.lower() - slack(i.lower()) && v <= i.upper() + slack(i.upper())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Tree, vm::VmShape};
+
+    #[test]
+    fn contains_tolerance() {
+        let i = Interval::new(0.0, 1000.0);
+        assert!(contains(i, 500.0, 0.0));
+        assert!(!contains(i, 1000.5, 0.0));
+        assert!(contains(i, 1000.005, 1e-5));
+        assert!(!contains(i, 1000.5, 1e-5));
+        assert!(!contains(i, f32::NAN, 1e-5));
+        assert!(contains(f32::NAN.into(), 1.0, 0.0));
+    }
+
+    #[test]
+    fn vm_soundness() {
+        let (x, y, z) = Tree::axes();
+        let shapes = [
+            (x.square() + y.square() + z.square()).sqrt() - 0.5,
+            x.clone().min(y.clone()).max(z.clone() * 2.0),
+            (x.clone() * 10.0).sin() + y.clone().cos() * z.clone(),
+            x.clone().atan2(y.clone()) - z.clone().exp(),
+            (x.clone() / (y.square() + 0.1)).abs().ln(),
+        ];
+        for t in shapes {
+            let shape = VmShape::from(t);
+            interval_soundness(&shape, 200).unwrap();
+        }
+    }
+
+    #[test]
+    fn soundness_with_vars() {
+        use crate::var::Var;
+        let v = Var::new();
+        let t = Tree::x() * Tree::from(v) + Tree::y();
+        let mut ctx = crate::Context::new();
+        let root = ctx.import(&t);
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let mut vars = ShapeVars::new();
+        vars.insert(v.index().unwrap(), 3.0);
+        let settings = SoundnessSettings {
+            boxes: 50,
+            bounds: [Interval::new(-10.0, 10.0); 3],
+            ..Default::default()
+        };
+        interval_soundness_with(&shape, &vars, &settings).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! Limits on tape construction
+use crate::Error;
+use std::time::{Duration, Instant};
+
+/// Optional limits on tape construction
+///
+/// This is used to reject pathological expressions (e.g. from untrusted
+/// scripts) with an error, rather than spending unbounded time and memory
+/// building a tape.  The default budget is unlimited.
+///
+/// Checking `max_time` requires reading the system clock, which isn't
+/// available on `wasm32-unknown-unknown`; leave it unset on that platform.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BuildBudget {
+    /// Maximum number of operations in the flattened tape
+    pub max_ops: Option<usize>,
+
+    /// Maximum wall-clock time to spend building the tape
+    pub max_time: Option<Duration>,
+}
+
+impl BuildBudget {
+    /// Builds an unlimited budget
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking this budget, beginning the clock
+    pub(crate) fn start(&self) -> BudgetTracker {
+        BudgetTracker {
+            max_ops: self.max_ops,
+            deadline: self.max_time.map(|t| (Instant::now() + t, t)),
+        }
+    }
+}
+
+/// Running state for a [`BuildBudget`]
+pub(crate) struct BudgetTracker {
+    max_ops: Option<usize>,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl BudgetTracker {
+    /// Number of loop iterations between clock checks
+    pub const CHECK_INTERVAL: usize = 4096;
+
+    /// Checks whether `n` operations are within the budget
+    #[inline]
+    pub fn check_ops(&self, n: usize) -> Result<(), Error> {
+        match self.max_ops {
+            Some(max) if n > max => Err(Error::TapeTooLong(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks whether we've run out of time
+    #[inline]
+    pub fn check_time(&self) -> Result<(), Error> {
+        match self.deadline {
+            Some((deadline, t)) if Instant::now() > deadline => {
+                Err(Error::BuildTimeout(t))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn budget_ops() {
+        let b = BuildBudget {
+            max_ops: Some(10),
+            ..Default::default()
+        }
+        .start();
+        assert!(b.check_ops(10).is_ok());
+        assert!(matches!(b.check_ops(11), Err(Error::TapeTooLong(10))));
+        assert!(b.check_time().is_ok());
+    }
+
+    #[test]
+    fn budget_time() {
+        let b = BuildBudget {
+            max_time: Some(Duration::ZERO),
+            ..Default::default()
+        }
+        .start();
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(b.check_time(), Err(Error::BuildTimeout(_))));
+        assert!(b.check_ops(usize::MAX).is_ok());
+    }
+}
@@ -0,0 +1,406 @@
+//! Stable view of tape operations, for use by external tools
+use crate::{
+    compiler::{RegOp, SsaOp},
+    context::{BinaryOpcode, UnaryOpcode},
+    var::VarMap,
+};
+use std::fmt::Write;
+
+/// An argument to a [`Clause`]
+///
+/// New variants may be added in future releases, so matches must include a
+/// wildcard arm.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Operand {
+    /// An SSA register, written by exactly one earlier clause
+    Reg(u32),
+    /// An immediate value
+    Imm(f32),
+}
+
+/// A single tape operation, decoded into a stable form
+///
+/// Unlike [`SsaOp`], whose variants encode operand order and immediate
+/// position (and may change between releases), a `Clause` uses the same
+/// opcodes as the [`Context`](crate::Context) and always stores operands in
+/// mathematical order.  For example, both `SubRegImm` and `SubImmReg` become
+/// [`Clause::Binary`] with [`BinaryOpcode::Sub`], with `lhs` and `rhs` in the
+/// order that they're subtracted.
+///
+/// This is intended for tools that transpile tapes into other languages; see
+/// [`SsaTape::clauses`](crate::compiler::SsaTape::clauses).  New kinds of
+/// clause may be added in future releases, so matches must include a wildcard
+/// arm.
+///
+/// ```
+/// use fidget_core::{
+///     compiler::{Clause, Operand, SsaTape},
+///     context::{BinaryOpcode, Context},
+/// };
+///
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let root = ctx.sub(1.0, x)?;
+/// let (tape, _vars) = SsaTape::new(&ctx, &[root])?;
+///
+/// let clauses = tape.clauses().collect::<Vec<_>>();
+/// assert_eq!(clauses.len(), 3);
+/// assert!(matches!(clauses[0], Clause::Input { out: 0, var: 0 }));
+/// assert_eq!(clauses[1], Clause::Binary {
+///     op: BinaryOpcode::Sub,
+///     out: 1,
+///     lhs: Operand::Imm(1.0),
+///     rhs: Operand::Reg(0),
+/// });
+/// assert!(matches!(clauses[2], Clause::Output { arg: 1, index: 0 }));
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Clause {
+    /// Reads the input variable with the given index into a register
+    ///
+    /// The index refers to the tape's [`VarMap`](crate::var::VarMap).
+    Input {
+        /// Output register
+        out: u32,
+        /// Variable index
+        var: u32,
+    },
+    /// Writes a register to the output with the given index
+    Output {
+        /// Register to write
+        arg: u32,
+        /// Output index
+        index: u32,
+    },
+    /// Copies a register or immediate into a register
+    Copy {
+        /// Output register
+        out: u32,
+        /// Value to copy
+        arg: Operand,
+    },
+    /// Applies a one-argument operation
+    Unary {
+        /// Operation
+        op: UnaryOpcode,
+        /// Output register
+        out: u32,
+        /// Argument register
+        arg: u32,
+    },
+    /// Applies a two-argument operation
+    ///
+    /// For [`BinaryOpcode::Atan`], `lhs` is `y` and `rhs` is `x`.  At most one
+    /// of `lhs` and `rhs` is an immediate.
+    Binary {
+        /// Operation
+        op: BinaryOpcode,
+        /// Output register
+        out: u32,
+        /// Left-hand argument
+        lhs: Operand,
+        /// Right-hand argument
+        rhs: Operand,
+    },
+}
+
+impl Clause {
+    /// Returns the register written by this clause, if any
+    pub fn output(&self) -> Option<u32> {
+        match self {
+            Clause::Input { out, .. }
+            | Clause::Copy { out, .. }
+            | Clause::Unary { out, .. }
+            | Clause::Binary { out, .. } => Some(*out),
+            Clause::Output { .. } => None,
+        }
+    }
+
+    /// Writes a single line of disassembly
+    ///
+    /// Registers are printed with the given prefix; inputs are annotated with
+    /// their variable name if `names` is long enough to include them.
+    pub(crate) fn disassemble(
+        &self,
+        w: &mut String,
+        reg: &str,
+        names: &[String],
+    ) {
+        let operand = |o: Operand| match o {
+            Operand::Reg(r) => format!("{reg}{r}"),
+            Operand::Imm(imm) => format!("{imm}"),
+        };
+        match *self {
+            Clause::Input { out, var } => {
+                write!(w, "{reg}{out} = INPUT[{var}]").unwrap();
+                if let Some(name) = names.get(var as usize) {
+                    write!(w, "  # {name}").unwrap();
+                }
+            }
+            Clause::Output { arg, index } => {
+                write!(w, "OUTPUT[{index}] = {reg}{arg}").unwrap()
+            }
+            Clause::Copy { out, arg } => {
+                write!(w, "{reg}{out} = COPY {}", operand(arg)).unwrap()
+            }
+            Clause::Unary { op, out, arg } => {
+                let op = format!("{op:?}").to_uppercase();
+                write!(w, "{reg}{out} = {op} {reg}{arg}").unwrap()
+            }
+            Clause::Binary { op, out, lhs, rhs } => {
+                let op = format!("{op:?}").to_uppercase();
+                let (lhs, rhs) = (operand(lhs), operand(rhs));
+                write!(w, "{reg}{out} = {op} {lhs} {rhs}").unwrap()
+            }
+        }
+        w.push('\n');
+    }
+}
+
+/// Returns a human-readable name for each variable, ordered by index
+///
+/// Variables declared with [`VarInfo`](crate::var::VarInfo) use their declared
+/// name; others use their [`Display`](std::fmt::Display) representation.
+pub(crate) fn var_names(vars: &VarMap) -> Vec<String> {
+    let mut out = vec![String::new(); vars.len()];
+    for (v, i) in vars.iter() {
+        out[i] = match vars.info(&v) {
+            Some(info) => info.name.clone(),
+            None => v.to_string(),
+        };
+    }
+    out
+}
+
+/// Decodes an opcode into a [`Clause`]
+///
+/// [`SsaOp`] and [`RegOp`] share most of their variants (differing only in
+/// register type), so this is written once for both.  Any variants which are
+/// specific to a particular opcode type are passed in as extra match arms.
+macro_rules! decode {
+    ($op:expr, $t:ident<$r:ty>, $($extra:tt)*) => {{
+        use Operand::{Imm, Reg};
+        let unary = |op, out: $r, arg: $r| Clause::Unary {
+            op,
+            out: out.into(),
+            arg: arg.into(),
+        };
+        let binary = |op, out: $r, lhs, rhs| Clause::Binary {
+            op,
+            out: out.into(),
+            lhs,
+            rhs,
+        };
+        match $op {
+            $t::Input(out, var) => Clause::Input { out: out.into(), var },
+            $t::Output(arg, index) => Clause::Output { arg: arg.into(), index },
+            $t::CopyReg(out, arg) => Clause::Copy {
+                out: out.into(),
+                arg: Reg(arg.into()),
+            },
+            $t::CopyImm(out, imm) => Clause::Copy {
+                out: out.into(),
+                arg: Imm(imm),
+            },
+
+            $t::NegReg(out, arg) => unary(UnaryOpcode::Neg, out, arg),
+            $t::AbsReg(out, arg) => unary(UnaryOpcode::Abs, out, arg),
+            $t::RecipReg(out, arg) => unary(UnaryOpcode::Recip, out, arg),
+            $t::SqrtReg(out, arg) => unary(UnaryOpcode::Sqrt, out, arg),
+            $t::SquareReg(out, arg) => unary(UnaryOpcode::Square, out, arg),
+            $t::FloorReg(out, arg) => unary(UnaryOpcode::Floor, out, arg),
+            $t::CeilReg(out, arg) => unary(UnaryOpcode::Ceil, out, arg),
+            $t::RoundReg(out, arg) => unary(UnaryOpcode::Round, out, arg),
+            $t::SinReg(out, arg) => unary(UnaryOpcode::Sin, out, arg),
+            $t::CosReg(out, arg) => unary(UnaryOpcode::Cos, out, arg),
+            $t::TanReg(out, arg) => unary(UnaryOpcode::Tan, out, arg),
+            $t::AsinReg(out, arg) => unary(UnaryOpcode::Asin, out, arg),
+            $t::AcosReg(out, arg) => unary(UnaryOpcode::Acos, out, arg),
+            $t::AtanReg(out, arg) => unary(UnaryOpcode::Atan, out, arg),
+            $t::ExpReg(out, arg) => unary(UnaryOpcode::Exp, out, arg),
+            $t::LnReg(out, arg) => unary(UnaryOpcode::Ln, out, arg),
+            $t::NotReg(out, arg) => unary(UnaryOpcode::Not, out, arg),
+
+            $t::AddRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Add, out, Reg(a.into()), Imm(imm))
+            }
+            $t::MulRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Mul, out, Reg(a.into()), Imm(imm))
+            }
+            $t::DivRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Div, out, Reg(a.into()), Imm(imm))
+            }
+            $t::DivImmReg(out, a, imm) => {
+                binary(BinaryOpcode::Div, out, Imm(imm), Reg(a.into()))
+            }
+            $t::SubRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Sub, out, Reg(a.into()), Imm(imm))
+            }
+            $t::SubImmReg(out, a, imm) => {
+                binary(BinaryOpcode::Sub, out, Imm(imm), Reg(a.into()))
+            }
+            $t::ModRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Mod, out, Reg(a.into()), Imm(imm))
+            }
+            $t::ModImmReg(out, a, imm) => {
+                binary(BinaryOpcode::Mod, out, Imm(imm), Reg(a.into()))
+            }
+            $t::AtanRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Atan, out, Reg(a.into()), Imm(imm))
+            }
+            $t::AtanImmReg(out, a, imm) => {
+                binary(BinaryOpcode::Atan, out, Imm(imm), Reg(a.into()))
+            }
+            $t::CompareRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Compare, out, Reg(a.into()), Imm(imm))
+            }
+            $t::CompareImmReg(out, a, imm) => {
+                binary(BinaryOpcode::Compare, out, Imm(imm), Reg(a.into()))
+            }
+            $t::MinRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Min, out, Reg(a.into()), Imm(imm))
+            }
+            $t::MaxRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Max, out, Reg(a.into()), Imm(imm))
+            }
+            $t::AndRegImm(out, a, imm) => {
+                binary(BinaryOpcode::And, out, Reg(a.into()), Imm(imm))
+            }
+            $t::OrRegImm(out, a, imm) => {
+                binary(BinaryOpcode::Or, out, Reg(a.into()), Imm(imm))
+            }
+
+            $t::AddRegReg(out, a, b) => {
+                binary(BinaryOpcode::Add, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::MulRegReg(out, a, b) => {
+                binary(BinaryOpcode::Mul, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::DivRegReg(out, a, b) => {
+                binary(BinaryOpcode::Div, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::SubRegReg(out, a, b) => {
+                binary(BinaryOpcode::Sub, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::ModRegReg(out, a, b) => {
+                binary(BinaryOpcode::Mod, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::AtanRegReg(out, a, b) => {
+                binary(BinaryOpcode::Atan, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::CompareRegReg(out, a, b) => {
+                binary(BinaryOpcode::Compare, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::MinRegReg(out, a, b) => {
+                binary(BinaryOpcode::Min, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::MaxRegReg(out, a, b) => {
+                binary(BinaryOpcode::Max, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::AndRegReg(out, a, b) => {
+                binary(BinaryOpcode::And, out, Reg(a.into()), Reg(b.into()))
+            }
+            $t::OrRegReg(out, a, b) => {
+                binary(BinaryOpcode::Or, out, Reg(a.into()), Reg(b.into()))
+            }
+            $($extra)*
+        }
+    }};
+}
+
+impl From<SsaOp> for Clause {
+    fn from(op: SsaOp) -> Self {
+        decode!(op, SsaOp<u32>,)
+    }
+}
+
+impl Clause {
+    /// Decodes a register-allocated opcode
+    ///
+    /// Registers in the resulting clause are physical registers, not SSA
+    /// slots.  Returns `None` for [`RegOp::Load`] and [`RegOp::Store`], which
+    /// have no equivalent clause.
+    pub(crate) fn from_reg_op(op: RegOp) -> Option<Self> {
+        Some(
+            decode!(op, RegOp<u8>, RegOp::Load(..) | RegOp::Store(..) => return None,),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, compiler::SsaTape};
+
+    #[test]
+    fn test_clauses() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.div(2.0, x).unwrap();
+        let b = ctx.max(a, y).unwrap();
+        let c = ctx.sqrt(b).unwrap();
+        let (tape, vars) = SsaTape::new(&ctx, &[c]).unwrap();
+        let clauses = tape.clauses().collect::<Vec<_>>();
+        assert_eq!(clauses.len(), tape.len());
+
+        // Every register is written exactly once, before it's read
+        let mut seen = std::collections::HashSet::new();
+        let check = |seen: &std::collections::HashSet<u32>, o: &Operand| {
+            if let Operand::Reg(r) = o {
+                assert!(seen.contains(r));
+            }
+        };
+        for c in &clauses {
+            match c {
+                Clause::Input { var, .. } => {
+                    assert!((*var as usize) < vars.len())
+                }
+                Clause::Output { arg, .. } | Clause::Unary { arg, .. } => {
+                    assert!(seen.contains(arg))
+                }
+                Clause::Copy { arg, .. } => check(&seen, arg),
+                Clause::Binary { lhs, rhs, .. } => {
+                    check(&seen, lhs);
+                    check(&seen, rhs);
+                }
+            }
+            if let Some(out) = c.output() {
+                assert!(seen.insert(out));
+            }
+        }
+
+        assert!(clauses.iter().any(|c| matches!(
+            c,
+            Clause::Binary {
+                op: BinaryOpcode::Div,
+                lhs: Operand::Imm(2.0),
+                rhs: Operand::Reg(..),
+                ..
+            }
+        )));
+        assert!(clauses.iter().any(|c| matches!(
+            c,
+            Clause::Binary {
+                op: BinaryOpcode::Max,
+                lhs: Operand::Reg(..),
+                rhs: Operand::Reg(..),
+                ..
+            }
+        )));
+        assert!(matches!(
+            clauses[clauses.len() - 2],
+            Clause::Unary {
+                op: UnaryOpcode::Sqrt,
+                ..
+            }
+        ));
+        assert!(matches!(
+            clauses.last(),
+            Some(Clause::Output { index: 0, .. })
+        ));
+    }
+}
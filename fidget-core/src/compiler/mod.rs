@@ -10,6 +10,13 @@
 mod alloc;
 pub use alloc::RegisterAllocator;
 
+mod budget;
+mod clause;
+pub(crate) use budget::BudgetTracker;
+pub use budget::BuildBudget;
+pub(crate) use clause::var_names;
+pub use clause::{Clause, Operand};
+
 mod op;
 
 mod lru;
@@ -17,9 +24,14 @@ pub(crate) use lru::Lru;
 pub use op::{RegOp, RegOpDiscriminants, SsaOp};
 
 mod reg_tape;
+mod shader;
 mod ssa_tape;
 
+pub use shader::ShaderTarget;
+pub(crate) use shader::{write_c, write_function};
+
 pub use reg_tape::RegTape;
+pub(crate) use reg_tape::disassemble_op;
 pub use ssa_tape::SsaTape;
 
 #[cfg(test)]
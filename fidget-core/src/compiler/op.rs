@@ -263,8 +263,279 @@ impl SsaOp {
             | SsaOp::OrRegReg(..) => true,
         }
     }
+
+    /// Replaces the output pseudo-register, if present
+    pub(crate) fn set_output(&mut self, r: u32) {
+        match self {
+            SsaOp::Output(..) => (),
+            SsaOp::Input(out, ..)
+            | SsaOp::CopyImm(out, ..)
+            | SsaOp::NegReg(out, ..)
+            | SsaOp::AbsReg(out, ..)
+            | SsaOp::RecipReg(out, ..)
+            | SsaOp::SqrtReg(out, ..)
+            | SsaOp::SquareReg(out, ..)
+            | SsaOp::FloorReg(out, ..)
+            | SsaOp::CeilReg(out, ..)
+            | SsaOp::RoundReg(out, ..)
+            | SsaOp::CopyReg(out, ..)
+            | SsaOp::SinReg(out, ..)
+            | SsaOp::CosReg(out, ..)
+            | SsaOp::TanReg(out, ..)
+            | SsaOp::AsinReg(out, ..)
+            | SsaOp::AcosReg(out, ..)
+            | SsaOp::AtanReg(out, ..)
+            | SsaOp::ExpReg(out, ..)
+            | SsaOp::LnReg(out, ..)
+            | SsaOp::NotReg(out, ..)
+            | SsaOp::AddRegImm(out, ..)
+            | SsaOp::MulRegImm(out, ..)
+            | SsaOp::DivRegImm(out, ..)
+            | SsaOp::DivImmReg(out, ..)
+            | SsaOp::SubImmReg(out, ..)
+            | SsaOp::SubRegImm(out, ..)
+            | SsaOp::AddRegReg(out, ..)
+            | SsaOp::MulRegReg(out, ..)
+            | SsaOp::DivRegReg(out, ..)
+            | SsaOp::SubRegReg(out, ..)
+            | SsaOp::AtanRegReg(out, ..)
+            | SsaOp::AtanRegImm(out, ..)
+            | SsaOp::AtanImmReg(out, ..)
+            | SsaOp::MinRegImm(out, ..)
+            | SsaOp::MaxRegImm(out, ..)
+            | SsaOp::MinRegReg(out, ..)
+            | SsaOp::MaxRegReg(out, ..)
+            | SsaOp::CompareRegReg(out, ..)
+            | SsaOp::CompareRegImm(out, ..)
+            | SsaOp::CompareImmReg(out, ..)
+            | SsaOp::ModRegReg(out, ..)
+            | SsaOp::ModRegImm(out, ..)
+            | SsaOp::ModImmReg(out, ..)
+            | SsaOp::AndRegImm(out, ..)
+            | SsaOp::AndRegReg(out, ..)
+            | SsaOp::OrRegImm(out, ..)
+            | SsaOp::OrRegReg(out, ..) => *out = r,
+        }
+    }
+
+    /// Applies a function to each input pseudo-register
+    pub(crate) fn map_inputs(&mut self, mut f: impl FnMut(u32) -> u32) {
+        match self {
+            SsaOp::Input(..) | SsaOp::CopyImm(..) => (),
+            SsaOp::Output(arg, ..)
+            | SsaOp::NegReg(_, arg)
+            | SsaOp::AbsReg(_, arg)
+            | SsaOp::RecipReg(_, arg)
+            | SsaOp::SqrtReg(_, arg)
+            | SsaOp::SquareReg(_, arg)
+            | SsaOp::FloorReg(_, arg)
+            | SsaOp::CeilReg(_, arg)
+            | SsaOp::RoundReg(_, arg)
+            | SsaOp::CopyReg(_, arg)
+            | SsaOp::SinReg(_, arg)
+            | SsaOp::CosReg(_, arg)
+            | SsaOp::TanReg(_, arg)
+            | SsaOp::AsinReg(_, arg)
+            | SsaOp::AcosReg(_, arg)
+            | SsaOp::AtanReg(_, arg)
+            | SsaOp::ExpReg(_, arg)
+            | SsaOp::LnReg(_, arg)
+            | SsaOp::NotReg(_, arg)
+            | SsaOp::AddRegImm(_, arg, ..)
+            | SsaOp::MulRegImm(_, arg, ..)
+            | SsaOp::DivRegImm(_, arg, ..)
+            | SsaOp::DivImmReg(_, arg, ..)
+            | SsaOp::SubImmReg(_, arg, ..)
+            | SsaOp::SubRegImm(_, arg, ..)
+            | SsaOp::AtanRegImm(_, arg, ..)
+            | SsaOp::AtanImmReg(_, arg, ..)
+            | SsaOp::MinRegImm(_, arg, ..)
+            | SsaOp::MaxRegImm(_, arg, ..)
+            | SsaOp::CompareRegImm(_, arg, ..)
+            | SsaOp::CompareImmReg(_, arg, ..)
+            | SsaOp::ModRegImm(_, arg, ..)
+            | SsaOp::ModImmReg(_, arg, ..)
+            | SsaOp::AndRegImm(_, arg, ..)
+            | SsaOp::OrRegImm(_, arg, ..) => *arg = f(*arg),
+            SsaOp::AddRegReg(_, lhs, rhs)
+            | SsaOp::MulRegReg(_, lhs, rhs)
+            | SsaOp::DivRegReg(_, lhs, rhs)
+            | SsaOp::SubRegReg(_, lhs, rhs)
+            | SsaOp::AtanRegReg(_, lhs, rhs)
+            | SsaOp::MinRegReg(_, lhs, rhs)
+            | SsaOp::MaxRegReg(_, lhs, rhs)
+            | SsaOp::CompareRegReg(_, lhs, rhs)
+            | SsaOp::ModRegReg(_, lhs, rhs)
+            | SsaOp::AndRegReg(_, lhs, rhs)
+            | SsaOp::OrRegReg(_, lhs, rhs) => {
+                *lhs = f(*lhs);
+                *rhs = f(*rhs);
+            }
+        }
+    }
+
+    /// Returns the input pseudo-registers (0, 1, or 2 values)
+    pub fn inputs(&self) -> impl Iterator<Item = u32> {
+        let out = match *self {
+            SsaOp::Input(..) | SsaOp::CopyImm(..) => [None, None],
+            SsaOp::Output(arg, ..)
+            | SsaOp::NegReg(_, arg)
+            | SsaOp::AbsReg(_, arg)
+            | SsaOp::RecipReg(_, arg)
+            | SsaOp::SqrtReg(_, arg)
+            | SsaOp::SquareReg(_, arg)
+            | SsaOp::FloorReg(_, arg)
+            | SsaOp::CeilReg(_, arg)
+            | SsaOp::RoundReg(_, arg)
+            | SsaOp::CopyReg(_, arg)
+            | SsaOp::SinReg(_, arg)
+            | SsaOp::CosReg(_, arg)
+            | SsaOp::TanReg(_, arg)
+            | SsaOp::AsinReg(_, arg)
+            | SsaOp::AcosReg(_, arg)
+            | SsaOp::AtanReg(_, arg)
+            | SsaOp::ExpReg(_, arg)
+            | SsaOp::LnReg(_, arg)
+            | SsaOp::NotReg(_, arg)
+            | SsaOp::AddRegImm(_, arg, ..)
+            | SsaOp::MulRegImm(_, arg, ..)
+            | SsaOp::DivRegImm(_, arg, ..)
+            | SsaOp::DivImmReg(_, arg, ..)
+            | SsaOp::SubImmReg(_, arg, ..)
+            | SsaOp::SubRegImm(_, arg, ..)
+            | SsaOp::AtanRegImm(_, arg, ..)
+            | SsaOp::AtanImmReg(_, arg, ..)
+            | SsaOp::MinRegImm(_, arg, ..)
+            | SsaOp::MaxRegImm(_, arg, ..)
+            | SsaOp::CompareRegImm(_, arg, ..)
+            | SsaOp::CompareImmReg(_, arg, ..)
+            | SsaOp::ModRegImm(_, arg, ..)
+            | SsaOp::ModImmReg(_, arg, ..)
+            | SsaOp::AndRegImm(_, arg, ..)
+            | SsaOp::OrRegImm(_, arg, ..) => [Some(arg), None],
+            SsaOp::AddRegReg(_, lhs, rhs)
+            | SsaOp::MulRegReg(_, lhs, rhs)
+            | SsaOp::DivRegReg(_, lhs, rhs)
+            | SsaOp::SubRegReg(_, lhs, rhs)
+            | SsaOp::AtanRegReg(_, lhs, rhs)
+            | SsaOp::MinRegReg(_, lhs, rhs)
+            | SsaOp::MaxRegReg(_, lhs, rhs)
+            | SsaOp::CompareRegReg(_, lhs, rhs)
+            | SsaOp::ModRegReg(_, lhs, rhs)
+            | SsaOp::AndRegReg(_, lhs, rhs)
+            | SsaOp::OrRegReg(_, lhs, rhs) => [Some(lhs), Some(rhs)],
+        };
+        out.into_iter().flatten()
+    }
+}
+
+/// Generates a packed `[u32; 4]` encoding for [`SsaOp`]
+///
+/// Opcode values are assigned in the order that variants are listed here, so
+/// new variants must be added to the end of the list.
+macro_rules! ssa_words {
+    ($($kind:ident $name:ident),* $(,)?) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u32)]
+        enum SsaCode { $($name),* }
+
+        impl SsaOp {
+            /// Packs the operation into `[opcode, out, lhs, rhs]` words
+            ///
+            /// Immediates are stored as their `f32` bit pattern, and unused
+            /// words are zero.
+            pub(crate) fn to_words(self) -> [u32; 4] {
+                match self {
+                    $(ssa_words!(@pat $kind $name a b c) => {
+                        ssa_words!(@pack $kind $name a b c)
+                    })*
+                }
+            }
+
+            /// Unpacks an operation from [`to_words`](Self::to_words) output
+            ///
+            /// Returns `None` if the opcode is unknown.
+            pub(crate) fn from_words(w: [u32; 4]) -> Option<Self> {
+                let [code, a, b, c] = w;
+                $(
+                    if code == SsaCode::$name as u32 {
+                        return Some(ssa_words!(@unpack $kind $name a b c));
+                    }
+                )*
+                None
+            }
+        }
+    };
+    (@pat idx $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b) };
+    (@pat imm $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b) };
+    (@pat unary $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b) };
+    (@pat reg_imm $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b, $c) };
+    (@pat reg_reg $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b, $c) };
+
+    (@pack idx $name:ident $a:ident $b:ident $c:ident) => { [SsaCode::$name as u32, $a, $b, 0] };
+    (@pack imm $name:ident $a:ident $b:ident $c:ident) => { [SsaCode::$name as u32, $a, 0, $b.to_bits()] };
+    (@pack unary $name:ident $a:ident $b:ident $c:ident) => { [SsaCode::$name as u32, $a, $b, 0] };
+    (@pack reg_imm $name:ident $a:ident $b:ident $c:ident) => { [SsaCode::$name as u32, $a, $b, $c.to_bits()] };
+    (@pack reg_reg $name:ident $a:ident $b:ident $c:ident) => { [SsaCode::$name as u32, $a, $b, $c] };
+
+    (@unpack idx $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b) };
+    (@unpack imm $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, f32::from_bits($c)) };
+    (@unpack unary $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b) };
+    (@unpack reg_imm $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b, f32::from_bits($c)) };
+    (@unpack reg_reg $name:ident $a:ident $b:ident $c:ident) => { SsaOp::$name($a, $b, $c) };
 }
 
+ssa_words!(
+    idx Output,
+    idx Input,
+    unary CopyReg,
+    imm CopyImm,
+    unary NegReg,
+    unary AbsReg,
+    unary RecipReg,
+    unary SqrtReg,
+    unary SquareReg,
+    unary FloorReg,
+    unary CeilReg,
+    unary RoundReg,
+    unary SinReg,
+    unary CosReg,
+    unary TanReg,
+    unary AsinReg,
+    unary AcosReg,
+    unary AtanReg,
+    unary ExpReg,
+    unary LnReg,
+    unary NotReg,
+    reg_imm AddRegImm,
+    reg_imm MulRegImm,
+    reg_imm DivRegImm,
+    reg_imm DivImmReg,
+    reg_imm SubImmReg,
+    reg_imm SubRegImm,
+    reg_reg ModRegReg,
+    reg_imm ModRegImm,
+    reg_imm AtanRegImm,
+    reg_imm CompareRegImm,
+    reg_imm MinRegImm,
+    reg_imm MaxRegImm,
+    reg_imm AndRegImm,
+    reg_imm OrRegImm,
+    reg_imm ModImmReg,
+    reg_imm AtanImmReg,
+    reg_imm CompareImmReg,
+    reg_reg AddRegReg,
+    reg_reg MulRegReg,
+    reg_reg DivRegReg,
+    reg_reg SubRegReg,
+    reg_reg CompareRegReg,
+    reg_reg AtanRegReg,
+    reg_reg MinRegReg,
+    reg_reg MaxRegReg,
+    reg_reg AndRegReg,
+    reg_reg OrRegReg,
+);
+
 opcodes!(
     /// Operations used in register-allocated tapes
     ///
@@ -1,6 +1,10 @@
 //! Tape used for evaluation
-use crate::compiler::{RegOp, RegisterAllocator, SsaTape};
+use crate::{
+    compiler::{Clause, RegOp, RegisterAllocator, SsaTape, clause::var_names},
+    var::VarMap,
+};
 use serde::{Deserialize, Serialize};
+use std::fmt::Write;
 
 /// Low-level tape for use with the Fidget virtual machine (or to be lowered
 /// further into machine instructions).
@@ -57,6 +61,10 @@ impl RegTape {
     pub fn is_empty(&self) -> bool {
         self.tape.is_empty()
     }
+    /// Returns the number of heap bytes allocated by the tape
+    pub fn memory_usage(&self) -> usize {
+        self.tape.capacity() * std::mem::size_of::<RegOp>()
+    }
     /// Returns a front-to-back iterator
     ///
     /// This is the opposite of evaluation order; it will visit the root of the
@@ -65,12 +73,39 @@ impl RegTape {
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = &RegOp> {
         self.into_iter()
     }
+
+    /// Returns a human-readable listing of the tape, in evaluation order
+    ///
+    /// Registers are written as `rN` and memory slots as `[N]`.  If `vars` is
+    /// provided, each input is annotated with its variable name.
+    pub fn disassemble(&self, vars: Option<&VarMap>) -> String {
+        let names = vars.map(var_names).unwrap_or_default();
+        let mut out = String::new();
+        for &op in self.iter().rev() {
+            disassemble_op(op, &mut out, &names);
+        }
+        out
+    }
+
     #[inline]
     pub(crate) fn push(&mut self, op: RegOp) {
         self.tape.push(op)
     }
 }
 
+/// Writes a single line of disassembly for the given operation
+///
+/// See [`RegTape::disassemble`] for the format.
+pub(crate) fn disassemble_op(op: RegOp, w: &mut String, names: &[String]) {
+    match op {
+        RegOp::Load(reg, mem) => writeln!(w, "r{reg} = LOAD [{mem}]").unwrap(),
+        RegOp::Store(reg, mem) => {
+            writeln!(w, "[{mem}] = STORE r{reg}").unwrap()
+        }
+        op => Clause::from_reg_op(op).unwrap().disassemble(w, "r", names),
+    }
+}
+
 impl<'a> IntoIterator for &'a RegTape {
     type Item = &'a RegOp;
     type IntoIter = std::slice::Iter<'a, RegOp>;
@@ -0,0 +1,355 @@
+//! Shader and C source generation from SSA clauses
+use crate::{
+    Error,
+    compiler::{Clause, Operand, SsaTape},
+    context::{BinaryOpcode, UnaryOpcode},
+    var::{Var, VarMap},
+};
+use std::fmt::Write;
+
+/// Language used for generated source
+///
+/// Generated source consists of a prelude of helper functions (prefixed with
+/// `fidget_`), which check their inputs to match the VM's float semantics,
+/// followed by one function per exported tape.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ShaderTarget {
+    /// GLSL 3.30 or GLSL ES 3.00 (or later)
+    Glsl,
+    /// Metal Shading Language
+    ///
+    /// Metal enables fast math by default, which lets the compiler assume
+    /// that values are finite; shaders should be compiled with fast math
+    /// disabled if NaN and infinity must be handled like the VM.
+    Msl,
+    /// C99, depending only on `<math.h>` and `<stddef.h>`
+    ///
+    /// Helper functions are declared `static inline`, so sources for multiple
+    /// tapes may be linked into the same program.  NaN and infinity are
+    /// handled like the VM, so the source should not be compiled with
+    /// `-ffast-math`.
+    C,
+}
+
+impl ShaderTarget {
+    /// Returns helper functions used by generated code
+    ///
+    /// Built-in math functions have undefined (in GLSL and MSL) or IEEE 754
+    /// (in C) results for out-of-domain inputs, and don't agree with the VM
+    /// on NaN handling in `min` and `max`, so these helpers check their inputs
+    /// to match the VM's float semantics.  The helpers are written once; only
+    /// headers, qualifiers, literals, and built-in names differ by target.
+    pub(crate) fn prelude(self) -> String {
+        let (header, qualifier) = match self {
+            ShaderTarget::Glsl => ("", ""),
+            ShaderTarget::Msl => {
+                ("#include <metal_stdlib>\nusing namespace metal;\n\n", "")
+            }
+            ShaderTarget::C => (
+                "#include <math.h>\n#include <stddef.h>\n\n",
+                "static inline ",
+            ),
+        };
+        let [zero, half, one, neg_one, nan, neg_inf] =
+            [0.0, 0.5, 1.0, -1.0, f32::NAN, f32::NEG_INFINITY]
+                .map(|f| self.float(f));
+        let [abs, trunc, sqrt, asin, acos, log, min, max] =
+            ["abs", "trunc", "sqrt", "asin", "acos", "log", "min", "max"]
+                .map(|f| self.builtin(f));
+        // GLSL and MSL have no exact remainder, so it's computed with `trunc`
+        let rem = match self {
+            ShaderTarget::Glsl | ShaderTarget::Msl => "a - b * trunc(a / b)",
+            ShaderTarget::C => "fmodf(a, b)",
+        };
+        let q = qualifier;
+        format!(
+            "\
+{header}{q}float fidget_sqrt(float a) {{
+    return (a < {zero} || isnan(a)) ? {nan} : {sqrt}(a);
+}}
+{q}float fidget_round(float a) {{
+    float t = {trunc}(a);
+    return {abs}(a - t) >= {half} ? t + (a < {zero} ? {neg_one} : {one}) : t;
+}}
+{q}float fidget_asin(float a) {{
+    return (a < {neg_one} || a > {one} || isnan(a)) ? {nan} : {asin}(a);
+}}
+{q}float fidget_acos(float a) {{
+    return (a < {neg_one} || a > {one} || isnan(a)) ? {nan} : {acos}(a);
+}}
+{q}float fidget_ln(float a) {{
+    if (a < {zero} || isnan(a)) {{
+        return {nan};
+    }} else if (a == {zero}) {{
+        return {neg_inf};
+    }}
+    return {log}(a);
+}}
+{q}float fidget_not(float a) {{
+    return (a == {zero} && !isnan(a)) ? {one} : {zero};
+}}
+{q}float fidget_min(float a, float b) {{
+    return (isnan(a) || isnan(b)) ? {nan} : {min}(a, b);
+}}
+{q}float fidget_max(float a, float b) {{
+    return (isnan(a) || isnan(b)) ? {nan} : {max}(a, b);
+}}
+{q}float fidget_compare(float a, float b) {{
+    if (isnan(a) || isnan(b)) {{
+        return {nan};
+    }}
+    return a < b ? {neg_one} : (a > b ? {one} : {zero});
+}}
+{q}float fidget_mod(float a, float b) {{
+    float r = {rem};
+    return r < {zero} ? r + {abs}(b) : r;
+}}
+{q}float fidget_and(float a, float b) {{
+    return (a == {zero} && !isnan(a)) ? a : b;
+}}
+{q}float fidget_or(float a, float b) {{
+    return (a != {zero} || isnan(a)) ? a : b;
+}}
+"
+        )
+    }
+
+    /// Returns the name of an `N`-element float vector type
+    fn vec_type(self, n: usize) -> String {
+        match self {
+            ShaderTarget::Glsl => format!("vec{n}"),
+            ShaderTarget::Msl => format!("float{n}"),
+            ShaderTarget::C => unreachable!("C has no vector types"),
+        }
+    }
+
+    /// Returns the name of a single-precision built-in math function
+    ///
+    /// `name` is the GLSL / MSL name; C uses the `f`-suffixed `<math.h>`
+    /// function instead.
+    fn builtin(self, name: &str) -> String {
+        match (self, name) {
+            (ShaderTarget::Glsl | ShaderTarget::Msl, _) => name.to_owned(),
+            (ShaderTarget::C, "abs" | "min" | "max") => format!("f{name}f"),
+            (ShaderTarget::C, _) => format!("{name}f"),
+        }
+    }
+
+    /// Formats a float as an expression which reproduces it exactly
+    fn float(self, f: f32) -> String {
+        let (bitcast, suffix) = match self {
+            ShaderTarget::Glsl => ("uintBitsToFloat", ""),
+            ShaderTarget::Msl => ("as_type<float>", "f"),
+            ShaderTarget::C if f.is_nan() => return "NAN".to_owned(),
+            ShaderTarget::C if f.is_infinite() => {
+                return if f < 0.0 { "(-INFINITY)" } else { "INFINITY" }
+                    .to_owned();
+            }
+            ShaderTarget::C => ("", "f"),
+        };
+        if !f.is_finite() {
+            format!("{bitcast}({:#010x}u)", f.to_bits())
+        } else if f.is_sign_negative() {
+            format!("({f:?}{suffix})")
+        } else {
+            format!("{f:?}{suffix}")
+        }
+    }
+
+    fn unary(self, op: UnaryOpcode, arg: &str) -> String {
+        let f = match op {
+            UnaryOpcode::Neg => return format!("-{arg}"),
+            UnaryOpcode::Square => return format!("{arg} * {arg}"),
+            UnaryOpcode::Recip => {
+                return format!("{} / {arg}", self.float(1.0));
+            }
+            UnaryOpcode::Abs => self.builtin("abs"),
+            UnaryOpcode::Sqrt => "fidget_sqrt".to_owned(),
+            UnaryOpcode::Floor => self.builtin("floor"),
+            UnaryOpcode::Ceil => self.builtin("ceil"),
+            UnaryOpcode::Round => "fidget_round".to_owned(),
+            UnaryOpcode::Sin => self.builtin("sin"),
+            UnaryOpcode::Cos => self.builtin("cos"),
+            UnaryOpcode::Tan => self.builtin("tan"),
+            UnaryOpcode::Asin => "fidget_asin".to_owned(),
+            UnaryOpcode::Acos => "fidget_acos".to_owned(),
+            UnaryOpcode::Atan => self.builtin("atan"),
+            UnaryOpcode::Exp => self.builtin("exp"),
+            UnaryOpcode::Ln => "fidget_ln".to_owned(),
+            UnaryOpcode::Not => "fidget_not".to_owned(),
+        };
+        format!("{f}({arg})")
+    }
+
+    fn binary(self, op: BinaryOpcode, lhs: &str, rhs: &str) -> String {
+        let f = match op {
+            BinaryOpcode::Add => return format!("{lhs} + {rhs}"),
+            BinaryOpcode::Sub => return format!("{lhs} - {rhs}"),
+            BinaryOpcode::Mul => return format!("{lhs} * {rhs}"),
+            BinaryOpcode::Div => return format!("{lhs} / {rhs}"),
+            BinaryOpcode::Atan => match self {
+                ShaderTarget::Glsl => "atan".to_owned(),
+                ShaderTarget::Msl | ShaderTarget::C => self.builtin("atan2"),
+            },
+            BinaryOpcode::Min => "fidget_min".to_owned(),
+            BinaryOpcode::Max => "fidget_max".to_owned(),
+            BinaryOpcode::Compare => "fidget_compare".to_owned(),
+            BinaryOpcode::Mod => "fidget_mod".to_owned(),
+            BinaryOpcode::And => "fidget_and".to_owned(),
+            BinaryOpcode::Or => "fidget_or".to_owned(),
+        };
+        format!("{f}({lhs}, {rhs})")
+    }
+}
+
+/// Writes a function `{ty} {name}(vec3 p)` which evaluates the clauses
+///
+/// The return type is `float` for a single output, or an `N`-element vector
+/// for `N` outputs (up to 4).  Only [`Var::X`], [`Var::Y`], and [`Var::Z`] may
+/// be used as inputs; they're read from `p`.
+///
+/// In C, the function is `float {name}(const float* p)` for a single output,
+/// or `void {name}(const float* p, float* out)` for multiple outputs.
+pub(crate) fn write_function(
+    target: ShaderTarget,
+    out: &mut String,
+    name: &str,
+    clauses: impl Iterator<Item = Clause>,
+    vars: &VarMap,
+    output_count: usize,
+) -> Result<(), Error> {
+    let mut inputs = vec![""; vars.len()];
+    for (v, i) in vars.iter() {
+        inputs[i] = match (target, v) {
+            (ShaderTarget::C, Var::X) => "p[0]",
+            (ShaderTarget::C, Var::Y) => "p[1]",
+            (ShaderTarget::C, Var::Z) => "p[2]",
+            (_, Var::X) => "p.x",
+            (_, Var::Y) => "p.y",
+            (_, Var::Z) => "p.z",
+            (_, v) => return Err(Error::BadShaderVar(v)),
+        };
+    }
+    let input = |var: u32| inputs[var as usize].to_owned();
+
+    match (target, output_count) {
+        (ShaderTarget::C, 1) => {
+            writeln!(out, "float {name}(const float* p) {{").unwrap();
+            writeln!(out, "    float result;").unwrap();
+            write_body(target, out, clauses, "    ", input, |_| {
+                "result".to_owned()
+            });
+            writeln!(out, "    return result;\n}}").unwrap();
+        }
+        (ShaderTarget::C, _) => {
+            writeln!(out, "void {name}(const float* p, float* out) {{")
+                .unwrap();
+            write_body(target, out, clauses, "    ", input, |i| {
+                format!("out[{i}]")
+            });
+            writeln!(out, "}}").unwrap();
+        }
+        (_, 1..=4) => {
+            let ty = match output_count {
+                1 => "float".to_owned(),
+                n => target.vec_type(n),
+            };
+            writeln!(out, "{ty} {name}({} p) {{", target.vec_type(3)).unwrap();
+            writeln!(out, "    {ty} result;").unwrap();
+            write_body(target, out, clauses, "    ", input, |i| {
+                if output_count == 1 {
+                    debug_assert_eq!(i, 0);
+                    "result".to_owned()
+                } else {
+                    format!("result[{i}]")
+                }
+            });
+            writeln!(out, "    return result;\n}}").unwrap();
+        }
+        (_, n) => return Err(Error::BadOutputCount(n, 1)),
+    }
+    Ok(())
+}
+
+/// Writes C99 source which evaluates the given tape
+///
+/// See [`VmData::to_c`](crate::vm::VmData::to_c) for the generated API.
+pub(crate) fn write_c(
+    out: &mut String,
+    name: &str,
+    tape: &SsaTape,
+    slice: bool,
+) {
+    let target = ShaderTarget::C;
+    out.push_str(&target.prelude());
+    writeln!(out, "\nvoid {name}(const float* vars, float* out) {{").unwrap();
+    write_body(
+        target,
+        out,
+        tape.clauses(),
+        "    ",
+        |v| format!("vars[{v}]"),
+        |i| format!("out[{i}]"),
+    );
+    out.push_str("}\n");
+
+    if slice {
+        writeln!(
+            out,
+            "\nvoid {name}_slice(const float* const* vars, float* const* out, \
+             size_t n) {{\n    for (size_t i = 0; i < n; ++i) {{"
+        )
+        .unwrap();
+        write_body(
+            target,
+            out,
+            tape.clauses(),
+            "        ",
+            |v| format!("vars[{v}][i]"),
+            |i| format!("out[{i}][i]"),
+        );
+        out.push_str("    }\n}\n");
+    }
+}
+
+/// Writes one statement per clause
+///
+/// `input` returns the expression which reads the given input, and `output`
+/// returns the location to which the given output is written.
+fn write_body(
+    target: ShaderTarget,
+    out: &mut String,
+    clauses: impl Iterator<Item = Clause>,
+    indent: &str,
+    input: impl Fn(u32) -> String,
+    output: impl Fn(u32) -> String,
+) {
+    let operand = |o| match o {
+        Operand::Reg(r) => format!("r{r}"),
+        Operand::Imm(f) => target.float(f),
+    };
+    for c in clauses {
+        let line = match c {
+            Clause::Input { out, var } => {
+                format!("float r{out} = {};", input(var))
+            }
+            Clause::Output { arg, index } => {
+                format!("{} = r{arg};", output(index))
+            }
+            Clause::Copy { out, arg } => {
+                format!("float r{out} = {};", operand(arg))
+            }
+            Clause::Unary { op, out, arg } => {
+                format!(
+                    "float r{out} = {};",
+                    target.unary(op, &format!("r{arg}"))
+                )
+            }
+            Clause::Binary { op, out, lhs, rhs } => {
+                let (lhs, rhs) = (operand(lhs), operand(rhs));
+                format!("float r{out} = {};", target.binary(op, &lhs, &rhs))
+            }
+        };
+        writeln!(out, "{indent}{line}").unwrap();
+    }
+}
@@ -1,13 +1,14 @@
 //use crate::vm::{RegisterAllocator, Tape as VmTape};
 use crate::{
     Context, Error,
-    compiler::SsaOp,
+    compiler::{BudgetTracker, BuildBudget, Clause, SsaOp, clause::var_names},
     context::{BinaryOpcode, Node, Op, UnaryOpcode},
     var::VarMap,
 };
 use serde::{Deserialize, Serialize};
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
 
 /// Instruction tape, storing [opcodes in SSA form](crate::compiler::SsaOp)
 ///
@@ -37,6 +38,27 @@ impl SsaTape {
     /// This should always succeed unless the `root` is from a different
     /// `Context`, in which case `Error::BadNode` will be returned.
     pub fn new(ctx: &Context, roots: &[Node]) -> Result<(Self, VarMap), Error> {
+        Self::new_with_budget(ctx, roots, &BuildBudget::default())
+    }
+
+    /// Flattens a subtree of the graph, subject to a [`BuildBudget`]
+    ///
+    /// Returns [`Error::TapeTooLong`] or [`Error::BuildTimeout`] if the budget
+    /// is exceeded; the operation budget is checked before the tape is built.
+    pub fn new_with_budget(
+        ctx: &Context,
+        roots: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<(Self, VarMap), Error> {
+        Self::build(ctx, roots, &budget.start())
+    }
+
+    /// Flattens a subtree of the graph, checking an in-progress budget
+    pub(crate) fn build(
+        ctx: &Context,
+        roots: &[Node],
+        budget: &BudgetTracker,
+    ) -> Result<(Self, VarMap), Error> {
         let mut mapping = HashMap::new();
         let mut parent_count: HashMap<Node, usize> = HashMap::new();
         let mut slot_count = 0;
@@ -56,6 +78,10 @@ impl SsaTape {
             if !seen.insert(node) {
                 continue;
             }
+            if seen.len() % BudgetTracker::CHECK_INTERVAL == 0 {
+                budget.check_ops(slot_count as usize)?;
+                budget.check_time()?;
+            }
             let op = ctx.get_op(node).ok_or(Error::BadNode)?;
             let prev = match op {
                 Op::Const(c) => {
@@ -64,6 +90,11 @@ impl SsaTape {
                 _ => {
                     if let Op::Input(v) = op {
                         vars.insert(*v);
+                        if let (Some(i), Some(info)) =
+                            (v.index(), ctx.var_info(*v))
+                        {
+                            vars.set_info(i, info.clone());
+                        }
                     }
                     let i = slot_count;
                     slot_count += 1;
@@ -77,6 +108,9 @@ impl SsaTape {
             }
         }
 
+        // Output nodes may add an extra clause, for constant outputs
+        budget.check_ops(slot_count as usize + roots.len())?;
+
         // Now that we've populated our parents, flatten the graph
         let mut seen = HashSet::new();
         let mut todo = roots.to_vec();
@@ -102,6 +136,9 @@ impl SsaTape {
                 continue;
             }
 
+            if seen.len() % BudgetTracker::CHECK_INTERVAL == 0 {
+                budget.check_time()?;
+            }
             let op = ctx.get_op(node).unwrap();
             for child in op.iter_children() {
                 todo.push(child);
@@ -243,14 +280,140 @@ impl SsaTape {
             tape.push(op);
         }
 
-        Ok((
-            SsaTape {
-                tape,
-                choice_count,
-                output_count: roots.len(),
-            },
-            vars,
-        ))
+        let mut out = SsaTape {
+            tape,
+            choice_count,
+            output_count: roots.len(),
+        };
+        out.peephole();
+        Ok((out, vars))
+    }
+
+    /// Applies local rewrites to the tape, then removes dead operations
+    ///
+    /// This is called automatically when the tape is built, before register
+    /// allocation.  Rewrites are exact in IEEE-754 arithmetic (ignoring the
+    /// sign of `NaN`), so evaluation results are unchanged:
+    /// - Copies are removed, with their users reading the source directly
+    /// - `-(-x)` becomes `x`
+    /// - `a + (-b)` and `(-b) + a` become `a - b`, and `a - (-b)` becomes
+    ///   `a + b`
+    /// - `(-a) * (-b)` and `(-a) / (-b)` drop both negations
+    /// - Negations are folded into immediates, e.g. `(-x) * c` becomes
+    ///   `x * -c` and `c - (-x)` becomes `x + c`
+    /// - `abs(-x)` and `square(-x)` drop the negation
+    ///
+    /// Registers are renumbered afterwards, so they remain tightly packed.
+    ///
+    /// `mul` + `add` pairs are deliberately not fused into a fused
+    /// multiply-add: it rounds once instead of twice, so it's not an exact
+    /// rewrite, and fused and unfused tapes (or evaluators which fuse and
+    /// those which can't) would no longer produce identical results.
+    pub fn peephole(&mut self) {
+        let reg_count = self
+            .tape
+            .iter()
+            .flat_map(|op| op.output().into_iter().chain(op.inputs()))
+            .max()
+            .map(|r| r as usize + 1)
+            .unwrap_or(0);
+        let mut alias = (0..reg_count as u32).collect::<Vec<_>>();
+        let mut defs: Vec<Option<SsaOp>> = vec![None; reg_count];
+
+        // Rewrite in evaluation order, so that arguments are already final
+        let mut forward = Vec::with_capacity(self.tape.len());
+        for mut op in self.tape.drain(..).rev() {
+            op.map_inputs(|r| alias[r as usize]);
+            let neg = |r: u32| match defs[r as usize] {
+                Some(SsaOp::NegReg(_, x)) => Some(x),
+                _ => None,
+            };
+            op = match op {
+                SsaOp::CopyReg(out, src) => {
+                    alias[out as usize] = src;
+                    continue;
+                }
+                SsaOp::NegReg(out, a) if neg(a).is_some() => {
+                    alias[out as usize] = neg(a).unwrap();
+                    continue;
+                }
+                SsaOp::AbsReg(out, a) => {
+                    SsaOp::AbsReg(out, neg(a).unwrap_or(a))
+                }
+                SsaOp::SquareReg(out, a) => {
+                    SsaOp::SquareReg(out, neg(a).unwrap_or(a))
+                }
+                SsaOp::AddRegReg(out, a, b) => match (neg(a), neg(b)) {
+                    (_, Some(b)) => SsaOp::SubRegReg(out, a, b),
+                    (Some(a), None) => SsaOp::SubRegReg(out, b, a),
+                    (None, None) => op,
+                },
+                SsaOp::SubRegReg(out, a, b) => match neg(b) {
+                    Some(b) => SsaOp::AddRegReg(out, a, b),
+                    None => op,
+                },
+                SsaOp::MulRegReg(out, a, b) => match (neg(a), neg(b)) {
+                    (Some(a), Some(b)) => SsaOp::MulRegReg(out, a, b),
+                    _ => op,
+                },
+                SsaOp::DivRegReg(out, a, b) => match (neg(a), neg(b)) {
+                    (Some(a), Some(b)) => SsaOp::DivRegReg(out, a, b),
+                    _ => op,
+                },
+                SsaOp::MulRegImm(out, a, imm) => match neg(a) {
+                    Some(a) => SsaOp::MulRegImm(out, a, -imm),
+                    None => op,
+                },
+                SsaOp::DivRegImm(out, a, imm) => match neg(a) {
+                    Some(a) => SsaOp::DivRegImm(out, a, -imm),
+                    None => op,
+                },
+                SsaOp::DivImmReg(out, a, imm) => match neg(a) {
+                    Some(a) => SsaOp::DivImmReg(out, a, -imm),
+                    None => op,
+                },
+                SsaOp::AddRegImm(out, a, imm) => match neg(a) {
+                    Some(a) => SsaOp::SubImmReg(out, a, imm),
+                    None => op,
+                },
+                SsaOp::SubImmReg(out, a, imm) => match neg(a) {
+                    Some(a) => SsaOp::AddRegImm(out, a, imm),
+                    None => op,
+                },
+                op => op,
+            };
+            if let Some(out) = op.output() {
+                defs[out as usize] = Some(op);
+            }
+            forward.push(op);
+        }
+
+        // Dead code elimination, walking backwards from the outputs
+        let mut used = vec![false; reg_count];
+        let mut choice_count = 0;
+        for op in forward.into_iter().rev() {
+            if op.output().is_some_and(|out| !used[out as usize]) {
+                continue;
+            }
+            for r in op.inputs() {
+                used[r as usize] = true;
+            }
+            choice_count += op.has_choice() as usize;
+            self.tape.push(op);
+        }
+        self.choice_count = choice_count;
+
+        // Renumber registers in evaluation order
+        let mut remap = vec![u32::MAX; reg_count];
+        let mut next = 0;
+        for op in self.tape.iter_mut().rev() {
+            op.map_inputs(|r| remap[r as usize]);
+            if let Some(out) = op.output() {
+                remap[out as usize] = next;
+                op.set_output(next);
+                next += 1;
+            }
+        }
     }
 
     /// Checks whether the tape is empty
@@ -263,6 +426,11 @@ impl SsaTape {
         self.tape.len()
     }
 
+    /// Returns the number of heap bytes allocated by the tape
+    pub fn memory_usage(&self) -> usize {
+        self.tape.capacity() * std::mem::size_of::<SsaOp>()
+    }
+
     /// Iterates over clauses in the tape in reverse-evaluation order
     ///
     /// The root (output) of the tape will be first in the iterator
@@ -270,140 +438,139 @@ impl SsaTape {
         self.tape.iter()
     }
 
+    /// Iterates over clauses in the tape in evaluation order
+    ///
+    /// Unlike [`iter`](Self::iter), this returns [`Clause`] values, which are
+    /// a stable representation meant for tools that translate tapes into
+    /// other languages.
+    pub fn clauses(&self) -> impl Iterator<Item = Clause> + '_ {
+        self.tape.iter().rev().map(|op| Clause::from(*op))
+    }
+
     /// Resets to an empty tape, preserving allocations
     pub fn reset(&mut self) {
         self.tape.clear();
         self.choice_count = 0;
     }
-    /// Pretty-prints the given tape to `stdout`
-    pub fn pretty_print(&self) {
-        for &op in self.tape.iter().rev() {
-            match op {
-                SsaOp::Output(arg, i) => {
-                    println!("OUTPUT[{i}] = ${arg}");
-                }
-                SsaOp::Input(out, i) => {
-                    println!("${out} = INPUT[{i}]");
-                }
-                SsaOp::NegReg(out, arg)
-                | SsaOp::AbsReg(out, arg)
-                | SsaOp::RecipReg(out, arg)
-                | SsaOp::SqrtReg(out, arg)
-                | SsaOp::CopyReg(out, arg)
-                | SsaOp::SquareReg(out, arg)
-                | SsaOp::FloorReg(out, arg)
-                | SsaOp::CeilReg(out, arg)
-                | SsaOp::RoundReg(out, arg)
-                | SsaOp::SinReg(out, arg)
-                | SsaOp::CosReg(out, arg)
-                | SsaOp::TanReg(out, arg)
-                | SsaOp::AsinReg(out, arg)
-                | SsaOp::AcosReg(out, arg)
-                | SsaOp::AtanReg(out, arg)
-                | SsaOp::ExpReg(out, arg)
-                | SsaOp::LnReg(out, arg)
-                | SsaOp::NotReg(out, arg) => {
-                    let op = match op {
-                        SsaOp::NegReg(..) => "NEG",
-                        SsaOp::AbsReg(..) => "ABS",
-                        SsaOp::RecipReg(..) => "RECIP",
-                        SsaOp::SqrtReg(..) => "SQRT",
-                        SsaOp::SquareReg(..) => "SQUARE",
-                        SsaOp::FloorReg(..) => "FLOOR",
-                        SsaOp::CeilReg(..) => "CEIL",
-                        SsaOp::RoundReg(..) => "ROUND",
-                        SsaOp::SinReg(..) => "SIN",
-                        SsaOp::CosReg(..) => "COS",
-                        SsaOp::TanReg(..) => "TAN",
-                        SsaOp::AsinReg(..) => "ASIN",
-                        SsaOp::AcosReg(..) => "ACOS",
-                        SsaOp::AtanReg(..) => "ATAN",
-                        SsaOp::ExpReg(..) => "EXP",
-                        SsaOp::LnReg(..) => "LN",
-                        SsaOp::NotReg(..) => "NOT",
-                        SsaOp::CopyReg(..) => "COPY",
-                        _ => unreachable!(),
-                    };
-                    println!("${out} = {op} ${arg}");
-                }
 
-                SsaOp::AddRegReg(out, lhs, rhs)
-                | SsaOp::MulRegReg(out, lhs, rhs)
-                | SsaOp::DivRegReg(out, lhs, rhs)
-                | SsaOp::SubRegReg(out, lhs, rhs)
-                | SsaOp::MinRegReg(out, lhs, rhs)
-                | SsaOp::MaxRegReg(out, lhs, rhs)
-                | SsaOp::ModRegReg(out, lhs, rhs)
-                | SsaOp::AndRegReg(out, lhs, rhs)
-                | SsaOp::AtanRegReg(out, lhs, rhs)
-                | SsaOp::OrRegReg(out, lhs, rhs) => {
-                    let op = match op {
-                        SsaOp::AddRegReg(..) => "ADD",
-                        SsaOp::MulRegReg(..) => "MUL",
-                        SsaOp::DivRegReg(..) => "DIV",
-                        SsaOp::AtanRegReg(..) => "ATAN",
-                        SsaOp::SubRegReg(..) => "SUB",
-                        SsaOp::MinRegReg(..) => "MIN",
-                        SsaOp::MaxRegReg(..) => "MAX",
-                        SsaOp::ModRegReg(..) => "MAX",
-                        SsaOp::AndRegReg(..) => "AND",
-                        SsaOp::OrRegReg(..) => "OR",
-                        _ => unreachable!(),
-                    };
-                    println!("${out} = {op} ${lhs} ${rhs}");
-                }
+    /// Converts the tape into a GraphViz drawing
+    ///
+    /// Each node is an SSA operation, with edges pointing to its arguments.
+    /// Operations with a choice (`min`, `max`, `and`, and `or`) are drawn in
+    /// blue, with dashed edges to the arguments which may be pruned when the
+    /// tape is simplified.
+    ///
+    /// Operations are grouped by the choices that they depend on: each group
+    /// is drawn as a labeled cluster listing the choice branches (e.g. `n4.lhs`
+    /// for the left-hand argument of node `n4`) which must all be taken for
+    /// the group's operations to be used.  If any of those branches isn't
+    /// taken, simplification removes every operation in the group.
+    /// Operations which are used regardless of choices aren't clustered.
+    pub fn dot(&self) -> String {
+        let ops = self.tape.iter().rev().collect::<Vec<_>>();
 
-                SsaOp::AddRegImm(out, arg, imm)
-                | SsaOp::MulRegImm(out, arg, imm)
-                | SsaOp::DivRegImm(out, arg, imm)
-                | SsaOp::DivImmReg(out, arg, imm)
-                | SsaOp::SubImmReg(out, arg, imm)
-                | SsaOp::SubRegImm(out, arg, imm)
-                | SsaOp::AtanRegImm(out, arg, imm)
-                | SsaOp::AtanImmReg(out, arg, imm)
-                | SsaOp::MinRegImm(out, arg, imm)
-                | SsaOp::MaxRegImm(out, arg, imm)
-                | SsaOp::ModRegImm(out, arg, imm)
-                | SsaOp::ModImmReg(out, arg, imm)
-                | SsaOp::AndRegImm(out, arg, imm)
-                | SsaOp::OrRegImm(out, arg, imm) => {
-                    let (op, swap) = match op {
-                        SsaOp::AddRegImm(..) => ("ADD", false),
-                        SsaOp::MulRegImm(..) => ("MUL", false),
-                        SsaOp::DivImmReg(..) => ("DIV", true),
-                        SsaOp::DivRegImm(..) => ("DIV", false),
-                        SsaOp::SubImmReg(..) => ("SUB", true),
-                        SsaOp::SubRegImm(..) => ("SUB", false),
-                        SsaOp::AtanImmReg(..) => ("ATAN", true),
-                        SsaOp::AtanRegImm(..) => ("ATAN", false),
-                        SsaOp::MinRegImm(..) => ("MIN", false),
-                        SsaOp::MaxRegImm(..) => ("MAX", false),
-                        SsaOp::ModRegImm(..) => ("MOD", false),
-                        SsaOp::ModImmReg(..) => ("MOD", true),
-                        SsaOp::AndRegImm(..) => ("AND", false),
-                        SsaOp::OrRegImm(..) => ("OR", false),
-                        _ => unreachable!(),
-                    };
-                    if swap {
-                        println!("${out} = {op} {imm} ${arg}");
-                    } else {
-                        println!("${out} = {op} ${arg} {imm}");
-                    }
-                }
-                SsaOp::CompareRegReg(out, lhs, rhs) => {
-                    println!("${out} = COMPARE {lhs} {rhs}")
-                }
-                SsaOp::CompareRegImm(out, arg, imm) => {
-                    println!("${out} = COMPARE {arg} {imm}")
-                }
-                SsaOp::CompareImmReg(out, arg, imm) => {
-                    println!("${out} = COMPARE {imm} {arg}")
-                }
-                SsaOp::CopyImm(out, imm) => {
-                    println!("${out} = COPY {imm}");
+        // Map from SSA register to node index, for drawing edges
+        let mut regs = HashMap::new();
+        for (i, op) in ops.iter().enumerate() {
+            if let Some(r) = op.output() {
+                regs.insert(r, i);
+            }
+        }
+
+        // Find the choice branches required by each node, walking from the
+        // outputs towards the inputs.  A node that's reachable through
+        // several paths only requires the branches common to all of them.
+        let mut groups: Vec<Option<BTreeSet<(usize, bool)>>> =
+            vec![None; ops.len()];
+        for (i, op) in ops.iter().enumerate().rev() {
+            if matches!(op, SsaOp::Output(..)) {
+                groups[i] = Some(BTreeSet::new());
+            }
+            let Some(group) = groups[i].clone() else {
+                continue;
+            };
+            for (j, arg) in op.inputs().enumerate() {
+                let mut g = group.clone();
+                if op.has_choice() {
+                    g.insert((i, j == 0));
                 }
+                let prev = &mut groups[regs[&arg]];
+                *prev = Some(match prev.take() {
+                    Some(p) => p.intersection(&g).cloned().collect(),
+                    None => g,
+                });
+            }
+        }
+        let mut clusters: BTreeMap<_, Vec<usize>> = BTreeMap::new();
+        for (i, g) in groups.into_iter().enumerate() {
+            clusters.entry(g.unwrap_or_default()).or_default().push(i);
+        }
+
+        let mut out = "digraph mygraph{\n".to_owned();
+        for (k, (group, nodes)) in clusters.iter().enumerate() {
+            if !group.is_empty() {
+                let label = group
+                    .iter()
+                    .map(|(i, lhs)| {
+                        format!("n{i}.{}", if *lhs { "lhs" } else { "rhs" })
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" & ");
+                writeln!(out, "subgraph cluster_{k} {{").unwrap();
+                writeln!(
+                    out,
+                    r##"label = "{label}"; style = "filled"; color = "#EEEEEE""##
+                )
+                .unwrap();
+            }
+            for &i in nodes {
+                let op = ops[i];
+                let (color, shape) = match op {
+                    SsaOp::Input(..) => ("#FF0000", "circle"),
+                    SsaOp::CopyImm(..) => ("#00FF00", "oval"),
+                    SsaOp::Output(..) => ("#000000", "oval"),
+                    op if op.has_choice() => ("#1E90FF", "box"),
+                    _ => ("#DAA520", "box"),
+                };
+                writeln!(
+                    out,
+                    r#"n{i} [label = "{op:?}", color = "{color}", shape = "{shape}"]"#,
+                )
+                .unwrap();
+            }
+            if !group.is_empty() {
+                out += "}\n";
+            }
+        }
+
+        for (i, op) in ops.iter().enumerate() {
+            let style = if op.has_choice() { "dashed" } else { "solid" };
+            for arg in op.inputs() {
+                writeln!(out, "n{i} -> n{} [style = \"{style}\"]", regs[&arg])
+                    .unwrap();
             }
         }
+        out += "}\n";
+        out
+    }
+
+    /// Returns a human-readable listing of the tape, in evaluation order
+    ///
+    /// SSA registers are written as `$n`, and immediates are written inline.
+    /// If `vars` is provided, each input is annotated with its variable name
+    /// (using the name from [`Context::declare_var`] where available).
+    pub fn disassemble(&self, vars: Option<&VarMap>) -> String {
+        let names = vars.map(var_names).unwrap_or_default();
+        let mut out = String::new();
+        for c in self.clauses() {
+            c.disassemble(&mut out, "$", &names);
+        }
+        out
+    }
+
+    /// Pretty-prints the given tape to `stdout`
+    pub fn pretty_print(&self) {
+        print!("{}", self.disassemble(None));
     }
 }
 
@@ -430,6 +597,96 @@ mod test {
         assert_eq!(vs.len(), 2);
     }
 
+    #[test]
+    fn test_peephole() {
+        let forward = [
+            SsaOp::Input(0, 0),
+            SsaOp::Input(1, 1),
+            SsaOp::NegReg(2, 0),
+            SsaOp::NegReg(3, 2),
+            SsaOp::CopyReg(4, 3),
+            SsaOp::AddRegReg(5, 1, 2),
+            SsaOp::MulRegImm(6, 2, 3.0),
+            SsaOp::MinRegReg(7, 5, 4),
+            SsaOp::SubImmReg(8, 2, 1.0),
+            SsaOp::MaxRegReg(9, 7, 6),
+            SsaOp::Output(9, 0),
+            SsaOp::Output(8, 1),
+        ];
+        let mut tape = SsaTape {
+            tape: forward.into_iter().rev().collect(),
+            choice_count: 2,
+            output_count: 2,
+        };
+        tape.peephole();
+
+        let expected = [
+            SsaOp::Input(0, 0),
+            SsaOp::Input(1, 1),
+            SsaOp::SubRegReg(2, 1, 0),
+            SsaOp::MulRegImm(3, 0, -3.0),
+            SsaOp::MinRegReg(4, 2, 0),
+            SsaOp::AddRegImm(5, 0, 1.0),
+            SsaOp::MaxRegReg(6, 4, 3),
+            SsaOp::Output(6, 0),
+            SsaOp::Output(5, 1),
+        ];
+        let actual = tape.iter().rev().map(|op| format!("{op:?}"));
+        let expected = expected.iter().map(|op| format!("{op:?}"));
+        assert!(actual.eq(expected));
+        assert_eq!(tape.choice_count, 2);
+
+        // Rewrites which would change the sign of zero are skipped
+        let forward = [
+            SsaOp::Input(0, 0),
+            SsaOp::Input(1, 1),
+            SsaOp::SubRegReg(2, 0, 1),
+            SsaOp::NegReg(3, 2),
+            SsaOp::Output(3, 0),
+        ];
+        let mut tape = SsaTape {
+            tape: forward.into_iter().rev().collect(),
+            choice_count: 0,
+            output_count: 1,
+        };
+        tape.peephole();
+        assert_eq!(tape.len(), 5);
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.min(x, y).unwrap();
+        let root = ctx.add(a, 1.0).unwrap();
+        let (tape, _vs) = SsaTape::new(&ctx, &[root]).unwrap();
+
+        let dot = tape.dot();
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(dot.matches("[label").count(), tape.len());
+        assert_eq!(dot.matches("->").count(), 4); // output, add, min (x2)
+        assert_eq!(dot.matches("dashed").count(), 2);
+        assert_eq!(dot.matches("#1E90FF").count(), 1);
+
+        // `x` and `y` are each only used by one branch of the `min`
+        assert_eq!(dot.matches("subgraph cluster").count(), 2);
+        assert!(dot.contains(r#"label = "n2.lhs""#), "{dot}");
+        assert!(dot.contains(r#"label = "n2.rhs""#), "{dot}");
+
+        // `x` is used by both branches (directly, and through `b`), so it's not
+        // part of a group
+        let b = ctx.add(x, y).unwrap();
+        let root = ctx.max(x, b).unwrap();
+        let (tape, _vs) = SsaTape::new(&ctx, &[root]).unwrap();
+        let dot = tape.dot();
+        assert_eq!(dot.matches("subgraph cluster").count(), 1, "{dot}");
+        let cluster = dot.split("subgraph cluster").nth(1).unwrap();
+        let cluster = cluster.split('}').next().unwrap();
+        assert_eq!(cluster.matches("[label").count(), 2, "{dot}"); // y, add
+        assert!(cluster.contains("Add"), "{dot}");
+    }
+
     #[test]
     fn test_dupe() {
         let mut ctx = Context::new();
@@ -80,6 +80,12 @@ where
     pub fn keys(&self) -> impl Iterator<Item = I> {
         (0..self.data.len()).map(I::new)
     }
+    /// Returns the approximate number of heap bytes held by this map
+    pub fn memory_usage(&self) -> usize {
+        self.data.capacity() * std::mem::size_of::<V>()
+            + self.map.capacity()
+                * (std::mem::size_of::<V>() + std::mem::size_of::<I>())
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -17,17 +17,24 @@
 //! In other words, the typical workflow is `Tree → (Context, Node) → Function`.
 mod indexed;
 mod op;
+mod stats;
 mod tree;
 
 use indexed::{Index, IndexMap, IndexVec, define_index};
 pub use op::{BinaryOpcode, Op, UnaryOpcode};
+pub use stats::Stats;
 pub use tree::{Tree, TreeOp};
 
-use crate::{Error, var::Var};
+use crate::{
+    Error,
+    types::Interval,
+    var::{Var, VarIndex, VarInfo},
+    vm::Choice,
+};
 
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
-use std::io::{BufRead, BufReader, Read};
+use std::io::Read;
 use std::sync::Arc;
 
 use nalgebra::Matrix4;
@@ -35,6 +42,12 @@ use ordered_float::OrderedFloat;
 
 define_index!(Node, "An index in the `Context::ops` map");
 
+/// Maximum number of nodes visited when canonicalizing an affine sum
+///
+/// This bounds the cost of each call to [`Context::add`] or [`Context::sub`];
+/// larger sums are left as-is.
+const AFFINE_LIMIT: usize = 32;
+
 /// A `Context` holds a set of deduplicated constants, variables, and
 /// operations.
 ///
@@ -48,6 +61,8 @@ define_index!(Node, "An index in the `Context::ops` map");
 #[derive(Debug, Default)]
 pub struct Context {
     ops: IndexMap<Op, Node>,
+    /// Metadata for variables declared with [`Context::declare_var`]
+    var_info: HashMap<VarIndex, VarInfo>,
 }
 
 impl Context {
@@ -69,6 +84,47 @@ impl Context {
     /// ```
     pub fn clear(&mut self) {
         self.ops.clear();
+        self.var_info.clear();
+    }
+
+    /// Removes every node which isn't reachable from the given roots
+    ///
+    /// This rebuilds the context's storage, so **all** existing [`Node`]
+    /// handles are invalidated; the returned nodes correspond to `roots`, in
+    /// the same order.  Metadata for declared variables is kept.
+    ///
+    /// ```
+    /// # use fidget_core::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let _garbage = ctx.add(x, y)?;
+    /// let root = ctx.square(x)?;
+    /// assert_eq!(ctx.len(), 4);
+    ///
+    /// let [root] = ctx.compact(&[root])?.try_into().unwrap();
+    /// assert_eq!(ctx.len(), 2);
+    /// assert_eq!(ctx.eval_xyz(root, 3.0, 0.0, 0.0)?, 9.0);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn compact(&mut self, roots: &[Node]) -> Result<Vec<Node>, Error> {
+        // Children are always created before their parents, so rebuilding in
+        // index order preserves both validity and the relative order of nodes
+        // (which commutative operations rely on).
+        let order = self.reachable(roots)?;
+
+        let mut ops = IndexMap::default();
+        let mut remap = HashMap::new();
+        for n in order {
+            let op = match *self.get_op(n).unwrap() {
+                op @ (Op::Const(..) | Op::Input(..)) => op,
+                Op::Unary(op, a) => Op::Unary(op, remap[&a]),
+                Op::Binary(op, a, b) => Op::Binary(op, remap[&a], remap[&b]),
+            };
+            remap.insert(n, ops.insert(op));
+        }
+        self.ops = ops;
+        Ok(roots.iter().map(|r| remap[r]).collect())
     }
 
     /// Returns the number of [`Op`] nodes in the context
@@ -92,6 +148,15 @@ impl Context {
         self.ops.is_empty()
     }
 
+    /// Returns the approximate number of heap bytes held by the context
+    ///
+    /// This is based on allocated capacity, so it doesn't shrink when nodes
+    /// are removed (e.g. by [`Context::clear`]); it therefore doubles as a
+    /// high-water mark for the context's storage.
+    pub fn memory_usage(&self) -> usize {
+        self.ops.memory_usage()
+    }
+
     /// Checks whether the given [`Node`] is valid in this context
     fn check_node(&self, node: Node) -> Result<(), Error> {
         self.get_op(node).ok_or(Error::BadNode).map(|_| ())
@@ -159,6 +224,11 @@ impl Context {
         self.var(Var::Z)
     }
 
+    /// Constructs or finds a [`Var::W`] node
+    pub fn w(&mut self) -> Node {
+        self.var(Var::W)
+    }
+
     /// Constructs or finds a variable input node
     ///
     /// To make an anonymous variable, call this function with [`Var::new()`]:
@@ -180,6 +250,71 @@ impl Context {
         self.ops.insert(Op::Input(v))
     }
 
+    /// Declares a new named variable, with a default value and optional range
+    ///
+    /// The variable is a fresh [`Var::V`], which is evaluated like any other
+    /// variable.  Its metadata is carried into tapes built from this context,
+    /// so it can be looked up with [`VarMap::info`](crate::var::VarMap::info)
+    /// or [`VarMap::find`](crate::var::VarMap::find), and
+    /// [`VarMap::defaults`](crate::var::VarMap::defaults) builds a variable
+    /// slice with every default applied.
+    ///
+    /// Returns an error if the name is already in use, or if the default is
+    /// outside of the range.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, vm::VmShape, shape::EzShape};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.declare_var("radius", 0.5, Some(0.0..=1.0))?;
+    /// let root = ctx.sub(x, r)?;
+    ///
+    /// let shape = VmShape::new(&ctx, root)?;
+    /// let tape = shape.ez_point_tape();
+    /// let vars = tape.vars();
+    /// let mut args = vars.defaults();
+    /// args[vars.find("radius").unwrap()] = 0.25;
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn declare_var(
+        &mut self,
+        name: &str,
+        default: f64,
+        range: Option<std::ops::RangeInclusive<f64>>,
+    ) -> Result<Node, Error> {
+        if self.find_var(name).is_some() {
+            return Err(Error::DuplicateVarName(name.to_owned()));
+        }
+        if range.as_ref().is_some_and(|r| !r.contains(&default)) {
+            return Err(Error::DefaultOutOfRange(default));
+        }
+        let v = Var::new();
+        self.var_info.insert(
+            v.index().unwrap(),
+            VarInfo {
+                name: name.to_owned(),
+                default,
+                range,
+            },
+        );
+        Ok(self.var(v))
+    }
+
+    /// Returns metadata for a variable declared with
+    /// [`declare_var`](Self::declare_var)
+    pub fn var_info(&self, v: Var) -> Option<&VarInfo> {
+        v.index().and_then(|i| self.var_info.get(&i))
+    }
+
+    /// Looks up a variable declared with [`declare_var`](Self::declare_var) by
+    /// name
+    pub fn find_var(&self, name: &str) -> Option<Var> {
+        self.var_info
+            .iter()
+            .find(|(_, info)| info.name == name)
+            .map(|(i, _)| Var::V(*i))
+    }
+
     /// Returns a 3-element array of `X`, `Y`, `Z` nodes
     pub fn axes(&mut self) -> [Node; 3] {
         [self.x(), self.y(), self.z()]
@@ -256,6 +391,204 @@ impl Context {
         self.op_binary(a.min(b), a.max(b), op)
     }
 
+    /// Decomposes `n` into a sum of scaled terms plus a constant
+    ///
+    /// Terms are accumulated into `terms` and `k`, scaled by `scale`.  This
+    /// walks through `add`, `sub`, `neg`, and multiplication by a constant;
+    /// any other node is treated as a term.
+    ///
+    /// Returns `false` if the budget is exhausted, a node is invalid, or a term
+    /// appears more than once.  Merging repeated terms would change results
+    /// for non-finite values (e.g. `x - x` is not 0 if `x` is infinite).
+    fn affine_terms(
+        &self,
+        n: Node,
+        scale: f64,
+        terms: &mut BTreeMap<Node, f64>,
+        k: &mut f64,
+        budget: &mut usize,
+    ) -> bool {
+        let mut todo = vec![(n, scale)];
+        while let Some((n, s)) = todo.pop() {
+            let Some(op) = self.get_op(n) else {
+                return false;
+            };
+            if *budget == 0 {
+                return false;
+            }
+            *budget -= 1;
+            match op {
+                Op::Const(c) => *k += s * c.0,
+                Op::Unary(UnaryOpcode::Neg, a) => todo.push((*a, -s)),
+                Op::Binary(BinaryOpcode::Add, a, b) => {
+                    todo.push((*a, s));
+                    todo.push((*b, s));
+                }
+                Op::Binary(BinaryOpcode::Sub, a, b) => {
+                    todo.push((*a, s));
+                    todo.push((*b, -s));
+                }
+                // Scaled terms are not distributed, because that would
+                // increase the number of multiplications.
+                Op::Binary(BinaryOpcode::Mul, a, b) => {
+                    let (t, c) = match (self.get_const(*a), self.get_const(*b))
+                    {
+                        (Ok(c), _) => (*b, c),
+                        (_, Ok(c)) => (*a, c),
+                        _ => (n, 1.0),
+                    };
+                    if terms.insert(t, s * c).is_some() {
+                        return false;
+                    }
+                }
+                _ => {
+                    if terms.insert(n, s).is_some() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Builds `a + sign * b` as a canonical affine sum
+    ///
+    /// The sum is built as `P - N + k`, where `P` and `N` are the sums of
+    /// positive and negative terms, each added in node order.  Sums which
+    /// differ only by association or commutation are therefore deduplicated,
+    /// and existing partial sums are reused.  Returns `Ok(None)` if the sum
+    /// can't be canonicalized (see [`Context::affine_terms`]).
+    fn affine_sum(
+        &mut self,
+        a: Node,
+        b: Node,
+        sign: f64,
+    ) -> Result<Option<Node>, Error> {
+        let mut terms = BTreeMap::new();
+        let mut k = 0.0;
+        let mut budget = AFFINE_LIMIT;
+        if !self.affine_terms(a, 1.0, &mut terms, &mut k, &mut budget)
+            || !self.affine_terms(b, sign, &mut terms, &mut k, &mut budget)
+        {
+            return Ok(None);
+        }
+
+        let mut pos = None;
+        let mut neg = None;
+        for (n, c) in terms {
+            let (sum, t) = if c < 0.0 {
+                (&mut neg, self.scaled(n, -c)?)
+            } else {
+                (&mut pos, self.scaled(n, c)?)
+            };
+            *sum = Some(match *sum {
+                Some(sum) => {
+                    self.op_binary_commutative(sum, t, BinaryOpcode::Add)?
+                }
+                None => t,
+            });
+        }
+        let out = match (pos, neg) {
+            (None, None) => self.constant(k),
+            (Some(p), None) => self.offset(p, k)?,
+            (None, Some(n)) if k == 0.0 => {
+                self.op_unary(n, UnaryOpcode::Neg)?
+            }
+            (None, Some(n)) => {
+                let k = self.constant(k);
+                self.op_binary(k, n, BinaryOpcode::Sub)?
+            }
+            (Some(p), Some(n)) => {
+                let d = self.op_binary(p, n, BinaryOpcode::Sub)?;
+                self.offset(d, k)?
+            }
+        };
+        Ok(Some(out))
+    }
+
+    /// Builds `n + k`, skipping the addition if `k` is 0
+    fn offset(&mut self, n: Node, k: f64) -> Result<Node, Error> {
+        if k == 0.0 {
+            Ok(n)
+        } else if k < 0.0 {
+            let k = self.constant(-k);
+            self.op_binary(n, k, BinaryOpcode::Sub)
+        } else {
+            let k = self.constant(k);
+            self.op_binary_commutative(n, k, BinaryOpcode::Add)
+        }
+    }
+
+    /// Builds `n * c`, skipping the multiplication if `c` is 1
+    fn scaled(&mut self, n: Node, c: f64) -> Result<Node, Error> {
+        if c == 1.0 {
+            Ok(n)
+        } else {
+            let c = self.constant(c);
+            self.op_binary_commutative(n, c, BinaryOpcode::Mul)
+        }
+    }
+
+    /// Builds a unary operation, calling individual builders to apply
+    /// optimizations
+    pub(crate) fn op_unary_builder(
+        &mut self,
+        arg: Node,
+        op: UnaryOpcode,
+    ) -> Result<Node, Error> {
+        match op {
+            UnaryOpcode::Neg => self.neg(arg),
+            UnaryOpcode::Abs => self.abs(arg),
+            UnaryOpcode::Recip => self.recip(arg),
+            UnaryOpcode::Sqrt => self.sqrt(arg),
+            UnaryOpcode::Square => self.square(arg),
+            UnaryOpcode::Floor => self.floor(arg),
+            UnaryOpcode::Ceil => self.ceil(arg),
+            UnaryOpcode::Round => self.round(arg),
+            UnaryOpcode::Sin => self.sin(arg),
+            UnaryOpcode::Cos => self.cos(arg),
+            UnaryOpcode::Tan => self.tan(arg),
+            UnaryOpcode::Asin => self.asin(arg),
+            UnaryOpcode::Acos => self.acos(arg),
+            UnaryOpcode::Atan => self.atan(arg),
+            UnaryOpcode::Exp => self.exp(arg),
+            UnaryOpcode::Ln => self.ln(arg),
+            UnaryOpcode::Not => self.not(arg),
+        }
+    }
+
+    /// Builds a binary operation, calling individual builders to apply
+    /// optimizations
+    pub(crate) fn op_binary_builder(
+        &mut self,
+        lhs: Node,
+        rhs: Node,
+        op: BinaryOpcode,
+    ) -> Result<Node, Error> {
+        match op {
+            BinaryOpcode::Add => self.add(lhs, rhs),
+            BinaryOpcode::Sub => self.sub(lhs, rhs),
+            BinaryOpcode::Mul => self.mul(lhs, rhs),
+            BinaryOpcode::Div => self.div(lhs, rhs),
+            BinaryOpcode::Atan => self.atan2(lhs, rhs),
+            BinaryOpcode::Min => self.min(lhs, rhs),
+            BinaryOpcode::Max => self.max(lhs, rhs),
+            BinaryOpcode::Compare => self.compare(lhs, rhs),
+            BinaryOpcode::Mod => self.modulo(lhs, rhs),
+            BinaryOpcode::And => self.and(lhs, rhs),
+            BinaryOpcode::Or => self.or(lhs, rhs),
+        }
+    }
+
+    /// If the given node is a unary operation of the given type, returns its
+    /// argument
+    fn get_unary(&self, n: Node, op: UnaryOpcode) -> Option<Node> {
+        match self.get_op(n) {
+            Some(Op::Unary(o, a)) if *o == op => Some(*a),
+            _ => None,
+        }
+    }
+
     /// Builds an addition node
     /// ```
     /// # let mut ctx = fidget_core::context::Context::new();
@@ -278,7 +611,19 @@ impl Context {
             match (self.get_const(a), self.get_const(b)) {
                 (Ok(0.0), _) => Ok(b),
                 (_, Ok(0.0)) => Ok(a),
-                _ => self.op_binary_commutative(a, b, BinaryOpcode::Add),
+                _ => {
+                    if let Some(n) = self.affine_sum(a, b, 1.0)? {
+                        Ok(n)
+                    } else if let Some(b) = self.get_unary(b, UnaryOpcode::Neg)
+                    {
+                        self.sub(a, b)
+                    } else if let Some(a) = self.get_unary(a, UnaryOpcode::Neg)
+                    {
+                        self.sub(b, a)
+                    } else {
+                        self.op_binary_commutative(a, b, BinaryOpcode::Add)
+                    }
+                }
             }
         }
     }
@@ -306,6 +651,8 @@ impl Context {
                 (_, Ok(1.0)) => Ok(a),
                 (Ok(0.0), _) => Ok(a),
                 (_, Ok(0.0)) => Ok(b),
+                (Ok(-1.0), _) => self.neg(b),
+                (_, Ok(-1.0)) => self.neg(a),
                 _ => self.op_binary_commutative(a, b, BinaryOpcode::Mul),
             }
         }
@@ -450,7 +797,10 @@ impl Context {
     /// ```
     pub fn neg<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
         let a = a.into_node(self)?;
-        self.op_unary(a, UnaryOpcode::Neg)
+        match self.get_unary(a, UnaryOpcode::Neg) {
+            Some(a) => Ok(a),
+            None => self.op_unary(a, UnaryOpcode::Neg),
+        }
     }
 
     /// Builds a reciprocal node
@@ -478,7 +828,15 @@ impl Context {
     /// ```
     pub fn abs<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
         let a = a.into_node(self)?;
-        self.op_unary(a, UnaryOpcode::Abs)
+        if let Some(a) = self.get_unary(a, UnaryOpcode::Neg) {
+            self.abs(a)
+        } else if self.get_unary(a, UnaryOpcode::Abs).is_some()
+            || self.get_unary(a, UnaryOpcode::Square).is_some()
+        {
+            Ok(a)
+        } else {
+            self.op_unary(a, UnaryOpcode::Abs)
+        }
     }
 
     /// Builds a node which calculates the square root of its input
@@ -561,7 +919,181 @@ impl Context {
     /// ```
     pub fn square<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
         let a = a.into_node(self)?;
-        self.op_unary(a, UnaryOpcode::Square)
+        match self
+            .get_unary(a, UnaryOpcode::Neg)
+            .or_else(|| self.get_unary(a, UnaryOpcode::Abs))
+        {
+            Some(a) => self.square(a),
+            None => self.op_unary(a, UnaryOpcode::Square),
+        }
+    }
+
+    /// Builds a node which raises its input to a constant integer power
+    ///
+    /// The power is lowered into a chain of squares and multiplications
+    /// (exponentiation by squaring), so the resulting graph has `O(log n)`
+    /// nodes; negative powers take the reciprocal first.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.powi(x, 5).unwrap();
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 32.0);
+    /// let op = ctx.powi(x, -2).unwrap();
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.25);
+    /// ```
+    pub fn powi<A: IntoNode>(&mut self, a: A, n: i64) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let mut x = match n.cmp(&0) {
+            std::cmp::Ordering::Less => self.recip(a)?,
+            std::cmp::Ordering::Equal => return Ok(self.constant(1.0)),
+            std::cmp::Ordering::Greater => a,
+        };
+        let mut n = n.unsigned_abs();
+        let mut y: Option<Node> = None;
+        while n > 1 {
+            if n % 2 == 1 {
+                y = Some(match y {
+                    Some(y) => self.mul(x, y)?,
+                    None => x,
+                });
+                n -= 1;
+            }
+            x = self.square(x)?;
+            n /= 2;
+        }
+        match y {
+            Some(y) => self.mul(x, y),
+            None => Ok(x),
+        }
+    }
+
+    /// Builds a node which calculates the cube root of its input
+    ///
+    /// Unlike `powf(a, 1.0 / 3.0)`, this is defined for negative inputs: it is
+    /// lowered into `sign(a) * exp(ln(abs(a)) / 3)` (see [`sign`](Self::sign)).
+    /// The argument to `ln` is clamped to `f32::MIN_POSITIVE`, so that
+    /// intervals which include zero remain finite.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.cbrt(x).unwrap();
+    /// let v = ctx.eval_xyz(op, -8.0, 0.0, 0.0).unwrap();
+    /// assert!((v + 2.0).abs() < 1e-12);
+    /// let v = ctx.eval_xyz(op, 0.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// ```
+    pub fn cbrt<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let sign = self.sign(a)?;
+        let abs = self.abs(a)?;
+        let abs = self.max(abs, f32::MIN_POSITIVE as f64)?;
+        let ln = self.ln(abs)?;
+        let scaled = self.mul(ln, 1.0 / 3.0)?;
+        let root = self.exp(scaled)?;
+        self.mul(sign, root)
+    }
+
+    /// Builds a node which calculates the hyperbolic sine of its input
+    ///
+    /// This is lowered into `(exp(a) - exp(-a)) / 2`; both terms are
+    /// monotone in the same direction, so interval results are tight.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.sinh(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert!((v - 1f64.sinh()).abs() < 1e-12);
+    /// ```
+    pub fn sinh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let pos = self.exp(a)?;
+        let neg_a = self.neg(a)?;
+        let neg = self.exp(neg_a)?;
+        let diff = self.sub(pos, neg)?;
+        self.mul(diff, 0.5)
+    }
+
+    /// Builds a node which calculates the hyperbolic cosine of its input
+    ///
+    /// This is lowered into `sqrt(1 + sinh(a)²)`, which keeps interval results
+    /// tight (unlike `(exp(a) + exp(-a)) / 2`).
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.cosh(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert!((v - 1f64.cosh()).abs() < 1e-12);
+    /// ```
+    pub fn cosh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let s = self.sinh(a)?;
+        let s2 = self.square(s)?;
+        let sum = self.add(s2, 1.0)?;
+        self.sqrt(sum)
+    }
+
+    /// Builds a node which calculates the hyperbolic tangent of its input
+    ///
+    /// This is lowered into `1 - 2 / (exp(2a) + 1)`, which uses its input once
+    /// (so interval results are tight) and saturates to `±1` for large inputs.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.tanh(x).unwrap();
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert!((v - 1f64.tanh()).abs() < 1e-12);
+    /// let v = ctx.eval_xyz(op, 1000.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn tanh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let a2 = self.mul(a, 2.0)?;
+        let e = self.exp(a2)?;
+        let denom = self.add(e, 1.0)?;
+        let frac = self.div(2.0, denom)?;
+        self.sub(1.0, frac)
+    }
+
+    /// Builds a node which raises `a` to the power of `b`
+    ///
+    /// Both operands may be arbitrary nodes (e.g. a variable exponent).  This
+    /// is lowered into `exp(b * ln(a))`, so interval bounds and gradients come
+    /// from those (monotone) operations; the result is only defined for
+    /// `a >= 0`, and is `NaN` (or a `NaN` interval) when `a` may be negative.
+    /// Use [`powi`](Self::powi) for constant integer powers of signed values.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.powf(x, y).unwrap();
+    /// let v = ctx.eval_xyz(op, 4.0, 0.5, 0.0).unwrap();
+    /// assert!((v - 2.0).abs() < 1e-12);
+    /// ```
+    pub fn powf<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        let ln = self.ln(a)?;
+        let scaled = self.mul(b, ln)?;
+        self.exp(scaled)
+    }
+
+    /// Raises `a` to the power `b`, picking the best available lowering
+    ///
+    /// Constant integer exponents (up to ±64) use [`powi`](Self::powi), which
+    /// is valid for negative bases; everything else uses
+    /// [`powf`](Self::powf).
+    pub(crate) fn pow(&mut self, a: Node, b: Node) -> Result<Node, Error> {
+        match self.get_const(b) {
+            Ok(n) if n.fract() == 0.0 && n.abs() <= 64.0 => {
+                self.powi(a, n as i64)
+            }
+            _ => self.powf(a, b),
+        }
     }
 
     /// Builds a node which takes the floor of its input
@@ -627,7 +1159,15 @@ impl Context {
         match (self.get_const(a), self.get_const(b)) {
             (Ok(0.0), _) => self.neg(b),
             (_, Ok(0.0)) => Ok(a),
-            _ => self.op_binary(a, b, BinaryOpcode::Sub),
+            _ => {
+                if let Some(n) = self.affine_sum(a, b, -1.0)? {
+                    Ok(n)
+                } else if let Some(b) = self.get_unary(b, UnaryOpcode::Neg) {
+                    self.add(a, b)
+                } else {
+                    self.op_binary(a, b, BinaryOpcode::Sub)
+                }
+            }
         }
     }
 
@@ -651,6 +1191,7 @@ impl Context {
         match (self.get_const(a), self.get_const(b)) {
             (Ok(0.0), _) => Ok(a),
             (_, Ok(1.0)) => Ok(a),
+            (_, Ok(-1.0)) => self.neg(a),
             _ => self.op_binary(a, b, BinaryOpcode::Div),
         }
     }
@@ -751,96 +1292,394 @@ impl Context {
         self.min(shift, 1.0)
     }
 
-    /// Builds a node that takes the modulo (least non-negative remainder)
-    pub fn modulo<A: IntoNode, B: IntoNode>(
+    /// Builds the union of two shapes
+    ///
+    /// Shapes are treated as sign fields (negative inside), so this is
+    /// equivalent to [`min`](Self::min), including its choice bookkeeping
+    /// for tape simplification.
+    pub fn union<A: IntoNode, B: IntoNode>(
         &mut self,
         a: A,
         b: B,
     ) -> Result<Node, Error> {
-        let a = a.into_node(self)?;
-        let b = b.into_node(self)?;
-        self.op_binary(a, b, BinaryOpcode::Mod)
+        self.min(a, b)
     }
 
-    /// Builds a node that returns the first node if the condition is not
-    /// equal to zero, else returns the other node
-    ///
-    /// The result is `a` if `condition != 0`, else the result is `b`.
-    /// ```
-    /// # let mut ctx = fidget_core::context::Context::new();
-    /// let x = ctx.x();
-    /// let y = ctx.y();
-    /// let z = ctx.z();
-    ///
-    /// let if_else = ctx.if_nonzero_else(x, y, z).unwrap();
+    /// Builds the intersection of two shapes
     ///
-    /// assert_eq!(ctx.eval_xyz(if_else, 0.0, 2.0, 3.0).unwrap(), 3.0);
-    /// assert_eq!(ctx.eval_xyz(if_else, 1.0, 2.0, 3.0).unwrap(), 2.0);
-    /// assert_eq!(ctx.eval_xyz(if_else, 0.0, f64::NAN, 3.0).unwrap(), 3.0);
-    /// assert_eq!(ctx.eval_xyz(if_else, 1.0, 2.0, f64::NAN).unwrap(), 2.0);
-    /// ```
-    pub fn if_nonzero_else<Condition: IntoNode, A: IntoNode, B: IntoNode>(
+    /// This is equivalent to [`max`](Self::max); see [`union`](Self::union).
+    pub fn intersection<A: IntoNode, B: IntoNode>(
         &mut self,
-        condition: Condition,
         a: A,
         b: B,
     ) -> Result<Node, Error> {
-        let condition = condition.into_node(self)?;
-        let a = a.into_node(self)?;
-        let b = b.into_node(self)?;
-
-        let lhs = self.and(condition, a)?;
-        let n_condition = self.not(condition)?;
-        let rhs = self.and(n_condition, b)?;
-        self.or(lhs, rhs)
+        self.max(a, b)
     }
 
-    ////////////////////////////////////////////////////////////////////////////
-    /// Evaluates the given node with the provided values for X, Y, and Z.
+    /// Builds the complement of a shape, swapping inside and outside
     ///
-    /// This is extremely inefficient; consider converting the node into a
-    /// [`Shape`](crate::shape::Shape) and using its evaluators instead.
+    /// This is equivalent to [`neg`](Self::neg).  Unlike [`not`](Self::not),
+    /// it treats its input as a sign field rather than a logical value.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// // Region where x < 0 and y >= 0
+    /// let not_y = ctx.complement(y).unwrap();
+    /// let op = ctx.intersection(x, not_y).unwrap();
+    /// assert!(ctx.eval_xyz(op, -1.0, 1.0, 0.0).unwrap() < 0.0);
+    /// assert!(ctx.eval_xyz(op, -1.0, -1.0, 0.0).unwrap() > 0.0);
+    /// ```
+    pub fn complement<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        self.neg(a)
+    }
+
+    /// Builds a node which takes the minimum of every input node
     ///
+    /// The inputs are combined as a balanced binary tree of `min` nodes (with
+    /// `O(log n)` depth), rather than a long chain; this keeps register
+    /// pressure low when evaluating unions of many shapes.  Returns a constant
+    /// `+∞` if `nodes` is empty.
     /// ```
     /// # let mut ctx = fidget_core::context::Context::new();
     /// let x = ctx.x();
     /// let y = ctx.y();
     /// let z = ctx.z();
-    /// let op = ctx.mul(x, y).unwrap();
-    /// let op = ctx.div(op, z).unwrap();
-    /// let v = ctx.eval_xyz(op, 3.0, 5.0, 2.0).unwrap();
-    /// assert_eq!(v, 7.5); // (3.0 * 5.0) / 2.0
+    /// let op = ctx.min_all(&[x, y, z]).unwrap();
+    /// let v = ctx.eval_xyz(op, 3.0, 1.0, 2.0).unwrap();
+    /// assert_eq!(v, 1.0);
     /// ```
-    pub fn eval_xyz(
-        &self,
-        root: Node,
-        x: f64,
-        y: f64,
-        z: f64,
-    ) -> Result<f64, Error> {
-        let vars = [(Var::X, x), (Var::Y, y), (Var::Z, z)]
-            .into_iter()
-            .collect();
-        self.eval(root, &vars)
+    pub fn min_all(&mut self, nodes: &[Node]) -> Result<Node, Error> {
+        if nodes.is_empty() {
+            Ok(self.constant(f64::INFINITY))
+        } else {
+            self.reduce_balanced(nodes, Self::min)
+        }
     }
 
-    /// Evaluates the given node with a generic set of variables
+    /// Builds a node which takes the maximum of every input node
     ///
-    /// This is extremely inefficient; consider converting the node into a
-    /// [`Shape`](crate::shape::Shape) and using its evaluators instead.
-    pub fn eval(
-        &self,
-        root: Node,
-        vars: &HashMap<Var, f64>,
-    ) -> Result<f64, Error> {
-        let mut cache = vec![None; self.ops.len()].into();
-        self.eval_inner(root, vars, &mut cache)
+    /// See [`min_all`](Self::min_all) for details; returns a constant `-∞`
+    /// if `nodes` is empty.
+    pub fn max_all(&mut self, nodes: &[Node]) -> Result<Node, Error> {
+        if nodes.is_empty() {
+            Ok(self.constant(f64::NEG_INFINITY))
+        } else {
+            self.reduce_balanced(nodes, Self::max)
+        }
     }
 
-    fn eval_inner(
-        &self,
-        node: Node,
-        vars: &HashMap<Var, f64>,
+    /// Combines a non-empty list of nodes as a balanced binary tree
+    fn reduce_balanced(
+        &mut self,
+        nodes: &[Node],
+        f: fn(&mut Self, Node, Node) -> Result<Node, Error>,
+    ) -> Result<Node, Error> {
+        match nodes.len() {
+            1 => Ok(nodes[0]),
+            n => {
+                let lhs = self.reduce_balanced(&nodes[..n / 2], f)?;
+                let rhs = self.reduce_balanced(&nodes[n / 2..], f)?;
+                f(self, lhs, rhs)
+            }
+        }
+    }
+
+    /// Builds a node that clamps `a` to the range `lo..=hi`
+    ///
+    /// This is lowered into `min(max(a, lo), hi)`; both nodes record choices
+    /// during interval evaluation, so a tracing evaluator can simplify the
+    /// clamp to `a`, `lo`, or `hi` within a region.  The result is unspecified
+    /// if `lo > hi`.
+    ///
+    /// This is deliberately not a native opcode.  It wouldn't record fewer
+    /// choices: each [`Choice`] selects between two inputs, and clamping
+    /// picks between three, so it needs two choices either way; meanwhile, a
+    /// three-operand instruction would need support in the VM, both JIT
+    /// backends, and every shader and bytecode emitter.
+    ///
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.clamp(x, -1.0, 2.0).unwrap();
+    /// let v = ctx.eval_xyz(op, -3.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -1.0);
+    /// let v = ctx.eval_xyz(op, 0.5, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.5);
+    /// let v = ctx.eval_xyz(op, 3.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// ```
+    pub fn clamp<A: IntoNode, L: IntoNode, H: IntoNode>(
+        &mut self,
+        a: A,
+        lo: L,
+        hi: H,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let lo = lo.into_node(self)?;
+        let hi = hi.into_node(self)?;
+        let lower = self.max(a, lo)?;
+        self.min(lower, hi)
+    }
+
+    /// Builds a node that returns the sign of its input
+    ///
+    /// The result is `-1` for negative values, `1` for positive values, and
+    /// `0` for zero.
+    ///
+    /// This is built from [`and`](Self::and) and [`or`](Self::or) nodes, so it
+    /// can be simplified using a tracing evaluator: when the input's interval
+    /// doesn't contain zero, the node collapses to a constant `±1`.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.sign(x).unwrap();
+    /// let v = ctx.eval_xyz(op, -3.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -1.0);
+    /// let v = ctx.eval_xyz(op, 0.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn sign<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let pos = self.greater_than(a, 0.0)?;
+        let pos = self.and(pos, 1.0)?;
+        let neg = self.less_than(a, 0.0)?;
+        let neg = self.and(neg, -1.0)?;
+        self.or(pos, neg)
+    }
+
+    /// Builds a node that is 1 if `lhs > rhs` and 0 otherwise
+    ///
+    /// During interval evaluation, the result is `[0, 1]` if the comparison
+    /// is ambiguous.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.greater_than(x, y).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 2.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn greater_than<A: IntoNode, B: IntoNode>(
+        &mut self,
+        lhs: A,
+        rhs: B,
+    ) -> Result<Node, Error> {
+        self.less_than(rhs, lhs)
+    }
+
+    /// Builds a node that is 1 if `lhs == rhs` and 0 otherwise
+    ///
+    /// During interval evaluation, the result is `[0, 1]` if the comparison
+    /// is ambiguous.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let op = ctx.equal(x, y).unwrap();
+    /// let v = ctx.eval_xyz(op, 0.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 1.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// ```
+    pub fn equal<A: IntoNode, B: IntoNode>(
+        &mut self,
+        lhs: A,
+        rhs: B,
+    ) -> Result<Node, Error> {
+        let cmp = self.compare(lhs, rhs)?;
+        self.not(cmp)
+    }
+
+    /// Builds a node that takes the modulo (least non-negative remainder)
+    pub fn modulo<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        self.op_binary(a, b, BinaryOpcode::Mod)
+    }
+
+    /// Builds a node that takes the truncated remainder of `a / b`
+    ///
+    /// Unlike [`modulo`](Self::modulo), the result has the same sign as `a`
+    /// (matching Rust's `%` operator).  This is built from
+    /// [`compare`](Self::compare) and [`modulo`](Self::modulo) of absolute
+    /// values, so it uses their interval rules.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.rem(x, 2.0).unwrap();
+    /// let v = ctx.eval_xyz(op, 5.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.0);
+    /// let v = ctx.eval_xyz(op, -5.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -1.0);
+    /// ```
+    pub fn rem<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        let sign = self.compare(a, 0.0)?;
+        let a_abs = self.abs(a)?;
+        let b_abs = self.abs(b)?;
+        let m = self.modulo(a_abs, b_abs)?;
+        self.mul(sign, m)
+    }
+
+    /// Builds a node that returns the first node if the condition is not
+    /// equal to zero, else returns the other node
+    ///
+    /// The result is `a` if `condition != 0`, else the result is `b`.
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let z = ctx.z();
+    ///
+    /// let if_else = ctx.if_nonzero_else(x, y, z).unwrap();
+    ///
+    /// assert_eq!(ctx.eval_xyz(if_else, 0.0, 2.0, 3.0).unwrap(), 3.0);
+    /// assert_eq!(ctx.eval_xyz(if_else, 1.0, 2.0, 3.0).unwrap(), 2.0);
+    /// assert_eq!(ctx.eval_xyz(if_else, 0.0, f64::NAN, 3.0).unwrap(), 3.0);
+    /// assert_eq!(ctx.eval_xyz(if_else, 1.0, 2.0, f64::NAN).unwrap(), 2.0);
+    /// ```
+    pub fn if_nonzero_else<Condition: IntoNode, A: IntoNode, B: IntoNode>(
+        &mut self,
+        condition: Condition,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let condition = condition.into_node(self)?;
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+
+        let lhs = self.and(condition, a)?;
+        let n_condition = self.not(condition)?;
+        let rhs = self.and(n_condition, b)?;
+        self.or(lhs, rhs)
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    /// Evaluates the given node with the provided values for X, Y, and Z.
+    ///
+    /// This is extremely inefficient; consider converting the node into a
+    /// [`Shape`](crate::shape::Shape) and using its evaluators instead.
+    ///
+    /// ```
+    /// # let mut ctx = fidget_core::context::Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let z = ctx.z();
+    /// let op = ctx.mul(x, y).unwrap();
+    /// let op = ctx.div(op, z).unwrap();
+    /// let v = ctx.eval_xyz(op, 3.0, 5.0, 2.0).unwrap();
+    /// assert_eq!(v, 7.5); // (3.0 * 5.0) / 2.0
+    /// ```
+    pub fn eval_xyz(
+        &self,
+        root: Node,
+        x: f64,
+        y: f64,
+        z: f64,
+    ) -> Result<f64, Error> {
+        let vars = [(Var::X, x), (Var::Y, y), (Var::Z, z)]
+            .into_iter()
+            .collect();
+        self.eval(root, &vars)
+    }
+
+    /// Evaluates the given node with a generic set of variables
+    ///
+    /// This is extremely inefficient; consider converting the node into a
+    /// [`Shape`](crate::shape::Shape) and using its evaluators instead.
+    pub fn eval(
+        &self,
+        root: Node,
+        vars: &HashMap<Var, f64>,
+    ) -> Result<f64, Error> {
+        let mut cache = vec![None; self.ops.len()].into();
+        self.eval_inner(root, vars, &mut cache)
+    }
+
+    /// Evaluates the given nodes, returning the value of every node that they
+    /// depend on
+    ///
+    /// This is useful for debugging, e.g. showing the value of each
+    /// subexpression at a cursor position.  The returned map includes the
+    /// roots, and every node reachable from them (including inputs and
+    /// constants).
+    ///
+    /// Every node is made into an output of a single VM tape, which is then
+    /// evaluated with [`VmPointEval`](crate::vm::VmPointEval), so values are
+    /// identical to those computed by a [`Shape`](crate::shape::Shape).  This
+    /// is much slower than evaluating the roots alone.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let sum = ctx.add(x, y)?;
+    /// let root = ctx.square(sum)?;
+    ///
+    /// let vars = [(Var::X, 1.0), (Var::Y, 2.0)].into_iter().collect();
+    /// let values = ctx.eval_nodes(&[root], &vars)?;
+    /// assert_eq!(values.len(), 4);
+    /// assert_eq!(values[&sum], 3.0);
+    /// assert_eq!(values[&root], 9.0);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn eval_nodes(
+        &self,
+        roots: &[Node],
+        vars: &HashMap<Var, f32>,
+    ) -> Result<HashMap<Node, f32>, Error> {
+        use crate::{
+            eval::{Function, MathFunction, Tape, TracingEvaluator},
+            vm::VmFunction,
+        };
+        let nodes = self.reachable(roots)?;
+        let f = VmFunction::new(self, &nodes)?;
+        let tape = f.point_tape(Default::default());
+        let mut args = vec![0.0; tape.vars().len()];
+        for (v, i) in tape.vars().iter() {
+            args[i] = *vars.get(&v).ok_or(Error::MissingVar(v))?;
+        }
+        let mut eval = VmFunction::new_point_eval();
+        let (out, _trace) = eval.eval(&tape, &args)?;
+        Ok(nodes.into_iter().zip(out.iter().cloned()).collect())
+    }
+
+    /// Returns every node reachable from the given roots, in sorted order
+    pub(crate) fn reachable(&self, roots: &[Node]) -> Result<Vec<Node>, Error> {
+        let mut seen = std::collections::HashSet::new();
+        let mut todo = roots.to_vec();
+        while let Some(n) = todo.pop() {
+            if seen.insert(n) {
+                let op = self.get_op(n).ok_or(Error::BadNode)?;
+                todo.extend(op.iter_children());
+            }
+        }
+        let mut nodes: Vec<Node> = seen.into_iter().collect();
+        nodes.sort();
+        Ok(nodes)
+    }
+
+    fn eval_inner(
+        &self,
+        node: Node,
+        vars: &HashMap<Var, f64>,
         cache: &mut IndexVec<Option<f64>, Node>,
     ) -> Result<f64, Error> {
         if node.0 >= cache.len() {
@@ -934,69 +1773,39 @@ impl Context {
     /// assert_eq!(ctx.len(), 7);
     /// ```
     ///
-    /// This representation is loosely defined and only intended for use in
-    /// quick experiments.
+    /// This is the `.vm` format used by libfive; see
+    /// [`io::read_vm`](crate::io::read_vm) for details.
     pub fn from_text<R: Read>(r: R) -> Result<(Self, Node), Error> {
-        let reader = BufReader::new(r);
-        let mut ctx = Self::new();
-        let mut seen = BTreeMap::new();
-        let mut last = None;
+        crate::io::read_vm(r)
+    }
 
-        for line in reader.lines().map(|line| line.unwrap()) {
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            let mut iter = line.split_whitespace();
-            let i: String = iter.next().unwrap().to_owned();
-            let opcode = iter.next().unwrap();
-
-            let mut pop = || {
-                let txt = iter.next().unwrap();
-                seen.get(txt)
-                    .cloned()
-                    .ok_or_else(|| Error::UnknownVariable(txt.to_string()))
-            };
-            let node = match opcode {
-                "const" => ctx.constant(iter.next().unwrap().parse().unwrap()),
-                "var-x" => ctx.x(),
-                "var-y" => ctx.y(),
-                "var-z" => ctx.z(),
-                "abs" => ctx.abs(pop()?)?,
-                "neg" => ctx.neg(pop()?)?,
-                "sqrt" => ctx.sqrt(pop()?)?,
-                "square" => ctx.square(pop()?)?,
-                "floor" => ctx.floor(pop()?)?,
-                "ceil" => ctx.ceil(pop()?)?,
-                "round" => ctx.round(pop()?)?,
-                "sin" => ctx.sin(pop()?)?,
-                "cos" => ctx.cos(pop()?)?,
-                "tan" => ctx.tan(pop()?)?,
-                "asin" => ctx.asin(pop()?)?,
-                "acos" => ctx.acos(pop()?)?,
-                "atan" => ctx.atan(pop()?)?,
-                "ln" => ctx.ln(pop()?)?,
-                "not" => ctx.not(pop()?)?,
-                "exp" => ctx.exp(pop()?)?,
-                "add" => ctx.add(pop()?, pop()?)?,
-                "mul" => ctx.mul(pop()?, pop()?)?,
-                "min" => ctx.min(pop()?, pop()?)?,
-                "max" => ctx.max(pop()?, pop()?)?,
-                "div" => ctx.div(pop()?, pop()?)?,
-                "atan2" => ctx.atan2(pop()?, pop()?)?,
-                "sub" => ctx.sub(pop()?, pop()?)?,
-                "compare" => ctx.compare(pop()?, pop()?)?,
-                "mod" => ctx.modulo(pop()?, pop()?)?,
-                "and" => ctx.and(pop()?, pop()?)?,
-                "or" => ctx.or(pop()?, pop()?)?,
-                op => return Err(Error::UnknownOpcode(op.to_owned())),
-            };
-            seen.insert(i, node);
-            last = Some(node);
-        }
-        match last {
-            Some(node) => Ok((ctx, node)),
-            None => Err(Error::EmptyFile),
-        }
+    /// Writes the subgraph under `root` in the `.vm` text format
+    ///
+    /// See [`io::write_vm`](crate::io::write_vm) for details.
+    pub fn write_vm<W: std::io::Write>(
+        &self,
+        root: Node,
+        w: W,
+    ) -> Result<(), Error> {
+        crate::io::write_vm(self, root, w)
+    }
+
+    /// Returns statistics about the graph under `root`
+    ///
+    /// See [`Stats`] for the values which are computed.
+    /// ```
+    /// # use fidget_core::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let root = ctx.min(x, y)?;
+    /// let stats = ctx.stats(root)?;
+    /// assert_eq!(stats.ops(), 1);
+    /// assert_eq!(stats.choices, 1);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn stats(&self, root: Node) -> Result<Stats, Error> {
+        Stats::new(self, root)
     }
 
     /// Converts the entire context into a GraphViz drawing
@@ -1011,6 +1820,24 @@ impl Context {
         out
     }
 
+    /// Converts the graph under the given roots into a GraphViz drawing
+    ///
+    /// Unlike [`dot`](Self::dot), this only includes nodes which are reachable
+    /// from `roots`.  Choice operations (`min`, `max`, `and`, and `or`) are
+    /// drawn in blue.
+    pub fn dot_subgraph(&self, roots: &[Node]) -> Result<String, Error> {
+        let nodes = self.reachable(roots)?;
+
+        let mut out = "digraph mygraph{\n".to_owned();
+        for node in nodes {
+            let op = self.get_op(node).unwrap();
+            out += &self.dot_node(node);
+            out += &op.dot_edges(node);
+        }
+        out += "}\n";
+        Ok(out)
+    }
+
     /// Converts the given node into a GraphViz node
     ///
     /// (this is a local function instead of a function on `Op` because it
@@ -1158,18 +1985,9 @@ impl Context {
                                 affine.push(mat);
                                 todo.push(Action::PopAffine);
                             } else {
-                                let (x, y, z) = axes.last().unwrap();
-                                let mut out = [None; 3];
-                                for i in 0..3 {
-                                    let a = self.mul(mat[(i, 0)], *x).unwrap();
-                                    let b = self.mul(mat[(i, 1)], *y).unwrap();
-                                    let c = self.mul(mat[(i, 2)], *z).unwrap();
-                                    let d = self.constant(mat[(i, 3)]);
-                                    let ab = self.add(a, b).unwrap();
-                                    let cd = self.add(c, d).unwrap();
-                                    out[i] = Some(self.add(ab, cd).unwrap());
-                                }
-                                let [x, y, z] = out.map(Option::unwrap);
+                                let (x, y, z) = *axes.last().unwrap();
+                                let [x, y, z] =
+                                    self.affine_axes(&mat, [x, y, z]).unwrap();
                                 axes.push((x, y, z));
                                 todo.push(Action::Pop);
                             }
@@ -1190,21 +2008,8 @@ impl Context {
                         TreeOp::Binary(op, ..) => {
                             let lhs = stack.pop().unwrap();
                             let rhs = stack.pop().unwrap();
-                            // Call individual builders to apply optimizations
-                            let out = match op {
-                                BinaryOpcode::Add => self.add(lhs, rhs),
-                                BinaryOpcode::Sub => self.sub(lhs, rhs),
-                                BinaryOpcode::Mul => self.mul(lhs, rhs),
-                                BinaryOpcode::Div => self.div(lhs, rhs),
-                                BinaryOpcode::Atan => self.atan2(lhs, rhs),
-                                BinaryOpcode::Min => self.min(lhs, rhs),
-                                BinaryOpcode::Max => self.max(lhs, rhs),
-                                BinaryOpcode::Compare => self.compare(lhs, rhs),
-                                BinaryOpcode::Mod => self.modulo(lhs, rhs),
-                                BinaryOpcode::And => self.and(lhs, rhs),
-                                BinaryOpcode::Or => self.or(lhs, rhs),
-                            }
-                            .unwrap();
+                            let out =
+                                self.op_binary_builder(lhs, rhs, *op).unwrap();
                             if Arc::strong_count(t) > 1 {
                                 seen.insert(
                                     (*axes.last().unwrap(), Arc::as_ptr(t)),
@@ -1328,27 +2133,441 @@ impl Context {
         Ok(stack.pop().unwrap())
     }
 
-    /// Takes the symbolic derivative of a node with respect to a variable
-    pub fn deriv(&mut self, n: Node, v: Var) -> Result<Node, Error> {
-        if self.get_op(n).is_none() {
-            return Err(Error::BadNode);
-        }
-
-        // Do recursion on the heap to avoid stack overflows for deep trees
-        enum Action {
-            /// Pushes `Up(n)` followed by `Down(n)` for each child
-            Down(Node),
-            /// Consumes trees from the stack and pushes a new tree
-            Up(Node, Op),
-        }
-        let mut todo = vec![Action::Down(n)];
-        let mut stack = vec![];
-        let zero = self.constant(0.0);
-
-        // Cache of Node -> Node mapping, for deduplication
-        let mut seen: HashMap<Node, Node> = HashMap::new();
+    /// Returns a new root with the `X`, `Y`, and `Z` axes replaced by the given
+    /// nodes
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::remap_xyz`].  Axes are
+    /// substituted simultaneously, i.e. the `X` within the new `y` node is not
+    /// itself remapped.  The original graph is left unchanged.
+    ///
+    /// ```
+    /// # use fidget_core::context::Context;
+    /// let mut ctx = Context::new();
+    /// let [x, y, z] = ctx.axes();
+    /// let circle = ctx.square(x).unwrap();
+    /// let y2 = ctx.square(y).unwrap();
+    /// let circle = ctx.add(circle, y2).unwrap();
+    /// let circle = ctx.sqrt(circle).unwrap();
+    /// let circle = ctx.sub(circle, 1.0).unwrap();
+    ///
+    /// // Translate by +2 on the X axis
+    /// let x2 = ctx.sub(x, 2.0).unwrap();
+    /// let moved = ctx.remap_xyz(circle, x2, y, z).unwrap();
+    /// assert_eq!(ctx.eval_xyz(moved, 2.0, 0.0, 0.0).unwrap(), -1.0);
+    /// assert_eq!(ctx.eval_xyz(circle, 2.0, 0.0, 0.0).unwrap(), 1.0);
+    /// ```
+    pub fn remap_xyz(
+        &mut self,
+        root: Node,
+        x: Node,
+        y: Node,
+        z: Node,
+    ) -> Result<Node, Error> {
+        let map = [(self.x(), x), (self.y(), y), (self.z(), z)]
+            .into_iter()
+            .collect();
+        self.remap(root, &map)
+    }
 
-        while let Some(t) = todo.pop() {
+    /// Returns a new root with every use of `old` replaced by `new`
+    ///
+    /// Only the parts of the graph which depend on `old` are rebuilt (with
+    /// the usual deduplication and simplification); the original graph is
+    /// left unchanged.
+    ///
+    /// ```
+    /// # use fidget_core::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.constant(1.0);
+    /// let shape = ctx.sub(x, r).unwrap();
+    ///
+    /// let r2 = ctx.constant(2.0);
+    /// let bigger = ctx.substitute(shape, r, r2).unwrap();
+    /// assert_eq!(ctx.eval_xyz(bigger, 0.0, 0.0, 0.0).unwrap(), -2.0);
+    /// ```
+    pub fn substitute(
+        &mut self,
+        root: Node,
+        old: Node,
+        new: Node,
+    ) -> Result<Node, Error> {
+        self.check_node(old)?;
+        self.remap(root, &[(old, new)].into_iter().collect())
+    }
+
+    /// Returns a new root with the given variable bound to a constant value
+    ///
+    /// The graph is rebuilt with constant folding and simplification, so
+    /// branches which only depended on the variable collapse.  Build a new
+    /// shape from the returned node to get a smaller tape.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let t = ctx.var(Var::new());
+    /// let a = ctx.sin(t)?;
+    /// let root = ctx.add(x, a)?;
+    ///
+    /// let bound = ctx.bind_var(root, ctx.get_var(t)?, 0.0)?;
+    /// assert_eq!(bound, x); // sin(0) = 0, and x + 0 = x
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn bind_var(
+        &mut self,
+        root: Node,
+        v: Var,
+        value: f64,
+    ) -> Result<Node, Error> {
+        let old = self.var(v);
+        let new = self.constant(value);
+        self.remap(root, &[(old, new)].into_iter().collect())
+    }
+
+    /// Returns a new root with branches that are inactive within the given
+    /// bounds removed
+    ///
+    /// The graph is evaluated once with interval arithmetic over the region
+    /// `bounds` (in X, Y, Z order); other variables are unbounded.  Every
+    /// `min`, `max`, `and`, or `or` operation which always picks the same
+    /// branch is replaced by that branch, and the graph is rebuilt with the
+    /// usual simplifications.
+    ///
+    /// The result is only equivalent to `root` inside `bounds`.  The original
+    /// nodes are left in the context; call [`compact`](Self::compact) to drop
+    /// them.  See [`prune_with_ranges`](Self::prune_with_ranges) to also use
+    /// the declared ranges of other variables.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, types::Interval};
+    /// let mut ctx = Context::new();
+    /// let [x, y, _] = ctx.axes();
+    /// let a = ctx.sub(x, 1.0)?;
+    /// let b = ctx.add(y, 1.0)?;
+    /// let root = ctx.min(a, b)?;
+    ///
+    /// let bounds = [Interval::new(-1.0, 0.0), Interval::new(0.0, 1.0), 0.0.into()];
+    /// assert_eq!(ctx.prune(root, bounds)?, a);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn prune(
+        &mut self,
+        root: Node,
+        bounds: [Interval; 3],
+    ) -> Result<Node, Error> {
+        self.prune_inner(root, bounds, false)
+    }
+
+    /// Returns a new root with branches that are inactive within the given
+    /// bounds and declared variable ranges removed
+    ///
+    /// This is like [`prune`](Self::prune), but variables other than X, Y, Z
+    /// use the range from their [`VarInfo`] (if present) instead of being
+    /// unbounded.  Ranges aren't checked during evaluation, so the result is
+    /// only equivalent to `root` if every such variable is evaluated within
+    /// its declared range; values outside of the range may produce different
+    /// results.
+    pub fn prune_with_ranges(
+        &mut self,
+        root: Node,
+        bounds: [Interval; 3],
+    ) -> Result<Node, Error> {
+        self.prune_inner(root, bounds, true)
+    }
+
+    fn prune_inner(
+        &mut self,
+        root: Node,
+        bounds: [Interval; 3],
+        use_ranges: bool,
+    ) -> Result<Node, Error> {
+        let order = self.reachable(&[root])?;
+
+        // Children are created before their parents, so we can evaluate and
+        // rebuild in a single pass in index order.  Intervals are computed on
+        // the original graph, which matches the pruned graph within `bounds`.
+        let mut values: HashMap<Node, Interval> = HashMap::new();
+        let mut pruned: HashMap<Node, Node> = HashMap::new();
+        for n in order {
+            let op = *self.get_op(n).unwrap();
+            let (value, out) = match op {
+                Op::Const(c) => ((c.0 as f32).into(), n),
+                Op::Input(v) => {
+                    let value = match v {
+                        Var::X => bounds[0],
+                        Var::Y => bounds[1],
+                        Var::Z => bounds[2],
+                        v => match self
+                            .var_info(v)
+                            .and_then(|i| i.range.clone())
+                            .filter(|_| use_ranges)
+                        {
+                            Some(r) => Interval::new(
+                                *r.start() as f32,
+                                *r.end() as f32,
+                            ),
+                            None => {
+                                Interval::new(f32::NEG_INFINITY, f32::INFINITY)
+                            }
+                        },
+                    };
+                    (value, n)
+                }
+                Op::Unary(op, a) => {
+                    let value = interval_unary(op, values[&a]);
+                    let out = if pruned[&a] == a {
+                        n
+                    } else {
+                        self.op_unary_builder(pruned[&a], op)?
+                    };
+                    (value, out)
+                }
+                Op::Binary(op, a, b) => {
+                    let (value, choice) =
+                        interval_binary(op, values[&a], values[&b]);
+                    let out = match choice {
+                        Choice::Left => pruned[&a],
+                        Choice::Right => pruned[&b],
+                        _ if pruned[&a] == a && pruned[&b] == b => n,
+                        _ => {
+                            self.op_binary_builder(pruned[&a], pruned[&b], op)?
+                        }
+                    };
+                    (value, out)
+                }
+            };
+            values.insert(n, value);
+            pruned.insert(n, out);
+        }
+        Ok(pruned[&root])
+    }
+
+    /// Performs an affine remapping of the given node
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::remap_affine`]; see
+    /// [`remap_xyz`](Self::remap_xyz) for details.
+    pub fn remap_affine(
+        &mut self,
+        root: Node,
+        mat: nalgebra::Affine3<f64>,
+    ) -> Result<Node, Error> {
+        let axes = self.axes();
+        let [x, y, z] = self.affine_axes(&mat.to_homogeneous(), axes)?;
+        self.remap_xyz(root, x, y, z)
+    }
+
+    /// Moves the shape by the given offset
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::translate`].
+    ///
+    /// ```
+    /// # use fidget_core::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let moved = ctx.translate(x, nalgebra::Vector3::new(1.0, 0.0, 0.0));
+    /// let v = ctx.eval_xyz(moved.unwrap(), 1.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.0);
+    /// ```
+    pub fn translate(
+        &mut self,
+        root: Node,
+        offset: nalgebra::Vector3<f64>,
+    ) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.translate(offset)))
+    }
+
+    /// Scales the shape about the origin, with a separate scale for each axis
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::scale`].
+    pub fn scale(
+        &mut self,
+        root: Node,
+        scale: nalgebra::Vector3<f64>,
+    ) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.scale(scale)))
+    }
+
+    /// Rotates the shape about the X axis, with an angle in radians
+    pub fn rotate_x(&mut self, root: Node, angle: f64) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.rotate_x(angle)))
+    }
+
+    /// Rotates the shape about the Y axis, with an angle in radians
+    pub fn rotate_y(&mut self, root: Node, angle: f64) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.rotate_y(angle)))
+    }
+
+    /// Rotates the shape about the Z axis, with an angle in radians
+    pub fn rotate_z(&mut self, root: Node, angle: f64) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.rotate_z(angle)))
+    }
+
+    /// Rotates the shape about an axis through the origin
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::rotate_axis_angle`].
+    pub fn rotate_axis_angle(
+        &mut self,
+        root: Node,
+        axis: nalgebra::Vector3<f64>,
+        angle: f64,
+    ) -> Result<Node, Error> {
+        self.tree_transform(root, |t| Ok(t.rotate_axis_angle(axis, angle)))
+    }
+
+    /// Applies an arbitrary affine transform to the shape
+    ///
+    /// This is the [`Context`] equivalent of [`Tree::transform`], and returns
+    /// [`Error::SingularMatrix`] if `mat` is not invertible.
+    pub fn transform(
+        &mut self,
+        root: Node,
+        mat: nalgebra::Affine3<f64>,
+    ) -> Result<Node, Error> {
+        self.tree_transform(root, |t| t.transform(mat))
+    }
+
+    /// Applies one of the [`Tree`] transforms to a node
+    ///
+    /// The transform is applied to a bare axis, which it wraps in a single
+    /// [`TreeOp::RemapAffine`]; that remapping matrix is then applied to
+    /// `root` with [`remap_affine`](Self::remap_affine).
+    fn tree_transform<F: FnOnce(&Tree) -> Result<Tree, Error>>(
+        &mut self,
+        root: Node,
+        f: F,
+    ) -> Result<Node, Error> {
+        let t = f(&Tree::x())?;
+        let TreeOp::RemapAffine { mat, .. } = &*t else {
+            unreachable!("tree transforms always build an affine remap")
+        };
+        self.remap_affine(root, *mat)
+    }
+
+    /// Builds the given axes, transformed by a homogeneous matrix
+    ///
+    /// Zero coefficients are folded away by the individual builders.
+    fn affine_axes(
+        &mut self,
+        mat: &Matrix4<f64>,
+        [x, y, z]: [Node; 3],
+    ) -> Result<[Node; 3], Error> {
+        let mut out = [x, y, z];
+        for (i, o) in out.iter_mut().enumerate() {
+            let a = self.mul(mat[(i, 0)], x)?;
+            let b = self.mul(mat[(i, 1)], y)?;
+            let c = self.mul(mat[(i, 2)], z)?;
+            let d = self.constant(mat[(i, 3)]);
+            let ab = self.add(a, b)?;
+            let cd = self.add(c, d)?;
+            *o = self.add(ab, cd)?;
+        }
+        Ok(out)
+    }
+
+    /// Rebuilds the graph under `root`, replacing nodes according to `map`
+    ///
+    /// Replacements are not themselves remapped.  Subgraphs which don't
+    /// contain any replaced nodes are reused as-is; everything else is rebuilt
+    /// with the usual builders, so the result is deduplicated and simplified.
+    fn remap(
+        &mut self,
+        root: Node,
+        map: &HashMap<Node, Node>,
+    ) -> Result<Node, Error> {
+        self.check_node(root)?;
+        for n in map.values() {
+            self.check_node(*n)?;
+        }
+
+        // Do recursion on the heap to avoid stack overflows for deep trees
+        enum Action {
+            /// Pushes `Up(n)` followed by `Down(n)` for each child
+            Down(Node),
+            /// Consumes nodes from the stack and pushes a new node
+            Up(Node, Op),
+        }
+        let mut todo = vec![Action::Down(root)];
+        let mut stack = vec![];
+
+        // Cache of Node -> Node mapping, seeded with the replacements
+        let mut seen = map.clone();
+
+        while let Some(t) = todo.pop() {
+            match t {
+                Action::Down(n) => {
+                    if let Some(p) = seen.get(&n) {
+                        stack.push(*p);
+                        continue;
+                    }
+                    let op = *self.get_op(n).unwrap();
+                    match op {
+                        Op::Const(..) | Op::Input(..) => {
+                            seen.insert(n, n);
+                            stack.push(n);
+                        }
+                        Op::Unary(_op, arg) => {
+                            todo.push(Action::Up(n, op));
+                            todo.push(Action::Down(arg));
+                        }
+                        Op::Binary(_op, lhs, rhs) => {
+                            todo.push(Action::Up(n, op));
+                            todo.push(Action::Down(lhs));
+                            todo.push(Action::Down(rhs));
+                        }
+                    }
+                }
+                Action::Up(n, op) => {
+                    let out = match op {
+                        Op::Const(..) | Op::Input(..) => unreachable!(),
+                        Op::Unary(op, arg) => {
+                            let new_arg = stack.pop().unwrap();
+                            if new_arg == arg {
+                                n
+                            } else {
+                                self.op_unary_builder(new_arg, op)?
+                            }
+                        }
+                        Op::Binary(op, lhs, rhs) => {
+                            let new_lhs = stack.pop().unwrap();
+                            let new_rhs = stack.pop().unwrap();
+                            if new_lhs == lhs && new_rhs == rhs {
+                                n
+                            } else {
+                                self.op_binary_builder(new_lhs, new_rhs, op)?
+                            }
+                        }
+                    };
+                    seen.insert(n, out);
+                    stack.push(out);
+                }
+            }
+        }
+        assert_eq!(stack.len(), 1);
+        Ok(stack.pop().unwrap())
+    }
+
+    /// Takes the symbolic derivative of a node with respect to a variable
+    pub fn deriv(&mut self, n: Node, v: Var) -> Result<Node, Error> {
+        if self.get_op(n).is_none() {
+            return Err(Error::BadNode);
+        }
+
+        // Do recursion on the heap to avoid stack overflows for deep trees
+        enum Action {
+            /// Pushes `Up(n)` followed by `Down(n)` for each child
+            Down(Node),
+            /// Consumes trees from the stack and pushes a new tree
+            Up(Node, Op),
+        }
+        let mut todo = vec![Action::Down(n)];
+        let mut stack = vec![];
+        let zero = self.constant(0.0);
+
+        // Cache of Node -> Node mapping, for deduplication
+        let mut seen: HashMap<Node, Node> = HashMap::new();
+
+        while let Some(t) = todo.pop() {
             match t {
                 Action::Down(n) => {
                     // If we've already seen this TreeOp with these axes, then
@@ -1493,33 +2712,8 @@ impl Context {
                             }
                             BinaryOpcode::Compare => Ok(zero),
                             BinaryOpcode::Mod => {
-                                let e = self.div(v_lhs, v_rhs).unwrap();
-                                let q = self.floor(e).unwrap();
-
-                                // XXX
-                                // (we don't actually have %, so hack it from
-                                // `modulo`, which is actually `rem_euclid`)
-                                // ???
-                                let m = self.modulo(q, v_rhs).unwrap();
-                                let cond = self.less_than(q, zero).unwrap();
-                                let offset = self
-                                    .if_nonzero_else(cond, v_rhs, zero)
-                                    .unwrap();
-                                let m = self.sub(m, offset).unwrap();
-
-                                // Torn from the div_euclid implementation
-                                let outer = self.less_than(m, zero).unwrap();
-                                let inner =
-                                    self.less_than(zero, v_rhs).unwrap();
-                                let qa = self.sub(q, 1.0).unwrap();
-                                let qb = self.add(q, 1.0).unwrap();
-                                let inner = self
-                                    .if_nonzero_else(inner, qa, qb)
-                                    .unwrap();
-                                let e = self
-                                    .if_nonzero_else(outer, inner, q)
-                                    .unwrap();
-
+                                let e =
+                                    self.mod_quotient(v_lhs, v_rhs).unwrap();
                                 let v = self.mul(d_rhs, e).unwrap();
                                 self.sub(d_lhs, v)
                             }
@@ -1542,9 +2736,274 @@ impl Context {
         assert_eq!(stack.len(), 1);
         Ok(stack.pop().unwrap())
     }
+
+    /// Computes the quotient `q` such that `modulo(a, b) = a - b * q`
+    ///
+    /// (`-q` is the partial derivative of `modulo(a, b)` with respect to `b`)
+    fn mod_quotient(
+        &mut self,
+        v_lhs: Node,
+        v_rhs: Node,
+    ) -> Result<Node, Error> {
+        // `q` is an integer, so rounding removes floating-point error
+        let m = self.modulo(v_lhs, v_rhs)?;
+        let d = self.sub(v_lhs, m)?;
+        let q = self.div(d, v_rhs)?;
+        self.round(q)
+    }
+
+    /// Takes the symbolic gradient of a node with respect to many variables
+    ///
+    /// This uses reverse-mode differentiation: adjoints are propagated from
+    /// the root towards the leaves in a single pass, so the cost is roughly
+    /// independent of the number of variables (unlike calling
+    /// [`Context::deriv`] once per variable).  The resulting nodes share
+    /// subexpressions with each other and with `n`, so they can be compiled
+    /// into a single multi-output tape alongside the original expression:
+    ///
+    /// ```
+    /// # use fidget_core::{
+    /// #     Context, var::Var, vm::VmFunction,
+    /// #     eval::{Function, MathFunction, TracingEvaluator},
+    /// # };
+    /// let mut ctx = Context::new();
+    /// let (a, b) = (Var::new(), Var::new());
+    /// let (na, nb) = (ctx.var(a), ctx.var(b));
+    /// let ab = ctx.mul(na, nb)?;
+    /// let root = ctx.sin(ab)?;
+    ///
+    /// let grad = ctx.gradient(root, &[a, b])?;
+    /// let f = VmFunction::new(&ctx, &[root, grad[0], grad[1]])?;
+    ///
+    /// let tape = f.point_tape(Default::default());
+    /// let mut eval = VmFunction::new_point_eval();
+    /// let mut args = [0.0; 2];
+    /// args[f.vars()[&a]] = 2.0;
+    /// args[f.vars()[&b]] = 3.0;
+    /// let (out, _) = eval.eval(&tape, &args)?;
+    /// assert_eq!(out[0], 6f32.sin());
+    /// assert_eq!(out[1], 3.0 * 6f32.cos()); // d/da = b * cos(a * b)
+    /// assert_eq!(out[2], 2.0 * 6f32.cos()); // d/db = a * cos(a * b)
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    ///
+    /// Returns one node per variable, in the same order as `vars`.  Variables
+    /// which do not affect `n` have a gradient of zero.  The derivative rules
+    /// (including their behavior at discontinuities) match [`Context::deriv`],
+    /// though results may differ at singularities (e.g. `∂(a / b) / ∂a` is
+    /// infinite rather than `NaN` when `b = 0`).
+    pub fn gradient(
+        &mut self,
+        n: Node,
+        vars: &[Var],
+    ) -> Result<Vec<Node>, Error> {
+        let order = self.reachable(&[n])?;
+
+        // Children are created before their parents, so visiting nodes in
+        // reverse index order guarantees that each node's adjoint is complete
+        // before it is propagated to its children.
+        let zero = self.constant(0.0);
+        let mut adjoint: HashMap<Node, Node> = HashMap::new();
+        adjoint.insert(n, self.constant(1.0));
+        let mut out: HashMap<Var, Node> = HashMap::new();
+        for n in order.into_iter().rev() {
+            let Some(&d) = adjoint.get(&n) else {
+                continue;
+            };
+            let mut acc = |ctx: &mut Self, child: Node, v: Node| {
+                let v = match adjoint.get(&child) {
+                    Some(&prev) => ctx.add(prev, v)?,
+                    None => v,
+                };
+                adjoint.insert(child, v);
+                Ok::<(), Error>(())
+            };
+            match *self.get_op(n).unwrap() {
+                Op::Const(..) => (),
+                Op::Input(v) => {
+                    out.insert(v, d);
+                }
+                Op::Unary(op, arg) => {
+                    let v = match op {
+                        UnaryOpcode::Neg => self.neg(d)?,
+                        UnaryOpcode::Abs => {
+                            let cond = self.less_than(arg, zero)?;
+                            let neg = self.neg(d)?;
+                            self.if_nonzero_else(cond, neg, d)?
+                        }
+                        UnaryOpcode::Recip => {
+                            let a = self.square(arg)?;
+                            let b = self.neg(d)?;
+                            self.div(b, a)?
+                        }
+                        UnaryOpcode::Sqrt => {
+                            let v = self.mul(n, 2.0)?;
+                            self.div(d, v)?
+                        }
+                        UnaryOpcode::Square => {
+                            let v = self.mul(d, arg)?;
+                            self.mul(2.0, v)?
+                        }
+                        UnaryOpcode::Floor
+                        | UnaryOpcode::Ceil
+                        | UnaryOpcode::Round
+                        | UnaryOpcode::Not => continue,
+                        UnaryOpcode::Sin => {
+                            let c = self.cos(arg)?;
+                            self.mul(c, d)?
+                        }
+                        UnaryOpcode::Cos => {
+                            let s = self.sin(arg)?;
+                            let s = self.neg(s)?;
+                            self.mul(s, d)?
+                        }
+                        UnaryOpcode::Tan => {
+                            let c = self.cos(arg)?;
+                            let c = self.square(c)?;
+                            self.div(d, c)?
+                        }
+                        UnaryOpcode::Asin => {
+                            let v = self.square(arg)?;
+                            let v = self.sub(1.0, v)?;
+                            let v = self.sqrt(v)?;
+                            self.div(d, v)?
+                        }
+                        UnaryOpcode::Acos => {
+                            let v = self.square(arg)?;
+                            let v = self.sub(1.0, v)?;
+                            let v = self.sqrt(v)?;
+                            let v = self.neg(v)?;
+                            self.div(d, v)?
+                        }
+                        UnaryOpcode::Atan => {
+                            let v = self.square(arg)?;
+                            let v = self.add(1.0, v)?;
+                            self.div(d, v)?
+                        }
+                        UnaryOpcode::Exp => self.mul(n, d)?,
+                        UnaryOpcode::Ln => self.div(d, arg)?,
+                    };
+                    acc(self, arg, v)?;
+                }
+                Op::Binary(op, lhs, rhs) => {
+                    let (d_lhs, d_rhs) = match op {
+                        BinaryOpcode::Add => (d, d),
+                        BinaryOpcode::Sub => (d, self.neg(d)?),
+                        BinaryOpcode::Mul => {
+                            (self.mul(d, rhs)?, self.mul(lhs, d)?)
+                        }
+                        BinaryOpcode::Div => {
+                            let a = self.div(d, rhs)?;
+                            let v = self.square(rhs)?;
+                            let b = self.mul(lhs, d)?;
+                            let b = self.div(b, v)?;
+                            (a, self.neg(b)?)
+                        }
+                        BinaryOpcode::Atan => {
+                            let a = self.square(lhs)?;
+                            let b = self.square(rhs)?;
+                            let v = self.add(a, b)?;
+                            let v = self.div(d, v)?;
+                            let b = self.mul(lhs, v)?;
+                            (self.mul(rhs, v)?, self.neg(b)?)
+                        }
+                        BinaryOpcode::Min => {
+                            let cond = self.less_than(lhs, rhs)?;
+                            (
+                                self.if_nonzero_else(cond, d, zero)?,
+                                self.if_nonzero_else(cond, zero, d)?,
+                            )
+                        }
+                        BinaryOpcode::Max => {
+                            let cond = self.less_than(rhs, lhs)?;
+                            (
+                                self.if_nonzero_else(cond, d, zero)?,
+                                self.if_nonzero_else(cond, zero, d)?,
+                            )
+                        }
+                        BinaryOpcode::Compare => continue,
+                        BinaryOpcode::Mod => {
+                            let e = self.mod_quotient(lhs, rhs)?;
+                            let v = self.mul(d, e)?;
+                            (d, self.neg(v)?)
+                        }
+                        BinaryOpcode::And => {
+                            let cond = self.compare(lhs, zero)?;
+                            (
+                                self.if_nonzero_else(cond, zero, d)?,
+                                self.if_nonzero_else(cond, d, zero)?,
+                            )
+                        }
+                        BinaryOpcode::Or => {
+                            let cond = self.compare(lhs, zero)?;
+                            (
+                                self.if_nonzero_else(cond, d, zero)?,
+                                self.if_nonzero_else(cond, zero, d)?,
+                            )
+                        }
+                    };
+                    acc(self, lhs, d_lhs)?;
+                    acc(self, rhs, d_rhs)?;
+                }
+            }
+        }
+
+        Ok(vars
+            .iter()
+            .map(|v| out.get(v).copied().unwrap_or(zero))
+            .collect())
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
+/// Evaluates a unary operation with interval arithmetic
+fn interval_unary(op: UnaryOpcode, a: Interval) -> Interval {
+    match op {
+        UnaryOpcode::Neg => -a,
+        UnaryOpcode::Abs => a.abs(),
+        UnaryOpcode::Recip => a.recip(),
+        UnaryOpcode::Sqrt => a.sqrt(),
+        UnaryOpcode::Square => a.square(),
+        UnaryOpcode::Floor => a.floor(),
+        UnaryOpcode::Ceil => a.ceil(),
+        UnaryOpcode::Round => a.round(),
+        UnaryOpcode::Sin => a.sin(),
+        UnaryOpcode::Cos => a.cos(),
+        UnaryOpcode::Tan => a.tan(),
+        UnaryOpcode::Asin => a.asin(),
+        UnaryOpcode::Acos => a.acos(),
+        UnaryOpcode::Atan => a.atan(),
+        UnaryOpcode::Exp => a.exp(),
+        UnaryOpcode::Ln => a.ln(),
+        UnaryOpcode::Not => a.logical_not(),
+    }
+}
+
+/// Evaluates a binary operation with interval arithmetic
+///
+/// Returns the result and the branch taken by choice operations (or
+/// [`Choice::Both`] for non-choice operations).
+fn interval_binary(
+    op: BinaryOpcode,
+    a: Interval,
+    b: Interval,
+) -> (Interval, Choice) {
+    let value = match op {
+        BinaryOpcode::Add => a + b,
+        BinaryOpcode::Sub => a - b,
+        BinaryOpcode::Mul => a * b,
+        BinaryOpcode::Div => a / b,
+        BinaryOpcode::Atan => a.atan2(b),
+        BinaryOpcode::Mod => a.rem_euclid(b),
+        BinaryOpcode::Compare => a.compare(b),
+        BinaryOpcode::Min => return a.min_choice(b),
+        BinaryOpcode::Max => return a.max_choice(b),
+        BinaryOpcode::And => return a.and_choice(b),
+        BinaryOpcode::Or => return a.or_choice(b),
+    };
+    (value, Choice::Both)
+}
+
 /// Helper trait for things that can be converted into a [`Node`] given a
 /// [`Context`].
 ///
@@ -1620,6 +3079,24 @@ mod test {
         assert_eq!(tape.vars.len(), 2);
     }
 
+    #[test]
+    fn test_memory_usage() {
+        let mut ctx = Context::new();
+        assert_eq!(ctx.memory_usage(), 0);
+        let x = ctx.x();
+        let y = ctx.y();
+        let r = ctx.add(x, y).unwrap();
+        let m = ctx.memory_usage();
+        assert!(m >= 3 * std::mem::size_of::<Op>());
+
+        let tape = VmData::<255>::new(&ctx, &[r]).unwrap();
+        assert!(tape.memory_usage() > 0);
+
+        // Clearing the context doesn't release its storage
+        ctx.clear();
+        assert_eq!(ctx.memory_usage(), m);
+    }
+
     #[test]
     fn test_dupe() {
         let mut ctx = Context::new();
@@ -1657,6 +3134,659 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        for n in -9..=9 {
+            let op = ctx.powi(x, n).unwrap();
+            let v = ctx.eval_xyz(op, 1.5, 0.0, 0.0).unwrap();
+            let expected = 1.5f64.powi(n as i32);
+            assert!((v - expected).abs() < 1e-12, "{n}: {v} != {expected}");
+        }
+
+        // High powers are built from a short chain of squares
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        ctx.powi(x, 16).unwrap();
+        assert_eq!(ctx.len(), 5); // x, x^2, x^4, x^8, x^16
+    }
+
+    #[test]
+    fn test_powf() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let op = ctx.powf(x, y).unwrap();
+        for (a, b) in [(2.0, 3.0), (4.0, -0.5), (0.5, 2.5), (3.0, 0.0)] {
+            let v = ctx.eval_xyz(op, a, b, 0.0).unwrap();
+            let expected = f64::powf(a, b);
+            assert!((v - expected).abs() < 1e-12, "{a}^{b}: {v}");
+        }
+        assert!(ctx.eval_xyz(op, -2.0, 2.0, 0.0).unwrap().is_nan());
+
+        // Interval bounds and gradients are conservative
+        use crate::{
+            types::{Grad, Interval},
+            vm::VmShape,
+        };
+        let shape = VmShape::new(&ctx, op).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = VmShape::new_interval_eval();
+        let (i, _) = eval
+            .eval(
+                &tape,
+                Interval::new(1.0, 2.0),
+                Interval::new(2.0, 3.0),
+                0.0.into(),
+            )
+            .unwrap();
+        assert!(i.lower() <= 1.0 && i.upper() >= 8.0, "{i:?}");
+        let (i, _) = eval
+            .eval(
+                &tape,
+                Interval::new(-1.0, 2.0),
+                Interval::new(2.0, 3.0),
+                0.0.into(),
+            )
+            .unwrap();
+        assert!(i.has_nan());
+
+        let tape = shape.grad_slice_tape(Default::default());
+        let mut eval = VmShape::new_grad_slice_eval();
+        let g = eval
+            .eval(
+                &tape,
+                &[Grad::new(2.0, 1.0, 0.0, 0.0)],
+                &[Grad::new(3.0, 0.0, 1.0, 0.0)],
+                &[Grad::new(0.0, 0.0, 0.0, 1.0)],
+            )
+            .unwrap()[0];
+        assert!((g.v - 8.0).abs() < 1e-5, "{g:?}");
+        assert!((g.dx - 12.0).abs() < 1e-4, "{g:?}"); // y * x^(y - 1)
+        assert!((g.dy - 8.0 * 2f32.ln()).abs() < 1e-4, "{g:?}"); // x^y ln(x)
+        assert_eq!(g.dz, 0.0);
+    }
+
+    #[test]
+    fn test_rem() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let op = ctx.rem(x, y).unwrap();
+        for (a, b) in [(5.0, 3.0), (-5.0, 3.0), (5.0, -3.0), (-5.5, -2.0)] {
+            let v = ctx.eval_xyz(op, a, b, 0.0).unwrap();
+            assert_eq!(v, a % b, "{a} % {b}");
+        }
+        assert_eq!(ctx.eval_xyz(op, 0.0, 3.0, 0.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_min_max_all() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let nodes = (0..100)
+            .map(|i| ctx.sub(x, i as f64).unwrap())
+            .collect::<Vec<_>>();
+        let min = ctx.min_all(&nodes).unwrap();
+        let max = ctx.max_all(&nodes).unwrap();
+        assert_eq!(ctx.eval_xyz(min, 0.0, 0.0, 0.0).unwrap(), -99.0);
+        assert_eq!(ctx.eval_xyz(max, 0.0, 0.0, 0.0).unwrap(), 0.0);
+
+        // The resulting tree is balanced
+        fn depth(ctx: &Context, n: Node) -> usize {
+            match ctx.get_op(n).unwrap() {
+                Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, a, b) => {
+                    1 + depth(ctx, *a).max(depth(ctx, *b))
+                }
+                _ => 0,
+            }
+        }
+        assert_eq!(depth(&ctx, min), 7);
+        assert_eq!(depth(&ctx, max), 7);
+
+        let empty = ctx.min_all(&[]).unwrap();
+        assert_eq!(ctx.get_const(empty).unwrap(), f64::INFINITY);
+        let one = ctx.max_all(&[x]).unwrap();
+        assert_eq!(one, x);
+    }
+
+    #[test]
+    fn test_eval_nodes() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let a = ctx.mul(x, 2.0).unwrap();
+        let b = ctx.sin(y).unwrap();
+        let root = ctx.min(a, b).unwrap();
+        let unused = ctx.add(z, 1.0).unwrap();
+
+        let vars = [(Var::X, 3.0), (Var::Y, 0.0)].into_iter().collect();
+        let values = ctx.eval_nodes(&[root], &vars).unwrap();
+        let two = ctx.constant(2.0);
+        assert_eq!(values.len(), 6);
+        assert_eq!(values[&x], 3.0);
+        assert_eq!(values[&two], 2.0);
+        assert_eq!(values[&a], 6.0);
+        assert_eq!(values[&b], 0.0);
+        assert_eq!(values[&root], 0.0);
+        assert!(!values.contains_key(&z));
+        assert!(!values.contains_key(&unused));
+
+        // Every variable used by any root must be provided
+        let values = ctx.eval_nodes(&[root, unused], &vars);
+        assert!(matches!(values, Err(Error::MissingVar(Var::Z))));
+
+        // Multiple roots are evaluated together, with `f32` arithmetic
+        let vars = [(Var::X, 0.1), (Var::Y, 0.0), (Var::Z, 0.2)].into();
+        let values = ctx.eval_nodes(&[root, unused], &vars).unwrap();
+        assert_eq!(values.len(), 9);
+        assert_eq!(values[&a], 0.1f32 * 2.0);
+        assert_eq!(values[&unused], 0.2f32 + 1.0);
+        assert_eq!(values[&root], 0.0);
+    }
+
+    #[test]
+    fn test_hyperbolic() {
+        use crate::{
+            types::{Grad, Interval},
+            vm::VmShape,
+        };
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let ops = [
+            (ctx.sinh(x).unwrap(), f64::sinh as fn(f64) -> f64),
+            (ctx.cosh(x).unwrap(), f64::cosh),
+            (ctx.tanh(x).unwrap(), f64::tanh),
+        ];
+        for (op, f) in ops {
+            for v in [-3.0, -0.5, 0.0, 0.25, 2.0] {
+                let out = ctx.eval_xyz(op, v, 0.0, 0.0).unwrap();
+                assert!((out - f(v)).abs() < 1e-12, "{v}: {out} != {}", f(v));
+            }
+
+            // Interval results contain (and are close to) the true range
+            let shape = VmShape::new(&ctx, op).unwrap();
+            let tape = shape.interval_tape(Default::default());
+            let mut eval = VmShape::new_interval_eval();
+            let (i, _) = eval
+                .eval(&tape, Interval::new(-1.0, 0.5), 0.0.into(), 0.0.into())
+                .unwrap();
+            let samples = (0..=30).map(|j| f(-1.0 + j as f64 * 0.05) as f32);
+            let (lo, hi) = samples.fold((f32::INFINITY, f32::NEG_INFINITY), {
+                |(lo, hi), v| (lo.min(v), hi.max(v))
+            });
+            assert!(i.lower() <= lo + 1e-5 && i.upper() >= hi - 1e-5, "{i:?}");
+            assert!(i.lower() > lo - 1e-3 && i.upper() < hi + 1e-3, "{i:?}");
+        }
+
+        // Gradients follow the usual derivatives
+        let shape = VmShape::new(&ctx, ops[2].0).unwrap();
+        let tape = shape.grad_slice_tape(Default::default());
+        let mut eval = VmShape::new_grad_slice_eval();
+        let g = eval
+            .eval(
+                &tape,
+                &[Grad::new(0.5, 1.0, 0.0, 0.0)],
+                &[Grad::new(0.0, 0.0, 1.0, 0.0)],
+                &[Grad::new(0.0, 0.0, 0.0, 1.0)],
+            )
+            .unwrap()[0];
+        let expected = 1.0 - 0.5f32.tanh().powi(2);
+        assert!((g.dx - expected).abs() < 1e-5, "{g:?}");
+    }
+
+    #[test]
+    fn test_cbrt() {
+        use crate::{
+            types::{Grad, Interval},
+            vm::VmShape,
+        };
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let op = ctx.cbrt(x).unwrap();
+        for v in [-27.0, -2.0, -0.001, 0.0, 0.5, 8.0, 1e6] {
+            let out = ctx.eval_xyz(op, v, 0.0, 0.0).unwrap();
+            assert!((out - f64::cbrt(v)).abs() < 1e-9, "{v}: {out}");
+        }
+
+        let shape = VmShape::new(&ctx, op).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = VmShape::new_interval_eval();
+        for (lo, hi) in [(-8.0, -1.0), (1.0, 27.0), (-8.0, 27.0)] {
+            let (i, _) = eval
+                .eval(&tape, Interval::new(lo, hi), 0.0.into(), 0.0.into())
+                .unwrap();
+            assert!(i.lower() <= lo.cbrt() + 1e-5, "{i:?}");
+            assert!(i.upper() >= hi.cbrt() - 1e-5, "{i:?}");
+        }
+
+        let tape = shape.grad_slice_tape(Default::default());
+        let mut eval = VmShape::new_grad_slice_eval();
+        let g = eval
+            .eval(
+                &tape,
+                &[Grad::new(-8.0, 1.0, 0.0, 0.0)],
+                &[Grad::new(0.0, 0.0, 1.0, 0.0)],
+                &[Grad::new(0.0, 0.0, 0.0, 1.0)],
+            )
+            .unwrap()[0];
+        assert!((g.v + 2.0).abs() < 1e-5, "{g:?}");
+        assert!((g.dx - 1.0 / 12.0).abs() < 1e-5, "{g:?}");
+    }
+
+    #[test]
+    fn test_identities() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let nx = ctx.neg(x).unwrap();
+        let ny = ctx.neg(y).unwrap();
+
+        assert_eq!(ctx.neg(nx).unwrap(), x);
+        let ax = ctx.abs(x).unwrap();
+        assert_eq!(ctx.abs(nx).unwrap(), ax);
+        assert_eq!(ctx.abs(ax).unwrap(), ax);
+        let sx = ctx.square(x).unwrap();
+        assert_eq!(ctx.square(nx).unwrap(), sx);
+        assert_eq!(ctx.square(ax).unwrap(), sx);
+        assert_eq!(ctx.abs(sx).unwrap(), sx);
+
+        assert_eq!(ctx.mul(x, -1.0).unwrap(), nx);
+        assert_eq!(ctx.div(x, -1.0).unwrap(), nx);
+
+        let x_minus_y = ctx.sub(x, y).unwrap();
+        assert_eq!(ctx.add(x, ny).unwrap(), x_minus_y);
+        assert_eq!(ctx.add(ny, x).unwrap(), x_minus_y);
+        let x_plus_y = ctx.add(x, y).unwrap();
+        assert_eq!(ctx.sub(x, ny).unwrap(), x_plus_y);
+
+        // -x + -y = -x - y
+        let out = ctx.add(nx, ny).unwrap();
+        assert_eq!(ctx.eval_xyz(out, 1.0, 2.0, 0.0).unwrap(), -3.0);
+    }
+
+    #[test]
+    fn test_affine_cse() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+
+        // x*2 + y == y + 2*x
+        let a = ctx.mul(x, 2.0).unwrap();
+        let a = ctx.add(a, y).unwrap();
+        let b = ctx.mul(2.0, x).unwrap();
+        let b = ctx.add(y, b).unwrap();
+        assert_eq!(a, b);
+
+        // (x + y) + z == x + (y + z) == (z - -x) + y
+        let xy = ctx.add(x, y).unwrap();
+        let a = ctx.add(xy, z).unwrap();
+        let yz = ctx.add(y, z).unwrap();
+        let b = ctx.add(x, yz).unwrap();
+        let nx = ctx.neg(x).unwrap();
+        let c = ctx.sub(z, nx).unwrap();
+        let c = ctx.add(c, y).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+
+        // Constants are merged
+        let a = ctx.add(x, 1.0).unwrap();
+        let a = ctx.add(a, 2.0).unwrap();
+        let b = ctx.add(3.0, x).unwrap();
+        assert_eq!(a, b);
+
+        // Repeated terms are not merged, because x - x is not 0 for infinite x
+        let a = ctx.sub(xy, y).unwrap();
+        assert_ne!(a, x);
+        assert!(ctx.eval_xyz(a, 1.0, f64::INFINITY, 0.0).unwrap().is_nan());
+
+        // Existing partial sums are reused
+        let a = ctx.sub(y, x).unwrap();
+        assert_eq!(
+            ctx.get_op(a).unwrap(),
+            &Op::Binary(BinaryOpcode::Sub, y, x)
+        );
+        let a = ctx.sub(1.0, xy).unwrap();
+        let one = ctx.constant(1.0);
+        assert_eq!(
+            ctx.get_op(a).unwrap(),
+            &Op::Binary(BinaryOpcode::Sub, one, xy)
+        );
+        assert_eq!(ctx.eval_xyz(a, 2.0, 5.0, 0.0).unwrap(), -6.0);
+
+        // Translated copies of a primitive are merged on import
+        let t1 = (Tree::x() - 1.0) - 1.0;
+        let t2 = Tree::x() - 2.0;
+        assert_eq!(ctx.import(&t1), ctx.import(&t2));
+        let t = Tree::x().remap_xyz(Tree::x() + 1.0, Tree::y(), Tree::z());
+        let a = ctx.import(&t.remap_xyz(Tree::x() + 1.0, Tree::y(), Tree::z()));
+        let b = ctx.import(&(Tree::x() + 2.0));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_remap_xyz() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let a = ctx.mul(x, 2.0).unwrap();
+        let root = ctx.sub(a, y).unwrap();
+
+        // Swapping axes is simultaneous
+        let swapped = ctx.remap_xyz(root, y, x, z).unwrap();
+        assert_eq!(ctx.eval_xyz(swapped, 1.0, 3.0, 0.0).unwrap(), 5.0);
+        assert_eq!(ctx.eval_xyz(root, 1.0, 3.0, 0.0).unwrap(), -1.0);
+
+        // Identity remapping returns the same node
+        assert_eq!(ctx.remap_xyz(root, x, y, z).unwrap(), root);
+
+        // Rebuilt nodes are simplified
+        let zero = ctx.constant(0.0);
+        let out = ctx.remap_xyz(root, zero, y, z).unwrap();
+        let ny = ctx.neg(y).unwrap();
+        assert_eq!(out, ny);
+        let one = ctx.constant(1.0);
+        let out = ctx.remap_xyz(root, one, one, z).unwrap();
+        assert_eq!(ctx.get_const(out).unwrap(), 1.0);
+
+        // Rebuilt unary nodes are simplified too, e.g. -(-y) = y and
+        // abs(y * y) = y * y
+        let nx = ctx.neg(x).unwrap();
+        let out = ctx.remap_xyz(nx, ny, y, z).unwrap();
+        assert_eq!(out, y);
+        let ax = ctx.abs(x).unwrap();
+        let yy = ctx.square(y).unwrap();
+        let out = ctx.remap_xyz(ax, yy, y, z).unwrap();
+        assert_eq!(out, yy);
+
+        // Remapping matches the equivalent Tree operation
+        let t = Tree::x().sin() * Tree::y() + Tree::z();
+        let n = ctx.import(&t);
+        let sx = ctx.sin(x).unwrap();
+        let remapped = ctx.remap_xyz(n, y, sx, x).unwrap();
+        let expected =
+            ctx.import(&t.remap_xyz(Tree::y(), Tree::x().sin(), Tree::x()));
+        assert_eq!(remapped, expected);
+
+        let mut other = Context::new();
+        for i in 0..1000 {
+            other.constant(i as f64);
+        }
+        let bad = other.constant(-1.0);
+        assert!(matches!(
+            ctx.remap_xyz(root, bad, y, z),
+            Err(Error::BadNode)
+        ));
+    }
+
+    #[test]
+    fn test_transforms() {
+        use nalgebra::Vector3;
+
+        let sphere = {
+            let (x, y, z) = Tree::axes();
+            (x.square() + y.square() + z.square()).sqrt() - 1.0
+        };
+        let mut ctx = Context::new();
+        let root = ctx.import(&sphere);
+
+        let v = Vector3::new(1.0, 2.0, 3.0);
+        let moved = ctx.translate(root, v).unwrap();
+        assert_eq!(moved, ctx.import(&sphere.translate(v)));
+        assert_eq!(ctx.eval_xyz(moved, 1.0, 2.0, 3.0).unwrap(), -1.0);
+
+        // Chained translations are merged
+        let a = ctx.translate(root, Vector3::new(1.0, 0.0, 0.0)).unwrap();
+        let a = ctx.translate(a, Vector3::new(1.0, 0.0, 0.0)).unwrap();
+        let b = ctx.translate(root, Vector3::new(2.0, 0.0, 0.0)).unwrap();
+        assert_eq!(a, b);
+
+        let s = ctx.scale(root, Vector3::new(2.0, 1.0, 1.0)).unwrap();
+        assert_eq!(ctx.eval_xyz(s, 2.0, 0.0, 0.0).unwrap(), 0.0);
+        assert_eq!(ctx.eval_xyz(s, 0.0, 2.0, 0.0).unwrap(), 1.0);
+
+        let t = ctx.translate(root, Vector3::new(2.0, 0.0, 0.0)).unwrap();
+        let r = ctx.rotate_z(t, std::f64::consts::FRAC_PI_2).unwrap();
+        assert!((ctx.eval_xyz(r, 0.0, 2.0, 0.0).unwrap() + 1.0).abs() < 1e-9);
+        let r = ctx.rotate_x(t, 1.0).unwrap();
+        // Rotating about X doesn't move a shape centered on the X axis
+        assert!((ctx.eval_xyz(r, 2.0, 0.0, 0.0).unwrap() + 1.0).abs() < 1e-9);
+
+        let singular = nalgebra::Affine3::from_matrix_unchecked(
+            nalgebra::Matrix4::zeros(),
+        );
+        assert!(matches!(
+            ctx.transform(root, singular),
+            Err(Error::SingularMatrix(..))
+        ));
+        let mat = nalgebra::convert(nalgebra::Translation3::from(v));
+        assert_eq!(ctx.transform(root, mat).unwrap(), moved);
+    }
+
+    #[test]
+    fn test_substitute() {
+        let mut ctx = Context::new();
+        let [x, y, _z] = ctx.axes();
+        let radius = ctx.mul(y, 0.5).unwrap();
+        let x2 = ctx.square(x).unwrap();
+        let r = ctx.sqrt(x2).unwrap();
+        let root = ctx.sub(r, radius).unwrap();
+
+        // Swap out the radius subtree
+        let new_radius = ctx.constant(3.0);
+        let out = ctx.substitute(root, radius, new_radius).unwrap();
+        assert_eq!(ctx.eval_xyz(out, 1.0, 100.0, 0.0).unwrap(), -2.0);
+        assert_eq!(ctx.eval_xyz(root, 1.0, 100.0, 0.0).unwrap(), -49.0);
+        assert_eq!(ctx.substitute(root, radius, radius).unwrap(), root);
+
+        // Shared subtrees are kept
+        let Op::Binary(BinaryOpcode::Sub, lhs, _) = *ctx.get_op(out).unwrap()
+        else {
+            panic!("invalid op");
+        };
+        assert_eq!(lhs, r);
+
+        // Substituting a node that isn't used is a no-op
+        let unused = ctx.z();
+        assert_eq!(ctx.substitute(root, unused, x).unwrap(), root);
+
+        let mut other = Context::new();
+        for i in 0..1000 {
+            other.constant(i as f64);
+        }
+        let bad = other.constant(-1.0);
+        assert!(matches!(ctx.substitute(root, bad, x), Err(Error::BadNode)));
+        assert!(matches!(ctx.substitute(root, x, bad), Err(Error::BadNode)));
+    }
+
+    #[test]
+    fn test_declare_var() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let r = ctx.declare_var("radius", 0.5, Some(0.0..=1.0)).unwrap();
+        let offset = ctx.declare_var("offset", 2.0, None).unwrap();
+        let a = ctx.sub(x, r).unwrap();
+        let root = ctx.add(a, offset).unwrap();
+
+        let v = ctx.get_var(r).unwrap();
+        assert_eq!(ctx.find_var("radius"), Some(v));
+        assert_eq!(ctx.var_info(v).unwrap().default, 0.5);
+        assert_eq!(ctx.var_info(v).unwrap().range, Some(0.0..=1.0));
+        assert!(ctx.var_info(Var::X).is_none());
+        assert!(ctx.find_var("height").is_none());
+
+        assert!(matches!(
+            ctx.declare_var("radius", 0.5, None),
+            Err(Error::DuplicateVarName(..))
+        ));
+        assert!(matches!(
+            ctx.declare_var("height", 2.0, Some(0.0..=1.0)),
+            Err(Error::DefaultOutOfRange(2.0))
+        ));
+
+        // Metadata is available from the tape
+        let tape = VmData::<255>::new(&ctx, &[root]).unwrap();
+        assert_eq!(tape.vars.len(), 3);
+        assert_eq!(tape.vars.info(&v).unwrap().name, "radius");
+        assert!(tape.vars.info(&Var::X).is_none());
+        let i = tape.vars.find("radius").unwrap();
+        let j = tape.vars.find("offset").unwrap();
+        assert_eq!(i, tape.vars[&v]);
+        assert!(tape.vars.find("height").is_none());
+        let defaults = tape.vars.defaults();
+        assert_eq!(defaults[i], 0.5);
+        assert_eq!(defaults[j], 2.0);
+        assert_eq!(defaults[tape.vars[&Var::X]], 0.0);
+
+        ctx.clear();
+        assert!(ctx.find_var("radius").is_none());
+    }
+
+    #[test]
+    fn test_bind_var() {
+        let mut ctx = Context::new();
+        let [x, y, _] = ctx.axes();
+        let t = ctx.var(Var::new());
+        let vt = ctx.get_var(t).unwrap();
+
+        // if t > 0.5 { x } else { y }
+        let c = ctx.greater_than(t, 0.5).unwrap();
+        let root = ctx.if_nonzero_else(c, x, y).unwrap();
+        assert_eq!(ctx.bind_var(root, vt, 1.0).unwrap(), x);
+        assert_eq!(ctx.bind_var(root, vt, 0.0).unwrap(), y);
+
+        // Binding an unused variable is a no-op
+        assert_eq!(ctx.bind_var(root, Var::Z, 1.0).unwrap(), root);
+
+        let a = ctx.mul(x, t).unwrap();
+        let b = ctx.bind_var(a, vt, 3.0).unwrap();
+        assert_eq!(ctx.eval_xyz(b, 2.0, 0.0, 0.0).unwrap(), 6.0);
+        let b = ctx.bind_var(a, Var::X, 3.0).unwrap();
+        let vars = [(vt, 2.0)].into_iter().collect();
+        assert_eq!(ctx.eval(b, &vars).unwrap(), 6.0);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut ctx = Context::new();
+        let (x, y, z) = Tree::axes();
+        let a = ctx.import(&(x.clone().square() + y.clone().square()));
+        let b = ctx.import(&(x.clone() - 1.0).min(z.sin()));
+        for i in 0..100 {
+            ctx.import(&(y.clone() * i as f64 + z.clone()));
+        }
+        let before = ctx.len();
+        let va = ctx.eval_xyz(a, 1.0, 2.0, 3.0).unwrap();
+        let vb = ctx.eval_xyz(b, 1.0, 2.0, 3.0).unwrap();
+
+        let out = ctx.compact(&[b, a, b]).unwrap();
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[0], out[2]);
+        assert!(ctx.len() < before);
+        assert_eq!(ctx.len(), 10);
+        assert_eq!(ctx.eval_xyz(out[1], 1.0, 2.0, 3.0).unwrap(), va);
+        assert_eq!(ctx.eval_xyz(out[0], 1.0, 2.0, 3.0).unwrap(), vb);
+
+        // Existing nodes are still deduplicated
+        let n = ctx.import(&(x.square() + y.square()));
+        assert_eq!(n, out[1]);
+        assert_eq!(ctx.len(), 10);
+
+        let bad = ctx.compact(&[Node::new(1000)]);
+        assert!(matches!(bad, Err(Error::BadNode)));
+        assert!(ctx.compact(&[]).unwrap().is_empty());
+        assert!(ctx.is_empty());
+    }
+
+    #[test]
+    fn test_dot_subgraph() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let a = ctx.max(x, y).unwrap();
+        let _unused = ctx.sin(z).unwrap();
+        let dot = ctx.dot_subgraph(&[a]).unwrap();
+        assert_eq!(dot.matches("label").count(), 3);
+        assert_eq!(dot.matches("->").count(), 2);
+        assert!(dot.contains("#1E90FF"));
+        assert!(!dot.contains("sin"));
+        assert!(ctx.dot().contains("sin"));
+
+        assert!(Context::new().dot_subgraph(&[a]).is_err());
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut ctx = Context::new();
+        let (x, y, z) = Tree::axes();
+        let spheres = (0..8)
+            .map(|i| {
+                let dx = x.clone() - (i as f64 * 4.0);
+                let r = (dx.square() + y.clone().square() + z.clone().square())
+                    .sqrt();
+                ctx.import(&(r - 1.0))
+            })
+            .collect::<Vec<_>>();
+        let root = ctx.min_all(&spheres).unwrap();
+
+        // Only the sphere at x = 8 matters near that point
+        let bounds = [
+            Interval::new(7.0, 9.0),
+            Interval::new(-1.0, 1.0),
+            Interval::new(-1.0, 1.0),
+        ];
+        let pruned = ctx.prune(root, bounds).unwrap();
+        assert_eq!(pruned, spheres[2]);
+        for (px, py) in [(7.5, 0.0), (8.0, 0.5), (9.0, -1.0)] {
+            assert_eq!(
+                ctx.eval_xyz(root, px, py, 0.0).unwrap(),
+                ctx.eval_xyz(pruned, px, py, 0.0).unwrap()
+            );
+        }
+
+        // A region between two spheres keeps both of them
+        let bounds = [
+            Interval::new(1.5, 2.5),
+            Interval::new(-1.0, 1.0),
+            Interval::new(-1.0, 1.0),
+        ];
+        let pruned = ctx.prune(root, bounds).unwrap();
+        let stats = ctx.stats(pruned).unwrap();
+        assert_eq!(stats.choices, 1);
+
+        // Nothing is pruned if the bounds cover every sphere
+        let everything = Interval::new(-100.0, 100.0);
+        let all = ctx.prune(root, [everything; 3]).unwrap();
+        assert_eq!(all, root);
+
+        // Variables are unbounded, unless their declared ranges are opted into
+        let t = ctx.declare_var("t", 0.5, Some(0.0..=1.0)).unwrap();
+        let u = ctx.var(Var::new());
+        let [x, _, _] = ctx.axes();
+        let a = ctx.max(t, x).unwrap();
+        let b = ctx.max(u, x).unwrap();
+        let r = ctx.add(a, b).unwrap();
+        let neg = Interval::new(-2.0, -1.0);
+        assert_eq!(ctx.prune(r, [neg; 3]).unwrap(), r);
+        let out = ctx.prune_with_ranges(r, [neg; 3]).unwrap();
+        let expected = ctx.add(t, b).unwrap();
+        assert_eq!(out, expected);
+
+        // Rebuilt unary operations are simplified, e.g. -(-x) = x
+        let y = ctx.y();
+        let nx = ctx.neg(x).unwrap();
+        let m = ctx.min(nx, y).unwrap();
+        let r = ctx.neg(m).unwrap();
+        let bounds = [
+            Interval::new(1.0, 2.0),
+            Interval::new(0.0, 1.0),
+            Interval::new(0.0, 1.0),
+        ];
+        assert_eq!(ctx.prune(r, bounds).unwrap(), x);
+
+        assert!(matches!(
+            ctx.prune(Node::new(100_000), [neg; 3]),
+            Err(Error::BadNode)
+        ));
+    }
+
     #[test]
     fn import_optimization() {
         let t = Tree::x() + 0;
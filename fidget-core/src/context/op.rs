@@ -78,9 +78,13 @@ impl Op {
         match self {
             Op::Const(..) => "green",
             Op::Input(..) => "red",
-            Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, ..) => {
-                "dodgerblue"
-            }
+            Op::Binary(
+                BinaryOpcode::Min
+                | BinaryOpcode::Max
+                | BinaryOpcode::And
+                | BinaryOpcode::Or,
+                ..,
+            ) => "dodgerblue",
             Op::Binary(..) | Op::Unary(..) => "goldenrod",
         }
     }
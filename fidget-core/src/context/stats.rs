@@ -0,0 +1,148 @@
+//! Statistics about a math expression in a [`Context`]
+use crate::{
+    Error,
+    context::{BinaryOpcode, Context, Node, Op, UnaryOpcode},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Statistics about the graph under a particular root, from [`Context::stats`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Number of unique nodes (including constants and inputs)
+    pub nodes: usize,
+    /// Number of constant nodes
+    pub consts: usize,
+    /// Number of variable (input) nodes
+    pub vars: usize,
+    /// Number of operations with each unary opcode
+    pub unary: BTreeMap<UnaryOpcode, usize>,
+    /// Number of operations with each binary opcode
+    pub binary: BTreeMap<BinaryOpcode, usize>,
+    /// Length of the longest chain of operations from the root to a leaf
+    pub depth: usize,
+    /// Number of choice operations (`min`, `max`, `and`, and `or`)
+    pub choices: usize,
+    /// Estimated register pressure
+    ///
+    /// This is the maximum number of values which are live at once when
+    /// evaluating operations in order, ignoring constants (which are usually
+    /// stored as immediates).  If it exceeds an evaluator's register limit,
+    /// values will be spilled to memory.
+    pub registers: usize,
+}
+
+impl Stats {
+    /// Total number of operations (excluding constants and inputs)
+    pub fn ops(&self) -> usize {
+        self.unary.values().sum::<usize>() + self.binary.values().sum::<usize>()
+    }
+
+    pub(crate) fn new(ctx: &Context, root: Node) -> Result<Self, Error> {
+        // Nodes are created after their children, so sorting by index gives a
+        // valid evaluation order.
+        let order = ctx.reachable(&[root])?;
+
+        let mut out = Stats {
+            nodes: order.len(),
+            ..Default::default()
+        };
+        let mut depth: HashMap<Node, usize> = HashMap::new();
+        let mut last_use: HashMap<Node, usize> = HashMap::new();
+        for (i, n) in order.iter().enumerate() {
+            let op = ctx.get_op(*n).unwrap();
+            match op {
+                Op::Const(..) => out.consts += 1,
+                Op::Input(..) => out.vars += 1,
+                Op::Unary(op, ..) => *out.unary.entry(*op).or_default() += 1,
+                Op::Binary(op, ..) => {
+                    *out.binary.entry(*op).or_default() += 1;
+                    if matches!(
+                        op,
+                        BinaryOpcode::Min
+                            | BinaryOpcode::Max
+                            | BinaryOpcode::And
+                            | BinaryOpcode::Or
+                    ) {
+                        out.choices += 1;
+                    }
+                }
+            }
+            let d =
+                op.iter_children().map(|c| depth[&c] + 1).max().unwrap_or(0);
+            depth.insert(*n, d);
+            for c in op.iter_children() {
+                last_use.insert(c, i);
+            }
+        }
+        out.depth = depth[&root];
+
+        // Count how many values die at each step, then sweep through the
+        // evaluation order to find the maximum number of live values.
+        let mut dying = vec![0; order.len()];
+        for (n, i) in &last_use {
+            if !matches!(ctx.get_op(*n), Some(Op::Const(..))) {
+                dying[*i] += 1;
+            }
+        }
+        let mut live = 0usize;
+        for (i, n) in order.iter().enumerate() {
+            live -= dying[i];
+            if !matches!(ctx.get_op(*n), Some(Op::Const(..))) {
+                live += 1;
+                out.registers = out.registers.max(live);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::context::Tree;
+
+    #[test]
+    fn stats_circle() {
+        let mut ctx = Context::new();
+        let (x, y, _) = Tree::axes();
+        let root = ctx.import(&((x.square() + y.square()).sqrt() - 0.5));
+        let s = ctx.stats(root).unwrap();
+        assert_eq!(s.nodes, 8);
+        assert_eq!(s.consts, 1);
+        assert_eq!(s.vars, 2);
+        assert_eq!(s.ops(), 5);
+        assert_eq!(s.unary[&UnaryOpcode::Square], 2);
+        assert_eq!(s.unary[&UnaryOpcode::Sqrt], 1);
+        assert_eq!(s.binary[&BinaryOpcode::Add], 1);
+        assert_eq!(s.binary[&BinaryOpcode::Sub], 1);
+        assert_eq!(s.depth, 4);
+        assert_eq!(s.choices, 0);
+        assert_eq!(s.registers, 2);
+    }
+
+    #[test]
+    fn stats_choices() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.max(a, z).unwrap();
+        let c = ctx.and(b, x).unwrap();
+        let root = ctx.neg(c).unwrap();
+        let s = ctx.stats(root).unwrap();
+        assert_eq!(s.choices, 3);
+        assert_eq!(s.depth, 4);
+        assert_eq!(s.ops(), 4);
+        assert_eq!(s.registers, 3);
+
+        // Only the subgraph under the root is counted
+        let s = ctx.stats(a).unwrap();
+        assert_eq!(s.nodes, 3);
+        assert_eq!(s.choices, 1);
+
+        let s = ctx.stats(x).unwrap();
+        assert_eq!(s.depth, 0);
+        assert_eq!(s.registers, 1);
+
+        assert!(Context::new().stats(root).is_err());
+    }
+}
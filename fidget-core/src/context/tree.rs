@@ -235,6 +235,32 @@ impl Tree {
         (Self::x(), Self::y(), Self::z())
     }
 
+    /// Returns cylindrical coordinates `(r, theta, z)` about the Z axis
+    ///
+    /// `r` is `sqrt(x² + y²)`, built with [`square`](Self::square) so that
+    /// interval results are tight.  `theta` is `atan2(y, x)`, in the range
+    /// `[-π, π]`, with a branch cut along the -X axis; intervals which touch
+    /// the branch cut evaluate to the full `[-π, π]` range, and the derivatives
+    /// of `theta` are continuous across it.  Derivatives of both `r` and
+    /// `theta` are undefined (NaN) on the Z axis itself.
+    pub fn cylindrical() -> (Self, Self, Self) {
+        let (x, y, z) = Self::axes();
+        let r = (x.square() + y.square()).sqrt();
+        (r, y.atan2(x), z)
+    }
+
+    /// Returns spherical coordinates `(r, theta, phi)`
+    ///
+    /// `r` is the distance from the origin; `theta` is the azimuthal angle,
+    /// matching [`Tree::cylindrical`]; and `phi` is the polar angle from the +Z
+    /// axis, in the range `[0, π]` (with no branch cut).
+    pub fn spherical() -> (Self, Self, Self) {
+        let (x, y, z) = Self::axes();
+        let rxy = (x.square() + y.square()).sqrt();
+        let r = (x.square() + y.square() + z.square()).sqrt();
+        (r, y.atan2(x), rxy.atan2(z))
+    }
+
     /// Returns a pointer to the inner [`TreeOp`]
     ///
     /// This can be used as a strong (but not unique) identity.
@@ -287,6 +313,71 @@ impl Tree {
         Self(out.into())
     }
 
+    /// Moves the tree by the given offset
+    ///
+    /// Like other transforms, this is implemented by lazily remapping the
+    /// tree's coordinates (with [`remap_affine`](Self::remap_affine)), so
+    /// chains of transforms are flattened into a single matrix.
+    pub fn translate(&self, offset: nalgebra::Vector3<f64>) -> Tree {
+        self.remap_affine(nalgebra::convert(nalgebra::Translation3::from(
+            -offset,
+        )))
+    }
+
+    /// Scales the tree about the origin, with a separate scale for each axis
+    pub fn scale(&self, scale: nalgebra::Vector3<f64>) -> Tree {
+        self.remap_affine(nalgebra::convert(nalgebra::Scale3::from(
+            scale.map(|s| 1.0 / s),
+        )))
+    }
+
+    /// Rotates the tree about the X axis, with an angle in radians
+    pub fn rotate_x(&self, angle: f64) -> Tree {
+        self.rotate_axis_angle(nalgebra::Vector3::x(), angle)
+    }
+
+    /// Rotates the tree about the Y axis, with an angle in radians
+    pub fn rotate_y(&self, angle: f64) -> Tree {
+        self.rotate_axis_angle(nalgebra::Vector3::y(), angle)
+    }
+
+    /// Rotates the tree about the Z axis, with an angle in radians
+    pub fn rotate_z(&self, angle: f64) -> Tree {
+        self.rotate_axis_angle(nalgebra::Vector3::z(), angle)
+    }
+
+    /// Rotates the tree about an axis through the origin
+    ///
+    /// The angle is in radians, following the right-hand rule; the axis does
+    /// not need to be normalized, but must be non-zero.
+    pub fn rotate_axis_angle(
+        &self,
+        axis: nalgebra::Vector3<f64>,
+        angle: f64,
+    ) -> Tree {
+        let axis = nalgebra::Unit::new_normalize(axis);
+        self.remap_affine(nalgebra::convert(
+            nalgebra::Rotation3::from_axis_angle(&axis, -angle),
+        ))
+    }
+
+    /// Applies an arbitrary affine transform to the tree
+    ///
+    /// The transform maps from the tree's original coordinates to its new
+    /// coordinates (i.e. the shape is moved by `mat`); internally, the tree's
+    /// coordinates are remapped by the inverse of `mat`.
+    ///
+    /// Returns [`Error::SingularMatrix`] if `mat` is not invertible.
+    pub fn transform(
+        &self,
+        mat: nalgebra::Affine3<f64>,
+    ) -> Result<Tree, Error> {
+        let inv = mat
+            .try_inverse()
+            .ok_or(Error::SingularMatrix("transform is not invertible"))?;
+        Ok(self.remap_affine(inv))
+    }
+
     /// Returns the inner [`Var`] if this is an input tree, or `None`
     pub fn var(&self) -> Option<Var> {
         if let TreeOp::Input(v) = &*self.0 {
@@ -303,9 +394,22 @@ impl Tree {
         ctx.deriv(node, v).and_then(|d| ctx.export(d)).unwrap()
     }
 
+    /// Performs reverse-mode symbolic differentiation with respect to many
+    /// variables at once
+    ///
+    /// See [`Context::gradient`](crate::Context::gradient) for details.
+    pub fn gradient(&self, vars: &[Var]) -> Vec<Tree> {
+        let mut ctx = crate::Context::new();
+        let node = ctx.import(self);
+        let grad = ctx.gradient(node, vars).unwrap();
+        grad.into_iter().map(|d| ctx.export(d).unwrap()).collect()
+    }
+
     /// Raises this tree to the power of an integer using exponentiation by squaring
+    ///
+    /// See [`Context::powi`](crate::Context::powi) for the equivalent
+    /// operation on a [`Context`](crate::Context).
     pub fn pow(&self, mut n: i64) -> Self {
-        // TODO should this also be in `Context`?
         let mut x = match n.cmp(&0) {
             Ordering::Less => {
                 n = -n;
@@ -333,6 +437,14 @@ impl Tree {
         }
         x
     }
+
+    /// Raises this tree to the power of another tree
+    ///
+    /// See [`Context::powf`](crate::Context::powf) for details; the result is
+    /// only defined for non-negative values of `self`.
+    pub fn powf<T: Into<Tree>>(&self, other: T) -> Self {
+        (other.into() * self.ln()).exp()
+    }
 }
 
 impl TryFrom<Tree> for Var {
@@ -354,6 +466,9 @@ impl Tree {
     pub fn z() -> Self {
         Tree(Arc::new(TreeOp::Input(Var::Z)))
     }
+    pub fn w() -> Self {
+        Tree(Arc::new(TreeOp::Input(Var::W)))
+    }
     pub fn constant(f: f64) -> Self {
         Tree(Arc::new(TreeOp::Const(f)))
     }
@@ -384,12 +499,48 @@ impl Tree {
     pub fn min<T: Into<Tree>>(&self, other: T) -> Self {
         Self::op_binary(self.clone(), other.into(), BinaryOpcode::Min)
     }
+    pub fn union<T: Into<Tree>>(&self, other: T) -> Self {
+        self.min(other)
+    }
+    pub fn intersection<T: Into<Tree>>(&self, other: T) -> Self {
+        self.max(other)
+    }
+    pub fn complement(&self) -> Self {
+        self.neg()
+    }
     pub fn compare<T: Into<Tree>>(&self, other: T) -> Self {
         Self::op_binary(self.clone(), other.into(), BinaryOpcode::Compare)
     }
+    pub fn less_than<T: Into<Tree>>(&self, other: T) -> Self {
+        other.into().compare(self.clone()).max(0.0)
+    }
+    pub fn greater_than<T: Into<Tree>>(&self, other: T) -> Self {
+        self.compare(other).max(0.0)
+    }
+    pub fn equal<T: Into<Tree>>(&self, other: T) -> Self {
+        self.compare(other).not()
+    }
     pub fn modulo<T: Into<Tree>>(&self, other: T) -> Self {
         Self::op_binary(self.clone(), other.into(), BinaryOpcode::Mod)
     }
+    pub fn if_nonzero_else<A: Into<Tree>, B: Into<Tree>>(
+        &self,
+        a: A,
+        b: B,
+    ) -> Self {
+        self.and(a).or(self.not().and(b))
+    }
+    pub fn clamp<L: Into<Tree>, H: Into<Tree>>(&self, lo: L, hi: H) -> Self {
+        self.max(lo).min(hi)
+    }
+    pub fn sign(&self) -> Self {
+        let pos = self.greater_than(0.0).and(1.0);
+        let neg = self.less_than(0.0).and(-1.0);
+        pos.or(neg)
+    }
+    pub fn rem<T: Into<Tree>>(&self, other: T) -> Self {
+        self.compare(0.0) * self.abs().modulo(other.into().abs())
+    }
     pub fn and<T: Into<Tree>>(&self, other: T) -> Self {
         Self::op_binary(self.clone(), other.into(), BinaryOpcode::And)
     }
@@ -429,6 +580,19 @@ impl Tree {
     pub fn ln(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Ln)
     }
+    pub fn cbrt(&self) -> Self {
+        let abs = self.abs().max(f32::MIN_POSITIVE as f64);
+        self.sign() * (abs.ln() * (1.0 / 3.0)).exp()
+    }
+    pub fn sinh(&self) -> Self {
+        (self.exp() - self.neg().exp()) * 0.5
+    }
+    pub fn cosh(&self) -> Self {
+        (self.sinh().square() + 1.0).sqrt()
+    }
+    pub fn tanh(&self) -> Self {
+        1.0 - 2.0 / ((self.clone() * 2.0).exp() + 1.0)
+    }
     pub fn not(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Not)
     }
@@ -480,11 +644,42 @@ impl std::ops::Neg for Tree {
     }
 }
 
+/// Intersection of two shapes (treated as sign fields)
+impl<A: Into<Tree>> std::ops::BitAnd<A> for Tree {
+    type Output = Tree;
+    fn bitand(self, other: A) -> Self::Output {
+        self.intersection(other)
+    }
+}
+
+/// Union of two shapes (treated as sign fields)
+impl<A: Into<Tree>> std::ops::BitOr<A> for Tree {
+    type Output = Tree;
+    fn bitor(self, other: A) -> Self::Output {
+        self.union(other)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::Context;
 
+    #[test]
+    fn tree_csg_ops() {
+        let (x, y, _) = Tree::axes();
+        let circle = (x.square() + y.square()).sqrt() - 1.0;
+        let shape = (circle.clone() | (x.clone() - 2.0))
+            & (y.clone() - 0.5).complement();
+
+        let mut ctx = Context::new();
+        let root = ctx.import(&shape);
+        assert!(ctx.eval_xyz(root, 0.0, 0.75, 0.0).unwrap() < 0.0);
+        assert!(ctx.eval_xyz(root, 0.0, 0.25, 0.0).unwrap() > 0.0);
+        assert!(ctx.eval_xyz(root, 3.0, 2.0, 0.0).unwrap() > 0.0);
+        assert!(ctx.eval_xyz(root, 1.5, 2.0, 0.0).unwrap() < 0.0);
+    }
+
     #[test]
     fn tree_x() {
         let x1 = Tree::x();
@@ -498,6 +693,88 @@ mod test {
         assert_eq!(x1, x2);
     }
 
+    #[test]
+    fn test_coordinates() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+        let mut ctx = Context::new();
+        let mut eval = |t: Tree, x, y, z| {
+            let node = ctx.import(&t);
+            ctx.eval_xyz(node, x, y, z).unwrap()
+        };
+
+        let (r, theta, z) = Tree::cylindrical();
+        assert_eq!(eval(r.clone(), 3.0, 4.0, 5.0), 5.0);
+        assert_eq!(eval(theta.clone(), 0.0, 1.0, 0.0), FRAC_PI_2);
+        assert_eq!(eval(theta.clone(), -1.0, 0.0, 0.0), PI);
+        assert_eq!(eval(theta, 1.0, -1.0, 0.0), -FRAC_PI_4);
+        assert_eq!(eval(z, 3.0, 4.0, 5.0), 5.0);
+
+        let (r, theta, phi) = Tree::spherical();
+        assert_eq!(eval(r, 2.0, 3.0, 6.0), 7.0);
+        assert_eq!(eval(theta, 0.0, 1.0, 1.0), FRAC_PI_2);
+        assert_eq!(eval(phi.clone(), 0.0, 0.0, 1.0), 0.0);
+        assert_eq!(eval(phi.clone(), 1.0, 1.0, 0.0), FRAC_PI_2);
+        assert_eq!(eval(phi, 0.0, 0.0, -1.0), PI);
+    }
+
+    #[test]
+    fn test_transforms() {
+        use nalgebra::Vector3;
+        let eval = |t: Tree, x, y, z| {
+            let mut ctx = Context::new();
+            let node = ctx.import(&t);
+            ctx.eval_xyz(node, x, y, z).unwrap()
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+
+        let s = Tree::x() + 2.0 * Tree::y() + 3.0 * Tree::z();
+        let t = s.translate(Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(eval(t.clone(), 1.0, 0.0, 0.0), 0.0);
+        assert_eq!(eval(t, 0.0, 0.0, 0.0), -1.0);
+
+        let t = s.scale(Vector3::new(2.0, 4.0, 0.5));
+        assert_eq!(eval(t.clone(), 2.0, 0.0, 0.0), 1.0);
+        assert_eq!(eval(t.clone(), 0.0, 4.0, 0.0), 2.0);
+        assert_eq!(eval(t, 0.0, 0.0, 0.5), 3.0);
+
+        // Rotating X by 90° about Z moves it to the Y axis
+        let t = Tree::x().rotate_z(std::f64::consts::FRAC_PI_2);
+        assert!(close(eval(t.clone(), 0.0, 1.0, 0.0), 1.0));
+        assert!(close(eval(t, 1.0, 0.0, 0.0), 0.0));
+        let t = Tree::y().rotate_x(std::f64::consts::FRAC_PI_2);
+        assert!(close(eval(t, 0.0, 0.0, 1.0), 1.0));
+        let t = Tree::z().rotate_y(std::f64::consts::FRAC_PI_2);
+        assert!(close(eval(t, 1.0, 0.0, 0.0), 1.0));
+
+        // Three 120° rotations about the diagonal cycle X -> Y -> Z
+        let t = Tree::x().rotate_axis_angle(
+            Vector3::new(1.0, 1.0, 1.0),
+            2.0 / 3.0 * std::f64::consts::PI,
+        );
+        assert!(close(eval(t, 0.0, 1.0, 0.0), 1.0));
+
+        // Transforms compose in order
+        let t = Tree::x()
+            .translate(Vector3::new(1.0, 0.0, 0.0))
+            .rotate_z(std::f64::consts::FRAC_PI_2);
+        assert!(close(eval(t.clone(), 0.0, 1.0, 0.0), 0.0));
+        assert!(close(eval(t, 0.0, 2.0, 0.0), 1.0));
+
+        let mat = nalgebra::convert(nalgebra::Translation3::new(0.0, 0.0, 2.0));
+        let t = Tree::z().transform(mat).unwrap();
+        assert_eq!(eval(t, 0.0, 0.0, 2.0), 0.0);
+
+        let singular = nalgebra::Affine3::from_matrix_unchecked(
+            nalgebra::Matrix4::from_diagonal(&nalgebra::Vector4::new(
+                1.0, 0.0, 1.0, 1.0,
+            )),
+        );
+        assert!(matches!(
+            Tree::x().transform(singular),
+            Err(Error::SingularMatrix(..))
+        ));
+    }
+
     #[test]
     fn test_remap_xyz() {
         // Remapping X
@@ -773,6 +1050,25 @@ mod test {
         assert_eq!(v, 0.0);
     }
 
+    #[test]
+    fn tree_gradient() {
+        let x = Tree::x();
+        let y = Tree::y();
+        let (vx, vy) = (x.var().unwrap(), y.var().unwrap());
+        let g = (x * 3.0).gradient(&[vx, vy, Var::new()]);
+        assert_eq!(g.len(), 3);
+        let TreeOp::Const(v) = *g[0] else {
+            panic!("invalid gradient {:?}", g[0])
+        };
+        assert_eq!(v, 3.0);
+        for d in &g[1..] {
+            let TreeOp::Const(v) = **d else {
+                panic!("invalid gradient {d:?}")
+            };
+            assert_eq!(v, 0.0);
+        }
+    }
+
     #[test]
     fn tree_pow() {
         let a = Tree::from(3);
@@ -793,14 +1089,13 @@ mod test {
 
     #[test]
     fn tree_poke() {
-        use facet::Facet;
         #[derive(facet::Facet)]
         struct Transform {
             tree: Tree,
             x: f64,
         }
 
-        let builder = facet::Partial::alloc_shape(Transform::SHAPE)
+        let builder = facet::Partial::alloc::<Transform>()
             .unwrap()
             .set_field("tree", Tree::x() + 2.0 * Tree::y())
             .unwrap()
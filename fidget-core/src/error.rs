@@ -41,10 +41,30 @@ pub enum Error {
     #[error("unknown variable {0}")]
     UnknownVariable(String),
 
+    /// A variable with this name has already been declared
+    #[error("variable `{0}` has already been declared")]
+    DuplicateVarName(String),
+
+    /// Default value for a variable is outside of its range
+    #[error("default value {0} is outside of the variable's range")]
+    DefaultOutOfRange(f64),
+
     /// Empty file
     #[error("empty file")]
     EmptyFile,
 
+    /// Serialized tape data is malformed
+    #[error("invalid tape data: {0}")]
+    BadTapeData(&'static str),
+
+    /// Serialized tape data uses an unknown format version
+    #[error("unsupported tape format version {0}")]
+    BadTapeVersion(u32),
+
+    /// Malformed line in a text file
+    #[error("parse error on line {0}: {1}")]
+    ParseError(usize, String),
+
     /// Choice slice length does not match choice count
     #[error("choice slice length ({0}) does not match choice count ({1})")]
     BadChoiceSlice(usize, usize),
@@ -53,14 +73,26 @@ pub enum Error {
     #[error("variable slice lengths are mismatched")]
     MismatchedSlices,
 
+    /// Interval bounds are out of order
+    #[error("invalid interval [{0}, {1}]")]
+    BadInterval(f32, f32),
+
     /// Variable slice length does not match expected count
     #[error("variable slice length ({0}) does not match expected count ({1})")]
     BadVarSlice(usize, usize),
 
+    /// Output count does not match the expected count
+    #[error("output count ({0}) does not match expected count ({1})")]
+    BadOutputCount(usize, usize),
+
     /// Variable index exceeds max var index for this tape
     #[error("variable index ({0}) exceeds max var index for this tape ({1})")]
     BadVarIndex(usize, usize),
 
+    /// Variable cannot be used as an input to generated shader code
+    #[error("variable {0} is not supported in shader code")]
+    BadShaderVar(Var),
+
     /// Could not solve for matrix pseudo-inverse
     #[error("could not solve for matrix pseudo-inverse: {0}")]
     SingularMatrix(&'static str),
@@ -80,4 +112,36 @@ pub enum Error {
     /// Tile size list must not be empty
     #[error("tile size list must not be empty")]
     EmptyTileSizes,
+
+    /// Tile size list could not be parsed
+    #[error("could not parse tile sizes from `{0}`")]
+    BadTileSizeString(String),
+
+    /// Tape exceeds the operation budget
+    #[error("tape exceeds the maximum of {0} operations")]
+    TapeTooLong(usize),
+
+    /// Tape construction exceeded its time budget
+    #[error("tape construction exceeded its time budget of {0:?}")]
+    BuildTimeout(std::time::Duration),
+
+    /// JIT function has too many inputs or outputs
+    #[error("JIT function has {0} inputs or outputs; the maximum is {1}")]
+    JitTooManyArgs(usize, usize),
+
+    /// JIT function needs a stack frame which is too large
+    #[error("JIT stack frame of {0} bytes exceeds the maximum of {1} bytes")]
+    JitStackTooLarge(usize, usize),
+
+    /// JIT assembly failed
+    #[error("JIT assembly failed: {0}")]
+    JitAssembly(String),
+
+    /// Point evaluation fell outside of the interval result
+    #[error("unsound interval evaluation: {0}")]
+    UnsoundInterval(Box<crate::check::SoundnessViolation>),
+
+    /// Operation is not supported by this implementation
+    #[error("{0} is not supported by this implementation")]
+    Unsupported(&'static str),
 }
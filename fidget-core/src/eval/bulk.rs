@@ -30,6 +30,13 @@ pub trait BulkEvaluator: Default {
     /// This is a workaround for plumbing purposes
     type TapeStorage;
 
+    /// Native number of items processed in a single iteration
+    ///
+    /// Evaluators accept slices of any length (see
+    /// [`eval`](BulkEvaluator::eval)), but callers which build their own input
+    /// buffers may prefer to use lengths which are a multiple of this value.
+    const SIMD_SIZE: usize = 1;
+
     /// Evaluates many points using the given instruction tape
     ///
     /// `vars` should be a slice-of-slices (or a slice-of-`Vec`s) representing
@@ -38,6 +45,11 @@ pub trait BulkEvaluator: Default {
     ///
     /// The returned slice is borrowed from the evaluator.
     ///
+    /// Slices don't need to be a multiple of [`SIMD_SIZE`](Self::SIMD_SIZE)
+    /// in length; trailing items are handled internally, without copying the
+    /// entire input.  Internal buffers are grown as needed and then reused, so
+    /// evaluating slices no longer than a previous call doesn't allocate.
+    ///
     /// Returns an error if any of the `var` slices are of different lengths, or
     /// if all variables aren't present.
     fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
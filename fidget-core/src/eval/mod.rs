@@ -1,9 +1,10 @@
 //! Traits and data structures for function evaluation
 use crate::{
     Error,
+    compiler::BuildBudget,
     context::{Context, Node},
     types::{Grad, Interval},
-    var::VarMap,
+    var::{Var, VarMap},
 };
 
 #[cfg(any(test, feature = "eval-tests"))]
@@ -11,10 +12,14 @@ use crate::{
 pub mod test;
 
 mod bulk;
+mod denormal;
+mod pool;
 mod tracing;
 
 // Reexport a few types
 pub use bulk::{BulkEvaluator, BulkOutput};
+pub use denormal::FlushDenormals;
+pub use pool::{EvalPool, PooledEval};
 pub use tracing::TracingEvaluator;
 
 /// A tape represents something that can be evaluated by an evaluator
@@ -168,6 +173,12 @@ pub trait Function: Send + Sync + Clone {
     ) -> <Self::GradSliceEval as BulkEvaluator>::Tape;
 
     /// Computes a simplified tape using the given trace, and reusing storage
+    ///
+    /// Simplification is typically performed once per tile (or octree cell),
+    /// so it is designed to run without heap allocation in steady state.
+    /// Callers should keep a single [`Workspace`](Self::Workspace) per thread
+    /// and reuse it across calls, and should pass in [`Storage`](Self::Storage)
+    /// reclaimed from previous functions (see [`recycle`](Self::recycle)).
     fn simplify(
         &self,
         trace: &Self::Trace,
@@ -194,6 +205,23 @@ pub trait Function: Send + Sync + Clone {
 
     /// Checks to see whether this function can ever be simplified
     fn can_simplify(&self) -> bool;
+
+    /// Returns an identifier for this function's underlying data
+    ///
+    /// Clones of a function must share the same identifier, and two functions
+    /// which are alive at the same time must not (unless they're clones).
+    /// Since tapes depend only on the function (not on variable values), this
+    /// can be used to cache tapes; see [`TapeCache`](crate::render::TapeCache),
+    /// which holds a clone of each cached function so that its identifier
+    /// can't be reused by a different function.
+    ///
+    /// For functions which store their data in an `Arc`, the address of the
+    /// shared data (e.g. from [`Arc::as_ptr`](std::sync::Arc::as_ptr)) is a
+    /// suitable identifier.  The default implementation returns `None`, which
+    /// means that tapes for this function are never cached.
+    fn id(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// A [`Function`] which can be built from a math expression
@@ -202,4 +230,78 @@ pub trait MathFunction: Function {
     fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error>
     where
         Self: Sized;
+
+    /// Builds a new function, subject to the given [`BuildBudget`]
+    ///
+    /// Returns [`Error::TapeTooLong`] or [`Error::BuildTimeout`] if the budget
+    /// is exceeded.
+    ///
+    /// The default implementation checks [`BuildBudget::max_ops`] against the
+    /// number of non-constant nodes plus one output clause per root (matching
+    /// the count used by [`VmData`](crate::vm::VmData)) before calling
+    /// [`new`](Self::new), then
+    /// checks [`BuildBudget::max_time`] after it returns (so it can't
+    /// interrupt a slow build).  Implementations should override it to check
+    /// the budget during construction.
+    fn new_with_budget(
+        ctx: &Context,
+        nodes: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let tracker = budget.start();
+        let ops = ctx
+            .reachable(nodes)?
+            .into_iter()
+            .filter(|n| ctx.get_const(*n).is_err())
+            .count();
+        tracker.check_ops(ops + nodes.len())?;
+        let f = Self::new(ctx, nodes)?;
+        tracker.check_time()?;
+        Ok(f)
+    }
+
+    /// Returns a new function with the given variable replaced by a constant
+    ///
+    /// Operations which depend on `var` are constant-folded and simplified,
+    /// so the new function never evaluates them.
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    fn specialize(&self, _var: Var, _value: f32) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Err(Error::Unsupported("MathFunction::specialize"))
+    }
+
+    /// Returns a function which evaluates `self` on the outputs of `inner`
+    ///
+    /// Output `i` of `inner` is wired to the input variable `wiring[i]` of
+    /// `self`; other inputs of `self` are passed through unchanged.  Returns
+    /// [`Error::BadOutputCount`] if `wiring` doesn't have one entry per output
+    /// of `inner`.
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    fn compose(&self, _inner: &Self, _wiring: &[Var]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Err(Error::Unsupported("MathFunction::compose"))
+    }
+
+    /// Returns a function which computes partial derivatives of `self`
+    ///
+    /// The new function has `vars.len()` outputs per output of `self`;
+    /// output `i * vars.len() + j` is the derivative of output `i` with
+    /// respect to `vars[j]`.
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    fn gradient(&self, _vars: &[Var]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Err(Error::Unsupported("MathFunction::gradient"))
+    }
 }
@@ -0,0 +1,200 @@
+//! Pool of evaluators which can be shared between threads
+use crate::eval::{BulkEvaluator, Function, TracingEvaluator};
+use std::sync::Mutex;
+
+/// Shared pool of evaluators for a single [`Function`]
+///
+/// Evaluators take `&mut self`, so they can't be shared between threads;
+/// instead, each thread needs its own evaluator, which should be reused across
+/// calls to avoid reallocating internal storage.  An `EvalPool` builds the
+/// function's tapes once, then hands out evaluators through a `&self` API,
+/// making it easy to evaluate from many threads (e.g. in a `rayon` parallel
+/// iterator).
+///
+/// Each `get_*` function checks out an idle evaluator from the pool (building
+/// a new one if none are available) and wraps it in a [`PooledEval`] guard,
+/// which dereferences to the evaluator and returns it to the pool when
+/// dropped.  The pool therefore holds at most one evaluator of each kind per
+/// thread that used it concurrently.
+///
+/// ```
+/// # use fidget_core::{
+/// #     context::Context, eval::{EvalPool, MathFunction, TracingEvaluator},
+/// #     vm::VmFunction,
+/// # };
+/// # use rayon::prelude::*;
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let root = ctx.square(x)?;
+/// let f = VmFunction::new(&ctx, &[root])?;
+///
+/// let pool = EvalPool::new(&f);
+/// let out: Vec<f32> = (0..100)
+///     .into_par_iter()
+///     .map(|i| {
+///         let mut eval = pool.get_point();
+///         let (out, _trace) = eval.eval(pool.point_tape(), &[i as f32])?;
+///         Ok(out[0])
+///     })
+///     .collect::<Result<_, fidget_core::Error>>()?;
+/// assert_eq!(out[3], 9.0);
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+pub struct EvalPool<F: Function> {
+    point_tape: <F::PointEval as TracingEvaluator>::Tape,
+    interval_tape: <F::IntervalEval as TracingEvaluator>::Tape,
+    float_slice_tape: <F::FloatSliceEval as BulkEvaluator>::Tape,
+    grad_slice_tape: <F::GradSliceEval as BulkEvaluator>::Tape,
+
+    point: Mutex<Vec<F::PointEval>>,
+    interval: Mutex<Vec<F::IntervalEval>>,
+    float_slice: Mutex<Vec<F::FloatSliceEval>>,
+    grad_slice: Mutex<Vec<F::GradSliceEval>>,
+}
+
+impl<F: Function> EvalPool<F> {
+    /// Builds a new pool, constructing tapes for the given function
+    pub fn new(f: &F) -> Self {
+        Self {
+            point_tape: f.point_tape(Default::default()),
+            interval_tape: f.interval_tape(Default::default()),
+            float_slice_tape: f.float_slice_tape(Default::default()),
+            grad_slice_tape: f.grad_slice_tape(Default::default()),
+            point: Mutex::default(),
+            interval: Mutex::default(),
+            float_slice: Mutex::default(),
+            grad_slice: Mutex::default(),
+        }
+    }
+
+    /// Returns the tape used for point evaluation
+    pub fn point_tape(&self) -> &<F::PointEval as TracingEvaluator>::Tape {
+        &self.point_tape
+    }
+
+    /// Returns the tape used for interval evaluation
+    pub fn interval_tape(
+        &self,
+    ) -> &<F::IntervalEval as TracingEvaluator>::Tape {
+        &self.interval_tape
+    }
+
+    /// Returns the tape used for float slice evaluation
+    pub fn float_slice_tape(
+        &self,
+    ) -> &<F::FloatSliceEval as BulkEvaluator>::Tape {
+        &self.float_slice_tape
+    }
+
+    /// Returns the tape used for gradient slice evaluation
+    pub fn grad_slice_tape(
+        &self,
+    ) -> &<F::GradSliceEval as BulkEvaluator>::Tape {
+        &self.grad_slice_tape
+    }
+
+    /// Checks out a point evaluator
+    pub fn get_point(&self) -> PooledEval<'_, F::PointEval> {
+        PooledEval::new(&self.point)
+    }
+
+    /// Checks out an interval evaluator
+    pub fn get_interval(&self) -> PooledEval<'_, F::IntervalEval> {
+        PooledEval::new(&self.interval)
+    }
+
+    /// Checks out a float slice evaluator
+    pub fn get_float_slice(&self) -> PooledEval<'_, F::FloatSliceEval> {
+        PooledEval::new(&self.float_slice)
+    }
+
+    /// Checks out a gradient slice evaluator
+    pub fn get_grad_slice(&self) -> PooledEval<'_, F::GradSliceEval> {
+        PooledEval::new(&self.grad_slice)
+    }
+}
+
+/// Evaluator checked out from an [`EvalPool`]
+///
+/// This dereferences to the inner evaluator, and returns it to the pool when
+/// dropped.
+pub struct PooledEval<'a, E> {
+    eval: Option<E>,
+    pool: &'a Mutex<Vec<E>>,
+}
+
+impl<'a, E: Default> PooledEval<'a, E> {
+    fn new(pool: &'a Mutex<Vec<E>>) -> Self {
+        let eval = pool.lock().unwrap().pop().unwrap_or_default();
+        Self {
+            eval: Some(eval),
+            pool,
+        }
+    }
+}
+
+impl<E> std::ops::Deref for PooledEval<'_, E> {
+    type Target = E;
+    fn deref(&self) -> &E {
+        self.eval.as_ref().unwrap()
+    }
+}
+
+impl<E> std::ops::DerefMut for PooledEval<'_, E> {
+    fn deref_mut(&mut self) -> &mut E {
+        self.eval.as_mut().unwrap()
+    }
+}
+
+impl<E> Drop for PooledEval<'_, E> {
+    fn drop(&mut self) {
+        if let (Some(e), Ok(mut pool)) = (self.eval.take(), self.pool.lock()) {
+            pool.push(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::Context, eval::MathFunction, types::Interval, vm::VmFunction,
+    };
+    use rayon::prelude::*;
+
+    #[test]
+    fn eval_pool() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let root = ctx.add(x, y).unwrap();
+        let f = VmFunction::new(&ctx, &[root]).unwrap();
+        let pool = EvalPool::new(&f);
+
+        (0..256).into_par_iter().for_each(|i| {
+            let v = i as f32;
+            let mut eval = pool.get_point();
+            let (out, _) = eval.eval(pool.point_tape(), &[v, 1.0]).unwrap();
+            assert_eq!(out[0], v + 1.0);
+
+            let mut eval = pool.get_interval();
+            let args = [Interval::new(0.0, v), Interval::new(1.0, 2.0)];
+            let (out, _) = eval.eval(pool.interval_tape(), &args).unwrap();
+            assert_eq!(out[0], Interval::new(1.0, v + 2.0));
+
+            let mut eval = pool.get_float_slice();
+            let out = eval
+                .eval(pool.float_slice_tape(), &[vec![v; 3], vec![2.0; 3]])
+                .unwrap();
+            assert_eq!(out[0], [v + 2.0; 3]);
+        });
+
+        // Evaluators are returned to the pool and reused
+        let n = pool.point.lock().unwrap().len();
+        assert!(n >= 1);
+        let a = pool.get_point();
+        assert_eq!(pool.point.lock().unwrap().len(), n - 1);
+        drop(a);
+        assert_eq!(pool.point.lock().unwrap().len(), n);
+    }
+}
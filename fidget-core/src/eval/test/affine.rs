@@ -0,0 +1,141 @@
+use super::{
+    CanonicalBinaryOp, CanonicalUnaryOp, interval::TestInterval, test_args,
+};
+use crate::{
+    context::Context,
+    eval::{Function, MathFunction, Tape, TracingEvaluator},
+    types::{Affine, Interval},
+    var::Var,
+    vm::{VmAffineEval, VmFunction},
+};
+
+/// Helper struct to test the affine arithmetic evaluator
+///
+/// Results are checked for containment against point-wise evaluation, using
+/// the same arguments as the interval tests.
+pub struct TestAffine;
+
+impl TestAffine {
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let args = TestInterval::<VmFunction>::interval_test_args();
+
+        let mut ctx = Context::new();
+        let v = ctx.var(Var::new());
+        let node = C::build(&mut ctx, v);
+
+        let shape = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = VmAffineEval::default();
+
+        for &a in args.iter() {
+            let (o, _trace) = eval.eval(&tape, &[Affine::axis(a, 0)]).unwrap();
+            TestInterval::<VmFunction>::compare_interval_results(
+                a,
+                a.lower().into(),
+                o[0].to_interval(),
+                |x, _| C::eval_f32(x),
+                C::NAME,
+            );
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = TestInterval::<VmFunction>::interval_test_args();
+
+        let mut ctx = Context::new();
+        let va = Var::new();
+        let vb = Var::new();
+        let a = ctx.var(va);
+        let b = ctx.var(vb);
+
+        let node = C::build(&mut ctx, a, b);
+        let shape = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = VmAffineEval::default();
+
+        let name = format!("{}(reg, reg)", C::NAME);
+        for &lhs in args.iter() {
+            for &rhs in args.iter() {
+                let mut vs = [Affine::from(0.0); 2];
+                vs[tape.vars()[&va]] = Affine::axis(lhs, 0);
+                vs[tape.vars()[&vb]] = Affine::axis(rhs, 1);
+                let (o, _trace) = eval.eval(&tape, &vs).unwrap();
+                TestInterval::<VmFunction>::compare_interval_results(
+                    lhs,
+                    rhs,
+                    o[0].to_interval(),
+                    C::eval_reg_reg_f32,
+                    &name,
+                );
+            }
+        }
+
+        let name = format!("{}(reg, imm)", C::NAME);
+        for &rhs in test_args().iter() {
+            let node = C::build(&mut ctx, a, rhs);
+            let shape = VmFunction::new(&ctx, &[node]).unwrap();
+            let tape = shape.interval_tape(Default::default());
+            for &lhs in args.iter() {
+                let (o, _trace) =
+                    eval.eval(&tape, &[Affine::axis(lhs, 0)]).unwrap();
+                TestInterval::<VmFunction>::compare_interval_results(
+                    lhs,
+                    rhs.into(),
+                    o[0].to_interval(),
+                    C::eval_reg_imm_f32,
+                    &name,
+                );
+            }
+        }
+
+        // Both arguments are the same variable, so only the diagonal is valid
+        let name = format!("{}(reg, reg) (correlated)", C::NAME);
+        let node = C::build(&mut ctx, a, a);
+        let shape = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        for &lhs in args.iter() {
+            let (o, _trace) =
+                eval.eval(&tape, &[Affine::axis(lhs, 0)]).unwrap();
+            TestInterval::<VmFunction>::compare_interval_results(
+                lhs,
+                lhs.lower().into(),
+                o[0].to_interval(),
+                |x, _| C::eval_reg_reg_f32(x, x),
+                &name,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_affine_tighter() {
+    // (x + y) - x - y is zero (up to rounding), but intervals can't tell
+    let mut ctx = Context::new();
+    let x = ctx.x();
+    let y = ctx.y();
+    let sum = ctx.add(x, y).unwrap();
+    let out = ctx.sub(sum, x).unwrap();
+    let out = ctx.sub(out, y).unwrap();
+
+    let shape = VmFunction::new(&ctx, &[out]).unwrap();
+    let tape = shape.interval_tape(Default::default());
+    let ix = tape.vars()[&Var::X];
+    let iy = tape.vars()[&Var::Y];
+
+    let mut vs = [Interval::from(0.0); 2];
+    vs[ix] = Interval::new(-1.0, 2.0);
+    vs[iy] = Interval::new(3.0, 5.0);
+    let mut eval = VmFunction::new_interval_eval();
+    let (i, _) = eval.eval(&tape, &vs).unwrap();
+    assert_eq!(i[0], Interval::new(-5.0, 5.0));
+
+    let vs = [Affine::axis(vs[0], 0), Affine::axis(vs[1], 1)];
+    let mut eval = VmAffineEval::default();
+    let (a, _) = eval.eval(&tape, &vs).unwrap();
+    let a = a[0].to_interval();
+    assert!(a.lower() <= 0.0 && a.upper() >= 0.0);
+    assert!(a.width() < 1e-4, "{a:?}");
+}
+
+crate::all_unary_tests!(TestAffine);
+crate::all_binary_tests!(TestAffine);
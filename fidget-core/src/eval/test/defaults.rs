@@ -0,0 +1,126 @@
+//! Tests for provided methods of [`Function`] and [`MathFunction`]
+use crate::{
+    Error,
+    compiler::BuildBudget,
+    context::{Context, Node},
+    eval::{BulkEvaluator, Function, MathFunction, TracingEvaluator},
+    var::{Var, VarMap},
+    vm::{VmData, VmFunction},
+};
+
+/// Downstream-style function, which only implements required methods
+#[derive(Clone)]
+struct Minimal(VmFunction);
+
+impl Function for Minimal {
+    type Trace = <VmFunction as Function>::Trace;
+    type Storage = <VmFunction as Function>::Storage;
+    type Workspace = <VmFunction as Function>::Workspace;
+    type TapeStorage = <VmFunction as Function>::TapeStorage;
+    type PointEval = <VmFunction as Function>::PointEval;
+    type IntervalEval = <VmFunction as Function>::IntervalEval;
+    type FloatSliceEval = <VmFunction as Function>::FloatSliceEval;
+    type GradSliceEval = <VmFunction as Function>::GradSliceEval;
+
+    fn point_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::PointEval as TracingEvaluator>::Tape {
+        self.0.point_tape(storage)
+    }
+    fn interval_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::IntervalEval as TracingEvaluator>::Tape {
+        self.0.interval_tape(storage)
+    }
+    fn float_slice_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::FloatSliceEval as BulkEvaluator>::Tape {
+        self.0.float_slice_tape(storage)
+    }
+    fn grad_slice_tape(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::GradSliceEval as BulkEvaluator>::Tape {
+        self.0.grad_slice_tape(storage)
+    }
+    fn simplify(
+        &self,
+        trace: &Self::Trace,
+        storage: Self::Storage,
+        workspace: &mut Self::Workspace,
+    ) -> Result<Self, Error> {
+        self.0.simplify(trace, storage, workspace).map(Minimal)
+    }
+    fn recycle(self) -> Option<Self::Storage> {
+        self.0.recycle()
+    }
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+    fn vars(&self) -> &VarMap {
+        self.0.vars()
+    }
+    fn can_simplify(&self) -> bool {
+        self.0.can_simplify()
+    }
+}
+
+impl MathFunction for Minimal {
+    fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        VmFunction::new(ctx, nodes).map(Minimal)
+    }
+}
+
+#[test]
+fn default_new_with_budget() {
+    let mut ctx = Context::new();
+    let x = ctx.x();
+    let y = ctx.y();
+    let a = ctx.add(x, 1.0).unwrap();
+    let b = ctx.mul(a, y).unwrap();
+
+    // x, y, a, b, and the output are counted; the constant isn't
+    let budget = |max_ops| BuildBudget {
+        max_ops: Some(max_ops),
+        ..Default::default()
+    };
+    assert!(Minimal::new_with_budget(&ctx, &[b], &budget(5)).is_ok());
+    assert!(matches!(
+        Minimal::new_with_budget(&ctx, &[b], &budget(4)),
+        Err(Error::TapeTooLong(4))
+    ));
+    assert!(VmData::<255>::new_with_budget(&ctx, &[b], &budget(5)).is_ok());
+}
+
+#[test]
+fn default_specialize() {
+    let mut ctx = Context::new();
+    let x = ctx.x();
+    let f = Minimal::new(&ctx, &[x]).unwrap();
+    assert!(matches!(
+        f.specialize(Var::X, 1.0),
+        Err(Error::Unsupported(..))
+    ));
+}
+
+#[test]
+fn default_compose() {
+    let mut ctx = Context::new();
+    let x = ctx.x();
+    let f = Minimal::new(&ctx, &[x]).unwrap();
+    assert!(matches!(
+        f.compose(&f, &[Var::X]),
+        Err(Error::Unsupported(..))
+    ));
+}
+
+#[test]
+fn default_gradient() {
+    let mut ctx = Context::new();
+    let x = ctx.x();
+    let f = Minimal::new(&ctx, &[x]).unwrap();
+    assert!(matches!(f.gradient(&[Var::X]), Err(Error::Unsupported(..))));
+}
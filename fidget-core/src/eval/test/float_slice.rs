@@ -92,6 +92,33 @@ impl<F: Function + MathFunction> TestFloatSlice<F> {
         assert_eq!(&out[0], [2.0, 8.0, 8.0, -2.0, -4.0, -6.0, 0.0]);
     }
 
+    pub fn test_f_simd_size() {
+        let w = <F::FloatSliceEval as BulkEvaluator>::SIMD_SIZE;
+        assert!(w >= 1);
+
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let x2 = ctx.mul(x, 2.0).unwrap();
+        let y = ctx.add(x2, 1.0).unwrap();
+        let shape = F::new(&ctx, &[y]).unwrap();
+        let tape = shape.float_slice_tape(Default::default());
+        let mut eval = F::new_float_slice_eval();
+
+        // Evaluating the longest slice first sizes the output buffers, so
+        // that shorter slices (including non-multiples of the SIMD size) are
+        // evaluated without reallocating.
+        let xs = (0..w * 4 + 1).map(|i| i as f32).collect::<Vec<_>>();
+        let ptr = eval.eval(&tape, &[xs.as_slice()]).unwrap()[0].as_ptr();
+        for n in (0..=xs.len()).rev() {
+            let out = eval.eval(&tape, &[&xs[..n]]).unwrap();
+            assert_eq!(out[0].len(), n);
+            assert_eq!(out[0].as_ptr(), ptr);
+            for (x, o) in xs.iter().zip(&out[0]) {
+                assert_eq!(*o, x * 2.0 + 1.0);
+            }
+        }
+    }
+
     pub fn test_f_sin() {
         let mut ctx = Context::new();
         let a = ctx.x();
@@ -449,6 +476,7 @@ macro_rules! float_slice_tests {
     ($t:ty) => {
         $crate::float_slice_test!(test_give_take, $t);
         $crate::float_slice_test!(test_vectorized, $t);
+        $crate::float_slice_test!(test_f_simd_size, $t);
         $crate::float_slice_test!(test_f_sin, $t);
         $crate::float_slice_test!(test_f_shape_var, $t);
         $crate::float_slice_test!(test_f_stress, $t);
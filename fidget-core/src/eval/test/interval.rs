@@ -198,10 +198,14 @@ where
         let tape = shape.interval_tape(Default::default());
 
         let mut eval = F::new_interval_eval();
-        assert_eq!(
-            eval.eval(&tape, &[[0.0, 1.0].into()]).unwrap().0[0],
-            [0.0, 1.0_f32.sin()].into()
-        );
+        let v = eval.eval(&tape, &[[0.0, 1.0].into()]).unwrap().0[0];
+        if cfg!(feature = "outward-rounding") {
+            // Transcendental results are widened by a few ULPs
+            v.compare_eq([0.0, 1.0_f32.sin()].into());
+            assert!(v.contains(0.0) && v.contains(1.0_f32.sin()));
+        } else {
+            assert_eq!(v, [0.0, 1.0_f32.sin()].into());
+        }
 
         let y = ctx.y();
         let y = ctx.mul(y, 2.0).unwrap();
@@ -763,6 +767,89 @@ where
         assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
     }
 
+    pub fn test_i_sign() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let sign = ctx.sign(x).unwrap();
+
+        let shape = F::new(&ctx, &[sign]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let (out, data) = eval.eval(&tape, &[[-1.0, 2.0].into()]).unwrap();
+        assert_eq!(out[0], [-1.0, 1.0].into());
+        assert!(data.is_none());
+
+        // Unambiguous inputs collapse the tape to a constant
+        for (i, v) in [([0.5, 2.0], 1.0), ([-2.0, -0.5], -1.0)] {
+            let (out, data) = eval.eval(&tape, &[i.into()]).unwrap();
+            assert_eq!(out[0], v.into());
+            let s = shape
+                .simplify(
+                    data.expect("must have trace"),
+                    Default::default(),
+                    &mut Default::default(),
+                )
+                .unwrap();
+            assert!(s.size() < shape.size());
+            let t = s.interval_tape(Default::default());
+            let (out, data) = eval.eval(&t, &[[-5.0, 5.0].into()]).unwrap();
+            assert_eq!(out[0], v.into());
+            assert!(data.is_none());
+        }
+    }
+
+    pub fn test_i_clamp() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let clamp = ctx.clamp(x, -1.0, 1.0).unwrap();
+
+        let shape = F::new(&ctx, &[clamp]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let (out, data) = eval.eval(&tape, &[[-2.0, 2.0].into()]).unwrap();
+        assert_eq!(out[0], [-1.0, 1.0].into());
+        assert!(data.is_none());
+
+        let (out, data) = eval.eval(&tape, &[[-0.5, 0.5].into()]).unwrap();
+        assert_eq!(out[0], [-0.5, 0.5].into());
+        let s = shape
+            .simplify(
+                data.expect("must have trace"),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(s.size() < shape.size());
+
+        let (out, data) = eval.eval(&tape, &[[1.5, 2.0].into()]).unwrap();
+        assert_eq!(out[0], [1.0, 1.0].into());
+        assert!(data.is_some());
+    }
+
+    pub fn test_i_comparisons() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let lt = ctx.less_than(x, 1.0).unwrap();
+        let gt = ctx.greater_than(x, 1.0).unwrap();
+        let eq = ctx.equal(x, 1.0).unwrap();
+
+        for (node, lo, hi, mid) in [
+            (lt, 1.0, 0.0, [0.0, 1.0]),
+            (gt, 0.0, 1.0, [0.0, 1.0]),
+            (eq, 0.0, 0.0, [0.0, 1.0]),
+        ] {
+            let shape = F::new(&ctx, &[node]).unwrap();
+            let tape = shape.interval_tape(Default::default());
+            let mut eval = F::new_interval_eval();
+            let (out, _) = eval.eval(&tape, &[[-1.0, 0.5].into()]).unwrap();
+            assert_eq!(out[0], lo.into());
+            let (out, _) = eval.eval(&tape, &[[1.5, 3.0].into()]).unwrap();
+            assert_eq!(out[0], hi.into());
+            let (out, _) = eval.eval(&tape, &[[0.0, 2.0].into()]).unwrap();
+            assert_eq!(out[0], mid.into());
+        }
+    }
+
     pub fn test_i_simplify_conditional() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -1228,6 +1315,9 @@ macro_rules! interval_tests {
         $crate::interval_test!(test_i_modulo, $t);
         $crate::interval_test!(test_i_simplify, $t);
         $crate::interval_test!(test_i_simplify_conditional, $t);
+        $crate::interval_test!(test_i_sign, $t);
+        $crate::interval_test!(test_i_clamp, $t);
+        $crate::interval_test!(test_i_comparisons, $t);
         $crate::interval_test!(test_i_stress, $t);
         $crate::interval_test!(test_i_multiple_outputs, $t);
 
@@ -5,6 +5,12 @@ pub mod interval;
 pub mod point;
 
 // Internal-only tests
+#[cfg(test)]
+mod affine;
+#[cfg(test)]
+mod defaults;
+#[cfg(test)]
+mod reverse_deriv;
 #[cfg(test)] // not enabled for eval-tests
 mod symbolic_deriv;
 
@@ -42,6 +48,59 @@ pub fn build_stress_fn(n: usize) -> (Context, Node) {
     (ctx, sum)
 }
 
+/// Builds a function which uses every opcode
+///
+/// Unary operations are applied to `x` and binary operations to `(y, z)`,
+/// followed by register-immediate and immediate-register clauses; the results
+/// are summed into a single output.
+pub fn build_all_ops() -> (Context, Node) {
+    let mut ctx = Context::new();
+    let (x, y, z) = (ctx.x(), ctx.y(), ctx.z());
+    let mut sum = ctx.constant(0.0);
+    for f in [
+        Context::neg,
+        Context::abs,
+        Context::recip,
+        Context::sqrt,
+        Context::square,
+        Context::floor,
+        Context::ceil,
+        Context::round,
+        Context::sin,
+        Context::cos,
+        Context::tan,
+        Context::asin,
+        Context::acos,
+        Context::atan,
+        Context::exp,
+        Context::ln,
+        Context::not,
+    ] {
+        let n = f(&mut ctx, x).unwrap();
+        sum = ctx.add(sum, n).unwrap();
+    }
+    for f in [
+        Context::sub::<Node, Node>,
+        Context::mul,
+        Context::div,
+        Context::atan2,
+        Context::min,
+        Context::max,
+        Context::compare,
+        Context::modulo,
+        Context::and,
+        Context::or,
+    ] {
+        let n = f(&mut ctx, y, z).unwrap();
+        sum = ctx.add(sum, n).unwrap();
+    }
+    let n = ctx.max(x, 0.5).unwrap();
+    sum = ctx.add(sum, n).unwrap();
+    let n = ctx.sub(2.0, y).unwrap();
+    sum = ctx.add(sum, n).unwrap();
+    (ctx, sum)
+}
+
 /// Pick a bunch of arguments, some of which are spicy
 fn test_args_n(n: i64) -> Vec<f32> {
     let mut args = (-n..=n)
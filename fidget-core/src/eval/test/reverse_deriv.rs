@@ -0,0 +1,137 @@
+use super::{CanonicalBinaryOp, CanonicalUnaryOp, test_args};
+use crate::{
+    context::Context,
+    eval::{BulkEvaluator, Function, MathFunction},
+    types::Grad,
+    var::Var,
+    vm::VmFunction,
+};
+
+/// Helper struct to test reverse-mode differentiation
+///
+/// Results from [`Context::gradient`] are checked against forward-mode
+/// gradients from the [`Grad`] evaluator.
+pub struct TestReverseDerivs;
+
+/// Checks a single derivative value
+///
+/// At singularities (e.g. division by zero), forward-mode evaluation may
+/// produce `NaN` where reverse-mode evaluation produces an infinity; both are
+/// accepted.
+fn check(name: &str, v: f32, expected: f32, actual: f32) {
+    let err = (expected - actual).abs();
+    let err_frac = err / expected.abs().max(actual.abs());
+    assert!(
+        expected == actual
+            || err < 1e-6
+            || err_frac < 1e-6
+            || (expected.is_nan() && !actual.is_finite())
+            || v.is_nan(),
+        "mismatch in {name}: {expected} != {actual} ({err})",
+    );
+}
+
+impl TestReverseDerivs {
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let args = test_args();
+
+        let mut ctx = Context::new();
+        let va = Var::new();
+        let a = ctx.var(va);
+        let node = C::build(&mut ctx, a);
+        let shape = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.grad_slice_tape(Default::default());
+        let mut eval = VmFunction::new_grad_slice_eval();
+
+        // The original node is included as an extra output so that the tape
+        // always has an input, even if the gradient is constant.
+        let mut grad = ctx.gradient(node, &[va]).unwrap();
+        grad.push(node);
+        let shape_grad = VmFunction::new(&ctx, &grad).unwrap();
+        let tape_grad = shape_grad.float_slice_tape(Default::default());
+        let mut eval_grad = VmFunction::new_float_slice_eval();
+
+        let args_g = args
+            .iter()
+            .map(|&v| Grad::new(v, 1.0, 0.0, 0.0))
+            .collect::<Vec<_>>();
+        let out = eval.eval(&tape, &[args_g.as_slice()]).unwrap();
+        let out_grad = eval_grad.eval(&tape_grad, &[args.as_slice()]).unwrap();
+        for (i, a) in args.iter().enumerate() {
+            check(
+                &format!("'d {}' at {a}", C::NAME),
+                out[0][i].v,
+                out[0][i].dx,
+                out_grad[0][i],
+            );
+        }
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = test_args();
+
+        let mut ctx = Context::new();
+        let va = Var::new();
+        let vb = Var::new();
+        let a = ctx.var(va);
+        let b = ctx.var(vb);
+
+        let node = C::build(&mut ctx, a, b);
+        let shape = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.grad_slice_tape(Default::default());
+        let mut eval = VmFunction::new_grad_slice_eval();
+
+        // Both partial derivatives are computed by a single tape
+        let mut grad = ctx.gradient(node, &[va, vb]).unwrap();
+        grad.push(node);
+        let shape_grad = VmFunction::new(&ctx, &grad).unwrap();
+        let tape_grad = shape_grad.float_slice_tape(Default::default());
+        let mut eval_grad = VmFunction::new_float_slice_eval();
+
+        for rot in 0..args.len() {
+            let mut rgsa = args.clone();
+            rgsa.rotate_left(rot);
+
+            let args_g = args
+                .iter()
+                .map(|v| Grad::new(*v, 1.0, 0.0, 0.0))
+                .collect::<Vec<_>>();
+            let rgsa_g = rgsa
+                .iter()
+                .map(|v| Grad::new(*v, 0.0, 1.0, 0.0))
+                .collect::<Vec<_>>();
+
+            let ia = shape.vars().get(&va).unwrap();
+            let ib = shape.vars().get(&vb).unwrap();
+            let mut vs = [[].as_slice(), [].as_slice()];
+            vs[ia] = args_g.as_slice();
+            vs[ib] = rgsa_g.as_slice();
+            let out = eval.eval(&tape, &vs).unwrap();
+
+            let mut vs = [[].as_slice(), [].as_slice()];
+            vs[shape_grad.vars()[&va]] = args.as_slice();
+            vs[shape_grad.vars()[&vb]] = rgsa.as_slice();
+            let out_grad = eval_grad.eval(&tape_grad, &vs).unwrap();
+
+            for i in 0..out[0].len() {
+                let v = out[0][i];
+                let (a, b) = (args[i], rgsa[i]);
+                check(
+                    &format!("'d {}(a, b) / da' at ({a}, {b})", C::NAME),
+                    v.v,
+                    v.dx,
+                    out_grad[0][i],
+                );
+                check(
+                    &format!("'d {}(a, b) / db' at ({a}, {b})", C::NAME),
+                    v.v,
+                    v.dy,
+                    out_grad[1][i],
+                );
+            }
+        }
+    }
+}
+
+crate::all_unary_tests!(TestReverseDerivs);
+crate::all_binary_tests!(TestReverseDerivs);
@@ -37,7 +37,7 @@ impl TestSymbolicDerivs {
             eval_deriv.eval(&tape_deriv, &[args.as_slice()]).unwrap();
         for (v, (a, b)) in args.iter().zip(out[0].iter().zip(&out_deriv[0])) {
             let a = a.dx;
-            let err = a - b;
+            let err = (a - b).abs();
             let err_frac = err / a.abs().max(b.abs());
             assert!(
                 a == *b
@@ -122,7 +122,7 @@ impl TestSymbolicDerivs {
                 let a = args[i];
                 let b = rgsa[i];
 
-                let err = v.dx - da;
+                let err = (v.dx - da).abs();
                 let err_frac = err / da.abs().max(v.dx.abs());
                 assert!(
                     v.dx == da
@@ -137,7 +137,7 @@ impl TestSymbolicDerivs {
                 );
 
                 let db = out_b_deriv[i];
-                let err = v.dy - db;
+                let err = (v.dy - db).abs();
                 let err_frac = err / db.abs().max(v.dy.abs());
                 assert!(
                     v.dy == db
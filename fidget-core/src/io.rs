@@ -0,0 +1,310 @@
+//! Reading and writing shapes in external file formats
+//!
+//! [`read_vm`] and [`write_vm`] handle the flat `.vm` text format used by
+//! [libfive](https://libfive.com) and Antimony, which is also used for the
+//! models in this repository.  Each line defines a single node, named by an
+//! arbitrary token and referring to previously-defined nodes by name:
+//!
+//! ```text
+//! # Circle of radius 1
+//! _0 var-x
+//! _1 square _0
+//! _2 var-y
+//! _3 square _2
+//! _4 add _1 _3
+//! _5 sqrt _4
+//! _6 const 1
+//! _7 sub _5 _6
+//! ```
+//!
+//! The last node in the file is the root.
+use crate::{
+    Error,
+    context::{BinaryOpcode, Context, Node, Op, UnaryOpcode},
+    var::Var,
+};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+};
+
+/// Parses a `.vm` file into a new [`Context`], returning the root node
+///
+/// In addition to Fidget's own opcodes (e.g. `add`, `square`, `compare`), this
+/// accepts the libfive names `recip`, `log`, and `pow`.  `var-free` declares a
+/// new free variable, and `const-var` is treated as the identity function.
+/// `pow` with a constant integer exponent is lowered to
+/// [`Context::powi`], so it's valid for negative bases.
+///
+/// ```
+/// use fidget_core::io::read_vm;
+///
+/// let txt = "
+/// _0 var-x
+/// _1 const 2
+/// _2 pow _0 _1
+/// ";
+/// let (ctx, root) = read_vm(txt.as_bytes())?;
+/// let v = ctx.eval_xyz(root, -3.0, 0.0, 0.0)?;
+/// assert_eq!(v, 9.0);
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+pub fn read_vm<R: Read>(r: R) -> Result<(Context, Node), Error> {
+    let reader = BufReader::new(r);
+    let mut ctx = Context::new();
+    let mut seen: HashMap<String, Node> = HashMap::new();
+    let mut last = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_num = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| Error::ParseError(line_num, msg.to_owned());
+
+        let mut iter = line.split_whitespace();
+        let name = iter.next().unwrap().to_owned();
+        let opcode = iter.next().ok_or_else(|| err("missing opcode"))?;
+
+        let mut arg = || {
+            let txt = iter.next().ok_or_else(|| err("missing argument"))?;
+            seen.get(txt)
+                .cloned()
+                .ok_or_else(|| Error::UnknownVariable(txt.to_string()))
+        };
+        let node = match opcode {
+            "const" => {
+                let v = iter.next().ok_or_else(|| err("missing constant"))?;
+                let v = v.parse().map_err(|_| err("invalid constant"))?;
+                ctx.constant(v)
+            }
+            "var-x" => ctx.x(),
+            "var-y" => ctx.y(),
+            "var-z" => ctx.z(),
+            "var-w" => ctx.w(),
+            "var-free" => ctx.var(Var::new()),
+            "const-var" => arg()?,
+            "abs" => ctx.abs(arg()?)?,
+            "neg" => ctx.neg(arg()?)?,
+            "recip" => ctx.recip(arg()?)?,
+            "sqrt" => ctx.sqrt(arg()?)?,
+            "square" => ctx.square(arg()?)?,
+            "floor" => ctx.floor(arg()?)?,
+            "ceil" => ctx.ceil(arg()?)?,
+            "round" => ctx.round(arg()?)?,
+            "sin" => ctx.sin(arg()?)?,
+            "cos" => ctx.cos(arg()?)?,
+            "tan" => ctx.tan(arg()?)?,
+            "asin" => ctx.asin(arg()?)?,
+            "acos" => ctx.acos(arg()?)?,
+            "atan" => ctx.atan(arg()?)?,
+            "ln" | "log" => ctx.ln(arg()?)?,
+            "not" => ctx.not(arg()?)?,
+            "exp" => ctx.exp(arg()?)?,
+            "add" => ctx.add(arg()?, arg()?)?,
+            "mul" => ctx.mul(arg()?, arg()?)?,
+            "min" => ctx.min(arg()?, arg()?)?,
+            "max" => ctx.max(arg()?, arg()?)?,
+            "div" => ctx.div(arg()?, arg()?)?,
+            "atan2" => ctx.atan2(arg()?, arg()?)?,
+            "sub" => ctx.sub(arg()?, arg()?)?,
+            "compare" => ctx.compare(arg()?, arg()?)?,
+            "mod" => ctx.modulo(arg()?, arg()?)?,
+            "and" => ctx.and(arg()?, arg()?)?,
+            "or" => ctx.or(arg()?, arg()?)?,
+            "pow" => ctx.pow(arg()?, arg()?)?,
+            op => return Err(Error::UnknownOpcode(op.to_owned())),
+        };
+        if iter.next().is_some() {
+            return Err(err("too many arguments"));
+        }
+        seen.insert(name, node);
+        last = Some(node);
+    }
+    last.map(|node| (ctx, node)).ok_or(Error::EmptyFile)
+}
+
+/// Writes the subgraph under `root` as a `.vm` file
+///
+/// Nodes are written in dependency order and named `_0`, `_1`, etc, with the
+/// root last.  Opcodes use their libfive names where one exists (e.g. `log`
+/// rather than `ln`), and variables other than the X, Y, Z, and W axes are
+/// written as `var-free`.  The output can always be read back by [`read_vm`],
+/// but it's only readable by libfive-based tools if the graph avoids
+/// Fidget-specific opcodes (`floor`, `ceil`, `round`, `not`, `and`, `or`) and
+/// the W axis (`var-w`), which libfive doesn't support.
+///
+/// ```
+/// use fidget_core::{Context, io::{read_vm, write_vm}};
+///
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let root = ctx.sub(x, 1.0)?;
+/// let mut out = vec![];
+/// write_vm(&ctx, root, &mut out)?;
+/// assert_eq!(
+///     std::str::from_utf8(&out).unwrap(),
+///     "_0 var-x\n_1 const 1\n_2 sub _0 _1\n"
+/// );
+///
+/// let (ctx, root) = read_vm(out.as_slice())?;
+/// assert_eq!(ctx.eval_xyz(root, 3.0, 0.0, 0.0)?, 2.0);
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+pub fn write_vm<W: Write>(
+    ctx: &Context,
+    root: Node,
+    mut w: W,
+) -> Result<(), Error> {
+    // Find every node under the root; children are always inserted into the
+    // context before their parents, so sorting by index gives a valid order.
+    let nodes = ctx.reachable(&[root])?;
+
+    let mut names = HashMap::new();
+    for (i, n) in nodes.into_iter().enumerate() {
+        write!(w, "_{i} ")?;
+        match *ctx.get_op(n).unwrap() {
+            Op::Const(c) => write!(w, "const {}", c.0)?,
+            Op::Input(v) => {
+                let name = match v {
+                    Var::X => "var-x",
+                    Var::Y => "var-y",
+                    Var::Z => "var-z",
+                    Var::W => "var-w",
+                    Var::V(..) => "var-free",
+                };
+                write!(w, "{name}")?
+            }
+            Op::Unary(op, a) => {
+                let name = match op {
+                    UnaryOpcode::Neg => "neg",
+                    UnaryOpcode::Abs => "abs",
+                    UnaryOpcode::Recip => "recip",
+                    UnaryOpcode::Sqrt => "sqrt",
+                    UnaryOpcode::Square => "square",
+                    UnaryOpcode::Floor => "floor",
+                    UnaryOpcode::Ceil => "ceil",
+                    UnaryOpcode::Round => "round",
+                    UnaryOpcode::Sin => "sin",
+                    UnaryOpcode::Cos => "cos",
+                    UnaryOpcode::Tan => "tan",
+                    UnaryOpcode::Asin => "asin",
+                    UnaryOpcode::Acos => "acos",
+                    UnaryOpcode::Atan => "atan",
+                    UnaryOpcode::Exp => "exp",
+                    UnaryOpcode::Ln => "log",
+                    UnaryOpcode::Not => "not",
+                };
+                write!(w, "{name} _{}", names[&a])?
+            }
+            Op::Binary(op, a, b) => {
+                let name = match op {
+                    BinaryOpcode::Add => "add",
+                    BinaryOpcode::Sub => "sub",
+                    BinaryOpcode::Mul => "mul",
+                    BinaryOpcode::Div => "div",
+                    BinaryOpcode::Atan => "atan2",
+                    BinaryOpcode::Min => "min",
+                    BinaryOpcode::Max => "max",
+                    BinaryOpcode::Compare => "compare",
+                    BinaryOpcode::Mod => "mod",
+                    BinaryOpcode::And => "and",
+                    BinaryOpcode::Or => "or",
+                };
+                write!(w, "{name} _{} _{}", names[&a], names[&b])?
+            }
+        }
+        writeln!(w)?;
+        names.insert(n, i);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_vm_models() {
+        for txt in [
+            include_str!("../../fidget/models/hi.vm"),
+            include_str!("../../fidget/models/quarter.vm"),
+        ] {
+            let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+            assert!(ctx.eval_xyz(root, 0.0, 0.0, 0.0).is_ok());
+        }
+    }
+
+    #[test]
+    fn read_vm_libfive() {
+        let txt = "
+            # Comments and indentation are allowed
+            a var-x
+            b recip a
+            c log a
+            d const-var c
+            e add b d
+        ";
+        let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+        let v = ctx.eval_xyz(root, 2.0, 0.0, 0.0).unwrap();
+        assert_eq!(v, 0.5 + 2f64.ln());
+
+        // Integer powers are valid for negative bases
+        let txt = "a var-x\nb const 3\nc pow a b";
+        let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+        assert_eq!(ctx.eval_xyz(root, -2.0, 0.0, 0.0).unwrap(), -8.0);
+        let txt = "a var-x\nb const 0.5\nc pow a b";
+        let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+        let v = ctx.eval_xyz(root, 4.0, 0.0, 0.0).unwrap();
+        assert!((v - 2.0).abs() < 1e-12);
+
+        let txt = "a var-x\nb var-free\nc add a b";
+        let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+        assert!(matches!(
+            ctx.eval_xyz(root, 2.0, 0.0, 0.0),
+            Err(Error::MissingVar(..))
+        ));
+    }
+
+    #[test]
+    fn write_vm_roundtrip() {
+        let txt = include_str!("../../fidget/models/bear.vm");
+        let (ctx, root) = read_vm(txt.as_bytes()).unwrap();
+        let mut out = vec![];
+        write_vm(&ctx, root, &mut out).unwrap();
+        let (ctx2, root2) = read_vm(out.as_slice()).unwrap();
+        assert_eq!(ctx.export(root).unwrap(), ctx2.export(root2).unwrap());
+
+        // Only nodes under the root are written
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let _unused = ctx.add(x, y).unwrap();
+        let root = ctx.ln(y).unwrap();
+        let mut out = vec![];
+        ctx.write_vm(root, &mut out).unwrap();
+        assert_eq!(std::str::from_utf8(&out).unwrap(), "_0 var-y\n_1 log _0\n");
+
+        assert!(matches!(
+            write_vm(&Context::new(), root, vec![]),
+            Err(Error::BadNode)
+        ));
+    }
+
+    #[test]
+    fn read_vm_errors() {
+        let check = |txt: &str| read_vm(txt.as_bytes()).unwrap_err();
+        assert!(matches!(check(""), Error::EmptyFile));
+        assert!(matches!(check("_0 foo"), Error::UnknownOpcode(..)));
+        assert!(matches!(check("_0 neg _1"), Error::UnknownVariable(..)));
+        assert!(matches!(check("_0"), Error::ParseError(1, ..)));
+        assert!(matches!(
+            check("_0 var-x\n_1 add _0"),
+            Error::ParseError(2, ..)
+        ));
+        assert!(matches!(check("_0 const one"), Error::ParseError(1, ..)));
+        assert!(matches!(check("_0 var-x _0"), Error::ParseError(1, ..)));
+    }
+}
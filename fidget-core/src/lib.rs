@@ -58,8 +58,12 @@
 pub mod context;
 pub use context::Context;
 
+pub mod check;
 pub mod compiler;
 pub mod eval;
+pub mod io;
+pub mod parse;
+pub mod query;
 pub mod render;
 pub mod shape;
 pub mod types;
@@ -0,0 +1,473 @@
+//! Parsing math expressions from strings
+//!
+//! [`parse`] turns an infix expression such as `sqrt(x*x + y*y) - 1` into a
+//! [`Node`] in a [`Context`], without needing a scripting engine:
+//!
+//! ```
+//! use fidget_core::{Context, parse::parse};
+//!
+//! let mut ctx = Context::new();
+//! let circle = parse(&mut ctx, "sqrt(x*x + y*y) - 1")?;
+//! assert_eq!(ctx.eval_xyz(circle, 3.0, 4.0, 0.0)?, 4.0);
+//! # Ok::<(), fidget_core::Error>(())
+//! ```
+//!
+//! The grammar supports
+//! - Numbers (`1`, `0.5`, `1e-3`) and the constants `pi` and `e`
+//! - The axes `x`, `y`, `z`, and `w`, plus any variable declared with
+//!   [`Context::declare_var`]
+//! - Binary operators `+`, `-`, `*`, `/`, `%` (modulo), and `^` (power, which
+//!   is right-associative), with the usual precedence
+//! - Unary `-` and `+`
+//! - Function calls, e.g. `min(a, b)`; `min` and `max` accept any number of
+//!   arguments.  The available functions are the [`Context`] builders with
+//!   the same name (plus `log` as an alias for `ln`, and `pow` for powers).
+use crate::{
+    Error,
+    context::{Context, Node},
+    var::Var,
+};
+
+/// Maximum nesting depth, to avoid overflowing the stack on hostile input
+const MAX_DEPTH: usize = 256;
+
+/// Parses an expression into a node in the given context
+///
+/// Returns [`Error::ParseError`] (with a 1-indexed line number) for malformed
+/// input, [`Error::UnknownVariable`] for unknown names, and
+/// [`Error::UnknownOpcode`] for unknown functions.
+pub fn parse(ctx: &mut Context, text: &str) -> Result<Node, Error> {
+    let tokens = tokenize(text)?;
+    let mut p = Parser {
+        ctx,
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let out = p.expr()?;
+    match p.tokens.get(p.pos) {
+        None => Ok(out),
+        Some((t, line)) => Err(Error::ParseError(
+            *line,
+            format!("unexpected {t} after expression"),
+        )),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(v) => write!(f, "number `{v}`"),
+            Token::Ident(s) => write!(f, "`{s}`"),
+            Token::Symbol(c) => write!(f, "`{c}`"),
+        }
+    }
+}
+
+/// Splits the input into `(token, line)` tuples
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let mut out = vec![];
+    let mut line = 1;
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        if c == '\n' {
+            line += 1;
+            chars.next();
+        } else if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = i;
+            let mut prev = None;
+            while let Some(&(j, c)) = chars.peek() {
+                let exponent = matches!(prev, Some('e' | 'E'));
+                if c.is_ascii_digit()
+                    || c == '.'
+                    || ((c == 'e' || c == 'E') && !exponent)
+                    || ((c == '+' || c == '-') && exponent)
+                {
+                    end = j + c.len_utf8();
+                    prev = Some(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let s = &text[i..end];
+            let v = s.parse::<f64>().map_err(|_| {
+                Error::ParseError(line, format!("invalid number `{s}`"))
+            })?;
+            out.push((Token::Num(v), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = i;
+            while let Some(&(j, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    end = j + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            out.push((Token::Ident(text[i..end].to_owned()), line));
+        } else if "+-*/%^(),".contains(c) {
+            out.push((Token::Symbol(c), line));
+            chars.next();
+        } else {
+            return Err(Error::ParseError(
+                line,
+                format!("unexpected character `{c}`"),
+            ));
+        }
+    }
+    Ok(out)
+}
+
+struct Parser<'a> {
+    ctx: &'a mut Context,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Returns the current line (or the last line, at the end of input)
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1)
+    }
+
+    /// Consumes the next token if it is the given symbol
+    fn eat(&mut self, c: char) -> bool {
+        if matches!(self.tokens.get(self.pos), Some((Token::Symbol(s), _)) if *s == c)
+        {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            let msg = match self.tokens.get(self.pos) {
+                Some((t, _)) => format!("expected `{c}`, found {t}"),
+                None => format!("expected `{c}`, found end of input"),
+            };
+            Err(Error::ParseError(self.line(), msg))
+        }
+    }
+
+    /// `expr := term (('+' | '-') term)*`
+    fn expr(&mut self) -> Result<Node, Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(Error::ParseError(
+                self.line(),
+                "expression is too deeply nested".to_owned(),
+            ));
+        }
+        let mut out = self.term()?;
+        loop {
+            if self.eat('+') {
+                let rhs = self.term()?;
+                out = self.ctx.add(out, rhs)?;
+            } else if self.eat('-') {
+                let rhs = self.term()?;
+                out = self.ctx.sub(out, rhs)?;
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        Ok(out)
+    }
+
+    /// `term := unary (('*' | '/' | '%') unary)*`
+    fn term(&mut self) -> Result<Node, Error> {
+        let mut out = self.unary()?;
+        loop {
+            if self.eat('*') {
+                let rhs = self.unary()?;
+                out = self.ctx.mul(out, rhs)?;
+            } else if self.eat('/') {
+                let rhs = self.unary()?;
+                out = self.ctx.div(out, rhs)?;
+            } else if self.eat('%') {
+                let rhs = self.unary()?;
+                out = self.ctx.modulo(out, rhs)?;
+            } else {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    /// `unary := ('-' | '+') unary | power`
+    fn unary(&mut self) -> Result<Node, Error> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(Error::ParseError(
+                self.line(),
+                "expression is too deeply nested".to_owned(),
+            ));
+        }
+        let out = if self.eat('-') {
+            let a = self.unary()?;
+            self.ctx.neg(a)?
+        } else if self.eat('+') {
+            self.unary()?
+        } else {
+            self.power()?
+        };
+        self.depth -= 1;
+        Ok(out)
+    }
+
+    /// `power := atom ('^' unary)?`
+    fn power(&mut self) -> Result<Node, Error> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exp = self.unary()?;
+            self.ctx.pow(base, exp)
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// `atom := number | name | name '(' args ')' | '(' expr ')'`
+    fn atom(&mut self) -> Result<Node, Error> {
+        let line = self.line();
+        let Some((t, _)) = self.tokens.get(self.pos).cloned() else {
+            return Err(Error::ParseError(
+                line,
+                "unexpected end of input".to_owned(),
+            ));
+        };
+        self.pos += 1;
+        match t {
+            Token::Num(v) => Ok(self.ctx.constant(v)),
+            Token::Symbol('(') => {
+                let out = self.expr()?;
+                self.expect(')')?;
+                Ok(out)
+            }
+            Token::Ident(name) if self.eat('(') => {
+                let mut args = vec![];
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                self.call(&name, &args, line)
+            }
+            Token::Ident(name) => self.name(&name),
+            t => Err(Error::ParseError(line, format!("unexpected {t}"))),
+        }
+    }
+
+    /// Looks up a named value
+    fn name(&mut self, name: &str) -> Result<Node, Error> {
+        let out = match name {
+            "x" => self.ctx.x(),
+            "y" => self.ctx.y(),
+            "z" => self.ctx.z(),
+            "w" => self.ctx.var(Var::W),
+            "pi" => self.ctx.constant(std::f64::consts::PI),
+            "e" => self.ctx.constant(std::f64::consts::E),
+            _ => match self.ctx.find_var(name) {
+                Some(v) => self.ctx.var(v),
+                None => return Err(Error::UnknownVariable(name.to_owned())),
+            },
+        };
+        Ok(out)
+    }
+
+    /// Calls a named function
+    fn call(
+        &mut self,
+        name: &str,
+        args: &[Node],
+        line: usize,
+    ) -> Result<Node, Error> {
+        let arity = match name {
+            "min" | "max" => {
+                if args.is_empty() {
+                    return Err(Error::ParseError(
+                        line,
+                        format!("`{name}` needs at least one argument"),
+                    ));
+                }
+                return if name == "min" {
+                    self.ctx.min_all(args)
+                } else {
+                    self.ctx.max_all(args)
+                };
+            }
+            "neg" | "abs" | "recip" | "sqrt" | "square" | "floor" | "ceil"
+            | "round" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan"
+            | "exp" | "ln" | "log" | "not" | "sinh" | "cosh" | "tanh"
+            | "cbrt" | "sign" => 1,
+            "atan2" | "pow" | "mod" | "rem" | "compare" => 2,
+            "clamp" => 3,
+            _ => return Err(Error::UnknownOpcode(name.to_owned())),
+        };
+        if args.len() != arity {
+            return Err(Error::ParseError(
+                line,
+                format!(
+                    "`{name}` takes {arity} argument{}, but {} were given",
+                    if arity == 1 { "" } else { "s" },
+                    args.len()
+                ),
+            ));
+        }
+        let ctx = &mut *self.ctx;
+        match name {
+            "neg" => ctx.neg(args[0]),
+            "abs" => ctx.abs(args[0]),
+            "recip" => ctx.recip(args[0]),
+            "sqrt" => ctx.sqrt(args[0]),
+            "square" => ctx.square(args[0]),
+            "floor" => ctx.floor(args[0]),
+            "ceil" => ctx.ceil(args[0]),
+            "round" => ctx.round(args[0]),
+            "sin" => ctx.sin(args[0]),
+            "cos" => ctx.cos(args[0]),
+            "tan" => ctx.tan(args[0]),
+            "asin" => ctx.asin(args[0]),
+            "acos" => ctx.acos(args[0]),
+            "atan" => ctx.atan(args[0]),
+            "exp" => ctx.exp(args[0]),
+            "ln" | "log" => ctx.ln(args[0]),
+            "not" => ctx.not(args[0]),
+            "sinh" => ctx.sinh(args[0]),
+            "cosh" => ctx.cosh(args[0]),
+            "tanh" => ctx.tanh(args[0]),
+            "cbrt" => ctx.cbrt(args[0]),
+            "sign" => ctx.sign(args[0]),
+            "atan2" => ctx.atan2(args[0], args[1]),
+            "pow" => self.ctx.pow(args[0], args[1]),
+            "mod" => ctx.modulo(args[0], args[1]),
+            "rem" => ctx.rem(args[0], args[1]),
+            "compare" => ctx.compare(args[0], args[1]),
+            "clamp" => ctx.clamp(args[0], args[1], args[2]),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(text: &str, x: f64, y: f64, z: f64) -> f64 {
+        let mut ctx = Context::new();
+        let n = parse(&mut ctx, text).unwrap();
+        ctx.eval_xyz(n, x, y, z).unwrap()
+    }
+
+    #[test]
+    fn parse_precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0, 0.0, 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0, 0.0, 0.0), 9.0);
+        assert_eq!(eval("8 - 3 - 2", 0.0, 0.0, 0.0), 3.0);
+        assert_eq!(eval("8 / 4 / 2", 0.0, 0.0, 0.0), 1.0);
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.0, 0.0, 0.0), 512.0);
+        assert_eq!(eval("-x ^ 2", 3.0, 0.0, 0.0), -9.0);
+        assert_eq!(eval("2 ^ -1", 0.0, 0.0, 0.0), 0.5);
+        assert_eq!(eval("x ^ 0.5", 4.0, 0.0, 0.0), 2.0);
+        assert_eq!(eval("--x", 3.0, 0.0, 0.0), 3.0);
+        assert_eq!(eval("+x * -y", 3.0, 2.0, 0.0), -6.0);
+        assert_eq!(eval("x % 2", 5.0, 0.0, 0.0), 1.0);
+        assert_eq!(eval("1.5e1 + 2E-1", 0.0, 0.0, 0.0), 15.2);
+        assert_eq!(eval("2 * pi", 0.0, 0.0, 0.0), std::f64::consts::TAU);
+    }
+
+    #[test]
+    fn parse_functions() {
+        assert_eq!(eval("sqrt(x*x + y*y) - 1", 3.0, 4.0, 0.0), 4.0);
+        assert_eq!(eval("min(x, y, z)", 3.0, 1.0, 2.0), 1.0);
+        assert_eq!(eval("max(x)", 3.0, 1.0, 2.0), 3.0);
+        assert_eq!(
+            eval("atan2(y, x)", 0.0, 1.0, 0.0),
+            std::f64::consts::FRAC_PI_2
+        );
+        assert_eq!(eval("clamp(x, 0, 1)", 3.0, 0.0, 0.0), 1.0);
+        assert_eq!(eval("pow(x, 3)", 2.0, 0.0, 0.0), 8.0);
+        assert_eq!(eval("log(exp(x))", 2.0, 0.0, 0.0), 2.0);
+        assert_eq!(eval("abs(sign(x))", -2.0, 0.0, 0.0), 1.0);
+        assert_eq!(
+            eval("sin(x)\n  + cos(y)", 1.0, 2.0, 0.0),
+            1f64.sin() + 2f64.cos()
+        );
+    }
+
+    #[test]
+    fn parse_vars() {
+        let mut ctx = Context::new();
+        let r = ctx.declare_var("radius", 0.5, None).unwrap();
+        let n = parse(&mut ctx, "x - radius").unwrap();
+        let v = ctx.get_var(r).unwrap();
+        let vars = [(Var::X, 2.0), (v, 0.5)].into_iter().collect();
+        assert_eq!(ctx.eval(n, &vars).unwrap(), 1.5);
+
+        let n = parse(&mut ctx, "w").unwrap();
+        assert_eq!(ctx.get_var(n).unwrap(), Var::W);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut ctx = Context::new();
+        for (text, line) in [
+            ("", 1),
+            ("1 +", 1),
+            ("(1 + 2", 1),
+            ("1 + 2)", 1),
+            ("x y", 1),
+            ("1 $ 2", 1),
+            ("1.2.3", 1),
+            ("sin(x, y)", 1),
+            ("min()", 1),
+            ("x +\n\n*", 3),
+        ] {
+            match parse(&mut ctx, text) {
+                Err(Error::ParseError(i, _)) => assert_eq!(i, line, "{text}"),
+                r => panic!("expected parse error for {text:?}, got {r:?}"),
+            }
+        }
+        assert!(matches!(
+            parse(&mut ctx, "radius * 2"),
+            Err(Error::UnknownVariable(s)) if s == "radius"
+        ));
+        assert!(matches!(
+            parse(&mut ctx, "foo(x)"),
+            Err(Error::UnknownOpcode(s)) if s == "foo"
+        ));
+
+        let deep = "(".repeat(10_000) + "x" + &")".repeat(10_000);
+        assert!(matches!(
+            parse(&mut ctx, &deep),
+            Err(Error::ParseError(1, _))
+        ));
+        let deep = "-".repeat(10_000) + "x";
+        assert!(matches!(
+            parse(&mut ctx, &deep),
+            Err(Error::ParseError(1, _))
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+//! Geometric queries on shapes (raycasting and closest-point projection)
+//!
+//! Every query runs under a [`QueryBudget`], which limits the number of
+//! evaluations; if the budget is exhausted (or the field is malformed, e.g.
+//! returning NaN), the query returns [`QueryResult::DidNotConverge`] rather
+//! than spinning forever.
+//!
+//! ```
+//! use fidget_core::{
+//!     context::Tree,
+//!     query::{QueryBudget, QueryResult, raycast},
+//!     shape::ShapeVars,
+//!     vm::VmShape,
+//! };
+//! use nalgebra::{Point3, Vector3};
+//!
+//! let (x, y, z) = Tree::axes();
+//! let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+//! let shape = VmShape::from(sphere);
+//!
+//! let hit = raycast(
+//!     &shape,
+//!     &ShapeVars::new(),
+//!     Point3::new(-3.0, 0.0, 0.0),
+//!     Vector3::new(1.0, 0.0, 0.0),
+//!     10.0,
+//!     1e-4,
+//!     &QueryBudget::default(),
+//! )?;
+//! let QueryResult::Found(hit) = hit else { panic!() };
+//! assert!((hit.t - 2.0).abs() < 1e-3);
+//! # Ok::<(), fidget_core::Error>(())
+//! ```
+use crate::{
+    Error,
+    eval::Function,
+    shape::{EzShape, Shape, ShapeVars},
+    types::{Grad, Interval},
+};
+use nalgebra::{Point3, Vector3};
+
+/// Limits on the amount of work done by a single query
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct QueryBudget {
+    /// Maximum number of evaluations (of any type)
+    pub max_evals: usize,
+}
+
+impl Default for QueryBudget {
+    fn default() -> Self {
+        Self { max_evals: 10_000 }
+    }
+}
+
+/// Result of a geometric query
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum QueryResult<T> {
+    /// The query succeeded
+    Found(T),
+    /// The query completed, but found nothing (e.g. a ray that misses)
+    NotFound,
+    /// The query ran out of budget or encountered an ill-behaved field
+    DidNotConverge,
+}
+
+/// Intersection between a ray and a shape's surface
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// Distance along the ray, in multiples of the ray direction
+    pub t: f32,
+    /// Position of the hit
+    pub pos: Point3<f32>,
+}
+
+/// Finds the first intersection between a ray and the shape
+///
+/// The ray is checked from `origin` to `origin + dir * max_t`, using interval
+/// arithmetic to skip empty regions (so this is robust to fields which aren't
+/// distance fields).  Segments are subdivided until they're shorter than
+/// `epsilon` (in units of `t`); a hit is reported at the start of the first
+/// such segment where the field is non-positive.
+///
+/// If the ray starts inside the shape, the hit is at `t = 0`.  Returns
+/// [`QueryResult::DidNotConverge`] if the budget is exhausted or if any
+/// candidate segment evaluates to NaN.
+pub fn raycast<F: Function>(
+    shape: &Shape<F>,
+    vars: &ShapeVars<f32>,
+    origin: Point3<f32>,
+    dir: Vector3<f32>,
+    max_t: f32,
+    epsilon: f32,
+    budget: &QueryBudget,
+) -> Result<QueryResult<RayHit>, Error> {
+    let i_tape = shape.ez_interval_tape();
+    let p_tape = shape.ez_point_tape();
+    let mut i_eval = Shape::<F>::new_interval_eval();
+    let mut p_eval = Shape::<F>::new_point_eval();
+
+    let mut evals = 0;
+    let mut saw_nan = false;
+
+    // Segments are pushed far-to-near, so the nearest is popped first
+    let mut todo = vec![(0.0, max_t)];
+    while let Some((t0, t1)) = todo.pop() {
+        if evals >= budget.max_evals {
+            return Ok(QueryResult::DidNotConverge);
+        }
+        evals += 1;
+
+        let a = origin + dir * t0;
+        let b = origin + dir * t1;
+        let span = |i: usize| Interval::new(a[i].min(b[i]), a[i].max(b[i]));
+        let (i, _) = i_eval.eval_v(&i_tape, span(0), span(1), span(2), vars)?;
+        if i.lower() > 0.0 {
+            continue;
+        } else if i.upper() < 0.0 {
+            return Ok(QueryResult::Found(RayHit { t: t0, pos: a }));
+        }
+
+        if t1 - t0 > epsilon {
+            let mid = (t0 + t1) / 2.0;
+            todo.push((mid, t1));
+            todo.push((t0, mid));
+            continue;
+        }
+
+        // We've reached the minimum segment size; look for a sign change
+        for (t, p) in [(t0, a), (t1, b)] {
+            if evals >= budget.max_evals {
+                return Ok(QueryResult::DidNotConverge);
+            }
+            evals += 1;
+            let (v, _) = p_eval.eval_v(&p_tape, p.x, p.y, p.z, vars)?;
+            if v.is_nan() {
+                saw_nan = true;
+            } else if v <= 0.0 {
+                return Ok(QueryResult::Found(RayHit { t, pos: p }));
+            }
+        }
+    }
+
+    Ok(if saw_nan {
+        QueryResult::DidNotConverge
+    } else {
+        QueryResult::NotFound
+    })
+}
+
+/// Projects a point onto the shape's surface
+///
+/// This uses Newton's method along the field's gradient, stopping when the
+/// field's magnitude is below `epsilon`.  For a distance field, this finds
+/// the closest point on the surface; for other fields, it finds a nearby
+/// surface point.
+///
+/// Returns [`QueryResult::DidNotConverge`] if the budget is exhausted, or if
+/// the field or its gradient is non-finite or zero.
+pub fn closest_point<F: Function>(
+    shape: &Shape<F>,
+    vars: &ShapeVars<f32>,
+    start: Point3<f32>,
+    epsilon: f32,
+    budget: &QueryBudget,
+) -> Result<QueryResult<Point3<f32>>, Error> {
+    let tape = shape.ez_grad_slice_tape();
+    let mut eval = Shape::<F>::new_grad_slice_eval();
+
+    let mut p = start;
+    for _ in 0..budget.max_evals {
+        let x = Grad::new(p.x, 1.0, 0.0, 0.0);
+        let y = Grad::new(p.y, 0.0, 1.0, 0.0);
+        let z = Grad::new(p.z, 0.0, 0.0, 1.0);
+        let g = eval.eval_v(&tape, &[x], &[y], &[z], vars)?[0];
+
+        if !g.v.is_finite() {
+            return Ok(QueryResult::DidNotConverge);
+        } else if g.v.abs() < epsilon {
+            return Ok(QueryResult::Found(p));
+        }
+        let d = Vector3::new(g.dx, g.dy, g.dz);
+        let norm2 = d.norm_squared();
+        if !norm2.is_finite() || norm2 == 0.0 {
+            return Ok(QueryResult::DidNotConverge);
+        }
+        p -= d * (g.v / norm2);
+    }
+    Ok(QueryResult::DidNotConverge)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Tree, vm::VmShape};
+
+    fn sphere() -> VmShape {
+        let (x, y, z) = Tree::axes();
+        VmShape::from((x.square() + y.square() + z.square()).sqrt() - 1.0)
+    }
+
+    #[test]
+    fn raycast_sphere() {
+        let shape = sphere();
+        let vars = ShapeVars::new();
+        let budget = QueryBudget::default();
+        let r = raycast(
+            &shape,
+            &vars,
+            Point3::new(0.0, -5.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            10.0,
+            1e-4,
+            &budget,
+        )
+        .unwrap();
+        let QueryResult::Found(hit) = r else {
+            panic!("unexpected result {r:?}")
+        };
+        assert!((hit.t - 4.0).abs() < 1e-3);
+        assert!((hit.pos.y + 1.0).abs() < 1e-3);
+
+        // Miss
+        let r = raycast(
+            &shape,
+            &vars,
+            Point3::new(0.0, -5.0, 2.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            10.0,
+            1e-4,
+            &budget,
+        )
+        .unwrap();
+        assert_eq!(r, QueryResult::NotFound);
+
+        // Starting inside the shape
+        let r = raycast(
+            &shape,
+            &vars,
+            Point3::origin(),
+            Vector3::new(0.0, 1.0, 0.0),
+            10.0,
+            1e-4,
+            &budget,
+        )
+        .unwrap();
+        assert!(matches!(r, QueryResult::Found(RayHit { t: 0.0, .. })));
+
+        // Tiny budget
+        let r = raycast(
+            &shape,
+            &vars,
+            Point3::new(0.0, -5.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            10.0,
+            1e-4,
+            &QueryBudget { max_evals: 5 },
+        )
+        .unwrap();
+        assert_eq!(r, QueryResult::DidNotConverge);
+    }
+
+    #[test]
+    fn raycast_nan() {
+        // sqrt(-1) everywhere
+        let shape = VmShape::from((Tree::x().square() + 1.0).neg().sqrt());
+        let r = raycast(
+            &shape,
+            &ShapeVars::new(),
+            Point3::new(0.0, -5.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            10.0,
+            1e-2,
+            &QueryBudget::default(),
+        )
+        .unwrap();
+        assert_eq!(r, QueryResult::DidNotConverge);
+    }
+
+    #[test]
+    fn closest_point_sphere() {
+        let shape = sphere();
+        let r = closest_point(
+            &shape,
+            &ShapeVars::new(),
+            Point3::new(2.0, 1.0, 0.5),
+            1e-5,
+            &QueryBudget::default(),
+        )
+        .unwrap();
+        let QueryResult::Found(p) = r else {
+            panic!("unexpected result {r:?}")
+        };
+        assert!((p.coords.norm() - 1.0).abs() < 1e-4);
+        let dir = Vector3::new(2.0, 1.0, 0.5).normalize();
+        assert!((p.coords - dir).norm() < 1e-4);
+
+        // Zero gradient at the origin
+        let r = closest_point(
+            &shape,
+            &ShapeVars::new(),
+            Point3::origin(),
+            1e-5,
+            &QueryBudget::default(),
+        )
+        .unwrap();
+        assert_eq!(r, QueryResult::DidNotConverge);
+    }
+}
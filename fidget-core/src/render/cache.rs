@@ -0,0 +1,128 @@
+//! Caching of root tapes across renders
+use super::RenderHandle;
+use crate::{
+    eval::Function,
+    shape::{Shape, Transformed},
+};
+use nalgebra::Matrix4;
+use std::collections::HashMap;
+
+/// Cache of root render tapes, keyed by function identity
+///
+/// Tapes (including JIT-compiled code) depend only on a shape's expression:
+/// transforms are applied to the inputs during evaluation, and variable values
+/// are passed in separately.  When only the transform (e.g. panning, zooming,
+/// or rotating the view) or [`ShapeVars`](crate::shape::ShapeVars) (e.g.
+/// parameter tweaks or animation time) change between calls, the root tapes
+/// remain valid.  Keeping a `TapeCache` across calls lets renderers and mesher
+/// skip root tape construction entirely and re-evaluate with the new transform
+/// and variable values.
+///
+/// Shapes are identified by [`Function::id`]; the cache holds a clone of each
+/// shape, so identifiers can't be reused while an entry is alive.  Shapes
+/// without an identifier aren't cached, so their tapes are rebuilt by every
+/// call.  Simplified tapes are not cached, because they depend on variable
+/// values (through interval evaluation).
+pub struct TapeCache<F: Function> {
+    handles: HashMap<usize, RenderHandle<F>>,
+}
+
+impl<F: Function> Default for TapeCache<F> {
+    fn default() -> Self {
+        Self {
+            handles: HashMap::new(),
+        }
+    }
+}
+
+impl<F: Function> TapeCache<F> {
+    /// Builds a new empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a render handle for the given shape and transform
+    ///
+    /// The first call for a given shape builds the root interval, float
+    /// slice, and gradient slice tapes; later calls (with any transform)
+    /// return a handle which shares them.
+    pub fn get(
+        &mut self,
+        shape: &Shape<F>,
+        mat: Matrix4<f32>,
+    ) -> RenderHandle<F, Transformed> {
+        let build = || {
+            let mut rh = RenderHandle::new(shape.clone());
+            let mut storage = vec![];
+            let _ = rh.i_tape(&mut storage);
+            let _ = rh.f_tape(&mut storage);
+            let _ = rh.g_tape(&mut storage);
+            rh
+        };
+        if let Some(id) = shape.inner().id() {
+            self.handles
+                .entry(id)
+                .or_insert_with(build)
+                .with_transform(mat)
+        } else {
+            build().with_transform(mat)
+        }
+    }
+
+    /// Returns the number of cached handles
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Checks whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Removes all cached handles
+    pub fn clear(&mut self) {
+        self.handles.clear()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Tree, vm::VmShape};
+
+    #[test]
+    fn tape_cache() {
+        let (x, y, _) = Tree::axes();
+        let a = VmShape::from(x.clone() + y.clone());
+        let b = VmShape::from(x * y);
+        let mut cache = TapeCache::new();
+        assert!(cache.is_empty());
+
+        let mat = Matrix4::new_scaling(2.0);
+        let h1 = cache.get(&a, mat);
+        assert_eq!(cache.len(), 1);
+        let h2 = cache.get(&a.clone(), mat);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(h1.shape().inner().id(), h2.shape().inner().id());
+
+        // Changing the transform reuses the cached tapes
+        let h3 = cache.get(&a, Matrix4::identity());
+        assert_eq!(cache.len(), 1);
+        assert_eq!(h1.shape().inner().id(), h3.shape().inner().id());
+        assert_eq!(h1.shape().transform(), mat);
+        assert_eq!(h3.shape().transform(), Matrix4::identity());
+
+        // Every root tape is built up front, so none are rebuilt per call
+        for h in [&h1, &h3] {
+            assert!(h.i_tape.is_some());
+            assert!(h.f_tape.is_some());
+            assert!(h.g_tape.is_some());
+        }
+
+        cache.get(&b, mat);
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//! Camera type shared between 2D and 3D rendering
+use super::VoxelSize;
+use nalgebra::{Matrix4, Point3, UnitQuaternion, Vector3};
+
+/// Projection used by a [`Camera`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Orthographic projection (parallel rays along the `Z` axis)
+    #[default]
+    Orthographic,
+
+    /// Perspective projection with the given strength
+    ///
+    /// The strength is applied in world coordinates: a point at world-space
+    /// depth `z` is scaled by `1 / (1 + strength * z)`, so geometry closer to
+    /// the viewer (at `+z`) appears larger.  A strength of `0.0` is equivalent
+    /// to an orthographic projection; `0.3` is a reasonable default.
+    Perspective(f32),
+}
+
+/// Camera mapping between screen, world, and model coordinates
+///
+/// Screen and world coordinates are as described in
+/// [`RegionSize`](super::RegionSize): world coordinates span `±1` on the
+/// shorter screen axis.  The world-to-model transform applies the
+/// [`projection`](Self::projection), a uniform [`scale`](Self::scale), the
+/// [`rotation`](Self::rotation), and finally a translation to
+/// [`center`](Self::center).
+///
+/// The same camera can be used for 2D and 3D rendering; in 2D, only the
+/// width and height of [`size`](Self::size) are used, and the image is
+/// rendered on the world-space `z = 0` plane (where the projection has no
+/// effect).
+///
+/// It may also be used to convert between screen and model coordinates, e.g.
+/// for picking:
+///
+/// ```
+/// # use fidget_core::render::{Camera, VoxelSize};
+/// # use nalgebra::{Point3, Vector3};
+/// let camera = Camera {
+///     size: VoxelSize::new(100, 100, 100),
+///     center: Vector3::new(1.0, 2.0, 3.0),
+///     scale: 2.0,
+///     ..Camera::default()
+/// };
+/// // The middle of the screen is at the camera's center
+/// let p = camera.screen_to_model_point(Point3::new(50.0, 49.0, 50.0));
+/// assert_eq!(p, Point3::new(1.0, 2.0, 3.0));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// Screen size, in pixels (and voxels, for the depth axis)
+    pub size: VoxelSize,
+
+    /// Model-space position at the center of the view
+    pub center: Vector3<f32>,
+
+    /// Half-size of the view (along its shorter axis) in model units
+    pub scale: f32,
+
+    /// Rotation from world to model orientation
+    pub rotation: UnitQuaternion<f32>,
+
+    /// Projection from world to view coordinates
+    pub projection: Projection,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            size: VoxelSize::from(512),
+            center: Vector3::zeros(),
+            scale: 1.0,
+            rotation: UnitQuaternion::identity(),
+            projection: Projection::Orthographic,
+        }
+    }
+}
+
+impl Camera {
+    /// Returns the projection matrix, applied in world coordinates
+    pub fn projection_mat(&self) -> Matrix4<f32> {
+        let mut out = Matrix4::identity();
+        if let Projection::Perspective(s) = self.projection {
+            out[(3, 2)] = s;
+        }
+        out
+    }
+
+    /// Returns the world-to-model transform matrix
+    pub fn world_to_model(&self) -> Matrix4<f32> {
+        Matrix4::new_translation(&self.center)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_scaling(self.scale)
+            * self.projection_mat()
+    }
+
+    /// Returns the screen-to-world transform matrix
+    pub fn screen_to_world(&self) -> Matrix4<f32> {
+        self.size.screen_to_world()
+    }
+
+    /// Returns the combined screen-to-model transform matrix
+    ///
+    /// If the camera uses a perspective projection, the resulting matrix is
+    /// projective and points must be divided by their `w` coordinate.
+    pub fn screen_to_model(&self) -> Matrix4<f32> {
+        self.world_to_model() * self.screen_to_world()
+    }
+
+    /// Converts a point from screen to model coordinates
+    pub fn screen_to_model_point(&self, p: Point3<f32>) -> Point3<f32> {
+        self.screen_to_model().transform_point(&p)
+    }
+
+    /// Converts a point from model to screen coordinates
+    ///
+    /// Returns `None` if the camera's transform is singular (e.g. its scale is
+    /// zero).
+    pub fn model_to_screen_point(&self, p: Point3<f32>) -> Option<Point3<f32>> {
+        let mat = self.screen_to_model().try_inverse()?;
+        Some(mat.transform_point(&p))
+    }
+
+    /// Returns the model-space ray under the given screen position
+    ///
+    /// The ray is returned as a pair of points, at the front (`z = depth`) and
+    /// back (`z = 0`) of the screen volume.  This is useful for picking, e.g.
+    /// in combination with the depth buffer from a 3D render.
+    pub fn screen_ray(&self, x: f32, y: f32) -> (Point3<f32>, Point3<f32>) {
+        let front = Point3::new(x, y, self.size.depth() as f32);
+        let back = Point3::new(x, y, 0.0);
+        (
+            self.screen_to_model_point(front),
+            self.screen_to_model_point(back),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn camera_roundtrip() {
+        let camera = Camera {
+            size: VoxelSize::new(200, 100, 50),
+            center: Vector3::new(1.0, -2.0, 0.5),
+            scale: 3.0,
+            rotation: UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3),
+            projection: Projection::Perspective(0.3),
+        };
+        for p in [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(150.0, 25.0, 40.0),
+            Point3::new(199.0, 99.0, 10.0),
+        ] {
+            let m = camera.screen_to_model_point(p);
+            let q = camera.model_to_screen_point(m).unwrap();
+            assert!((p - q).norm() < 1e-3, "{p} => {m} => {q}");
+        }
+    }
+
+    #[test]
+    fn camera_orthographic() {
+        let camera = Camera {
+            size: VoxelSize::new(100, 100, 100),
+            scale: 2.0,
+            ..Camera::default()
+        };
+        let (front, back) = camera.screen_ray(50.0, 49.0);
+        assert_eq!(front, Point3::new(0.0, 0.0, 2.0));
+        assert_eq!(back, Point3::new(0.0, 0.0, -2.0));
+
+        // Rotating 90° about Y maps world Z to model X
+        let camera = Camera {
+            rotation: UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                std::f32::consts::FRAC_PI_2,
+            ),
+            ..camera
+        };
+        let (front, _back) = camera.screen_ray(50.0, 49.0);
+        assert!((front - Point3::new(2.0, 0.0, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn camera_perspective() {
+        let camera = Camera {
+            size: VoxelSize::new(100, 100, 100),
+            projection: Projection::Perspective(0.5),
+            ..Camera::default()
+        };
+        // The corner of the screen is closer to the center in front
+        let (front, back) = camera.screen_ray(0.0, 99.0);
+        assert!(front.x.abs() < back.x.abs());
+        assert!(front.y.abs() < back.y.abs());
+
+        // The world-space z = 0 plane is unaffected by perspective
+        let p = camera.screen_to_model_point(Point3::new(0.0, 99.0, 50.0));
+        assert!((p - Point3::new(-1.0, -1.0, 0.0)).norm() < 1e-6);
+    }
+}
@@ -7,7 +7,9 @@ use std::sync::{
 /// Thread pool to use for multithreaded rendering
 ///
 /// Most users will use the global Rayon pool, but it's possible to provide your
-/// own as well.
+/// own as well (e.g. to limit parallelism when embedding Fidget in a larger
+/// application).  Rendering never spawns threads of its own: all work is
+/// dispatched onto the selected pool.
 pub enum ThreadPool {
     /// User-provided pool
     Custom(rayon::ThreadPool),
@@ -16,6 +18,20 @@ pub enum ThreadPool {
 }
 
 impl ThreadPool {
+    /// Builds a custom pool with the given number of threads
+    ///
+    /// If `n` is 0, Rayon picks the number of threads (typically one per CPU).
+    ///
+    /// ```
+    /// # use fidget_core::render::ThreadPool;
+    /// let pool = ThreadPool::with_threads(3).unwrap();
+    /// assert_eq!(pool.thread_count(), 3);
+    /// ```
+    pub fn with_threads(n: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let p = rayon::ThreadPoolBuilder::new().num_threads(n).build()?;
+        Ok(ThreadPool::Custom(p))
+    }
+
     /// Runs a function across the thread pool
     pub fn run<F: FnOnce() -> V + Send, V: Send>(&self, f: F) -> V {
         match self {
@@ -33,6 +49,12 @@ impl ThreadPool {
     }
 }
 
+impl From<rayon::ThreadPool> for ThreadPool {
+    fn from(p: rayon::ThreadPool) -> Self {
+        ThreadPool::Custom(p)
+    }
+}
+
 /// Token to cancel an in-progress operation
 #[derive(Clone, Default)]
 pub struct CancelToken(Arc<AtomicBool>);
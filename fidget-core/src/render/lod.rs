@@ -0,0 +1,297 @@
+//! Precomputed level-of-detail shape hierarchy
+use crate::{
+    Error,
+    eval::Function,
+    shape::{Shape, ShapeTracingEval, ShapeVars},
+    types::Interval,
+};
+use nalgebra::Point3;
+
+/// Contents of a single cell in a [`LodTree`]
+pub enum LodCell<F> {
+    /// The cell is entirely outside the shape
+    Empty,
+    /// The cell is entirely inside the shape
+    Full,
+    /// The cell contains the surface, with a shape simplified for the cell
+    Ambiguous(Shape<F>),
+}
+
+impl<F: Clone> Clone for LodCell<F> {
+    fn clone(&self) -> Self {
+        match self {
+            LodCell::Empty => LodCell::Empty,
+            LodCell::Full => LodCell::Full,
+            LodCell::Ambiguous(s) => LodCell::Ambiguous(s.clone()),
+        }
+    }
+}
+
+impl<F> LodCell<F> {
+    /// Returns the cell's simplified shape, if it contains the surface
+    pub fn shape(&self) -> Option<&Shape<F>> {
+        match self {
+            LodCell::Ambiguous(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct LodNode<F> {
+    cell: LodCell<F>,
+    /// Index of the first of eight contiguous children, if subdivided
+    children: Option<usize>,
+}
+
+/// Precomputed hierarchy of simplified shapes over a fixed octree
+///
+/// The tree is built once (running interval evaluation and simplification for
+/// every cell), then queried by level and cell position.  This lets real-time
+/// consumers look up a region-appropriate shape (e.g. to build a
+/// [`RenderHandle`](super::RenderHandle) or evaluation tapes) without running
+/// interval arithmetic at runtime.
+///
+/// Level 0 is the root cell, covering the full bounds; level `n` divides the
+/// bounds into `2ⁿ` cells on each axis.  Cells which are [`LodCell::Empty`] or
+/// [`LodCell::Full`] are not subdivided.
+///
+/// Simplification depends on variable values, so the tree is only valid for
+/// the [`ShapeVars`] used to build it.
+pub struct LodTree<F> {
+    bounds: [Interval; 3],
+    depth: usize,
+    nodes: Vec<LodNode<F>>,
+}
+
+impl<F: Function> LodTree<F> {
+    /// Builds a tree to the given depth
+    ///
+    /// `depth` is the deepest level which is stored; a depth of 0 stores only
+    /// the root cell.
+    pub fn build(
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        bounds: [Interval; 3],
+        depth: usize,
+    ) -> Result<Self, Error> {
+        let mut builder = LodBuilder {
+            eval: Shape::<F>::new_interval_eval(),
+            workspace: Default::default(),
+            nodes: vec![],
+            depth,
+        };
+        builder.nodes.push(LodNode {
+            cell: LodCell::Empty,
+            children: None,
+        });
+        builder.recurse(shape, vars, 0, bounds, 0)?;
+        Ok(Self {
+            bounds,
+            depth,
+            nodes: builder.nodes,
+        })
+    }
+}
+
+impl<F> LodTree<F> {
+    /// Returns the bounds of the root cell
+    pub fn bounds(&self) -> [Interval; 3] {
+        self.bounds
+    }
+
+    /// Returns the deepest level stored in the tree
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of cells stored in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks whether the tree is empty (which is never true)
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the cell at the given level and integer position
+    ///
+    /// Positions range from `0` to `2^level - 1` on each axis.  Returns `None`
+    /// if the level is deeper than the tree, the position is out of range, or
+    /// the cell was not subdivided (because an ancestor is empty or full); use
+    /// [`find`](Self::find) to get the deepest available cell instead.
+    pub fn cell(&self, level: usize, pos: [usize; 3]) -> Option<&LodCell<F>> {
+        let (found, cell) = self.walk(level, pos)?;
+        (found == level).then_some(cell)
+    }
+
+    /// Returns the deepest cell containing the given point, up to `level`
+    ///
+    /// The result is a tuple of `(level, cell)`, where the level may be
+    /// shallower than requested if the cell was not subdivided.  Returns `None`
+    /// if the point is outside of the tree's bounds.
+    pub fn find(
+        &self,
+        p: Point3<f32>,
+        level: usize,
+    ) -> Option<(usize, &LodCell<F>)> {
+        let level = level.min(self.depth);
+        let n = 1usize << level;
+        let mut pos = [0; 3];
+        for (i, b) in self.bounds.iter().enumerate() {
+            if p[i] < b.lower() || p[i] > b.upper() {
+                return None;
+            }
+            let f = (p[i] - b.lower()) / b.width();
+            pos[i] = ((f * n as f32) as usize).min(n - 1);
+        }
+        self.walk(level, pos)
+    }
+
+    /// Returns the bounds of the cell at the given level and position
+    pub fn cell_bounds(&self, level: usize, pos: [usize; 3]) -> [Interval; 3] {
+        let n = (1usize << level) as f32;
+        std::array::from_fn(|i| {
+            let b = self.bounds[i];
+            let lo = b.lower() + b.width() * pos[i] as f32 / n;
+            let hi = b.lower() + b.width() * (pos[i] + 1) as f32 / n;
+            Interval::new(lo, hi)
+        })
+    }
+
+    /// Walks down the tree towards a cell, returning the deepest one found
+    fn walk(
+        &self,
+        level: usize,
+        pos: [usize; 3],
+    ) -> Option<(usize, &LodCell<F>)> {
+        if level > self.depth || pos.iter().any(|p| *p >= 1 << level) {
+            return None;
+        }
+        let mut node = &self.nodes[0];
+        for d in 0..level {
+            let Some(c) = node.children else {
+                return Some((d, &node.cell));
+            };
+            let shift = level - d - 1;
+            let i = ((pos[0] >> shift) & 1)
+                | (((pos[1] >> shift) & 1) << 1)
+                | (((pos[2] >> shift) & 1) << 2);
+            node = &self.nodes[c + i];
+        }
+        Some((level, &node.cell))
+    }
+}
+
+struct LodBuilder<F: Function> {
+    eval: ShapeTracingEval<F::IntervalEval>,
+    workspace: F::Workspace,
+    nodes: Vec<LodNode<F>>,
+    depth: usize,
+}
+
+impl<F: Function> LodBuilder<F> {
+    fn recurse(
+        &mut self,
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        index: usize,
+        bounds: [Interval; 3],
+        level: usize,
+    ) -> Result<(), Error> {
+        let tape = shape.interval_tape(Default::default());
+        let (i, trace) = self
+            .eval
+            .eval_v(&tape, bounds[0], bounds[1], bounds[2], vars)?;
+        let shape = if i.upper() < 0.0 {
+            self.nodes[index].cell = LodCell::Full;
+            return Ok(());
+        } else if i.lower() > 0.0 {
+            self.nodes[index].cell = LodCell::Empty;
+            return Ok(());
+        } else if let Some(trace) = trace {
+            shape.simplify(trace, Default::default(), &mut self.workspace)?
+        } else {
+            shape.clone()
+        };
+        self.nodes[index].cell = LodCell::Ambiguous(shape.clone());
+
+        if level < self.depth {
+            let first = self.nodes.len();
+            self.nodes[index].children = Some(first);
+            for _ in 0..8 {
+                self.nodes.push(LodNode {
+                    cell: LodCell::Empty,
+                    children: None,
+                });
+            }
+            for i in 0..8 {
+                let child = std::array::from_fn(|axis| {
+                    let b = bounds[axis];
+                    let mid = b.midpoint();
+                    if (i >> axis) & 1 == 0 {
+                        Interval::new(b.lower(), mid)
+                    } else {
+                        Interval::new(mid, b.upper())
+                    }
+                });
+                self.recurse(&shape, vars, first + i, child, level + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Tree, vm::VmShape};
+
+    #[test]
+    fn lod_tree() {
+        let (x, y, z) = Tree::axes();
+        let a = ((x.clone() - 0.5).square() + y.square() + z.square()).sqrt()
+            - 0.25;
+        let b = ((x + 0.5).square() + y.square() + z.square()).sqrt() - 0.25;
+        let shape = VmShape::from(a.min(b));
+        let bounds = [Interval::new(-1.0, 1.0); 3];
+        let tree =
+            LodTree::build(&shape, &ShapeVars::new(), bounds, 3).unwrap();
+        assert_eq!(tree.depth(), 3);
+
+        let root = tree.cell(0, [0, 0, 0]).unwrap().shape().unwrap();
+        assert_eq!(root.size(), shape.size());
+
+        // Cells on either side of the X axis are simplified to a single sphere
+        let (level, cell) = tree.find(Point3::new(0.5, 0.0, 0.0), 3).unwrap();
+        assert_eq!(level, 3);
+        let s = cell.shape().unwrap();
+        assert!(s.size() < shape.size());
+
+        // Corners are empty, and not subdivided
+        let (level, cell) =
+            tree.find(Point3::new(0.95, 0.95, 0.95), 3).unwrap();
+        assert_eq!(level, 2);
+        assert!(matches!(cell, LodCell::Empty));
+        assert!(tree.cell(3, [7, 7, 7]).is_none());
+        assert!(matches!(tree.cell(2, [3, 3, 3]), Some(LodCell::Empty)));
+
+        // Out-of-bounds queries
+        assert!(tree.find(Point3::new(2.0, 0.0, 0.0), 3).is_none());
+        assert!(tree.cell(1, [2, 0, 0]).is_none());
+        assert!(tree.cell(4, [0, 0, 0]).is_none());
+
+        // Simplified shapes match the original within their cells
+        let mut eval = VmShape::new_point_eval();
+        let full = shape.point_tape(Default::default());
+        let simple = s.point_tape(Default::default());
+        let b = tree.cell_bounds(3, [6, 4, 4]);
+        for p in [0.0, 0.3, 0.7, 1.0] {
+            let px = b[0].lower() + b[0].width() * p;
+            let py = b[1].lower() + b[1].width() * p;
+            let (v1, _) = eval.eval(&full, px, py, 0.0).unwrap();
+            let (v2, _) = eval.eval(&simple, px, py, 0.0).unwrap();
+            assert_eq!(v1, v2);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-stage timing and counters for render and meshing pipelines
+use std::time::{Duration, Instant};
+
+/// A stage in a render or meshing pipeline
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Stage {
+    /// Interval evaluation of a tile or cell
+    IntervalEval,
+    /// Tape simplification based on an interval trace
+    Simplify,
+    /// Fetching an evaluator tape from a [`RenderHandle`](super::RenderHandle)
+    ///
+    /// Tapes are built lazily, so this includes tape construction (e.g. JIT
+    /// compilation); fetching a cached tape is nearly free.
+    TapeBuild,
+    /// Float or gradient slice evaluation of individual pixels or voxels
+    Fill,
+}
+
+/// Accumulated time and call count for a single [`Stage`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StageMetrics {
+    /// Total time spent in this stage
+    pub time: Duration,
+    /// Number of times that this stage was entered
+    pub count: usize,
+}
+
+impl StageMetrics {
+    fn merge(&mut self, other: &StageMetrics) {
+        self.time += other.time;
+        self.count += other.count;
+    }
+}
+
+/// Metrics for every [`Stage`], accumulated on a single thread
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ThreadMetrics {
+    /// See [`Stage::IntervalEval`]
+    pub interval_eval: StageMetrics,
+    /// See [`Stage::Simplify`]
+    pub simplify: StageMetrics,
+    /// See [`Stage::TapeBuild`]
+    pub tape_build: StageMetrics,
+    /// See [`Stage::Fill`]
+    pub fill: StageMetrics,
+}
+
+impl ThreadMetrics {
+    /// Accumulates another set of metrics into this one
+    pub fn merge(&mut self, other: &ThreadMetrics) {
+        for s in [
+            Stage::IntervalEval,
+            Stage::Simplify,
+            Stage::TapeBuild,
+            Stage::Fill,
+        ] {
+            self[s].merge(&other[s]);
+        }
+    }
+}
+
+impl std::ops::Index<Stage> for ThreadMetrics {
+    type Output = StageMetrics;
+    fn index(&self, s: Stage) -> &StageMetrics {
+        match s {
+            Stage::IntervalEval => &self.interval_eval,
+            Stage::Simplify => &self.simplify,
+            Stage::TapeBuild => &self.tape_build,
+            Stage::Fill => &self.fill,
+        }
+    }
+}
+
+impl std::ops::IndexMut<Stage> for ThreadMetrics {
+    fn index_mut(&mut self, s: Stage) -> &mut StageMetrics {
+        match s {
+            Stage::IntervalEval => &mut self.interval_eval,
+            Stage::Simplify => &mut self.simplify,
+            Stage::TapeBuild => &mut self.tape_build,
+            Stage::Fill => &mut self.fill,
+        }
+    }
+}
+
+/// Structured timing and counters returned from a render or meshing call
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PipelineMetrics {
+    /// Wall-clock time for the entire call
+    pub wall_time: Duration,
+
+    /// Per-thread metrics
+    ///
+    /// When running in a thread pool, this is indexed by the Rayon thread
+    /// index; otherwise, it contains a single item.  Threads which did no work
+    /// have all-zero metrics.
+    pub threads: Vec<ThreadMetrics>,
+}
+
+impl PipelineMetrics {
+    /// Returns metrics summed across all threads
+    pub fn total(&self) -> ThreadMetrics {
+        let mut out = ThreadMetrics::default();
+        for t in &self.threads {
+            out.merge(t);
+        }
+        out
+    }
+
+    /// Accumulates metrics from the given thread
+    pub fn record(&mut self, thread: usize, m: &ThreadMetrics) {
+        if self.threads.len() <= thread {
+            self.threads.resize(thread + 1, ThreadMetrics::default());
+        }
+        self.threads[thread].merge(m);
+    }
+
+    /// Accumulates metrics for the current thread
+    ///
+    /// This uses [`rayon::current_thread_index`] if called from within a Rayon
+    /// thread pool, or index 0 otherwise.
+    pub fn record_current(&mut self, m: &ThreadMetrics) {
+        self.record(rayon::current_thread_index().unwrap_or(0), m)
+    }
+}
+
+/// Lightweight recorder used by render and meshing workers
+///
+/// When disabled, recording is a no-op and the clock is never read.
+#[derive(Debug, Default)]
+pub struct MetricsRecorder(Option<ThreadMetrics>);
+
+impl MetricsRecorder {
+    /// Builds a new recorder, which is a no-op unless `enabled` is true
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled.then(ThreadMetrics::default))
+    }
+
+    /// Checks whether this recorder is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns a start time (if enabled), to be passed to [`Self::record`]
+    #[inline]
+    pub fn start(&self) -> Option<Instant> {
+        self.0.as_ref().map(|_| Instant::now())
+    }
+
+    /// Records a single pass through the given stage
+    #[inline]
+    pub fn record(&mut self, stage: Stage, start: Option<Instant>) {
+        if let (Some(m), Some(start)) = (self.0.as_mut(), start) {
+            let s = &mut m[stage];
+            s.time += start.elapsed();
+            s.count += 1;
+        }
+    }
+
+    /// Takes accumulated metrics, resetting the recorder
+    ///
+    /// Returns `None` if the recorder is disabled
+    pub fn take(&mut self) -> Option<ThreadMetrics> {
+        self.0.as_mut().map(std::mem::take)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recorder_disabled() {
+        let mut r = MetricsRecorder::new(false);
+        let t = r.start();
+        assert!(t.is_none());
+        r.record(Stage::Fill, t);
+        assert!(r.take().is_none());
+    }
+
+    #[test]
+    fn pipeline_total() {
+        let mut r = MetricsRecorder::new(true);
+        let t = r.start();
+        r.record(Stage::Fill, t);
+        r.record(Stage::Fill, t);
+        r.record(Stage::Simplify, t);
+        let m = r.take().unwrap();
+        assert_eq!(m.fill.count, 2);
+        assert_eq!(m.simplify.count, 1);
+        assert_eq!(r.take().unwrap(), ThreadMetrics::default());
+
+        let mut p = PipelineMetrics::default();
+        p.record(2, &m);
+        p.record(0, &m);
+        assert_eq!(p.threads.len(), 3);
+        assert_eq!(p.threads[1], ThreadMetrics::default());
+        let total = p.total();
+        assert_eq!(total.fill.count, 4);
+        assert_eq!(total.simplify.count, 2);
+        assert_eq!(total.interval_eval.count, 0);
+    }
+}
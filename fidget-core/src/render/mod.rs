@@ -2,13 +2,24 @@
 use crate::{
     Error,
     eval::{BulkEvaluator, Function, Trace, TracingEvaluator},
-    shape::{Shape, ShapeTape},
+    shape::{Shape, ShapeTape, Transformed},
 };
+use nalgebra::Matrix4;
 
+mod cache;
+mod camera;
 mod config;
+mod lod;
+mod metrics;
 mod region;
 
+pub use cache::TapeCache;
+pub use camera::{Camera, Projection};
 pub use config::{CancelToken, ThreadPool};
+pub use lod::{LodCell, LodTree};
+pub use metrics::{
+    MetricsRecorder, PipelineMetrics, Stage, StageMetrics, ThreadMetrics,
+};
 pub use region::{ImageSize, RegionSize, VoxelSize};
 
 /// A `RenderHandle` contains lazily-populated tapes for rendering
@@ -54,6 +65,12 @@ impl<F: Function, T> RenderHandle<F, T> {
         }
     }
 
+    /// Borrows the handle's shape
+    #[inline]
+    pub fn shape(&self) -> &Shape<F, T> {
+        &self.shape
+    }
+
     /// Returns a tape for tracing interval evaluation
     #[inline]
     pub fn i_tape(
@@ -152,6 +169,12 @@ impl<F: Function, T> RenderHandle<F, T> {
         }
     }
 
+    /// Returns the size of the underlying shape (see [`Shape::size`])
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.shape.size()
+    }
+
     /// Recycles the entire handle into the given storage vectors
     #[inline]
     pub fn recycle(
@@ -179,6 +202,29 @@ impl<F: Function, T> RenderHandle<F, T> {
     }
 }
 
+impl<F: Function> RenderHandle<F> {
+    /// Returns a handle for the shape with the given transform applied
+    ///
+    /// Tapes don't depend on the transform, so any tapes which have already
+    /// been populated in this handle are reused (rather than being rebuilt).
+    /// This makes it cheap to render the same shape from many viewpoints.
+    ///
+    /// Simplified child handles are not copied, because they depend on
+    /// interval results in transformed coordinates.
+    pub fn with_transform(
+        &self,
+        mat: Matrix4<f32>,
+    ) -> RenderHandle<F, Transformed> {
+        RenderHandle {
+            shape: self.shape.with_transform(mat),
+            i_tape: self.i_tape.as_ref().map(|t| t.with_transform(mat)),
+            f_tape: self.f_tape.as_ref().map(|t| t.with_transform(mat)),
+            g_tape: self.g_tape.as_ref().map(|t| t.with_transform(mat)),
+            next: None,
+        }
+    }
+}
+
 /// Container representing an ordered, checked list of tile sizes
 ///
 /// This object wraps a `Vec<usize>`, guaranteeing three invariants:
@@ -186,7 +232,7 @@ impl<F: Function, T> RenderHandle<F, T> {
 /// - There must be at least one tile size
 /// - Tiles must be ordered from largest to smallest
 /// - Each tile size must be exactly divisible by subsequent tile sizes
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TileSizes(Vec<usize>);
 
 impl TileSizes {
@@ -217,6 +263,24 @@ impl TileSizes {
     }
 }
 
+impl std::str::FromStr for TileSizes {
+    type Err = Error;
+
+    /// Parses a comma-separated list of tile sizes, e.g. `"128,32,8"`
+    ///
+    /// This makes it easy to override an evaluator's
+    /// [`RenderHints`] at runtime (e.g. from a command-line argument), since
+    /// the best tile sizes depend on the machine as well as the evaluator.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let sizes = s
+            .split(',')
+            .map(|v| v.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::BadTileSizeString(s.to_owned()))?;
+        Self::new(&sizes)
+    }
+}
+
 impl std::ops::Index<usize> for TileSizes {
     type Output = usize;
 
@@ -237,6 +301,14 @@ impl std::ops::Index<std::ops::RangeFrom<usize>> for TileSizes {
 /// This is a bit of a grab-bag trait for both rasterization and meshing; it's
 /// in `fidget-core` so that other evaluators can implement it without needing
 /// to depend on `fidget-raster` or `fidget-mesh`.
+///
+/// Tile sizes are only defaults: render configs store their own
+/// [`TileSizes`], which may be replaced at runtime (e.g. parsed from a string
+/// with [`FromStr`](std::str::FromStr)) to tune for a particular machine.
+/// The register limit is not a render setting, because it's baked into each
+/// tape when the shape is built: the interpreter's limit is the const
+/// parameter of [`GenericVmFunction`](crate::vm::GenericVmFunction), and the
+/// JIT's limit is the number of hardware registers reserved by its backend.
 pub trait RenderHints {
     /// Recommended tile sizes for 3D rendering
     fn tile_sizes_3d() -> TileSizes;
@@ -254,3 +326,31 @@ pub trait RenderHints {
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tile_sizes_from_str() {
+        let t: TileSizes = "128, 32,8".parse().unwrap();
+        assert_eq!(t, TileSizes::new(&[128, 32, 8]).unwrap());
+
+        assert!(matches!(
+            "128,x".parse::<TileSizes>(),
+            Err(Error::BadTileSizeString(..))
+        ));
+        assert!(matches!(
+            "".parse::<TileSizes>(),
+            Err(Error::BadTileSizeString(..))
+        ));
+        assert!(matches!(
+            "32,128".parse::<TileSizes>(),
+            Err(Error::BadTileOrder(32, 128))
+        ));
+        assert!(matches!(
+            "128,48".parse::<TileSizes>(),
+            Err(Error::BadTileSize(128, 48))
+        ));
+    }
+}
@@ -28,12 +28,13 @@
 
 use crate::{
     Error,
+    compiler::BuildBudget,
     context::{Context, Node, Tree},
     eval::{BulkEvaluator, Function, MathFunction, Tape, TracingEvaluator},
-    types::{Grad, Interval},
+    types::{Affine, Grad, Interval},
     var::{Var, VarIndex, VarMap},
 };
-use nalgebra::{Matrix4, Point3};
+use nalgebra::{Matrix3, Matrix4, Point3};
 use std::collections::HashMap;
 
 /// A shape represents an implicit surface
@@ -266,6 +267,12 @@ impl<F: Clone> Shape<F, Transformed> {
 /// Note that this cannot store `X`, `Y`, `Z` variables (which are passed in as
 /// first-class arguments); it only stores [`Var::V`] values (identified by
 /// their inner [`VarIndex`]).
+///
+/// Variable values are bound at evaluation time: changing them never
+/// invalidates a [`Shape`] or its root tapes (including JIT-compiled code), so
+/// tapes may be reused across calls with different values (see
+/// [`TapeCache`](crate::render::TapeCache)).  Only simplified tapes depend on
+/// variable values, through the traces captured during evaluation.
 pub struct ShapeVars<F>(HashMap<VarIndex, F>);
 
 impl<F> Default for ShapeVars<F> {
@@ -398,6 +405,178 @@ impl<F: MathFunction> Shape<F> {
     {
         Self::new_with_axes(ctx, node, [Var::X, Var::Y, Var::Z])
     }
+
+    /// Builds a new shape with default axes, subject to a [`BuildBudget`]
+    ///
+    /// See [`MathFunction::new_with_budget`] for details.
+    pub fn new_with_budget(
+        ctx: &Context,
+        node: Node,
+        budget: &BuildBudget,
+    ) -> Result<Self, Error> {
+        let f = F::new_with_budget(ctx, &[node], budget)?;
+        Ok(Self {
+            f,
+            axes: [Var::X, Var::Y, Var::Z],
+            transform: None,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Specializes the shape to the plane at the given Z value
+    ///
+    /// The Z axis is replaced by a constant and the function is re-simplified
+    /// (see [`MathFunction::specialize`]), so 2D evaluation (e.g. rendering a
+    /// cross-section of a 3D model) doesn't pay for operations which only
+    /// depend on Z.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, shape::EzShape, vm::VmShape};
+    /// let (x, y, z) = Tree::axes();
+    /// let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+    /// let shape = VmShape::from(sphere);
+    /// let slice = shape.specialize_2d(0.6)?;
+    /// assert!(slice.size() < shape.size());
+    ///
+    /// let tape = slice.ez_point_tape();
+    /// let mut eval = VmShape::new_point_eval();
+    /// let (v, _) = eval.eval(&tape, 0.8, 0.0, 0.0)?;
+    /// assert!(v.abs() < 1e-6);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn specialize_2d(&self, z: f32) -> Result<Self, Error> {
+        let f = self.f.specialize(self.axes[2], z)?;
+        Ok(Self {
+            f,
+            axes: self.axes,
+            transform: None,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Applies a coordinate warp to the shape
+    ///
+    /// `warp` must have three outputs, which are computed from this shape's
+    /// axes and then used in place of them, i.e. the result evaluates
+    /// `f(g(x, y, z))`.  This is the building block for deformations such as
+    /// twists, bends, and lattice deformers.  The two functions are stitched
+    /// into a single function (see [`MathFunction::compose`]).
+    ///
+    /// ```
+    /// # use fidget_core::{
+    /// #     context::{Context, Tree}, eval::MathFunction, shape::EzShape,
+    /// #     vm::{VmFunction, VmShape},
+    /// # };
+    /// let (x, y, _) = Tree::axes();
+    /// let circle = (x.square() + y.square()).sqrt() - 1.0;
+    /// let shape = VmShape::from(circle);
+    ///
+    /// // Stretch the shape by 2x along the X axis
+    /// let mut ctx = Context::new();
+    /// let (x, y, z) = (ctx.x(), ctx.y(), ctx.z());
+    /// let x2 = ctx.div(x, 2.0)?;
+    /// let warp = VmFunction::new(&ctx, &[x2, y, z])?;
+    /// let stretched = shape.warp(&warp)?;
+    ///
+    /// let tape = stretched.ez_point_tape();
+    /// let mut eval = VmShape::new_point_eval();
+    /// let (v, _) = eval.eval(&tape, 2.0, 0.0, 0.0)?;
+    /// assert_eq!(v, 0.0);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn warp(&self, warp: &F) -> Result<Self, Error> {
+        let f = self.f.compose(warp, &self.axes)?;
+        Ok(Self {
+            f,
+            axes: self.axes,
+            transform: None,
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<F: MathFunction, T> Shape<F, T> {
+    /// Returns a shape which computes the magnitude of this shape's gradient
+    ///
+    /// The new shape evaluates `sqrt(∂f/∂x² + ∂f/∂y² + ∂f/∂z²)` with respect
+    /// to the shape's axes (see [`MathFunction::gradient`]).  Evaluating it
+    /// with intervals produces bounds on the gradient magnitude over a region;
+    /// the upper bound is a Lipschitz constant for the region, which gives
+    /// provably safe step sizes for sphere tracing and error bounds for
+    /// meshing.  If the bounds include `NaN` (e.g. at a singularity), no
+    /// guarantee can be made for that region.
+    ///
+    /// If the shape has a transform, then the gradient is taken with respect to
+    /// the coordinates passed into the shape (before the transform is applied,
+    /// using the chain rule), and the new shape keeps the same transform.  Returns [`Error::Unsupported`] if the
+    /// transform is projective.
+    ///
+    /// ```
+    /// # use fidget_core::{
+    /// #     context::Tree, shape::EzShape, types::Interval, vm::VmShape,
+    /// # };
+    /// let (x, y, _) = Tree::axes();
+    /// let shape = VmShape::from(x * 3.0 + y.square());
+    /// let grad = shape.gradient_magnitude()?;
+    ///
+    /// let tape = grad.ez_interval_tape();
+    /// let mut eval = VmShape::new_interval_eval();
+    /// let (out, _) = eval.eval(
+    ///     &tape,
+    ///     Interval::new(0.0, 1.0),
+    ///     Interval::new(0.0, 2.0),
+    ///     Interval::new(0.0, 0.0),
+    /// )?;
+    ///
+    /// // The true gradient magnitude ranges from 3 to 5 in this region
+    /// assert!(out.lower() <= 3.0 && out.upper() >= 5.0);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn gradient_magnitude(&self) -> Result<Self, Error> {
+        // If the shape has a transform `M`, then it evaluates `f(M p)`, so its
+        // gradient (by the chain rule) is `Mᵀ ∇f(M p)`.  This only holds for
+        // affine transforms; the new shape keeps the transform, so `∇f` is
+        // evaluated at `M p`.
+        let mat = match self.transform {
+            Some(m) => {
+                let w = m[(3, 3)];
+                if m[(3, 0)] != 0.0
+                    || m[(3, 1)] != 0.0
+                    || m[(3, 2)] != 0.0
+                    || w == 0.0
+                {
+                    return Err(Error::Unsupported(
+                        "gradient magnitude with a projective transform",
+                    ));
+                }
+                m.fixed_view::<3, 3>(0, 0) / w
+            }
+            None => Matrix3::identity(),
+        };
+        let grad = self.f.gradient(&self.axes)?;
+
+        let mut ctx = Context::new();
+        let vs = [Var::new(), Var::new(), Var::new()];
+        let ds = vs.map(|v| ctx.var(v));
+        let mut sum = ctx.constant(0.0);
+        for j in 0..3 {
+            let mut d = ctx.constant(0.0);
+            for (i, di) in ds.iter().enumerate() {
+                let t = ctx.mul(*di, mat[(i, j)])?;
+                d = ctx.add(d, t)?;
+            }
+            let d2 = ctx.square(d)?;
+            sum = ctx.add(sum, d2)?;
+        }
+        let root = ctx.sqrt(sum)?;
+        let f = F::new(&ctx, &[root])?.compose(&grad, &vs)?;
+        Ok(Self {
+            f,
+            axes: self.axes,
+            transform: self.transform,
+            _marker: std::marker::PhantomData,
+        })
+    }
 }
 
 /// Converts a [`Tree`] to a [`Shape`] with the default axes
@@ -421,6 +600,19 @@ pub struct ShapeTape<T> {
     transform: Option<Matrix4<f32>>,
 }
 
+impl<T: Clone> ShapeTape<T> {
+    /// Returns a copy of this tape with a different transform
+    ///
+    /// The inner tape doesn't depend on the transform, so it's shared.
+    pub(crate) fn with_transform(&self, mat: Matrix4<f32>) -> Self {
+        Self {
+            tape: self.tape.clone(),
+            axes: self.axes,
+            transform: Some(mat),
+        }
+    }
+}
+
 impl<T: Tape> ShapeTape<T> {
     /// Recycles the inner tape's storage for reuse
     pub fn recycle(self) -> Option<T::Storage> {
@@ -431,6 +623,38 @@ impl<T: Tape> ShapeTape<T> {
     pub fn vars(&self) -> &VarMap {
         self.tape.vars()
     }
+
+    /// Resolves named values into a [`ShapeVars`]
+    ///
+    /// Names are looked up in the tape's [`VarInfo`](crate::var::VarInfo)
+    /// metadata and converted with `f`.  If a declared variable isn't in the
+    /// map, its default value is passed to `default` instead.  Returns
+    /// [`Error::UnknownVariable`] if a name doesn't match any declared
+    /// variable (e.g. because of a typo), or [`Error::MissingVar`] if the tape
+    /// uses a variable (other than X, Y, Z) which has no metadata, and
+    /// therefore no default.
+    fn named_vars<V: Copy, D>(
+        &self,
+        vars: &HashMap<&str, V>,
+        f: impl Fn(V) -> D,
+        default: impl Fn(f32) -> D,
+    ) -> Result<ShapeVars<D>, Error> {
+        let vs = self.vars();
+        if let Some(name) = vars.keys().find(|name| vs.find(name).is_none()) {
+            return Err(Error::UnknownVariable(name.to_string()));
+        }
+        let mut out = ShapeVars::new();
+        for (v, _) in vs.iter() {
+            let Var::V(i) = v else { continue };
+            let info = vs.info(&v).ok_or(Error::MissingVar(v))?;
+            let value = match vars.get(info.name.as_str()) {
+                Some(value) => f(*value),
+                None => default(info.default as f32),
+            };
+            out.insert(i, value);
+        }
+        Ok(out)
+    }
 }
 
 /// Wrapper around a [`TracingEvaluator`]
@@ -535,6 +759,48 @@ where
         let (out, trace) = self.eval.eval(&tape.tape, &self.scratch)?;
         Ok((out[0], trace))
     }
+
+    /// Tracing evaluation of a single sample, with variables given by name
+    ///
+    /// Names are resolved through the tape's variable metadata (see
+    /// [`Context::declare_var`]); this is less error-prone than building a
+    /// [`ShapeVars`] by hand for models with many parameters.  Declared
+    /// variables which aren't in the map use their default value.
+    /// [`Error::UnknownVariable`] is returned if a name doesn't match any
+    /// variable declared for the tape, and [`Error::MissingVar`] is returned
+    /// if the tape uses a variable which wasn't declared, since it has no
+    /// default.
+    ///
+    /// Before evaluation, the tape's transform matrix is applied (if present).
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, vm::VmShape, shape::EzShape};
+    /// # use std::collections::HashMap;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.declare_var("radius", 0.5, None)?;
+    /// let root = ctx.sub(x, r)?;
+    /// let shape = VmShape::new(&ctx, root)?;
+    ///
+    /// let tape = shape.ez_point_tape();
+    /// let mut eval = VmShape::new_point_eval();
+    /// let vars = HashMap::from([("radius", 0.25)]);
+    /// let (v, _) = eval.eval_named(&tape, 1.0, 0.0, 0.0, &vars)?;
+    /// assert_eq!(v, 0.75);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    #[inline]
+    pub fn eval_named<F: Into<E::Data> + Copy, V: Into<E::Data> + Copy>(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: F,
+        y: F,
+        z: F,
+        vars: &HashMap<&str, V>,
+    ) -> Result<(E::Data, Option<&E::Trace>), Error> {
+        let vars = tape.named_vars(vars, V::into, E::Data::from)?;
+        self.eval_v(tape, x, y, z, &vars)
+    }
 }
 
 /// Wrapper around a [`BulkEvaluator`]
@@ -719,6 +985,51 @@ where
         let out = self.eval.eval(&tape.tape, &self.scratch)?;
         Ok(out.borrow(0))
     }
+
+    /// Bulk evaluation of many samples, with fixed variables given by name
+    ///
+    /// Names are resolved through the tape's variable metadata, as in
+    /// [`ShapeTracingEval::eval_named`]; otherwise, this is equivalent to
+    /// [`eval_v`](Self::eval_v).
+    #[inline]
+    pub fn eval_named<G: Into<E::Data> + Copy>(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: &[E::Data],
+        y: &[E::Data],
+        z: &[E::Data],
+        vars: &HashMap<&str, G>,
+    ) -> Result<&[E::Data], Error> {
+        let vars = tape.named_vars(vars, G::into, E::Data::from)?;
+        self.eval_v(tape, x, y, z, &vars)
+    }
+
+    /// Bulk evaluation of many samples, with slices of variables given by name
+    ///
+    /// Names are resolved through the tape's variable metadata, as in
+    /// [`ShapeTracingEval::eval_named`]; otherwise, this is equivalent to
+    /// [`eval_vs`](Self::eval_vs).
+    #[inline]
+    pub fn eval_vs_named<
+        V: std::ops::Deref<Target = [G]>,
+        G: Into<E::Data> + Copy,
+    >(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: &[E::Data],
+        y: &[E::Data],
+        z: &[E::Data],
+        vars: &HashMap<&str, V>,
+    ) -> Result<&[E::Data], Error> {
+        let slices: HashMap<&str, &[G]> =
+            vars.iter().map(|(k, v)| (*k, v.deref())).collect();
+        let vars = tape.named_vars(
+            &slices,
+            |vs| vs.iter().map(|v| (*v).into()).collect::<Vec<E::Data>>(),
+            |d| vec![E::Data::from(d); x.len()],
+        )?;
+        self.eval_vs(tape, x, y, z, &vars)
+    }
 }
 
 /// Trait for types that can be transformed by a 4x4 homogeneous transform matrix
@@ -757,6 +1068,22 @@ impl Transformable for Interval {
     }
 }
 
+impl Transformable for Affine {
+    fn transform(
+        x: Affine,
+        y: Affine,
+        z: Affine,
+        mat: Matrix4<f32>,
+    ) -> (Affine, Affine, Affine) {
+        let out = [0, 1, 2, 3].map(|i| {
+            let row = mat.row(i);
+            x * row[0] + y * row[1] + z * row[2] + Affine::from(row[3])
+        });
+
+        (out[0] / out[3], out[1] / out[3], out[2] / out[3])
+    }
+}
+
 impl Transformable for Grad {
     fn transform(
         x: Grad,
@@ -802,6 +1129,191 @@ mod test {
         assert!(seen.iter().all(|i| *i));
     }
 
+    #[test]
+    fn eval_named() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.declare_var("a", 0.0, None).unwrap();
+        let b = ctx.declare_var("b", 0.0, None).unwrap();
+        let root = ctx.mul(x, a).unwrap();
+        let root = ctx.sub(root, b).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+
+        let vars = HashMap::from([("a", 2.0), ("b", 0.5)]);
+        let tape = shape.ez_point_tape();
+        let mut eval = VmShape::new_point_eval();
+        let (v, _) = eval.eval_named(&tape, 3.0, 0.0, 0.0, &vars).unwrap();
+        assert_eq!(v, 5.5);
+
+        // Names which weren't declared are rejected, rather than ignored
+        let typo = HashMap::from([("a", 2.0), ("bb", 0.5)]);
+        let out = eval.eval_named(&tape, 3.0, 0.0, 0.0, &typo);
+        assert!(matches!(out, Err(Error::UnknownVariable(s)) if s == "bb"));
+
+        let tape = shape.ez_float_slice_tape();
+        let mut eval = VmShape::new_float_slice_eval();
+        let xs = [1.0, 2.0];
+        let out = eval.eval_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &vars);
+        assert_eq!(out.unwrap(), [1.5, 3.5]);
+
+        let slices =
+            HashMap::from([("a", vec![1.0, -1.0]), ("b", vec![0.0, 1.0])]);
+        let out = eval.eval_vs_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &slices);
+        assert_eq!(out.unwrap(), [1.0, -3.0]);
+
+        let missing = HashMap::from([("a", 2.0)]);
+        let out = eval.eval_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &missing);
+        assert_eq!(out.unwrap(), [2.0, 4.0]);
+
+        let out = eval.eval_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &typo);
+        assert!(matches!(out, Err(Error::UnknownVariable(..))));
+        let typo = HashMap::from([("aa", vec![1.0, -1.0])]);
+        let out = eval.eval_vs_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &typo);
+        assert!(matches!(out, Err(Error::UnknownVariable(..))));
+    }
+
+    #[test]
+    fn eval_named_defaults() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.declare_var("a", 2.0, None).unwrap();
+        let b = ctx.declare_var("b", 0.5, None).unwrap();
+        let root = ctx.mul(x, a).unwrap();
+        let root = ctx.sub(root, b).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+
+        let tape = shape.ez_point_tape();
+        let mut eval = VmShape::new_point_eval();
+        let vars = HashMap::from([("a", 3.0)]);
+        let (v, _) = eval.eval_named(&tape, 1.0, 0.0, 0.0, &vars).unwrap();
+        assert_eq!(v, 2.5);
+        let none: HashMap<&str, f32> = HashMap::new();
+        let (v, _) = eval.eval_named(&tape, 1.0, 0.0, 0.0, &none).unwrap();
+        assert_eq!(v, 1.5);
+
+        let tape = shape.ez_float_slice_tape();
+        let mut eval = VmShape::new_float_slice_eval();
+        let xs = [1.0, 2.0];
+        let slices = HashMap::from([("b", vec![0.0, 1.0])]);
+        let out = eval.eval_vs_named(&tape, &xs, &[0.0; 2], &[0.0; 2], &slices);
+        assert_eq!(out.unwrap(), [2.0, 3.0]);
+
+        // A variable without metadata has no default, so it must be provided
+        let c = ctx.var(Var::new());
+        let root = ctx.add(root, c).unwrap();
+        let shape = VmShape::new(&ctx, root).unwrap();
+        let tape = shape.ez_point_tape();
+        let mut eval = VmShape::new_point_eval();
+        let out = eval.eval_named(&tape, 1.0, 0.0, 0.0, &vars);
+        assert!(matches!(out, Err(Error::MissingVar(..))));
+    }
+
+    #[test]
+    fn gradient_magnitude() {
+        let (x, y, z) = Tree::axes();
+        let s = VmShape::from((x.clone() * y.clone()).sin() + z * x);
+        let g = s.gradient_magnitude().unwrap();
+
+        let tape = g.ez_interval_tape();
+        let mut eval = VmShape::new_interval_eval();
+        let (xs, ys, zs) = (
+            Interval::new(-1.0, 0.5),
+            Interval::new(0.25, 2.0),
+            Interval::new(-3.0, -1.0),
+        );
+        let (bound, _) = eval.eval(&tape, xs, ys, zs).unwrap();
+
+        // Compare against the gradient evaluator on a grid of samples
+        let tape = s.ez_grad_slice_tape();
+        let mut eval = VmShape::new_grad_slice_eval();
+        let n = 8;
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    let t = |r: Interval, i| r.lerp(i as f32 / (n - 1) as f32);
+                    let (x, y, z) = (t(xs, i), t(ys, j), t(zs, k));
+                    let out = eval
+                        .eval(
+                            &tape,
+                            &[Grad::new(x, 1.0, 0.0, 0.0)],
+                            &[Grad::new(y, 0.0, 1.0, 0.0)],
+                            &[Grad::new(z, 0.0, 0.0, 1.0)],
+                        )
+                        .unwrap();
+                    let d = out[0];
+                    let m = (d.dx.powi(2) + d.dy.powi(2) + d.dz.powi(2)).sqrt();
+                    assert!(
+                        bound.lower() <= m && m <= bound.upper(),
+                        "{m} not in {bound}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gradient_magnitude_transformed() {
+        use nalgebra::{Rotation3, Translation3, Vector3};
+
+        let (x, y, z) = Tree::axes();
+        let s = VmShape::from(x * 3.0 + y.square() + z);
+        let mat = Translation3::new(0.5, -0.25, 1.0).to_homogeneous()
+            * Rotation3::from_euler_angles(0.3, -0.6, 1.1).to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 0.5, 1.5));
+        let s = s.with_transform(mat);
+        let g = s.gradient_magnitude().unwrap();
+
+        // Compare against finite differences of the transformed shape
+        let f_tape = s.ez_point_tape();
+        let g_tape = g.ez_point_tape();
+        let mut eval = VmShape::new_point_eval();
+        let mut f = |x, y, z| eval.eval(&f_tape, x, y, z).unwrap().0;
+        let eps = 1e-2;
+        let mut expected = vec![];
+        for (x, y, z) in [(0.0, 0.0, 0.0), (0.5, -1.0, 0.25), (-2.0, 1.5, 1.0)]
+        {
+            let dx = (f(x + eps, y, z) - f(x - eps, y, z)) / (2.0 * eps);
+            let dy = (f(x, y + eps, z) - f(x, y - eps, z)) / (2.0 * eps);
+            let dz = (f(x, y, z + eps) - f(x, y, z - eps)) / (2.0 * eps);
+            expected.push((x, y, z, (dx * dx + dy * dy + dz * dz).sqrt()));
+        }
+        let mut eval = VmShape::new_point_eval();
+        for (x, y, z, m) in expected {
+            let (v, _) = eval.eval(&g_tape, x, y, z).unwrap();
+            assert!((v - m).abs() < 1e-2 * m, "{v} != {m} at ({x}, {y}, {z})");
+        }
+
+        // Projective transforms aren't supported
+        let mut mat = Matrix4::identity();
+        mat[(3, 0)] = 0.5;
+        let s = VmShape::from(Tree::x()).with_transform(mat);
+        assert!(matches!(
+            s.gradient_magnitude(),
+            Err(Error::Unsupported(..))
+        ));
+    }
+
+    #[test]
+    fn shape_w_axis() {
+        // A circle whose radius is controlled by the W axis
+        let (x, y, _) = Tree::axes();
+        let s = (x.square() + y.square()).sqrt() - Tree::w();
+        let s = VmShape::from(s);
+
+        let mut vars = ShapeVars::new();
+        let tape = s.ez_point_tape();
+        let mut eval = VmShape::new_point_eval();
+        for (w, expected) in [(1.0, 1.0), (2.0, 0.0), (4.0, -2.0)] {
+            vars.insert(Var::W.index().unwrap(), w);
+            let (v, _) = eval.eval_v(&tape, 2.0, 0.0, 0.0, &vars).unwrap();
+            assert_eq!(v, expected);
+        }
+
+        // Missing extra axes are reported as bad variable bindings
+        let vars = ShapeVars::<f32>::new();
+        assert!(eval.eval_v(&tape, 2.0, 0.0, 0.0, &vars).is_err());
+    }
+
     #[test]
     fn shape_eval_bulk_size() {
         let s = Tree::constant(1.0);
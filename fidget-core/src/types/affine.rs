@@ -0,0 +1,559 @@
+use crate::{types::Interval, vm::Choice};
+
+/// An affine form, used for range evaluation with tracked correlations
+///
+/// An affine form represents a range of values as
+/// `center + Σ e[i] * ε[i] ± err`, where each `ε[i]` is an unknown value in
+/// `[-1, 1]`.  The three noise symbols `ε[0..3]` are shared between all
+/// affine forms in an evaluation, so (unlike [`Interval`]) linear operations
+/// on correlated values cancel: with `x = 0 + 1ε₀`, `x - x` is exactly zero,
+/// and rotating a region leaves its extent unchanged.
+///
+/// The noise symbols are typically bound to the X, Y, Z axes with
+/// [`Affine::axis`]; other inputs (built with `From<Interval>`) have no
+/// correlation and are stored entirely in the error term.
+///
+/// Linear operations (addition, subtraction, negation, and scaling by a
+/// constant) are exact.  Multiplication and some nonlinear functions
+/// (`square`, `sqrt`, `recip`, `exp`, `ln`) use linear approximations and
+/// add their approximation error to the error term; everything else falls
+/// back to [`Interval`] arithmetic, losing correlations.  Every operation adds
+/// a small allowance for floating-point rounding to the error term.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Affine {
+    center: f32,
+    terms: [f32; 3],
+    err: f32,
+}
+
+impl Affine {
+    /// Builds a new affine form from its center, noise terms, and error
+    ///
+    /// # Panics
+    /// If `err` is negative
+    #[inline]
+    pub fn new(center: f32, terms: [f32; 3], err: f32) -> Self {
+        assert!(err >= 0.0 || err.is_nan(), "invalid error term {err}");
+        Self { center, terms, err }
+    }
+
+    /// Builds an affine form spanning the given interval along one axis
+    ///
+    /// The interval's radius is assigned to noise symbol `axis`, so values
+    /// built from the same axis are correlated.
+    ///
+    /// # Panics
+    /// If `axis` is not in the 0-2 range
+    #[inline]
+    pub fn axis(i: Interval, axis: usize) -> Self {
+        assert!(axis < 3, "invalid axis {axis}");
+        let mut terms = [0.0; 3];
+        let (center, t) = (i.midpoint(), i.width() / 2.0);
+        terms[axis] = t;
+
+        // Account for rounding if `center ± t` doesn't cover the interval
+        let err = if center - t <= i.lower() && center + t >= i.upper() {
+            0.0
+        } else {
+            (center.abs() + t) * f32::EPSILON
+        };
+        Self { center, terms, err }
+    }
+
+    /// Returns the center value
+    #[inline]
+    pub fn center(&self) -> f32 {
+        self.center
+    }
+
+    /// Returns the coefficients of the three noise symbols
+    #[inline]
+    pub fn terms(&self) -> [f32; 3] {
+        self.terms
+    }
+
+    /// Returns the uncorrelated error term
+    #[inline]
+    pub fn err(&self) -> f32 {
+        self.err
+    }
+
+    /// Returns the total radius of the affine form
+    #[inline]
+    pub fn radius(&self) -> f32 {
+        self.terms.iter().map(|t| t.abs()).sum::<f32>() + self.err
+    }
+
+    /// Checks whether the affine form contains a NaN value
+    #[inline]
+    pub fn has_nan(&self) -> bool {
+        self.center.is_nan() || self.radius().is_nan()
+    }
+
+    /// Converts the affine form into an [`Interval`]
+    ///
+    /// The interval encloses the affine form, widened slightly (if the form
+    /// is not constant) to account for rounding in `center ± radius`.
+    #[inline]
+    pub fn to_interval(self) -> Interval {
+        if self.has_nan() {
+            return f32::NAN.into();
+        }
+        let r = self.radius();
+        let slack = if r > 0.0 {
+            (self.center.abs() + r) * f32::EPSILON
+        } else {
+            0.0
+        };
+        let (lo, hi) = (self.center - r - slack, self.center + r + slack);
+        if lo.is_nan() || hi.is_nan() {
+            f32::NAN.into()
+        } else {
+            Interval::new(lo, hi)
+        }
+    }
+
+    /// Returns the largest magnitude of any value within the affine form
+    #[inline]
+    fn magnitude(&self) -> f32 {
+        self.center.abs() + self.radius()
+    }
+
+    /// Adds a rounding allowance for a result computed from operands of the
+    /// given magnitude
+    ///
+    /// The magnitude must be from the operands rather than the result, because
+    /// cancellation can make the result arbitrarily small.
+    #[inline]
+    fn rounded(mut self, m: f32) -> Self {
+        self.err += 2.0 * m * f32::EPSILON;
+        self
+    }
+
+    /// Applies an [`Interval`] function, discarding correlations
+    #[inline]
+    fn lift(self, f: impl Fn(Interval) -> Interval) -> Self {
+        f(self.to_interval()).into()
+    }
+
+    /// Applies a function with the min-range linear approximation
+    ///
+    /// `f` must be monotonic and either convex or concave over the range of
+    /// `self`, with derivative `df`; the approximation is then
+    /// `α * self + ζ ± δ`, where `α` is the smaller-magnitude derivative at
+    /// the endpoints of the range.
+    fn min_range(
+        self,
+        f: impl Fn(f32) -> f32,
+        df: impl Fn(f32) -> f32,
+    ) -> Self {
+        let i = self.to_interval();
+        let (a, b) = (i.lower(), i.upper());
+        if a == b {
+            return f(self.center).into();
+        }
+        let (fa, fb) = (f(a), f(b));
+        if fa.is_nan() || fb.is_nan() {
+            return f32::NAN.into();
+        }
+        let (da, db) = (df(a), df(b));
+        let alpha = if da.abs() < db.abs() { da } else { db };
+
+        // With this choice of slope, `f(x) - alpha * x` is monotonic, so its
+        // range is bounded by its values at the endpoints.
+        let ga = fa - alpha * a;
+        let gb = fb - alpha * b;
+        let (lo, hi) = if ga < gb { (ga, gb) } else { (gb, ga) };
+        let zeta = (lo + hi) / 2.0;
+        let delta = (hi - lo) / 2.0;
+
+        let mut out = self * alpha;
+        out.center += zeta;
+        out.err += delta;
+        if !out.center.is_finite() || !out.radius().is_finite() {
+            // Fall back to the monotonic function's range
+            return Interval::new(fa.min(fb), fa.max(fb)).into();
+        }
+        // `f` itself may be off by an ulp or so, which is covered by the
+        // magnitude of its outputs
+        out.rounded(
+            out.magnitude()
+                + fa.abs()
+                + fb.abs()
+                + alpha.abs() * (a.abs() + b.abs()),
+        )
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        let i = self.to_interval();
+        if i.lower() >= 0.0 {
+            self
+        } else if i.upper() <= 0.0 {
+            -self
+        } else {
+            self.lift(Interval::abs)
+        }
+    }
+
+    /// Squares the affine form
+    pub fn square(self) -> Self {
+        if self.has_nan() {
+            return f32::NAN.into();
+        }
+        // (c + d)² = c² + 2cd + d², where d² is in [0, r²]
+        let r = self.radius();
+        let r2 = r * r / 2.0;
+        Self {
+            center: self.center * self.center + r2,
+            terms: self.terms.map(|t| 2.0 * self.center * t),
+            err: 2.0 * self.center.abs() * self.err + r2,
+        }
+        .rounded(self.magnitude() * self.magnitude())
+    }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let i = self.to_interval();
+        if i.lower() > 0.0 {
+            self.min_range(f32::sqrt, |x| 0.5 / x.sqrt())
+        } else {
+            self.lift(Interval::sqrt)
+        }
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        let i = self.to_interval();
+        if i.lower() > 0.0 || i.upper() < 0.0 {
+            self.min_range(f32::recip, |x| -1.0 / (x * x))
+        } else {
+            self.lift(Interval::recip)
+        }
+    }
+
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        self.min_range(f32::exp, f32::exp)
+    }
+
+    /// Natural log
+    pub fn ln(self) -> Self {
+        let i = self.to_interval();
+        if i.lower() > 0.0 {
+            self.min_range(f32::ln, f32::recip)
+        } else {
+            self.lift(Interval::ln)
+        }
+    }
+
+    /// Sine, evaluated with interval arithmetic
+    pub fn sin(self) -> Self {
+        self.lift(Interval::sin)
+    }
+
+    /// Cosine, evaluated with interval arithmetic
+    pub fn cos(self) -> Self {
+        self.lift(Interval::cos)
+    }
+
+    /// Tangent, evaluated with interval arithmetic
+    pub fn tan(self) -> Self {
+        self.lift(Interval::tan)
+    }
+
+    /// Arcsine, evaluated with interval arithmetic
+    pub fn asin(self) -> Self {
+        self.lift(Interval::asin)
+    }
+
+    /// Arccosine, evaluated with interval arithmetic
+    pub fn acos(self) -> Self {
+        self.lift(Interval::acos)
+    }
+
+    /// Arctangent, evaluated with interval arithmetic
+    pub fn atan(self) -> Self {
+        self.lift(Interval::atan)
+    }
+
+    /// Floor, evaluated with interval arithmetic
+    pub fn floor(self) -> Self {
+        self.lift(|i| i.floor())
+    }
+
+    /// Ceiling, evaluated with interval arithmetic
+    pub fn ceil(self) -> Self {
+        self.lift(|i| i.ceil())
+    }
+
+    /// Rounding, evaluated with interval arithmetic
+    pub fn round(self) -> Self {
+        self.lift(|i| i.round())
+    }
+
+    /// Two-argument arctangent, evaluated with interval arithmetic
+    pub fn atan2(self, x: Self) -> Self {
+        self.to_interval().atan2(x.to_interval()).into()
+    }
+
+    /// Euclidean remainder, evaluated with interval arithmetic
+    pub fn rem_euclid(self, other: Self) -> Self {
+        self.to_interval().rem_euclid(other.to_interval()).into()
+    }
+
+    /// Selects between two affine forms based on an interval [`Choice`]
+    ///
+    /// If one side is always selected, its affine form (and correlations) are
+    /// preserved; otherwise, the interval result is used.
+    #[inline]
+    fn choose(self, rhs: Self, (v, c): (Interval, Choice)) -> (Self, Choice) {
+        let out = match c {
+            Choice::Left => self,
+            Choice::Right => rhs,
+            _ => v.into(),
+        };
+        (out, c)
+    }
+
+    /// Calculates the minimum of two affine forms
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other.
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        let r = self.to_interval().min_choice(rhs.to_interval());
+        self.choose(rhs, r)
+    }
+
+    /// Calculates the maximum of two affine forms
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        let r = self.to_interval().max_choice(rhs.to_interval());
+        self.choose(rhs, r)
+    }
+
+    /// Short-circuiting logical `AND`, returning a [`Choice`]
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        let r = self.to_interval().and_choice(rhs.to_interval());
+        self.choose(rhs, r)
+    }
+
+    /// Short-circuiting logical `OR`, returning a [`Choice`]
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        let r = self.to_interval().or_choice(rhs.to_interval());
+        self.choose(rhs, r)
+    }
+
+    /// Logical `NOT`, using interval arithmetic
+    ///
+    /// See [`Interval::logical_not`] for details.
+    pub fn logical_not(self) -> Self {
+        self.to_interval().logical_not().into()
+    }
+
+    /// Compares two affine forms, using interval arithmetic
+    ///
+    /// See [`Interval::compare`] for details.
+    pub fn compare(self, rhs: Self) -> Self {
+        self.to_interval().compare(rhs.to_interval()).into()
+    }
+}
+
+impl std::fmt::Display for Affine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c] = self.terms;
+        write!(f, "{} + {a}ε₀ + {b}ε₁ + {c}ε₂ ± {}", self.center, self.err)
+    }
+}
+
+impl From<f32> for Affine {
+    #[inline]
+    fn from(v: f32) -> Self {
+        Self {
+            center: v,
+            terms: [0.0; 3],
+            err: 0.0,
+        }
+    }
+}
+
+impl From<Interval> for Affine {
+    #[inline]
+    fn from(i: Interval) -> Self {
+        if i.has_nan() {
+            return f32::NAN.into();
+        }
+        let center = i.midpoint();
+        Self {
+            center,
+            terms: [0.0; 3],
+            err: (i.upper() - center).max(center - i.lower()),
+        }
+    }
+}
+
+impl From<Affine> for Interval {
+    #[inline]
+    fn from(a: Affine) -> Self {
+        a.to_interval()
+    }
+}
+
+impl std::ops::Add<Affine> for Affine {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            center: self.center + rhs.center,
+            terms: std::array::from_fn(|i| self.terms[i] + rhs.terms[i]),
+            err: self.err + rhs.err,
+        }
+        .rounded(self.magnitude() + rhs.magnitude())
+    }
+}
+
+impl std::ops::Sub<Affine> for Affine {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl std::ops::Neg for Affine {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        Self {
+            center: -self.center,
+            terms: self.terms.map(|t| -t),
+            err: self.err,
+        }
+    }
+}
+
+impl std::ops::Mul<f32> for Affine {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: f32) -> Self {
+        if rhs == 0.0 && !self.has_nan() {
+            return 0.0.into();
+        }
+        Self {
+            center: self.center * rhs,
+            terms: self.terms.map(|t| t * rhs),
+            err: self.err * rhs.abs(),
+        }
+        .rounded(self.magnitude() * rhs.abs())
+    }
+}
+
+impl std::ops::Mul<Affine> for Affine {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        // (a + da)(b + db) = ab + a db + b da + da db, where the last term is
+        // bounded by the product of the radii
+        Self {
+            center: self.center * rhs.center,
+            terms: std::array::from_fn(|i| {
+                self.center * rhs.terms[i] + rhs.center * self.terms[i]
+            }),
+            err: self.center.abs() * rhs.err
+                + rhs.center.abs() * self.err
+                + self.radius() * rhs.radius(),
+        }
+        .rounded(self.magnitude() * rhs.magnitude())
+    }
+}
+
+impl std::ops::Div<Affine> for Affine {
+    type Output = Self;
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        if rhs.radius() == 0.0 {
+            self * rhs.center.recip()
+        } else {
+            self * rhs.recip()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_contains(a: Affine, f: impl Fn(f32) -> f32, x: Interval) {
+        let out = a.to_interval();
+        for i in 0..=100 {
+            let v = f(x.lerp(i as f32 / 100.0));
+            assert!(
+                out.lower() <= v && v <= out.upper(),
+                "{v} is not in {out:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn affine_linear() {
+        let x = Affine::axis(Interval::new(-1.0, 3.0), 0);
+        assert_eq!(x.center(), 1.0);
+        assert_eq!(x.terms(), [2.0, 0.0, 0.0]);
+
+        // Correlated values cancel (up to rounding), unlike intervals
+        let d = (x - x).to_interval();
+        assert!(d.contains(0.0) && d.width() < 1e-5, "{d:?}");
+        let i = Interval::new(-1.0, 3.0);
+        assert_eq!(i - i, Interval::new(-4.0, 4.0));
+
+        // Rotation by 45° doesn't grow the region's extent along the diagonal
+        let y = Affine::axis(Interval::new(-1.0, 1.0), 1);
+        let x = Affine::axis(Interval::new(-1.0, 1.0), 0);
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        let u = x * s + y * s;
+        let v = x * s - y * s;
+        let back = (u * s + v * s).to_interval();
+        assert!(back.upper() < 1.0 + 1e-5, "{back:?}");
+    }
+
+    #[test]
+    fn affine_nonlinear() {
+        let i = Interval::new(0.5, 4.0);
+        let x = Affine::axis(i, 2);
+        check_contains(x.square(), |v| v * v, i);
+        check_contains(x.sqrt(), f32::sqrt, i);
+        check_contains(x.recip(), f32::recip, i);
+        check_contains(x.exp(), f32::exp, i);
+        check_contains(x.ln(), f32::ln, i);
+        check_contains(x * x, |v| v * v, i);
+        check_contains(-x.recip(), |v| -1.0 / v, i);
+
+        let i = Interval::new(-2.0, 1.0);
+        let x = Affine::axis(i, 0);
+        check_contains(x.square(), |v| v * v, i);
+        check_contains(x.abs(), f32::abs, i);
+        check_contains(x.sin(), f32::sin, i);
+        assert!(x.recip().has_nan());
+
+        // x² - 4x is much tighter than with intervals
+        let i = Interval::new(2.0, 3.0);
+        let x = Affine::axis(i, 0);
+        let a = (x.square() - x * 4.0).to_interval();
+        let b = i.square() - i * 4.0;
+        check_contains(x.square() - x * 4.0, |v| v * v - 4.0 * v, i);
+        assert!(a.width() < b.width(), "{a:?} vs {b:?}");
+    }
+
+    #[test]
+    fn affine_choice() {
+        let x = Affine::axis(Interval::new(0.0, 1.0), 0);
+        let (v, c) = x.min_choice(Affine::from(2.0));
+        assert_eq!(c, Choice::Left);
+        assert_eq!(v, x);
+
+        let (_, c) = x.max_choice(Affine::from(0.5));
+        assert_eq!(c, Choice::Both);
+    }
+}
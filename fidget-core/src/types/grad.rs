@@ -181,9 +181,13 @@ impl Grad {
     /// Least non-negative remainder
     #[inline]
     pub fn rem_euclid(&self, rhs: Grad) -> Self {
-        let e = self.v.div_euclid(rhs.v);
+        // Recover the quotient from the remainder (rather than using
+        // `div_euclid`, which can disagree with `rem_euclid` near multiples
+        // of `rhs`), matching `Context::deriv` and `Context::gradient`
+        let v = self.v.rem_euclid(rhs.v);
+        let e = ((self.v - v) / rhs.v).round();
         Grad {
-            v: self.v.rem_euclid(rhs.v),
+            v,
             dx: self.dx - rhs.dx * e,
             dy: self.dy - rhs.dy * e,
             dz: self.dz - rhs.dz * e,
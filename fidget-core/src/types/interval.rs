@@ -2,11 +2,202 @@ use crate::vm::Choice;
 use std::f32::consts::PI;
 use std::f32::consts::TAU;
 
+/// Outward rounding of computed bounds
+///
+/// Each operation returns its rounded result and the error term (`exact -
+/// rounded`), computed with error-free transformations; the result is then
+/// nudged by one ULP only if it was rounded in the wrong direction.  An error
+/// term of NaN means "unknown", and widens in both directions.
+///
+/// When the `outward-rounding` feature is disabled, error terms aren't
+/// computed and all of these functions are plain arithmetic.
+mod round {
+    const ENABLED: bool = cfg!(feature = "outward-rounding");
+
+    /// Below this magnitude, FMA-based error terms may underflow, so we widen
+    /// unconditionally (this is conservative)
+    const TINY: f32 = 1e-30;
+
+    #[inline]
+    fn down((v, err): (f32, f32)) -> f32 {
+        if ENABLED && (err < 0.0 || err.is_nan()) {
+            v.next_down()
+        } else {
+            v
+        }
+    }
+
+    #[inline]
+    fn up((v, err): (f32, f32)) -> f32 {
+        if ENABLED && (err > 0.0 || err.is_nan()) {
+            v.next_up()
+        } else {
+            v
+        }
+    }
+
+    /// `a + b` with its error term (Knuth's TwoSum)
+    #[inline]
+    fn add(a: f32, b: f32) -> (f32, f32) {
+        let s = a + b;
+        let err = if !ENABLED {
+            0.0
+        } else if !s.is_finite() {
+            f32::NAN
+        } else {
+            let bb = s - a;
+            (a - (s - bb)) + (b - bb)
+        };
+        (s, err)
+    }
+
+    /// `a * b` with its error term (exact product via FMA)
+    #[inline]
+    fn mul(a: f32, b: f32) -> (f32, f32) {
+        let p = a * b;
+        let err = if !ENABLED || a == 0.0 || b == 0.0 {
+            0.0
+        } else if !p.is_finite() || p.abs() < TINY {
+            f32::NAN
+        } else {
+            a.mul_add(b, -p)
+        };
+        (p, err)
+    }
+
+    /// `a / b` with the sign of its error term (exact residual via FMA)
+    #[inline]
+    fn div(a: f32, b: f32) -> (f32, f32) {
+        let q = a / b;
+        let err = if !ENABLED || a == 0.0 {
+            0.0
+        } else if !q.is_finite() || q.abs() < TINY || a.abs() < TINY {
+            f32::NAN
+        } else {
+            // a - q * b, which has the same sign as (a / b - q) * b
+            -q.mul_add(b, -a) * b.signum()
+        };
+        (q, err)
+    }
+
+    /// `sqrt(a)` with its error term (exact residual via FMA)
+    #[inline]
+    fn sqrt(a: f32) -> (f32, f32) {
+        let s = a.sqrt();
+        let err = if !ENABLED || a == 0.0 {
+            0.0
+        } else if !s.is_finite() || a < TINY {
+            f32::NAN
+        } else {
+            -s.mul_add(s, -a)
+        };
+        (s, err)
+    }
+
+    /// `a.rem_euclid(b)` with its error term
+    ///
+    /// `a % b` is always exact; only the fixup for negative remainders rounds.
+    #[inline]
+    fn rem_euclid(a: f32, b: f32) -> (f32, f32) {
+        let r = a % b;
+        if r < 0.0 { add(r, b.abs()) } else { (r, 0.0) }
+    }
+
+    #[inline]
+    pub fn add_down(a: f32, b: f32) -> f32 {
+        down(add(a, b))
+    }
+    #[inline]
+    pub fn add_up(a: f32, b: f32) -> f32 {
+        up(add(a, b))
+    }
+    #[inline]
+    pub fn sub_down(a: f32, b: f32) -> f32 {
+        down(add(a, -b))
+    }
+    #[inline]
+    pub fn sub_up(a: f32, b: f32) -> f32 {
+        up(add(a, -b))
+    }
+    #[inline]
+    pub fn mul_down(a: f32, b: f32) -> f32 {
+        down(mul(a, b))
+    }
+    #[inline]
+    pub fn mul_up(a: f32, b: f32) -> f32 {
+        up(mul(a, b))
+    }
+    #[inline]
+    pub fn div_down(a: f32, b: f32) -> f32 {
+        down(div(a, b))
+    }
+    #[inline]
+    pub fn div_up(a: f32, b: f32) -> f32 {
+        up(div(a, b))
+    }
+    #[inline]
+    pub fn sqrt_down(a: f32) -> f32 {
+        down(sqrt(a))
+    }
+    #[inline]
+    pub fn sqrt_up(a: f32) -> f32 {
+        up(sqrt(a))
+    }
+    #[inline]
+    pub fn rem_euclid_down(a: f32, b: f32) -> f32 {
+        down(rem_euclid(a, b))
+    }
+    #[inline]
+    pub fn rem_euclid_up(a: f32, b: f32) -> f32 {
+        up(rem_euclid(a, b))
+    }
+
+    /// Rounds down the result of a transcendental function
+    ///
+    /// Transcendental functions aren't correctly rounded, so we widen by two
+    /// ULPs (assuming that the platform's `libm` is accurate to within one).
+    #[inline]
+    pub fn libm_down(v: f32) -> f32 {
+        if ENABLED {
+            v.next_down().next_down()
+        } else {
+            v
+        }
+    }
+
+    /// Rounds up the result of a transcendental function
+    #[inline]
+    pub fn libm_up(v: f32) -> f32 {
+        if ENABLED { v.next_up().next_up() } else { v }
+    }
+
+    /// Rounds down the result of a transcendental function, keeping zeros
+    ///
+    /// This is only valid for functions which return zero exactly when their
+    /// input is exact (`sin`, `tan`, `asin`, `atan` at 0 and `ln` at 1).
+    #[inline]
+    pub fn libm_down_nz(v: f32) -> f32 {
+        if v == 0.0 { v } else { libm_down(v) }
+    }
+
+    /// Rounds up the result of a transcendental function, keeping zeros
+    ///
+    /// See [`libm_down_nz`] for when this is valid.
+    #[inline]
+    pub fn libm_up_nz(v: f32) -> f32 {
+        if v == 0.0 { v } else { libm_up(v) }
+    }
+}
+
 /// Stores a range, with conservative calculations to guarantee that it always
 /// contains the actual value.
 ///
 /// # Warning
-/// This implementation does not set rounding modes, so it may not be _perfect_.
+/// By default, this implementation uses round-to-nearest arithmetic, so bounds
+/// may be off by an ULP or two.  Enable the `outward-rounding` feature to widen
+/// every inexact bound outwards, producing certified enclosures.  This affects
+/// every user of `Interval` (including the VM interpreter), but not the JIT's
+/// interval evaluator.
 #[derive(Copy, Clone, PartialEq)]
 #[repr(C)]
 pub struct Interval {
@@ -82,14 +273,22 @@ impl Interval {
     /// that both sides of the multiplication are the same value.
     #[inline]
     pub fn square(self) -> Self {
+        use round::{mul_down, mul_up};
         if self.upper < 0.0 {
-            Interval::new(self.upper.powi(2), self.lower.powi(2))
+            Interval::new(
+                mul_down(self.upper, self.upper),
+                mul_up(self.lower, self.lower),
+            )
         } else if self.lower > 0.0 {
-            Interval::new(self.lower.powi(2), self.upper.powi(2))
+            Interval::new(
+                mul_down(self.lower, self.lower),
+                mul_up(self.upper, self.upper),
+            )
         } else if self.has_nan() {
             f32::NAN.into()
         } else {
-            Interval::new(0.0, self.lower.abs().max(self.upper.abs()).powi(2))
+            let m = self.lower.abs().max(self.upper.abs());
+            Interval::new(0.0, mul_up(m, m))
         }
     }
 
@@ -104,9 +303,35 @@ impl Interval {
         }
     }
 
+    /// Widens the result of a bounded transcendental function
+    ///
+    /// If `zero_is_exact` is true, zero bounds are not widened (see
+    /// `round::libm_down_nz` for when this is valid).
+    #[inline]
+    fn libm_outward(self, min: f32, max: f32, zero_is_exact: bool) -> Self {
+        if self.has_nan() {
+            self
+        } else if zero_is_exact {
+            Interval::new(
+                round::libm_down_nz(self.lower).max(min),
+                round::libm_up_nz(self.upper).min(max),
+            )
+        } else {
+            Interval::new(
+                round::libm_down(self.lower).max(min),
+                round::libm_up(self.upper).min(max),
+            )
+        }
+    }
+
     /// Computes the sine of the interval
     #[inline]
     pub fn sin(self) -> Self {
+        self.sin_inner().libm_outward(-1.0, 1.0, true)
+    }
+
+    #[inline]
+    fn sin_inner(self) -> Self {
         if self.has_nan() {
             f32::NAN.into()
         } else if self.width() >= TAU {
@@ -157,6 +382,11 @@ impl Interval {
     /// Computes the cosine of the interval
     #[inline]
     pub fn cos(self) -> Self {
+        self.cos_inner().libm_outward(-1.0, 1.0, false)
+    }
+
+    #[inline]
+    fn cos_inner(self) -> Self {
         if self.has_nan() {
             f32::NAN.into()
         } else if self.width() >= TAU {
@@ -214,7 +444,10 @@ impl Interval {
             let lower = self.lower.tan();
             let upper = self.upper.tan();
             if upper >= lower {
-                Interval::new(lower, upper)
+                Interval::new(
+                    round::libm_down_nz(lower),
+                    round::libm_up_nz(upper),
+                )
             } else {
                 f32::NAN.into()
             }
@@ -228,7 +461,10 @@ impl Interval {
         if self.lower < -1.0 || self.upper > 1.0 {
             f32::NAN.into()
         } else {
-            Interval::new(self.lower.asin(), self.upper.asin())
+            Interval::new(
+                round::libm_down_nz(self.lower.asin()),
+                round::libm_up_nz(self.upper.asin()),
+            )
         }
     }
     /// Computes the arccosine of the interval
@@ -239,18 +475,29 @@ impl Interval {
         if self.lower < -1.0 || self.upper > 1.0 {
             f32::NAN.into()
         } else {
-            Interval::new(self.upper.acos(), self.lower.acos())
+            Interval::new(self.upper.acos(), self.lower.acos()).libm_outward(
+                0.0,
+                f32::INFINITY,
+                false,
+            )
         }
     }
     /// Computes the arctangent of the interval
     #[inline]
     pub fn atan(self) -> Self {
-        Interval::new(self.lower.atan(), self.upper.atan())
+        Interval::new(
+            round::libm_down_nz(self.lower.atan()),
+            round::libm_up_nz(self.upper.atan()),
+        )
     }
     /// Computes the exponent function applied to the interval
     #[inline]
     pub fn exp(self) -> Self {
-        Interval::new(self.lower.exp(), self.upper.exp())
+        Interval::new(self.lower.exp(), self.upper.exp()).libm_outward(
+            0.0,
+            f32::INFINITY,
+            false,
+        )
     }
     /// Computes the natural log of the input interval
     ///
@@ -260,7 +507,10 @@ impl Interval {
         if self.lower <= 0.0 {
             f32::NAN.into()
         } else {
-            Interval::new(self.lower.ln(), self.upper.ln())
+            Interval::new(
+                round::libm_down_nz(self.lower.ln()),
+                round::libm_up_nz(self.upper.ln()),
+            )
         }
     }
     /// Calculates the square root of the interval
@@ -271,7 +521,10 @@ impl Interval {
         if self.lower < 0.0 {
             f32::NAN.into()
         } else {
-            Interval::new(self.lower.sqrt(), self.upper.sqrt())
+            Interval::new(
+                round::sqrt_down(self.lower),
+                round::sqrt_up(self.upper),
+            )
         }
     }
     /// Calculates the reciprocal of the interval
@@ -280,7 +533,10 @@ impl Interval {
     #[inline]
     pub fn recip(self) -> Self {
         if self.lower > 0.0 || self.upper < 0.0 {
-            Interval::new(1.0 / self.upper, 1.0 / self.lower)
+            Interval::new(
+                round::div_down(1.0, self.upper),
+                round::div_up(1.0, self.lower),
+            )
         } else {
             f32::NAN.into()
         }
@@ -380,6 +636,39 @@ impl Interval {
         }
     }
 
+    /// Calculates the logical `NOT` of an interval
+    ///
+    /// Returns 1 if the interval is exactly zero, 0 if it can't contain zero,
+    /// and `[0, 1]` otherwise (including if it contains `NAN`).
+    #[inline]
+    pub fn logical_not(self) -> Self {
+        if !self.contains(0.0) && !self.has_nan() {
+            Interval::new(0.0, 0.0)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            Interval::new(1.0, 1.0)
+        } else {
+            Interval::new(0.0, 1.0)
+        }
+    }
+
+    /// Compares two intervals
+    ///
+    /// Returns -1 if `self` is always less than `rhs`, 1 if it is always
+    /// greater, and `[-1, 1]` if the intervals overlap.  If either side is
+    /// `NAN`, returns the `NAN` interval.
+    #[inline]
+    pub fn compare(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            f32::NAN.into()
+        } else if self.upper < rhs.lower {
+            Interval::from(-1.0)
+        } else if self.lower > rhs.upper {
+            Interval::from(1.0)
+        } else {
+            Interval::new(-1.0, 1.0)
+        }
+    }
+
     /// Returns the midpoint of the interval
     #[inline]
     pub fn midpoint(self) -> f32 {
@@ -454,8 +743,8 @@ impl Interval {
             let b = self.upper / other.lower;
             if a != a.floor() && a.floor() == b.floor() {
                 Interval::new(
-                    self.lower.rem_euclid(other.lower),
-                    self.upper.rem_euclid(other.lower),
+                    round::rem_euclid_down(self.lower, other.lower),
+                    round::rem_euclid_up(self.upper, other.lower),
                 )
             } else {
                 Interval::new(0.0, other.abs().upper())
@@ -538,7 +827,7 @@ impl Interval {
                     update(y.lower, x.lower);
                     update(y.upper, x.lower);
                 }
-                Interval::new(lower, upper)
+                Interval::new(round::libm_down(lower), round::libm_up(upper))
             }
         }
     }
@@ -568,7 +857,10 @@ impl std::ops::Add<Interval> for Interval {
     type Output = Self;
     #[inline]
     fn add(self, rhs: Self) -> Self {
-        Interval::new(self.lower + rhs.lower, self.upper + rhs.upper)
+        Interval::new(
+            round::add_down(self.lower, rhs.lower),
+            round::add_up(self.upper, rhs.upper),
+        )
     }
 }
 
@@ -579,20 +871,14 @@ impl std::ops::Mul<Interval> for Interval {
         if self.has_nan() || rhs.has_nan() {
             return f32::NAN.into();
         }
-        let mut out = [0.0; 4];
-        let mut k = 0;
+        let mut lower = round::mul_down(self.lower, rhs.lower);
+        let mut upper = round::mul_up(self.lower, rhs.lower);
         for i in [self.lower, self.upper] {
             for j in [rhs.lower, rhs.upper] {
-                out[k] = i * j;
-                k += 1;
+                lower = lower.min(round::mul_down(i, j));
+                upper = upper.max(round::mul_up(i, j));
             }
         }
-        let mut lower = out[0];
-        let mut upper = out[0];
-        for &v in &out[1..] {
-            lower = lower.min(v);
-            upper = upper.max(v);
-        }
         Interval::new(lower, upper)
     }
 }
@@ -605,9 +891,15 @@ impl std::ops::Mul<f32> for Interval {
         if self.has_nan() || rhs.is_nan() {
             f32::NAN.into()
         } else if rhs < 0.0 {
-            Interval::new(self.upper * rhs, self.lower * rhs)
+            Interval::new(
+                round::mul_down(self.upper, rhs),
+                round::mul_up(self.lower, rhs),
+            )
         } else {
-            Interval::new(self.lower * rhs, self.upper * rhs)
+            Interval::new(
+                round::mul_down(self.lower, rhs),
+                round::mul_up(self.upper, rhs),
+            )
         }
     }
 }
@@ -621,20 +913,14 @@ impl std::ops::Div<Interval> for Interval {
             return f32::NAN.into();
         }
         if rhs.lower > 0.0 || rhs.upper < 0.0 {
-            let mut out = [0.0; 4];
-            let mut k = 0;
+            let mut lower = round::div_down(self.lower, rhs.lower);
+            let mut upper = round::div_up(self.lower, rhs.lower);
             for i in [self.lower, self.upper] {
                 for j in [rhs.lower, rhs.upper] {
-                    out[k] = i / j;
-                    k += 1;
+                    lower = lower.min(round::div_down(i, j));
+                    upper = upper.max(round::div_up(i, j));
                 }
             }
-            let mut lower = out[0];
-            let mut upper = out[0];
-            for &v in &out[1..] {
-                lower = lower.min(v);
-                upper = upper.max(v);
-            }
             Interval::new(lower, upper)
         } else {
             f32::NAN.into()
@@ -647,7 +933,10 @@ impl std::ops::Sub<Interval> for Interval {
 
     #[inline]
     fn sub(self, rhs: Self) -> Self {
-        Interval::new(self.lower - rhs.upper, self.upper - rhs.lower)
+        Interval::new(
+            round::sub_down(self.lower, rhs.upper),
+            round::sub_up(self.upper, rhs.lower),
+        )
     }
 }
 
@@ -681,4 +970,93 @@ mod test {
         assert_eq!(v, [0.0, 1.0].into());
         assert_eq!(c, Choice::Both);
     }
+
+    /// Checks that `f64` oracle values are contained in the interval
+    #[cfg(feature = "outward-rounding")]
+    fn check_enclosure(name: &str, i: Interval, oracle: &[f64]) {
+        for &v in oracle {
+            assert!(
+                i.lower() as f64 <= v && v <= i.upper() as f64,
+                "{name}: {v} is not in {i:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "outward-rounding")]
+    #[test]
+    fn outward_rounding_add() {
+        // 0.1 + 0.2 rounds up in f32, so the point interval would not contain
+        // the exact sum without outward rounding.
+        let (a, b) = (0.1f32, 0.2f32);
+        let out = Interval::from(a) + Interval::from(b);
+        check_enclosure("add", out, &[a as f64 + b as f64]);
+        assert!(((a + b) as f64) > a as f64 + b as f64);
+
+        // Exact zeros are preserved, so sqrt(x^2 + y^2) stays valid
+        let z = Interval::new(-1.0, 1.0).square() + Interval::new(0.0, 2.0);
+        assert_eq!(z.lower(), 0.0);
+        assert!(!z.sqrt().has_nan());
+    }
+
+    #[cfg(feature = "outward-rounding")]
+    #[test]
+    fn outward_rounding_oracle() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x1234);
+        let gen_interval = |rng: &mut rand::rngs::StdRng| {
+            let scale = 10f32.powi(rng.random_range(-6..6));
+            let a = rng.random_range(-1.0..1.0) * scale;
+            let b = rng.random_range(-1.0..1.0) * scale;
+            Interval::new(a.min(b), a.max(b))
+        };
+        // Corners and interior samples of an interval
+        let samples = |i: Interval| {
+            let mut out = vec![i.lower(), i.upper()];
+            for k in 1..16 {
+                out.push(i.lerp(k as f32 / 16.0).clamp(i.lower(), i.upper()));
+            }
+            out
+        };
+
+        for _ in 0..10_000 {
+            let x = gen_interval(&mut rng);
+            let y = gen_interval(&mut rng);
+            let xs = samples(x);
+            let ys = samples(y);
+            let pairs = |f: fn(f64, f64) -> f64| {
+                let mut out = vec![];
+                for &a in &xs {
+                    for &b in &ys {
+                        out.push(f(a as f64, b as f64));
+                    }
+                }
+                out
+            };
+            let unary = |f: fn(f64) -> f64| {
+                xs.iter().map(|&a| f(a as f64)).collect::<Vec<_>>()
+            };
+
+            check_enclosure("add", x + y, &pairs(|a, b| a + b));
+            check_enclosure("sub", x - y, &pairs(|a, b| a - b));
+            check_enclosure("mul", x * y, &pairs(|a, b| a * b));
+            if !y.contains(0.0) {
+                check_enclosure("div", x / y, &pairs(|a, b| a / b));
+            }
+            check_enclosure("square", x.square(), &unary(|a| a * a));
+            if !x.contains(0.0) {
+                check_enclosure("recip", x.recip(), &unary(|a| 1.0 / a));
+            }
+            if x.lower() >= 0.0 {
+                check_enclosure("sqrt", x.sqrt(), &unary(f64::sqrt));
+            }
+            if x.lower() > 0.0 {
+                check_enclosure("ln", x.ln(), &unary(f64::ln));
+            }
+            check_enclosure("exp", x.exp(), &unary(f64::exp));
+            check_enclosure("sin", x.sin(), &unary(f64::sin));
+            check_enclosure("cos", x.cos(), &unary(f64::cos));
+            check_enclosure("atan", x.atan(), &unary(f64::atan));
+            check_enclosure("atan2", y.atan2(x), &pairs(|a, b| b.atan2(a)));
+        }
+    }
 }
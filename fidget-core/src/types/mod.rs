@@ -1,6 +1,8 @@
 //! Custom types used during evaluation
 
+mod affine;
 mod grad;
 mod interval;
+pub use affine::Affine;
 pub use grad::Grad;
 pub use interval::Interval;
@@ -8,7 +8,7 @@
 use crate::Error;
 use crate::context::{Context, IntoNode, Node};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::RangeInclusive};
 
 /// The [`Var`] type is an input to a math expression
 ///
@@ -18,6 +18,12 @@ use std::collections::HashMap;
 /// Variables are "global", in that every instance of `Var::X` represents the
 /// same thing.  To generate a "local" variable, [`Var::new`] picks a random
 /// 64-bit value, which is very unlikely to collide with anything else.
+///
+/// Shapes may use additional axes beyond X, Y, Z (e.g. a fourth axis to drive
+/// blend spaces or sweeps); these are ordinary variables, which are bound to
+/// constants at evaluation time using [`ShapeVars`](crate::shape::ShapeVars).
+/// [`Var::W`] is a predefined variable for the common case of a single extra
+/// axis.
 #[derive(
     Copy,
     Clone,
@@ -57,7 +63,39 @@ pub enum Var {
 #[serde(transparent)]
 pub struct VarIndex(u64);
 
+impl VarIndex {
+    /// Returns the raw index value
+    pub(crate) fn raw(&self) -> u64 {
+        self.0
+    }
+
+    /// Builds an index from a raw value
+    pub(crate) fn from_raw(i: u64) -> Self {
+        Self(i)
+    }
+}
+
 impl Var {
+    /// Variable representing a fourth (W) axis
+    ///
+    /// Unlike `X`, `Y`, and `Z`, this is a [`Var::V`] with a reserved index,
+    /// so it is bound with [`ShapeVars`](crate::shape::ShapeVars) like any
+    /// other variable:
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, var::Var, shape::{EzShape, ShapeVars}};
+    /// # use fidget_core::vm::VmShape;
+    /// let shape = VmShape::from(Tree::x() + Tree::w());
+    /// let mut vars = ShapeVars::new();
+    /// vars.insert(Var::W.index().unwrap(), 2.0);
+    ///
+    /// let tape = shape.ez_point_tape();
+    /// let mut eval = VmShape::new_point_eval();
+    /// let (v, _) = eval.eval_v(&tape, 1.0, 0.0, 0.0, &vars).unwrap();
+    /// assert_eq!(v, 3.0);
+    /// ```
+    pub const W: Var = Var::V(VarIndex(u64::MAX));
+
     /// Returns a new variable, with a random 64-bit index
     ///
     /// The odds of collision with any previous variable are infintesimally
@@ -81,6 +119,7 @@ impl std::fmt::Display for Var {
             Var::X => write!(f, "X"),
             Var::Y => write!(f, "Y"),
             Var::Z => write!(f, "Z"),
+            &Var::W => write!(f, "W"),
             Var::V(VarIndex(v)) if *v < 256 => write!(f, "v_{v}"),
             Var::V(VarIndex(v)) => write!(f, "V({v:x})"),
         }
@@ -93,6 +132,22 @@ impl IntoNode for Var {
     }
 }
 
+/// Metadata for a named variable
+///
+/// Variables are declared with metadata using
+/// [`Context::declare_var`](crate::context::Context::declare_var); the
+/// metadata is then available from any tape built from that context, through
+/// [`VarMap::info`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VarInfo {
+    /// Human-readable name
+    pub name: String,
+    /// Default value
+    pub default: f64,
+    /// Optional range of valid values
+    pub range: Option<RangeInclusive<f64>>,
+}
+
 /// Map from [`Var`] to a particular index
 ///
 /// Variable indexes are automatically assigned the first time
@@ -108,6 +163,8 @@ pub struct VarMap {
     y: Option<usize>,
     z: Option<usize>,
     v: HashMap<VarIndex, usize>,
+    #[serde(default)]
+    info: HashMap<VarIndex, VarInfo>,
 }
 
 #[allow(missing_docs)]
@@ -127,6 +184,14 @@ impl VarMap {
             && self.z.is_none()
             && self.v.is_empty()
     }
+    /// Returns the approximate number of heap bytes held by the map
+    pub fn memory_usage(&self) -> usize {
+        self.v.capacity()
+            * (std::mem::size_of::<VarIndex>() + std::mem::size_of::<usize>())
+            + self.info.capacity()
+                * (std::mem::size_of::<VarIndex>()
+                    + std::mem::size_of::<VarInfo>())
+    }
     pub fn get(&self, v: &Var) -> Option<usize> {
         match v {
             Var::X => self.x,
@@ -148,6 +213,45 @@ impl VarMap {
         };
     }
 
+    /// Iterates over `(variable, index)` pairs, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (Var, usize)> + '_ {
+        [(Var::X, self.x), (Var::Y, self.y), (Var::Z, self.z)]
+            .into_iter()
+            .filter_map(|(v, i)| i.map(|i| (v, i)))
+            .chain(self.v.iter().map(|(v, i)| (Var::V(*v), *i)))
+    }
+
+    /// Returns metadata for the given variable, if it was declared with any
+    pub fn info(&self, v: &Var) -> Option<&VarInfo> {
+        v.index().and_then(|i| self.info.get(&i))
+    }
+
+    /// Attaches metadata to a variable
+    pub fn set_info(&mut self, v: VarIndex, info: VarInfo) {
+        self.info.insert(v, info);
+    }
+
+    /// Returns the index of the variable with the given name
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.info
+            .iter()
+            .find(|(_, info)| info.name == name)
+            .and_then(|(i, _)| self.v.get(i).cloned())
+    }
+
+    /// Returns a variable slice filled with default values
+    ///
+    /// Variables without metadata (including `X`, `Y`, and `Z`) are set to 0.
+    pub fn defaults(&self) -> Vec<f32> {
+        let mut out = vec![0.0; self.len()];
+        for (i, info) in &self.info {
+            if let Some(j) = self.v.get(i) {
+                out[*j] = info.default as f32;
+            }
+        }
+        out
+    }
+
     /// Checks whether tracing arguments are valid
     pub fn check_tracing_arguments<T>(&self, vars: &[T]) -> Result<(), Error> {
         if vars.len() < self.len() {
@@ -0,0 +1,83 @@
+//! Interval evaluation which reports bounds for every node in an expression
+use super::{GenericVmTape, VmData, VmIntervalEval};
+use crate::{
+    Context, Error,
+    context::Node,
+    eval::{Tape, TracingEvaluator},
+    types::Interval,
+    var::VarMap,
+};
+use std::sync::Arc;
+
+/// Interval evaluator which reports the bounds of every subexpression
+///
+/// This is intended for visualization and debugging: for a given region, it
+/// computes the interval of every [`Node`] reachable from a set of roots, so
+/// that tools can show (for example) how close each branch of a `min` or
+/// `max` is to being selected.
+///
+/// Internally, every node is made into an output of a single VM tape, so
+/// bounds are identical to those computed by [`VmIntervalEval`]; however,
+/// evaluation is much slower than evaluating the roots alone.
+///
+/// ```
+/// # use fidget_core::{context::Context, types::Interval, vm::VmNodeBoundsEval};
+/// # use fidget_core::var::Var;
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let y = ctx.y();
+/// let a = ctx.sub(x, 1.0)?;
+/// let root = ctx.min(a, y)?;
+///
+/// let mut eval = VmNodeBoundsEval::new(&ctx, &[root])?;
+/// let mut args = [Interval::from(0.0); 2];
+/// args[eval.vars()[&Var::X]] = Interval::new(0.0, 1.0);
+/// args[eval.vars()[&Var::Y]] = Interval::new(2.0, 3.0);
+///
+/// let bounds = eval.eval(&args)?.collect::<std::collections::HashMap<_, _>>();
+/// assert_eq!(bounds[&a], Interval::new(-1.0, 0.0));
+/// assert_eq!(bounds[&root], Interval::new(-1.0, 0.0));
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+pub struct VmNodeBoundsEval {
+    /// Nodes in output order
+    nodes: Vec<Node>,
+    tape: GenericVmTape<{ u8::MAX as usize }>,
+    eval: VmIntervalEval<{ u8::MAX as usize }>,
+}
+
+impl VmNodeBoundsEval {
+    /// Builds a new evaluator for every node reachable from `roots`
+    pub fn new(ctx: &Context, roots: &[Node]) -> Result<Self, Error> {
+        let nodes = ctx.reachable(roots)?;
+        let data = VmData::new(ctx, &nodes)?;
+        Ok(Self {
+            nodes,
+            tape: GenericVmTape(Arc::new(data)),
+            eval: VmIntervalEval::default(),
+        })
+    }
+
+    /// Returns the nodes being evaluated, in the order of evaluation results
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Returns the mapping from [`Var`](crate::var::Var) to input index
+    pub fn vars(&self) -> &VarMap {
+        self.tape.vars()
+    }
+
+    /// Evaluates every node over the given input intervals
+    ///
+    /// `vars` is indexed by [`vars()`](Self::vars), as in
+    /// [`TracingEvaluator::eval`].  Returns an iterator over `(node, bounds)`
+    /// pairs, in the same order as [`nodes()`](Self::nodes).
+    pub fn eval(
+        &mut self,
+        vars: &[Interval],
+    ) -> Result<impl Iterator<Item = (Node, Interval)> + '_, Error> {
+        let (out, _trace) = self.eval.eval(&self.tape, vars)?;
+        Ok(self.nodes.iter().cloned().zip(out.iter().cloned()))
+    }
+}
@@ -1,13 +1,17 @@
 //! General-purpose tapes for use during evaluation or further compilation
 use crate::{
     Error,
-    compiler::{RegOp, RegTape, RegisterAllocator, SsaOp, SsaTape},
+    compiler::{
+        BuildBudget, Clause, Operand, RegOp, RegTape, RegisterAllocator,
+        ShaderTarget, SsaOp, SsaTape, disassemble_op, var_names, write_c,
+        write_function,
+    },
     context::{Context, Node},
-    var::VarMap,
+    var::{Var, VarIndex, VarInfo, VarMap},
     vm::Choice,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 /// A flattened math expression, ready for evaluation or further compilation.
 ///
@@ -77,8 +81,33 @@ pub struct VmData<const N: usize = { u8::MAX as usize }> {
 impl<const N: usize> VmData<N> {
     /// Builds a new tape for the given node
     pub fn new(context: &Context, nodes: &[Node]) -> Result<Self, Error> {
-        let (ssa, vars) = SsaTape::new(context, nodes)?;
+        Self::new_with_budget(context, nodes, &BuildBudget::default())
+    }
+
+    /// Builds a new tape for the given node, subject to a [`BuildBudget`]
+    pub fn new_with_budget(
+        context: &Context,
+        nodes: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "build_tape",
+            roots = nodes.len(),
+            ssa_len = tracing::field::Empty,
+            asm_len = tracing::field::Empty,
+        )
+        .entered();
+
+        let budget = budget.start();
+        let (ssa, vars) = SsaTape::build(context, nodes, &budget)?;
         let asm = RegTape::new::<N>(&ssa);
+        budget.check_time()?;
+
+        #[cfg(feature = "tracing")]
+        span.record("ssa_len", ssa.len())
+            .record("asm_len", asm.len());
+
         Ok(Self {
             ssa,
             asm,
@@ -96,6 +125,17 @@ impl<const N: usize> VmData<N> {
         self.asm.is_empty()
     }
 
+    /// Returns the approximate number of heap bytes held by this tape
+    ///
+    /// This includes the [`VarMap`], which may be shared with other tapes
+    /// built by [`VmData::simplify`].
+    pub fn memory_usage(&self) -> usize {
+        self.ssa.memory_usage()
+            + self.asm.memory_usage()
+            + std::mem::size_of::<VarMap>()
+            + self.vars.memory_usage()
+    }
+
     /// Returns the number of choice (min/max) nodes in the tape.
     ///
     /// This is required because some evaluators pre-allocate spaces for the
@@ -127,6 +167,14 @@ impl<const N: usize> VmData<N> {
         workspace: &mut VmWorkspace<M>,
         mut tape: VmData<M>,
     ) -> Result<VmData<M>, Error> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "simplify_tape",
+            in_len = self.ssa.tape.len(),
+            out_len = tracing::field::Empty,
+        )
+        .entered();
+
         if choices.len() != self.choice_count() {
             return Err(Error::BadChoiceSlice(
                 choices.len(),
@@ -303,6 +351,9 @@ impl<const N: usize> VmData<N> {
         assert_eq!(workspace.count as usize + 1, ops_out.len());
         let asm_tape = workspace.alloc.finalize();
 
+        #[cfg(feature = "tracing")]
+        span.record("out_len", ops_out.len());
+
         Ok(VmData {
             ssa: SsaTape {
                 tape: ops_out,
@@ -314,20 +365,558 @@ impl<const N: usize> VmData<N> {
         })
     }
 
+    /// Rebuilds this tape's expressions in a [`Context`]
+    ///
+    /// Each input variable is replaced by the corresponding node in `inputs`,
+    /// or by a new variable node if it's not present in the map.  Returns one
+    /// node per output, in output order.
+    ///
+    /// Nodes are built with the context's usual simplifications (e.g.
+    /// constant folding), so substituting constants for inputs simplifies the
+    /// resulting expression.
+    pub fn to_context(
+        &self,
+        ctx: &mut Context,
+        inputs: &HashMap<Var, Node>,
+    ) -> Result<Vec<Node>, Error> {
+        let mut vars = vec![Var::X; self.vars.len()];
+        for (v, i) in self.vars.iter() {
+            vars[i] = v;
+        }
+
+        let mut regs: HashMap<u32, Node> = HashMap::new();
+        let mut roots = vec![None; self.output_count()];
+        for c in self.clauses() {
+            let node = match c {
+                Clause::Input { var: i, .. } => {
+                    let v = vars[i as usize];
+                    match inputs.get(&v) {
+                        Some(n) => *n,
+                        None => ctx.var(v),
+                    }
+                }
+                Clause::Output { arg, index } => {
+                    roots[index as usize] = Some(regs[&arg]);
+                    continue;
+                }
+                Clause::Copy { arg, .. } => match arg {
+                    Operand::Reg(r) => regs[&r],
+                    Operand::Imm(imm) => ctx.constant(imm.into()),
+                },
+                Clause::Unary { op, arg, .. } => {
+                    ctx.op_unary_builder(regs[&arg], op)?
+                }
+                Clause::Binary { op, lhs, rhs, .. } => {
+                    let mut get = |o| match o {
+                        Operand::Reg(r) => regs[&r],
+                        Operand::Imm(imm) => ctx.constant(imm.into()),
+                    };
+                    let (lhs, rhs) = (get(lhs), get(rhs));
+                    ctx.op_binary_builder(lhs, rhs, op)?
+                }
+            };
+            regs.insert(c.output().unwrap(), node);
+        }
+        roots
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::BadTapeData("missing output"))
+    }
+
+    /// Builds a tape from nodes in a scratch [`Context`], copying variable
+    /// metadata from the given maps
+    fn rebuild(
+        ctx: &Context,
+        roots: &[Node],
+        sources: &[&VarMap],
+    ) -> Result<Self, Error> {
+        let mut out = Self::new(ctx, roots)?;
+        let out_vars = Arc::get_mut(&mut out.vars).unwrap();
+        for vars in sources {
+            for (v, _) in vars.iter() {
+                if out_vars.get(&v).is_none() {
+                    continue;
+                }
+                if let (Some(i), Some(info)) = (v.index(), vars.info(&v)) {
+                    out_vars.set_info(i, info.clone());
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Builds a new tape with the given variable replaced by a constant
+    ///
+    /// The tape is rebuilt through a [`Context`] (see
+    /// [`to_context`](Self::to_context)), so every operation which depended on
+    /// `var` is constant-folded and re-simplified; for example, `min` / `max`
+    /// branches which become constant are removed if they're never selected.
+    /// The resulting tape no longer has `var` as an input.  Variable metadata
+    /// (see [`VarInfo`]) is preserved.
+    ///
+    /// Choice indices in the new tape are unrelated to those in `self`, so
+    /// traces from the original tape must not be used to simplify it.
+    pub fn specialize(&self, var: Var, value: f32) -> Result<Self, Error> {
+        let mut ctx = Context::new();
+        let c = ctx.constant(value.into());
+        let roots = self.to_context(&mut ctx, &[(var, c)].into())?;
+        Self::rebuild(&ctx, &roots, &[&self.vars])
+    }
+
+    /// Builds a tape which evaluates `self` on the outputs of `inner`
+    ///
+    /// Output `i` of `inner` is wired to the input variable `wiring[i]` of
+    /// `self`; for example, if `inner` computes a coordinate warp `g(x, y, z)`
+    /// with three outputs, then `self.compose(&inner, &[Var::X, Var::Y,
+    /// Var::Z])` computes `f(g(x, y, z))`.  Inputs of `self` which aren't in
+    /// `wiring` are passed through unchanged, so the resulting tape's inputs
+    /// are the union of the inputs of `inner` and any unwired inputs of
+    /// `self`.
+    ///
+    /// The two tapes are stitched together in a [`Context`] and rebuilt, so
+    /// the result is simplified as a whole.  Variable metadata is preserved.
+    ///
+    /// Returns [`Error::BadOutputCount`] if `wiring` doesn't have one entry
+    /// per output of `inner`.
+    pub fn compose(&self, inner: &Self, wiring: &[Var]) -> Result<Self, Error> {
+        if wiring.len() != inner.output_count() {
+            return Err(Error::BadOutputCount(
+                inner.output_count(),
+                wiring.len(),
+            ));
+        }
+        let mut ctx = Context::new();
+        let outputs = inner.to_context(&mut ctx, &HashMap::new())?;
+        let inputs = wiring.iter().cloned().zip(outputs).collect();
+        let roots = self.to_context(&mut ctx, &inputs)?;
+        Self::rebuild(&ctx, &roots, &[&self.vars, &inner.vars])
+    }
+
+    /// Builds a tape which computes partial derivatives of every output
+    ///
+    /// The resulting tape has `vars.len()` outputs per output of `self`, in
+    /// output-major order: output `i * vars.len() + j` is the derivative of
+    /// output `i` with respect to `vars[j]`.  Derivatives are built with
+    /// reverse-mode differentiation (see [`Context::gradient`]), then the
+    /// tape is rebuilt and simplified.  Variable metadata is preserved.
+    ///
+    /// Because the result is an ordinary tape, it can be evaluated with any
+    /// evaluator; in particular, interval evaluation produces bounds on the
+    /// gradient over a region.
+    pub fn gradient(&self, vars: &[Var]) -> Result<Self, Error> {
+        let mut ctx = Context::new();
+        let roots = self.to_context(&mut ctx, &HashMap::new())?;
+        let mut out = Vec::with_capacity(roots.len() * vars.len());
+        for r in roots {
+            out.extend(ctx.gradient(r, vars)?);
+        }
+        Self::rebuild(&ctx, &out, &[&self.vars])
+    }
+
+    /// Generates shader source for a function `float f(vec3 p)`
+    ///
+    /// The source is self-contained: it includes a small set of helper
+    /// functions (prefixed with `fidget_`) which match the VM's float
+    /// semantics, then the function itself, so it can be pasted into an
+    /// existing shader (e.g. a raymarcher).  See [`ShaderTarget`] for
+    /// supported languages; in MSL, the function is `float f(float3 p)`, and
+    /// in C, it's `float f(const float* p)` (reading X, Y, and Z from
+    /// `p[0..3]`).
+    ///
+    /// If `gradient` is true, the source also includes a function `vec3
+    /// f_grad(vec3 p)`, which returns the partial derivatives with respect to
+    /// X, Y, and Z (see [`gradient`](Self::gradient)); in C, it's `void
+    /// f_grad(const float* p, float* out)`.
+    ///
+    /// Returns [`Error::BadOutputCount`] if the tape doesn't have exactly one
+    /// output, or [`Error::BadShaderVar`] if it uses variables other than
+    /// [`Var::X`], [`Var::Y`], and [`Var::Z`].
+    pub fn to_shader(
+        &self,
+        target: ShaderTarget,
+        gradient: bool,
+    ) -> Result<String, Error> {
+        if self.output_count() != 1 {
+            return Err(Error::BadOutputCount(self.output_count(), 1));
+        }
+        let mut out = target.prelude();
+        out += "\n";
+        write_function(target, &mut out, "f", self.clauses(), &self.vars, 1)?;
+        if gradient {
+            let g = self.gradient(&[Var::X, Var::Y, Var::Z])?;
+            out += "\n";
+            write_function(
+                target,
+                &mut out,
+                "f_grad",
+                g.clauses(),
+                &g.vars,
+                3,
+            )?;
+        }
+        Ok(out)
+    }
+
+    /// Generates GLSL source for a function `float f(vec3 p)`
+    ///
+    /// This is equivalent to [`to_shader`](Self::to_shader) with
+    /// [`ShaderTarget::Glsl`].
+    pub fn to_glsl(&self, gradient: bool) -> Result<String, Error> {
+        self.to_shader(ShaderTarget::Glsl, gradient)
+    }
+
+    /// Generates Metal Shading Language source for a function `float
+    /// f(float3 p)`
+    ///
+    /// This is equivalent to [`to_shader`](Self::to_shader) with
+    /// [`ShaderTarget::Msl`].
+    pub fn to_msl(&self, gradient: bool) -> Result<String, Error> {
+        self.to_shader(ShaderTarget::Msl, gradient)
+    }
+
+    /// Generates portable C99 source which evaluates the tape
+    ///
+    /// The source depends only on `<math.h>` and `<stddef.h>`, so it can be
+    /// compiled into firmware or plugins where JIT compilation isn't
+    /// possible.  It defines a function
+    /// ```c
+    /// void name(const float* vars, float* out);
+    /// ```
+    /// which reads inputs from `vars` (ordered by the tape's
+    /// [`VarMap`]) and writes one value per output to `out`.
+    ///
+    /// If `slice` is true, the source also defines a function which
+    /// evaluates many points at once, matching the layout used by
+    /// [`BulkEvaluator`](crate::eval::BulkEvaluator):
+    /// ```c
+    /// void name_slice(const float* const* vars, float* const* out, size_t n);
+    /// ```
+    ///
+    /// The source is generated by the same lowering as
+    /// [`to_shader`](Self::to_shader) with [`ShaderTarget::C`]; unlike that
+    /// function, it accepts any number of outputs and any variables.
+    /// Helper functions are declared `static`, so sources for multiple tapes
+    /// (with different names) may be linked into the same program.  NaN and
+    /// infinity are handled like the VM, so the source should not be compiled
+    /// with `-ffast-math`.
+    pub fn to_c(&self, name: &str, slice: bool) -> String {
+        let mut out = String::new();
+        write_c(&mut out, name, &self.ssa, slice);
+        out
+    }
+
+    /// Produces an iterator that visits [`Clause`] values in evaluation order
+    ///
+    /// See [`SsaTape::clauses`] for details.
+    pub fn clauses(&self) -> impl Iterator<Item = Clause> + '_ {
+        self.ssa.clauses()
+    }
+
     /// Produces an iterator that visits [`RegOp`] values in evaluation order
     pub fn iter_asm(&self) -> impl Iterator<Item = RegOp> + '_ {
         self.asm.iter().cloned().rev()
     }
 
-    /// Pretty-prints the inner SSA tape
+    /// Returns a line of disassembly for each [`RegOp`], in evaluation order
+    ///
+    /// Lines match the operations from [`iter_asm`](Self::iter_asm), and are
+    /// in the format of [`RegTape::disassemble`] (without trailing newlines).
+    pub fn disassemble_asm(&self) -> impl Iterator<Item = String> + '_ {
+        let names = var_names(&self.vars);
+        self.iter_asm().map(move |op| {
+            let mut out = String::new();
+            disassemble_op(op, &mut out, &names);
+            out.truncate(out.trim_end().len());
+            out
+        })
+    }
+
+    /// Converts the inner SSA tape into a GraphViz drawing
+    ///
+    /// See [`SsaTape::dot`] for details.
+    pub fn dot(&self) -> String {
+        self.ssa.dot()
+    }
+
+    /// Returns a human-readable listing of the SSA and register-allocated
+    /// tapes, with inputs annotated by variable name
+    ///
+    /// See [`SsaTape::disassemble`] and [`RegTape::disassemble`] for details.
+    pub fn disassemble(&self) -> String {
+        format!(
+            "# SSA\n{}\n# Registers ({N})\n{}",
+            self.ssa.disassemble(Some(&self.vars)),
+            self.asm.disassemble(Some(&self.vars)),
+        )
+    }
+
+    /// Pretty-prints the SSA and register-allocated tapes to `stdout`
+    ///
+    /// See [`disassemble`](Self::disassemble) for details.
     pub fn pretty_print(&self) {
-        self.ssa.pretty_print();
-        for a in self.iter_asm() {
-            println!("{a:?}");
+        print!("{}", self.disassemble());
+    }
+
+    /// Serializes the tape into a compact, versioned binary format
+    ///
+    /// The format stores the SSA tape, choice and output counts, and the
+    /// variable table (including [`VarInfo`] metadata).  The register-allocated
+    /// tape is rebuilt by [`from_bytes`](Self::from_bytes), so data may be
+    /// loaded with a different register count `N`.
+    ///
+    /// All values are little-endian:
+    /// - Magic bytes `b"FDGT"` and a `u32` format version (currently 1)
+    /// - `u32` choice count, `u32` output count, and `u32` operation count
+    /// - Operations (in the same reversed order as [`SsaTape::tape`]), each
+    ///   packed as four `u32` words `[opcode, out, lhs, rhs]`; immediates are
+    ///   stored as `f32` bits in the `rhs` slot
+    /// - `u32` variable count, then each variable in index order, as a tag
+    ///   byte (0-2 for X-Z, 3 for [`Var::V`] followed by its `u64` index)
+    /// - `u32` metadata count, then each entry as a `u32` variable index,
+    ///   a `u32`-length-prefixed UTF-8 name, an `f64` default, and a `u8` flag
+    ///   followed by two `f64` range bounds if present
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, vm::VmData};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let root = ctx.sin(x)?;
+    /// let data = VmData::<255>::new(&ctx, &[root])?;
+    ///
+    /// let bytes = data.to_bytes();
+    /// let out = VmData::<255>::from_bytes(&bytes)?;
+    /// assert_eq!(out.iter_asm().collect::<Vec<_>>(),
+    ///            data.iter_asm().collect::<Vec<_>>());
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+        out.extend(TAPE_MAGIC);
+        out.extend(TAPE_VERSION.to_le_bytes());
+
+        let ssa = &self.ssa;
+        for v in [ssa.choice_count, ssa.output_count, ssa.tape.len()] {
+            out.extend((v as u32).to_le_bytes());
+        }
+        for op in &ssa.tape {
+            for w in op.to_words() {
+                out.extend(w.to_le_bytes());
+            }
+        }
+
+        let mut vars = self.vars.iter().collect::<Vec<_>>();
+        vars.sort_by_key(|(_, i)| *i);
+        out.extend((vars.len() as u32).to_le_bytes());
+        for (v, _) in &vars {
+            match v {
+                Var::X => out.push(0),
+                Var::Y => out.push(1),
+                Var::Z => out.push(2),
+                Var::V(i) => {
+                    out.push(3);
+                    out.extend(i.raw().to_le_bytes());
+                }
+            }
+        }
+
+        let info = vars
+            .iter()
+            .filter_map(|(v, i)| self.vars.info(v).map(|info| (*i, info)))
+            .collect::<Vec<_>>();
+        out.extend((info.len() as u32).to_le_bytes());
+        for (i, info) in info {
+            out.extend((i as u32).to_le_bytes());
+            out.extend((info.name.len() as u32).to_le_bytes());
+            out.extend(info.name.as_bytes());
+            out.extend(info.default.to_le_bytes());
+            match &info.range {
+                Some(r) => {
+                    out.push(1);
+                    out.extend(r.start().to_le_bytes());
+                    out.extend(r.end().to_le_bytes());
+                }
+                None => out.push(0),
+            }
         }
+        out
+    }
+
+    /// Deserializes a tape written by [`to_bytes`](Self::to_bytes)
+    ///
+    /// The tape is validated before register allocation; malformed data
+    /// returns [`Error::BadTapeData`], and data from a newer version of the
+    /// format returns [`Error::BadTapeVersion`].
+    pub fn from_bytes(mut data: &[u8]) -> Result<Self, Error> {
+        let r = &mut data;
+        if take::<4>(r)? != TAPE_MAGIC {
+            return Err(Error::BadTapeData("missing magic bytes"));
+        }
+        let version = read_u32(r)?;
+        if version != TAPE_VERSION {
+            return Err(Error::BadTapeVersion(version));
+        }
+
+        let choice_count = read_u32(r)? as usize;
+        let output_count = read_u32(r)? as usize;
+        let op_count = read_u32(r)? as usize;
+        if op_count > r.len() / 16 {
+            return Err(Error::BadTapeData("truncated data"));
+        }
+        // Each output and choice needs its own operation; checking this here
+        // avoids huge allocations (or capacity overflow on 32-bit targets)
+        // when validating corrupted data.
+        if output_count > op_count || choice_count > op_count {
+            return Err(Error::BadTapeData("invalid output or choice count"));
+        }
+        let mut tape = Vec::with_capacity(op_count);
+        for _ in 0..op_count {
+            let w = [read_u32(r)?, read_u32(r)?, read_u32(r)?, read_u32(r)?];
+            let op = SsaOp::from_words(w)
+                .ok_or(Error::BadTapeData("unknown opcode"))?;
+            tape.push(op);
+        }
+
+        let mut vars = VarMap::new();
+        let var_count = read_u32(r)? as usize;
+        let mut seen = vec![];
+        for _ in 0..var_count {
+            let v = match take::<1>(r)?[0] {
+                0 => Var::X,
+                1 => Var::Y,
+                2 => Var::Z,
+                3 => Var::V(VarIndex::from_raw(u64::from_le_bytes(take::<8>(
+                    r,
+                )?))),
+                _ => return Err(Error::BadTapeData("invalid variable tag")),
+            };
+            if vars.get(&v).is_some() {
+                return Err(Error::BadTapeData("duplicate variable"));
+            }
+            vars.insert(v);
+            seen.push(v);
+        }
+        let info_count = read_u32(r)? as usize;
+        for _ in 0..info_count {
+            let i = read_u32(r)? as usize;
+            let v = seen
+                .get(i)
+                .and_then(|v| v.index())
+                .ok_or(Error::BadTapeData("invalid variable metadata"))?;
+            let len = read_u32(r)? as usize;
+            if len > r.len() {
+                return Err(Error::BadTapeData("truncated data"));
+            }
+            let (name, rest) = r.split_at(len);
+            *r = rest;
+            let name = std::str::from_utf8(name)
+                .map_err(|_| Error::BadTapeData("invalid variable name"))?
+                .to_owned();
+            let default = f64::from_le_bytes(take::<8>(r)?);
+            let range = match take::<1>(r)?[0] {
+                0 => None,
+                1 => {
+                    let lo = f64::from_le_bytes(take::<8>(r)?);
+                    let hi = f64::from_le_bytes(take::<8>(r)?);
+                    Some(lo..=hi)
+                }
+                _ => return Err(Error::BadTapeData("invalid range flag")),
+            };
+            vars.set_info(
+                v,
+                VarInfo {
+                    name,
+                    default,
+                    range,
+                },
+            );
+        }
+        if !r.is_empty() {
+            return Err(Error::BadTapeData("trailing bytes"));
+        }
+
+        let ssa = SsaTape {
+            tape,
+            choice_count,
+            output_count,
+        };
+        validate_ssa(&ssa, vars.len())?;
+        let asm = RegTape::new::<N>(&ssa);
+        Ok(Self {
+            ssa,
+            asm,
+            vars: vars.into(),
+        })
     }
 }
 
+/// Magic bytes at the start of serialized tape data
+const TAPE_MAGIC: [u8; 4] = *b"FDGT";
+
+/// Current version of the serialized tape format
+const TAPE_VERSION: u32 = 1;
+
+/// Removes `K` bytes from the front of a slice
+fn take<const K: usize>(data: &mut &[u8]) -> Result<[u8; K], Error> {
+    let (head, rest) = data
+        .split_first_chunk::<K>()
+        .ok_or(Error::BadTapeData("truncated data"))?;
+    *data = rest;
+    Ok(*head)
+}
+
+fn read_u32(data: &mut &[u8]) -> Result<u32, Error> {
+    take::<4>(data).map(u32::from_le_bytes)
+}
+
+/// Checks that a deserialized tape is well-formed
+///
+/// Every register must be written exactly once before it is read and must be
+/// read at least once (register allocation assumes there's no dead code),
+/// counts must match the contents of the tape, and input indices must be in
+/// range.
+fn validate_ssa(ssa: &SsaTape, var_count: usize) -> Result<(), Error> {
+    let mut written = vec![false; ssa.tape.len()];
+    let mut read = vec![false; ssa.tape.len()];
+    let mut outputs = vec![false; ssa.output_count];
+    let mut choice_count = 0;
+    let bad = |s| Err(Error::BadTapeData(s));
+    for op in ssa.tape.iter().rev() {
+        for i in op.inputs() {
+            if !written.get(i as usize).copied().unwrap_or(false) {
+                return bad("register read before it is written");
+            }
+            read[i as usize] = true;
+        }
+        match *op {
+            SsaOp::Output(_, i) => match outputs.get_mut(i as usize) {
+                Some(o @ false) => *o = true,
+                _ => return bad("invalid output index"),
+            },
+            SsaOp::Input(_, i) if i as usize >= var_count => {
+                return bad("invalid input index");
+            }
+            _ => (),
+        }
+        if let Some(out) = op.output() {
+            match written.get_mut(out as usize) {
+                Some(w @ false) => *w = true,
+                _ => return bad("register written more than once"),
+            }
+        }
+        choice_count += op.has_choice() as usize;
+    }
+    if choice_count != ssa.choice_count {
+        return bad("choice count does not match tape");
+    }
+    if outputs.iter().any(|o| !o) {
+        return bad("output count does not match tape");
+    }
+    if written.iter().zip(&read).any(|(w, r)| *w && !*r) {
+        return bad("register written but never read");
+    }
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Data structures used during [`VmData::simplify`]
@@ -415,4 +1004,560 @@ mod test {
             .unwrap();
         assert_eq!(next.len(), 6);
     }
+
+    #[test]
+    fn disassemble() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let r = ctx.declare_var("radius", 1.0, None).unwrap();
+        let a = ctx.sub(2.0, x).unwrap();
+        let b = ctx.max(a, r).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[b]).unwrap();
+        let text = data.disassemble();
+        assert!(text.contains("$0 = INPUT[1]  # radius\n"), "{text}");
+        assert!(text.contains("$1 = INPUT[0]  # X\n"), "{text}");
+        assert!(text.contains("$2 = SUB 2 $1\n"), "{text}");
+        assert!(text.contains("$3 = MAX $0 $2\n"), "{text}");
+        assert!(text.contains("OUTPUT[0] = $3\n"), "{text}");
+        assert!(text.contains("r1 = SUB 2 r1\n"), "{text}");
+
+        // Without a variable map, inputs aren't annotated
+        let text = data.ssa.disassemble(None);
+        assert!(!text.contains('#'), "{text}");
+    }
+
+    #[test]
+    fn specialize() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let z = ctx.z();
+        let r = ctx.declare_var("radius", 1.0, None).unwrap();
+        let a = ctx.add(z, 1.0).unwrap();
+        let b = ctx.min(a, 2.0).unwrap();
+        let c = ctx.mul(b, r).unwrap();
+        let root = ctx.add(x, c).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[root]).unwrap();
+        assert_eq!(data.choice_count(), 1);
+        assert!(data.vars.get(&Var::Z).is_some());
+
+        // min(0 + 1, 2) is folded to a constant
+        let next = data.specialize(Var::Z, 0.0).unwrap();
+        assert_eq!(next.choice_count(), 0);
+        assert_eq!(next.output_count(), 1);
+        assert!(next.len() < data.len());
+        assert!(next.vars.get(&Var::Z).is_none());
+        assert_eq!(next.vars.len(), 2);
+        assert!(next.vars.find("radius").is_some());
+
+        // Specializing on an unused variable leaves the tape unchanged
+        let same = data.specialize(Var::new(), 0.0).unwrap();
+        assert_eq!(same.len(), data.len());
+        assert_eq!(same.choice_count(), 1);
+    }
+
+    #[test]
+    fn compose() {
+        use crate::eval::{Function, TracingEvaluator};
+        use crate::vm::VmFunction;
+
+        // f(x, y, s) = x * s + y
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.declare_var("scale", 1.0, None).unwrap();
+        let xs = ctx.mul(x, s).unwrap();
+        let f = ctx.add(xs, y).unwrap();
+        let f = VmData::<255>::new(&ctx, &[f]).unwrap();
+
+        // g(x, y, t) = (x + t, 2 * y)
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let t = ctx.declare_var("offset", 0.0, None).unwrap();
+        let gx = ctx.add(x, t).unwrap();
+        let gy = ctx.mul(y, 2.0).unwrap();
+        let g = VmData::<255>::new(&ctx, &[gx, gy]).unwrap();
+
+        assert!(matches!(
+            f.compose(&g, &[Var::X]),
+            Err(Error::BadOutputCount(2, 1))
+        ));
+
+        // f(g(x, y, t), s) = (x + t) * s + 2 * y
+        let h = f.compose(&g, &[Var::X, Var::Y]).unwrap();
+        assert_eq!(h.output_count(), 1);
+        assert_eq!(h.vars.len(), 4);
+        let h = VmFunction::from(h);
+        let vars = h.vars();
+        let mut args = [0.0; 4];
+        args[vars[&Var::X]] = 1.0;
+        args[vars[&Var::Y]] = 2.0;
+        args[vars.find("offset").unwrap()] = 3.0;
+        args[vars.find("scale").unwrap()] = 5.0;
+        let mut eval = VmFunction::new_point_eval();
+        let (out, _) =
+            eval.eval(&h.point_tape(Default::default()), &args).unwrap();
+        assert_eq!(out[0], (1.0 + 3.0) * 5.0 + 2.0 * 2.0);
+    }
+
+    #[test]
+    fn simplify_reuses_storage() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let xy = ctx.min(x, y).unwrap();
+        let xyz = ctx.max(xy, z).unwrap();
+        let data = VmData::<255>::new(&ctx, &[xyz]).unwrap();
+
+        let choices = [Choice::Left, Choice::Both];
+        let mut workspace = VmWorkspace::default();
+        let a = data
+            .simplify::<255>(&choices, &mut workspace, Default::default())
+            .unwrap();
+        let ssa_ptr = a.ssa.tape.as_ptr();
+        let asm_ptr = a.asm.iter().next().unwrap() as *const RegOp;
+
+        // Simplifying again with recycled storage and the same workspace
+        // reuses the previous allocations.
+        let b = data.simplify::<255>(&choices, &mut workspace, a).unwrap();
+        assert_eq!(b.ssa.tape.as_ptr(), ssa_ptr);
+        assert_eq!(b.asm.iter().next().unwrap() as *const RegOp, asm_ptr);
+    }
+
+    #[test]
+    fn gradient() {
+        use crate::eval::{Function, TracingEvaluator};
+        use crate::vm::VmFunction;
+
+        // f = (x * y, x + z)
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.mul(x, y).unwrap();
+        let b = ctx.add(x, z).unwrap();
+        let f = VmData::<255>::new(&ctx, &[a, b]).unwrap();
+
+        let g = f.gradient(&[Var::X, Var::Y]).unwrap();
+        assert_eq!(g.output_count(), 4);
+        let g = VmFunction::from(g);
+        let vars = g.vars();
+        let mut args = [0.0; 2];
+        args[vars[&Var::X]] = 2.0;
+        args[vars[&Var::Y]] = 3.0;
+        let mut eval = VmFunction::new_point_eval();
+        let (out, _) =
+            eval.eval(&g.point_tape(Default::default()), &args).unwrap();
+        assert_eq!(out, [3.0, 2.0, 1.0, 0.0]);
+    }
+
+    /// Parses and validates generated GLSL with `naga`
+    ///
+    /// The source is wrapped in a fragment shader which calls `f` (and
+    /// `f_grad`, if present), so that every helper is type-checked.
+    fn validate_glsl(src: &str) -> naga::Module {
+        let grad = if src.contains("f_grad") {
+            "f_grad(p)"
+        } else {
+            "vec3(0.0)"
+        };
+        let shader = format!(
+            "#version 450\n\
+             layout(location = 0) out vec4 color;\n\
+             {src}\n\
+             void main() {{\n\
+             \x20   vec3 p = gl_FragCoord.xyz;\n\
+             \x20   color = vec4({grad}, f(p));\n\
+             }}\n"
+        );
+        let options =
+            naga::front::glsl::Options::from(naga::ShaderStage::Fragment);
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&options, &shader)
+            .unwrap_or_else(|e| panic!("invalid GLSL: {e:?}\n{shader}"));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("invalid GLSL: {e:?}\n{shader}"));
+        module
+    }
+
+    #[test]
+    fn to_shader() {
+        let (mut ctx, sum) = crate::eval::test::build_all_ops();
+        let sum = ctx.max(sum, -2.5).unwrap();
+        let inf = ctx.constant(f64::INFINITY);
+        let sum = ctx.min(sum, inf).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[sum]).unwrap();
+        let module = validate_glsl(&data.to_glsl(true).unwrap());
+        let names = |m: &naga::Module| {
+            m.functions
+                .iter()
+                .filter_map(|(_, f)| f.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let fns = names(&module);
+        assert!(fns.iter().any(|n| n == "f"));
+        assert!(fns.iter().any(|n| n == "f_grad"));
+
+        let module = validate_glsl(&data.to_glsl(false).unwrap());
+        let fns = names(&module);
+        assert!(fns.iter().any(|n| n == "f"));
+        assert!(!fns.iter().any(|n| n == "f_grad"));
+
+        // There's no MSL parser in `naga`, so check the syntax differences
+        let src = data.to_msl(true).unwrap();
+        assert!(src.starts_with("#include <metal_stdlib>"));
+        assert!(src.contains("float f(float3 p) {"));
+        assert!(src.contains("float3 f_grad(float3 p) {"));
+        assert!(src.contains("(-2.5f)"));
+        assert!(src.contains("as_type<float>(0x7f800000u)"));
+        assert!(src.contains("atan2("));
+        assert!(!src.contains("vec3"));
+    }
+
+    #[test]
+    fn to_shader_errors() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let w = ctx.var(Var::W);
+        let xw = ctx.add(x, w).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[xw]).unwrap();
+        assert!(matches!(
+            data.to_glsl(false),
+            Err(Error::BadShaderVar(Var::W))
+        ));
+
+        let data = VmData::<255>::new(&ctx, &[x, x]).unwrap();
+        assert!(matches!(
+            data.to_glsl(false),
+            Err(Error::BadOutputCount(2, 1))
+        ));
+    }
+
+    #[test]
+    fn to_c() {
+        let mut ctx = Context::new();
+        let (x, y) = (ctx.x(), ctx.y());
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.modulo(a, -2.5).unwrap();
+        let c = ctx.sin(x).unwrap();
+        let nan = ctx.constant(f64::NAN);
+        let d = ctx.max(c, nan).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[b, d]).unwrap();
+        let src = data.to_c("shape", false);
+        assert!(src.contains("void shape(const float* vars, float* out) {"));
+        assert!(src.contains("fidget_mod(r2, (-2.5f))"));
+        assert!(src.contains("out[1] = "));
+        assert!(!src.contains("shape_slice"));
+
+        let src = data.to_c("shape", true);
+        assert!(src.contains(
+            "void shape_slice(const float* const* vars, float* const* out, \
+             size_t n) {"
+        ));
+        assert!(src.contains("vars[0][i]"));
+
+        let src = data.to_shader(ShaderTarget::C, true).unwrap_err();
+        assert!(matches!(src, Error::BadOutputCount(2, 1)));
+    }
+
+    /// Compiles generated C with the system compiler and compares its results
+    /// against the VM
+    #[test]
+    fn to_c_compiled() {
+        use crate::eval::{Function, TracingEvaluator};
+        use crate::vm::VmFunction;
+        use std::{fmt::Write, process::Command};
+
+        if Command::new("cc").arg("--version").output().is_err() {
+            eprintln!("skipping to_c_compiled: no C compiler found");
+            return;
+        }
+
+        let (mut ctx, sum) = crate::eval::test::build_all_ops();
+        let (x, y, z) = (ctx.x(), ctx.y(), ctx.z());
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.modulo(a, z).unwrap();
+        let c = ctx.sin(y).unwrap();
+        let nan = ctx.constant(f64::NAN);
+        let d = ctx.max(c, nan).unwrap();
+        let data = VmData::<255>::new(&ctx, &[sum, b, d]).unwrap();
+        let single = VmData::<255>::new(&ctx, &[sum]).unwrap();
+
+        // X stays within (0, 1) so that the unary operations in `sum` are in
+        // their domains; Y and Z cover negative values and zero.
+        let n = 24;
+        let pts: Vec<[f32; 3]> = (0..n)
+            .map(|i| {
+                let t = i as f32 / n as f32;
+                [0.02 + t * 0.95, t * 8.0 - 4.0, 3.0 - (i % 7) as f32]
+            })
+            .collect();
+
+        let mut src = data.to_c("shape", true);
+        let shader = single.to_shader(ShaderTarget::C, true).unwrap();
+        src += shader.strip_prefix(&ShaderTarget::C.prelude()).unwrap();
+        src +=
+            "\n#include <stdint.h>\n#include <stdio.h>\n#include <string.h>\n";
+        src += "static void print(float f) {\n";
+        src += "    uint32_t u;\n    memcpy(&u, &f, sizeof(u));\n";
+        src += "    printf(\"%08x\\n\", (unsigned)u);\n}\n";
+        for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+            write!(src, "static const float {name}[{n}] = {{").unwrap();
+            for p in &pts {
+                write!(src, "{:?}f, ", p[axis]).unwrap();
+            }
+            src += "};\n";
+        }
+        let vars = data.vars.clone();
+        let index = |v| vars[&v];
+        write!(
+            src,
+            "int main(void) {{
+    float out[3], o0[{n}], o1[{n}], o2[{n}], g[3];
+    float* outs[3] = {{o0, o1, o2}};
+    const float* vars[3];
+    vars[{ix}] = X;
+    vars[{iy}] = Y;
+    vars[{iz}] = Z;
+    shape_slice(vars, outs, {n});
+    for (int i = 0; i < {n}; ++i) {{
+        float v[3], p[3] = {{X[i], Y[i], Z[i]}};
+        v[{ix}] = X[i];
+        v[{iy}] = Y[i];
+        v[{iz}] = Z[i];
+        shape(v, out);
+        for (int j = 0; j < 3; ++j) {{
+            print(out[j]);
+            print(outs[j][i]);
+        }}
+        print(f(p));
+        f_grad(p, g);
+        for (int j = 0; j < 3; ++j) {{
+            print(g[j]);
+        }}
+    }}
+    return 0;
+}}
+",
+            ix = index(Var::X),
+            iy = index(Var::Y),
+            iz = index(Var::Z),
+        )
+        .unwrap();
+
+        let dir = std::env::temp_dir()
+            .join(format!("fidget-to-c-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (c_file, exe) = (dir.join("shape.c"), dir.join("shape"));
+        std::fs::write(&c_file, &src).unwrap();
+        let status = Command::new("cc")
+            .args(["-std=c99", "-O1", "-Wall", "-Werror", "-o"])
+            .arg(&exe)
+            .arg(&c_file)
+            .arg("-lm")
+            .status()
+            .unwrap();
+        assert!(status.success(), "failed to compile:\n{src}");
+        let output = Command::new(&exe).output().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut results = stdout
+            .lines()
+            .map(|line| f32::from_bits(u32::from_str_radix(line, 16).unwrap()));
+
+        let grad = single.gradient(&[Var::X, Var::Y, Var::Z]).unwrap();
+        let shape = VmFunction::from(data);
+        let grad = VmFunction::from(grad);
+        let mut eval = VmFunction::new_point_eval();
+        let check = |expected: f32, actual: f32| {
+            assert!(
+                (expected.is_nan() && actual.is_nan())
+                    || expected == actual
+                    || (expected - actual).abs()
+                        <= 1e-5 * expected.abs().max(1.0),
+                "expected {expected}, got {actual}"
+            );
+        };
+        for p in &pts {
+            let mut args = [0.0; 3];
+            for (axis, v) in [Var::X, Var::Y, Var::Z].into_iter().enumerate() {
+                args[index(v)] = p[axis];
+            }
+            let (out, _) = eval
+                .eval(&shape.point_tape(Default::default()), &args)
+                .unwrap();
+            let out = out.to_vec();
+            for &v in &out {
+                check(v, results.next().unwrap());
+                check(v, results.next().unwrap());
+            }
+            check(out[0], results.next().unwrap());
+
+            let mut args = [0.0; 3];
+            for (axis, v) in [Var::X, Var::Y, Var::Z].into_iter().enumerate() {
+                if let Some(i) = grad.vars().get(&v) {
+                    args[i] = p[axis];
+                }
+            }
+            let (out, _) = eval
+                .eval(&grad.point_tape(Default::default()), &args)
+                .unwrap();
+            for &v in out {
+                check(v, results.next().unwrap());
+            }
+        }
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn build_budget() {
+        let mut ctx = Context::new();
+        let mut sum = ctx.x();
+        for i in 0..10_000 {
+            let c = ctx.constant(i as f64);
+            let y = ctx.y();
+            let t = ctx.mul(y, c).unwrap();
+            sum = ctx.add(sum, t).unwrap();
+        }
+
+        let ok = BuildBudget {
+            max_ops: Some(100_000),
+            ..Default::default()
+        };
+        assert!(VmData::<255>::new_with_budget(&ctx, &[sum], &ok).is_ok());
+
+        let too_small = BuildBudget {
+            max_ops: Some(1000),
+            ..Default::default()
+        };
+        let r = VmData::<255>::new_with_budget(&ctx, &[sum], &too_small);
+        assert!(matches!(r, Err(Error::TapeTooLong(1000))));
+
+        // Small tapes are checked after the first pass
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.add(x, y).unwrap();
+        let tiny = BuildBudget {
+            max_ops: Some(3),
+            ..Default::default()
+        };
+        let r = VmData::<255>::new_with_budget(&ctx, &[s], &tiny);
+        assert!(matches!(r, Err(Error::TapeTooLong(3))));
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        use crate::{
+            eval::{Function, TracingEvaluator},
+            vm::VmFunction,
+        };
+
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let r = ctx.declare_var("radius", 0.5, Some(0.0..=2.0)).unwrap();
+        let t = ctx.var(Var::new());
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.max(a, 1.5).unwrap();
+        let c = ctx.atan2(z, t).unwrap();
+        let d = ctx.sub(b, r).unwrap();
+        let e = ctx.mul(c, 2.0).unwrap();
+        let root = ctx.add(d, e).unwrap();
+
+        let data = VmData::<255>::new(&ctx, &[root, a]).unwrap();
+        let bytes = data.to_bytes();
+        let out = VmData::<255>::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            out.iter_asm().collect::<Vec<_>>(),
+            data.iter_asm().collect::<Vec<_>>()
+        );
+        assert_eq!(out.choice_count(), data.choice_count());
+        assert_eq!(out.output_count(), 2);
+        assert_eq!(out.to_bytes(), bytes);
+
+        let vr = ctx.get_var(r).unwrap();
+        let vt = ctx.get_var(t).unwrap();
+        for v in [Var::X, Var::Y, Var::Z, vr, vt] {
+            assert_eq!(out.vars.get(&v), data.vars.get(&v));
+        }
+        assert_eq!(out.vars.info(&vr), data.vars.info(&vr));
+        assert_eq!(out.vars.find("radius"), data.vars.get(&vr));
+        assert!(out.vars.info(&vt).is_none());
+
+        // Evaluation results match, including with a different register count
+        let small = VmData::<2>::from_bytes(&bytes).unwrap();
+        assert!(small.len() > data.len());
+        let args = out.vars.defaults();
+        let f = VmFunction::from(out);
+        let g = crate::vm::GenericVmFunction::<2>::from(small);
+        let mut eval = VmFunction::new_point_eval();
+        let mut eval2 = crate::vm::GenericVmFunction::<2>::new_point_eval();
+        let (a, _) =
+            eval.eval(&f.point_tape(Default::default()), &args).unwrap();
+        let a = a.to_vec();
+        let (b, _) = eval2
+            .eval(&g.point_tape(Default::default()), &args)
+            .unwrap();
+        assert_eq!(a, b);
+
+        // Truncated or corrupted data is rejected without panicking
+        for i in 0..bytes.len() {
+            assert!(VmData::<255>::from_bytes(&bytes[..i]).is_err());
+            let mut bad = bytes.clone();
+            bad[i] ^= 0xA5;
+            let _ = VmData::<255>::from_bytes(&bad);
+        }
+        let mut bad = bytes.clone();
+        bad[4] = 2;
+        assert!(matches!(
+            VmData::<255>::from_bytes(&bad),
+            Err(Error::BadTapeVersion(2))
+        ));
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(matches!(
+            VmData::<255>::from_bytes(&long),
+            Err(Error::BadTapeData("trailing bytes"))
+        ));
+    }
+
+    #[test]
+    fn bytes_dead_register() {
+        // Register 1 is written but never read; this would panic during
+        // register allocation if it weren't rejected by validation.  Ops are
+        // stored in reverse evaluation order.
+        let tape = [
+            SsaOp::Output(0, 0),
+            SsaOp::CopyImm(0, 1.0),
+            SsaOp::CopyImm(1, 2.0),
+        ];
+        let mut bytes = vec![];
+        bytes.extend(TAPE_MAGIC);
+        bytes.extend(TAPE_VERSION.to_le_bytes());
+        for v in [0u32, 1, tape.len() as u32] {
+            bytes.extend(v.to_le_bytes());
+        }
+        for op in tape {
+            for w in op.to_words() {
+                bytes.extend(w.to_le_bytes());
+            }
+        }
+        bytes.extend(0u32.to_le_bytes()); // variables
+        bytes.extend(0u32.to_le_bytes()); // variable metadata
+        assert!(matches!(
+            VmData::<255>::from_bytes(&bytes),
+            Err(Error::BadTapeData("register written but never read"))
+        ));
+    }
 }
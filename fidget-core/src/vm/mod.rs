@@ -1,7 +1,7 @@
 //! Simple virtual machine for shape evaluation
 use crate::{
     Context, Error,
-    compiler::RegOp,
+    compiler::{BuildBudget, RegOp, ShaderTarget},
     context::Node,
     eval::{
         BulkEvaluator, BulkOutput, Function, MathFunction, Tape, Trace,
@@ -9,16 +9,27 @@ use crate::{
     },
     render::{RenderHints, TileSizes},
     shape::Shape,
-    types::{Grad, Interval},
-    var::VarMap,
+    types::{Affine, Grad, Interval},
+    var::{Var, VarMap},
 };
 use std::sync::Arc;
 
+mod bounds;
 mod choice;
 mod data;
 
+// `std::time::Instant` panics on `wasm32-unknown-unknown`
+#[cfg(not(target_arch = "wasm32"))]
+mod profile;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd128;
+
+pub use bounds::VmNodeBoundsEval;
 pub use choice::Choice;
 pub use data::{VmData, VmWorkspace};
+#[cfg(not(target_arch = "wasm32"))]
+pub use profile::{ClauseProfile, VmProfileEval};
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -50,6 +61,24 @@ impl<const N: usize> GenericVmTape<N> {
     pub fn data(&self) -> &VmData<N> {
         &self.0
     }
+
+    /// Generates GLSL source for the tape
+    ///
+    /// See [`VmData::to_glsl`] for details.
+    pub fn to_glsl(&self, gradient: bool) -> Result<String, Error> {
+        self.0.to_glsl(gradient)
+    }
+
+    /// Generates shader source for the tape in the given language
+    ///
+    /// See [`VmData::to_shader`] for details.
+    pub fn to_shader(
+        &self,
+        target: ShaderTarget,
+        gradient: bool,
+    ) -> Result<String, Error> {
+        self.0.to_shader(target, gradient)
+    }
 }
 
 impl<const N: usize> Tape for GenericVmTape<N> {
@@ -103,14 +132,12 @@ impl Trace for VmTrace {
     }
 }
 
-#[cfg(any(test, feature = "eval-tests"))]
 impl From<Vec<Choice>> for VmTrace {
     fn from(v: Vec<Choice>) -> Self {
         Self(v)
     }
 }
 
-#[cfg(any(test, feature = "eval-tests"))]
 impl AsRef<[Choice]> for VmTrace {
     fn as_ref(&self) -> &[Choice] {
         &self.0
@@ -161,6 +188,13 @@ impl<const N: usize> GenericVmFunction<N> {
         self.0.output_count()
     }
 
+    /// Returns the approximate number of heap bytes held by this function
+    ///
+    /// See [`VmData::memory_usage`] for details.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<VmData<N>>() + self.0.memory_usage()
+    }
+
     /// Simplifies the function with the given trace and a new register count
     pub fn simplify_with<const M: usize>(
         &self,
@@ -234,6 +268,11 @@ impl<const N: usize> Function for GenericVmFunction<N> {
     fn can_simplify(&self) -> bool {
         self.0.choice_count() > 0
     }
+
+    #[inline]
+    fn id(&self) -> Option<usize> {
+        Some(Arc::as_ptr(&self.0) as usize)
+    }
 }
 
 impl<const N: usize> RenderHints for GenericVmFunction<N> {
@@ -251,6 +290,39 @@ impl<const N: usize> MathFunction for GenericVmFunction<N> {
         let d = VmData::new(ctx, nodes)?;
         Ok(Self(d.into()))
     }
+
+    fn new_with_budget(
+        ctx: &Context,
+        nodes: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<Self, Error> {
+        let d = VmData::new_with_budget(ctx, nodes, budget)?;
+        Ok(Self(d.into()))
+    }
+
+    /// Rebuilds the function with a constant in place of `var`
+    ///
+    /// See [`VmData::specialize`] for details.
+    fn specialize(&self, var: Var, value: f32) -> Result<Self, Error> {
+        let d = self.0.specialize(var, value)?;
+        Ok(Self(d.into()))
+    }
+
+    /// Stitches two functions together
+    ///
+    /// See [`VmData::compose`] for details.
+    fn compose(&self, inner: &Self, wiring: &[Var]) -> Result<Self, Error> {
+        let d = self.0.compose(&inner.0, wiring)?;
+        Ok(Self(d.into()))
+    }
+
+    /// Builds a function computing partial derivatives
+    ///
+    /// See [`VmData::gradient`] for details.
+    fn gradient(&self, vars: &[Var]) -> Result<Self, Error> {
+        let d = self.0.gradient(vars)?;
+        Ok(Self(d.into()))
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -308,32 +380,108 @@ impl<T: From<f32> + Clone> TracingVmEval<T> {
     }
 }
 
-/// VM-based tracing evaluator for intervals
-#[derive(Default)]
-pub struct VmIntervalEval<const N: usize>(TracingVmEval<Interval>);
-impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
-    type Data = Interval;
-    type Tape = GenericVmTape<N>;
-    type Trace = VmTrace;
-    type TapeStorage = EmptyTapeStorage;
+/// Interval-like types which are evaluated by [`TracingVmEval::eval_ops`]
+///
+/// This is implemented by [`Interval`] and [`Affine`], so that both tracing
+/// evaluators share the same opcode semantics (including `min` / `max` choices
+/// and the interval fallbacks for `NOT` and `COMPARE`).
+trait IntervalOps:
+    Copy
+    + From<f32>
+    + std::ops::Neg<Output = Self>
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self>
+    + std::ops::Mul<f32, Output = Self>
+    + std::ops::Div<Output = Self>
+{
+    fn abs(self) -> Self;
+    fn recip(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn square(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn logical_not(self) -> Self;
+    fn atan2(self, rhs: Self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn compare(self, rhs: Self) -> Self;
+    fn min_choice(self, rhs: Self) -> (Self, Choice);
+    fn max_choice(self, rhs: Self) -> (Self, Choice);
+    fn and_choice(self, rhs: Self) -> (Self, Choice);
+    fn or_choice(self, rhs: Self) -> (Self, Choice);
+}
 
-    #[inline]
-    fn eval(
+macro_rules! impl_interval_ops {
+    ($t:ty, |$s:ident| $recv:expr) => {
+        impl IntervalOps for $t {
+            fn abs($s) -> Self { <$t>::abs($s) }
+            fn recip($s) -> Self { <$t>::recip($s) }
+            fn sqrt($s) -> Self { <$t>::sqrt($s) }
+            fn square($s) -> Self { <$t>::square($s) }
+            fn floor($s) -> Self { <$t>::floor($recv) }
+            fn ceil($s) -> Self { <$t>::ceil($recv) }
+            fn round($s) -> Self { <$t>::round($recv) }
+            fn sin($s) -> Self { <$t>::sin($s) }
+            fn cos($s) -> Self { <$t>::cos($s) }
+            fn tan($s) -> Self { <$t>::tan($s) }
+            fn asin($s) -> Self { <$t>::asin($s) }
+            fn acos($s) -> Self { <$t>::acos($s) }
+            fn atan($s) -> Self { <$t>::atan($s) }
+            fn exp($s) -> Self { <$t>::exp($s) }
+            fn ln($s) -> Self { <$t>::ln($s) }
+            fn logical_not($s) -> Self { <$t>::logical_not($s) }
+            fn atan2($s, rhs: Self) -> Self { <$t>::atan2($s, rhs) }
+            fn rem_euclid($s, rhs: Self) -> Self {
+                <$t>::rem_euclid($recv, rhs)
+            }
+            fn compare($s, rhs: Self) -> Self { <$t>::compare($s, rhs) }
+            fn min_choice($s, rhs: Self) -> (Self, Choice) {
+                <$t>::min_choice($s, rhs)
+            }
+            fn max_choice($s, rhs: Self) -> (Self, Choice) {
+                <$t>::max_choice($s, rhs)
+            }
+            fn and_choice($s, rhs: Self) -> (Self, Choice) {
+                <$t>::and_choice($s, rhs)
+            }
+            fn or_choice($s, rhs: Self) -> (Self, Choice) {
+                <$t>::or_choice($s, rhs)
+            }
+        }
+    };
+}
+
+// `Interval` takes `&self` for a few operations
+impl_interval_ops!(Interval, |self| &self);
+impl_interval_ops!(Affine, |self| self);
+
+impl<T: IntervalOps> TracingVmEval<T> {
+    /// Evaluates a tape with interval-like arithmetic, recording choices
+    fn eval_ops<const N: usize>(
         &mut self,
-        tape: &Self::Tape,
-        vars: &[Interval],
-    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+        tape: &GenericVmTape<N>,
+        vars: &[T],
+    ) -> Result<(&[T], Option<&VmTrace>), Error> {
         tape.vars().check_tracing_arguments(vars)?;
         let tape = tape.data();
-        self.0.resize_slots(tape);
+        self.resize_slots(tape);
 
         let mut simplify = false;
-        let mut v = SlotArray(&mut self.0.slots);
-        let mut choices = self.0.choices.as_mut_slice().iter_mut();
+        let mut v = SlotArray(&mut self.slots);
+        let mut choices = self.choices.as_mut_slice().iter_mut();
         for op in tape.iter_asm() {
             match op {
                 RegOp::Output(arg, i) => {
-                    self.0.out[i as usize] = v[arg];
+                    self.out[i as usize] = v[arg];
                 }
                 RegOp::Input(out, i) => {
                     v[out] = vars[i as usize];
@@ -387,13 +535,7 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     v[out] = v[arg].ln();
                 }
                 RegOp::NotReg(out, arg) => {
-                    v[out] = if !v[arg].contains(0.0) && !v[arg].has_nan() {
-                        Interval::new(0.0, 0.0)
-                    } else if v[arg].lower() == 0.0 && v[arg].upper() == 0.0 {
-                        Interval::new(1.0, 1.0)
-                    } else {
-                        Interval::new(0.0, 1.0)
-                    };
+                    v[out] = v[arg].logical_not();
                 }
                 RegOp::CopyReg(out, arg) => v[out] = v[arg],
                 RegOp::AddRegImm(out, arg, imm) => {
@@ -406,21 +548,21 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     v[out] = v[arg] / imm.into();
                 }
                 RegOp::DivImmReg(out, arg, imm) => {
-                    let imm: Interval = imm.into();
+                    let imm = T::from(imm);
                     v[out] = imm / v[arg];
                 }
                 RegOp::AtanRegImm(out, arg, imm) => {
                     v[out] = v[arg].atan2(imm.into());
                 }
                 RegOp::AtanImmReg(out, arg, imm) => {
-                    let imm: Interval = imm.into();
+                    let imm = T::from(imm);
                     v[out] = imm.atan2(v[arg]);
                 }
                 RegOp::AtanRegReg(out, lhs, rhs) => {
                     v[out] = v[lhs].atan2(v[rhs]);
                 }
                 RegOp::SubImmReg(out, arg, imm) => {
-                    v[out] = Interval::from(imm) - v[arg];
+                    v[out] = T::from(imm) - v[arg];
                 }
                 RegOp::SubRegImm(out, arg, imm) => {
                     v[out] = v[arg] - imm.into();
@@ -468,44 +610,20 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     v[out] = v[arg].rem_euclid(imm.into());
                 }
                 RegOp::ModImmReg(out, arg, imm) => {
-                    v[out] = Interval::from(imm).rem_euclid(v[arg]);
+                    v[out] = T::from(imm).rem_euclid(v[arg]);
                 }
                 RegOp::AddRegReg(out, lhs, rhs) => v[out] = v[lhs] + v[rhs],
                 RegOp::MulRegReg(out, lhs, rhs) => v[out] = v[lhs] * v[rhs],
                 RegOp::DivRegReg(out, lhs, rhs) => v[out] = v[lhs] / v[rhs],
                 RegOp::SubRegReg(out, lhs, rhs) => v[out] = v[lhs] - v[rhs],
                 RegOp::CompareRegReg(out, lhs, rhs) => {
-                    v[out] = if v[lhs].has_nan() || v[rhs].has_nan() {
-                        f32::NAN.into()
-                    } else if v[lhs].upper() < v[rhs].lower() {
-                        Interval::from(-1.0)
-                    } else if v[lhs].lower() > v[rhs].upper() {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = v[lhs].compare(v[rhs]);
                 }
                 RegOp::CompareRegImm(out, arg, imm) => {
-                    v[out] = if v[arg].has_nan() || imm.is_nan() {
-                        f32::NAN.into()
-                    } else if v[arg].upper() < imm {
-                        Interval::from(-1.0)
-                    } else if v[arg].lower() > imm {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = v[arg].compare(imm.into());
                 }
                 RegOp::CompareImmReg(out, arg, imm) => {
-                    v[out] = if v[arg].has_nan() || imm.is_nan() {
-                        f32::NAN.into()
-                    } else if imm < v[arg].lower() {
-                        Interval::from(-1.0)
-                    } else if imm > v[arg].upper() {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = T::from(imm).compare(v[arg]);
                 }
                 RegOp::MinRegReg(out, lhs, rhs) => {
                     let (value, choice) = v[lhs].min_choice(v[rhs]);
@@ -530,14 +648,50 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                 }
             }
         }
-        Ok((
-            &self.0.out,
-            if simplify {
-                Some(&self.0.choices)
-            } else {
-                None
-            },
-        ))
+        Ok((&self.out, if simplify { Some(&self.choices) } else { None }))
+    }
+}
+
+/// VM-based tracing evaluator for intervals
+#[derive(Default)]
+pub struct VmIntervalEval<const N: usize>(TracingVmEval<Interval>);
+impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
+    type Data = Interval;
+    type Tape = GenericVmTape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    #[inline]
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Interval],
+    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+        self.0.eval_ops(tape, vars)
+    }
+}
+
+/// VM-based tracing evaluator for affine arithmetic
+///
+/// Affine forms track linear correlations between their inputs, so they often
+/// produce tighter bounds than [`VmIntervalEval`] (e.g. for rotated shapes).
+/// Operations without a good affine approximation fall back to interval
+/// arithmetic.
+#[derive(Default)]
+pub struct VmAffineEval<const N: usize>(TracingVmEval<Affine>);
+impl<const N: usize> TracingEvaluator for VmAffineEval<N> {
+    type Data = Affine;
+    type Tape = GenericVmTape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    #[inline]
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Affine],
+    ) -> Result<(&[Affine], Option<&VmTrace>), Error> {
+        self.0.eval_ops(tape, vars)
     }
 }
 
@@ -843,7 +997,6 @@ impl<const N: usize> TracingEvaluator for VmPointEval<N> {
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Bulk evaluator for VM tapes
-#[derive(Default)]
 struct BulkVmEval<T> {
     /// Workspace for data
     slots: Vec<Vec<T>>,
@@ -852,6 +1005,15 @@ struct BulkVmEval<T> {
     out: Vec<Vec<T>>,
 }
 
+impl<T> Default for BulkVmEval<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::default(),
+            out: Vec::default(),
+        }
+    }
+}
+
 impl<T: From<f32> + Clone> BulkVmEval<T> {
     /// Reserves slots for the given tape and slice size
     fn resize_slots<const N: usize>(&mut self, tape: &VmData<N>, size: usize) {
@@ -869,317 +1031,699 @@ impl<T: From<f32> + Clone> BulkVmEval<T> {
     }
 }
 
-/// VM-based bulk evaluator for arrays of points, yielding point values
+/// VM-based bulk evaluator for arrays of intervals
+///
+/// This evaluates many intervals with the same tape in a single call (e.g.
+/// sibling cells during octree subdivision).  Data is stored as one array per
+/// tape slot, and the tape is walked once per call, with each operation
+/// applied to every interval before moving to the next; this amortizes
+/// dispatch overhead, which dominates single-interval evaluation.
+///
+/// Each operation uses the same [`Interval`] arithmetic as
+/// [`VmIntervalEval`], so results are identical to evaluating each interval
+/// separately.  However, no trace is recorded: `min`, `max`, `and`, and `or`
+/// always produce the combined result (as if both branches were chosen), and
+/// results can't be used to simplify the tape.  Use [`VmIntervalEval`] if
+/// simplification is needed.
+///
+/// In addition to [`BulkEvaluator::eval`] (which takes arrays of
+/// [`Interval`] values), [`eval_bounds`](Self::eval_bounds) accepts separate
+/// arrays of lower and upper bounds.
 #[derive(Default)]
-pub struct VmFloatSliceEval<const N: usize>(BulkVmEval<f32>);
-impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
-    type Data = f32;
-    type Tape = GenericVmTape<N>;
-    type TapeStorage = EmptyTapeStorage;
+pub struct VmIntervalSliceEval<const N: usize> {
+    bulk: BulkVmEval<Interval>,
 
-    #[inline]
-    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+    /// Scratch buffers used when packing SoA bounds into intervals
+    scratch: Vec<Vec<Interval>>,
+}
+
+impl<const N: usize> VmIntervalSliceEval<N> {
+    /// Evaluates many intervals, given as separate arrays of bounds
+    ///
+    /// `lower` and `upper` are indexed by variable (like the `vars` argument
+    /// to [`BulkEvaluator::eval`]), then by position within the array.
+    ///
+    /// Returns [`Error::MismatchedSlices`] if the two arrays have different
+    /// shapes, [`Error::BadInterval`] if any lower bound is above its upper
+    /// bound, or any error that [`BulkEvaluator::eval`] would return.
+    pub fn eval_bounds<V: std::ops::Deref<Target = [f32]>>(
         &mut self,
-        tape: &Self::Tape,
+        tape: &GenericVmTape<N>,
+        lower: &[V],
+        upper: &[V],
+    ) -> Result<BulkOutput<'_, Interval>, Error> {
+        if lower.len() != upper.len() {
+            return Err(Error::MismatchedSlices);
+        }
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize_with(lower.len(), Vec::new);
+        for ((s, lo), hi) in scratch.iter_mut().zip(lower).zip(upper) {
+            if lo.len() != hi.len() {
+                return Err(Error::MismatchedSlices);
+            }
+            s.clear();
+            for (a, b) in lo.iter().zip(hi.iter()) {
+                if !(b >= a || (a.is_nan() && b.is_nan())) {
+                    return Err(Error::BadInterval(*a, *b));
+                }
+                s.push(Interval::new(*a, *b));
+            }
+        }
+        let size = self.eval_inner(tape, &scratch);
+        self.scratch = scratch;
+        Ok(BulkOutput::new(&self.bulk.out, size?))
+    }
+
+    fn eval_inner<V: std::ops::Deref<Target = [Interval]>>(
+        &mut self,
+        tape: &GenericVmTape<N>,
         vars: &[V],
-    ) -> Result<BulkOutput<'_, f32>, Error> {
+    ) -> Result<usize, Error> {
         tape.vars().check_bulk_arguments(vars)?;
         let tape = tape.data();
 
         let size = vars.first().map(|v| v.len()).unwrap_or(0);
-        self.0.resize_slots(tape, size);
+        self.bulk.resize_slots(tape, size);
 
-        let mut v = SlotArray(&mut self.0.slots);
         for op in tape.iter_asm() {
-            match op {
-                RegOp::Output(arg, i) => {
-                    self.0.out[i as usize][0..size]
-                        .copy_from_slice(&v[arg][0..size]);
+            Self::eval_op(&mut self.bulk, vars, op, size);
+        }
+        Ok(size)
+    }
+
+    /// Evaluates a single operation on the first `size` items of each slot
+    #[inline(always)]
+    fn eval_op<V: std::ops::Deref<Target = [Interval]>>(
+        bulk: &mut BulkVmEval<Interval>,
+        vars: &[V],
+        op: RegOp,
+        size: usize,
+    ) {
+        let mut v = SlotArray(&mut bulk.slots);
+        match op {
+            RegOp::Output(arg, i) => {
+                bulk.out[i as usize][0..size].copy_from_slice(&v[arg][0..size]);
+            }
+            RegOp::Input(out, i) => {
+                v[out][0..size].copy_from_slice(&vars[i as usize]);
+            }
+            RegOp::NegReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = -v[arg][i];
                 }
-                RegOp::Input(out, i) => {
-                    v[out][0..size].copy_from_slice(&vars[i as usize]);
+            }
+            RegOp::AbsReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].abs();
                 }
-                RegOp::NegReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = -v[arg][i];
-                    }
+            }
+            RegOp::RecipReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].recip();
                 }
-                RegOp::AbsReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].abs();
-                    }
+            }
+            RegOp::SqrtReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].sqrt();
                 }
-                RegOp::RecipReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = 1.0 / v[arg][i];
-                    }
+            }
+            RegOp::SquareReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].square();
                 }
-                RegOp::SqrtReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sqrt();
-                    }
+            }
+            RegOp::FloorReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].floor();
                 }
-                RegOp::SquareReg(out, arg) => {
-                    for i in 0..size {
-                        let s = v[arg][i];
-                        v[out][i] = s * s;
-                    }
+            }
+            RegOp::CeilReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].ceil();
                 }
-                RegOp::FloorReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].floor();
-                    }
+            }
+            RegOp::RoundReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].round();
                 }
-                RegOp::CeilReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].ceil();
-                    }
+            }
+            RegOp::SinReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].sin();
                 }
-                RegOp::RoundReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].round();
-                    }
+            }
+            RegOp::CosReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].cos();
                 }
-                RegOp::SinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].sin();
-                    }
+            }
+            RegOp::TanReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].tan();
                 }
-                RegOp::CosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].cos();
-                    }
+            }
+            RegOp::AsinReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].asin();
                 }
-                RegOp::TanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].tan();
-                    }
+            }
+            RegOp::AcosReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].acos();
                 }
-                RegOp::AsinReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].asin();
-                    }
+            }
+            RegOp::AtanReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].atan();
                 }
-                RegOp::AcosReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].acos();
-                    }
+            }
+            RegOp::ExpReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].exp();
                 }
-                RegOp::AtanReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].atan();
-                    }
+            }
+            RegOp::LnReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].ln();
                 }
-                RegOp::ExpReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].exp();
-                    }
+            }
+            RegOp::NotReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].logical_not();
                 }
-                RegOp::LnReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].ln();
-                    }
+            }
+            RegOp::CopyReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i];
                 }
-                RegOp::NotReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = (v[arg][i] == 0.0).into();
-                    }
+            }
+            RegOp::AddRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i] + imm;
                 }
-                RegOp::CopyReg(out, arg) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i];
-                    }
+            }
+            RegOp::MulRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i] * imm;
                 }
-                RegOp::AddRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] + imm;
-                    }
+            }
+            RegOp::DivRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i] / imm;
                 }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] * imm;
-                    }
+            }
+            RegOp::DivImmReg(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = imm / v[arg][i];
                 }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] / imm;
-                    }
+            }
+            RegOp::AtanRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].atan2(imm);
                 }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm / v[arg][i];
-                    }
+            }
+            RegOp::AtanImmReg(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = imm.atan2(v[arg][i]);
                 }
-                RegOp::AtanRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].atan2(imm);
-                    }
+            }
+            RegOp::SubImmReg(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = imm - v[arg][i];
                 }
-                RegOp::AtanImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm.atan2(v[arg][i]);
-                    }
+            }
+            RegOp::SubRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i] - imm;
                 }
-                RegOp::AtanRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i].atan2(v[rhs][i]);
-                    }
+            }
+            RegOp::CompareRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].compare(imm);
                 }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm - v[arg][i];
-                    }
+            }
+            RegOp::CompareImmReg(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = imm.compare(v[arg][i]);
                 }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i] - imm;
-                    }
+            }
+            RegOp::MinRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].min_choice(imm).0;
                 }
-                RegOp::CompareImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm
-                            .partial_cmp(&v[arg][i])
-                            .map(|c| c as i8 as f32)
-                            .unwrap_or(f32::NAN)
-                    }
+            }
+            RegOp::MaxRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].max_choice(imm).0;
                 }
-                RegOp::CompareRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i]
-                            .partial_cmp(&imm)
-                            .map(|c| c as i8 as f32)
-                            .unwrap_or(f32::NAN)
-                    }
+            }
+            RegOp::AndRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].and_choice(imm).0;
                 }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
-                            f32::NAN
-                        } else {
-                            v[arg][i].min(imm)
-                        };
-                    }
+            }
+            RegOp::OrRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].or_choice(imm).0;
                 }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
-                            f32::NAN
-                        } else {
-                            v[arg][i].max(imm)
-                        };
-                    }
+            }
+            RegOp::ModRegImm(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = v[arg][i].rem_euclid(imm);
                 }
-                RegOp::AndRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] =
-                            if v[arg][i] == 0.0 { v[arg][i] } else { imm };
-                    }
+            }
+            RegOp::ModImmReg(out, arg, imm) => {
+                let imm = Interval::from(imm);
+                for i in 0..size {
+                    v[out][i] = imm.rem_euclid(v[arg][i]);
                 }
-                RegOp::OrRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] =
-                            if v[arg][i] != 0.0 { v[arg][i] } else { imm };
-                    }
+            }
+            RegOp::AddRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] + v[rhs][i];
                 }
-                RegOp::ModRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
-                    }
+            }
+            RegOp::MulRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] * v[rhs][i];
                 }
-                RegOp::ModRegImm(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = v[arg][i].rem_euclid(imm);
-                    }
+            }
+            RegOp::DivRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] / v[rhs][i];
                 }
-                RegOp::ModImmReg(out, arg, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm.rem_euclid(v[arg][i]);
-                    }
+            }
+            RegOp::SubRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] - v[rhs][i];
                 }
-                RegOp::AddRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] + v[rhs][i];
-                    }
+            }
+            RegOp::AtanRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].atan2(v[rhs][i]);
                 }
-                RegOp::MulRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] * v[rhs][i];
-                    }
+            }
+            RegOp::CompareRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].compare(v[rhs][i]);
                 }
-                RegOp::DivRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] / v[rhs][i];
-                    }
+            }
+            RegOp::MinRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].min_choice(v[rhs][i]).0;
                 }
-                RegOp::SubRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i] - v[rhs][i];
-                    }
+            }
+            RegOp::MaxRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].max_choice(v[rhs][i]).0;
                 }
-                RegOp::CompareRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = v[lhs][i]
-                            .partial_cmp(&v[rhs][i])
-                            .map(|c| c as i8 as f32)
-                            .unwrap_or(f32::NAN)
-                    }
+            }
+            RegOp::AndRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].and_choice(v[rhs][i]).0;
                 }
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
-                        {
-                            f32::NAN
-                        } else {
-                            v[lhs][i].min(v[rhs][i])
-                        };
-                    }
+            }
+            RegOp::OrRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].or_choice(v[rhs][i]).0;
                 }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
-                        {
-                            f32::NAN
-                        } else {
-                            v[lhs][i].max(v[rhs][i])
-                        };
-                    }
+            }
+            RegOp::ModRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
                 }
-                RegOp::AndRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i] == 0.0 {
-                            v[lhs][i]
-                        } else {
-                            v[rhs][i]
-                        };
-                    }
+            }
+            RegOp::CopyImm(out, imm) => {
+                v[out][0..size].fill(imm.into());
+            }
+            RegOp::Load(out, mem) => {
+                for i in 0..size {
+                    v[out][i] = v[mem][i];
                 }
-                RegOp::OrRegReg(out, lhs, rhs) => {
-                    for i in 0..size {
-                        v[out][i] = if v[lhs][i] != 0.0 {
-                            v[lhs][i]
-                        } else {
-                            v[rhs][i]
-                        };
-                    }
+            }
+            RegOp::Store(out, mem) => {
+                for i in 0..size {
+                    v[mem][i] = v[out][i];
                 }
-                RegOp::CopyImm(out, imm) => {
-                    for i in 0..size {
-                        v[out][i] = imm;
-                    }
+            }
+        }
+    }
+}
+
+impl<const N: usize> BulkEvaluator for VmIntervalSliceEval<N> {
+    type Data = Interval;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    #[inline]
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Interval>, Error> {
+        let size = self.eval_inner(tape, vars)?;
+        Ok(BulkOutput::new(&self.bulk.out, size))
+    }
+}
+
+/// VM-based bulk evaluator for arrays of points, yielding point values
+#[derive(Default)]
+pub struct VmFloatSliceEval<const N: usize>(BulkVmEval<f32>);
+impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
+    type Data = f32;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    #[inline]
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, f32>, Error> {
+        tape.vars().check_bulk_arguments(vars)?;
+        let tape = tape.data();
+
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.0.resize_slots(tape, size);
+
+        for op in tape.iter_asm() {
+            Self::eval_op(&mut self.0, vars, op, size);
+        }
+        Ok(BulkOutput::new(&self.0.out, size))
+    }
+}
+
+impl<const N: usize> VmFloatSliceEval<N> {
+    /// Evaluates a single operation on the first `size` items of each slot
+    #[inline(always)]
+    fn eval_op<V: std::ops::Deref<Target = [f32]>>(
+        bulk: &mut BulkVmEval<f32>,
+        vars: &[V],
+        op: RegOp,
+        size: usize,
+    ) {
+        let mut v = SlotArray(&mut bulk.slots);
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        if simd128::eval_op(&mut v, op, size) {
+            return;
+        }
+        match op {
+            RegOp::Output(arg, i) => {
+                bulk.out[i as usize][0..size].copy_from_slice(&v[arg][0..size]);
+            }
+            RegOp::Input(out, i) => {
+                v[out][0..size].copy_from_slice(&vars[i as usize]);
+            }
+            RegOp::NegReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = -v[arg][i];
                 }
-                RegOp::Load(out, mem) => {
-                    for i in 0..size {
-                        v[out][i] = v[mem][i];
-                    }
+            }
+            RegOp::AbsReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].abs();
                 }
-                RegOp::Store(out, mem) => {
-                    for i in 0..size {
-                        v[mem][i] = v[out][i];
-                    }
+            }
+            RegOp::RecipReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = 1.0 / v[arg][i];
+                }
+            }
+            RegOp::SqrtReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].sqrt();
+                }
+            }
+            RegOp::SquareReg(out, arg) => {
+                for i in 0..size {
+                    let s = v[arg][i];
+                    v[out][i] = s * s;
+                }
+            }
+            RegOp::FloorReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].floor();
+                }
+            }
+            RegOp::CeilReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].ceil();
+                }
+            }
+            RegOp::RoundReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].round();
+                }
+            }
+            RegOp::SinReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].sin();
+                }
+            }
+            RegOp::CosReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].cos();
+                }
+            }
+            RegOp::TanReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].tan();
+                }
+            }
+            RegOp::AsinReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].asin();
+                }
+            }
+            RegOp::AcosReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].acos();
+                }
+            }
+            RegOp::AtanReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].atan();
+                }
+            }
+            RegOp::ExpReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].exp();
+                }
+            }
+            RegOp::LnReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].ln();
+                }
+            }
+            RegOp::NotReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = (v[arg][i] == 0.0).into();
+                }
+            }
+            RegOp::CopyReg(out, arg) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i];
+                }
+            }
+            RegOp::AddRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i] + imm;
+                }
+            }
+            RegOp::MulRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i] * imm;
+                }
+            }
+            RegOp::DivRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i] / imm;
+                }
+            }
+            RegOp::DivImmReg(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm / v[arg][i];
+                }
+            }
+            RegOp::AtanRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].atan2(imm);
+                }
+            }
+            RegOp::AtanImmReg(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm.atan2(v[arg][i]);
+                }
+            }
+            RegOp::AtanRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].atan2(v[rhs][i]);
+                }
+            }
+            RegOp::SubImmReg(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm - v[arg][i];
+                }
+            }
+            RegOp::SubRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i] - imm;
+                }
+            }
+            RegOp::CompareImmReg(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm
+                        .partial_cmp(&v[arg][i])
+                        .map(|c| c as i8 as f32)
+                        .unwrap_or(f32::NAN)
+                }
+            }
+            RegOp::CompareRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i]
+                        .partial_cmp(&imm)
+                        .map(|c| c as i8 as f32)
+                        .unwrap_or(f32::NAN)
+                }
+            }
+            RegOp::MinRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
+                        f32::NAN
+                    } else {
+                        v[arg][i].min(imm)
+                    };
+                }
+            }
+            RegOp::MaxRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
+                        f32::NAN
+                    } else {
+                        v[arg][i].max(imm)
+                    };
+                }
+            }
+            RegOp::AndRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = if v[arg][i] == 0.0 { v[arg][i] } else { imm };
+                }
+            }
+            RegOp::OrRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = if v[arg][i] != 0.0 { v[arg][i] } else { imm };
+                }
+            }
+            RegOp::ModRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
+                }
+            }
+            RegOp::ModRegImm(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = v[arg][i].rem_euclid(imm);
+                }
+            }
+            RegOp::ModImmReg(out, arg, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm.rem_euclid(v[arg][i]);
+                }
+            }
+            RegOp::AddRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] + v[rhs][i];
+                }
+            }
+            RegOp::MulRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] * v[rhs][i];
+                }
+            }
+            RegOp::DivRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] / v[rhs][i];
+                }
+            }
+            RegOp::SubRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i] - v[rhs][i];
+                }
+            }
+            RegOp::CompareRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = v[lhs][i]
+                        .partial_cmp(&v[rhs][i])
+                        .map(|c| c as i8 as f32)
+                        .unwrap_or(f32::NAN)
+                }
+            }
+            RegOp::MinRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan() {
+                        f32::NAN
+                    } else {
+                        v[lhs][i].min(v[rhs][i])
+                    };
+                }
+            }
+            RegOp::MaxRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan() {
+                        f32::NAN
+                    } else {
+                        v[lhs][i].max(v[rhs][i])
+                    };
+                }
+            }
+            RegOp::AndRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = if v[lhs][i] == 0.0 {
+                        v[lhs][i]
+                    } else {
+                        v[rhs][i]
+                    };
+                }
+            }
+            RegOp::OrRegReg(out, lhs, rhs) => {
+                for i in 0..size {
+                    v[out][i] = if v[lhs][i] != 0.0 {
+                        v[lhs][i]
+                    } else {
+                        v[rhs][i]
+                    };
+                }
+            }
+            RegOp::CopyImm(out, imm) => {
+                for i in 0..size {
+                    v[out][i] = imm;
+                }
+            }
+            RegOp::Load(out, mem) => {
+                for i in 0..size {
+                    v[out][i] = v[mem][i];
+                }
+            }
+            RegOp::Store(out, mem) => {
+                for i in 0..size {
+                    v[mem][i] = v[out][i];
                 }
             }
         }
-        Ok(BulkOutput::new(&self.0.out, size))
     }
 }
 
@@ -1523,4 +2067,203 @@ mod test {
     crate::interval_tests!(VmFunction);
     crate::float_slice_tests!(VmFunction);
     crate::point_tests!(VmFunction);
+
+    #[test]
+    fn interval_slice_all_ops() {
+        let (ctx, node) = crate::eval::test::build_all_ops();
+        let f = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = f.interval_tape(Default::default());
+
+        let mut bounds = [vec![], vec![], vec![]];
+        for i in 0..32 {
+            for (j, b) in bounds.iter_mut().enumerate() {
+                let lo = ((i * (j + 2)) % 9) as f32 / 2.0 - 2.0;
+                let size = ((i * (j + 3)) % 5) as f32 / 4.0;
+                b.push(Interval::new(lo, lo + size));
+            }
+        }
+        let mut eval = VmIntervalSliceEval::new();
+        let out = eval.eval(&tape, &bounds).unwrap();
+        let mut single = VmFunction::new_interval_eval();
+        for (i, o) in out[0].iter().enumerate() {
+            let args = [bounds[0][i], bounds[1][i], bounds[2][i]];
+            let (expected, _) = single.eval(&tape, &args).unwrap();
+            let e = expected[0];
+            assert!(
+                (e.has_nan() && o.has_nan()) || e == *o,
+                "mismatch at {i}: {e} != {o}"
+            );
+        }
+    }
+
+    #[test]
+    fn interval_slice() {
+        use crate::context::Tree;
+
+        let (x, y, z) = Tree::axes();
+        let t = (x.square() + y.sin())
+            .max(z.clone() * x.clone())
+            .min(y.clone() / 2.0 - 1.0)
+            + x.compare(z.clone()).abs()
+            + y.clone().modulo(1.5).and(z.clone())
+            - (x.clone() / y.clone()).or(x.atan2(z));
+        let mut ctx = Context::new();
+        let node = ctx.import(&t);
+        let f = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = f.interval_tape(Default::default());
+
+        // Build a grid of intervals for each variable
+        let mut bounds = [vec![], vec![], vec![]];
+        for i in 0..64 {
+            for (j, b) in bounds.iter_mut().enumerate() {
+                let lo = ((i * (j + 3)) % 11) as f32 - 5.0;
+                let size = ((i * (j + 5)) % 7) as f32 / 2.0;
+                b.push(Interval::new(lo, lo + size));
+            }
+        }
+
+        let mut eval = VmIntervalSliceEval::new();
+        let out = eval.eval(&tape, &bounds).unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].len(), 64);
+        let out = out[0].to_vec();
+
+        let mut single = VmFunction::new_interval_eval();
+        for (i, o) in out.iter().enumerate() {
+            let args = [bounds[0][i], bounds[1][i], bounds[2][i]];
+            let (expected, _) = single.eval(&tape, &args).unwrap();
+            let e = expected[0];
+            assert!(
+                (e.has_nan() && o.has_nan()) || e == *o,
+                "mismatch at {i}: {e} != {o}"
+            );
+        }
+
+        // Separate arrays of bounds produce the same results
+        let lower = bounds
+            .clone()
+            .map(|b| b.iter().map(|i| i.lower()).collect());
+        let upper = bounds.map(|b| b.iter().map(|i| i.upper()).collect());
+        let out2 = eval.eval_bounds(&tape, &lower, &upper).unwrap();
+        for (a, b) in out.iter().zip(&out2[0]) {
+            assert!((a.has_nan() && b.has_nan()) || a == b);
+        }
+
+        let mut short: [Vec<f32>; 3] = upper.clone();
+        short[1].pop();
+        assert!(matches!(
+            eval.eval_bounds(&tape, &lower, &short),
+            Err(Error::MismatchedSlices)
+        ));
+        assert!(matches!(
+            eval.eval_bounds(&tape, &lower[..2], &upper[..2]),
+            Err(Error::BadVarSlice(..))
+        ));
+
+        // Out-of-order bounds are rejected rather than panicking
+        let mut flipped = upper.clone();
+        flipped[2][7] = lower[2][7] - 1.0;
+        assert!(matches!(
+            eval.eval_bounds(&tape, &lower, &flipped),
+            Err(Error::BadInterval(..))
+        ));
+    }
+
+    #[test]
+    fn node_bounds() {
+        let mut ctx = Context::new();
+        let [x, y, z] = ctx.axes();
+        let a = ctx.mul(x, 2.0).unwrap();
+        let b = ctx.sin(y).unwrap();
+        let c = ctx.max(a, b).unwrap();
+        let root = ctx.sub(c, z).unwrap();
+        let other = ctx.square(y).unwrap();
+
+        let mut eval = VmNodeBoundsEval::new(&ctx, &[root, other]).unwrap();
+        assert_eq!(eval.nodes().len(), 9); // includes the constant 2.0
+        let vars = eval.vars();
+        let axes = [Var::X, Var::Y, Var::Z].map(|v| vars[&v]);
+        let inputs = [
+            Interval::new(-1.0, 0.5),
+            Interval::new(-2.0, 0.0),
+            Interval::new(1.0, 2.0),
+        ];
+        let mut args = [Interval::from(0.0); 3];
+        for (i, v) in axes.iter().zip(inputs) {
+            args[*i] = v;
+        }
+
+        let bounds: std::collections::HashMap<_, _> =
+            eval.eval(&args).unwrap().collect();
+        assert_eq!(bounds[&x], inputs[0]);
+        assert_eq!(bounds[&a], Interval::new(-2.0, 1.0));
+        assert_eq!(bounds[&other], Interval::new(0.0, 4.0));
+
+        // Bounds match those from a regular interval evaluator
+        for n in [b, c, root] {
+            let f = VmFunction::new(&ctx, &[n]).unwrap();
+            let tape = f.interval_tape(Default::default());
+            let mut args = vec![Interval::from(0.0); tape.vars().len()];
+            for (v, i) in [Var::X, Var::Y, Var::Z].iter().zip(inputs) {
+                if let Some(j) = tape.vars().get(v) {
+                    args[j] = i;
+                }
+            }
+            let mut single = VmFunction::new_interval_eval();
+            let (out, _) = single.eval(&tape, &args).unwrap();
+            assert_eq!(out[0], bounds[&n]);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn profile_eval() {
+        use crate::context::Tree;
+
+        let (x, y, _) = Tree::axes();
+        let t = (x.square() + y.clone()).min(y.sin() * 2.0);
+        let mut ctx = Context::new();
+        let node = ctx.import(&t);
+        let f = VmFunction::new(&ctx, &[node]).unwrap();
+        let tape = f.float_slice_tape(Default::default());
+
+        let xs: Vec<f32> = (0..10).map(|i| i as f32 / 3.0 - 1.0).collect();
+        let ys: Vec<f32> = (0..10).map(|i| 2.0 - i as f32 / 4.0).collect();
+        let args = [xs.clone(), ys.clone()];
+
+        let mut eval = VmProfileEval::new();
+        let out = eval.eval(&tape, &args).unwrap()[0].to_vec();
+        let mut float = VmFloatSliceEval::new();
+        let expected = float.eval(&tape, &args).unwrap();
+        assert_eq!(out, expected[0]);
+
+        eval.eval(&tape, &[&xs[..3], &ys[..3]]).unwrap();
+        let p = eval.profile();
+        let ops: Vec<_> = tape.data().iter_asm().collect();
+        assert_eq!(p.iter().map(|c| c.op).collect::<Vec<_>>(), ops);
+        for c in p {
+            assert_eq!(c.count, 2);
+            assert_eq!(c.items, 13);
+        }
+        assert_eq!(eval.report().lines().count(), ops.len());
+
+        eval.reset();
+        assert!(eval.profile().iter().all(|c| c.count == 0));
+        assert_eq!(eval.profile().len(), ops.len());
+
+        // A different tape discards previous statistics
+        let mut point = VmFunction::new_point_eval();
+        let (_, trace) = point
+            .eval(&f.point_tape(Default::default()), &[0.0, 1.0])
+            .unwrap();
+        let trace = trace.unwrap().clone();
+        let g = f
+            .simplify(&trace, Default::default(), &mut Default::default())
+            .unwrap();
+        let tape = g.float_slice_tape(Default::default());
+        eval.eval(&tape, &args).unwrap();
+        assert_eq!(eval.profile().len(), tape.data().len());
+        assert!(tape.data().len() < ops.len());
+        assert!(eval.profile().iter().all(|c| c.count == 1));
+    }
 }
@@ -0,0 +1,191 @@
+//! Instrumented evaluator which records per-clause execution statistics
+use super::{BulkVmEval, EmptyTapeStorage, GenericVmTape, VmFloatSliceEval};
+use crate::{
+    Error,
+    compiler::{RegOp, disassemble_op, var_names},
+    eval::{BulkEvaluator, BulkOutput, Tape},
+};
+use std::{
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Execution statistics for a single operation in a VM tape
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClauseProfile {
+    /// Operation in the register-allocated tape
+    pub op: RegOp,
+    /// Number of times that this operation was executed
+    pub count: u64,
+    /// Total number of items evaluated by this operation
+    ///
+    /// This is the sum of slice lengths across every execution.
+    pub items: u64,
+    /// Cumulative time spent executing this operation
+    pub time: Duration,
+}
+
+/// VM-based bulk evaluator for arrays of points, which records how often each
+/// clause is executed and how long it takes
+///
+/// This evaluator produces the same results as [`VmFloatSliceEval`], but
+/// times every operation individually, which makes it significantly slower.
+/// It's intended for finding which subexpressions of a model dominate
+/// evaluation time, and for checking how much work is removed by
+/// simplification.
+///
+/// Statistics are recorded for the most recently evaluated tape; evaluating a
+/// different tape discards any previous statistics.
+///
+/// Timing uses [`std::time::Instant`], which panics on
+/// `wasm32-unknown-unknown`, so this evaluator isn't built for `wasm32`
+/// targets.
+///
+/// ```
+/// # use fidget_core::{
+/// #     context::{Context, Tree}, eval::{BulkEvaluator, Function, MathFunction},
+/// #     vm::{VmFunction, VmProfileEval},
+/// # };
+/// let tree = Tree::x().square() + Tree::y().sin();
+/// let mut ctx = Context::new();
+/// let root = ctx.import(&tree);
+/// let f = VmFunction::new(&ctx, &[root])?;
+/// let tape = f.float_slice_tape(Default::default());
+///
+/// let mut eval = VmProfileEval::new();
+/// eval.eval(&tape, &[vec![0.0, 1.0, 2.0], vec![3.0, 4.0, 5.0]])?;
+/// eval.eval(&tape, &[vec![6.0], vec![7.0]])?;
+///
+/// let p = eval.profile();
+/// assert_eq!(p.len(), tape.data().len());
+/// assert!(p.iter().all(|c| c.count == 2 && c.items == 4));
+/// println!("{}", eval.report());
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+pub struct VmProfileEval<const N: usize> {
+    bulk: BulkVmEval<f32>,
+
+    /// Tape for which statistics are recorded
+    tape: Option<GenericVmTape<N>>,
+
+    /// Statistics for each operation, in evaluation order
+    profile: Vec<ClauseProfile>,
+}
+
+impl<const N: usize> Default for VmProfileEval<N> {
+    fn default() -> Self {
+        Self {
+            bulk: BulkVmEval::default(),
+            tape: None,
+            profile: vec![],
+        }
+    }
+}
+
+impl<const N: usize> VmProfileEval<N> {
+    /// Returns statistics for each operation in the most recent tape
+    ///
+    /// Operations are in evaluation order, i.e. the same order as
+    /// [`VmData::iter_asm`](crate::vm::VmData::iter_asm).
+    pub fn profile(&self) -> &[ClauseProfile] {
+        &self.profile
+    }
+
+    /// Returns the total time spent evaluating operations
+    pub fn total_time(&self) -> Duration {
+        self.profile.iter().map(|c| c.time).sum()
+    }
+
+    /// Resets all statistics to zero, without forgetting the tape
+    pub fn reset(&mut self) {
+        for c in &mut self.profile {
+            c.count = 0;
+            c.items = 0;
+            c.time = Duration::ZERO;
+        }
+    }
+
+    /// Returns a human-readable report, listing operations from most to least
+    /// expensive
+    ///
+    /// Each line shows the share of total time, the cumulative time, the
+    /// execution count, the operation's position in the tape, and its
+    /// disassembly (in the format of
+    /// [`RegTape::disassemble`](crate::compiler::RegTape::disassemble)).
+    pub fn report(&self) -> String {
+        let names = self
+            .tape
+            .as_ref()
+            .map(|t| var_names(t.vars()))
+            .unwrap_or_default();
+        let total = self.total_time().as_secs_f64();
+
+        let mut order: Vec<usize> = (0..self.profile.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.profile[i].time));
+
+        let mut out = String::new();
+        for i in order {
+            let c = &self.profile[i];
+            let t = c.time.as_secs_f64();
+            let pct = if total > 0.0 { t / total * 100.0 } else { 0.0 };
+            write!(
+                &mut out,
+                "{pct:5.1}% {:>10.3}ms {:>8}x  @{i:<5} ",
+                t * 1000.0,
+                c.count,
+            )
+            .unwrap();
+            disassemble_op(c.op, &mut out, &names);
+        }
+        out
+    }
+
+    /// Prepares statistics for the given tape, resetting them if it changed
+    fn bind(&mut self, tape: &GenericVmTape<N>) {
+        if self
+            .tape
+            .as_ref()
+            .is_some_and(|t| Arc::ptr_eq(&t.0, &tape.0))
+        {
+            return;
+        }
+        self.profile.clear();
+        self.profile
+            .extend(tape.data().iter_asm().map(|op| ClauseProfile {
+                op,
+                count: 0,
+                items: 0,
+                time: Duration::ZERO,
+            }));
+        self.tape = Some(tape.clone());
+    }
+}
+
+impl<const N: usize> BulkEvaluator for VmProfileEval<N> {
+    type Data = f32;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, f32>, Error> {
+        tape.vars().check_bulk_arguments(vars)?;
+        self.bind(tape);
+        let data = tape.data();
+
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.bulk.resize_slots(data, size);
+
+        for (op, c) in data.iter_asm().zip(self.profile.iter_mut()) {
+            let start = Instant::now();
+            VmFloatSliceEval::<N>::eval_op(&mut self.bulk, vars, op, size);
+            c.time += start.elapsed();
+            c.count += 1;
+            c.items += size as u64;
+        }
+        Ok(BulkOutput::new(&self.bulk.out, size))
+    }
+}
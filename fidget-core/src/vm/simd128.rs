@@ -0,0 +1,158 @@
+//! Float slice evaluation using WebAssembly SIMD
+//!
+//! This module is only built for `wasm32` targets with the `simd128` target
+//! feature enabled (e.g. with `RUSTFLAGS="-C target-feature=+simd128"`).
+//! Arithmetic operations are evaluated on four values at a time; everything
+//! else falls back to the scalar loops in [`VmFloatSliceEval`](super::VmFloatSliceEval).
+use super::SlotArray;
+use crate::compiler::RegOp;
+use std::arch::wasm32::*;
+
+/// Evaluates a single operation, four values at a time
+///
+/// Returns `false` if the operation isn't handled here, in which case the
+/// caller must evaluate it with the scalar implementation.
+pub(super) fn eval_op(
+    v: &mut SlotArray<'_, Vec<f32>>,
+    op: RegOp,
+    size: usize,
+) -> bool {
+    match op {
+        RegOp::CopyReg(out, arg) => unary(v, out, arg, size, |a| a),
+        RegOp::NegReg(out, arg) => unary(v, out, arg, size, |a| f32x4_neg(a)),
+        RegOp::AbsReg(out, arg) => unary(v, out, arg, size, |a| f32x4_abs(a)),
+        RegOp::SqrtReg(out, arg) => unary(v, out, arg, size, |a| f32x4_sqrt(a)),
+        RegOp::FloorReg(out, arg) => {
+            unary(v, out, arg, size, |a| f32x4_floor(a))
+        }
+        RegOp::CeilReg(out, arg) => unary(v, out, arg, size, |a| f32x4_ceil(a)),
+        RegOp::SquareReg(out, arg) => {
+            unary(v, out, arg, size, |a| f32x4_mul(a, a))
+        }
+        RegOp::RecipReg(out, arg) => {
+            unary(v, out, arg, size, |a| f32x4_div(f32x4_splat(1.0), a))
+        }
+
+        RegOp::AddRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_add(a, b))
+        }
+        RegOp::SubRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_sub(a, b))
+        }
+        RegOp::MulRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_mul(a, b))
+        }
+        RegOp::DivRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_div(a, b))
+        }
+        // `f32x4.min` and `f32x4.max` propagate NaN, matching the VM
+        RegOp::MinRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_min(a, b))
+        }
+        RegOp::MaxRegReg(out, lhs, rhs) => {
+            binary(v, out, lhs, rhs, size, |a, b| f32x4_max(a, b))
+        }
+
+        RegOp::AddRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_add(a, imm))
+        }
+        RegOp::SubRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_sub(a, imm))
+        }
+        RegOp::SubImmReg(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_sub(imm, a))
+        }
+        RegOp::MulRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_mul(a, imm))
+        }
+        RegOp::DivRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_div(a, imm))
+        }
+        RegOp::DivImmReg(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_div(imm, a))
+        }
+        RegOp::MinRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_min(a, imm))
+        }
+        RegOp::MaxRegImm(out, arg, imm) => {
+            let imm = f32x4_splat(imm);
+            unary(v, out, arg, size, |a| f32x4_max(a, imm))
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Loads four values from the front of a slice
+#[inline]
+fn load(s: &[f32]) -> v128 {
+    let s = &s[..4];
+    // SAFETY: `s` contains four values, and `v128_load` has no alignment
+    // requirements
+    unsafe { v128_load(s.as_ptr() as *const v128) }
+}
+
+/// Stores four values to the front of a slice
+#[inline]
+fn store(s: &mut [f32], a: v128) {
+    let s = &mut s[..4];
+    // SAFETY: `s` contains four values, and `v128_store` has no alignment
+    // requirements
+    unsafe { v128_store(s.as_mut_ptr() as *mut v128, a) }
+}
+
+/// Applies `f` to a single value, using the first lane
+#[inline]
+fn scalar(a: f32, f: impl Fn(v128) -> v128) -> f32 {
+    f32x4_extract_lane::<0>(f(f32x4_splat(a)))
+}
+
+#[inline]
+fn unary(
+    v: &mut SlotArray<'_, Vec<f32>>,
+    out: u8,
+    arg: u8,
+    size: usize,
+    f: impl Fn(v128) -> v128,
+) {
+    // `out` and `arg` may be the same slot, so we load before each store
+    // instead of borrowing both slices at once.
+    let mut i = 0;
+    while i + 4 <= size {
+        let a = load(&v[arg][i..]);
+        store(&mut v[out][i..], f(a));
+        i += 4;
+    }
+    for i in i..size {
+        v[out][i] = scalar(v[arg][i], &f);
+    }
+}
+
+#[inline]
+fn binary(
+    v: &mut SlotArray<'_, Vec<f32>>,
+    out: u8,
+    lhs: u8,
+    rhs: u8,
+    size: usize,
+    f: impl Fn(v128, v128) -> v128,
+) {
+    let mut i = 0;
+    while i + 4 <= size {
+        let a = load(&v[lhs][i..]);
+        let b = load(&v[rhs][i..]);
+        store(&mut v[out][i..], f(a, b));
+        i += 4;
+    }
+    for i in i..size {
+        let b = f32x4_splat(v[rhs][i]);
+        v[out][i] = scalar(v[lhs][i], |a| f(a, b));
+    }
+}
@@ -55,6 +55,8 @@ use serde::{Deserialize, Serialize};
 pub struct View2 {
     center: Vector2<f32>,
     scale: f32,
+    #[serde(default)]
+    angle: f32,
 }
 
 impl Default for View2 {
@@ -62,6 +64,7 @@ impl Default for View2 {
         Self {
             scale: 1.0,
             center: Vector2::new(0.0, 0.0),
+            angle: 0.0,
         }
     }
 }
@@ -72,7 +75,11 @@ impl View2 {
     /// The resulting camera will point at the center, and the viewport will be
     /// ± `scale` in size.
     pub fn from_center_and_scale(center: Vector2<f32>, scale: f32) -> Self {
-        Self { center, scale }
+        Self {
+            center,
+            scale,
+            angle: 0.0,
+        }
     }
 
     /// Returns a `(center, scale)` tuple
@@ -88,19 +95,40 @@ impl View2 {
         Self::from_center_and_scale(center, scale)
     }
 
+    /// Returns the view's rotation angle, in radians
+    ///
+    /// The model is rotated clockwise on screen as the angle increases (i.e.
+    /// the view rotates counterclockwise).
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// Sets the view's rotation angle (in radians) about its center
+    pub fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
     /// Returns the scaling matrix for this view
     fn scale_mat(&self) -> Matrix3<f32> {
         Matrix3::new_scaling(self.scale)
     }
 
+    /// Returns the rotation matrix for this view
+    fn rot_mat(&self) -> Matrix3<f32> {
+        Matrix3::new_rotation(self.angle)
+    }
+
     /// Returns the translation matrix for this view
     fn translation_mat(&self) -> Matrix3<f32> {
         Matrix3::new_translation(&self.center)
     }
 
     /// Returns the world-to-model transform matrix
+    ///
+    /// This is a uniform scaling operation, followed by rotation, followed by
+    /// translation.
     pub fn world_to_model(&self) -> Matrix3<f32> {
-        self.translation_mat() * self.scale_mat()
+        self.translation_mat() * self.rot_mat() * self.scale_mat()
     }
 
     /// Transform a point from world to model space
@@ -147,6 +175,41 @@ impl View2 {
         }
         amount != 1.0
     }
+
+    /// Rotates the camera about a particular position (in world space)
+    ///
+    /// `amount` is in radians; if `pos` is `None`, the view rotates about its
+    /// center.  Returns `true` if the view has changed, `false` otherwise.
+    ///
+    /// ```
+    /// # use nalgebra::{Vector2, Point2};
+    /// # use fidget_gui::View2;
+    /// let mut view = View2::default();
+    /// let p = Point2::new(0.5, 0.5);
+    /// let before = view.transform_point(&p);
+    /// view.rotate(std::f32::consts::FRAC_PI_2, Some(p));
+    ///
+    /// // The pivot stays fixed in model space
+    /// assert!((view.transform_point(&p) - before).norm() < 1e-6);
+    ///
+    /// // The world's +X axis now points along the model's +Y axis
+    /// let q = view.transform_point(&Point2::new(1.5, 0.5));
+    /// assert!((q - Point2::new(0.5, 1.5)).norm() < 1e-6);
+    /// ```
+    pub fn rotate(&mut self, amount: f32, pos: Option<Point2<f32>>) -> bool {
+        match pos {
+            Some(before) => {
+                let pos_before = self.transform_point(&before);
+                self.angle += amount;
+                let pos_after = self.transform_point(&before);
+                self.center += pos_before - pos_after;
+            }
+            None => {
+                self.angle += amount;
+            }
+        }
+        amount != 0.0
+    }
 }
 
 /// Object providing a view-to-model transform in 2D
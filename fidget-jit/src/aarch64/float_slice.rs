@@ -1,8 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, IMM_REG, OFFSET, REGISTER_LIMIT,
+    Assembler, AssemblerData, CallSite, IMM_REG, OFFSET, REGISTER_LIMIT,
     float_slice::FloatSliceAssembler, mmap::Mmap, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::Error;
 
 pub const SIMD_WIDTH: usize = 4;
 
@@ -87,9 +88,13 @@ const STACK_SIZE: u32 = 0x230;
 impl Assembler for FloatSliceAssembler {
     type Data = f32;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
-        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE as usize,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             // Preserve frame and link register, and set up the frame pointer
             ; stp   x29, x30, [sp, 0x0]
@@ -131,7 +136,7 @@ impl Assembler for FloatSliceAssembler {
             // Loop body: math begins below
         );
 
-        Self(out)
+        Ok(Self(out))
     }
 
     fn bytes_per_clause() -> usize {
@@ -177,51 +182,27 @@ impl Assembler for FloatSliceAssembler {
     }
 
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_sin(f: f32) -> f32 {
-            f.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_cos(f: f32) -> f32 {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_tan(f: f32) -> f32 {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_asin(f: f32) -> f32 {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_acos(f: f32) -> f32 {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_atan(f: f32) -> f32 {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_exp(f: f32) -> f32 {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_ln(f: f32) -> f32 {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -313,9 +294,6 @@ impl Assembler for FloatSliceAssembler {
         )
     }
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
@@ -423,7 +401,25 @@ impl Assembler for FloatSliceAssembler {
         IMM_REG.wrapping_sub(OFFSET)
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+
+    fn call_targets() -> Vec<usize> {
+        vec![
+            float_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            float_atan2 as *const () as usize,
+        ]
+    }
+
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         dynasm!(self.0.ops
             // update our "items remaining" counter
             ; sub x2, x2, 4 // We handle 4 items at a time
@@ -460,6 +456,44 @@ impl Assembler for FloatSliceAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "C" fn float_sin(f: f32) -> f32 {
+    f.sin()
+}
+
+extern "C" fn float_cos(f: f32) -> f32 {
+    f.cos()
+}
+
+extern "C" fn float_tan(f: f32) -> f32 {
+    f.tan()
+}
+
+extern "C" fn float_asin(f: f32) -> f32 {
+    f.asin()
+}
+
+extern "C" fn float_acos(f: f32) -> f32 {
+    f.acos()
+}
+
+extern "C" fn float_atan(f: f32) -> f32 {
+    f.atan()
+}
+
+extern "C" fn float_exp(f: f32) -> f32 {
+    f.exp()
+}
+
+extern "C" fn float_ln(f: f32) -> f32 {
+    f.ln()
+}
+
+extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
 #[expect(clippy::useless_conversion)]
 impl FloatSliceAssembler {
     fn call_fn_unary(
@@ -491,14 +525,11 @@ impl FloatSliceAssembler {
             ; stp q26, q27, [sp, 0x170]
             ; stp q28, q29, [sp, 0x190]
             ; stp q30, q31, [sp, 0x1b0]
-
-            // Load the function address, awkwardly, into a callee-saved
-            // register (so we only need to do this once)
-            ; movz x24, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x24, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x24, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x24, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into a callee-saved
+        // register (so we only need to do this once)
+        self.0.load_fn(24, addr);
+        dynasm!(self.0.ops
             // We're going to back up our argument into d8/d9 (since the callee
             // only saves the bottom 64 bits).  Note that d8/d9 may be our input
             // argument, so we'll move it to v0 first.
@@ -580,14 +611,11 @@ impl FloatSliceAssembler {
             ; stp q26, q27, [sp, 0x170]
             ; stp q28, q29, [sp, 0x190]
             ; stp q30, q31, [sp, 0x1b0]
-
-            // Load the function address, awkwardly, into a callee-saved
-            // register (so we only need to do this once)
-            ; movz x24, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x24, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x24, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x24, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into a callee-saved
+        // register (so we only need to do this once)
+        self.0.load_fn(24, addr);
+        dynasm!(self.0.ops
             // We're going to back up our argument into d8/d9/d10/d11 (since the
             // callee only saves the bottom 64 bits).  Note that d8/d9/d10/d11
             // may be our input argument, so we'll move it to v0/v1 first.
@@ -1,9 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, IMM_REG, OFFSET, REGISTER_LIMIT,
+    Assembler, AssemblerData, CallSite, IMM_REG, OFFSET, REGISTER_LIMIT,
     grad_slice::GradSliceAssembler, mmap::Mmap, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
-use fidget_core::types::Grad;
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::{Error, types::Grad};
 
 /// Implementation for the gradient slice assembler on `aarch64`
 ///
@@ -87,9 +87,13 @@ const STACK_SIZE: u32 = 0x220;
 impl Assembler for GradSliceAssembler {
     type Data = Grad;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
-        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE as usize,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             // Preserve frame and link register, and set up the frame pointer
             ; stp   x29, x30, [sp, 0x0]
@@ -130,7 +134,7 @@ impl Assembler for GradSliceAssembler {
             // Loop body: math begins below
         );
 
-        Self(out)
+        Ok(Self(out))
     }
 
     fn bytes_per_clause() -> usize {
@@ -176,51 +180,27 @@ impl Assembler for GradSliceAssembler {
     }
 
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn grad_sin(v: Grad) -> Grad {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, grad_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_cos(f: Grad) -> Grad {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_tan(f: Grad) -> Grad {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_asin(f: Grad) -> Grad {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_acos(f: Grad) -> Grad {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_atan(f: Grad) -> Grad {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_exp(f: Grad) -> Grad {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_ln(f: Grad) -> Grad {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -283,21 +263,12 @@ impl Assembler for GradSliceAssembler {
 
     // TODO hand-write these functions
     fn build_floor(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn grad_floor(v: Grad) -> Grad {
-            v.floor()
-        }
         self.call_fn_unary(out_reg, lhs_reg, grad_floor);
     }
     fn build_ceil(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn grad_ceil(v: Grad) -> Grad {
-            v.ceil()
-        }
         self.call_fn_unary(out_reg, lhs_reg, grad_ceil);
     }
     fn build_round(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn grad_round(v: Grad) -> Grad {
-            v.round()
-        }
         self.call_fn_unary(out_reg, lhs_reg, grad_round);
     }
 
@@ -366,9 +337,6 @@ impl Assembler for GradSliceAssembler {
     }
 
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn grad_atan2(y: Grad, x: Grad) -> Grad {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
     }
 
@@ -414,9 +382,6 @@ impl Assembler for GradSliceAssembler {
     }
 
     fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn grad_modulo(lhs: Grad, rhs: Grad) -> Grad {
-            lhs.rem_euclid(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_modulo);
     }
 
@@ -506,7 +471,29 @@ impl Assembler for GradSliceAssembler {
         IMM_REG.wrapping_sub(OFFSET)
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+
+    fn call_targets() -> Vec<usize> {
+        vec![
+            grad_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            grad_floor as *const () as usize,
+            grad_ceil as *const () as usize,
+            grad_round as *const () as usize,
+            grad_atan2 as *const () as usize,
+            grad_modulo as *const () as usize,
+        ]
+    }
+
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         dynasm!(self.0.ops
             // update our "items remaining" counter
             ; sub x2, x2, 1 // We handle 1 item at a time
@@ -542,6 +529,60 @@ impl Assembler for GradSliceAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "C" fn grad_sin(v: Grad) -> Grad {
+    v.sin()
+}
+
+extern "C" fn float_cos(f: Grad) -> Grad {
+    f.cos()
+}
+
+extern "C" fn float_tan(f: Grad) -> Grad {
+    f.tan()
+}
+
+extern "C" fn float_asin(f: Grad) -> Grad {
+    f.asin()
+}
+
+extern "C" fn float_acos(f: Grad) -> Grad {
+    f.acos()
+}
+
+extern "C" fn float_atan(f: Grad) -> Grad {
+    f.atan()
+}
+
+extern "C" fn float_exp(f: Grad) -> Grad {
+    f.exp()
+}
+
+extern "C" fn float_ln(f: Grad) -> Grad {
+    f.ln()
+}
+
+extern "C" fn grad_floor(v: Grad) -> Grad {
+    v.floor()
+}
+
+extern "C" fn grad_ceil(v: Grad) -> Grad {
+    v.ceil()
+}
+
+extern "C" fn grad_round(v: Grad) -> Grad {
+    v.round()
+}
+
+extern "C" fn grad_atan2(y: Grad, x: Grad) -> Grad {
+    y.atan2(x)
+}
+
+extern "C" fn grad_modulo(lhs: Grad, rhs: Grad) -> Grad {
+    lhs.rem_euclid(rhs)
+}
+
 #[expect(clippy::useless_conversion)]
 impl GradSliceAssembler {
     fn call_fn_unary(
@@ -573,15 +614,12 @@ impl GradSliceAssembler {
             ; stp q26, q27, [sp, 0x170]
             ; stp q28, q29, [sp, 0x190]
             ; stp q30, q31, [sp, 0x1b0]
-
-            // Load the function address, awkwardly, into x0 (it doesn't matter
-            // that it can be overwritten, because we're only ever calling it
-            // once)
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into x0 (it doesn't matter
+        // that it can be overwritten, because we're only ever calling it
+        // once)
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             // Prepare to call our stuff!
             ; mov s0, V(reg(arg_reg)).s[0]
             ; mov s1, V(reg(arg_reg)).s[1]
@@ -651,15 +689,12 @@ impl GradSliceAssembler {
             ; stp q26, q27, [sp, 0x170]
             ; stp q28, q29, [sp, 0x190]
             ; stp q30, q31, [sp, 0x1b0]
-
-            // Load the function address, awkwardly, into x0 (it doesn't matter
-            // that it could be thrashed by the call, since we're only calling
-            // it once).
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into x0 (it doesn't matter
+        // that it could be thrashed by the call, since we're only calling
+        // it once).
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             // Prepare to call our stuff!
             ; mov s0, V(reg(lhs_reg)).s[0]
             ; mov s1, V(reg(lhs_reg)).s[1]
@@ -1,9 +1,10 @@
 use crate::{
-    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG,
-    OFFSET, REGISTER_LIMIT, interval::IntervalAssembler, mmap::Mmap, reg,
+    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, CallSite,
+    IMM_REG, OFFSET, REGISTER_LIMIT, interval::IntervalAssembler, mmap::Mmap,
+    reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, dynasm};
-use fidget_core::types::Interval;
+use dynasmrt::{DynasmApi, dynasm};
+use fidget_core::{Error, types::Interval};
 
 /// Implementation for the interval assembler on `aarch64`
 ///
@@ -82,10 +83,15 @@ const STACK_SIZE: u32 = 0x100;
 #[expect(clippy::useless_conversion)]
 impl Assembler for IntervalAssembler {
     type Data = Interval;
+    const MAX_STACK_SIZE: usize = 32768;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
-        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE as usize,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             // Preserve frame and link register, and set up the frame pointer
             ; stp   x29, x30, [sp, 0x0]
@@ -97,7 +103,7 @@ impl Assembler for IntervalAssembler {
             ; stp   d12, d13, [sp, 0x30]
             ; stp   d14, d15, [sp, 0x40]
         );
-        Self(out)
+        Ok(Self(out))
     }
 
     fn bytes_per_clause() -> usize {
@@ -133,51 +139,27 @@ impl Assembler for IntervalAssembler {
         );
     }
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn interval_sin(v: Interval) -> Interval {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, interval_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_cos(f: Interval) -> Interval {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_tan(f: Interval) -> Interval {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_asin(f: Interval) -> Interval {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_acos(f: Interval) -> Interval {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_atan(f: Interval) -> Interval {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_exp(f: Interval) -> Interval {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_ln(f: Interval) -> Interval {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -513,19 +495,10 @@ impl Assembler for IntervalAssembler {
     }
 
     fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn interval_modulo(
-            lhs: Interval,
-            rhs: Interval,
-        ) -> Interval {
-            lhs.rem_euclid(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_modulo);
     }
 
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn interval_atan2(lhs: Interval, rhs: Interval) -> Interval {
-            lhs.atan2(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
     }
 
@@ -761,7 +734,26 @@ impl Assembler for IntervalAssembler {
         IMM_REG.wrapping_sub(OFFSET)
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+
+    fn call_targets() -> Vec<usize> {
+        vec![
+            interval_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            interval_modulo as *const () as usize,
+            interval_atan2 as *const () as usize,
+        ]
+    }
+
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         if self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 // Restore callee-saved registers
@@ -785,6 +777,48 @@ impl Assembler for IntervalAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "C" fn interval_sin(v: Interval) -> Interval {
+    v.sin()
+}
+
+extern "C" fn float_cos(f: Interval) -> Interval {
+    f.cos()
+}
+
+extern "C" fn float_tan(f: Interval) -> Interval {
+    f.tan()
+}
+
+extern "C" fn float_asin(f: Interval) -> Interval {
+    f.asin()
+}
+
+extern "C" fn float_acos(f: Interval) -> Interval {
+    f.acos()
+}
+
+extern "C" fn float_atan(f: Interval) -> Interval {
+    f.atan()
+}
+
+extern "C" fn float_exp(f: Interval) -> Interval {
+    f.exp()
+}
+
+extern "C" fn float_ln(f: Interval) -> Interval {
+    f.ln()
+}
+
+extern "C" fn interval_modulo(lhs: Interval, rhs: Interval) -> Interval {
+    lhs.rem_euclid(rhs)
+}
+
+extern "C" fn interval_atan2(lhs: Interval, rhs: Interval) -> Interval {
+    lhs.atan2(rhs)
+}
+
 #[expect(clippy::useless_conversion)]
 impl IntervalAssembler {
     fn ensure_callee_regs_saved(&mut self) {
@@ -822,14 +856,11 @@ impl IntervalAssembler {
             ; stp d26, d27, [sp, 0xa0]
             ; stp d28, d29, [sp, 0xb0]
             ; stp d30, d31, [sp, 0xc0]
-
-            // Load the function address, awkwardly, into x0 (it doesn't matter
-            // that it's about to be overwritten, because we only call it once)
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into x0 (it doesn't matter
+        // that it's about to be overwritten, because we only call it once)
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             // Prepare to call our stuff!
             ; mov s0, V(reg(arg_reg)).s[0]
             ; mov s1, V(reg(arg_reg)).s[1]
@@ -883,14 +914,11 @@ impl IntervalAssembler {
             ; stp d26, d27, [sp, 0xa0]
             ; stp d28, d29, [sp, 0xb0]
             ; stp d30, d31, [sp, 0xc0]
-
-            // Load the function address, awkwardly, into a caller-saved
-            // register (so we only need to do this once)
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into a caller-saved
+        // register (so we only need to do this once)
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             // Prepare to call our stuff!
             ; mov s0, V(reg(lhs_reg)).s[0]
             ; mov s1, V(reg(lhs_reg)).s[1]
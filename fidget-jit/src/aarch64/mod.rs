@@ -24,6 +24,38 @@ pub const REGISTER_LIMIT: usize = 24;
 pub const IMM_REG: u8 = 3;
 /// `v4-7` are used for as temporary variables
 pub const OFFSET: u8 = 8;
+/// Inputs and outputs are loaded with `ldr`, which has a limited offset range
+pub const MAX_ARGS: usize = 16384 / 8;
+/// Stack frames are reserved with a 16-bit immediate
+pub const MAX_STACK_SIZE: usize = 65536 - 16;
+
+/// Patches the function address loaded at `offset`
+///
+/// `offset` must be the start of a `movz` / `movk` / `movk` / `movk` sequence
+/// (emitted by `AssemblerData::load_fn`); returns `false` if it isn't.
+pub fn relocate(code: &mut [u8], offset: usize, addr: usize) -> bool {
+    let Some(code) = code.get_mut(offset..).and_then(|c| c.get_mut(..16))
+    else {
+        return false;
+    };
+    let mut rd = None;
+    for (i, chunk) in code.chunks_exact_mut(4).enumerate() {
+        let op = u32::from_le_bytes(chunk.try_into().unwrap());
+
+        // 64-bit `movz` (then `movk`), with the shift in bits 21-22
+        let hw = 3 - i as u32;
+        let base = if i == 0 { 0xD280_0000 } else { 0xF280_0000 };
+        if op & 0xFFE0_0000 != base | (hw << 21)
+            || *rd.get_or_insert(op & 0x1F) != op & 0x1F
+        {
+            return false;
+        }
+        let imm = ((addr as u64 >> (16 * hw)) & 0xFFFF) as u32;
+        let op = (op & !(0xFFFF << 5)) | (imm << 5);
+        chunk.copy_from_slice(&op.to_le_bytes());
+    }
+    true
+}
 
 pub mod float_slice;
 pub mod grad_slice;
@@ -1,8 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG,
-    OFFSET, REGISTER_LIMIT, mmap::Mmap, point::PointAssembler, reg,
+    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, CallSite,
+    IMM_REG, OFFSET, REGISTER_LIMIT, mmap::Mmap, point::PointAssembler, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, dynasm};
+use dynasmrt::{DynasmApi, dynasm};
+use fidget_core::Error;
 
 /// Implementation for the single-point assembler on `aarch64`
 ///
@@ -78,10 +79,16 @@ const STACK_SIZE: u32 = 0xb0;
 #[expect(clippy::useless_conversion)]
 impl Assembler for PointAssembler {
     type Data = f32;
+    const MAX_ARGS: usize = 16384 / 4;
+    const MAX_STACK_SIZE: usize = 16384;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
-        out.prepare_stack(slot_count, STACK_SIZE as usize);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE as usize,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             // Preserve frame and link register, and set up the frame pointer
             ; stp   x29, x30, [sp, 0x0]
@@ -94,7 +101,7 @@ impl Assembler for PointAssembler {
             ; stp   d14, d15, [sp, 0x40]
         );
 
-        Self(out)
+        Ok(Self(out))
     }
 
     fn bytes_per_clause() -> usize {
@@ -133,51 +140,27 @@ impl Assembler for PointAssembler {
         dynasm!(self.0.ops ; fmov S(reg(out_reg)), S(reg(lhs_reg)))
     }
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn point_sin(v: f32) -> f32 {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, point_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_cos(f: f32) -> f32 {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_tan(f: f32) -> f32 {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_asin(f: f32) -> f32 {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_acos(f: f32) -> f32 {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_atan(f: f32) -> f32 {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_exp(f: f32) -> f32 {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "C" fn float_ln(f: f32) -> f32 {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -263,9 +246,6 @@ impl Assembler for PointAssembler {
         )
     }
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
@@ -452,7 +432,25 @@ impl Assembler for PointAssembler {
         IMM_REG.wrapping_sub(OFFSET)
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+
+    fn call_targets() -> Vec<usize> {
+        vec![
+            point_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            float_atan2 as *const () as usize,
+        ]
+    }
+
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         if self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 // Restore callee-saved registers
@@ -475,6 +473,44 @@ impl Assembler for PointAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "C" fn point_sin(v: f32) -> f32 {
+    v.sin()
+}
+
+extern "C" fn float_cos(f: f32) -> f32 {
+    f.cos()
+}
+
+extern "C" fn float_tan(f: f32) -> f32 {
+    f.tan()
+}
+
+extern "C" fn float_asin(f: f32) -> f32 {
+    f.asin()
+}
+
+extern "C" fn float_acos(f: f32) -> f32 {
+    f.acos()
+}
+
+extern "C" fn float_atan(f: f32) -> f32 {
+    f.atan()
+}
+
+extern "C" fn float_exp(f: f32) -> f32 {
+    f.exp()
+}
+
+extern "C" fn float_ln(f: f32) -> f32 {
+    f.ln()
+}
+
+extern "C" fn float_atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
 #[expect(clippy::useless_conversion)]
 impl PointAssembler {
     fn ensure_callee_regs_saved(&mut self) {
@@ -513,14 +549,11 @@ impl PointAssembler {
             ; stp s26, s27, [sp, 0x78]
             ; stp s28, s29, [sp, 0x80]
             ; stp s30, s31, [sp, 0x88]
-
-            // Load the function address, awkwardly, into x0 (it doesn't matter
-            // that it's about to be overwritten, because we only call it once)
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into x0 (it doesn't matter
+        // that it's about to be overwritten, because we only call it once)
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             ; fmov s0, S(reg(arg_reg))
             ; blr x0
 
@@ -573,14 +606,11 @@ impl PointAssembler {
             ; stp s26, s27, [sp, 0x78]
             ; stp s28, s29, [sp, 0x80]
             ; stp s30, s31, [sp, 0x88]
-
-            // Load the function address, awkwardly, into x0 (it doesn't matter
-            // that it's about to be overwritten, because we only call it once)
-            ; movz x0, (addr >> 48) as u32 & 0xFFFF, lsl 48
-            ; movk x0, (addr >> 32) as u32 & 0xFFFF, lsl 32
-            ; movk x0, (addr >> 16) as u32 & 0xFFFF, lsl 16
-            ; movk x0, addr as u32 & 0xFFFF
-
+        );
+        // Load the function address, awkwardly, into x0 (it doesn't matter
+        // that it's about to be overwritten, because we only call it once)
+        self.0.load_fn(0, addr);
+        dynasm!(self.0.ops
             ; fmov s0, S(reg(lhs_reg))
             ; fmov s1, S(reg(rhs_reg))
             ; blr x0
@@ -0,0 +1,358 @@
+//! Caching of assembled machine code
+use crate::{CallSite, REGISTER_LIMIT, arch};
+use fidget_core::vm::VmData;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Cache of assembled machine code, used to skip JIT assembly
+///
+/// When a cache is installed with [`set_cache`], every JIT tape first looks up
+/// its [`JitCacheKey`] in the cache; on a hit, the cached entry is relocated
+/// and copied into executable memory instead of being assembled.  After a
+/// miss, the newly assembled code is inserted.
+///
+/// Entries are opaque byte strings.  Machine code calls Rust functions (for
+/// transcendental operations) by absolute address; entries store these
+/// addresses as indexes into a table, and are patched with the current
+/// process's addresses when loaded.  This means that entries may be persisted
+/// (e.g. with [`DiskJitCache`]) and shared between processes.
+///
+/// # Safety
+/// Entries returned by [`get`](JitCache::get) are executed as machine code,
+/// and can't be validated beyond bounds-checking their relocations.
+/// Implementations must only return an entry which was passed to
+/// [`insert`](JitCache::insert) with a key whose
+/// [bytes](JitCacheKey::as_bytes) are identical (matching hashes are not
+/// sufficient), by a process using the same build of `fidget-jit`.
+pub unsafe trait JitCache: Send + Sync {
+    /// Looks up the cache entry for the given key
+    fn get(&self, key: &JitCacheKey) -> Option<Arc<[u8]>>;
+
+    /// Stores a cache entry for the given key
+    fn insert(&self, key: &JitCacheKey, entry: &[u8]);
+}
+
+/// Key identifying a tape in a [`JitCache`]
+///
+/// The key encodes the `fidget-jit` version, the target architecture, the kind
+/// of evaluator, and the serialized tape; the register-allocated tape is
+/// rebuilt deterministically from the serialized tape, so it doesn't need to
+/// be included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JitCacheKey {
+    bytes: Vec<u8>,
+    hash: u64,
+}
+
+impl JitCacheKey {
+    /// Builds a key for a tape assembled with the given assembler
+    pub(crate) fn new<A>(t: &VmData<REGISTER_LIMIT>) -> Self {
+        let mut bytes = vec![];
+        for s in [
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::ARCH,
+            std::any::type_name::<A>(),
+        ] {
+            bytes.extend((s.len() as u32).to_le_bytes());
+            bytes.extend(s.as_bytes());
+        }
+        bytes.extend((REGISTER_LIMIT as u32).to_le_bytes());
+        bytes.extend(t.to_bytes());
+
+        // 64-bit FNV-1a, which is stable across processes and platforms
+        let mut hash = 0xcbf29ce484222325u64;
+        for &b in &bytes {
+            hash = (hash ^ u64::from(b)).wrapping_mul(0x100000001b3);
+        }
+        Self { bytes, hash }
+    }
+
+    /// Returns the key's bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns a 64-bit hash of the key
+    ///
+    /// The hash is the same in every process and on every platform, so it may
+    /// be used to name persistent cache entries.  Different keys may have the
+    /// same hash, so caches must also compare [`as_bytes`](Self::as_bytes).
+    pub fn stable_hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Process-wide cache used by the JIT
+static CACHE: RwLock<Option<Arc<dyn JitCache>>> = RwLock::new(None);
+
+/// Installs (or removes) the process-wide cache of assembled machine code
+///
+/// Returns the previous cache, if one was installed.
+///
+/// ```
+/// # use fidget_core::{context::Tree, shape::EzShape};
+/// # use fidget_jit::{JitShape, MemoryJitCache, set_cache};
+/// # use std::sync::Arc;
+/// let cache = Arc::new(MemoryJitCache::new(64));
+/// let prev = set_cache(Some(cache.clone()));
+///
+/// let shape = JitShape::from(Tree::x() * 1.25 + Tree::y() * 3.5);
+/// let _a = shape.ez_point_tape(); // assembled and cached
+/// let _b = shape.ez_point_tape(); // copied from the cache
+/// assert!(cache.hits() >= 1);
+///
+/// set_cache(prev);
+/// ```
+pub fn set_cache(
+    cache: Option<Arc<dyn JitCache>>,
+) -> Option<Arc<dyn JitCache>> {
+    let mut c = CACHE.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *c, cache)
+}
+
+/// Returns the process-wide cache, if installed
+pub(crate) fn get() -> Option<Arc<dyn JitCache>> {
+    CACHE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// In-memory [`JitCache`], holding a bounded number of entries
+///
+/// When the cache is full, the oldest entry is evicted.
+pub struct MemoryJitCache {
+    data: Mutex<MemoryJitCacheData>,
+    max_entries: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+#[derive(Default)]
+struct MemoryJitCacheData {
+    map: HashMap<Vec<u8>, Arc<[u8]>>,
+
+    /// Keys in insertion order, used for eviction
+    order: VecDeque<Vec<u8>>,
+}
+
+impl MemoryJitCache {
+    /// Builds a new cache which holds up to `max_entries` tapes
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            data: Mutex::default(),
+            max_entries,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of cached tapes
+    pub fn len(&self) -> usize {
+        self.data.lock().unwrap().map.len()
+    }
+
+    /// Checks whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lookups which found cached code
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lookups which did not find cached code
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Removes all cached tapes
+    pub fn clear(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.map.clear();
+        data.order.clear();
+    }
+}
+
+// SAFETY: entries are looked up by their full key
+unsafe impl JitCache for MemoryJitCache {
+    fn get(&self, key: &JitCacheKey) -> Option<Arc<[u8]>> {
+        let out = self.data.lock().unwrap().map.get(key.as_bytes()).cloned();
+        let counter = if out.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        out
+    }
+
+    fn insert(&self, key: &JitCacheKey, entry: &[u8]) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let key = key.as_bytes();
+        let mut data = self.data.lock().unwrap();
+        if data.map.insert(key.to_vec(), entry.into()).is_none() {
+            data.order.push_back(key.to_vec());
+        }
+        while data.map.len() > self.max_entries {
+            let k = data.order.pop_front().unwrap();
+            data.map.remove(&k);
+        }
+    }
+}
+
+/// On-disk [`JitCache`], storing one file per tape in a directory
+///
+/// Files are named by the key's [stable hash](JitCacheKey::stable_hash) and
+/// begin with the full key, which is checked when loading.  I/O errors are
+/// treated as cache misses (when loading) or ignored (when storing).
+///
+/// ```
+/// # use fidget_core::{context::Tree, shape::EzShape};
+/// # use fidget_jit::{DiskJitCache, JitShape, set_cache};
+/// # use std::sync::Arc;
+/// let dir = std::env::temp_dir().join("fidget-jit-cache-doctest");
+///
+/// // SAFETY: this directory is only used by `DiskJitCache`
+/// let cache = Arc::new(unsafe { DiskJitCache::new(&dir) }?);
+/// let prev = set_cache(Some(cache.clone()));
+///
+/// let shape = JitShape::from(Tree::x().sin() + Tree::y() * 3.5);
+/// let _tape = shape.ez_point_tape(); // loaded from disk in later runs
+///
+/// set_cache(prev);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct DiskJitCache {
+    dir: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl DiskJitCache {
+    /// Opens a cache in the given directory, creating it if necessary
+    ///
+    /// # Safety
+    /// Files in the directory are loaded as machine code.  The directory must
+    /// only be written by `DiskJitCache` (in this or other processes) using the
+    /// same build of `fidget-jit`.
+    pub unsafe fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the number of lookups which found cached code
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lookups which did not find cached code
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn path(&self, key: &JitCacheKey) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin", key.stable_hash()))
+    }
+}
+
+// SAFETY: files are only written by `insert`, per the contract of `new`, and
+// entries are returned only if the full key matches.
+unsafe impl JitCache for DiskJitCache {
+    fn get(&self, key: &JitCacheKey) -> Option<Arc<[u8]>> {
+        let out = std::fs::read(self.path(key)).ok().and_then(|data| {
+            let (n, rest) = data.split_first_chunk::<4>()?;
+            let (k, entry) =
+                rest.split_at_checked(u32::from_le_bytes(*n) as usize)?;
+            (k == key.as_bytes()).then(|| entry.into())
+        });
+        let counter = if out.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        out
+    }
+
+    fn insert(&self, key: &JitCacheKey, entry: &[u8]) {
+        let bytes = key.as_bytes();
+        let mut data = Vec::with_capacity(4 + bytes.len() + entry.len());
+        data.extend((bytes.len() as u32).to_le_bytes());
+        data.extend(bytes);
+        data.extend(entry);
+
+        // Write to a temporary file then rename it, so that concurrent
+        // readers never see a partially-written file.
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = self.path(key);
+        let tmp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        if std::fs::write(&tmp, &data).is_err()
+            || std::fs::rename(&tmp, &path).is_err()
+        {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+}
+
+/// Encodes assembled machine code as a cache entry
+///
+/// The entry begins with a relocation table, which stores each embedded
+/// function address as an index into `targets`.
+pub(crate) fn encode(
+    code: &[u8],
+    calls: &[CallSite],
+    targets: &[usize],
+) -> Vec<u8> {
+    let mut out = vec![];
+    out.extend((calls.len() as u32).to_le_bytes());
+    for c in calls {
+        let i = targets
+            .iter()
+            .position(|t| *t == c.addr)
+            .expect("call target is missing from `call_targets`");
+        out.extend((c.offset as u32).to_le_bytes());
+        out.extend((i as u32).to_le_bytes());
+    }
+    out.extend(code);
+    out
+}
+
+/// Decodes a cache entry, returning machine code relocated for this process
+///
+/// Returns `None` if the entry's relocation table is invalid.
+pub(crate) fn decode(entry: &[u8], targets: &[usize]) -> Option<Vec<u8>> {
+    let (n, mut rest) = entry.split_first_chunk::<4>()?;
+    let mut relocs = vec![];
+    for _ in 0..u32::from_le_bytes(*n) {
+        let (offset, r) = rest.split_first_chunk::<4>()?;
+        let (index, r) = r.split_first_chunk::<4>()?;
+        relocs.push((
+            u32::from_le_bytes(*offset) as usize,
+            u32::from_le_bytes(*index) as usize,
+        ));
+        rest = r;
+    }
+    let mut code = rest.to_vec();
+    for (offset, index) in relocs {
+        if !arch::relocate(&mut code, offset, *targets.get(index)?) {
+            return None;
+        }
+    }
+    Some(code)
+}
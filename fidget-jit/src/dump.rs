@@ -0,0 +1,183 @@
+//! Disassembly of JIT-compiled machine code, for debugging
+use crate::{
+    Assembler, JitFunction, REGISTER_LIMIT, assemble,
+    float_slice::FloatSliceAssembler, grad_slice::GradSliceAssembler,
+    interval::IntervalAssembler, mmap::Mmap, point::PointAssembler,
+};
+use fidget_core::{Error, vm::VmData};
+use std::fmt::Write;
+
+impl JitFunction {
+    /// Returns a listing of the machine code used for point evaluation
+    ///
+    /// The code is assembled from scratch (bypassing any installed
+    /// [`JitCache`](crate::JitCache)), then disassembled.  Each block of
+    /// instructions is preceded by a comment with the tape operation that
+    /// produced it, in the format of
+    /// [`RegTape::disassemble`](fidget_core::compiler::RegTape::disassemble);
+    /// the function prologue and epilogue are labeled separately.
+    ///
+    /// Instruction addresses are offsets from the start of the function, so
+    /// branch targets can be checked against the labeled blocks.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Context, eval::MathFunction};
+    /// # use fidget_jit::JitFunction;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let root = ctx.square(x)?;
+    /// let f = JitFunction::new(&ctx, &[root])?;
+    /// println!("{}", f.disassemble_point_tape()?);
+    /// # Ok::<(), fidget_core::Error>(())
+    /// ```
+    pub fn disassemble_point_tape(&self) -> Result<String, Error> {
+        disassemble::<PointAssembler>(self.0.data())
+    }
+
+    /// Returns a listing of the machine code used for interval evaluation
+    ///
+    /// See [`disassemble_point_tape`](Self::disassemble_point_tape) for the
+    /// format.
+    pub fn disassemble_interval_tape(&self) -> Result<String, Error> {
+        disassemble::<IntervalAssembler>(self.0.data())
+    }
+
+    /// Returns a listing of the machine code used for float slice evaluation
+    ///
+    /// See [`disassemble_point_tape`](Self::disassemble_point_tape) for the
+    /// format.
+    pub fn disassemble_float_slice_tape(&self) -> Result<String, Error> {
+        disassemble::<FloatSliceAssembler>(self.0.data())
+    }
+
+    /// Returns a listing of the machine code used for gradient evaluation
+    ///
+    /// See [`disassemble_point_tape`](Self::disassemble_point_tape) for the
+    /// format.
+    pub fn disassemble_grad_slice_tape(&self) -> Result<String, Error> {
+        disassemble::<GradSliceAssembler>(self.0.data())
+    }
+}
+
+fn disassemble<A: Assembler>(
+    t: &VmData<REGISTER_LIMIT>,
+) -> Result<String, Error> {
+    let mut bounds = vec![0];
+    let (code, _calls) = assemble::<A>(t, Mmap::default(), |i| bounds.push(i))?;
+    let code = code.as_slice();
+    bounds.push(code.len());
+
+    let labels = std::iter::once("prologue".to_owned())
+        .chain(t.disassemble_asm())
+        .chain(std::iter::once("epilogue".to_owned()));
+
+    let mut out = String::new();
+    for (label, b) in labels.zip(bounds.windows(2)) {
+        writeln!(&mut out, "; {label}").unwrap();
+        decode(&code[b[0]..b[1]], b[0], &mut out);
+    }
+    Ok(out)
+}
+
+/// Formats bytes as space-separated hex
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(&mut out, "{b:02x}").unwrap();
+    }
+    out
+}
+
+/// Disassembles a block of code which begins at the given offset
+#[cfg(target_arch = "x86_64")]
+fn decode(code: &[u8], offset: usize, out: &mut String) {
+    use iced_x86::{
+        Decoder, DecoderOptions, Formatter, Instruction, IntelFormatter,
+    };
+
+    let mut decoder =
+        Decoder::with_ip(64, code, offset as u64, DecoderOptions::NONE);
+    let mut formatter = IntelFormatter::new();
+    formatter.options_mut().set_branch_leading_zeros(false);
+
+    let mut inst = Instruction::default();
+    let mut text = String::new();
+    while decoder.can_decode() {
+        decoder.decode_out(&mut inst);
+        text.clear();
+        formatter.format(&inst, &mut text);
+
+        let start = inst.ip() as usize - offset;
+        let bytes = &code[start..start + inst.len()];
+        writeln!(out, "{:6x}:  {:<30} {text}", inst.ip(), hex(bytes)).unwrap();
+    }
+}
+
+/// Disassembles a block of code which begins at the given offset
+#[cfg(target_arch = "aarch64")]
+fn decode(code: &[u8], offset: usize, out: &mut String) {
+    use yaxpeax_arch::{Decoder, U8Reader};
+    use yaxpeax_arm::armv8::a64::InstDecoder;
+
+    let decoder = InstDecoder::default();
+    for (i, word) in code.chunks(4).enumerate() {
+        write!(out, "{:6x}:  {:<12} ", offset + i * 4, hex(word)).unwrap();
+        match decoder.decode(&mut U8Reader::new(word)) {
+            Ok(inst) => writeln!(out, "{inst}").unwrap(),
+            Err(_) => writeln!(out, "(invalid)").unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fidget_core::{context::Context, eval::MathFunction};
+
+    #[test]
+    fn disassemble_labels() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.sin(x).unwrap();
+        let b = ctx.mul(y, 2.5).unwrap();
+        let root = ctx.min(a, b).unwrap();
+        let f = JitFunction::new(&ctx, &[root]).unwrap();
+        let ops: Vec<String> = f.0.data().disassemble_asm().collect();
+
+        for s in [
+            f.disassemble_point_tape().unwrap(),
+            f.disassemble_interval_tape().unwrap(),
+            f.disassemble_float_slice_tape().unwrap(),
+            f.disassemble_grad_slice_tape().unwrap(),
+        ] {
+            let labels: Vec<&str> = s
+                .lines()
+                .filter_map(|line| line.strip_prefix("; "))
+                .collect();
+            assert_eq!(labels.len(), ops.len() + 2, "{s}");
+            assert_eq!(labels[0], "prologue");
+            assert_eq!(labels[labels.len() - 1], "epilogue");
+            for (a, b) in labels[1..].iter().zip(&ops) {
+                assert_eq!(a, b);
+            }
+
+            // Every labeled block contains at least one valid instruction
+            let mut count = 0;
+            for line in s.lines() {
+                if line.starts_with("; ") {
+                    assert!(count > 0 || line == "; prologue", "{s}");
+                    count = 0;
+                } else {
+                    assert!(!line.contains("(bad)"), "{s}");
+                    assert!(!line.contains("(invalid)"), "{s}");
+                    count += 1;
+                }
+            }
+            assert!(count > 0, "{s}");
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! JIT functions which are assembled in the background
+use crate::{
+    JitBulkFn, JitFloatSliceEval, JitFunction, JitGradSliceEval,
+    JitIntervalEval, JitPointEval, JitTracingFn, REGISTER_LIMIT, mmap::Mmap,
+};
+use fidget_core::{
+    Error,
+    compiler::BuildBudget,
+    context::{Context, Node},
+    eval::{
+        BulkEvaluator, BulkOutput, Function, MathFunction, Tape,
+        TracingEvaluator,
+    },
+    render::{RenderHints, TileSizes},
+    types::{Grad, Interval},
+    var::{Var, VarMap},
+    vm::{
+        GenericVmFunction, GenericVmTape, VmData, VmFloatSliceEval,
+        VmGradSliceEval, VmIntervalEval, VmPointEval, VmTrace, VmWorkspace,
+    },
+};
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{Arc, OnceLock},
+};
+
+/// Tapes assembled by a background thread
+#[derive(Default)]
+struct LazyTapes {
+    point: OnceLock<JitTracingFn<f32>>,
+    interval: OnceLock<JitTracingFn<Interval>>,
+    float_slice: OnceLock<JitBulkFn<f32>>,
+    grad_slice: OnceLock<JitBulkFn<Grad>>,
+
+    /// Set when the worker thread has finished
+    done: OnceLock<()>,
+}
+
+/// Function which is evaluated by the interpreter until its JIT tapes are ready
+///
+/// Assembling machine code for a large function can take a noticeable amount
+/// of time, which is paid before the first evaluation with a [`JitFunction`].
+/// A `LazyJitFunction` instead starts assembling its tapes on a worker thread
+/// (as a [`rayon::spawn`] job) when it's constructed.  Until a particular tape is ready, calls to
+/// [`Function::point_tape`] (etc) return a tape which is evaluated by the
+/// interpreter (see [`fidget_core::vm`]); once assembly finishes, newly
+/// requested tapes are JIT-compiled, so callers which rebuild tapes on each
+/// frame switch over to the JIT automatically.
+///
+/// Results are identical between the two paths, and traces from either can be
+/// used to simplify the function.  Simplified functions are usually much
+/// smaller, so they're assembled on demand (like a [`JitFunction`]) rather
+/// than in the background.  If assembly fails (see
+/// [`JitFunction::try_point_tape`]), the interpreter is used instead.
+///
+/// ```
+/// # use fidget_core::{context::Tree, eval::Function, shape::EzShape};
+/// # use fidget_jit::LazyJitShape;
+/// let shape = LazyJitShape::from(Tree::x() + Tree::y());
+///
+/// // Evaluation can start right away, using whichever backend is ready
+/// let tape = shape.ez_point_tape();
+/// let mut eval = LazyJitShape::new_point_eval();
+/// let (r, _trace) = eval.eval(&tape, 0.1, 0.3, 0.0)?;
+/// assert_eq!(r, 0.1 + 0.3);
+///
+/// // Block until the JIT has finished; new tapes will use it
+/// let f = shape.inner();
+/// f.wait();
+/// assert!(f.point_tape(Default::default()).is_jit());
+/// # Ok::<(), fidget_core::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct LazyJitFunction {
+    f: JitFunction,
+
+    /// Tapes assembled in the background, or `None` to assemble on demand
+    tapes: Option<Arc<LazyTapes>>,
+}
+
+impl LazyJitFunction {
+    /// Wraps a function, starting background assembly of its tapes
+    fn spawn(f: JitFunction) -> Self {
+        let tapes = Arc::new(LazyTapes::default());
+        let worker = (f.clone(), tapes.clone());
+        rayon::spawn(move || {
+            let (f, tapes) = worker;
+            // Interval and float slice tapes are used first when rendering.
+            // If assembly fails, the tape stays empty and the interpreter is
+            // used indefinitely.
+            let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                if let Ok(t) = f.try_interval_tape(Default::default()) {
+                    let _ = tapes.interval.set(t);
+                }
+                if let Ok(t) = f.try_float_slice_tape(Default::default()) {
+                    let _ = tapes.float_slice.set(t);
+                }
+                if let Ok(t) = f.try_point_tape(Default::default()) {
+                    let _ = tapes.point.set(t);
+                }
+                if let Ok(t) = f.try_grad_slice_tape(Default::default()) {
+                    let _ = tapes.grad_slice.set(t);
+                }
+            }));
+            let _ = tapes.done.set(());
+        });
+        Self {
+            f,
+            tapes: Some(tapes),
+        }
+    }
+
+    /// Checks whether background assembly has finished
+    pub fn is_ready(&self) -> bool {
+        self.tapes.as_ref().is_none_or(|t| t.done.get().is_some())
+    }
+
+    /// Blocks until background assembly has finished
+    ///
+    /// If called from a `rayon` worker thread, this runs other queued jobs
+    /// while waiting, so it can't deadlock a pool whose workers are all busy
+    /// waiting for assembly.
+    pub fn wait(&self) {
+        if let Some(t) = &self.tapes {
+            while t.done.get().is_none() {
+                if rayon::yield_now() != Some(rayon::Yield::Executed) {
+                    t.done.wait();
+                }
+            }
+        }
+    }
+
+    /// Returns the inner JIT function
+    pub fn jit(&self) -> &JitFunction {
+        &self.f
+    }
+
+    /// Returns the approximate number of heap bytes held by this function
+    ///
+    /// See [`JitFunction::memory_usage`] for details.
+    pub fn memory_usage(&self) -> usize {
+        self.f.memory_usage()
+    }
+
+    fn vm(&self) -> &GenericVmFunction<REGISTER_LIMIT> {
+        (&self.f).into()
+    }
+
+    /// Returns a tape from the given slot, building one if needed
+    ///
+    /// If the function is being assembled in the background and the tape isn't
+    /// ready yet, or if assembly fails, an interpreter tape is returned instead.
+    fn tape<T: Clone>(
+        &self,
+        slot: impl FnOnce(&LazyTapes) -> &OnceLock<T>,
+        jit: impl FnOnce() -> Result<T, Error>,
+    ) -> LazyTape<T> {
+        match &self.tapes {
+            None => match jit() {
+                Ok(t) => LazyTape::Jit(t),
+                Err(_) => LazyTape::Vm(self.vm().tape()),
+            },
+            Some(t) => match slot(t).get() {
+                Some(t) => LazyTape::Jit(t.clone()),
+                None => LazyTape::Vm(self.vm().tape()),
+            },
+        }
+    }
+}
+
+impl Function for LazyJitFunction {
+    type Trace = VmTrace;
+    type Storage = VmData<REGISTER_LIMIT>;
+    type Workspace = VmWorkspace<REGISTER_LIMIT>;
+
+    type TapeStorage = Mmap;
+
+    type IntervalEval = LazyIntervalEval;
+    type PointEval = LazyPointEval;
+    type FloatSliceEval = LazyFloatSliceEval;
+    type GradSliceEval = LazyGradSliceEval;
+
+    fn point_tape(&self, storage: Mmap) -> LazyTape<JitTracingFn<f32>> {
+        self.tape(|t| &t.point, || self.f.try_point_tape(storage))
+    }
+
+    fn interval_tape(&self, storage: Mmap) -> LazyTape<JitTracingFn<Interval>> {
+        self.tape(|t| &t.interval, || self.f.try_interval_tape(storage))
+    }
+
+    fn float_slice_tape(&self, storage: Mmap) -> LazyTape<JitBulkFn<f32>> {
+        self.tape(|t| &t.float_slice, || self.f.try_float_slice_tape(storage))
+    }
+
+    fn grad_slice_tape(&self, storage: Mmap) -> LazyTape<JitBulkFn<Grad>> {
+        self.tape(|t| &t.grad_slice, || self.f.try_grad_slice_tape(storage))
+    }
+
+    fn simplify(
+        &self,
+        trace: &Self::Trace,
+        storage: Self::Storage,
+        workspace: &mut Self::Workspace,
+    ) -> Result<Self, Error> {
+        let f = self.f.simplify(trace, storage, workspace)?;
+        Ok(Self { f, tapes: None })
+    }
+
+    fn recycle(self) -> Option<Self::Storage> {
+        self.f.recycle()
+    }
+
+    fn size(&self) -> usize {
+        self.f.size()
+    }
+
+    fn vars(&self) -> &VarMap {
+        self.f.vars()
+    }
+
+    fn can_simplify(&self) -> bool {
+        self.f.can_simplify()
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.f.id()
+    }
+}
+
+impl RenderHints for LazyJitFunction {
+    fn tile_sizes_3d() -> TileSizes {
+        JitFunction::tile_sizes_3d()
+    }
+
+    fn tile_sizes_2d() -> TileSizes {
+        JitFunction::tile_sizes_2d()
+    }
+
+    fn simplify_tree_during_meshing(d: usize) -> bool {
+        JitFunction::simplify_tree_during_meshing(d)
+    }
+}
+
+impl MathFunction for LazyJitFunction {
+    fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        JitFunction::new(ctx, nodes).map(Self::spawn)
+    }
+
+    fn new_with_budget(
+        ctx: &Context,
+        nodes: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<Self, Error> {
+        JitFunction::new_with_budget(ctx, nodes, budget).map(Self::spawn)
+    }
+
+    fn specialize(&self, var: Var, value: f32) -> Result<Self, Error> {
+        self.f.specialize(var, value).map(Self::spawn)
+    }
+
+    fn compose(&self, inner: &Self, wiring: &[Var]) -> Result<Self, Error> {
+        self.f.compose(&inner.f, wiring).map(Self::spawn)
+    }
+
+    fn gradient(&self, vars: &[Var]) -> Result<Self, Error> {
+        self.f.gradient(vars).map(Self::spawn)
+    }
+}
+
+impl From<JitFunction> for LazyJitFunction {
+    /// Wraps a function, starting background assembly of its tapes
+    fn from(f: JitFunction) -> Self {
+        Self::spawn(f)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Tape which is evaluated by either the interpreter or the JIT
+#[derive(Clone)]
+pub enum LazyTape<T> {
+    /// Interpreter tape, used while the JIT tape is being assembled
+    Vm(GenericVmTape<REGISTER_LIMIT>),
+    /// JIT tape
+    Jit(T),
+}
+
+impl<T> LazyTape<T> {
+    /// Checks whether this tape is evaluated by the JIT
+    pub fn is_jit(&self) -> bool {
+        matches!(self, LazyTape::Jit(..))
+    }
+}
+
+impl<T: Tape<Storage = Mmap>> Tape for LazyTape<T> {
+    type Storage = Mmap;
+    fn recycle(self) -> Option<Self::Storage> {
+        // Interpreter tapes (and JIT tapes which are shared with the function)
+        // don't own a mapping, so we return empty storage instead.
+        match self {
+            LazyTape::Vm(..) => Some(Mmap::default()),
+            LazyTape::Jit(t) => Some(t.recycle().unwrap_or_default()),
+        }
+    }
+
+    fn vars(&self) -> &VarMap {
+        match self {
+            LazyTape::Vm(t) => t.vars(),
+            LazyTape::Jit(t) => t.vars(),
+        }
+    }
+
+    fn output_count(&self) -> usize {
+        match self {
+            LazyTape::Vm(t) => t.output_count(),
+            LazyTape::Jit(t) => t.output_count(),
+        }
+    }
+}
+
+/// Evaluator which dispatches to the interpreter or the JIT, based on the tape
+#[derive(Default)]
+pub struct LazyEval<V, J> {
+    vm: V,
+    jit: J,
+}
+
+/// Tracing evaluator for point values, used by [`LazyJitFunction`]
+pub type LazyPointEval = LazyEval<VmPointEval<REGISTER_LIMIT>, JitPointEval>;
+
+/// Tracing evaluator for interval values, used by [`LazyJitFunction`]
+pub type LazyIntervalEval =
+    LazyEval<VmIntervalEval<REGISTER_LIMIT>, JitIntervalEval>;
+
+/// Bulk evaluator for arrays of points, used by [`LazyJitFunction`]
+pub type LazyFloatSliceEval =
+    LazyEval<VmFloatSliceEval<REGISTER_LIMIT>, JitFloatSliceEval>;
+
+/// Bulk evaluator for arrays of gradients, used by [`LazyJitFunction`]
+pub type LazyGradSliceEval =
+    LazyEval<VmGradSliceEval<REGISTER_LIMIT>, JitGradSliceEval>;
+
+impl<V, J, T> TracingEvaluator for LazyEval<V, J>
+where
+    V: TracingEvaluator<Tape = GenericVmTape<REGISTER_LIMIT>, Trace = VmTrace>,
+    J: TracingEvaluator<
+            Data = V::Data,
+            Tape = T,
+            TapeStorage = Mmap,
+            Trace = VmTrace,
+        >,
+    T: Tape<Storage = Mmap>,
+{
+    type Data = V::Data;
+    type Tape = LazyTape<T>;
+    type Trace = VmTrace;
+    type TapeStorage = Mmap;
+
+    #[inline]
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Self::Data],
+    ) -> Result<(&[Self::Data], Option<&Self::Trace>), Error> {
+        match tape {
+            LazyTape::Vm(t) => self.vm.eval(t, vars),
+            LazyTape::Jit(t) => self.jit.eval(t, vars),
+        }
+    }
+}
+
+impl<V, J, T> BulkEvaluator for LazyEval<V, J>
+where
+    V: BulkEvaluator<Tape = GenericVmTape<REGISTER_LIMIT>>,
+    J: BulkEvaluator<Data = V::Data, Tape = T, TapeStorage = Mmap>,
+    T: Tape<Storage = Mmap>,
+{
+    type Data = V::Data;
+    type Tape = LazyTape<T>;
+    type TapeStorage = Mmap;
+
+    #[inline]
+    fn eval<I: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[I],
+    ) -> Result<BulkOutput<'_, Self::Data>, Error> {
+        match tape {
+            LazyTape::Vm(t) => self.vm.eval(t, vars),
+            LazyTape::Jit(t) => self.jit.eval(t, vars),
+        }
+    }
+}
+
+/// A [`Shape`](fidget_core::shape::Shape) which uses the [`LazyJitFunction`]
+pub type LazyJitShape = fidget_core::shape::Shape<LazyJitFunction>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    fidget_core::grad_slice_tests!(LazyJitFunction);
+    fidget_core::interval_tests!(LazyJitFunction);
+    fidget_core::float_slice_tests!(LazyJitFunction);
+    fidget_core::point_tests!(LazyJitFunction);
+
+    #[test]
+    fn lazy_hot_swap() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.sin(x).unwrap();
+        let s = ctx.max(a, y).unwrap();
+        let f = LazyJitFunction::new(&ctx, &[s]).unwrap();
+
+        let mut eval = LazyIntervalEval::default();
+        let args = [Interval::new(0.0, 0.0), Interval::new(-2.0, -1.0)];
+        let tape = f.interval_tape(Default::default());
+        let (out, trace) = eval.eval(&tape, &args).unwrap();
+        assert_eq!(out[0], Interval::new(0.0, 0.0));
+        let trace = trace.unwrap().clone();
+
+        f.wait();
+        assert!(f.is_ready());
+        let tape = f.interval_tape(Default::default());
+        assert!(tape.is_jit());
+        let (out, jit_trace) = eval.eval(&tape, &args).unwrap();
+        assert_eq!(out[0], Interval::new(0.0, 0.0));
+        assert!(jit_trace == Some(&trace));
+
+        // Simplified functions are assembled on demand
+        let g = f
+            .simplify(&trace, Default::default(), &mut Default::default())
+            .unwrap();
+        assert!(g.is_ready());
+        let tape = g.float_slice_tape(Default::default());
+        assert!(tape.is_jit());
+        let mut eval = LazyFloatSliceEval::default();
+        let out = eval.eval(&tape, &[vec![0.0; 3], vec![1.0; 3]]).unwrap();
+        assert_eq!(out[0], [0.0; 3]);
+    }
+
+    #[test]
+    fn lazy_wait_in_pool() {
+        // Assembly is queued on the pool that's waiting for it, which only has
+        // a single worker; waiting must run the job rather than deadlock.
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let s = ctx.sin(x).unwrap();
+        let f = pool.install(|| {
+            let f = LazyJitFunction::new(&ctx, &[s]).unwrap();
+            f.wait();
+            f
+        });
+        assert!(f.is_ready());
+        assert!(f.point_tape(Default::default()).is_jit());
+    }
+}
@@ -26,7 +26,7 @@
 use crate::mmap::{Mmap, MmapWriter};
 use fidget_core::{
     Error,
-    compiler::RegOp,
+    compiler::{BuildBudget, RegOp},
     context::{Context, Node},
     eval::{
         BulkEvaluator, BulkOutput, Function, MathFunction, Tape,
@@ -34,7 +34,7 @@ use fidget_core::{
     },
     render::{RenderHints, TileSizes},
     types::{Grad, Interval},
-    var::VarMap,
+    var::{Var, VarMap},
     vm::{Choice, GenericVmFunction, VmData, VmTrace, VmWorkspace},
 };
 
@@ -44,8 +44,19 @@ use dynasmrt::{
 };
 use std::sync::Arc;
 
+mod cache;
+#[cfg(feature = "dump-asm")]
+mod dump;
+mod lazy;
 mod mmap;
 mod permit;
+pub use cache::{
+    DiskJitCache, JitCache, JitCacheKey, MemoryJitCache, set_cache,
+};
+pub use lazy::{
+    LazyEval, LazyFloatSliceEval, LazyGradSliceEval, LazyIntervalEval,
+    LazyJitFunction, LazyJitShape, LazyPointEval, LazyTape,
+};
 pub(crate) use permit::WritePermit;
 
 // Evaluators
@@ -112,11 +123,29 @@ trait Assembler {
     /// This should be a `repr(C)` type, so it can be passed around directly.
     type Data;
 
+    /// Maximum number of inputs or outputs
+    const MAX_ARGS: usize = arch::MAX_ARGS;
+
+    /// Maximum size of the stack frame, in bytes
+    const MAX_STACK_SIZE: usize = arch::MAX_STACK_SIZE;
+
     /// Initializes the assembler with the given slot count
     ///
     /// This will likely construct a function prelude and reserve space on the
-    /// stack for slot spills.
-    fn init(m: Mmap, slot_count: usize) -> Self;
+    /// stack for slot spills.  Returns an error if the stack frame would be
+    /// larger than [`Self::MAX_STACK_SIZE`].
+    fn init(m: Mmap, slot_count: usize) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// Returns the current offset into the generated code, in bytes
+    fn offset(&self) -> usize;
+
+    /// Returns the address of every function which generated code may call
+    ///
+    /// Cached machine code refers to these functions by their index in this
+    /// list, so that it can be relocated when loaded (see [`JitCache`]).
+    fn call_targets() -> Vec<usize>;
 
     /// Returns an approximate bytes per clause value, used for preallocation
     fn bytes_per_clause() -> usize {
@@ -271,7 +300,10 @@ trait Assembler {
     fn load_imm(&mut self, imm: f32) -> u8;
 
     /// Finalize the assembly code, returning a memory-mapped region
-    fn finalize(self) -> Result<Mmap, DynasmError>;
+    ///
+    /// The memory-mapped region is returned alongside the location of every
+    /// function address embedded in the code.
+    fn finalize(self) -> Result<(Mmap, Vec<CallSite>), Error>;
 }
 
 /// Trait defining SIMD width
@@ -285,9 +317,22 @@ pub trait SimdSize {
 
 /////////////////////////////////////////////////////////////////////////////////////////
 
+/// Location of a function address embedded in generated code
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CallSite {
+    /// Offset of the address load, in bytes
+    offset: usize,
+
+    /// Address of the called function
+    addr: usize,
+}
+
 pub(crate) struct AssemblerData<T> {
     ops: MmapAssembler,
 
+    /// Function addresses loaded by the generated code
+    calls: Vec<CallSite>,
+
     /// Current offset of the stack pointer, in bytes
     mem_offset: usize,
 
@@ -304,21 +349,36 @@ impl<T> AssemblerData<T> {
     fn new(mmap: Mmap) -> Self {
         Self {
             ops: MmapAssembler::from(mmap),
+            calls: vec![],
             mem_offset: 0,
             saved_callee_regs: false,
             _p: std::marker::PhantomData,
         }
     }
 
-    fn prepare_stack(&mut self, slot_count: usize, stack_size: usize) {
+    fn prepare_stack(
+        &mut self,
+        slot_count: usize,
+        stack_size: usize,
+        max_stack_size: usize,
+    ) -> Result<(), Error> {
         // We always use the stack, if only to store callee-saved registers
         let mem = slot_count.saturating_sub(REGISTER_LIMIT)
             * std::mem::size_of::<T>()
             + stack_size;
 
         // Round up to the nearest multiple of 16 bytes, for alignment
-        self.mem_offset = mem.next_multiple_of(16);
+        let mem = mem.next_multiple_of(16);
+        if mem > max_stack_size {
+            return Err(Error::JitStackTooLarge(mem, max_stack_size));
+        }
+        self.mem_offset = mem;
         self.push_stack();
+        Ok(())
+    }
+
+    fn offset(&self) -> usize {
+        self.ops.offset().0
     }
 
     fn stack_pos(&self, slot: u32) -> u32 {
@@ -327,22 +387,52 @@ impl<T> AssemblerData<T> {
     }
 }
 
+/// Stack size which can be allocated without probing
+#[cfg(target_arch = "x86_64")]
+const STACK_PAGE_SIZE: usize = 4096;
+
 #[cfg(target_arch = "x86_64")]
 impl<T> AssemblerData<T> {
     fn push_stack(&mut self) {
+        // Windows commits stack memory one page at a time, when the guard
+        // page below the stack is touched; moving `rsp` past the guard page
+        // would make later accesses fault.  Large frames are therefore probed
+        // one page at a time, like `__chkstk` does for compiled code.  This is
+        // harmless on other platforms, so we always do it.
+        let mut remaining = self.mem_offset;
+        while remaining > STACK_PAGE_SIZE {
+            dynasm!(self.ops
+                ; sub rsp, STACK_PAGE_SIZE as i32
+                ; mov rax, QWORD [rsp]
+            );
+            remaining -= STACK_PAGE_SIZE;
+        }
+        dynasm!(self.ops
+            ; sub rsp, remaining as i32
+        );
+    }
+
+    /// Loads the address of a function into the given register
+    ///
+    /// The location of the address is recorded, so that the code can be
+    /// relocated (see [`x86_64::relocate`]).
+    #[expect(clippy::useless_conversion)]
+    fn load_fn(&mut self, reg: u8, addr: usize) {
+        let offset = self.offset();
         dynasm!(self.ops
-            ; sub rsp, self.mem_offset as i32
+            ; mov Rq(reg), QWORD addr as _
         );
+        self.calls.push(CallSite { offset, addr });
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         dynasm!(self.ops
             ; add rsp, self.mem_offset as i32
             ; pop rbp
             ; vzeroupper
             ; ret
         );
-        self.ops.finalize()
+        Ok((self.ops.finalize()?, self.calls))
     }
 }
 
@@ -364,7 +454,23 @@ impl<T> AssemblerData<T> {
         }
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    /// Loads the address of a function into the given register
+    ///
+    /// The location of the address is recorded, so that the code can be
+    /// relocated (see [`aarch64::relocate`]).
+    #[allow(clippy::useless_conversion)]
+    fn load_fn(&mut self, reg: u8, addr: usize) {
+        let offset = self.offset();
+        dynasm!(self.ops
+            ; movz X(reg), (addr >> 48) as u32 & 0xFFFF, lsl 48
+            ; movk X(reg), (addr >> 32) as u32 & 0xFFFF, lsl 32
+            ; movk X(reg), (addr >> 16) as u32 & 0xFFFF, lsl 16
+            ; movk X(reg), addr as u32 & 0xFFFF
+        );
+        self.calls.push(CallSite { offset, addr });
+    }
+
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         // Fix up the stack
         if self.mem_offset < 4096 {
             dynasm!(self.ops
@@ -382,7 +488,7 @@ impl<T> AssemblerData<T> {
         dynasm!(self.ops
             ; ret
         );
-        self.ops.finalize()
+        Ok((self.ops.finalize()?, self.calls))
     }
 }
 
@@ -616,6 +722,12 @@ impl MmapAssembler {
     ///
     /// This should be called after any function which uses local labels.
     fn commit_local(&mut self) -> Result<(), DynasmError> {
+        // If writing failed, the code will be discarded by `finalize`
+        if self.mmap.failed() {
+            self.local_relocs.clear();
+            self.local_labels = [None; 26];
+            return Ok(());
+        }
         let baseaddr = self.mmap.as_ptr() as usize;
 
         for (loc, label) in self.local_relocs.take() {
@@ -632,8 +744,12 @@ impl MmapAssembler {
         Ok(())
     }
 
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
-        self.commit_local()?;
+    fn finalize(mut self) -> Result<Mmap, Error> {
+        self.commit_local()
+            .map_err(|e| Error::JitAssembly(e.to_string()))?;
+        if self.mmap.failed() {
+            return Ok(self.mmap.finalize()?);
+        }
 
         let baseaddr = self.mmap.as_ptr() as usize;
         for (loc, label) in self.global_relocs.take() {
@@ -641,13 +757,16 @@ impl MmapAssembler {
                 self.global_labels.get(label as usize).unwrap().unwrap();
             let buf = &mut self.mmap.as_mut_slice()[loc.range(0)];
             if loc.patch(buf, baseaddr, target.0).is_err() {
-                return Err(DynasmError::ImpossibleRelocation(
-                    TargetKind::Global("oh no"),
+                return Err(Error::JitAssembly(
+                    DynasmError::ImpossibleRelocation(TargetKind::Global(
+                        "oh no",
+                    ))
+                    .to_string(),
                 ));
             }
         }
 
-        Ok(self.mmap.finalize())
+        Ok(self.mmap.finalize()?)
     }
 }
 
@@ -668,15 +787,64 @@ impl From<Mmap> for MmapAssembler {
 fn build_asm_fn_with_storage<A: Assembler>(
     t: &VmData<REGISTER_LIMIT>,
     mut s: Mmap,
-) -> Mmap {
+) -> Result<Mmap, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+        "jit_assemble",
+        ty = std::any::type_name::<A::Data>(),
+        tape_len = t.len(),
+    )
+    .entered();
+
+    let cache = cache::get().map(|c| (c, cache::JitCacheKey::new::<A>(t)));
+    if let Some((c, key)) = &cache
+        && let Some(entry) = c.get(key)
+        && let Some(code) = cache::decode(&entry, &A::call_targets())
+    {
+        if code.len() > s.capacity() {
+            s = Mmap::new(code.len())?;
+        }
+        let mut w = MmapWriter::from(s);
+        for &b in code.iter() {
+            w.push(b);
+        }
+        return Ok(w.finalize()?);
+    }
+
+    let (out, calls) = assemble::<A>(t, s, |_| ())?;
+    if let Some((c, key)) = &cache {
+        let entry = cache::encode(out.as_slice(), &calls, &A::call_targets());
+        c.insert(key, &entry);
+    }
+    Ok(out)
+}
+
+/// Assembles a tape into machine code
+///
+/// `on_op` is called with the current code offset before each operation is
+/// assembled, then once more before the function epilogue.
+///
+/// Returns the machine code and the location of every embedded function
+/// address.
+fn assemble<A: Assembler>(
+    t: &VmData<REGISTER_LIMIT>,
+    mut s: Mmap,
+    mut on_op: impl FnMut(usize),
+) -> Result<(Mmap, Vec<CallSite>), Error> {
+    let arg_count = t.vars.len().max(t.output_count());
+    if arg_count > A::MAX_ARGS {
+        return Err(Error::JitTooManyArgs(arg_count, A::MAX_ARGS));
+    }
+
     let size_estimate = t.len() * A::bytes_per_clause();
     if size_estimate > 2 * s.capacity() {
-        s = Mmap::new(size_estimate).expect("failed to build mmap")
+        s = Mmap::new(size_estimate)?;
     }
 
-    let mut asm = A::init(s, t.slot_count());
+    let mut asm = A::init(s, t.slot_count())?;
 
     for op in t.iter_asm() {
+        on_op(asm.offset());
         match op {
             RegOp::Load(reg, mem) => {
                 asm.build_load(reg, mem);
@@ -844,8 +1012,41 @@ fn build_asm_fn_with_storage<A: Assembler>(
         }
     }
 
-    asm.finalize().expect("failed to build JIT function")
-    // JIT execute mode is restored here when the _guard is dropped
+    on_op(asm.offset());
+    asm.finalize()
+}
+
+/// Snapshot of executable memory held by JIT-compiled tapes
+///
+/// This is global across all threads, and counts every live mapping (including
+/// spare storage which has been recycled but not yet reused).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct JitMemoryUsage {
+    /// Number of bytes currently mapped
+    pub bytes: usize,
+    /// Number of live mappings
+    pub mappings: usize,
+    /// Maximum value of `bytes` since startup (or the last call to
+    /// [`reset_peak_memory_usage`])
+    pub peak_bytes: usize,
+}
+
+/// Returns the amount of executable memory currently held by JIT tapes
+///
+/// A steadily growing `mappings` count in a long-running process usually
+/// indicates that retired tapes are being leaked.
+pub fn memory_usage() -> JitMemoryUsage {
+    let (bytes, mappings, peak_bytes) = mmap::usage();
+    JitMemoryUsage {
+        bytes,
+        mappings,
+        peak_bytes,
+    }
+}
+
+/// Resets [`JitMemoryUsage::peak_bytes`] to the current mapped size
+pub fn reset_peak_memory_usage() {
+    mmap::reset_peak()
 }
 
 /// Function for use with a JIT evaluator
@@ -853,13 +1054,66 @@ fn build_asm_fn_with_storage<A: Assembler>(
 pub struct JitFunction(GenericVmFunction<REGISTER_LIMIT>);
 
 impl JitFunction {
+    /// Returns the approximate number of heap bytes held by this function
+    ///
+    /// This doesn't include machine code, which is owned by the tapes built
+    /// from the function (see [`JitTracingFn::memory_usage`] and
+    /// [`JitBulkFn::memory_usage`]).
+    pub fn memory_usage(&self) -> usize {
+        self.0.memory_usage()
+    }
+
+    /// Builds a tape for point evaluation, returning an error on failure
+    ///
+    /// [`Function::point_tape`] panics if assembly fails; this function instead
+    /// returns an error if the tape has too many inputs or outputs, needs a
+    /// stack frame which is too large for the target, or if executable memory
+    /// can't be allocated.
+    pub fn try_point_tape(
+        &self,
+        storage: Mmap,
+    ) -> Result<JitTracingFn<f32>, Error> {
+        self.tracing_tape::<point::PointAssembler>(storage)
+    }
+
+    /// Builds a tape for interval evaluation, returning an error on failure
+    ///
+    /// See [`try_point_tape`](Self::try_point_tape) for details.
+    pub fn try_interval_tape(
+        &self,
+        storage: Mmap,
+    ) -> Result<JitTracingFn<Interval>, Error> {
+        self.tracing_tape::<interval::IntervalAssembler>(storage)
+    }
+
+    /// Builds a tape for float slice evaluation, returning an error on failure
+    ///
+    /// See [`try_point_tape`](Self::try_point_tape) for details.
+    pub fn try_float_slice_tape(
+        &self,
+        storage: Mmap,
+    ) -> Result<JitBulkFn<f32>, Error> {
+        self.bulk_tape::<float_slice::FloatSliceAssembler>(storage)
+    }
+
+    /// Builds a tape for gradient slice evaluation, returning an error on
+    /// failure
+    ///
+    /// See [`try_point_tape`](Self::try_point_tape) for details.
+    pub fn try_grad_slice_tape(
+        &self,
+        storage: Mmap,
+    ) -> Result<JitBulkFn<Grad>, Error> {
+        self.bulk_tape::<grad_slice::GradSliceAssembler>(storage)
+    }
+
     fn tracing_tape<A: Assembler>(
         &self,
         storage: Mmap,
-    ) -> JitTracingFn<A::Data> {
-        let f = build_asm_fn_with_storage::<A>(self.0.data(), storage);
+    ) -> Result<JitTracingFn<A::Data>, Error> {
+        let f = build_asm_fn_with_storage::<A>(self.0.data(), storage)?;
         let ptr = f.as_ptr();
-        JitTracingFn {
+        Ok(JitTracingFn {
             mmap: f.into(),
             vars: self.0.data().vars.clone(),
             choice_count: self.0.choice_count(),
@@ -870,12 +1124,15 @@ impl JitFunction {
                     JitTracingFnPointer<A::Data>,
                 >(ptr)
             },
-        }
+        })
     }
-    fn bulk_tape<A: Assembler>(&self, storage: Mmap) -> JitBulkFn<A::Data> {
-        let f = build_asm_fn_with_storage::<A>(self.0.data(), storage);
+    fn bulk_tape<A: Assembler>(
+        &self,
+        storage: Mmap,
+    ) -> Result<JitBulkFn<A::Data>, Error> {
+        let f = build_asm_fn_with_storage::<A>(self.0.data(), storage)?;
         let ptr = f.as_ptr();
-        JitBulkFn {
+        Ok(JitBulkFn {
             mmap: f.into(),
             output_count: self.0.output_count(),
             vars: self.0.data().vars.clone(),
@@ -885,7 +1142,7 @@ impl JitFunction {
                     JitBulkFnPointer<A::Data>,
                 >(ptr)
             },
-        }
+        })
     }
 }
 
@@ -901,24 +1158,47 @@ impl Function for JitFunction {
     type FloatSliceEval = JitFloatSliceEval;
     type GradSliceEval = JitGradSliceEval;
 
+    /// Builds a tape for point evaluation
+    ///
+    /// # Panics
+    /// If assembly fails; use [`JitFunction::try_point_tape`] to handle errors
     #[inline]
     fn point_tape(&self, storage: Mmap) -> JitTracingFn<f32> {
-        self.tracing_tape::<point::PointAssembler>(storage)
+        self.try_point_tape(storage)
+            .expect("failed to build JIT point tape")
     }
 
+    /// Builds a tape for interval evaluation
+    ///
+    /// # Panics
+    /// If assembly fails; use [`JitFunction::try_interval_tape`] to handle
+    /// errors
     #[inline]
     fn interval_tape(&self, storage: Mmap) -> JitTracingFn<Interval> {
-        self.tracing_tape::<interval::IntervalAssembler>(storage)
+        self.try_interval_tape(storage)
+            .expect("failed to build JIT interval tape")
     }
 
+    /// Builds a tape for float slice evaluation
+    ///
+    /// # Panics
+    /// If assembly fails; use [`JitFunction::try_float_slice_tape`] to handle
+    /// errors
     #[inline]
     fn float_slice_tape(&self, storage: Mmap) -> JitBulkFn<f32> {
-        self.bulk_tape::<float_slice::FloatSliceAssembler>(storage)
+        self.try_float_slice_tape(storage)
+            .expect("failed to build JIT float slice tape")
     }
 
+    /// Builds a tape for gradient slice evaluation
+    ///
+    /// # Panics
+    /// If assembly fails; use [`JitFunction::try_grad_slice_tape`] to handle
+    /// errors
     #[inline]
     fn grad_slice_tape(&self, storage: Mmap) -> JitBulkFn<Grad> {
-        self.bulk_tape::<grad_slice::GradSliceAssembler>(storage)
+        self.try_grad_slice_tape(storage)
+            .expect("failed to build JIT gradient slice tape")
     }
 
     #[inline]
@@ -950,6 +1230,11 @@ impl Function for JitFunction {
     fn can_simplify(&self) -> bool {
         self.0.choice_count() > 0
     }
+
+    #[inline]
+    fn id(&self) -> Option<usize> {
+        self.0.id()
+    }
 }
 
 impl RenderHints for JitFunction {
@@ -971,6 +1256,31 @@ impl MathFunction for JitFunction {
     fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
         GenericVmFunction::new(ctx, nodes).map(JitFunction)
     }
+
+    /// Builds a new function, subject to the given [`BuildBudget`]
+    ///
+    /// The budget only applies to building the VM tape.  Machine code is
+    /// assembled later, when evaluator tapes are requested, and doesn't count
+    /// against the budget.
+    fn new_with_budget(
+        ctx: &Context,
+        nodes: &[Node],
+        budget: &BuildBudget,
+    ) -> Result<Self, Error> {
+        GenericVmFunction::new_with_budget(ctx, nodes, budget).map(JitFunction)
+    }
+
+    fn specialize(&self, var: Var, value: f32) -> Result<Self, Error> {
+        self.0.specialize(var, value).map(JitFunction)
+    }
+
+    fn compose(&self, inner: &Self, wiring: &[Var]) -> Result<Self, Error> {
+        self.0.compose(&inner.0, wiring).map(JitFunction)
+    }
+
+    fn gradient(&self, vars: &[Var]) -> Result<Self, Error> {
+        self.0.gradient(vars).map(JitFunction)
+    }
 }
 
 impl From<GenericVmFunction<REGISTER_LIMIT>> for JitFunction {
@@ -987,9 +1297,9 @@ impl<'a> From<&'a JitFunction> for &'a GenericVmFunction<REGISTER_LIMIT> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-// Selects the calling convention based on platform; this is forward-looking for
-// eventual x86 Windows support, where we still want to use the sysv64 calling
-// convention.
+// Selects the calling convention based on platform.  On x86_64, we use the
+// sysv64 calling convention everywhere (including Windows, where it isn't the
+// default), so that the assemblers don't need to handle the Win64 ABI.
 /// Macro to build a function type with a `extern "sysv64"` calling convention
 ///
 /// This is selected at compile time, based on `target_arch`
@@ -1050,6 +1360,15 @@ pub struct JitTracingFn<T> {
     fn_trace: JitTracingFnPointer<T>,
 }
 
+impl<T> JitTracingFn<T> {
+    /// Returns the number of bytes of executable memory held by this tape
+    ///
+    /// The mapping is shared between clones of the tape.
+    pub fn memory_usage(&self) -> usize {
+        self.mmap.capacity()
+    }
+}
+
 impl<T: Clone> Tape for JitTracingFn<T> {
     type Storage = Mmap;
     fn recycle(self) -> Option<Self::Storage> {
@@ -1162,6 +1481,15 @@ pub struct JitBulkFn<T> {
     fn_bulk: JitBulkFnPointer<T>,
 }
 
+impl<T> JitBulkFn<T> {
+    /// Returns the number of bytes of executable memory held by this tape
+    ///
+    /// The mapping is shared between clones of the tape.
+    pub fn memory_usage(&self) -> usize {
+        self.mmap.capacity()
+    }
+}
+
 impl<T: Clone> Tape for JitBulkFn<T> {
     type Storage = Mmap;
     fn recycle(self) -> Option<Self::Storage> {
@@ -1315,6 +1643,7 @@ impl BulkEvaluator for JitFloatSliceEval {
     type Data = f32;
     type Tape = JitBulkFn<Self::Data>;
     type TapeStorage = Mmap;
+    const SIMD_SIZE: usize = <f32 as SimdSize>::SIMD_SIZE;
 
     #[inline]
     fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
@@ -1334,6 +1663,7 @@ impl BulkEvaluator for JitGradSliceEval {
     type Data = Grad;
     type Tape = JitBulkFn<Self::Data>;
     type TapeStorage = Mmap;
+    const SIMD_SIZE: usize = <Grad as SimdSize>::SIMD_SIZE;
 
     #[inline]
     fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
@@ -1376,4 +1706,218 @@ mod test {
             assert_eq!(v, i);
         }
     }
+
+    #[test]
+    fn test_memory_usage() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.add(x, y).unwrap();
+        let f = JitFunction::new(&ctx, &[s]).unwrap();
+        assert!(f.memory_usage() > 0);
+
+        let tape = f.point_tape(Default::default());
+        let size = tape.memory_usage();
+        assert!(size > 0);
+
+        // Other tests may be running in parallel, so we can only check lower
+        // bounds on global memory usage.
+        let usage = memory_usage();
+        assert!(usage.bytes >= size);
+        assert!(usage.mappings >= 1);
+        assert!(usage.peak_bytes >= size);
+    }
+
+    #[test]
+    fn test_large_stack_frame() {
+        // Pair up values from opposite ends of a long list, so that most of
+        // them are live at once and must be spilled to the stack.
+        const N: usize = 1024;
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let vs: Vec<Node> =
+            (0..N).map(|i| ctx.add(x, i as f32).unwrap()).collect();
+        let mut sum = ctx.constant(0.0);
+        for i in 0..N {
+            let p = ctx.mul(vs[i], vs[N - 1 - i]).unwrap();
+            sum = ctx.add(sum, p).unwrap();
+        }
+        let f = JitFunction::new(&ctx, &[sum]).unwrap();
+        let vm: &GenericVmFunction<REGISTER_LIMIT> = (&f).into();
+        let spills = vm.data().slot_count() - REGISTER_LIMIT;
+        assert!(spills * std::mem::size_of::<Grad>() > 4096);
+
+        // Compare against the interpreter, which evaluates the same clauses
+        let tape = vm.point_tape(Default::default());
+        let mut eval = fidget_core::vm::VmPointEval::default();
+        let expected = eval.eval(&tape, &[0.5]).unwrap().0[0];
+
+        let tape = f.point_tape(Default::default());
+        let mut eval = JitPointEval::default();
+        let (out, _) = eval.eval(&tape, &[0.5]).unwrap();
+        assert_eq!(out[0], expected);
+
+        let tape = f.float_slice_tape(Default::default());
+        let mut eval = JitFloatSliceEval::default();
+        let out = eval.eval(&tape, &[vec![0.5; 9]]).unwrap();
+        assert_eq!(out[0], [expected; 9]);
+
+        let tape = f.grad_slice_tape(Default::default());
+        let mut eval = JitGradSliceEval::default();
+        let out = eval.eval(&tape, &[vec![Grad::from(0.5); 3]]).unwrap();
+        assert_eq!(out[0][0].v, expected);
+    }
+
+    #[test]
+    fn test_try_tape() {
+        const N: usize = 3000;
+        let mut ctx = Context::new();
+        let mut sum = ctx.constant(0.0);
+        for _ in 0..N {
+            let v = ctx.var(Var::new());
+            sum = ctx.add(sum, v).unwrap();
+        }
+        let f = JitFunction::new(&ctx, &[sum]).unwrap();
+
+        // The limit depends on the target architecture
+        let max = <float_slice::FloatSliceAssembler as Assembler>::MAX_ARGS;
+        match f.try_float_slice_tape(Default::default()) {
+            Ok(tape) => {
+                assert!(N <= max);
+                let mut eval = JitFloatSliceEval::default();
+                let args = vec![vec![1.0; 3]; N];
+                let out = eval.eval(&tape, &args).unwrap();
+                assert_eq!(out[0], [N as f32; 3]);
+            }
+            Err(Error::JitTooManyArgs(n, m)) => {
+                assert!(N > max);
+                assert_eq!((n, m), (N, max));
+            }
+            Err(e) => panic!("unexpected error {e:?}"),
+        }
+    }
+
+    /// Lock held by tests which install a process-wide [`JitCache`]
+    static CACHE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn test_jit_cache() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.sin(x).unwrap();
+        let s = ctx.mul(s, 7.125).unwrap();
+        let s = ctx.max(s, y).unwrap();
+        let f = JitFunction::new(&ctx, &[s]).unwrap();
+
+        let _guard = CACHE_LOCK.lock().unwrap();
+        let cache = Arc::new(MemoryJitCache::new(16));
+        let prev = set_cache(Some(cache.clone()));
+
+        // Other tests may use the cache while it's installed, so we can only
+        // check lower bounds on its statistics.
+        let mut point = JitPointEval::default();
+        let mut float = JitFloatSliceEval::default();
+        let mut interval = JitIntervalEval::default();
+        for _ in 0..2 {
+            let tape = f.point_tape(Default::default());
+            let (out, _) = point.eval(&tape, &[1.0, -10.0]).unwrap();
+            assert_eq!(out[0], 1f32.sin() * 7.125);
+
+            let tape = f.float_slice_tape(Default::default());
+            let out = float.eval(&tape, &[vec![0.0; 5], vec![3.0; 5]]).unwrap();
+            assert_eq!(out[0], [3.0; 5]);
+
+            let tape = f.interval_tape(Default::default());
+            let args = [Interval::new(0.0, 0.0), Interval::new(-2.0, -1.0)];
+            let (out, _) = interval.eval(&tape, &args).unwrap();
+            assert_eq!(out[0], Interval::new(0.0, 0.0));
+        }
+        assert!(cache.hits() >= 3);
+        assert!(!cache.is_empty());
+
+        set_cache(prev);
+    }
+
+    #[test]
+    fn test_jit_cache_relocation() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.sin(x).unwrap();
+        let s = ctx.atan2(s, y).unwrap();
+        let f = JitFunction::new(&ctx, &[s]).unwrap();
+
+        let (code, calls) = assemble::<point::PointAssembler>(
+            f.0.data(),
+            Mmap::default(),
+            |_| (),
+        )
+        .unwrap();
+        assert_eq!(calls.len(), 2);
+        let code = code.as_slice();
+        let targets = point::PointAssembler::call_targets();
+        let entry = cache::encode(code, &calls, &targets);
+        assert_eq!(cache::decode(&entry, &targets).unwrap(), code);
+
+        // Loading the entry in a process with different function addresses
+        // only changes the embedded addresses
+        let moved = targets.iter().map(|t| t + 0x1230).collect::<Vec<_>>();
+        let other = cache::decode(&entry, &moved).unwrap();
+        assert_eq!(other.len(), code.len());
+        assert_ne!(other, code);
+        let moved_calls = calls
+            .iter()
+            .map(|c| CallSite {
+                offset: c.offset,
+                addr: c.addr + 0x1230,
+            })
+            .collect::<Vec<_>>();
+        let back = cache::encode(&other, &moved_calls, &moved);
+        assert_eq!(cache::decode(&back, &targets).unwrap(), code);
+
+        // Malformed entries are rejected
+        assert!(cache::decode(&entry[..6], &targets).is_none());
+        let mut bad = entry.clone();
+        bad[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(cache::decode(&bad, &targets).is_none());
+        let mut bad = entry.clone();
+        bad[8..12].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(cache::decode(&bad, &targets).is_none());
+    }
+
+    #[test]
+    fn test_disk_jit_cache() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.sin(x).unwrap();
+        let t = ctx.atan2(y, x).unwrap();
+        let s = ctx.add(s, t).unwrap();
+        let f = JitFunction::new(&ctx, &[s]).unwrap();
+
+        let _guard = CACHE_LOCK.lock().unwrap();
+        let dir = std::env::temp_dir()
+            .join(format!("fidget-jit-test-{}", std::process::id()));
+        let mut point = JitPointEval::default();
+        for i in 0..2 {
+            // Open a new cache each time, as if in a new process
+            let cache = Arc::new(unsafe { DiskJitCache::new(&dir) }.unwrap());
+            let prev = set_cache(Some(cache.clone()));
+            let tape = f.point_tape(Default::default());
+            set_cache(prev);
+
+            let (out, _) = point.eval(&tape, &[1.0, 2.0]).unwrap();
+            assert_eq!(out[0], 1f32.sin() + 2f32.atan2(1.0));
+
+            // Other tests may use the cache while it's installed, so we can
+            // only check lower bounds on its statistics.
+            if i == 0 {
+                assert!(cache.misses() >= 1);
+            } else {
+                assert!(cache.hits() >= 1);
+            }
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -4,12 +4,51 @@ use windows::Win32::System::Memory::{
     VirtualFree,
 };
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Total number of bytes currently mapped by live [`Mmap`] objects
+static MAPPED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of live (non-empty) [`Mmap`] objects
+static MAPPED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// High-water mark of [`MAPPED_BYTES`]
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns `(bytes, mappings, peak_bytes)` for all live mappings
+pub(crate) fn usage() -> (usize, usize, usize) {
+    (
+        MAPPED_BYTES.load(Ordering::Relaxed),
+        MAPPED_COUNT.load(Ordering::Relaxed),
+        PEAK_BYTES.load(Ordering::Relaxed),
+    )
+}
+
+/// Resets the high-water mark to the current number of mapped bytes
+pub(crate) fn reset_peak() {
+    PEAK_BYTES.store(MAPPED_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+fn track_alloc(capacity: usize) {
+    let prev = MAPPED_BYTES.fetch_add(capacity, Ordering::Relaxed);
+    MAPPED_COUNT.fetch_add(1, Ordering::Relaxed);
+    PEAK_BYTES.fetch_max(prev + capacity, Ordering::Relaxed);
+}
+
+fn track_free(capacity: usize) {
+    MAPPED_BYTES.fetch_sub(capacity, Ordering::Relaxed);
+    MAPPED_COUNT.fetch_sub(1, Ordering::Relaxed);
+}
+
 pub struct Mmap {
     /// Pointer to a memory-mapped region, which may be uninitialized
     ptr: *mut std::ffi::c_void,
 
     /// Total length of the allocation
     capacity: usize,
+
+    /// Number of bytes written by the [`MmapWriter`] which finalized it
+    len: usize,
 }
 
 // SAFETY: this is philosophically a `Vec<u8>`, so can be sent to other threads
@@ -26,6 +65,7 @@ impl Mmap {
         Self {
             ptr: std::ptr::null_mut::<std::ffi::c_void>(),
             capacity: 0,
+            len: 0,
         }
     }
 
@@ -56,7 +96,12 @@ impl Mmap {
         if std::ptr::eq(ptr, libc::MAP_FAILED) {
             Err(std::io::Error::last_os_error())
         } else {
-            Ok(Self { ptr, capacity })
+            track_alloc(capacity);
+            Ok(Self {
+                ptr,
+                capacity,
+                len: 0,
+            })
         }
     }
 
@@ -76,7 +121,12 @@ impl Mmap {
         if ptr.is_null() {
             Err(std::io::Error::last_os_error())
         } else {
-            Ok(Self { ptr, capacity })
+            track_alloc(capacity);
+            Ok(Self {
+                ptr,
+                capacity,
+                len: 0,
+            })
         }
     }
 
@@ -84,6 +134,17 @@ impl Mmap {
     pub fn as_ptr(&self) -> *const std::ffi::c_void {
         self.ptr
     }
+
+    /// Returns the bytes written when this mmap was last finalized
+    pub fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            unsafe {
+                std::slice::from_raw_parts(self.ptr as *const u8, self.len)
+            }
+        }
+    }
 }
 
 #[cfg(any(target_os = "linux", target_os = "windows"))]
@@ -186,6 +247,7 @@ impl Drop for Mmap {
             unsafe {
                 libc::munmap(self.ptr, self.capacity as libc::size_t);
             }
+            track_free(self.capacity);
         }
     }
 }
@@ -197,6 +259,7 @@ impl Drop for Mmap {
             unsafe {
                 let _ = VirtualFree(self.ptr, 0, MEM_RELEASE);
             }
+            track_free(self.capacity);
         }
     }
 }
@@ -209,6 +272,9 @@ pub struct MmapWriter {
     /// Number of bytes that have been written
     len: usize,
 
+    /// Error from a failed resize, after which writes are discarded
+    error: Option<std::io::Error>,
+
     _permit: WritePermit,
 }
 
@@ -217,6 +283,7 @@ impl From<Mmap> for MmapWriter {
         MmapWriter {
             mmap,
             len: 0,
+            error: None,
             _permit: WritePermit::new(),
         }
     }
@@ -224,30 +291,55 @@ impl From<Mmap> for MmapWriter {
 
 impl MmapWriter {
     /// Writes a byte to the next uninitialized position, resizing if necessary
+    ///
+    /// If resizing fails, the byte is discarded (but still counted), and the
+    /// error is returned by [`finalize`](Self::finalize).
     #[inline(always)]
     pub fn push(&mut self, b: u8) {
-        if self.len == self.mmap.capacity {
+        if self.len == self.mmap.capacity && self.error.is_none() {
             self.double_capacity()
         }
-        unsafe {
-            *(self.mmap.ptr as *mut u8).add(self.len) = b;
+        if self.len < self.mmap.capacity {
+            unsafe {
+                *(self.mmap.ptr as *mut u8).add(self.len) = b;
+            }
         }
         self.len += 1;
     }
 
     #[inline(never)]
     fn double_capacity(&mut self) {
-        let mut next = Mmap::new(self.mmap.capacity * 2).unwrap();
-        unsafe {
-            std::ptr::copy_nonoverlapping(self.mmap.ptr, next.ptr, self.len());
+        match Mmap::new(self.mmap.capacity * 2) {
+            Ok(mut next) => {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        self.mmap.ptr,
+                        next.ptr,
+                        self.len(),
+                    );
+                }
+                std::mem::swap(&mut self.mmap, &mut next);
+            }
+            Err(e) => self.error = Some(e),
         }
-        std::mem::swap(&mut self.mmap, &mut next);
+    }
+
+    /// Checks whether a resize has failed
+    #[inline(always)]
+    pub fn failed(&self) -> bool {
+        self.error.is_some()
     }
 
     /// Finalizes the mmap, invalidating the system icache
-    pub fn finalize(self) -> Mmap {
+    ///
+    /// Returns an error if the mmap could not be resized during writing.
+    pub fn finalize(mut self) -> Result<Mmap, std::io::Error> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
         self.mmap.flush_cache(self.len);
-        self.mmap
+        self.mmap.len = self.len;
+        Ok(self.mmap)
     }
 
     /// Returns the number of bytes written
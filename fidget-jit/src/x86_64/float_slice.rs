@@ -1,8 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, IMM_REG, OFFSET, REGISTER_LIMIT,
+    Assembler, AssemblerData, CallSite, IMM_REG, OFFSET, REGISTER_LIMIT,
     float_slice::FloatSliceAssembler, mmap::Mmap, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::Error;
 
 pub const SIMD_WIDTH: usize = 8;
 
@@ -60,13 +61,17 @@ const STACK_SIZE_LOWER: usize = 0x220; // Positions relative to `rsp`
 impl Assembler for FloatSliceAssembler {
     type Data = f32;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
         dynasm!(out.ops
             ; push rbp
             ; mov rbp, rsp
         );
-        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE_UPPER + STACK_SIZE_LOWER,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             // TODO should there be a `vzeroupper` in here?
 
@@ -78,7 +83,7 @@ impl Assembler for FloatSliceAssembler {
             ; test rdx, rdx
             ; jz ->X // jump to the exit if we're done, otherwise fallthrough
         );
-        Self(out)
+        Ok(Self(out))
     }
     fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
         assert!((dst_reg as usize) < REGISTER_LIMIT);
@@ -118,51 +123,27 @@ impl Assembler for FloatSliceAssembler {
     }
 
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_sin(f: f32) -> f32 {
-            f.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_cos(f: f32) -> f32 {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_tan(f: f32) -> f32 {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_asin(f: f32) -> f32 {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_acos(f: f32) -> f32 {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_atan(f: f32) -> f32 {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_exp(f: f32) -> f32 {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_ln(f: f32) -> f32 {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -251,9 +232,6 @@ impl Assembler for FloatSliceAssembler {
         );
     }
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
@@ -376,7 +354,23 @@ impl Assembler for FloatSliceAssembler {
         );
         IMM_REG.wrapping_sub(OFFSET)
     }
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+    fn call_targets() -> Vec<usize> {
+        vec![
+            float_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            float_atan2 as *const () as usize,
+        ]
+    }
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         dynasm!(self.0.ops
             ; sub rdx, 8
             ; add rcx, 32
@@ -389,6 +383,44 @@ impl Assembler for FloatSliceAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "sysv64" fn float_sin(f: f32) -> f32 {
+    f.sin()
+}
+
+extern "sysv64" fn float_cos(f: f32) -> f32 {
+    f.cos()
+}
+
+extern "sysv64" fn float_tan(f: f32) -> f32 {
+    f.tan()
+}
+
+extern "sysv64" fn float_asin(f: f32) -> f32 {
+    f.asin()
+}
+
+extern "sysv64" fn float_acos(f: f32) -> f32 {
+    f.acos()
+}
+
+extern "sysv64" fn float_atan(f: f32) -> f32 {
+    f.atan()
+}
+
+extern "sysv64" fn float_exp(f: f32) -> f32 {
+    f.exp()
+}
+
+extern "sysv64" fn float_ln(f: f32) -> f32 {
+    f.ln()
+}
+
+extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
 #[expect(clippy::useless_conversion)]
 impl FloatSliceAssembler {
     fn call_fn_unary(
@@ -419,9 +451,10 @@ impl FloatSliceAssembler {
             ; vmovups [rsp + 0x120], ymm13
             ; vmovups [rsp + 0x140], ymm14
             ; vmovups [rsp + 0x160], ymm15
-
-            // Put the function pointer into a caller-saved register
-            ; mov r15, QWORD addr as _
+        );
+        // Put the function pointer into a caller-saved register
+        self.0.load_fn(15, addr);
+        dynasm!(self.0.ops
             ; vmovups [rsp + 0x180], Ry(reg(arg_reg))
 
             ; movd xmm0, [rsp + 0x180]
@@ -503,10 +536,10 @@ impl FloatSliceAssembler {
             ; vmovups [rsp + 0x120], ymm13
             ; vmovups [rsp + 0x140], ymm14
             ; vmovups [rsp + 0x160], ymm15
-
-            // Put the function pointer into a caller-saved register
-            ; mov r15, QWORD addr as _
-
+        );
+        // Put the function pointer into a caller-saved register
+        self.0.load_fn(15, addr);
+        dynasm!(self.0.ops
             // Copy our input arguments to the stack for safe-keeping
             ; vmovups [rsp + 0x180], Ry(reg(lhs_reg))
             ; vmovups [rsp + 0x200], Ry(reg(rhs_reg))
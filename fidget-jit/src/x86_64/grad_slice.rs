@@ -1,9 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, IMM_REG, OFFSET, REGISTER_LIMIT,
+    Assembler, AssemblerData, CallSite, IMM_REG, OFFSET, REGISTER_LIMIT,
     grad_slice::GradSliceAssembler, mmap::Mmap, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
-use fidget_core::types::Grad;
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::{Error, types::Grad};
 
 /// Implementation for the gradient slice assembler on `x86_64`
 ///
@@ -51,13 +51,17 @@ const STACK_SIZE_LOWER: usize = 0xc0; // Positions relative to `rsp`
 impl Assembler for GradSliceAssembler {
     type Data = Grad;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
         dynasm!(out.ops
             ; push rbp
             ; mov rbp, rsp
         );
-        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE_UPPER + STACK_SIZE_LOWER,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             ; xor rcx, rcx // set the array offset (rcx) to 0
 
@@ -67,7 +71,7 @@ impl Assembler for GradSliceAssembler {
             ; test rdx, rdx
             ; jz ->X // jump to the exit if we're done, otherwise fallthrough
         );
-        Self(out)
+        Ok(Self(out))
     }
     fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
         assert!((dst_reg as usize) < REGISTER_LIMIT);
@@ -107,51 +111,27 @@ impl Assembler for GradSliceAssembler {
     }
 
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn grad_sin(v: Grad) -> Grad {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, grad_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_cos(f: Grad) -> Grad {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_tan(f: Grad) -> Grad {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_asin(f: Grad) -> Grad {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_acos(f: Grad) -> Grad {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_atan(f: Grad) -> Grad {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_exp(f: Grad) -> Grad {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_ln(f: Grad) -> Grad {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -339,9 +319,6 @@ impl Assembler for GradSliceAssembler {
     }
 
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn grad_atan2(y: Grad, x: Grad) -> Grad {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
     }
 
@@ -392,9 +369,6 @@ impl Assembler for GradSliceAssembler {
         self.0.ops.commit_local().unwrap();
     }
     fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn grad_modulo(lhs: Grad, rhs: Grad) -> Grad {
-            lhs.rem_euclid(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_modulo);
     }
     fn build_not(&mut self, out_reg: u8, arg_reg: u8) {
@@ -472,7 +446,24 @@ impl Assembler for GradSliceAssembler {
         );
         IMM_REG.wrapping_sub(OFFSET)
     }
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+    fn call_targets() -> Vec<usize> {
+        vec![
+            grad_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            grad_atan2 as *const () as usize,
+            grad_modulo as *const () as usize,
+        ]
+    }
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         dynasm!(self.0.ops
             ; sub rdx, 1 // we process one element at a time
             ; add rcx, 16 // input is array is Grad (f32 x 4)
@@ -485,6 +476,48 @@ impl Assembler for GradSliceAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "sysv64" fn grad_sin(v: Grad) -> Grad {
+    v.sin()
+}
+
+extern "sysv64" fn float_cos(f: Grad) -> Grad {
+    f.cos()
+}
+
+extern "sysv64" fn float_tan(f: Grad) -> Grad {
+    f.tan()
+}
+
+extern "sysv64" fn float_asin(f: Grad) -> Grad {
+    f.asin()
+}
+
+extern "sysv64" fn float_acos(f: Grad) -> Grad {
+    f.acos()
+}
+
+extern "sysv64" fn float_atan(f: Grad) -> Grad {
+    f.atan()
+}
+
+extern "sysv64" fn float_exp(f: Grad) -> Grad {
+    f.exp()
+}
+
+extern "sysv64" fn float_ln(f: Grad) -> Grad {
+    f.ln()
+}
+
+extern "sysv64" fn grad_atan2(y: Grad, x: Grad) -> Grad {
+    y.atan2(x)
+}
+
+extern "sysv64" fn grad_modulo(lhs: Grad, rhs: Grad) -> Grad {
+    lhs.rem_euclid(rhs)
+}
+
 #[expect(clippy::useless_conversion)]
 impl GradSliceAssembler {
     fn call_fn_unary(
@@ -518,7 +551,9 @@ impl GradSliceAssembler {
             // call the function, packing the gradient into xmm0 + xmm1
             ; movsd xmm0, Rx(reg(arg_reg))
             ; vpshufd xmm1, Rx(reg(arg_reg)), 0b1110
-            ; mov rdx, QWORD addr as _
+        );
+        self.0.load_fn(2, addr);
+        dynasm!(self.0.ops
             ; call rdx
 
             // Restore gradient registers
@@ -582,7 +617,9 @@ impl GradSliceAssembler {
             ; movsd xmm2, Rx(reg(rhs_reg))
             ; vpshufd xmm3, Rx(reg(rhs_reg)), 0b1110
             ; movsd xmm0, Rx(reg(lhs_reg))
-            ; mov rdx, QWORD addr as _
+        );
+        self.0.load_fn(2, addr);
+        dynasm!(self.0.ops
             ; call rdx
 
             // Restore gradient registers
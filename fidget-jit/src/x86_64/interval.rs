@@ -1,9 +1,10 @@
 use crate::{
-    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG,
-    OFFSET, REGISTER_LIMIT, interval::IntervalAssembler, mmap::Mmap, reg,
+    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, CallSite,
+    IMM_REG, OFFSET, REGISTER_LIMIT, interval::IntervalAssembler, mmap::Mmap,
+    reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
-use fidget_core::types::Interval;
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::{Error, types::Interval};
 
 /// Implementation of the interval assembler on `x86_64`
 ///
@@ -50,17 +51,21 @@ const STACK_SIZE_LOWER: usize = 0x60; // Positions relative to `rsp`
 impl Assembler for IntervalAssembler {
     type Data = Interval;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
         dynasm!(out.ops
             ; push rbp
             ; mov rbp, rsp
         );
-        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE_UPPER + STACK_SIZE_LOWER,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             ; vzeroupper
         );
-        Self(out)
+        Ok(Self(out))
     }
     fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
         assert!((dst_reg as usize) < REGISTER_LIMIT);
@@ -97,51 +102,27 @@ impl Assembler for IntervalAssembler {
         );
     }
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn interval_sin(v: Interval) -> Interval {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, interval_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_cos(f: Interval) -> Interval {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_tan(f: Interval) -> Interval {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_asin(f: Interval) -> Interval {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_acos(f: Interval) -> Interval {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_atan(f: Interval) -> Interval {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_exp(f: Interval) -> Interval {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_ln(f: Interval) -> Interval {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -502,22 +483,10 @@ impl Assembler for IntervalAssembler {
         self.0.ops.commit_local().unwrap();
     }
     fn build_mod(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn interval_modulo(
-            lhs: Interval,
-            rhs: Interval,
-        ) -> Interval {
-            lhs.rem_euclid(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_modulo);
     }
 
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn interval_atan2(
-            lhs: Interval,
-            rhs: Interval,
-        ) -> Interval {
-            lhs.atan2(rhs)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
     }
 
@@ -750,7 +719,24 @@ impl Assembler for IntervalAssembler {
         );
         IMM_REG.wrapping_sub(OFFSET)
     }
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+    fn call_targets() -> Vec<usize> {
+        vec![
+            interval_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            interval_modulo as *const () as usize,
+            interval_atan2 as *const () as usize,
+        ]
+    }
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         if self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 ; mov r12, [rbp - 0x8]
@@ -763,6 +749,48 @@ impl Assembler for IntervalAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "sysv64" fn interval_sin(v: Interval) -> Interval {
+    v.sin()
+}
+
+extern "sysv64" fn float_cos(f: Interval) -> Interval {
+    f.cos()
+}
+
+extern "sysv64" fn float_tan(f: Interval) -> Interval {
+    f.tan()
+}
+
+extern "sysv64" fn float_asin(f: Interval) -> Interval {
+    f.asin()
+}
+
+extern "sysv64" fn float_acos(f: Interval) -> Interval {
+    f.acos()
+}
+
+extern "sysv64" fn float_atan(f: Interval) -> Interval {
+    f.atan()
+}
+
+extern "sysv64" fn float_exp(f: Interval) -> Interval {
+    f.exp()
+}
+
+extern "sysv64" fn float_ln(f: Interval) -> Interval {
+    f.ln()
+}
+
+extern "sysv64" fn interval_modulo(lhs: Interval, rhs: Interval) -> Interval {
+    lhs.rem_euclid(rhs)
+}
+
+extern "sysv64" fn interval_atan2(lhs: Interval, rhs: Interval) -> Interval {
+    lhs.atan2(rhs)
+}
+
 #[expect(clippy::useless_conversion)]
 impl IntervalAssembler {
     fn ensure_callee_regs_saved(&mut self) {
@@ -811,7 +839,9 @@ impl IntervalAssembler {
 
             // copy arg to xmm0
             ; vmovq xmm0, Rx(reg(arg_reg))
-            ; mov rsi, QWORD addr as _
+        );
+        self.0.load_fn(6, addr);
+        dynasm!(self.0.ops
             ; call rsi
 
             // Restore float registers
@@ -876,7 +906,9 @@ impl IntervalAssembler {
             // one of our values if we're using IMM_REG)
             ; vmovq xmm1, Rx(reg(rhs_reg))
             ; vmovq xmm0, Rx(reg(lhs_reg))
-            ; mov rsi, QWORD addr as _
+        );
+        self.0.load_fn(6, addr);
+        dynasm!(self.0.ops
             ; call rsi
 
             // Restore float registers
@@ -17,6 +17,25 @@ pub const REGISTER_LIMIT: usize = 12;
 pub const IMM_REG: u8 = 0;
 /// `xmm1-3` are available for use as temporaries.
 pub const OFFSET: u8 = 4;
+/// Inputs and outputs are addressed with 32-bit offsets
+pub const MAX_ARGS: usize = i32::MAX as usize / 16;
+/// Stack offsets are 32-bit immediates
+pub const MAX_STACK_SIZE: usize = i32::MAX as usize;
+
+/// Patches the function address loaded at `offset`
+///
+/// `offset` must be the start of a `mov r64, imm64` instruction (emitted by
+/// `AssemblerData::load_fn`); returns `false` if it isn't.
+pub fn relocate(code: &mut [u8], offset: usize, addr: usize) -> bool {
+    // `mov r64, imm64` is encoded as REX.W, (B8 + r), imm64
+    match code.get_mut(offset..).and_then(|c| c.get_mut(..10)) {
+        Some([0x48 | 0x49, 0xB8..=0xBF, imm @ ..]) => {
+            imm.copy_from_slice(&(addr as u64).to_le_bytes());
+            true
+        }
+        _ => false,
+    }
+}
 
 pub mod float_slice;
 pub mod grad_slice;
@@ -1,8 +1,9 @@
 use crate::{
-    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, IMM_REG,
-    OFFSET, REGISTER_LIMIT, mmap::Mmap, point::PointAssembler, reg,
+    Assembler, AssemblerData, CHOICE_BOTH, CHOICE_LEFT, CHOICE_RIGHT, CallSite,
+    IMM_REG, OFFSET, REGISTER_LIMIT, mmap::Mmap, point::PointAssembler, reg,
 };
-use dynasmrt::{DynasmApi, DynasmError, DynasmLabelApi, dynasm};
+use dynasmrt::{DynasmApi, DynasmLabelApi, dynasm};
+use fidget_core::Error;
 
 /// Implementation of the single-point assembler on `x86_64`
 ///
@@ -49,17 +50,21 @@ const STACK_SIZE_LOWER: usize = 0x30; // Positions relative to `rsp`
 impl Assembler for PointAssembler {
     type Data = f32;
 
-    fn init(mmap: Mmap, slot_count: usize) -> Self {
+    fn init(mmap: Mmap, slot_count: usize) -> Result<Self, Error> {
         let mut out = AssemblerData::new(mmap);
         dynasm!(out.ops
             ; push rbp
             ; mov rbp, rsp
         );
-        out.prepare_stack(slot_count, STACK_SIZE_UPPER + STACK_SIZE_LOWER);
+        out.prepare_stack(
+            slot_count,
+            STACK_SIZE_UPPER + STACK_SIZE_LOWER,
+            Self::MAX_STACK_SIZE,
+        )?;
         dynasm!(out.ops
             ; vzeroupper
         );
-        Self(out)
+        Ok(Self(out))
     }
 
     fn build_load(&mut self, dst_reg: u8, src_mem: u32) {
@@ -101,51 +106,27 @@ impl Assembler for PointAssembler {
         );
     }
     fn build_sin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn point_sin(v: f32) -> f32 {
-            v.sin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, point_sin);
     }
     fn build_cos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_cos(f: f32) -> f32 {
-            f.cos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_cos);
     }
     fn build_tan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_tan(f: f32) -> f32 {
-            f.tan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_tan);
     }
     fn build_asin(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_asin(f: f32) -> f32 {
-            f.asin()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_asin);
     }
     fn build_acos(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_acos(f: f32) -> f32 {
-            f.acos()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_acos);
     }
     fn build_atan(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_atan(f: f32) -> f32 {
-            f.atan()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_atan);
     }
     fn build_exp(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_exp(f: f32) -> f32 {
-            f.exp()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_exp);
     }
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8) {
-        extern "sysv64" fn float_ln(f: f32) -> f32 {
-            f.ln()
-        }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
@@ -228,9 +209,6 @@ impl Assembler for PointAssembler {
         );
     }
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
-        extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
-            y.atan2(x)
-        }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
@@ -407,7 +385,23 @@ impl Assembler for PointAssembler {
         );
         IMM_REG.wrapping_sub(OFFSET)
     }
-    fn finalize(mut self) -> Result<Mmap, DynasmError> {
+    fn offset(&self) -> usize {
+        self.0.offset()
+    }
+    fn call_targets() -> Vec<usize> {
+        vec![
+            point_sin as *const () as usize,
+            float_cos as *const () as usize,
+            float_tan as *const () as usize,
+            float_asin as *const () as usize,
+            float_acos as *const () as usize,
+            float_atan as *const () as usize,
+            float_exp as *const () as usize,
+            float_ln as *const () as usize,
+            float_atan2 as *const () as usize,
+        ]
+    }
+    fn finalize(mut self) -> Result<(Mmap, Vec<CallSite>), Error> {
         if self.0.saved_callee_regs {
             dynasm!(self.0.ops
                 ; mov r12, [rbp - 0x8]
@@ -420,6 +414,44 @@ impl Assembler for PointAssembler {
     }
 }
 
+// Functions called by generated code (see `call_targets`)
+
+extern "sysv64" fn point_sin(v: f32) -> f32 {
+    v.sin()
+}
+
+extern "sysv64" fn float_cos(f: f32) -> f32 {
+    f.cos()
+}
+
+extern "sysv64" fn float_tan(f: f32) -> f32 {
+    f.tan()
+}
+
+extern "sysv64" fn float_asin(f: f32) -> f32 {
+    f.asin()
+}
+
+extern "sysv64" fn float_acos(f: f32) -> f32 {
+    f.acos()
+}
+
+extern "sysv64" fn float_atan(f: f32) -> f32 {
+    f.atan()
+}
+
+extern "sysv64" fn float_exp(f: f32) -> f32 {
+    f.exp()
+}
+
+extern "sysv64" fn float_ln(f: f32) -> f32 {
+    f.ln()
+}
+
+extern "sysv64" fn float_atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
 #[expect(clippy::useless_conversion)]
 impl PointAssembler {
     fn ensure_callee_regs_saved(&mut self) {
@@ -465,7 +497,9 @@ impl PointAssembler {
 
             // call the function
             ; movss xmm0, Rx(reg(arg_reg))
-            ; mov rsi, QWORD addr as _
+        );
+        self.0.load_fn(6, addr);
+        dynasm!(self.0.ops
             ; call rsi
 
             // Restore float registers
@@ -526,7 +560,9 @@ impl PointAssembler {
             // last.
             ; movss xmm1, Rx(reg(rhs_reg))
             ; movss xmm0, Rx(reg(lhs_reg))
-            ; mov rsi, QWORD addr as _
+        );
+        self.0.load_fn(6, addr);
+        dynasm!(self.0.ops
             ; call rsi
 
             // Restore float registers
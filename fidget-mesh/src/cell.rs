@@ -181,6 +181,16 @@ impl<const D: usize> CellBounds<D> {
             .all(|axis| self[axis].contains(p[axis]))
     }
 
+    /// Clamps the given position to lie within the cell
+    pub fn clamp(&self, p: CellVertex<D>) -> CellVertex<D> {
+        let mut out = p;
+        for axis in Axis::array() {
+            let b = self[axis];
+            out.pos[axis.index()] = p[axis].clamp(b.lower(), b.upper());
+        }
+        out
+    }
+
     pub fn child(&self, corner: Corner<D>) -> Self {
         let bounds = Axis::array().map(|axis| {
             let i = axis.index();
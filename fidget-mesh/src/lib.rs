@@ -13,7 +13,7 @@
 //! However, they may contain self-intersections, and are not guaranteed to
 //! catch thin features (below the sampling grid resolution).
 //!
-//! The resulting [`Mesh`] objects can be written out as STL files.
+//! The resulting [`Mesh`] objects can be written out as STL or OBJ files.
 //!
 //! Here's a full example, meshing a sphere:
 //!
@@ -49,6 +49,7 @@ mod cell;
 mod codegen;
 mod dc;
 mod frame;
+mod normals;
 mod octree;
 mod output;
 mod qef;
@@ -60,6 +61,7 @@ pub mod types;
 
 // Re-export the main Octree type as public
 pub use octree::Octree;
+pub use qef::DEFAULT_SVD_CUTOFF;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -70,6 +72,11 @@ pub struct Mesh {
     pub triangles: Vec<nalgebra::Vector3<usize>>,
     /// Vertex positions
     pub vertices: Vec<nalgebra::Vector3<f32>>,
+    /// Vertex normals
+    ///
+    /// This is empty for meshes returned by [`Octree::walk_dual`]; call
+    /// [`Mesh::with_smooth_normals`] to generate normals.
+    pub normals: Vec<nalgebra::Vector3<f32>>,
 }
 
 impl Mesh {
@@ -90,11 +97,26 @@ pub struct Settings<'a> {
     /// Thread pool to use for rendering
     ///
     /// If this is `None`, then rendering is done in a single thread; otherwise,
-    /// the provided pool is used.
+    /// the provided pool is used.  The resulting mesh is bit-identical
+    /// regardless of this setting.
     pub threads: Option<&'a ThreadPool>,
 
     /// Token to cancel rendering
     pub cancel: CancelToken,
+
+    /// Flush denormal floats to zero during evaluation
+    ///
+    /// This can dramatically speed up evaluation of fields with very small
+    /// values on some CPUs, at the cost of treating values below
+    /// `f32::MIN_POSITIVE` as zero; see
+    /// [`FlushDenormals`](fidget_core::eval::FlushDenormals) for details.
+    pub flush_denormals: bool,
+
+    /// Strategy for positioning vertices within cells
+    pub vertex: VertexSettings,
+
+    /// Classification of field values as inside or outside the shape
+    pub iso: IsoSettings,
 }
 
 impl Default for Settings<'_> {
@@ -104,6 +126,78 @@ impl Default for Settings<'_> {
             world_to_model: nalgebra::Matrix4::identity(),
             threads: Some(&ThreadPool::Global),
             cancel: CancelToken::new(),
+            flush_denormals: false,
+            vertex: VertexSettings::default(),
+            iso: IsoSettings::default(),
+        }
+    }
+}
+
+/// Strategy for solving for a vertex position within a cell
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VertexPlacement {
+    /// Place each vertex at the mass point (average) of its edge intersections
+    ///
+    /// This never produces spiky artifacts, but rounds off sharp features.
+    MassPoint,
+
+    /// Minimize a quadratic error function (QEF) built from surface normals
+    ///
+    /// This snaps vertices to sharp edges and corners.
+    Qef {
+        /// Relative cutoff for singular values when solving the QEF
+        ///
+        /// Singular values below `svd_cutoff` times the largest singular value
+        /// are discarded, reducing the rank of the solution.  Larger values
+        /// are less likely to produce spikes in near-planar regions, but are
+        /// also less likely to snap to sharp features.
+        svd_cutoff: f32,
+    },
+}
+
+impl Default for VertexPlacement {
+    fn default() -> Self {
+        Self::Qef {
+            svd_cutoff: qef::DEFAULT_SVD_CUTOFF,
         }
     }
 }
+
+/// Settings for vertex positioning, used in [`Settings`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct VertexSettings {
+    /// Strategy for solving for vertex positions
+    pub placement: VertexPlacement,
+
+    /// Clamp vertices to the bounds of their cell
+    ///
+    /// Vertices which leave their cell can produce self-intersections; this
+    /// prevents that, at the cost of flattening features which extend past the
+    /// cell boundary.
+    pub clamp_to_cell: bool,
+}
+
+/// Settings for the surface's iso-value, used in [`Settings`]
+///
+/// A point is inside the shape if its field value is less than
+/// `iso_value + epsilon`; NaN values are always outside.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct IsoSettings {
+    /// Field value at which to extract the surface
+    pub iso_value: f32,
+
+    /// Band above the iso-value which is also treated as inside
+    ///
+    /// Fields built from `min` / `max` of non-distance expressions (e.g.
+    /// `max(f, 0)`) can have plateaus exactly at the iso-value, which have no
+    /// sign change and would otherwise be meshed as empty space.  A small
+    /// positive epsilon moves the surface just off of the plateau.
+    pub epsilon: f32,
+}
+
+impl IsoSettings {
+    /// Returns the threshold below which values are inside the shape
+    pub(crate) fn threshold(&self) -> f32 {
+        self.iso_value + self.epsilon
+    }
+}
@@ -0,0 +1,130 @@
+//! Vertex normal generation
+use super::Mesh;
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+impl Mesh {
+    /// Returns a copy of this mesh with smoothed per-vertex normals
+    ///
+    /// Each triangle corner is assigned the area-weighted average normal of
+    /// the triangles around that vertex which are within `crease_angle`
+    /// (in radians) of the corner's own triangle.  Vertices along sharp edges
+    /// (where adjacent triangles differ by more than the crease angle) are
+    /// split, so that each side of the edge is shaded independently.
+    ///
+    /// A crease angle of π produces fully smooth normals (with no split
+    /// vertices); a crease angle of 0 produces flat shading.
+    ///
+    /// The returned mesh has one entry in [`normals`](Self::normals) for each
+    /// vertex.
+    pub fn with_smooth_normals(&self, crease_angle: f32) -> Mesh {
+        let cos_crease = crease_angle.cos();
+
+        // Area-weighted and unit normals for each triangle; the latter is
+        // `None` for degenerate triangles.
+        let face_normals: Vec<Vector3<f32>> = self
+            .triangles
+            .iter()
+            .map(|t| {
+                let a = self.vertices[t.x];
+                let b = self.vertices[t.y];
+                let c = self.vertices[t.z];
+                (b - a).cross(&(c - a))
+            })
+            .collect();
+        let unit_normals: Vec<Option<Vector3<f32>>> =
+            face_normals.iter().map(|n| n.try_normalize(0.0)).collect();
+
+        // Triangles adjacent to each vertex
+        let mut adjacent = vec![vec![]; self.vertices.len()];
+        for (i, t) in self.triangles.iter().enumerate() {
+            for &v in t {
+                adjacent[v].push(i);
+            }
+        }
+
+        let mut out = Mesh::new();
+        let mut map: HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        for (i, t) in self.triangles.iter().enumerate() {
+            let mut tri = Vector3::zeros();
+            for (j, &v) in t.iter().enumerate() {
+                let sum: Vector3<f32> = adjacent[v]
+                    .iter()
+                    .filter(|&&f| match (unit_normals[i], unit_normals[f]) {
+                        (Some(a), Some(b)) => f == i || a.dot(&b) >= cos_crease,
+                        // Degenerate triangles take the average normal
+                        (None, Some(_)) => true,
+                        (_, None) => false,
+                    })
+                    .map(|&f| face_normals[f])
+                    .sum();
+                let normal =
+                    sum.try_normalize(0.0).unwrap_or_else(Vector3::zeros);
+                let key = (v, normal.map(f32::to_bits).into());
+                tri[j] = *map.entry(key).or_insert_with(|| {
+                    out.vertices.push(self.vertices[v]);
+                    out.normals.push(normal);
+                    out.vertices.len() - 1
+                });
+            }
+            out.triangles.push(tri);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Octree, Settings};
+    use fidget_core::{context::Tree, vm::VmShape};
+
+    #[test]
+    fn test_smooth_normals() {
+        let (x, y, z) = Tree::axes();
+        let settings = Settings {
+            depth: 5,
+            ..Default::default()
+        };
+
+        // A sphere has no sharp edges, so no vertices should be split
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - 0.6;
+        let mesh = Octree::build(&VmShape::from(sphere), &settings)
+            .unwrap()
+            .walk_dual();
+        let smooth = mesh.with_smooth_normals(45f32.to_radians());
+        assert_eq!(smooth.vertices.len(), mesh.vertices.len());
+        assert_eq!(smooth.normals.len(), smooth.vertices.len());
+        assert_eq!(smooth.triangles.len(), mesh.triangles.len());
+        for (v, n) in smooth.vertices.iter().zip(&smooth.normals) {
+            assert!((n.norm() - 1.0).abs() < 1e-4, "bad normal {n:?}");
+            let err = (v.normalize() - n).norm();
+            assert!(err < 0.2, "normal {n:?} is too far from {v:?}");
+        }
+
+        // A cube's corners and edges should be split, with axis-aligned
+        // normals on each side
+        let cube = x.abs().max(y.abs()).max(z.abs()) - 0.5;
+        let mesh = Octree::build(&VmShape::from(cube), &settings)
+            .unwrap()
+            .walk_dual();
+        let smooth = mesh.with_smooth_normals(30f32.to_radians());
+        assert!(smooth.vertices.len() > mesh.vertices.len());
+        for n in &smooth.normals {
+            let max = n.abs().max();
+            assert!((max - 1.0).abs() < 1e-4, "normal {n:?} is not on an axis");
+        }
+
+        // Fully smooth shading never splits vertices
+        let smooth = mesh.with_smooth_normals(std::f32::consts::PI);
+        assert_eq!(smooth.vertices.len(), mesh.vertices.len());
+
+        let mut obj = vec![];
+        smooth.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let count =
+            |prefix| obj.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("v "), smooth.vertices.len());
+        assert_eq!(count("vn "), smooth.normals.len());
+        assert_eq!(count("f "), smooth.triangles.len());
+    }
+}
@@ -1,7 +1,7 @@
 //! An octree data structure and implementation of Manifold Dual Contouring
 
 use super::{
-    Mesh, Settings,
+    IsoSettings, Mesh, Settings, VertexSettings,
     builder::MeshBuilder,
     cell::{Cell, CellIndex, CellVertex, Leaf},
     codegen::CELL_TO_VERT_TO_EDGES,
@@ -10,13 +10,20 @@ use super::{
     types::{Axis, CellMask, Corner, Edge},
 };
 use fidget_core::{
-    eval::Function,
-    render::{CancelToken, RenderHandle, RenderHints, ThreadPool},
-    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars},
+    eval::{FlushDenormals, Function},
+    render::{
+        CancelToken, MetricsRecorder, PipelineMetrics, RenderHandle,
+        RenderHints, Stage, TapeCache, ThreadMetrics, ThreadPool,
+    },
+    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars, Transformed},
     types::Grad,
 };
 use std::collections::VecDeque;
 
+/// Depth to which the octree is subdivided into independent tasks when meshing
+/// with a thread pool (giving `8^TASK_DEPTH` tasks)
+const TASK_DEPTH: u8 = 3;
+
 /// Octree storing occupancy and vertex positions for Manifold Dual Contouring
 #[derive(Debug)]
 pub struct Octree {
@@ -51,22 +58,88 @@ impl Octree {
         vars: &ShapeVars<f32>,
         settings: &Settings,
     ) -> Option<Self> {
+        Self::build_outer(shape, vars, settings, false).map(|(out, _)| out)
+    }
+
+    /// Builds an octree, returning per-stage timing and counters
+    ///
+    /// This is equivalent to [`build_with_vars`](Octree::build_with_vars), but
+    /// also records time spent in interval evaluation, simplification, tape
+    /// construction, and point / gradient evaluation on each thread.
+    pub fn build_with_metrics<F: Function + RenderHints + Clone>(
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        settings: &Settings,
+    ) -> Option<(Self, PipelineMetrics)> {
+        Self::build_outer(shape, vars, settings, true)
+    }
+
+    fn build_outer<F: Function + RenderHints + Clone>(
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        settings: &Settings,
+        metrics: bool,
+    ) -> Option<(Self, PipelineMetrics)> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "octree_build",
+            depth = settings.depth,
+            tape_len = shape.size(),
+        )
+        .entered();
+
+        let start = metrics.then(std::time::Instant::now);
+
         // Transform the shape given our world-to-model matrix
         let t = settings.world_to_model;
-        if t == nalgebra::Matrix4::identity() {
-            Self::build_inner(shape, vars, settings)
+        let (out, mut m) = if t == nalgebra::Matrix4::identity() {
+            let rh = RenderHandle::new(shape.clone());
+            Self::build_inner(rh, vars, settings, metrics)?
         } else {
-            let shape = shape.with_transform(t);
-            let mut out = Self::build_inner(&shape, vars, settings)?;
-
-            // Apply the transform from [-1, +1] back to model space
-            for v in &mut out.verts {
-                let p: nalgebra::Point3<f32> = v.pos.into();
-                let q = t.transform_point(&p);
-                v.pos = q.coords;
-            }
-            Some(out)
+            let rh = RenderHandle::new(shape.with_transform(t));
+            Self::build_transformed(rh, vars, settings, metrics)?
+        };
+        if let Some(start) = start {
+            m.wall_time = start.elapsed();
         }
+        Some((out, m))
+    }
+
+    /// Builds an octree, reusing root tapes from a [`TapeCache`]
+    ///
+    /// This is equivalent to [`build_with_vars`](Octree::build_with_vars), but
+    /// when only variable values change between calls, passing the same cache
+    /// skips root tape construction (including JIT compilation).
+    pub fn build_cached<F: Function + RenderHints + Clone>(
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        settings: &Settings,
+        cache: &mut TapeCache<F>,
+    ) -> Option<Self> {
+        let rh = cache.get(shape, settings.world_to_model);
+        Self::build_transformed(rh, vars, settings, false).map(|(out, _)| out)
+    }
+
+    /// Builds an octree from a transformed handle
+    ///
+    /// Vertices are mapped back from `[-1, +1]` to model space using the
+    /// handle's transform.
+    fn build_transformed<F: Function + RenderHints + Clone>(
+        rh: RenderHandle<F, Transformed>,
+        vars: &ShapeVars<f32>,
+        settings: &Settings,
+        metrics: bool,
+    ) -> Option<(Self, PipelineMetrics)> {
+        let t = rh.shape().transform();
+        let (mut out, m) = Self::build_inner(rh, vars, settings, metrics)?;
+
+        // Apply the transform from [-1, +1] back to model space
+        for v in &mut out.verts {
+            let p: nalgebra::Point3<f32> = v.pos.into();
+            let q = t.transform_point(&p);
+            v.pos = q.coords;
+        }
+        Some((out, m))
     }
 
     /// Builds an octree to the given depth
@@ -84,21 +157,19 @@ impl Octree {
     }
 
     fn build_inner<F: Function + RenderHints + Clone, T: Sync>(
-        shape: &Shape<F, T>,
+        mut eval: RenderHandle<F, T>,
         vars: &ShapeVars<f32>,
         settings: &Settings,
-    ) -> Option<Self> {
+        metrics: bool,
+    ) -> Option<(Self, PipelineMetrics)> {
         if let Some(threads) = settings.threads {
-            Self::build_inner_mt(
-                shape,
-                vars,
-                settings.depth,
-                &settings.cancel,
-                threads,
-            )
+            Self::build_inner_mt(eval, vars, settings, threads, metrics)
         } else {
-            let mut eval = RenderHandle::new(shape.clone());
+            let _guard = settings.flush_denormals.then(FlushDenormals::new);
             let mut out = OctreeBuilder::new();
+            out.metrics = MetricsRecorder::new(metrics);
+            out.vertex = settings.vertex;
+            out.iso = settings.iso;
             let mut hermite = LeafHermiteData::default();
             if out.recurse(
                 &mut eval,
@@ -108,7 +179,11 @@ impl Octree {
                 &settings.cancel,
                 &mut hermite,
             ) {
-                Some(out.octree)
+                let mut m = PipelineMetrics::default();
+                if let Some(t) = out.metrics.take() {
+                    m.record(0, &t);
+                }
+                Some((out.octree, m))
             } else {
                 None
             }
@@ -117,12 +192,14 @@ impl Octree {
 
     /// Multithreaded constructor
     fn build_inner_mt<F: Function + RenderHints + Clone, T: Sync>(
-        shape: &Shape<F, T>,
+        mut rh: RenderHandle<F, T>,
         vars: &ShapeVars<f32>,
-        max_depth: u8,
-        cancel: &CancelToken,
+        settings: &Settings,
         threads: &ThreadPool,
-    ) -> Option<Self> {
+        metrics: bool,
+    ) -> Option<(Self, PipelineMetrics)> {
+        let max_depth = settings.depth;
+        let cancel = &settings.cancel;
         let mut root = Octree::new();
         let mut todo = VecDeque::new();
         todo.push_back(CellIndex::<3>::default());
@@ -132,8 +209,11 @@ impl Octree {
         // We want a number of tasks that's significantly larger than our thread
         // count, so that we can fully saturate all cores even if tasks take
         // different amounts of time.
-        let target_count =
-            (8usize.pow(u32::from(max_depth))).min(threads.thread_count() * 10);
+        //
+        // The task count is deliberately independent of the thread count, so
+        // that the octree is split the same way (and produces bit-identical
+        // output) regardless of how many workers are available.
+        let target_count = 8usize.pow(u32::from(max_depth.min(TASK_DEPTH)));
         while todo.len() < target_count {
             let next = todo.pop_front().unwrap();
 
@@ -154,14 +234,26 @@ impl Octree {
             cell: CellIndex<3>,
             octree: Octree,
             hermite: LeafHermiteData,
+            metrics: Option<(usize, ThreadMetrics)>,
         }
-        let mut rh = RenderHandle::new(shape.clone());
         let _ = rh.i_tape(&mut vec![]); // pre-populate interval tape
         let out = threads.run(|| {
+            #[cfg(feature = "tracing")]
+            let _span =
+                tracing::debug_span!("octree_tasks", tasks = todo.len())
+                    .entered();
             todo.par_iter()
                 .map_init(
-                    || (OctreeBuilder::new(), rh.clone()),
+                    || {
+                        let mut builder = OctreeBuilder::new();
+                        builder.metrics = MetricsRecorder::new(metrics);
+                        builder.vertex = settings.vertex;
+                        builder.iso = settings.iso;
+                        (builder, rh.clone())
+                    },
                     |(builder, eval), cell| {
+                        let _guard =
+                            settings.flush_denormals.then(FlushDenormals::new);
                         let mut hermite = LeafHermiteData::default();
                         // Patch our cell so that it builds at index 0
                         let local_cell = CellIndex {
@@ -182,10 +274,14 @@ impl Octree {
                             &mut builder.octree,
                             Octree::new(),
                         );
+                        let metrics = builder.metrics.take().map(|m| {
+                            (rayon::current_thread_index().unwrap_or(0), m)
+                        });
                         Some(Output {
                             octree,
                             cell: *cell,
                             hermite,
+                            metrics,
                         })
                     },
                 )
@@ -195,7 +291,11 @@ impl Octree {
         // Copy hermite data into arrays, and compute cumulative offsets
         let mut cell_offsets = vec![root.cells.len()];
         let mut vert_offsets = vec![0];
+        let mut metrics = PipelineMetrics::default();
         for o in &out {
+            if let Some((thread, m)) = &o.metrics {
+                metrics.record(*thread, m);
+            }
             let (i, j) = o.cell.index.unwrap();
             hermites[i][j as usize] = o.hermite;
             let c = cell_offsets.last().unwrap() + o.octree.cells.len();
@@ -228,9 +328,13 @@ impl Octree {
         }
 
         // Walk back up the tree, merging cells as we go
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("octree_merge", tasks = fixup.len()).entered();
         for (cell, index) in fixup.into_iter().rev() {
             let h = hermites[index];
             root[cell] = root.check_done(
+                &settings.vertex,
                 cell,
                 index,
                 h,
@@ -239,11 +343,28 @@ impl Octree {
                     .unwrap_or(&mut LeafHermiteData::default()),
             );
         }
-        Some(root)
+        Some((root, metrics))
+    }
+
+    /// Returns the number of heap bytes allocated by the octree
+    ///
+    /// This is based on allocated capacity, so it reflects the largest size
+    /// reached during construction.
+    pub fn memory_usage(&self) -> usize {
+        self.cells.capacity() * std::mem::size_of::<[Cell<3>; 8]>()
+            + self.verts.capacity() * std::mem::size_of::<CellVertex<3>>()
     }
 
     /// Recursively walks the dual of the octree, building a mesh
     pub fn walk_dual(&self) -> Mesh {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "walk_dual",
+            cells = self.cells.len(),
+            verts = self.verts.len(),
+        )
+        .entered();
+
         let mut mesh = MeshBuilder::default();
 
         mesh.cell(self, CellIndex::default());
@@ -284,6 +405,7 @@ impl Octree {
     /// them if they're at the tail end of the array).
     fn check_done(
         &mut self,
+        vertex: &VertexSettings,
         cell: CellIndex<3>,
         index: usize,
         hermite_data: [LeafHermiteData; 8],
@@ -316,7 +438,7 @@ impl Octree {
         } else if empty_count == 8 {
             Cell::Empty
         } else if let Some(leaf) =
-            self.try_collapse(cell, index, hermite_data, hermite)
+            self.try_collapse(vertex, cell, index, hermite_data, hermite)
         {
             Cell::Leaf(leaf)
         } else {
@@ -347,6 +469,7 @@ impl Octree {
     /// returns `None`.
     fn try_collapse(
         &mut self,
+        vertex: &VertexSettings,
         cell: CellIndex<3>,
         index: usize,
         hermite_data: [LeafHermiteData; 8],
@@ -364,7 +487,7 @@ impl Octree {
         //   have been collapsed into a single empty / full cell
         // - The interior vertices *do not* match, in which case the
         //   cell should not be marked as collapsible.
-        let (pos, new_err) = hermite.solve();
+        let (pos, new_err) = hermite.qef().place(vertex, &cell.bounds);
         if new_err >= hermite.qef_err * 2.0 || !cell.bounds.contains(pos) {
             return None;
         }
@@ -506,6 +629,15 @@ pub(crate) struct OctreeBuilder<F: Function + RenderHints> {
     tape_storage: Vec<F::TapeStorage>,
     shape_storage: Vec<F::Storage>,
     workspace: F::Workspace,
+
+    /// Per-stage timing (disabled unless metrics are requested)
+    metrics: MetricsRecorder,
+
+    /// Vertex positioning settings
+    vertex: VertexSettings,
+
+    /// Inside / outside classification settings
+    iso: IsoSettings,
 }
 
 impl<F: Function + RenderHints> Default for OctreeBuilder<F> {
@@ -525,6 +657,9 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
             tape_storage: vec![],
             shape_storage: vec![],
             workspace: Default::default(),
+            metrics: Default::default(),
+            vertex: Default::default(),
+            iso: Default::default(),
         }
     }
 
@@ -549,29 +684,47 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
         if cancel.is_cancelled() {
             return false;
         }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "octree_cell",
+            depth = cell.depth,
+            tape_len = eval.size(),
+        )
+        .entered();
+
+        let t = self.metrics.start();
+        let tape = eval.i_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
         let (i, r) = self
             .eval_interval
             .eval_v(
-                eval.i_tape(&mut self.tape_storage),
+                tape,
                 cell.bounds[crate::types::X],
                 cell.bounds[crate::types::Y],
                 cell.bounds[crate::types::Z],
                 vars,
             )
             .unwrap();
-        self.octree[cell] = if i.upper() < 0.0 {
+        self.metrics.record(Stage::IntervalEval, t);
+        let threshold = self.iso.threshold();
+        self.octree[cell] = if i.upper() < threshold {
             Cell::Full
-        } else if i.lower() > 0.0 {
+        } else if i.lower() > threshold {
             Cell::Empty
         } else {
             let sub_tape = if F::simplify_tree_during_meshing(cell.depth) {
                 if let Some(trace) = r.as_ref() {
-                    eval.simplify(
+                    let t = self.metrics.start();
+                    let s = eval.simplify(
                         trace,
                         &mut self.workspace,
                         &mut self.shape_storage,
                         &mut self.tape_storage,
-                    )
+                    );
+                    self.metrics.record(Stage::Simplify, t);
+                    s
                 } else {
                     eval
                 }
@@ -600,7 +753,13 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
                 }
 
                 // Figure out whether the children can be collapsed
-                self.octree.check_done(cell, index, hermite_child, hermite)
+                self.octree.check_done(
+                    &self.vertex,
+                    cell,
+                    index,
+                    hermite_child,
+                    hermite,
+                )
             }
         };
         true
@@ -628,18 +787,29 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
             zs[i.index()] = z;
         }
 
+        let t = self.metrics.start();
+        let tape = eval.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
         let out = self
             .eval_float_slice
-            .eval_v(eval.f_tape(&mut self.tape_storage), &xs, &ys, &zs, vars)
+            .eval_v(tape, &xs, &ys, &zs, vars)
             .unwrap();
+        self.metrics.record(Stage::Fill, t);
         debug_assert_eq!(out.len(), 8);
 
+        // Values are inside if they're below the threshold; this is written
+        // so that NaN is consistently treated as outside.
+        let threshold = self.iso.threshold();
+        let inside = |v: f32| v < threshold;
+
         // Build a mask of active corners, which determines cell
         // topology / vertex count / active edges / etc.
         let mask = out
             .iter()
             .enumerate()
-            .filter(|(_i, v)| **v < 0.0)
+            .filter(|(_i, v)| inside(**v))
             .fold(0, |acc, (i, _v)| acc | (1 << i));
 
         // Early exit if the cell is completely empty or full
@@ -708,6 +878,10 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
         let zs =
             &mut [0.0; 12 * EDGE_SEARCH_SIZE][..edge_count * EDGE_SEARCH_SIZE];
 
+        // Edges where the N-ary search failed, which fall back to bisection
+        let mut bisect = [false; 12];
+        let bisect = &mut bisect[..edge_count];
+
         // This part looks hairy, but it's just doing an N-ary search along each
         // edge to find the intersection point.
         for _ in 0..EDGE_SEARCH_DEPTH {
@@ -731,27 +905,39 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
             debug_assert_eq!(i, EDGE_SEARCH_SIZE * edge_count);
 
             // Do the actual evaluation
+            let t = self.metrics.start();
+            let tape = eval.f_tape(&mut self.tape_storage);
+            self.metrics.record(Stage::TapeBuild, t);
+
+            let t = self.metrics.start();
             let out = self
                 .eval_float_slice
-                .eval_v(eval.f_tape(&mut self.tape_storage), xs, ys, zs, vars)
+                .eval_v(tape, xs, ys, zs, vars)
                 .unwrap();
+            self.metrics.record(Stage::Fill, t);
 
             // Update start and end positions based on evaluation
-            for ((start, end), search) in start
+            for (((start, end), search), bisect) in start
                 .iter_mut()
                 .zip(end.iter_mut())
                 .zip(out.chunks(EDGE_SEARCH_SIZE))
+                .zip(bisect.iter_mut())
             {
-                // The search must be inside-to-outside
-                debug_assert!(search[0] < 0.0);
-                debug_assert!(search[EDGE_SEARCH_SIZE - 1] >= 0.0);
-                let frac = search
+                if *bisect {
+                    continue;
+                }
+                // The search should be inside-to-outside, but re-evaluating
+                // the endpoints can disagree with the corner values for
+                // ill-behaved fields; if so, keep the current (known-good)
+                // bracket and fall back to bisection.
+                let Some(frac) = search
                     .iter()
-                    .enumerate()
-                    .find(|(_i, v)| **v >= 0.0)
-                    .unwrap()
-                    .0;
-                debug_assert!(frac > 0);
+                    .position(|v| !inside(*v))
+                    .filter(|&frac| frac > 0)
+                else {
+                    *bisect = true;
+                    continue;
+                };
                 debug_assert!(frac < EDGE_SEARCH_SIZE);
 
                 let f = |frac| {
@@ -771,6 +957,50 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
             }
         }
 
+        // Bisect any edges where the N-ary search failed, trusting the
+        // classification of the bracket's endpoints
+        let mut todo: arrayvec::ArrayVec<usize, 12> = arrayvec::ArrayVec::new();
+        loop {
+            todo.clear();
+            todo.extend((0..edge_count).filter(|&i| {
+                bisect[i] && start[i].zip_map(&end[i], u16::abs_diff).max() > 1
+            }));
+            if todo.is_empty() {
+                break;
+            }
+            let mid = |a: nalgebra::Vector3<u16>, b: nalgebra::Vector3<u16>| {
+                ((a.map(|v| v as u32) + b.map(|v| v as u32)) / 2)
+                    .map(|v| v as u16)
+            };
+            for (j, &i) in todo.iter().enumerate() {
+                let pos = cell.pos(mid(start[i], end[i]));
+                xs[j] = pos.x;
+                ys[j] = pos.y;
+                zs[j] = pos.z;
+            }
+            let n = todo.len();
+
+            let t = self.metrics.start();
+            let tape = eval.f_tape(&mut self.tape_storage);
+            self.metrics.record(Stage::TapeBuild, t);
+
+            let t = self.metrics.start();
+            let out = self
+                .eval_float_slice
+                .eval_v(tape, &xs[..n], &ys[..n], &zs[..n], vars)
+                .unwrap();
+            self.metrics.record(Stage::Fill, t);
+
+            for (&i, &v) in todo.iter().zip(out) {
+                let m = mid(start[i], end[i]);
+                if inside(v) {
+                    start[i] = m;
+                } else {
+                    end[i] = m;
+                }
+            }
+        }
+
         // Populate intersections to the average of start and end
         let intersections: arrayvec::ArrayVec<nalgebra::Vector3<u16>, 12> =
             start
@@ -797,10 +1027,14 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
         }
 
         // TODO: special case for cells with multiple gradients ("features")
-        let grads = self
-            .eval_grad_slice
-            .eval_v(eval.g_tape(&mut self.tape_storage), xs, ys, zs, vars)
-            .unwrap();
+        let t = self.metrics.start();
+        let tape = eval.g_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let grads =
+            self.eval_grad_slice.eval_v(tape, xs, ys, zs, vars).unwrap();
+        self.metrics.record(Stage::Fill, t);
 
         let mut verts: arrayvec::ArrayVec<_, 4> = arrayvec::ArrayVec::new();
         let mut i = 0;
@@ -835,7 +1069,7 @@ impl<F: Function + RenderHints> OctreeBuilder<F> {
             if let Some(pos) = force_point {
                 verts.push(CellVertex { pos });
             } else {
-                let (pos, err) = qef.solve();
+                let (pos, err) = qef.place(&self.vertex, &cell.bounds);
                 verts.push(pos);
 
                 // We overwrite the error here, because it's only used when
@@ -1015,8 +1249,8 @@ impl LeafHermiteData {
         Some(out)
     }
 
-    /// Solves the combined QEF
-    pub fn solve(&self) -> (CellVertex<3>, f32) {
+    /// Returns the combined QEF
+    pub fn qef(&self) -> QuadraticErrorSolver {
         let mut qef = self.center_qef;
         for &i in &self.intersections {
             qef += i.into();
@@ -1024,7 +1258,7 @@ impl LeafHermiteData {
         for &f in &self.face_qefs {
             qef += f;
         }
-        qef.solve()
+        qef
     }
 }
 
@@ -1034,6 +1268,7 @@ impl LeafHermiteData {
 mod test {
     use super::*;
     use crate::types::{Edge, X, Y, Z};
+    use crate::{IsoSettings, VertexPlacement, VertexSettings};
     use fidget_core::{
         context::{Context, Tree},
         render::ThreadPool,
@@ -1218,6 +1453,67 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_build_with_metrics() {
+        let shape = VmShape::from(sphere([0.0; 3], 0.85));
+
+        for threads in [None, Some(&ThreadPool::Global)] {
+            let settings = Settings {
+                depth: 4,
+                threads,
+                ..Default::default()
+            };
+            let (octree, metrics) = Octree::build_with_metrics(
+                &shape,
+                &ShapeVars::new(),
+                &settings,
+            )
+            .unwrap();
+            let total = metrics.total();
+            assert!(total.interval_eval.count > 0);
+            assert!(total.fill.count > 0);
+            assert!(total.tape_build.count > 0);
+            assert!(metrics.wall_time > std::time::Duration::ZERO);
+
+            // Collecting metrics shouldn't change the output
+            let expected = Octree::build(&shape, &settings).unwrap();
+
+            let (a, b) = (octree.walk_dual(), expected.walk_dual());
+            assert_eq!(a.vertices, b.vertices);
+            assert_eq!(a.triangles, b.triangles);
+        }
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        assert_eq!(Octree::new().memory_usage(), 0);
+
+        let shape = VmShape::from(sphere([0.0; 3], 0.85));
+        let mut prev = 0;
+        for depth in [2, 4] {
+            let settings = Settings {
+                depth,
+                ..Default::default()
+            };
+            let octree = Octree::build(&shape, &settings).unwrap();
+            let cells = std::mem::size_of::<[Cell<3>; 8]>();
+            let verts = std::mem::size_of::<CellVertex<3>>();
+            let used = octree.cells.len() * cells + octree.verts.len() * verts;
+            let usage = octree.memory_usage();
+            assert_eq!(
+                usage,
+                octree.cells.capacity() * cells
+                    + octree.verts.capacity() * verts
+            );
+            assert!(usage >= used, "{usage} < {used}");
+            assert!(usage <= used * 2 + 64 * cells, "{usage} >> {used}");
+
+            // Deeper octrees have more cells and vertices
+            assert!(usage > prev);
+            prev = usage;
+        }
+    }
+
     #[test]
     fn test_cube_verts() {
         let shape = VmShape::from(cube([-0.1, 0.6], [-0.2, 0.75], [-0.3, 0.4]));
@@ -1262,6 +1558,96 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_vertex_placement() {
+        let shape = VmShape::from(cube([-0.1, 0.6], [-0.2, 0.75], [-0.3, 0.4]));
+        const EPSILON: f32 = 1e-3;
+        let is_corner = |v: &nalgebra::Vector3<f32>| {
+            [-0.1, 0.6].iter().any(|x| (v.x - x).abs() < EPSILON)
+                && [-0.2, 0.75].iter().any(|y| (v.y - y).abs() < EPSILON)
+                && [-0.3, 0.4].iter().any(|z| (v.z - z).abs() < EPSILON)
+        };
+
+        // The QEF snaps vertices to the cube's corners
+        let mesh = Octree::build(&shape, &depth1_single_thread())
+            .unwrap()
+            .walk_dual();
+        assert!(mesh.vertices.iter().any(is_corner));
+
+        // Mass points are averages of edge intersections, so they never land
+        // exactly on a corner
+        let settings = Settings {
+            vertex: VertexSettings {
+                placement: VertexPlacement::MassPoint,
+                ..Default::default()
+            },
+            ..depth1_single_thread()
+        };
+        let mesh = Octree::build(&shape, &settings).unwrap().walk_dual();
+        assert!(!mesh.vertices.is_empty());
+        assert!(!mesh.vertices.iter().any(is_corner));
+        for v in &mesh.vertices {
+            let inside = |p: f32, [lo, hi]: [f32; 2]| {
+                p >= lo - EPSILON && p <= hi + EPSILON
+            };
+            assert!(inside(v.x, [-0.1, 0.6]), "bad vertex {v:?}");
+            assert!(inside(v.y, [-0.2, 0.75]), "bad vertex {v:?}");
+            assert!(inside(v.z, [-0.3, 0.4]), "bad vertex {v:?}");
+        }
+    }
+
+    #[test]
+    fn test_iso_settings() {
+        let (x, y, z) = Tree::axes();
+        let r = (x.square() + y.square() + z.square()).sqrt();
+        let settings = |iso_value, epsilon| Settings {
+            depth: 4,
+            threads: None,
+            iso: IsoSettings { iso_value, epsilon },
+            ..Default::default()
+        };
+        let check_radius = |mesh: &Mesh, radius: f32| {
+            assert!(!mesh.triangles.is_empty());
+            check_for_vertex_dupes(mesh).unwrap();
+            check_for_edge_matching(mesh).unwrap();
+            for v in &mesh.vertices {
+                let err = (v.norm() - radius).abs();
+                assert!(err < 0.02, "vertex {v:?} is not at radius {radius}");
+            }
+        };
+
+        // Meshing at a non-zero iso-value
+        let shape = VmShape::from(r.clone());
+        let mesh = Octree::build(&shape, &settings(0.5, 0.0))
+            .unwrap()
+            .walk_dual();
+        check_radius(&mesh, 0.5);
+
+        // A plateau at zero has no sign change, so it's empty by default
+        let shape = VmShape::from((r.clone() - 0.5).max(0.0));
+        let mesh = Octree::build(&shape, &settings(0.0, 0.0))
+            .unwrap()
+            .walk_dual();
+        assert!(mesh.triangles.is_empty());
+
+        // ...but can be meshed with an epsilon band
+        let mesh = Octree::build(&shape, &settings(0.0, 1e-3))
+            .unwrap()
+            .walk_dual();
+        check_radius(&mesh, 0.5);
+
+        // NaN values are outside the shape (sqrt(x) is NaN for negative x),
+        // and shouldn't trip up the edge search
+        let shape = VmShape::from(x.sqrt() + y.square() + z.square() - 0.5);
+        let mesh = Octree::build(&shape, &settings(0.0, 0.0))
+            .unwrap()
+            .walk_dual();
+        assert!(!mesh.triangles.is_empty());
+        for v in &mesh.vertices {
+            assert!(v.x > -0.1, "vertex {v:?} is in the NaN region");
+        }
+    }
+
     #[test]
     fn test_plane_center() {
         const EPSILON: f32 = 1e-3;
@@ -1462,7 +1848,8 @@ mod test {
 
     #[test]
     fn test_colonnade_manifold() {
-        const COLONNADE: &str = include_str!("../../models/colonnade.vm");
+        const COLONNADE: &str =
+            include_str!("../../fidget/models/colonnade.vm");
         let (ctx, root) = Context::from_text(COLONNADE.as_bytes()).unwrap();
         let tape = VmShape::new(&ctx, root).unwrap();
 
@@ -1486,7 +1873,8 @@ mod test {
 
     #[test]
     fn colonnade_bounds() {
-        const COLONNADE: &str = include_str!("../../models/colonnade.vm");
+        const COLONNADE: &str =
+            include_str!("../../fidget/models/colonnade.vm");
         let (ctx, root) = Context::from_text(COLONNADE.as_bytes()).unwrap();
         let tape = VmShape::new(&ctx, root).unwrap();
 
@@ -1515,7 +1903,7 @@ mod test {
 
     #[test]
     fn bear_bounds() {
-        const COLONNADE: &str = include_str!("../../models/bear.vm");
+        const COLONNADE: &str = include_str!("../../fidget/models/bear.vm");
         let (ctx, root) = Context::from_text(COLONNADE.as_bytes()).unwrap();
         let tape = VmShape::new(&ctx, root).unwrap();
 
@@ -1668,6 +2056,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_mesh_cached() {
+        let (x, y, z) = Tree::axes();
+        let v = Var::new();
+        let c = Tree::from(v);
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - c;
+        let shape = VmShape::from(sphere);
+
+        for threads in [None, Some(&ThreadPool::Global)] {
+            let settings = Settings {
+                depth: 4,
+                threads,
+                ..Default::default()
+            };
+            let mut cache = TapeCache::new();
+            for r in [0.5, 0.75] {
+                let mut vars = ShapeVars::new();
+                vars.insert(v.index().unwrap(), r);
+                let a =
+                    Octree::build_cached(&shape, &vars, &settings, &mut cache)
+                        .unwrap()
+                        .walk_dual();
+                let b = Octree::build_with_vars(&shape, &vars, &settings)
+                    .unwrap()
+                    .walk_dual();
+                assert_eq!(a.vertices, b.vertices);
+                assert_eq!(a.triangles, b.triangles);
+            }
+            assert_eq!(cache.len(), 1);
+        }
+    }
+
     #[test]
     fn test_octree_cancel() {
         let (x, y, z) = Tree::axes();
@@ -36,4 +36,35 @@ impl Mesh {
         }
         Ok(())
     }
+
+    /// Writes a Wavefront OBJ file to the given output
+    ///
+    /// If the mesh has one normal per vertex (e.g. from
+    /// [`Mesh::with_smooth_normals`]), they are written as well.
+    pub fn write_obj<F: std::io::Write>(
+        &self,
+        out: &mut F,
+    ) -> std::io::Result<()> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "# OBJ file exported by Fidget")?;
+        for v in &self.vertices {
+            writeln!(out, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+        let has_normals = self.normals.len() == self.vertices.len();
+        if has_normals {
+            for n in &self.normals {
+                writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+            }
+        }
+        for t in &self.triangles {
+            // OBJ indexes are 1-based
+            let [a, b, c] = [t.x + 1, t.y + 1, t.z + 1];
+            if has_normals {
+                writeln!(out, "f {a}//{a} {b}//{b} {c}//{c}")?;
+            } else {
+                writeln!(out, "f {a} {b} {c}")?;
+            }
+        }
+        Ok(())
+    }
 }
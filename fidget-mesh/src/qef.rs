@@ -1,4 +1,22 @@
-use super::cell::CellVertex;
+use super::{
+    VertexPlacement, VertexSettings,
+    cell::{CellBounds, CellVertex},
+};
+
+/// Default relative cutoff for singular values when solving a QEF
+///
+/// This is very much a tuned value (alas!).  If the value is too small, then we
+/// incorrectly pick high-rank solutions, which may shoot vertices out of their
+/// cells in near-planar situations.  If the value is too large, then we
+/// incorrectly pick low-rank solutions, which makes us less likely to snap to
+/// sharp features.
+///
+/// For example, our cone test needs to use a rank-3 solver for eigenvalues of
+/// [1.5633028, 1.430821, 0.0058764853] (a dynamic range of 2e3); while the bear
+/// model needs to use a rank-2 solver for eigenvalues of [2.87, 0.13, 5.64e-7]
+/// (a dynamic range of 10^7).  We pick 10^3 here somewhat arbitrarily to be
+/// within that range.
+pub const DEFAULT_SVD_CUTOFF: f32 = 1e-3;
 
 /// Solver for a quadratic error function to position a vertex within a cell
 #[derive(Copy, Clone, Debug, Default)]
@@ -60,15 +78,55 @@ impl QuadraticErrorSolver {
 
     /// Solve the given QEF, minimizing towards the mass point
     ///
-    /// Returns a vertex localized within the given cell, and adjusts the solver
-    /// to increase the likelihood that the vertex is bounded in the cell.
+    /// Returns the vertex and the QEF error at that vertex
+    pub fn solve(&self, placement: VertexPlacement) -> (CellVertex<3>, f32) {
+        let center = self.mass_point.xyz() / self.mass_point.w;
+        let pos = match placement {
+            VertexPlacement::MassPoint => center,
+            VertexPlacement::Qef { svd_cutoff } => {
+                self.solve_qef(center, svd_cutoff)
+            }
+        };
+        (CellVertex { pos }, self.error(pos))
+    }
+
+    /// Solves for a vertex within the given cell
     ///
-    /// Also returns the QEF error as the second item in the tuple
-    pub fn solve(&self) -> (CellVertex<3>, f32) {
+    /// If the settings request clamping, then the vertex is clamped to the
+    /// cell's bounds (and the error is recomputed at the clamped position).
+    pub fn place(
+        &self,
+        settings: &VertexSettings,
+        bounds: &CellBounds<3>,
+    ) -> (CellVertex<3>, f32) {
+        let (pos, err) = self.solve(settings.placement);
+        if settings.clamp_to_cell && !bounds.contains(pos) {
+            let pos = bounds.clamp(pos);
+            (pos, self.error(pos.pos))
+        } else {
+            (pos, err)
+        }
+    }
+
+    /// Returns the QEF error at the given position
+    ///
+    /// The error is clamped to a small > 0 value for ease of comparison
+    fn error(&self, pos: nalgebra::Vector3<f32>) -> f32 {
+        ((pos.transpose() * self.ata * pos - 2.0 * pos.transpose() * self.atb)
+            [0]
+            + self.btb)
+            .max(1e-6)
+    }
+
+    /// Minimizes the QEF, using `center` to resolve rank-deficient solutions
+    fn solve_qef(
+        &self,
+        center: nalgebra::Vector3<f32>,
+        svd_cutoff: f32,
+    ) -> nalgebra::Vector3<f32> {
         // This gets a little tricky; see
         // https://www.mattkeeter.com/projects/qef for a walkthrough of QEF math
         // and references to primary sources.
-        let center = self.mass_point.xyz() / self.mass_point.w;
         let atb = self.atb - self.ata * center;
 
         let svd = nalgebra::linalg::SVD::new(self.ata, true, true);
@@ -82,19 +140,8 @@ impl QuadraticErrorSolver {
         let singular_values = singular_values.map(|o| o.0);
 
         // Skip any eigenvalues that are small relative to the maximum
-        // eigenvalue.  This is very much a tuned value (alas!).  If the value
-        // is too small, then we incorrectly pick high-rank solutions, which may
-        // shoot vertices out of their cells in near-planar situations.  If the
-        // value is too large, then we incorrectly pick low-rank solutions,
-        // which makes us less likely to snap to sharp features.
-        //
-        // For example, our cone test needs to use a rank-3 solver for
-        // eigenvalues of [1.5633028, 1.430821, 0.0058764853] (a dynamic range
-        // of 2e3); while the bear model needs to use a rank-2 solver for
-        // eigenvalues of [2.87, 0.13, 5.64e-7] (a dynamic range of 10^7).  We
-        // pick 10^3 here somewhat arbitrarily to be within that range.
-        const EIGENVALUE_CUTOFF_RELATIVE: f32 = 1e-3;
-        let cutoff = singular_values[0].abs() * EIGENVALUE_CUTOFF_RELATIVE;
+        // eigenvalue (see `DEFAULT_SVD_CUTOFF` for discussion)
+        let cutoff = singular_values[0].abs() * svd_cutoff;
 
         // Intuition about `rank`:
         // 0 => all eigenvalues are invalid (?!), use the center point
@@ -107,14 +154,7 @@ impl QuadraticErrorSolver {
 
         let epsilon = singular_values.get(rank).cloned().unwrap_or(0.0);
         let sol = svd.solve(&atb, epsilon);
-        let pos = sol.map(|c| c + center).unwrap_or(center);
-        // We'll clamp the error to a small > 0 value for ease of comparison
-        let err = ((pos.transpose() * self.ata * pos
-            - 2.0 * pos.transpose() * self.atb)[0]
-            + self.btb)
-            .max(1e-6);
-
-        (CellVertex { pos }, err)
+        sol.map(|c| c + center).unwrap_or(center)
     }
 }
 
@@ -138,7 +178,7 @@ mod test {
             Vector3::new(-0.50, -1.0, -0.6),
             Vector4::new(0.0, 0.0, 0.31, 0.0),
         );
-        let (_out, err) = q.solve();
+        let (_out, err) = q.solve(VertexPlacement::default());
         assert_eq!(err, 1e-6);
     }
 
@@ -157,7 +197,7 @@ mod test {
             Vector3::new(-0.5, -0.25, 0.50),
             Vector4::new(-0.6666667, -0.33333334, 0.6666667, 0.0),
         );
-        let (out, err) = q.solve();
+        let (out, err) = q.solve(VertexPlacement::default());
         assert_eq!(err, 1e-6);
         let expected = Vector3::new(-0.5, -0.25, 0.5);
         assert!(
@@ -166,4 +206,45 @@ mod test {
             out.pos
         );
     }
+
+    #[test]
+    fn qef_placement() {
+        // Two nearly-parallel planes, which intersect along the line y = 0,
+        // z = 0.5 (far from the intersection points)
+        let mut q = QuadraticErrorSolver::new();
+        q.add_intersection(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector4::new(0.0, 1.0, 0.0, 0.0),
+        );
+        q.add_intersection(
+            Vector3::new(0.25, 0.05, 0.0),
+            Vector4::new(0.0, 1.0, 0.1, 0.0),
+        );
+
+        let (pos, err) = q.solve(VertexPlacement::MassPoint);
+        assert_eq!(pos.pos, Vector3::new(0.125, 0.025, 0.0));
+        assert!(err > 0.0);
+
+        let placement = VertexPlacement::Qef { svd_cutoff: 1e-6 };
+        let (pos, _) = q.solve(placement);
+        assert!((pos.pos.z - 0.5).abs() < 1e-3, "bad position {pos:?}");
+        let mut bounds = CellBounds::new();
+        bounds.bounds = [fidget_core::types::Interval::new(-0.1, 0.3); 3];
+        assert!(!bounds.contains(pos));
+
+        let settings = VertexSettings {
+            placement,
+            clamp_to_cell: true,
+        };
+        let (clamped, err) = q.place(&settings, &bounds);
+        assert!(bounds.contains(clamped));
+        assert_eq!(err, q.error(clamped.pos));
+
+        // Without clamping, `place` is the same as `solve`
+        let settings = VertexSettings {
+            placement,
+            clamp_to_cell: false,
+        };
+        assert_eq!(q.place(&settings, &bounds).0.pos, pos.pos);
+    }
 }
@@ -1,12 +1,23 @@
-use crate::{DistancePixel, GeometryBuffer, Image, RenderConfig, TileSizesRef};
+use crate::{
+    ColorImage, DistanceF32Writer, DistancePixel, DistanceWriter,
+    GeometryBuffer, Image, ImageRegion, PixelWriter, Polyline, RenderConfig,
+    TileSizesRef,
+};
 use fidget_core::{
-    eval::Function,
-    render::{CancelToken, ImageSize, ThreadPool, TileSizes, VoxelSize},
+    eval::{Function, MathFunction},
+    render::{
+        Camera, CancelToken, ImageSize, PipelineMetrics, RenderHandle,
+        TapeCache, ThreadPool, TileSizes, VoxelSize,
+    },
     shape::{Shape, ShapeVars},
 };
-use nalgebra::{Const, Matrix3, Matrix4, OPoint, Point2, Vector2};
+use nalgebra::{
+    Const, Matrix3, Matrix4, OPoint, Point2, Point3, Rotation3, Vector2,
+    Vector3,
+};
 
 /// Settings for 2D rendering
+#[derive(Clone)]
 pub struct ImageRenderConfig<'a> {
     /// Render size
     pub image_size: ImageSize,
@@ -14,9 +25,38 @@ pub struct ImageRenderConfig<'a> {
     /// World-to-model transform
     pub world_to_model: Matrix3<f32>,
 
+    /// Plane on which to render a cross-section of a 3D shape
+    ///
+    /// Model-space `(x, y)` coordinates (i.e. after applying
+    /// [`world_to_model`](Self::world_to_model)) are mapped onto this plane
+    /// before evaluating the shape.  If this is `None`, the shape is evaluated
+    /// on the `z = 0` plane.
+    ///
+    /// If the plane is parallel to the XY plane, then shapes passed to the
+    /// `run*` functions are specialized to its Z value (see
+    /// [`Shape::specialize_2d`]), so branches which only depend on Z aren't
+    /// evaluated at all.
+    pub slice: Option<SlicePlane>,
+
     /// Render the distance values of individual pixels
+    ///
+    /// If this is `false`, tiles which are entirely inside or outside the
+    /// shape are filled without evaluating individual pixels; see also
+    /// [`run_distance_field`](Self::run_distance_field).
     pub pixel_perfect: bool,
 
+    /// Number of samples per axis used to anti-alias pixels near the surface
+    ///
+    /// If this is 2 or more, then every pixel which is evaluated individually
+    /// (i.e. in tiles which contain the surface, or in every tile when
+    /// [`pixel_perfect`](Self::pixel_perfect) is set) is supersampled on an
+    /// `anti_alias × anti_alias` grid, and the fraction of samples inside the
+    /// shape is passed to [`PixelWriter::write_coverage`].  Pixels in filled
+    /// tiles have a coverage of exactly 0 or 1.
+    ///
+    /// Values of 0 and 1 disable anti-aliasing.
+    pub anti_alias: u8,
+
     /// Tile sizes to use during evaluation.
     ///
     /// You'll likely want to use
@@ -27,11 +67,20 @@ pub struct ImageRenderConfig<'a> {
     /// Thread pool to use for rendering
     ///
     /// If this is `None`, then rendering is done in a single thread; otherwise,
-    /// the provided pool is used.
+    /// the provided pool is used.  The rendered image is bit-identical
+    /// regardless of this setting.
     pub threads: Option<&'a ThreadPool>,
 
     /// Token to cancel rendering
     pub cancel: CancelToken,
+
+    /// Flush denormal floats to zero during evaluation
+    ///
+    /// This can dramatically speed up evaluation of fields with very small
+    /// values on some CPUs, at the cost of treating values below
+    /// `f32::MIN_POSITIVE` as zero; see
+    /// [`FlushDenormals`](fidget_core::eval::FlushDenormals) for details.
+    pub flush_denormals: bool,
 }
 
 impl Default for ImageRenderConfig<'_> {
@@ -40,9 +89,12 @@ impl Default for ImageRenderConfig<'_> {
             image_size: ImageSize::from(512),
             tile_sizes: TileSizes::new(&[128, 32, 8]).unwrap(),
             world_to_model: Matrix3::identity(),
+            slice: None,
             pixel_perfect: false,
+            anti_alias: 0,
             threads: Some(&ThreadPool::Global),
             cancel: CancelToken::new(),
+            flush_denormals: false,
         }
     }
 }
@@ -64,11 +116,88 @@ impl RenderConfig for ImageRenderConfig<'_> {
     fn is_cancelled(&self) -> bool {
         self.cancel.is_cancelled()
     }
+    fn flush_denormals(&self) -> bool {
+        self.flush_denormals
+    }
 }
 
 impl ImageRenderConfig<'_> {
+    /// Builds a configuration from a [`Camera`], using default settings
+    ///
+    /// The image is rendered on the camera's world-space `z = 0` plane, with
+    /// the same pixel-to-model mapping as the middle of a 3D render; its size
+    /// is taken from the width and height of [`Camera::size`].  Any camera
+    /// rotation is applied through the [`slice`](Self::slice) plane.
+    pub fn from_camera(camera: &Camera) -> Self {
+        let image_size =
+            ImageSize::new(camera.size.width(), camera.size.height());
+        // World units are based on the smallest axis, which may be depth
+        let s = camera.size.width().min(camera.size.height()) as f32
+            / camera
+                .size
+                .width()
+                .min(camera.size.height())
+                .min(camera.size.depth()) as f32;
+        let u = camera.rotation * Vector3::x();
+        let v = camera.rotation * Vector3::y();
+        Self {
+            image_size,
+            world_to_model: Matrix3::new_scaling(camera.scale * s),
+            slice: Some(SlicePlane::new(camera.center.into(), u, v)),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a configuration which renders a model-space rectangle, using
+    /// default settings
+    ///
+    /// The rectangle from `min` to `max` is stretched to fill the image, with
+    /// `min` at the bottom-left corner and `max` at the top-right corner.  If
+    /// the rectangle's aspect ratio doesn't match the image, then pixels are
+    /// non-square.
+    ///
+    /// As with the default view (which renders the region from `[-1, -1]` to
+    /// `[1, 1]`), pixels are sampled at their bottom-left corners.
+    ///
+    /// ```
+    /// # use fidget_core::render::ImageSize;
+    /// # use fidget_raster::ImageRenderConfig;
+    /// # use nalgebra::Point2;
+    /// let cfg = ImageRenderConfig::from_bounds(
+    ///     ImageSize::new(400, 100),
+    ///     Point2::new(-4.0, 1.0),
+    ///     Point2::new(4.0, 3.0),
+    /// );
+    /// let mat = cfg.mat();
+    /// let a = mat.transform_point(&Point2::new(0.0, 99.0));
+    /// assert!((a - Point2::new(-4.0, 1.0)).norm() < 1e-6);
+    /// let b = mat.transform_point(&Point2::new(400.0, -1.0));
+    /// assert!((b - Point2::new(4.0, 3.0)).norm() < 1e-6);
+    /// ```
+    pub fn from_bounds(
+        image_size: ImageSize,
+        min: Point2<f32>,
+        max: Point2<f32>,
+    ) -> Self {
+        // World coordinates span ±1 on the image's shorter axis, and further
+        // on its longer axis (see `RegionSize::screen_to_world`)
+        let m = image_size.width().min(image_size.height()) as f32;
+        let world = Vector2::new(
+            image_size.width() as f32 / m,
+            image_size.height() as f32 / m,
+        );
+        let scale = (max - min).component_div(&world) / 2.0;
+        let center = nalgebra::center(&min, &max);
+        Self {
+            image_size,
+            world_to_model: Matrix3::new_translation(&center.coords)
+                * Matrix3::new_nonuniform_scaling(&scale),
+            ..Default::default()
+        }
+    }
+
     /// Render a shape in 2D using this configuration
-    pub fn run<F: Function>(
+    pub fn run<F: MathFunction>(
         &self,
         shape: Shape<F>,
     ) -> Option<Image<DistancePixel>> {
@@ -76,18 +205,323 @@ impl ImageRenderConfig<'_> {
     }
 
     /// Render a shape in 2D using this configuration and variables
-    pub fn run_with_vars<F: Function>(
+    pub fn run_with_vars<F: MathFunction>(
         &self,
         shape: Shape<F>,
         vars: &ShapeVars<f32>,
     ) -> Option<Image<DistancePixel>> {
-        crate::render2d::<F>(shape, vars, self)
+        let shape = self.slice_shape(shape);
+        crate::render2d::<F, _>(shape, vars, self, &DistanceWriter, false)
+            .map(|(image, _)| image)
+    }
+
+    /// Render a shape's distance field in 2D, returning the field value at
+    /// every pixel
+    ///
+    /// Every pixel is evaluated individually (as if
+    /// [`pixel_perfect`](Self::pixel_perfect) were set), rather than filling
+    /// tiles which are entirely inside or outside the shape.  Tiles are still
+    /// simplified using interval arithmetic, which doesn't change the
+    /// resulting values, so this is cheaper than evaluating every pixel with
+    /// the full tape.  This is useful for generating signed distance field
+    /// textures.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, vm::VmShape};
+    /// # use fidget_raster::ImageRenderConfig;
+    /// # use fidget_core::shape::ShapeVars;
+    /// let (x, y, _) = Tree::axes();
+    /// let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+    /// let cfg = ImageRenderConfig::default();
+    /// let sdf = cfg.run_distance_field(shape, &ShapeVars::new()).unwrap();
+    /// assert!(sdf[(0, 0)] > 0.8);
+    /// assert!(sdf[(256, 256)] < -0.49);
+    /// ```
+    pub fn run_distance_field<F: MathFunction>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+    ) -> Option<Image<f32>> {
+        let cfg = Self {
+            pixel_perfect: true,
+            ..self.clone()
+        };
+        cfg.run_with_writer(shape, vars, &DistanceF32Writer)
+    }
+
+    /// Extracts the shape's zero isoline as a set of polylines
+    ///
+    /// The field is sampled at pixel corners and contoured with marching
+    /// squares; tiles which can't contain the contour are skipped using
+    /// interval arithmetic, and crossings are refined along the edges of each
+    /// pixel.  Polylines are in model coordinates, oriented with the inside of
+    /// the shape on their left; see [`Polyline`] for details.
+    ///
+    /// The [`pixel_perfect`](Self::pixel_perfect) and
+    /// [`anti_alias`](Self::anti_alias) settings are ignored.
+    ///
+    /// Returns `None` if rendering was cancelled.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, vm::VmShape};
+    /// # use fidget_raster::ImageRenderConfig;
+    /// # use fidget_core::shape::ShapeVars;
+    /// let (x, y, _) = Tree::axes();
+    /// let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+    /// let cfg = ImageRenderConfig::default();
+    /// let lines = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+    /// assert_eq!(lines.len(), 1);
+    /// assert!(lines[0].closed);
+    /// assert!(lines[0].signed_area() > 0.78);
+    /// ```
+    pub fn run_contours<F: MathFunction>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+    ) -> Option<Vec<Polyline>> {
+        let shape = self.slice_shape(shape);
+        crate::contour::contours(shape, vars, self, &[0.0])
+            .map(|mut out| out.pop().unwrap())
+    }
+
+    /// Extracts isolines of the shape at several levels in a single pass
+    ///
+    /// This is equivalent to calling [`run_contours`](Self::run_contours) on
+    /// the shape minus each level, but shares tile simplification and
+    /// evaluation between levels: each tile is subdivided while any level may
+    /// pass through it, and the field is only sampled once per pixel corner.
+    /// This is useful for topographic-style visualization or offset toolpaths
+    /// (e.g. passing levels every 1 mm).
+    ///
+    /// Returns one set of polylines per level, in the same order as `levels`,
+    /// or `None` if rendering was cancelled.  Each polyline is oriented with
+    /// values below its level on the left.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, vm::VmShape};
+    /// # use fidget_raster::ImageRenderConfig;
+    /// # use fidget_core::shape::ShapeVars;
+    /// let (x, y, _) = Tree::axes();
+    /// let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+    /// let cfg = ImageRenderConfig::default();
+    /// let out = cfg
+    ///     .run_isolines(shape, &ShapeVars::new(), &[-0.2, 0.0, 0.2])
+    ///     .unwrap();
+    /// assert_eq!(out.len(), 3);
+    /// assert!(out.iter().all(|lines| lines.len() == 1));
+    /// ```
+    pub fn run_isolines<F: MathFunction>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+        levels: &[f32],
+    ) -> Option<Vec<Vec<Polyline>>> {
+        let shape = self.slice_shape(shape);
+        crate::contour::contours(shape, vars, self, levels)
+    }
+
+    /// Render a shape in 2D, writing pixels in a custom output format
+    ///
+    /// Each pixel is converted by the [`PixelWriter`] while tiles are
+    /// assembled into the output image, rather than in a separate pass.
+    ///
+    /// ```
+    /// # use fidget_core::{context::Tree, vm::VmShape};
+    /// # use fidget_raster::{ImageRenderConfig, MaskWriter};
+    /// # use fidget_core::shape::ShapeVars;
+    /// let (x, y, _) = Tree::axes();
+    /// let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+    /// let cfg = ImageRenderConfig::default();
+    /// let mask = cfg
+    ///     .run_with_writer(shape, &ShapeVars::new(), &MaskWriter)
+    ///     .unwrap();
+    /// assert_eq!(mask[(256, 256)], 255);
+    /// assert_eq!(mask[(0, 0)], 0);
+    /// ```
+    pub fn run_with_writer<F: MathFunction, W: PixelWriter>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+        writer: &W,
+    ) -> Option<Image<W::Output>> {
+        let shape = self.slice_shape(shape);
+        crate::render2d::<F, W>(shape, vars, self, writer, false)
+            .map(|(image, _)| image)
+    }
+
+    /// Render a shape in 2D, reusing root tapes from a [`TapeCache`]
+    ///
+    /// When only variable values change between calls, passing the same cache
+    /// skips root tape construction (including JIT compilation).
+    pub fn run_cached<F: Function>(
+        &self,
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        cache: &mut TapeCache<F>,
+    ) -> Option<Image<DistancePixel>> {
+        let rh = cache.get(shape, self.mat3d());
+        let mut image = Image::new(self.image_size);
+        crate::render2d::render_into::<F, _>(
+            rh,
+            vars,
+            self,
+            &DistanceWriter,
+            None,
+            &mut image,
+            false,
+        )?;
+        Some(image)
+    }
+
+    /// Re-renders regions of an existing image
+    ///
+    /// Only root tiles which overlap one of the `regions` are evaluated, and
+    /// only pixels within the regions are written; the rest of `image` is left
+    /// untouched.  This is useful for editors where only a small part of the
+    /// view changes.
+    ///
+    /// Returns `None` if rendering was cancelled, in which case `image` is not
+    /// modified.
+    ///
+    /// # Panics
+    /// If the image size does not match [`image_size`](Self::image_size)
+    pub fn run_regions<F: MathFunction, W: PixelWriter>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+        writer: &W,
+        regions: &[ImageRegion],
+        image: &mut Image<W::Output>,
+    ) -> Option<()> {
+        let shape = self.slice_shape(shape);
+        let rh = RenderHandle::new(shape.with_transform(self.mat3d()));
+        crate::render2d::render_into::<F, W>(
+            rh,
+            vars,
+            self,
+            writer,
+            Some(regions),
+            image,
+            false,
+        )
+        .map(|_| ())
+    }
+
+    /// Render a shape in 2D, returning per-stage timing and counters
+    ///
+    /// Collecting metrics adds a small amount of overhead (reading the clock
+    /// around each stage), so it's only done when explicitly requested.
+    pub fn run_with_metrics<F: MathFunction>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+    ) -> Option<(Image<DistancePixel>, PipelineMetrics)> {
+        let shape = self.slice_shape(shape);
+        crate::render2d::<F, _>(shape, vars, self, &DistanceWriter, true)
     }
 
     /// Returns the combined screen-to-model transform matrix
     pub fn mat(&self) -> Matrix3<f32> {
         self.world_to_model * self.image_size.screen_to_world()
     }
+
+    /// Returns the corners of the rendered region in model coordinates
+    ///
+    /// Corners are the images of the screen-space corners `(0, 0)`, `(w, 0)`,
+    /// `(w, h)`, and `(0, h)` under [`mat`](Self::mat); open contours from
+    /// [`run_contours`](Self::run_contours) start and end on this boundary.
+    pub fn region(&self) -> [Point2<f32>; 4] {
+        let (w, h) = (
+            self.image_size.width() as f32,
+            self.image_size.height() as f32,
+        );
+        let mat = self.mat();
+        [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]
+            .map(|(x, y)| mat.transform_point(&Point2::new(x, y)))
+    }
+
+    /// Returns the model-space Z value of the slice plane, if it's constant
+    ///
+    /// This is the case when the [`slice`](Self::slice) plane is parallel to
+    /// the XY plane; returns `None` if there's no slice plane.
+    pub fn slice_z(&self) -> Option<f32> {
+        self.slice?;
+        let mat = self.mat3d();
+        (mat[(2, 0)] == 0.0 && mat[(2, 1)] == 0.0).then(|| mat[(2, 3)])
+    }
+
+    /// Specializes a shape to the slice plane's Z value, if it's constant
+    ///
+    /// Falls back to the original shape if specialization isn't supported.
+    fn slice_shape<F: MathFunction>(&self, shape: Shape<F>) -> Shape<F> {
+        match self.slice_z() {
+            Some(z) => shape.specialize_2d(z).unwrap_or(shape),
+            None => shape,
+        }
+    }
+
+    /// Returns the combined screen-to-model transform as a 3D matrix
+    ///
+    /// This includes the [`slice`](Self::slice) plane, if present; screen
+    /// coordinates are expected to have `z = 0`.
+    pub fn mat3d(&self) -> Matrix4<f32> {
+        let mat = self.mat().insert_row(2, 0.0).insert_column(2, 0.0);
+        match &self.slice {
+            Some(slice) => slice.mat() * mat,
+            None => mat,
+        }
+    }
+}
+
+/// Plane used to render a cross-section of a 3D shape
+///
+/// The plane is defined by an origin and two basis vectors; a 2D point
+/// `(x, y)` is mapped to `origin + x * u + y * v`.  The basis vectors need not
+/// be orthogonal or normalized, though they must not be parallel.
+///
+/// During rendering, interval evaluation of each tile simplifies the tape
+/// based on the region of the plane it covers, so branches of the model which
+/// don't touch the slice are pruned as usual.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SlicePlane {
+    /// Origin of the plane
+    pub origin: Point3<f32>,
+    /// Direction of the plane's X axis
+    pub u: Vector3<f32>,
+    /// Direction of the plane's Y axis
+    pub v: Vector3<f32>,
+}
+
+impl SlicePlane {
+    /// The XY plane at `z = 0` (equivalent to no slice)
+    pub const XY: Self = Self {
+        origin: Point3::new(0.0, 0.0, 0.0),
+        u: Vector3::new(1.0, 0.0, 0.0),
+        v: Vector3::new(0.0, 1.0, 0.0),
+    };
+
+    /// Builds a plane from an origin and basis vectors
+    pub fn new(origin: Point3<f32>, u: Vector3<f32>, v: Vector3<f32>) -> Self {
+        Self { origin, u, v }
+    }
+
+    /// Returns the plane's normal vector, `u × v` (normalized)
+    pub fn normal(&self) -> Vector3<f32> {
+        self.u.cross(&self.v).normalize()
+    }
+
+    /// Returns a matrix which maps points on the `z = 0` plane onto this plane
+    ///
+    /// The `z` axis is mapped to the plane's [`normal`](Self::normal), so the
+    /// matrix is invertible.
+    pub fn mat(&self) -> Matrix4<f32> {
+        Matrix4::from_columns(&[
+            self.u.push(0.0),
+            self.v.push(0.0),
+            self.normal().push(0.0),
+            self.origin.coords.push(1.0),
+        ])
+    }
 }
 
 /// Settings for 3D rendering
@@ -112,11 +546,20 @@ pub struct VoxelRenderConfig<'a> {
     /// Thread pool to use for rendering
     ///
     /// If this is `None`, then rendering is done in a single thread; otherwise,
-    /// the provided pool is used.
+    /// the provided pool is used.  The rendered image is bit-identical
+    /// regardless of this setting.
     pub threads: Option<&'a ThreadPool>,
 
     /// Token to cancel rendering
     pub cancel: CancelToken,
+
+    /// Flush denormal floats to zero during evaluation
+    ///
+    /// This can dramatically speed up evaluation of fields with very small
+    /// values on some CPUs, at the cost of treating values below
+    /// `f32::MIN_POSITIVE` as zero; see
+    /// [`FlushDenormals`](fidget_core::eval::FlushDenormals) for details.
+    pub flush_denormals: bool,
 }
 
 impl Default for VoxelRenderConfig<'_> {
@@ -127,6 +570,7 @@ impl Default for VoxelRenderConfig<'_> {
             world_to_model: Matrix4::identity(),
             threads: Some(&ThreadPool::Global),
             cancel: CancelToken::new(),
+            flush_denormals: false,
         }
     }
 }
@@ -148,9 +592,21 @@ impl RenderConfig for VoxelRenderConfig<'_> {
     fn is_cancelled(&self) -> bool {
         self.cancel.is_cancelled()
     }
+    fn flush_denormals(&self) -> bool {
+        self.flush_denormals
+    }
 }
 
 impl VoxelRenderConfig<'_> {
+    /// Builds a configuration from a [`Camera`], using default settings
+    pub fn from_camera(camera: &Camera) -> Self {
+        Self {
+            image_size: camera.size,
+            world_to_model: camera.world_to_model(),
+            ..Default::default()
+        }
+    }
+
     /// Render a shape in 3D using this configuration
     ///
     /// Returns a [`GeometryBuffer`] of pixel data, or `None` if rendering was
@@ -169,7 +625,128 @@ impl VoxelRenderConfig<'_> {
         shape: Shape<F>,
         vars: &ShapeVars<f32>,
     ) -> Option<GeometryBuffer> {
-        crate::render3d::<F>(shape, vars, self)
+        crate::render3d::<F>(shape, vars, self, false).map(|(image, _)| image)
+    }
+
+    /// Render a shape in 3D, reusing root tapes from a [`TapeCache`]
+    ///
+    /// When only variable values change between calls, passing the same cache
+    /// skips root tape construction (including JIT compilation).
+    pub fn run_cached<F: Function>(
+        &self,
+        shape: &Shape<F>,
+        vars: &ShapeVars<f32>,
+        cache: &mut TapeCache<F>,
+    ) -> Option<GeometryBuffer> {
+        let rh = cache.get(shape, self.mat());
+        crate::render3d::render_handle(rh, vars, self, false)
+            .map(|(image, _)| image)
+    }
+
+    /// Re-renders regions of an existing image
+    ///
+    /// Only root tiles which overlap one of the `regions` are evaluated, and
+    /// only pixels within the regions are written; the rest of `image` is left
+    /// untouched.
+    ///
+    /// Returns `None` if rendering was cancelled, in which case `image` is not
+    /// modified.
+    ///
+    /// # Panics
+    /// If the image size does not match [`image_size`](Self::image_size)
+    pub fn run_regions<F: Function>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+        regions: &[ImageRegion],
+        image: &mut GeometryBuffer,
+    ) -> Option<()> {
+        let rh = RenderHandle::new(shape.with_transform(self.mat()));
+        crate::render3d::render_handle_into::<F>(
+            rh,
+            vars,
+            self,
+            Some(regions),
+            image,
+            false,
+        )
+        .map(|_| ())
+    }
+
+    /// Render a shape in 3D, evaluating a color at each surface pixel
+    ///
+    /// `color` contains one shape per RGB channel, with values in the range
+    /// `0.0..=1.0`.  Color shapes are only evaluated at pixels which hit the
+    /// surface (at the same position used for normals), and are simplified
+    /// using interval evaluation over the surface within each tile.
+    ///
+    /// Returns the geometry buffer and a color image of the same size, or
+    /// `None` if rendering was cancelled.
+    pub fn run_with_color<F: Function>(
+        &self,
+        shape: Shape<F>,
+        color: [Shape<F>; 3],
+        vars: &ShapeVars<f32>,
+    ) -> Option<(GeometryBuffer, ColorImage)> {
+        let (geometry, _) = crate::render3d::<F>(shape, vars, self, false)?;
+        let (color, _) = crate::render3d::render_color::<F>(
+            color, &geometry, vars, self, false,
+        )?;
+        Some((geometry, color))
+    }
+
+    /// Render a shape in 3D, returning per-stage timing and counters
+    ///
+    /// Collecting metrics adds a small amount of overhead (reading the clock
+    /// around each stage), so it's only done when explicitly requested.
+    pub fn run_with_metrics<F: Function>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+    ) -> Option<(GeometryBuffer, PipelineMetrics)> {
+        crate::render3d::<F>(shape, vars, self, true)
+    }
+
+    /// Renders an orbit around the shape, returning one image per frame
+    ///
+    /// Frame `i` rotates the camera by `i * 360° / frames` about the world's
+    /// vertical (Y) axis, before applying
+    /// [`world_to_model`](Self::world_to_model); frame 0 matches [`run`](Self::run).
+    ///
+    /// The shape's root tapes are built once and shared between all frames,
+    /// so this is cheaper than calling [`run`](Self::run) repeatedly with
+    /// different transforms (especially with the JIT evaluator, where building
+    /// tapes means compiling machine code).
+    ///
+    /// Returns `None` if rendering was cancelled.
+    pub fn run_turntable<F: Function>(
+        &self,
+        shape: Shape<F>,
+        vars: &ShapeVars<f32>,
+        frames: usize,
+    ) -> Option<Vec<GeometryBuffer>> {
+        let mut rh = RenderHandle::new(shape);
+        let mut storage = vec![];
+        let _ = rh.i_tape(&mut storage);
+        let _ = rh.f_tape(&mut storage);
+        let _ = rh.g_tape(&mut storage);
+
+        (0..frames)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / frames as f32;
+                let rot = Rotation3::from_axis_angle(&Vector3::y_axis(), angle);
+                let mat = self.world_to_model
+                    * rot.to_homogeneous()
+                    * self.image_size.screen_to_world();
+                crate::render3d::render_handle(
+                    rh.with_transform(mat),
+                    vars,
+                    self,
+                    false,
+                )
+                .map(|(image, _)| image)
+            })
+            .collect()
     }
 
     /// Returns the combined screen-to-model transform matrix
@@ -253,4 +830,109 @@ mod test {
             Point2::new(1.0, -1.0)
         );
     }
+
+    #[test]
+    fn test_from_bounds() {
+        let (min, max) = (Point2::new(-4.0, 1.0), Point2::new(4.0, 3.0));
+        for (w, h) in [(200, 100), (100, 300), (64, 64)] {
+            let config =
+                ImageRenderConfig::from_bounds(ImageSize::new(w, h), min, max);
+            let mat = config.mat();
+            let h = h as f32;
+            let w = w as f32;
+            for (screen, model) in [
+                (Point2::new(0.0, h - 1.0), min),
+                (Point2::new(w, -1.0), max),
+                (Point2::new(w / 2.0, h / 2.0 - 1.0), Point2::new(0.0, 2.0)),
+            ] {
+                let p = mat.transform_point(&screen);
+                assert!((p - model).norm() < 1e-5, "{p} != {model}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_slice_mat() {
+        let config = ImageRenderConfig {
+            image_size: ImageSize::from(512),
+            ..Default::default()
+        };
+        let flat = config.mat3d();
+        assert_eq!(
+            flat.transform_point(&Point3::new(512.0, -1.0, 0.0)),
+            Point3::new(1.0, 1.0, 0.0)
+        );
+
+        // Slicing on the XZ plane at y = 0.5
+        let config = ImageRenderConfig {
+            image_size: ImageSize::from(512),
+            slice: Some(SlicePlane::new(
+                Point3::new(0.0, 0.5, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, 1.0),
+            )),
+            ..Default::default()
+        };
+        let mat = config.mat3d();
+        assert_eq!(
+            mat.transform_point(&Point3::new(512.0, -1.0, 0.0)),
+            Point3::new(1.0, 0.5, 1.0)
+        );
+        assert_eq!(
+            mat.transform_point(&Point3::new(0.0, 511.0, 0.0)),
+            Point3::new(-1.0, 0.5, -1.0)
+        );
+        assert_eq!(SlicePlane::XY.mat(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_slice_z() {
+        let config = ImageRenderConfig {
+            image_size: ImageSize::from(512),
+            ..Default::default()
+        };
+        assert_eq!(config.slice_z(), None);
+
+        let config = ImageRenderConfig {
+            slice: Some(SlicePlane::new(
+                Point3::new(1.0, 2.0, 0.6),
+                Vector3::new(0.0, 2.0, 0.0),
+                Vector3::new(-1.0, 0.0, 0.0),
+            )),
+            ..config
+        };
+        assert_eq!(config.slice_z(), Some(0.6));
+
+        let config = ImageRenderConfig {
+            slice: Some(SlicePlane::new(
+                Point3::new(0.0, 0.0, 0.6),
+                Vector3::new(1.0, 0.0, 1.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            )),
+            ..config
+        };
+        assert_eq!(config.slice_z(), None);
+    }
+
+    #[test]
+    fn test_from_camera() {
+        let camera = Camera {
+            size: VoxelSize::new(300, 200, 100),
+            center: Vector3::new(1.0, 2.0, 3.0),
+            scale: 2.5,
+            rotation: nalgebra::UnitQuaternion::from_euler_angles(
+                0.3, -0.2, 0.1,
+            ),
+            ..Camera::default()
+        };
+        let image = ImageRenderConfig::from_camera(&camera);
+        let voxels = VoxelRenderConfig::from_camera(&camera);
+        for (x, y) in [(0.0, 0.0), (150.0, 99.0), (299.0, 10.0)] {
+            let p = camera.screen_to_model_point(Point3::new(x, y, 50.0));
+            let a = image.mat3d().transform_point(&Point3::new(x, y, 0.0));
+            let b = voxels.mat().transform_point(&Point3::new(x, y, 50.0));
+            assert!((p - a).norm() < 1e-4, "{p} != {a}");
+            assert!((p - b).norm() < 1e-4, "{p} != {b}");
+        }
+    }
 }
@@ -0,0 +1,719 @@
+//! Contour extraction from 2D fields, using marching squares
+use super::RenderHandle;
+use crate::{
+    RenderConfig, RenderWorker, TileSizesRef,
+    config::{ImageRenderConfig, Tile},
+};
+use fidget_core::{
+    eval::Function,
+    render::{MetricsRecorder, Stage, ThreadPool},
+    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars, Transformed},
+    types::Interval,
+};
+use nalgebra::{Point2, Vector2};
+use std::collections::{HashMap, HashSet};
+
+/// Number of regula falsi iterations used to refine each edge crossing
+const REFINE_ITERS: usize = 4;
+
+/// Connected piece of a contour
+///
+/// Points are in model coordinates, i.e. the 2D coordinate system produced by
+/// [`ImageRenderConfig::mat`].  Contours are oriented so that the inside of the
+/// shape is on the left: closed outer boundaries run counter-clockwise, and
+/// the boundaries of holes run clockwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polyline {
+    /// Vertices of the polyline
+    ///
+    /// If the polyline is closed, the first point is not repeated at the end.
+    pub points: Vec<Point2<f32>>,
+
+    /// Whether the polyline is a closed loop
+    ///
+    /// Polylines are open if they leave the rendered region.
+    pub closed: bool,
+}
+
+impl Polyline {
+    /// Returns the signed area enclosed by the polyline
+    ///
+    /// The area is positive for counter-clockwise loops (i.e. outer
+    /// boundaries) and negative for clockwise loops (holes).  Open polylines
+    /// are treated as if they were closed by a straight segment.
+    pub fn signed_area(&self) -> f32 {
+        let n = self.points.len();
+        (0..n)
+            .map(|i| {
+                let a = self.points[i];
+                let b = self.points[(i + 1) % n];
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f32>()
+            / 2.0
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Edge of the sample lattice, in screen coordinates
+///
+/// Horizontal edges run from `(x, y)` to `(x + 1, y)`; vertical edges run from
+/// `(x, y)` to `(x, y + 1)`.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+struct EdgeKey {
+    x: u32,
+    y: u32,
+    vertical: bool,
+}
+
+impl EdgeKey {
+    fn horizontal(x: usize, y: usize) -> Self {
+        Self {
+            x: x as u32,
+            y: y as u32,
+            vertical: false,
+        }
+    }
+
+    fn vertical(x: usize, y: usize) -> Self {
+        Self {
+            x: x as u32,
+            y: y as u32,
+            vertical: true,
+        }
+    }
+
+    /// Returns the edge's endpoints, in screen coordinates
+    fn endpoints(&self) -> (Point2<usize>, Point2<usize>) {
+        let a = Point2::new(self.x as usize, self.y as usize);
+        let d = if self.vertical {
+            Vector2::new(0, 1)
+        } else {
+            Vector2::new(1, 0)
+        };
+        (a, a + d)
+    }
+}
+
+/// Contour segments within a root tile
+///
+/// Segments and edges are tagged with the index of their level.
+#[derive(Default)]
+struct TileContours {
+    /// Oriented segments, as `(start, end)` edges
+    segments: Vec<(usize, EdgeKey, EdgeKey)>,
+
+    /// Refined crossing position on each edge, in screen coordinates
+    edges: Vec<(usize, EdgeKey, Point2<f32>)>,
+}
+
+/// Image configuration, along with the levels to be contoured
+struct ContourConfig<'a> {
+    image: &'a ImageRenderConfig<'a>,
+    levels: &'a [f32],
+}
+
+impl RenderConfig for ContourConfig<'_> {
+    fn width(&self) -> u32 {
+        self.image.width()
+    }
+    fn height(&self) -> u32 {
+        self.image.height()
+    }
+    fn tile_sizes(&self) -> TileSizesRef<'_> {
+        self.image.tile_sizes()
+    }
+    fn threads(&self) -> Option<&ThreadPool> {
+        self.image.threads()
+    }
+    fn is_cancelled(&self) -> bool {
+        self.image.is_cancelled()
+    }
+    fn flush_denormals(&self) -> bool {
+        self.image.flush_denormals()
+    }
+}
+
+/// Per-thread worker
+struct Worker<'a, F: Function> {
+    tile_sizes: TileSizesRef<'a>,
+    width: usize,
+    height: usize,
+    levels: &'a [f32],
+
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+
+    eval_float_slice: ShapeBulkEval<F::FloatSliceEval>,
+    eval_interval: ShapeTracingEval<F::IntervalEval>,
+
+    /// Spare tape storage for reuse
+    tape_storage: Vec<F::TapeStorage>,
+
+    /// Spare shape storage for reuse
+    shape_storage: Vec<F::Storage>,
+
+    /// Workspace for shape simplification
+    workspace: F::Workspace,
+
+    /// Per-stage timing (disabled unless metrics are requested)
+    metrics: MetricsRecorder,
+
+    /// Output for the root tile being rendered
+    out: TileContours,
+}
+
+impl<'a, F: Function, T> RenderWorker<'a, F, T> for Worker<'a, F> {
+    type Config = ContourConfig<'a>;
+    type Output = TileContours;
+    fn new(cfg: &'a Self::Config) -> Self {
+        Worker::<F> {
+            tile_sizes: cfg.tile_sizes(),
+            width: cfg.width() as usize,
+            height: cfg.height() as usize,
+            levels: cfg.levels,
+            x: vec![],
+            y: vec![],
+            z: vec![],
+            eval_float_slice: Default::default(),
+            eval_interval: Default::default(),
+            tape_storage: vec![],
+            shape_storage: vec![],
+            workspace: Default::default(),
+            metrics: Default::default(),
+            out: Default::default(),
+        }
+    }
+
+    fn metrics(&mut self) -> &mut MetricsRecorder {
+        &mut self.metrics
+    }
+
+    fn render_tile(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        tile: Tile<2>,
+    ) -> Self::Output {
+        let active: Vec<usize> = (0..self.levels.len()).collect();
+        self.render_tile_recurse(shape, vars, 0, tile, &active);
+        std::mem::take(&mut self.out)
+    }
+}
+
+impl<F: Function> Worker<'_, F> {
+    fn render_tile_recurse<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        depth: usize,
+        tile: Tile<2>,
+        active: &[usize],
+    ) {
+        let tile_size = self.tile_sizes[depth];
+        if tile.corner.x >= self.width || tile.corner.y >= self.height {
+            return;
+        }
+
+        // The interval covers every lattice point used by the tile's cells,
+        // including those on its far edges.
+        let base = Point2::from(tile.corner).cast::<f32>();
+        let x = Interval::new(base.x, base.x + tile_size as f32);
+        let y = Interval::new(base.y, base.y + tile_size as f32);
+        let z = Interval::new(0.0, 0.0);
+
+        let t = self.metrics.start();
+        let tape = shape.i_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let (i, simplify) =
+            self.eval_interval.eval_v(tape, x, y, z, vars).unwrap();
+        self.metrics.record(Stage::IntervalEval, t);
+
+        // Skip tiles which can't contain any of the contours (and skip levels
+        // which can't be present in subtiles)
+        let active: Vec<usize> = active
+            .iter()
+            .copied()
+            .filter(|&k| {
+                let level = self.levels[k];
+                !(i.upper() < level || i.lower() >= level)
+            })
+            .collect();
+        if active.is_empty() {
+            return;
+        }
+
+        let sub_tape = if let Some(trace) = simplify.as_ref() {
+            let t = self.metrics.start();
+            let s = shape.simplify(
+                trace,
+                &mut self.workspace,
+                &mut self.shape_storage,
+                &mut self.tape_storage,
+            );
+            self.metrics.record(Stage::Simplify, t);
+            s
+        } else {
+            shape
+        };
+
+        if let Some(next_tile_size) = self.tile_sizes.get(depth + 1) {
+            let n = tile_size / next_tile_size;
+            for j in 0..n {
+                for i in 0..n {
+                    self.render_tile_recurse(
+                        sub_tape,
+                        vars,
+                        depth + 1,
+                        Tile::new(
+                            tile.corner + Vector2::new(i, j) * next_tile_size,
+                        ),
+                        &active,
+                    );
+                }
+            }
+        } else {
+            self.render_tile_cells(sub_tape, vars, tile_size, tile, &active);
+        }
+    }
+
+    /// Evaluates the tape at the given screen-space points
+    fn eval<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+    ) -> Vec<f32> {
+        self.z.resize(self.x.len(), 0.0);
+
+        let t = self.metrics.start();
+        let tape = shape.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let out = self
+            .eval_float_slice
+            .eval_v(tape, &self.x, &self.y, &self.z, vars)
+            .unwrap()
+            .to_vec();
+        self.metrics.record(Stage::Fill, t);
+        out
+    }
+
+    /// Runs marching squares on every cell in a leaf tile
+    ///
+    /// The lattice is evaluated once, then contoured for each active level.
+    fn render_tile_cells<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        tile_size: usize,
+        tile: Tile<2>,
+        active: &[usize],
+    ) {
+        // Sample the lattice points at the corners of every cell
+        let n = tile_size + 1;
+        self.x.clear();
+        self.y.clear();
+        for j in 0..n {
+            for i in 0..n {
+                self.x.push((tile.corner.x + i) as f32);
+                self.y.push((tile.corner.y + j) as f32);
+            }
+        }
+        let values = self.eval(shape, vars);
+        let value = |p: Point2<usize>| {
+            values[(p.x - tile.corner.x) + (p.y - tile.corner.y) * n]
+        };
+
+        let mut edges = HashSet::new();
+        for j in 0..tile_size {
+            for i in 0..tile_size {
+                let x = tile.corner.x + i;
+                let y = tile.corner.y + j;
+                if x >= self.width || y >= self.height {
+                    continue;
+                }
+                // Corners and edges of the cell, counter-clockwise in screen
+                // coordinates; edge `k` runs from corner `k` to `k + 1`
+                let v = [
+                    values[i + j * n],
+                    values[i + 1 + j * n],
+                    values[i + 1 + (j + 1) * n],
+                    values[i + (j + 1) * n],
+                ];
+                let e = [
+                    EdgeKey::horizontal(x, y),
+                    EdgeKey::vertical(x + 1, y),
+                    EdgeKey::horizontal(x, y + 1),
+                    EdgeKey::vertical(x, y),
+                ];
+                let center = v.iter().sum::<f32>() / 4.0;
+                for &k in active {
+                    let level = self.levels[k];
+                    let v = v.map(|v| v - level);
+                    for (a, b) in cell_segments(v, center - level) {
+                        self.out.segments.push((k, e[a], e[b]));
+                        edges.insert((k, e[a]));
+                        edges.insert((k, e[b]));
+                    }
+                }
+            }
+        }
+        if edges.is_empty() {
+            return;
+        }
+
+        // Refine the crossing on each edge with regula falsi, evaluating
+        // every edge (across all levels) in a single batch per iteration.
+        // Brackets store values relative to the edge's level.
+        let edges: Vec<(usize, EdgeKey)> = edges.into_iter().collect();
+        let mut brackets: Vec<_> = edges
+            .iter()
+            .map(|(k, e)| {
+                let (a, b) = e.endpoints();
+                let level = self.levels[*k];
+                ((0.0f32, value(a) - level), (1.0f32, value(b) - level))
+            })
+            .collect();
+        let guess = |((ta, fa), (tb, fb)): ((f32, f32), (f32, f32))| {
+            let t = ta + (tb - ta) * fa / (fa - fb);
+            if t.is_finite() {
+                t.clamp(ta, tb)
+            } else {
+                (ta + tb) / 2.0
+            }
+        };
+        for _ in 0..REFINE_ITERS {
+            self.x.clear();
+            self.y.clear();
+            for ((_, e), b) in edges.iter().zip(&brackets) {
+                let (a, _) = e.endpoints();
+                let t = guess(*b);
+                let (dx, dy) = if e.vertical { (0.0, t) } else { (t, 0.0) };
+                self.x.push(a.x as f32 + dx);
+                self.y.push(a.y as f32 + dy);
+            }
+            let out = self.eval(shape, vars);
+            for ((b, f), (k, _)) in brackets.iter_mut().zip(out).zip(&edges) {
+                let f = f - self.levels[*k];
+                let t = guess(*b);
+                if (f < 0.0) == (b.0.1 < 0.0) {
+                    b.0 = (t, f);
+                } else {
+                    b.1 = (t, f);
+                }
+            }
+        }
+        for ((k, e), b) in edges.into_iter().zip(brackets) {
+            let (a, _) = e.endpoints();
+            let t = guess(b);
+            let (dx, dy) = if e.vertical { (0.0, t) } else { (t, 0.0) };
+            let p = Point2::new(a.x as f32 + dx, a.y as f32 + dy);
+            self.out.edges.push((k, e, p));
+        }
+    }
+}
+
+/// Returns oriented contour segments within a single cell
+///
+/// `v` contains corner values in counter-clockwise order, and `center` is the
+/// value used to resolve saddle points.  Segments are returned as pairs of
+/// edge indices (where edge `k` runs from corner `k` to corner `k + 1`), and
+/// are oriented so that the inside of the shape is on their left.
+fn cell_segments(v: [f32; 4], center: f32) -> Vec<(usize, usize)> {
+    let inside = v.map(|v| v < 0.0);
+
+    // Walking counter-clockwise around the cell, crossings alternate between
+    // leaving the shape (`exits`) and entering it (`entries`).
+    let mut exits = vec![];
+    let mut entries = vec![];
+    for k in 0..4 {
+        match (inside[k], inside[(k + 1) % 4]) {
+            (true, false) => exits.push(k),
+            (false, true) => entries.push(k),
+            _ => (),
+        }
+    }
+
+    // Each segment runs from an exit to an entry.  If the center of a saddle
+    // cell is inside the shape, then each exit is paired with the following
+    // entry (joining the inside corners); otherwise, it's paired with the
+    // preceding entry.  With only two crossings, both rules are equivalent.
+    let join = center < 0.0;
+    exits
+        .into_iter()
+        .map(|a| {
+            let b = (1..4)
+                .map(|d| if join { (a + d) % 4 } else { (a + 4 - d) % 4 })
+                .find(|k| entries.contains(k))
+                .unwrap();
+            (a, b)
+        })
+        .collect()
+}
+
+/// Extracts isolines of a shape at the given levels
+///
+/// The shape is sampled on a lattice at pixel corners, and marching squares is
+/// run on each pixel-sized cell.  Interval arithmetic is used to skip tiles
+/// which cannot contain any of the contours (and to simplify the tape for the
+/// rest), then crossings are refined along lattice edges with a few iterations
+/// of regula falsi.  Every level shares the same tile subdivision, tape
+/// simplification, and lattice evaluation.
+///
+/// Returns one set of polylines per level (in the same order as `levels`), or
+/// `None` if rendering was cancelled.
+pub fn contours<F: Function>(
+    shape: Shape<F>,
+    vars: &ShapeVars<f32>,
+    config: &ImageRenderConfig,
+    levels: &[f32],
+) -> Option<Vec<Vec<Polyline>>> {
+    let rh: RenderHandle<F, Transformed> =
+        RenderHandle::new(shape.with_transform(config.mat3d()));
+    let config = ContourConfig {
+        image: config,
+        levels,
+    };
+    let (tiles, _metrics) =
+        super::render_tiles::<F, Worker<F>, _>(rh, vars, &config, None, false)?;
+
+    let mut out = vec![Segments::default(); levels.len()];
+    for (_tile, data) in tiles {
+        for (k, e, p) in data.edges {
+            out[k].positions.insert(e, p);
+        }
+        for (k, a, b) in data.segments {
+            out[k].next.insert(a, b);
+            out[k].starts.push(a);
+        }
+    }
+
+    // Screen coordinates have the Y axis pointing down, so the transform to
+    // model coordinates typically flips the orientation of every segment.
+    let mat = config.image.mat();
+    let flip = mat.fixed_view::<2, 2>(0, 0).determinant() < 0.0;
+    let out = out
+        .into_iter()
+        .map(|s| {
+            let mut lines = s.stitch();
+            for line in &mut lines {
+                for p in &mut line.points {
+                    *p = mat.transform_point(p);
+                }
+                if flip {
+                    line.points.reverse();
+                }
+            }
+            lines
+        })
+        .collect();
+    Some(out)
+}
+
+/// Oriented contour segments for a single level, in screen coordinates
+#[derive(Clone, Default)]
+struct Segments {
+    /// Crossing position on each edge
+    positions: HashMap<EdgeKey, Point2<f32>>,
+
+    /// Map from the start to the end of each segment
+    next: HashMap<EdgeKey, EdgeKey>,
+
+    /// Segment starts, in a deterministic order
+    starts: Vec<EdgeKey>,
+}
+
+impl Segments {
+    /// Joins segments into polylines
+    fn stitch(self) -> Vec<Polyline> {
+        // Segments are consistently oriented, so each edge has at most one
+        // outgoing and one incoming segment.  Open polylines start at edges
+        // with no incoming segment; everything else is part of a closed loop.
+        let incoming: HashSet<EdgeKey> = self.next.values().copied().collect();
+        let (open, rest): (Vec<_>, Vec<_>) =
+            self.starts.into_iter().partition(|e| !incoming.contains(e));
+
+        let mut out = vec![];
+        let mut seen = HashSet::new();
+        for start in open.into_iter().chain(rest) {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut points = vec![self.positions[&start]];
+            let mut e = start;
+            let mut closed = false;
+            while let Some(&n) = self.next.get(&e) {
+                if n == start {
+                    closed = true;
+                    break;
+                }
+                points.push(self.positions[&n]);
+                seen.insert(n);
+                e = n;
+            }
+            out.push(Polyline { points, closed });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fidget_core::{context::Tree, render::ImageSize, vm::VmShape};
+
+    fn circle(r: f32) -> Tree {
+        let (x, y, _) = Tree::axes();
+        (x.square() + y.square()).sqrt() - r
+    }
+
+    #[test]
+    fn cell_segment_orientation() {
+        // Bottom-left corner inside: the segment runs from the bottom edge to
+        // the left edge, with the corner on its left
+        assert_eq!(cell_segments([-1.0, 1.0, 1.0, 1.0], 0.5), vec![(0, 3)]);
+
+        // Everything but the bottom-right corner inside
+        assert_eq!(cell_segments([-1.0, 1.0, -1.0, -1.0], -0.5), vec![(0, 1)]);
+
+        // No crossings
+        assert!(cell_segments([-1.0; 4], -1.0).is_empty());
+        assert!(cell_segments([1.0; 4], 1.0).is_empty());
+
+        // Saddles
+        let v = [-1.0, 1.0, -1.0, 1.0];
+        assert_eq!(cell_segments(v, -0.1), vec![(0, 1), (2, 3)]);
+        assert_eq!(cell_segments(v, 0.1), vec![(0, 3), (2, 1)]);
+    }
+
+    #[test]
+    fn contour_circle() {
+        let shape = VmShape::from(circle(0.5));
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(256, 200),
+            ..Default::default()
+        };
+        let lines = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line.closed);
+        assert!(line.points.len() > 100);
+        for p in &line.points {
+            let r = p.coords.norm();
+            assert!((r - 0.5).abs() < 1e-4, "bad radius {r}");
+        }
+        let area = line.signed_area();
+        let expected = std::f32::consts::PI * 0.25;
+        assert!((area - expected).abs() < 1e-3, "bad area {area}");
+    }
+
+    #[test]
+    fn contour_holes() {
+        let ring = circle(0.8).max(-circle(0.4));
+        let shape = VmShape::from(ring);
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::from(128),
+            ..Default::default()
+        };
+        let mut lines = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+        assert_eq!(lines.len(), 2);
+        lines.sort_by(|a, b| a.signed_area().total_cmp(&b.signed_area()));
+        assert!(lines.iter().all(|p| p.closed));
+
+        // The hole runs clockwise and the outer boundary counter-clockwise
+        let pi = std::f32::consts::PI;
+        assert!((lines[0].signed_area() + pi * 0.16).abs() < 1e-2);
+        assert!((lines[1].signed_area() - pi * 0.64).abs() < 1e-2);
+    }
+
+    #[test]
+    fn contour_open() {
+        // This circle is clipped by the edges of the image
+        let shape = VmShape::from(circle(1.2));
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::from(64),
+            ..Default::default()
+        };
+        let lines = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+        assert_eq!(lines.len(), 4);
+        for line in &lines {
+            assert!(!line.closed);
+            for p in &line.points {
+                assert!((p.coords.norm() - 1.2).abs() < 1e-3);
+            }
+            // The inside of the circle is on the left
+            let d = line.points[1] - line.points[0];
+            let left = Vector2::new(-d.y, d.x);
+            assert!(left.dot(&line.points[0].coords) < 0.0);
+        }
+    }
+
+    #[test]
+    fn contour_threads() {
+        let shape = VmShape::from(circle(0.5).min(circle(0.3).remap_xyz(
+            Tree::x() - 0.6,
+            Tree::y(),
+            Tree::z(),
+        )));
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::from(200),
+            ..Default::default()
+        };
+        let a = cfg.run_contours(shape.clone(), &ShapeVars::new()).unwrap();
+        let cfg = ImageRenderConfig {
+            threads: None,
+            ..cfg
+        };
+        let b = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 1);
+    }
+
+    #[test]
+    fn contour_levels() {
+        let (x, y, _) = Tree::axes();
+        let shape = VmShape::from((x.square() + y.square()).sqrt());
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::from(256),
+            ..Default::default()
+        };
+        let levels = [0.6, 0.2, -0.5, 0.4, 2.0];
+        let out = cfg
+            .run_isolines(shape.clone(), &ShapeVars::new(), &levels)
+            .unwrap();
+        assert_eq!(out.len(), levels.len());
+        for (lines, level) in out.iter().zip(levels) {
+            if level <= 0.0 || level > 2f32.sqrt() {
+                assert!(lines.is_empty());
+                continue;
+            }
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].closed);
+            for p in &lines[0].points {
+                assert!((p.coords.norm() - level).abs() < 1e-4);
+            }
+        }
+
+        // Single-level contours match the multi-level results
+        for (lines, level) in out.iter().zip(levels) {
+            let shape = VmShape::from(
+                (Tree::x().square() + Tree::y().square()).sqrt() - level,
+            );
+            let single = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+            assert_eq!(lines, &single);
+        }
+    }
+
+    #[test]
+    fn contour_cancel() {
+        let shape = VmShape::from(circle(0.5));
+        let cfg = ImageRenderConfig::default();
+        cfg.cancel.cancel();
+        assert!(cfg.run_contours(shape, &ShapeVars::new()).is_none());
+    }
+}
@@ -5,21 +5,33 @@
 #![warn(missing_docs)]
 use crate::config::Tile;
 use fidget_core::{
-    eval::Function,
-    render::{ImageSize, RenderHandle, ThreadPool, TileSizes, VoxelSize},
-    shape::{Shape, ShapeVars},
+    eval::{FlushDenormals, Function},
+    render::{
+        ImageSize, MetricsRecorder, PipelineMetrics, RenderHandle, ThreadPool,
+        TileSizes, VoxelSize,
+    },
+    shape::ShapeVars,
 };
 use nalgebra::Point2;
 use rayon::prelude::*;
 use zerocopy::{FromBytes, Immutable, IntoBytes};
 
 mod config;
+mod contour;
 mod render2d;
 mod render3d;
+mod svg;
+mod writer;
 
 pub mod effects;
-pub use config::{ImageRenderConfig, VoxelRenderConfig};
+pub use config::{ImageRenderConfig, SlicePlane, VoxelRenderConfig};
+pub use contour::Polyline;
 pub use render2d::DistancePixel;
+pub use svg::write_svg;
+pub use writer::{
+    CoverageWriter, Distance16Writer, DistanceF32Writer, DistanceWriter,
+    MaskWriter, PixelWriter, RgbaWriter,
+};
 
 use render2d::render as render2d;
 use render3d::render as render3d;
@@ -74,17 +86,71 @@ impl TileSizesRef<'_> {
     }
 }
 
+/// Rectangular region of an image, in pixel coordinates
+///
+/// Regions are used to re-render part of an image, e.g.
+/// [`ImageRenderConfig::run_regions`] and [`VoxelRenderConfig::run_regions`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageRegion {
+    /// Minimum corner (inclusive)
+    pub min: Point2<usize>,
+    /// Maximum corner (exclusive)
+    pub max: Point2<usize>,
+}
+
+impl ImageRegion {
+    /// Builds a region from its corner, width, and height
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            min: Point2::new(x, y),
+            max: Point2::new(x + width, y + height),
+        }
+    }
+
+    /// Checks whether the given pixel is within the region
+    #[inline]
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.min.x..self.max.x).contains(&x)
+            && (self.min.y..self.max.y).contains(&y)
+    }
+
+    /// Checks whether the region overlaps a square tile
+    fn intersects(&self, corner: Point2<usize>, size: usize) -> bool {
+        corner.x < self.max.x
+            && corner.x + size > self.min.x
+            && corner.y < self.max.y
+            && corner.y + size > self.min.y
+    }
+}
+
+/// Checks whether a pixel is in any of the given regions
+///
+/// If `regions` is `None`, then every pixel is included
+#[inline]
+fn in_regions(regions: Option<&[ImageRegion]>, x: usize, y: usize) -> bool {
+    regions.is_none_or(|rs| rs.iter().any(|r| r.contains(x, y)))
+}
+
+/// Rendered tiles, returned from [`render_tiles`]
+type RenderedTiles<O> = Vec<(Tile<2>, O)>;
+
 /// Grand unified render function
 ///
 /// This handles tile generation and building + calling render workers in
 /// parallel (using [`rayon`] for parallelism at the tile level).
 ///
-/// It returns a set of output tiles, or `None` if rendering has been cancelled
+/// It returns a set of output tiles, or `None` if rendering has been cancelled.
+/// If `regions` is provided, then only root tiles which overlap at least one
+/// region are rendered.  If `metrics` is true, then per-thread
+/// [`PipelineMetrics`] are accumulated (otherwise, the returned metrics are
+/// empty).
 pub(crate) fn render_tiles<'a, F: Function, W: RenderWorker<'a, F, T>, T>(
-    shape: Shape<F, T>,
+    mut rh: RenderHandle<F, T>,
     vars: &ShapeVars<f32>,
     config: &'a W::Config,
-) -> Option<Vec<(Tile<2>, W::Output)>>
+    regions: Option<&[ImageRegion]>,
+    metrics: bool,
+) -> Option<(RenderedTiles<W::Output>, PipelineMetrics)>
 where
     W::Config: Send + Sync,
     T: Sync,
@@ -99,54 +165,90 @@ where
     let height = config.height() as usize;
     for i in 0..width.div_ceil(t) {
         for j in 0..height.div_ceil(t) {
-            tiles.push(Tile::new(Point2::new(
-                i * tile_sizes[0],
-                j * tile_sizes[0],
-            )));
+            let corner = Point2::new(i * tile_sizes[0], j * tile_sizes[0]);
+            if regions
+                .is_none_or(|rs| rs.iter().any(|r| r.intersects(corner, t)))
+            {
+                tiles.push(Tile::new(corner));
+            }
         }
     }
 
-    let mut rh = RenderHandle::new(shape);
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+        "render_tiles",
+        width,
+        height,
+        tiles = tiles.len(),
+        tape_len = rh.size(),
+    )
+    .entered();
 
     let _ = rh.i_tape(&mut vec![]); // populate i_tape before cloning
-    let init = || {
-        let rh = rh.clone();
-        let worker = W::new(config);
-        (worker, rh)
+    let new_worker = || {
+        let mut worker = W::new(config);
+        *worker.metrics() = MetricsRecorder::new(metrics);
+        worker
     };
+    let init = || (new_worker(), rh.clone());
 
-    match config.threads() {
+    let mut out = PipelineMetrics::default();
+    let tiles = match config.threads() {
         None => {
-            let mut worker = W::new(config);
-            tiles
+            let mut worker = new_worker();
+            let tiles = tiles
                 .into_iter()
                 .map(|tile| {
                     if config.is_cancelled() {
                         Err(())
                     } else {
+                        let _guard =
+                            config.flush_denormals().then(FlushDenormals::new);
                         let pixels = worker.render_tile(&mut rh, vars, tile);
                         Ok((tile, pixels))
                     }
                 })
                 .collect::<Result<Vec<_>, ()>>()
-                .ok()
+                .ok()?;
+            if let Some(m) = worker.metrics().take() {
+                out.record(0, &m);
+            }
+            tiles
         }
 
-        Some(p) => p.run(|| {
+        Some(p) => {
+            let tiles = p.run(|| {
+                tiles
+                    .into_par_iter()
+                    .map_init(init, |(w, rh), tile| {
+                        if config.is_cancelled() {
+                            Err(())
+                        } else {
+                            let _guard = config
+                                .flush_denormals()
+                                .then(FlushDenormals::new);
+                            let pixels = w.render_tile(rh, vars, tile);
+                            let m = w.metrics().take().map(|m| {
+                                (rayon::current_thread_index().unwrap_or(0), m)
+                            });
+                            Ok((tile, pixels, m))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, ()>>()
+                    .ok()
+            })?;
             tiles
-                .into_par_iter()
-                .map_init(init, |(w, rh), tile| {
-                    if config.is_cancelled() {
-                        Err(())
-                    } else {
-                        let pixels = w.render_tile(rh, vars, tile);
-                        Ok((tile, pixels))
+                .into_iter()
+                .map(|(tile, pixels, m)| {
+                    if let Some((thread, m)) = m {
+                        out.record(thread, &m);
                     }
+                    (tile, pixels)
                 })
-                .collect::<Result<Vec<_>, ()>>()
-                .ok()
-        }),
-    }
+                .collect()
+        }
+    };
+    Some((tiles, out))
 }
 
 /// Helper trait for tiled rendering configuration
@@ -156,6 +258,7 @@ pub(crate) trait RenderConfig {
     fn tile_sizes(&self) -> TileSizesRef<'_>;
     fn threads(&self) -> Option<&ThreadPool>;
     fn is_cancelled(&self) -> bool;
+    fn flush_denormals(&self) -> bool;
 }
 
 /// Helper trait for a tiled renderer worker
@@ -168,6 +271,9 @@ pub(crate) trait RenderWorker<'a, F: Function, T> {
     /// Workers are typically built on a per-thread basis
     fn new(cfg: &'a Self::Config) -> Self;
 
+    /// Returns the worker's metrics recorder
+    fn metrics(&mut self) -> &mut MetricsRecorder;
+
     /// Render a single tile, returning a worker-dependent output
     fn render_tile(
         &mut self,
@@ -295,6 +401,13 @@ impl<P, S: Clone> Image<P, S> {
     pub fn take(self) -> (Vec<P>, S) {
         (self.data, self.size)
     }
+
+    /// Returns the number of heap bytes allocated for pixel data
+    ///
+    /// This is based on allocated capacity, so it includes any unused space.
+    pub fn memory_usage(&self) -> usize {
+        self.data.capacity() * std::mem::size_of::<P>()
+    }
 }
 
 impl<P, S: ImageSizeLike> Image<P, S> {
@@ -1,12 +1,14 @@
 //! 2D bitmap rendering / rasterization
 use super::RenderHandle;
 use crate::{
-    Image, RenderConfig, RenderWorker, TileSizesRef,
+    Image, ImageRegion, PixelWriter, RenderConfig, RenderWorker, TileSizesRef,
     config::{ImageRenderConfig, Tile},
+    in_regions,
 };
 use fidget_core::{
     eval::Function,
-    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars},
+    render::{MetricsRecorder, PipelineMetrics, Stage},
+    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars, Transformed},
     types::Interval,
 };
 use nalgebra::{Point2, Vector2};
@@ -111,10 +113,21 @@ impl From<f32> for DistancePixel {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Rendered root tile
+struct TileData {
+    pixels: Image<DistancePixel>,
+
+    /// Per-pixel coverage, or empty if anti-aliasing is disabled
+    coverage: Vec<f32>,
+}
+
 /// Per-thread worker
 struct Worker<'a, F: Function> {
     tile_sizes: TileSizesRef<'a>,
     pixel_perfect: bool,
+
+    /// Samples per axis for anti-aliasing, or 0 if it's disabled
+    anti_alias: usize,
     scratch: Scratch,
 
     eval_float_slice: ShapeBulkEval<F::FloatSliceEval>,
@@ -129,30 +142,51 @@ struct Worker<'a, F: Function> {
     /// Workspace for shape simplification
     workspace: F::Workspace,
 
+    /// Per-stage timing (disabled unless metrics are requested)
+    metrics: MetricsRecorder,
+
     /// Tile being rendered
     ///
     /// This is a root tile, i.e. width and height of `config.tile_sizes[0]`
     image: Image<DistancePixel>,
+
+    /// Coverage of each pixel in the tile being rendered
+    ///
+    /// This is only populated if anti-aliasing is enabled
+    coverage: Vec<f32>,
 }
 
 impl<'a, F: Function, T> RenderWorker<'a, F, T> for Worker<'a, F> {
     type Config = ImageRenderConfig<'a>;
-    type Output = Image<DistancePixel>;
+    type Output = TileData;
     fn new(cfg: &'a Self::Config) -> Self {
         let tile_sizes = cfg.tile_sizes();
+        let anti_alias = match cfg.anti_alias {
+            0 | 1 => 0,
+            n => n as usize,
+        };
         Worker::<F> {
-            scratch: Scratch::new(tile_sizes.last().pow(2)),
+            scratch: Scratch::new(
+                tile_sizes.last().pow(2) * anti_alias.max(1).pow(2),
+            ),
             pixel_perfect: cfg.pixel_perfect,
+            anti_alias,
             image: Default::default(),
+            coverage: vec![],
             tile_sizes,
             eval_float_slice: Default::default(),
             eval_interval: Default::default(),
             tape_storage: vec![],
             shape_storage: vec![],
             workspace: Default::default(),
+            metrics: Default::default(),
         }
     }
 
+    fn metrics(&mut self) -> &mut MetricsRecorder {
+        &mut self.metrics
+    }
+
     fn render_tile(
         &mut self,
         shape: &mut RenderHandle<F, T>,
@@ -160,8 +194,14 @@ impl<'a, F: Function, T> RenderWorker<'a, F, T> for Worker<'a, F> {
         tile: super::config::Tile<2>,
     ) -> Self::Output {
         self.image = Image::new((self.tile_sizes[0] as u32).into());
+        if self.anti_alias > 0 {
+            self.coverage = vec![0.0; self.tile_sizes[0].pow(2)];
+        }
         self.render_tile_recurse(shape, vars, 0, tile);
-        std::mem::take(&mut self.image)
+        TileData {
+            pixels: std::mem::take(&mut self.image),
+            coverage: std::mem::take(&mut self.coverage),
+        }
     }
 }
 
@@ -175,6 +215,16 @@ impl<F: Function> Worker<'_, F> {
     ) {
         let tile_size = self.tile_sizes[depth];
 
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "render_tile",
+            x = tile.corner.x,
+            y = tile.corner.y,
+            depth,
+            tape_len = shape.size(),
+        )
+        .entered();
+
         // Find the interval bounds of the region, in screen coordinates
         let base = Point2::from(tile.corner).cast::<f32>();
         let x = Interval::new(base.x, base.x + tile_size as f32);
@@ -182,10 +232,14 @@ impl<F: Function> Worker<'_, F> {
         let z = Interval::new(0.0, 0.0);
 
         // The shape applies the screen-to-model transform
-        let (i, simplify) = self
-            .eval_interval
-            .eval_v(shape.i_tape(&mut self.tape_storage), x, y, z, vars)
-            .unwrap();
+        let t = self.metrics.start();
+        let tape = shape.i_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let (i, simplify) =
+            self.eval_interval.eval_v(tape, x, y, z, vars).unwrap();
+        self.metrics.record(Stage::IntervalEval, t);
 
         if !self.pixel_perfect {
             let pixel = if i.upper() < 0.0 {
@@ -203,23 +257,30 @@ impl<F: Function> Worker<'_, F> {
             };
             if let Some(pixel) = pixel {
                 let fill = pixel.into();
+                let coverage = if pixel.inside { 1.0 } else { 0.0 };
                 for y in 0..tile_size {
                     let start = self
                         .tile_sizes
                         .pixel_offset(tile.add(Vector2::new(0, y)));
                     self.image[start..][..tile_size].fill(fill);
+                    if self.anti_alias > 0 {
+                        self.coverage[start..][..tile_size].fill(coverage);
+                    }
                 }
                 return;
             }
         }
 
         let sub_tape = if let Some(trace) = simplify.as_ref() {
-            shape.simplify(
+            let t = self.metrics.start();
+            let s = shape.simplify(
                 trace,
                 &mut self.workspace,
                 &mut self.shape_storage,
                 &mut self.tape_storage,
-            )
+            );
+            self.metrics.record(Stage::Simplify, t);
+            s
         } else {
             shape
         };
@@ -259,16 +320,22 @@ impl<F: Function> Worker<'_, F> {
             }
         }
 
+        let t = self.metrics.start();
+        let tape = shape.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
         let out = self
             .eval_float_slice
             .eval_v(
-                shape.f_tape(&mut self.tape_storage),
+                tape,
                 &self.scratch.x,
                 &self.scratch.y,
                 &self.scratch.z,
                 vars,
             )
             .unwrap();
+        self.metrics.record(Stage::Fill, t);
 
         let mut index = 0;
         for j in 0..tile_size {
@@ -278,6 +345,68 @@ impl<F: Function> Worker<'_, F> {
                 index += 1;
             }
         }
+
+        if self.anti_alias > 0 {
+            self.render_tile_coverage(shape, vars, tile_size, tile);
+        }
+    }
+
+    /// Computes the coverage of each pixel in a tile by supersampling
+    ///
+    /// Pixel `(i, j)` covers the square from `(i, j)` to `(i + 1, j + 1)` in
+    /// screen coordinates (matching the bounds used for interval evaluation),
+    /// which is sampled on a regular grid.
+    fn render_tile_coverage<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        tile_size: usize,
+        tile: Tile<2>,
+    ) {
+        let n = self.anti_alias;
+        let mut index = 0;
+        for j in 0..tile_size {
+            for i in 0..tile_size {
+                for sj in 0..n {
+                    for si in 0..n {
+                        let dx = (si as f32 + 0.5) / n as f32;
+                        let dy = (sj as f32 + 0.5) / n as f32;
+                        self.scratch.x[index] =
+                            (tile.corner[0] + i) as f32 + dx;
+                        self.scratch.y[index] =
+                            (tile.corner[1] + j) as f32 + dy;
+                        index += 1;
+                    }
+                }
+            }
+        }
+
+        let t = self.metrics.start();
+        let tape = shape.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let out = self
+            .eval_float_slice
+            .eval_v(
+                tape,
+                &self.scratch.x[..index],
+                &self.scratch.y[..index],
+                &self.scratch.z[..index],
+                vars,
+            )
+            .unwrap();
+        self.metrics.record(Stage::Fill, t);
+
+        let mut samples = out.chunks(n * n);
+        for j in 0..tile_size {
+            let o = self.tile_sizes.pixel_offset(tile.add(Vector2::new(0, j)));
+            for i in 0..tile_size {
+                let s = samples.next().unwrap();
+                let inside = s.iter().filter(|v| **v < 0.0).count();
+                self.coverage[o + i] = inside as f32 / s.len() as f32;
+            }
+        }
     }
 }
 
@@ -292,52 +421,91 @@ impl<F: Function> Worker<'_, F> {
 /// This function is parameterized by shape type (which determines how we
 /// perform evaluation).
 ///
-/// Returns an `Image<DistancePixel>` of pixel data if rendering succeeds, or
-/// `None` if rendering was cancelled (using the [`ImageRenderConfig::cancel`]
-/// token)
-pub fn render<F: Function>(
+/// Pixels are converted by the [`PixelWriter`] as tiles are assembled into the
+/// final image.
+///
+/// Returns an image of pixel data if rendering succeeds, or `None` if rendering
+/// was cancelled (using the [`ImageRenderConfig::cancel`] token).  Metrics are
+/// only accumulated if `metrics` is true.
+pub fn render<F: Function, W: PixelWriter>(
     shape: Shape<F>,
     vars: &ShapeVars<f32>,
     config: &ImageRenderConfig,
-) -> Option<Image<DistancePixel>> {
-    // Convert to a 4x4 matrix and apply to the shape
-    let mat = config.mat();
-    let mat = mat.insert_row(2, 0.0);
-    let mat = mat.insert_column(2, 0.0);
-    let shape = shape.with_transform(mat);
-
-    let tiles =
-        super::render_tiles::<F, Worker<F>, _>(shape.clone(), vars, config)?;
+    writer: &W,
+    metrics: bool,
+) -> Option<(Image<W::Output>, PipelineMetrics)> {
+    // Convert to a 4x4 matrix (including the slice plane) and apply it to the
+    // shape
+    let rh = RenderHandle::new(shape.with_transform(config.mat3d()));
+    let mut image = Image::new(config.image_size);
+    let metrics =
+        render_into(rh, vars, config, writer, None, &mut image, metrics)?;
+    Some((image, metrics))
+}
+
+/// Renders a shape from a [`RenderHandle`] into an existing image
+///
+/// The handle's transform is used instead of [`ImageRenderConfig::mat3d`].  If
+/// `regions` is provided, then only pixels within those regions are written;
+/// the rest of the image is left untouched.
+///
+/// Returns `None` if rendering was cancelled, in which case the image is not
+/// modified.
+pub(crate) fn render_into<F: Function, W: PixelWriter>(
+    rh: RenderHandle<F, Transformed>,
+    vars: &ShapeVars<f32>,
+    config: &ImageRenderConfig,
+    writer: &W,
+    regions: Option<&[ImageRegion]>,
+    image: &mut Image<W::Output>,
+    metrics: bool,
+) -> Option<PipelineMetrics> {
+    assert_eq!(image.size(), config.image_size, "image size mismatch");
+    let start = metrics.then(std::time::Instant::now);
+    let (tiles, mut metrics) = super::render_tiles::<F, Worker<F>, _>(
+        rh, vars, config, regions, metrics,
+    )?;
     let tile_sizes = config.tile_sizes();
 
     let width = config.image_size.width() as usize;
     let height = config.image_size.height() as usize;
-    let mut image = Image::new(config.image_size);
     for (tile, data) in tiles.iter() {
         let mut index = 0;
         for j in 0..tile_sizes[0] {
             let y = j + tile.corner.y;
             for i in 0..tile_sizes[0] {
                 let x = i + tile.corner.x;
-                if y < height && x < width {
-                    image[(y, x)] = data[index];
+                if y < height && x < width && in_regions(regions, x, y) {
+                    let p = data.pixels[index];
+                    image[(y, x)] = match data.coverage.get(index) {
+                        Some(&c) => writer.write_coverage(p, c),
+                        None => writer.write(p),
+                    };
                 }
                 index += 1;
             }
         }
     }
-    Some(image)
+    if let Some(start) = start {
+        metrics.wall_time = start.elapsed();
+    }
+    Some(metrics)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use fidget_core::{
-        Context, render::ImageSize, shape::Shape, vm::VmFunction,
+        Context,
+        render::{ImageSize, ThreadPool},
+        shape::Shape,
+        vm::VmFunction,
     };
 
-    const HI: &str =
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../models/hi.vm"));
+    const HI: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../fidget/models/hi.vm"
+    ));
 
     #[test]
     fn render2d_cancel() {
@@ -353,4 +521,338 @@ mod test {
         let out = cfg.run(shape);
         assert!(out.is_none());
     }
+
+    #[test]
+    fn render2d_memory_usage() {
+        use crate::{DistancePixel, GeometryBuffer, GeometryPixel};
+        use fidget_core::render::VoxelSize;
+
+        let (ctx, root) = Context::from_text(HI.as_bytes()).unwrap();
+        let shape = Shape::<VmFunction>::new(&ctx, root).unwrap();
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(64, 48),
+            ..Default::default()
+        };
+        let image = cfg.run(shape).unwrap();
+        assert_eq!(
+            image.memory_usage(),
+            64 * 48 * std::mem::size_of::<DistancePixel>()
+        );
+
+        let buf = GeometryBuffer::new(VoxelSize::new(16, 8, 4));
+        assert_eq!(
+            buf.memory_usage(),
+            16 * 8 * std::mem::size_of::<GeometryPixel>()
+        );
+    }
+
+    #[test]
+    fn render2d_writer() {
+        use crate::{DistanceF32Writer, MaskWriter, RgbaWriter};
+        let (ctx, root) = Context::from_text(HI.as_bytes()).unwrap();
+        let shape = Shape::<VmFunction>::new(&ctx, root).unwrap();
+
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(100, 80),
+            ..Default::default()
+        };
+        let vars = ShapeVars::new();
+        let expected = cfg.run(shape.clone()).unwrap();
+        let mask = cfg
+            .run_with_writer(shape.clone(), &vars, &MaskWriter)
+            .unwrap();
+        let rgba = cfg
+            .run_with_writer(shape.clone(), &vars, &RgbaWriter::default())
+            .unwrap();
+        let dist = cfg
+            .run_with_writer(shape, &vars, &DistanceF32Writer)
+            .unwrap();
+        assert_eq!(mask.size(), expected.size());
+        for (i, p) in expected.iter().enumerate() {
+            assert_eq!(mask[i] == 255, p.inside());
+            assert_eq!(rgba[i] == [255; 4], p.inside());
+            assert_eq!(dist[i] < 0.0, p.inside());
+        }
+    }
+
+    #[test]
+    fn render2d_regions() {
+        use crate::{DistanceWriter, ImageRegion};
+        use fidget_core::{context::Tree, render::TileSizes, vm::VmShape};
+
+        let (x, y, _) = Tree::axes();
+        let a = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+        let b = VmShape::from((x - 0.2).abs().max(y.abs()) - 0.6);
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(100, 80),
+            tile_sizes: TileSizes::new(&[32, 8]).unwrap(),
+            ..Default::default()
+        };
+        let vars = ShapeVars::new();
+        let expected_a = cfg.run(a.clone()).unwrap();
+        let expected_b = cfg.run(b.clone()).unwrap();
+
+        let regions = [
+            ImageRegion::new(10, 5, 30, 20),
+            ImageRegion::new(70, 60, 50, 50),
+        ];
+        let mut image = cfg.run(a).unwrap();
+        cfg.run_regions(b, &vars, &DistanceWriter, &regions, &mut image)
+            .unwrap();
+        for y in 0..80 {
+            for x in 0..100 {
+                let expected = if regions.iter().any(|r| r.contains(x, y)) {
+                    expected_b[(y, x)]
+                } else {
+                    expected_a[(y, x)]
+                };
+                assert_eq!(image[(y, x)].inside(), expected.inside());
+            }
+        }
+    }
+
+    #[test]
+    fn render2d_cached() {
+        use fidget_core::{context::Tree, render::TapeCache, var::Var};
+
+        let (x, y, _) = Tree::axes();
+        let v = Var::new();
+        let shape = fidget_core::vm::VmShape::from(
+            (x.square() + y.square()).sqrt() - Tree::from(v),
+        );
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(64, 64),
+            ..Default::default()
+        };
+        let mut cache = TapeCache::new();
+        for r in [0.25, 0.5, 0.75] {
+            let mut vars = ShapeVars::new();
+            vars.insert(v.index().unwrap(), r);
+            let a = cfg.run_cached(&shape, &vars, &mut cache).unwrap();
+            let b = cfg.run_with_vars(shape.clone(), &vars).unwrap();
+            assert!(
+                a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| a.inside() == b.inside())
+            );
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn render2d_cached_view() {
+        use fidget_core::{context::Tree, render::TapeCache, vm::VmShape};
+        use nalgebra::{Matrix3, Vector2};
+
+        // An off-center rectangle, which changes when rotated
+        let (x, y, _) = Tree::axes();
+        let shape = VmShape::from((x - 0.2).abs().max(y.abs() * 2.0) - 0.4);
+        let vars = ShapeVars::new();
+        let mut cache = TapeCache::new();
+        for (i, angle) in [0.0f32, 0.3, 1.2, 2.5].into_iter().enumerate() {
+            let cfg = ImageRenderConfig {
+                image_size: ImageSize::new(64, 48),
+                world_to_model: Matrix3::new_translation(&Vector2::new(
+                    0.1 * i as f32,
+                    -0.05,
+                )) * Matrix3::new_rotation(angle)
+                    * Matrix3::new_scaling(0.8 + 0.1 * i as f32),
+                ..Default::default()
+            };
+            let a = cfg.run_cached(&shape, &vars, &mut cache).unwrap();
+            let b = cfg.run(shape.clone()).unwrap();
+            assert!(
+                a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| a.inside() == b.inside())
+            );
+            assert!(a.iter().any(|p| p.inside()));
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn render2d_metrics() {
+        let (ctx, root) = Context::from_text(HI.as_bytes()).unwrap();
+        let shape = Shape::<VmFunction>::new(&ctx, root).unwrap();
+
+        for threads in [None, Some(&ThreadPool::Global)] {
+            let cfg = ImageRenderConfig {
+                image_size: ImageSize::new(256, 256),
+                threads,
+                ..Default::default()
+            };
+            let (image, metrics) = cfg
+                .run_with_metrics(shape.clone(), &ShapeVars::new())
+                .unwrap();
+            let total = metrics.total();
+            assert!(total.interval_eval.count > 0);
+            assert!(total.simplify.count > 0);
+            assert!(total.fill.count > 0);
+            assert!(!metrics.threads.is_empty());
+
+            let expected = cfg.run(shape.clone()).unwrap();
+            let key = |p: &DistancePixel| {
+                p.distance().map_err(|f| (f.inside, f.depth))
+            };
+            assert!(
+                image
+                    .iter()
+                    .zip(expected.iter())
+                    .all(|(a, b)| key(a) == key(b))
+            );
+        }
+    }
+
+    #[test]
+    fn render2d_slice() {
+        use crate::SlicePlane;
+        use fidget_core::context::Tree;
+        use nalgebra::{Point3, Vector3};
+
+        // Slicing a sphere at z = 0.6 gives a circle of radius 0.8
+        let (x, y, z) = Tree::axes();
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+        let (x, y, _) = Tree::axes();
+        let circle = (x.square() + y.square()).sqrt() - 0.8;
+
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(64, 64),
+            slice: Some(SlicePlane::new(
+                Point3::new(0.0, 0.0, 0.6),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            )),
+            ..Default::default()
+        };
+        let sliced = cfg.run(Shape::<VmFunction>::from(sphere)).unwrap();
+        let cfg = ImageRenderConfig { slice: None, ..cfg };
+        let expected = cfg.run(Shape::<VmFunction>::from(circle)).unwrap();
+        let inside = |img: &Image<DistancePixel>| {
+            img.iter().map(|p| p.inside()).collect::<Vec<_>>()
+        };
+        assert_eq!(inside(&sliced), inside(&expected));
+        assert!(sliced.iter().any(|p| p.inside()));
+        assert!(sliced.iter().any(|p| !p.inside()));
+    }
+
+    #[test]
+    fn render2d_anti_alias() {
+        use crate::{CoverageWriter, MaskWriter};
+        use fidget_core::{context::Tree, vm::VmShape};
+
+        let (x, y, _) = Tree::axes();
+        let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+        let vars = ShapeVars::new();
+
+        // Without anti-aliasing, coverage is binary
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(64, 64),
+            ..Default::default()
+        };
+        let expected = cfg.run(shape.clone()).unwrap();
+        let c = cfg
+            .run_with_writer(shape.clone(), &vars, &CoverageWriter)
+            .unwrap();
+        for (c, p) in c.iter().zip(expected.iter()) {
+            assert_eq!(*c, if p.inside() { 1.0 } else { 0.0 });
+        }
+
+        // The circle has a radius of 16 pixels
+        let area = std::f32::consts::PI * 16.0f32.powi(2);
+        for pixel_perfect in [false, true] {
+            let cfg = ImageRenderConfig {
+                image_size: ImageSize::new(64, 64),
+                anti_alias: 8,
+                pixel_perfect,
+                ..Default::default()
+            };
+            let c = cfg
+                .run_with_writer(shape.clone(), &vars, &CoverageWriter)
+                .unwrap();
+            let total: f32 = c.iter().sum();
+            assert!((total - area).abs() < area * 0.01, "{total} != {area}");
+            assert!(c.iter().any(|c| *c > 0.0 && *c < 1.0));
+            assert!(c.iter().all(|c| (0.0..=1.0).contains(c)));
+            assert_eq!(c[(32, 32)], 1.0);
+            assert_eq!(c[(0, 0)], 0.0);
+
+            let mask = cfg
+                .run_with_writer(shape.clone(), &vars, &MaskWriter)
+                .unwrap();
+            for (m, c) in mask.iter().zip(c.iter()) {
+                assert_eq!(*m, (c * 255.0).round() as u8);
+            }
+
+            // Distance values are unchanged by anti-aliasing
+            let d = cfg.run(shape.clone()).unwrap();
+            let e = ImageRenderConfig {
+                anti_alias: 0,
+                ..cfg
+            }
+            .run(shape.clone())
+            .unwrap();
+            assert!(
+                d.iter()
+                    .zip(e.iter())
+                    .all(|(a, b)| a.distance().ok() == b.distance().ok())
+            );
+        }
+    }
+
+    #[test]
+    fn render2d_distance_field() {
+        use fidget_core::{context::Tree, vm::VmShape};
+        use nalgebra::Point3;
+
+        let (x, y, _) = Tree::axes();
+        let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(96, 64),
+            ..Default::default()
+        };
+        let sdf = cfg.run_distance_field(shape, &ShapeVars::new()).unwrap();
+        let mat = cfg.mat3d();
+        for j in 0..sdf.height() {
+            for i in 0..sdf.width() {
+                let p =
+                    mat.transform_point(&Point3::new(i as f32, j as f32, 0.0));
+                let expected = (p.x.powi(2) + p.y.powi(2)).sqrt() - 0.5;
+                let d = sdf[(j, i)];
+                assert!((d - expected).abs() < 1e-5, "{d} != {expected}");
+            }
+        }
+        assert!(!cfg.pixel_perfect);
+    }
+
+    #[test]
+    fn render2d_flush_denormals() {
+        use fidget_core::{context::Tree, eval::FlushDenormals};
+        let shape = Shape::<VmFunction>::from(Tree::x() * 1e-38);
+
+        for threads in [None, Some(&ThreadPool::Global)] {
+            for flush_denormals in [false, true] {
+                let cfg = ImageRenderConfig {
+                    image_size: ImageSize::new(32, 32),
+                    pixel_perfect: true,
+                    threads,
+                    flush_denormals,
+                    ..Default::default()
+                };
+                let image = cfg.run(shape.clone()).unwrap();
+                let denormal = image.iter().any(|p| {
+                    let d = p.distance().unwrap();
+                    d != 0.0 && d.abs() < f32::MIN_POSITIVE
+                });
+                assert_eq!(
+                    denormal,
+                    !(flush_denormals && FlushDenormals::is_supported())
+                );
+            }
+        }
+
+        // The calling thread's floating-point state is restored afterwards
+        let tiny = std::hint::black_box(f32::MIN_POSITIVE);
+        assert!(tiny * std::hint::black_box(0.5) > 0.0);
+    }
 }
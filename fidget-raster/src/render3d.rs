@@ -1,13 +1,15 @@
 //! 3D bitmap rendering / rasterization
 use super::RenderHandle;
 use crate::{
-    GeometryBuffer, GeometryPixel, RenderConfig, RenderWorker, TileSizesRef,
-    VoxelSize,
+    ColorImage, GeometryBuffer, GeometryPixel, ImageRegion, ImageSize,
+    RenderConfig, RenderWorker, TileSizesRef, VoxelSize,
     config::{Tile, VoxelRenderConfig},
+    in_regions,
 };
 use fidget_core::{
     eval::Function,
-    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars},
+    render::{MetricsRecorder, PipelineMetrics, Stage, ThreadPool},
+    shape::{Shape, ShapeBulkEval, ShapeTracingEval, ShapeVars, Transformed},
     types::{Grad, Interval},
 };
 
@@ -64,6 +66,9 @@ struct Worker<'a, F: Function> {
     shape_storage: Vec<F::Storage>,
     workspace: F::Workspace,
 
+    /// Per-stage timing (disabled unless metrics are requested)
+    metrics: MetricsRecorder,
+
     /// Output images for this specific tile
     out: GeometryBuffer,
 }
@@ -89,9 +94,14 @@ impl<'a, F: Function, T> RenderWorker<'a, F, T> for Worker<'a, F> {
             tape_storage: vec![],
             shape_storage: vec![],
             workspace: Default::default(),
+            metrics: Default::default(),
         }
     }
 
+    fn metrics(&mut self) -> &mut MetricsRecorder {
+        &mut self.metrics
+    }
+
     fn render_tile(
         &mut self,
         shape: &mut RenderHandle<F, T>,
@@ -131,6 +141,17 @@ impl<F: Function> Worker<'_, F> {
         depth: usize,
         tile: Tile<3>,
     ) -> bool {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(
+            "render_tile",
+            x = tile.corner.x,
+            y = tile.corner.y,
+            z = tile.corner.z,
+            depth,
+            tape_len = shape.size(),
+        )
+        .entered();
+
         // Early exit if every single pixel is filled
         let tile_size = self.tile_sizes[depth];
         let fill_z = (tile.corner[2] + tile_size + 1) as f32;
@@ -146,10 +167,14 @@ impl<F: Function> Worker<'_, F> {
         let y = Interval::new(base.y, base.y + tile_size as f32);
         let z = Interval::new(base.z, base.z + tile_size as f32);
 
-        let (i, trace) = self
-            .eval_interval
-            .eval_v(shape.i_tape(&mut self.tape_storage), x, y, z, vars)
-            .unwrap();
+        let t = self.metrics.start();
+        let tape = shape.i_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let (i, trace) =
+            self.eval_interval.eval_v(tape, x, y, z, vars).unwrap();
+        self.metrics.record(Stage::IntervalEval, t);
 
         // Return early if this tile is completely empty or full, returning
         // `data_interval` to scratch memory for reuse.
@@ -167,12 +192,15 @@ impl<F: Function> Worker<'_, F> {
 
         // Calculate a simplified tape based on the trace
         let sub_tape = if let Some(trace) = trace.as_ref() {
-            shape.simplify(
+            let t = self.metrics.start();
+            let s = shape.simplify(
                 trace,
                 &mut self.workspace,
                 &mut self.shape_storage,
                 &mut self.tape_storage,
-            )
+            );
+            self.metrics.record(Stage::Simplify, t);
+            s
         } else {
             shape
         };
@@ -251,16 +279,22 @@ impl<F: Function> Worker<'_, F> {
         let size = index;
         assert!(size > 0);
 
+        let t = self.metrics.start();
+        let tape = shape.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
         let out = self
             .eval_float_slice
             .eval_v(
-                shape.f_tape(&mut self.tape_storage),
+                tape,
                 &self.scratch.x[..index],
                 &self.scratch.y[..index],
                 &self.scratch.z[..index],
                 vars,
             )
             .unwrap();
+        self.metrics.record(Stage::Fill, t);
 
         // We're iterating over a few things simultaneously
         // - col refers to the xy position in the tile
@@ -310,16 +344,22 @@ impl<F: Function> Worker<'_, F> {
         }
 
         if grad > 0 {
+            let t = self.metrics.start();
+            let tape = shape.g_tape(&mut self.tape_storage);
+            self.metrics.record(Stage::TapeBuild, t);
+
+            let t = self.metrics.start();
             let out = self
                 .eval_grad_slice
                 .eval_v(
-                    shape.g_tape(&mut self.tape_storage),
+                    tape,
                     &self.scratch.xg[..grad],
                     &self.scratch.yg[..grad],
                     &self.scratch.zg[..grad],
                     vars,
                 )
                 .unwrap();
+            self.metrics.record(Stage::Fill, t);
 
             for (index, o) in self.scratch.columns[0..grad].iter().enumerate() {
                 let g = out[index];
@@ -341,46 +381,377 @@ impl<F: Function> Worker<'_, F> {
 /// perform evaluation.
 ///
 /// Returns a [`GeometryBuffer`] of pixels, or `None` if rendering was cancelled
-/// (using the [`VoxelRenderConfig::cancel`] token)
+/// (using the [`VoxelRenderConfig::cancel`] token).  Metrics are only
+/// accumulated if `metrics` is true.
 pub fn render<F: Function>(
     shape: Shape<F>,
     vars: &ShapeVars<f32>,
     config: &VoxelRenderConfig,
-) -> Option<GeometryBuffer> {
-    let shape = shape.with_transform(config.mat());
+    metrics: bool,
+) -> Option<(GeometryBuffer, PipelineMetrics)> {
+    let rh = RenderHandle::new(shape.with_transform(config.mat()));
+    render_handle(rh, vars, config, metrics)
+}
+
+/// Renders a shape from a [`RenderHandle`] with a transform already applied
+///
+/// The handle's transform is used instead of [`VoxelRenderConfig::mat`], so
+/// that callers can render from different viewpoints while reusing tapes.
+pub(crate) fn render_handle<F: Function>(
+    rh: RenderHandle<F, Transformed>,
+    vars: &ShapeVars<f32>,
+    config: &VoxelRenderConfig,
+    metrics: bool,
+) -> Option<(GeometryBuffer, PipelineMetrics)> {
+    let mut image = GeometryBuffer::new(config.image_size);
+    let metrics =
+        render_handle_into(rh, vars, config, None, &mut image, metrics)?;
+    Some((image, metrics))
+}
 
-    let tiles = super::render_tiles::<F, Worker<F>, _>(shape, vars, config)?;
+/// Renders a shape from a [`RenderHandle`] into an existing image
+///
+/// If `regions` is provided, then only pixels within those regions are
+/// written; the rest of the image is left untouched.
+///
+/// Returns `None` if rendering was cancelled, in which case the image is not
+/// modified.
+pub(crate) fn render_handle_into<F: Function>(
+    rh: RenderHandle<F, Transformed>,
+    vars: &ShapeVars<f32>,
+    config: &VoxelRenderConfig,
+    regions: Option<&[ImageRegion]>,
+    image: &mut GeometryBuffer,
+    metrics: bool,
+) -> Option<PipelineMetrics> {
+    assert_eq!(image.size(), config.image_size, "image size mismatch");
+    let start = metrics.then(std::time::Instant::now);
+    let (tiles, mut metrics) = super::render_tiles::<F, Worker<F>, _>(
+        rh, vars, config, regions, metrics,
+    )?;
     let tile_sizes = config.tile_sizes();
 
     let width = config.image_size.width() as usize;
     let height = config.image_size.height() as usize;
-    let mut image = GeometryBuffer::new(config.image_size);
     for (tile, out) in tiles {
         let mut index = 0;
         for j in 0..tile_sizes[0] {
             let y = j + tile.corner.y;
             for i in 0..tile_sizes[0] {
                 let x = i + tile.corner.x;
-                if x < width && y < height {
+                if x < width && y < height && in_regions(regions, x, y) {
                     let o = y * width + x;
-                    if out[index].depth >= image[o].depth {
-                        // Clamp voxels to the image depth
-                        let d = (config.image_size.depth() - 1) as f32;
-                        if out[index].depth >= d {
-                            image[o] = GeometryPixel {
-                                depth: d + 1.0,
-                                normal: [0.0, 0.0, 1.0],
-                            };
-                        } else {
-                            image[o] = out[index];
-                        }
+                    // Clamp voxels to the image depth
+                    let d = (config.image_size.depth() - 1) as f32;
+                    if out[index].depth >= d {
+                        image[o] = GeometryPixel {
+                            depth: d + 1.0,
+                            normal: [0.0, 0.0, 1.0],
+                        };
+                    } else {
+                        image[o] = out[index];
                     }
                 }
                 index += 1;
             }
         }
     }
-    Some(image)
+    if let Some(start) = start {
+        metrics.wall_time = start.elapsed();
+    }
+    Some(metrics)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Configuration for surface color evaluation
+///
+/// This borrows the 3D render configuration (for tile sizes, threads, etc) and
+/// the geometry buffer whose surface is being colored.
+struct ColorRenderConfig<'a> {
+    config: &'a VoxelRenderConfig<'a>,
+    geometry: &'a GeometryBuffer,
+}
+
+impl RenderConfig for ColorRenderConfig<'_> {
+    fn width(&self) -> u32 {
+        self.config.width()
+    }
+    fn height(&self) -> u32 {
+        self.config.height()
+    }
+    fn threads(&self) -> Option<&ThreadPool> {
+        self.config.threads()
+    }
+    fn tile_sizes(&self) -> TileSizesRef<'_> {
+        self.config.tile_sizes()
+    }
+    fn is_cancelled(&self) -> bool {
+        self.config.is_cancelled()
+    }
+    fn flush_denormals(&self) -> bool {
+        self.config.flush_denormals()
+    }
+}
+
+/// Worker which evaluates a single color channel at surface pixels
+///
+/// Tiles are subdivided in 2D; at each level, the channel's tape is simplified
+/// using interval evaluation over the bounding box of surface hits within the
+/// tile, so only branches which are active near the visible surface are
+/// evaluated per-pixel.
+struct ColorWorker<'a, F: Function> {
+    tile_sizes: TileSizesRef<'a>,
+    geometry: &'a GeometryBuffer,
+
+    /// Reusable workspace for evaluation, to minimize allocation
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+
+    /// Output offsets for each point in `x`, `y`, `z`
+    offsets: Vec<usize>,
+
+    eval_float_slice: ShapeBulkEval<F::FloatSliceEval>,
+    eval_interval: ShapeTracingEval<F::IntervalEval>,
+
+    tape_storage: Vec<F::TapeStorage>,
+    shape_storage: Vec<F::Storage>,
+    workspace: F::Workspace,
+
+    /// Per-stage timing (disabled unless metrics are requested)
+    metrics: MetricsRecorder,
+
+    /// Output values for this specific tile (`NaN` for empty pixels)
+    out: Vec<f32>,
+}
+
+impl<'a, F: Function, T> RenderWorker<'a, F, T> for ColorWorker<'a, F> {
+    type Config = ColorRenderConfig<'a>;
+    type Output = Vec<f32>;
+
+    fn new(cfg: &'a Self::Config) -> Self {
+        let tile_sizes = cfg.config.tile_sizes();
+        let size2 = tile_sizes.last().pow(2);
+        ColorWorker {
+            tile_sizes,
+            geometry: cfg.geometry,
+            x: vec![0.0; size2],
+            y: vec![0.0; size2],
+            z: vec![0.0; size2],
+            offsets: vec![0; size2],
+
+            eval_float_slice: Default::default(),
+            eval_interval: Default::default(),
+
+            tape_storage: vec![],
+            shape_storage: vec![],
+            workspace: Default::default(),
+            metrics: Default::default(),
+            out: vec![],
+        }
+    }
+
+    fn metrics(&mut self) -> &mut MetricsRecorder {
+        &mut self.metrics
+    }
+
+    fn render_tile(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        tile: Tile<2>,
+    ) -> Self::Output {
+        self.out = vec![f32::NAN; self.tile_sizes[0].pow(2)];
+        self.render_tile_recurse(shape, vars, 0, tile);
+        std::mem::take(&mut self.out)
+    }
+}
+
+impl<F: Function> ColorWorker<'_, F> {
+    /// Returns the evaluation depth of the surface at the given pixel
+    ///
+    /// This is the Z position of the first filled voxel (matching the position
+    /// used for normals), or `None` if the pixel is out of bounds or empty.
+    fn surface(&self, x: usize, y: usize) -> Option<f32> {
+        let size = self.geometry.size();
+        if x >= size.width() as usize || y >= size.height() as usize {
+            return None;
+        }
+        let d = self.geometry[(y, x)].depth;
+        (d > 0.0).then_some(d - 1.0)
+    }
+
+    fn render_tile_recurse<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        depth: usize,
+        tile: Tile<2>,
+    ) {
+        // Find the Z range of surface hits within this tile
+        let tile_size = self.tile_sizes[depth];
+        let mut z_range: Option<(f32, f32)> = None;
+        for j in 0..tile_size {
+            for i in 0..tile_size {
+                let p = tile.add(Vector2::new(i, j));
+                if let Some(z) = self.surface(p.x, p.y) {
+                    z_range = Some(match z_range {
+                        Some((lo, hi)) => (lo.min(z), hi.max(z)),
+                        None => (z, z),
+                    });
+                }
+            }
+        }
+        let Some((zmin, zmax)) = z_range else {
+            return; // no surface in this tile
+        };
+
+        let base = tile.corner.cast::<f32>();
+        let x = Interval::new(base.x, base.x + tile_size as f32);
+        let y = Interval::new(base.y, base.y + tile_size as f32);
+        let z = Interval::new(zmin, zmax);
+
+        let t = self.metrics.start();
+        let tape = shape.i_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let (_, trace) =
+            self.eval_interval.eval_v(tape, x, y, z, vars).unwrap();
+        self.metrics.record(Stage::IntervalEval, t);
+
+        let sub_tape = if let Some(trace) = trace.as_ref() {
+            let t = self.metrics.start();
+            let s = shape.simplify(
+                trace,
+                &mut self.workspace,
+                &mut self.shape_storage,
+                &mut self.tape_storage,
+            );
+            self.metrics.record(Stage::Simplify, t);
+            s
+        } else {
+            shape
+        };
+
+        if let Some(next_tile_size) = self.tile_sizes.get(depth + 1) {
+            let n = tile_size / next_tile_size;
+            for j in 0..n {
+                for i in 0..n {
+                    self.render_tile_recurse(
+                        sub_tape,
+                        vars,
+                        depth + 1,
+                        Tile::new(
+                            tile.corner + Vector2::new(i, j) * next_tile_size,
+                        ),
+                    );
+                }
+            }
+        } else {
+            self.render_tile_pixels(sub_tape, vars, tile_size, tile);
+        }
+    }
+
+    fn render_tile_pixels<T>(
+        &mut self,
+        shape: &mut RenderHandle<F, T>,
+        vars: &ShapeVars<f32>,
+        tile_size: usize,
+        tile: Tile<2>,
+    ) {
+        let mut index = 0;
+        for j in 0..tile_size {
+            for i in 0..tile_size {
+                let p = tile.add(Vector2::new(i, j));
+                if let Some(z) = self.surface(p.x, p.y) {
+                    self.x[index] = p.x as f32;
+                    self.y[index] = p.y as f32;
+                    self.z[index] = z;
+                    self.offsets[index] = self.tile_sizes.pixel_offset(p);
+                    index += 1;
+                }
+            }
+        }
+        assert!(index > 0);
+
+        let t = self.metrics.start();
+        let tape = shape.f_tape(&mut self.tape_storage);
+        self.metrics.record(Stage::TapeBuild, t);
+
+        let t = self.metrics.start();
+        let out = self
+            .eval_float_slice
+            .eval_v(
+                tape,
+                &self.x[..index],
+                &self.y[..index],
+                &self.z[..index],
+                vars,
+            )
+            .unwrap();
+        self.metrics.record(Stage::Fill, t);
+
+        for (o, v) in self.offsets[..index].iter().zip(out) {
+            self.out[*o] = *v;
+        }
+    }
+}
+
+/// Evaluates color channels at each surface pixel of a rendered image
+///
+/// Each channel is a separate shape, evaluated at the position of the first
+/// filled voxel in each pixel (i.e. the same position used for normals);
+/// values are expected to be in the range `0.0..=1.0` and are clamped to that
+/// range.  Empty pixels are black.
+///
+/// Returns `None` if rendering was cancelled.
+pub(crate) fn render_color<F: Function>(
+    color: [Shape<F>; 3],
+    geometry: &GeometryBuffer,
+    vars: &ShapeVars<f32>,
+    config: &VoxelRenderConfig,
+    metrics: bool,
+) -> Option<(ColorImage, PipelineMetrics)> {
+    let start = metrics.then(std::time::Instant::now);
+    let cfg = ColorRenderConfig { config, geometry };
+    let tile_sizes = config.tile_sizes();
+
+    let width = config.image_size.width() as usize;
+    let height = config.image_size.height() as usize;
+    let mut image = ColorImage::new(ImageSize::new(
+        config.image_size.width(),
+        config.image_size.height(),
+    ));
+    let mut out_metrics = PipelineMetrics::default();
+    for (c, shape) in color.into_iter().enumerate() {
+        let rh = RenderHandle::new(shape.with_transform(config.mat()));
+        let (tiles, m) = super::render_tiles::<F, ColorWorker<F>, _>(
+            rh, vars, &cfg, None, metrics,
+        )?;
+        for (i, t) in m.threads.iter().enumerate() {
+            out_metrics.record(i, t);
+        }
+        for (tile, out) in tiles {
+            let mut index = 0;
+            for j in 0..tile_sizes[0] {
+                let y = j + tile.corner.y;
+                for i in 0..tile_sizes[0] {
+                    let x = i + tile.corner.x;
+                    if x < width && y < height && !out[index].is_nan() {
+                        let v = out[index].clamp(0.0, 1.0);
+                        image[y * width + x][c] = (v * 255.0).round() as u8;
+                    }
+                    index += 1;
+                }
+            }
+        }
+    }
+    if let Some(start) = start {
+        out_metrics.wall_time = start.elapsed();
+    }
+    Some((image, out_metrics))
 }
 
 #[cfg(test)]
@@ -388,6 +759,139 @@ mod test {
     use super::*;
     use fidget_core::{Context, render::VoxelSize, vm::VmShape};
 
+    #[test]
+    fn render3d_turntable() {
+        use fidget_core::context::Tree;
+        use nalgebra::{Rotation3, Vector3};
+
+        // Asymmetric shape: a sphere offset along +X
+        let (x, y, z) = Tree::axes();
+        let shape = VmShape::from(
+            ((x - 0.5).square() + y.square() + z.square()).sqrt() - 0.3,
+        );
+        let cfg = VoxelRenderConfig {
+            image_size: VoxelSize::from(64),
+            ..Default::default()
+        };
+        let frames = cfg
+            .run_turntable(shape.clone(), &ShapeVars::new(), 4)
+            .unwrap();
+        assert_eq!(frames.len(), 4);
+        assert!(frames[0].iter().any(|p| p.depth > 0.0));
+
+        for (i, frame) in frames.iter().enumerate() {
+            let angle = std::f32::consts::FRAC_PI_2 * i as f32;
+            let rot = Rotation3::from_axis_angle(&Vector3::y_axis(), angle);
+            let cfg = VoxelRenderConfig {
+                image_size: VoxelSize::from(64),
+                world_to_model: rot.to_homogeneous(),
+                ..Default::default()
+            };
+            let expected = cfg.run(shape.clone()).unwrap();
+            assert!(
+                frame
+                    .iter()
+                    .zip(expected.iter())
+                    .all(|(a, b)| a.depth == b.depth),
+                "frame {i} does not match"
+            );
+        }
+        // Frames are distinct views of the shape
+        assert!(
+            frames[0]
+                .iter()
+                .zip(frames[1].iter())
+                .any(|(a, b)| a.depth != b.depth)
+        );
+    }
+
+    #[test]
+    fn render3d_color() {
+        use fidget_core::context::Tree;
+
+        let (x, y, z) = Tree::axes();
+        let shape =
+            VmShape::from((x.square() + y.square() + z.square()).sqrt() - 0.8);
+        let color = [
+            VmShape::from(Tree::constant(1.0)),
+            VmShape::from(x.max(0.0)),
+            VmShape::from(z.clone()),
+        ];
+        let cfg = VoxelRenderConfig {
+            image_size: VoxelSize::from(64),
+            ..Default::default()
+        };
+        let (geometry, image) = cfg
+            .run_with_color(shape.clone(), color, &ShapeVars::new())
+            .unwrap();
+        assert_eq!(image.width(), 64);
+        assert_eq!(image.height(), 64);
+
+        let expected = cfg.run(shape).unwrap();
+        let mat = cfg.mat();
+        let mut hits = 0;
+        for j in 0..64 {
+            for i in 0..64 {
+                let g = geometry[(j, i)];
+                assert_eq!(g.depth, expected[(j, i)].depth);
+                let c = image[(j, i)];
+                if g.depth == 0.0 {
+                    assert_eq!(c, [0, 0, 0]);
+                    continue;
+                }
+                hits += 1;
+                let p = mat.transform_point(&Point3::new(
+                    i as f32,
+                    j as f32,
+                    g.depth - 1.0,
+                ));
+                let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                assert_eq!(c, [255, to_u8(p.x.max(0.0)), to_u8(p.z)]);
+            }
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn render3d_regions() {
+        use crate::ImageRegion;
+        use fidget_core::context::Tree;
+
+        let (x, y, z) = Tree::axes();
+        let a =
+            VmShape::from((x.square() + y.square() + z.square()).sqrt() - 0.5);
+        let b = VmShape::from(
+            ((x - 0.2).square() + y.square() + z.square()).sqrt() - 0.7,
+        );
+        let cfg = VoxelRenderConfig {
+            image_size: VoxelSize::new(64, 48, 64),
+            tile_sizes: fidget_core::render::TileSizes::new(&[16, 8]).unwrap(),
+            ..Default::default()
+        };
+        let expected_a = cfg.run(a.clone()).unwrap();
+        let expected_b = cfg.run(b.clone()).unwrap();
+
+        let regions = [ImageRegion::new(20, 10, 13, 30)];
+        let mut image = cfg.run(a).unwrap();
+        cfg.run_regions(b, &ShapeVars::new(), &regions, &mut image)
+            .unwrap();
+        let mut changed = 0;
+        for y in 0..48 {
+            for x in 0..64 {
+                let expected = if regions[0].contains(x, y) {
+                    expected_b[(y, x)]
+                } else {
+                    expected_a[(y, x)]
+                };
+                assert_eq!(image[(y, x)].depth, expected.depth);
+                if expected_a[(y, x)].depth != image[(y, x)].depth {
+                    changed += 1;
+                }
+            }
+        }
+        assert!(changed > 0);
+    }
+
     /// Make sure we don't crash if there's only a single tile
     #[test]
     fn test_tile_queues() {
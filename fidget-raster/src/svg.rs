@@ -0,0 +1,313 @@
+//! SVG output for extracted contours
+use crate::Polyline;
+use nalgebra::Point2;
+use std::io::{BufWriter, Write};
+
+/// Writes a set of contours as a filled SVG image
+///
+/// `region` is the boundary of the rendered region in model coordinates,
+/// typically from [`ImageRenderConfig::region`](crate::ImageRenderConfig::region)
+/// (its corners may be in either winding order).
+///
+/// Every contour is written as a subpath of a single filled `<path>`.
+/// Contours from [`run_contours`](crate::ImageRenderConfig::run_contours)
+/// wind in opposite directions around shapes and holes, so the `nonzero` fill
+/// rule cuts holes correctly (including islands within holes).  Open polylines
+/// (where the shape is clipped by the rendered region) are closed by walking
+/// counter-clockwise along the region's boundary, which keeps the inside of
+/// the shape on the left, until reaching the start of the next open polyline.
+/// If there are no open polylines and the outermost loops are holes, then the
+/// region's boundary is inside the shape, so it's added as an outer loop.
+///
+/// One SVG user unit is one model unit.  The Y axis is flipped (because SVG's
+/// Y axis points down), and the `viewBox` is fit to the region's bounds.
+///
+/// ```
+/// # use fidget_core::{context::Tree, vm::VmShape};
+/// # use fidget_raster::ImageRenderConfig;
+/// # use fidget_core::shape::ShapeVars;
+/// let (x, y, _) = Tree::axes();
+/// let shape = VmShape::from((x.square() + y.square()).sqrt() - 0.5);
+/// let cfg = ImageRenderConfig::default();
+/// let lines = cfg.run_contours(shape, &ShapeVars::new()).unwrap();
+/// let mut out = vec![];
+/// fidget_raster::write_svg(&lines, &cfg.region(), &mut out)?;
+/// assert!(String::from_utf8(out).unwrap().starts_with("<svg"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_svg<F: std::io::Write>(
+    lines: &[Polyline],
+    region: &[Point2<f32>],
+    out: &mut F,
+) -> std::io::Result<()> {
+    let mut out = BufWriter::new(out);
+
+    let mut min = [f32::INFINITY; 2];
+    let mut max = [f32::NEG_INFINITY; 2];
+    for p in region {
+        let y = 0.0 - p.y;
+        min = [min[0].min(p.x), min[1].min(y)];
+        max = [max[0].max(p.x), max[1].max(y)];
+    }
+    if min[0] > max[0] {
+        min = [0.0; 2];
+        max = [0.0; 2];
+    }
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min[0],
+        min[1],
+        max[0] - min[0],
+        max[1] - min[1]
+    )?;
+    let loops = fill_loops(lines, region);
+    if !loops.is_empty() {
+        write!(out, "<path d=\"")?;
+        for points in loops {
+            for (i, p) in points.iter().enumerate() {
+                let cmd = if i == 0 { 'M' } else { 'L' };
+                write!(out, "{cmd}{},{}", p.x, 0.0 - p.y)?;
+            }
+            write!(out, "Z")?;
+        }
+        writeln!(out, r#"" fill="black" fill-rule="nonzero"/>"#)?;
+    }
+    writeln!(out, "</svg>")?;
+    Ok(())
+}
+
+/// Converts contours into closed loops, closing open polylines along the
+/// boundary of `region`
+fn fill_loops(
+    lines: &[Polyline],
+    region: &[Point2<f32>],
+) -> Vec<Vec<Point2<f32>>> {
+    // Walk the boundary counter-clockwise, so that the region is on its left
+    let mut region = Polyline {
+        points: region.to_vec(),
+        closed: true,
+    };
+    if region.signed_area() < 0.0 {
+        region.points.reverse();
+    }
+    let region = region.points;
+
+    let (closed, open): (Vec<&Polyline>, Vec<&Polyline>) = lines
+        .iter()
+        .filter(|line| !line.points.is_empty())
+        .partition(|line| line.closed);
+    let mut out: Vec<Vec<Point2<f32>>> =
+        closed.iter().map(|line| line.points.clone()).collect();
+    if region.len() < 3 {
+        return out;
+    } else if open.is_empty() {
+        // Top-level loops all border the region's boundary, so they share an
+        // orientation; if they're holes, then the boundary is inside.
+        let outer = closed
+            .iter()
+            .map(|line| line.signed_area())
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()));
+        if outer.is_some_and(|a| a < 0.0) {
+            out.push(region);
+        }
+        return out;
+    }
+
+    let perimeter = region.len() as f32;
+    let starts: Vec<f32> = open
+        .iter()
+        .map(|line| boundary_position(&region, line.points[0]))
+        .collect();
+    let ends: Vec<f32> = open
+        .iter()
+        .map(|line| boundary_position(&region, *line.points.last().unwrap()))
+        .collect();
+
+    let mut used = vec![false; open.len()];
+    for i in 0..open.len() {
+        if used[i] {
+            continue;
+        }
+        let mut points = vec![];
+        let mut j = i;
+        while !used[j] {
+            used[j] = true;
+            points.extend(&open[j].points);
+
+            // Find the next start counter-clockwise from this end, adding
+            // region corners along the way
+            let end = ends[j];
+            let (next, dist) = starts
+                .iter()
+                .map(|s| (s - end).rem_euclid(perimeter))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            let mut c = end.floor() + 1.0;
+            while c < end + dist {
+                points.push(region[c as usize % region.len()]);
+                c += 1.0;
+            }
+            j = next;
+        }
+        out.push(points);
+    }
+    out
+}
+
+/// Returns the position of a point along the boundary of a polygon
+///
+/// The integer part is the index of the nearest edge (from corner `i` to
+/// corner `i + 1`), and the fractional part is the position along that edge.
+fn boundary_position(region: &[Point2<f32>], p: Point2<f32>) -> f32 {
+    (0..region.len())
+        .map(|i| {
+            let a = region[i];
+            let b = region[(i + 1) % region.len()];
+            let ab = b - a;
+            let t = ((p - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0);
+            let d = (a + ab * t - p).norm_squared();
+            (d, i as f32 + t.min(1.0 - f32::EPSILON))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap()
+        .1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ImageRenderConfig;
+    use fidget_core::{context::Tree, shape::ShapeVars, vm::VmShape};
+
+    fn square(x: f32, y: f32, r: f32) -> Vec<Point2<f32>> {
+        vec![
+            Point2::new(x - r, y - r),
+            Point2::new(x + r, y - r),
+            Point2::new(x + r, y + r),
+            Point2::new(x - r, y + r),
+        ]
+    }
+
+    fn open(points: &[(f32, f32)]) -> Polyline {
+        Polyline {
+            points: points.iter().map(|&(x, y)| Point2::new(x, y)).collect(),
+            closed: false,
+        }
+    }
+
+    /// Returns the total area filled by a set of loops
+    fn area(loops: Vec<Vec<Point2<f32>>>) -> f32 {
+        loops
+            .into_iter()
+            .map(|points| {
+                Polyline {
+                    points,
+                    closed: true,
+                }
+                .signed_area()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn svg_paths() {
+        let outer = Polyline {
+            points: square(0.0, 0.0, 2.0),
+            closed: true,
+        };
+        let mut hole = square(0.0, 0.0, 1.0);
+        hole.reverse();
+        let hole = Polyline {
+            points: hole,
+            closed: true,
+        };
+
+        let mut out = vec![];
+        let region = square(0.0, 0.0, 4.0);
+        write_svg(&[outer, hole], &region, &mut out).unwrap();
+        let s = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(lines.len(), 3, "{s}");
+        assert_eq!(
+            lines[0],
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-4 -4 8 8">"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"<path d="M-2,2L2,2L2,-2L-2,-2ZM-1,-1L1,-1L1,1L-1,1Z" fill="black" fill-rule="nonzero"/>"#
+        );
+        assert_eq!(lines[2], "</svg>");
+    }
+
+    #[test]
+    fn svg_empty() {
+        let mut out = vec![];
+        write_svg(&[], &[], &mut out).unwrap();
+        let s = String::from_utf8(out).unwrap();
+        assert_eq!(
+            s,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 0 0\">\n\
+             </svg>\n"
+        );
+    }
+
+    #[test]
+    fn fill_open() {
+        let region = square(0.5, 0.5, 0.5);
+
+        // The inside (x < 0.5) is on the left of the contour
+        let half = open(&[(0.5, 0.0), (0.5, 1.0)]);
+        let loops = fill_loops(std::slice::from_ref(&half), &region);
+        assert_eq!(
+            loops,
+            [[(0.5, 0.0), (0.5, 1.0), (0.0, 1.0), (0.0, 0.0)]
+                .map(|(x, y)| Point2::new(x, y))]
+        );
+
+        // The region's winding doesn't matter
+        let mut reversed = region.clone();
+        reversed.reverse();
+        assert_eq!(area(fill_loops(&[half], &reversed)), 0.5);
+
+        // Two open contours (0.25 < x < 0.75) are joined into one loop
+        let a = open(&[(0.75, 0.0), (0.75, 1.0)]);
+        let b = open(&[(0.25, 1.0), (0.25, 0.0)]);
+        let loops = fill_loops(&[a, b], &region);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(area(loops), 0.5);
+
+        // A hole without an outer loop means the boundary is inside
+        let mut hole = square(0.5, 0.5, 0.25);
+        hole.reverse();
+        let hole = Polyline {
+            points: hole,
+            closed: true,
+        };
+        assert_eq!(area(fill_loops(&[hole], &region)), 0.75);
+    }
+
+    #[test]
+    fn fill_clipped_shape() {
+        // A circle which is cut in half by the right edge of the image
+        let (x, y, _) = Tree::axes();
+        let circle = ((x - 1.0).square() + y.square()).sqrt() - 0.5;
+        let cfg = ImageRenderConfig::default();
+        let lines = cfg
+            .run_contours(VmShape::from(circle), &ShapeVars::new())
+            .unwrap();
+        assert!(lines.iter().any(|line| !line.closed));
+
+        let area = area(fill_loops(&lines, &cfg.region()));
+        let expected = std::f32::consts::PI * 0.25 / 2.0;
+        assert!((area - expected).abs() < 1e-3, "bad area {area}");
+
+        let mut out = vec![];
+        write_svg(&lines, &cfg.region(), &mut out).unwrap();
+        let s = String::from_utf8(out).unwrap();
+        assert!(s.contains(r#"fill-rule="nonzero""#));
+        assert!(!s.contains("stroke"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Output pixel formats for 2D rendering
+//!
+//! A [`PixelWriter`] converts each [`DistancePixel`] into an output pixel as
+//! tiles are assembled into the final image, so callers that want a mask or
+//! bitmap don't need to convert (and allocate) a second full-size image.
+use crate::DistancePixel;
+
+/// Converts rendered pixels into an output format
+///
+/// See [`ImageRenderConfig::run_with_writer`](crate::ImageRenderConfig::run_with_writer)
+/// for usage.
+pub trait PixelWriter: Sync {
+    /// Output pixel type
+    type Output: Copy + Default + Send;
+
+    /// Converts a single rendered pixel
+    fn write(&self, p: DistancePixel) -> Self::Output;
+
+    /// Converts a single rendered pixel, given its coverage
+    ///
+    /// `coverage` is the fraction of the pixel's area which is inside the
+    /// shape, in the range `0.0..=1.0`.  This is only called when
+    /// [`anti_alias`](crate::ImageRenderConfig::anti_alias) is enabled; the
+    /// default implementation ignores coverage and calls
+    /// [`write`](Self::write).
+    #[inline]
+    fn write_coverage(&self, p: DistancePixel, coverage: f32) -> Self::Output {
+        let _ = coverage;
+        self.write(p)
+    }
+}
+
+/// Writes [`DistancePixel`] values unchanged
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DistanceWriter;
+
+impl PixelWriter for DistanceWriter {
+    type Output = DistancePixel;
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> DistancePixel {
+        p
+    }
+}
+
+/// Writes a `u8` mask, which is 255 inside the shape and 0 outside
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MaskWriter;
+
+impl PixelWriter for MaskWriter {
+    type Output = u8;
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> u8 {
+        if p.inside() { u8::MAX } else { 0 }
+    }
+
+    /// Writes coverage scaled to `0..=255`
+    #[inline]
+    fn write_coverage(&self, _p: DistancePixel, coverage: f32) -> u8 {
+        (coverage * f32::from(u8::MAX)).round() as u8
+    }
+}
+
+/// Writes `f32` coverage values, in the range `0.0..=1.0`
+///
+/// Without [`anti_alias`](crate::ImageRenderConfig::anti_alias), coverage is
+/// 1 inside the shape and 0 outside.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CoverageWriter;
+
+impl PixelWriter for CoverageWriter {
+    type Output = f32;
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> f32 {
+        if p.inside() { 1.0 } else { 0.0 }
+    }
+
+    #[inline]
+    fn write_coverage(&self, _p: DistancePixel, coverage: f32) -> f32 {
+        coverage
+    }
+}
+
+/// Writes raw `f32` distance values
+///
+/// Pixels in filled regions (which don't have a distance value unless
+/// [`pixel_perfect`](crate::ImageRenderConfig::pixel_perfect) is set) are
+/// written as `-∞` (inside) or `+∞` (outside).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DistanceF32Writer;
+
+impl PixelWriter for DistanceF32Writer {
+    type Output = f32;
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> f32 {
+        match p.distance() {
+            Ok(d) => d,
+            Err(f) if f.inside => f32::NEG_INFINITY,
+            Err(_) => f32::INFINITY,
+        }
+    }
+}
+
+/// Writes `u16` distance values, centered on the surface
+///
+/// Distances in the range `-range..=range` are mapped to `0..=u16::MAX`, so
+/// the surface is at (roughly) `u16::MAX / 2`; values outside that range are
+/// clamped.  Filled regions are written as `0` (inside) or `u16::MAX`
+/// (outside), and `NaN` distances are written as `0`.
+#[derive(Copy, Clone, Debug)]
+pub struct Distance16Writer {
+    /// Distance which is mapped to `u16::MAX`
+    pub range: f32,
+}
+
+impl PixelWriter for Distance16Writer {
+    type Output = u16;
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> u16 {
+        match p.distance() {
+            Ok(d) => {
+                let v = (0.5 + d / (2.0 * self.range)).clamp(0.0, 1.0);
+                (v * f32::from(u16::MAX)).round() as u16
+            }
+            Err(f) if f.inside => 0,
+            Err(_) => u16::MAX,
+        }
+    }
+}
+
+/// Writes RGBA pixels, using one color inside the shape and another outside
+#[derive(Copy, Clone, Debug)]
+pub struct RgbaWriter {
+    /// Color for pixels inside the shape
+    pub inside: [u8; 4],
+    /// Color for pixels outside the shape
+    pub outside: [u8; 4],
+}
+
+impl Default for RgbaWriter {
+    /// Opaque white inside the shape and transparent outside
+    fn default() -> Self {
+        Self {
+            inside: [u8::MAX; 4],
+            outside: [0; 4],
+        }
+    }
+}
+
+impl PixelWriter for RgbaWriter {
+    type Output = [u8; 4];
+
+    #[inline]
+    fn write(&self, p: DistancePixel) -> [u8; 4] {
+        if p.inside() {
+            self.inside
+        } else {
+            self.outside
+        }
+    }
+
+    /// Blends between the outside and inside colors based on coverage
+    #[inline]
+    fn write_coverage(&self, _p: DistancePixel, coverage: f32) -> [u8; 4] {
+        std::array::from_fn(|i| {
+            let a = f32::from(self.outside[i]);
+            let b = f32::from(self.inside[i]);
+            (a + (b - a) * coverage).round() as u8
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_writers() {
+        let inside = DistancePixel::from(-0.5);
+        let outside = DistancePixel::from(0.25);
+
+        assert_eq!(MaskWriter.write(inside), 255);
+        assert_eq!(MaskWriter.write(outside), 0);
+
+        assert_eq!(DistanceF32Writer.write(inside), -0.5);
+        assert_eq!(DistanceF32Writer.write(outside), 0.25);
+
+        let w = Distance16Writer { range: 1.0 };
+        assert_eq!(w.write(inside), 16384);
+        assert_eq!(w.write(outside), 40959);
+        assert_eq!(w.write(DistancePixel::from(10.0)), u16::MAX);
+
+        let w = RgbaWriter {
+            inside: [1, 2, 3, 4],
+            ..Default::default()
+        };
+        assert_eq!(w.write(inside), [1, 2, 3, 4]);
+        assert_eq!(w.write(outside), [0; 4]);
+
+        assert_eq!(w.write_coverage(outside, 0.5), [1, 1, 2, 2]);
+        assert_eq!(MaskWriter.write_coverage(inside, 0.25), 64);
+        assert_eq!(CoverageWriter.write(inside), 1.0);
+        assert_eq!(CoverageWriter.write_coverage(inside, 0.75), 0.75);
+        assert_eq!(DistanceF32Writer.write_coverage(inside, 0.75), -0.5);
+    }
+}
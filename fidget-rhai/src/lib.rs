@@ -40,7 +40,19 @@
 //! ```
 //!
 //! `x, y, z` variables are also automatically injected into the `Engine`'s
-//! context before evaluation.
+//! context before evaluation, along with a fourth `w` axis (see
+//! [`Var::W`](fidget_core::var::Var::W)), which must be bound to a value when
+//! the resulting shape is evaluated.
+//!
+//! Cylindrical and spherical coordinates are available with `cylindrical()`
+//! (returning `{r, theta, z}`) and `spherical()` (returning `{r, theta, phi}`):
+//!
+//! ```
+//! # fidget_rhai::engine().run("
+//! let c = cylindrical();
+//! c.r - 1 - 0.1 * sin(c.theta * 6)
+//! # ").unwrap();
+//! ```
 //!
 //! # Mathematical constants
 //! The Rhai context includes common mathematical constants that can be used
@@ -243,7 +255,7 @@ use fidget_core::context::Tree;
 /// - [`set_fail_on_invalid_map_property`](rhai::Engine::set_fail_on_invalid_map_property)
 ///   set to `true`, so that missing map items raise an error
 /// - A custom resolver ([`resolver`]) which provides fallbacks for `x`, `y`,
-///   `z`, and mathematical constants (if not defined)
+///   `z`, `w`, and mathematical constants (if not defined)
 pub fn engine() -> rhai::Engine {
     let mut engine = rhai::Engine::new();
 
@@ -267,7 +279,7 @@ pub fn engine() -> rhai::Engine {
     engine
 }
 
-/// Variable resolver which provides `x`, `y`, `z` and mathematical constants if not found
+/// Variable resolver which provides `x`, `y`, `z`, `w`, and mathematical constants if not found
 pub fn resolver(
     name: &str,
     _index: usize,
@@ -280,6 +292,7 @@ pub fn resolver(
             "x" => Ok(Some(rhai::Dynamic::from(Tree::x()))),
             "y" => Ok(Some(rhai::Dynamic::from(Tree::y()))),
             "z" => Ok(Some(rhai::Dynamic::from(Tree::z()))),
+            "w" => Ok(Some(rhai::Dynamic::from(Tree::w()))),
             _ => {
                 // Try to resolve as a mathematical constant
                 if let Some(constant) = constants::get_constant(name) {
@@ -56,17 +56,23 @@ fn register_tree(engine: &mut rhai::Engine) {
                 TreeOp::Input(Var::X) => "x",
                 TreeOp::Input(Var::Y) => "y",
                 TreeOp::Input(Var::Z) => "z",
+                TreeOp::Input(Var::W) => "w",
                 _ => "Tree(..)",
             }
             .to_owned()
         })
         .register_fn("remap", remap_xyz)
-        .register_fn("remap", remap_xy);
+        .register_fn("remap", remap_xy)
+        .register_fn("powi", powi)
+        .register_fn("clamp", clamp)
+        .register_fn("if_nonzero_else", if_nonzero_else);
 }
 
 /// Installs the [`Tree`] type into a Rhai engine, with various overloads
 ///
-/// Also installs `axes() -> {x, y, z}`
+/// Also installs `axes() -> {x, y, z, w}`, `cylindrical() -> {r, theta, z}`, and
+/// `spherical() -> {r, theta, phi}` (see [`Tree::cylindrical`] and
+/// [`Tree::spherical`])
 pub fn register(engine: &mut rhai::Engine) {
     register_tree(engine);
     engine.register_fn("axes", || {
@@ -75,6 +81,23 @@ pub fn register(engine: &mut rhai::Engine) {
         out.insert("x".into(), rhai::Dynamic::from(Tree::x()));
         out.insert("y".into(), rhai::Dynamic::from(Tree::y()));
         out.insert("z".into(), rhai::Dynamic::from(Tree::z()));
+        out.insert("w".into(), rhai::Dynamic::from(Tree::w()));
+        out
+    });
+    engine.register_fn("cylindrical", || {
+        let (r, theta, z) = Tree::cylindrical();
+        let mut out = rhai::Map::new();
+        out.insert("r".into(), rhai::Dynamic::from(r));
+        out.insert("theta".into(), rhai::Dynamic::from(theta));
+        out.insert("z".into(), rhai::Dynamic::from(z));
+        out
+    });
+    engine.register_fn("spherical", || {
+        let (r, theta, phi) = Tree::spherical();
+        let mut out = rhai::Map::new();
+        out.insert("r".into(), rhai::Dynamic::from(r));
+        out.insert("theta".into(), rhai::Dynamic::from(theta));
+        out.insert("phi".into(), rhai::Dynamic::from(phi));
         out
     });
 
@@ -95,14 +118,23 @@ pub fn register(engine: &mut rhai::Engine) {
     register_binary_fns!("*", mul, engine);
     register_binary_fns!("/", div, engine);
     register_binary_fns!("%", modulo, engine);
+    register_binary_fns!("&", intersection, engine);
+    register_binary_fns!("|", union, engine);
+    register_binary_fns!("rem", rem, engine);
     register_binary_fns!("min", min, engine);
     register_binary_fns!("max", max, engine);
     register_binary_fns!("compare", compare, engine);
+    register_binary_fns!("less_than", less_than, engine);
+    register_binary_fns!("greater_than", greater_than, engine);
+    register_binary_fns!("equal", equal, engine);
     register_binary_fns!("and", and, engine);
     register_binary_fns!("or", or, engine);
     register_binary_fns!("atan2", atan2, engine);
+    register_binary_fns!("pow", powf, engine);
     register_unary_fns!("abs", abs, engine);
+    register_unary_fns!("sign", sign, engine);
     register_unary_fns!("sqrt", sqrt, engine);
+    register_unary_fns!("cbrt", cbrt, engine);
     register_unary_fns!("square", square, engine);
     register_unary_fns!("sin", sin, engine);
     register_unary_fns!("cos", cos, engine);
@@ -112,11 +144,15 @@ pub fn register(engine: &mut rhai::Engine) {
     register_unary_fns!("atan", atan, engine);
     register_unary_fns!("exp", exp, engine);
     register_unary_fns!("ln", ln, engine);
+    register_unary_fns!("sinh", sinh, engine);
+    register_unary_fns!("cosh", cosh, engine);
+    register_unary_fns!("tanh", tanh, engine);
     register_unary_fns!("not", not, engine);
     register_unary_fns!("ceil", ceil, engine);
     register_unary_fns!("floor", floor, engine);
     register_unary_fns!("round", round, engine);
     register_unary_fns!("-", neg, engine);
+    register_unary_fns!("!", complement, engine);
 
     // Ban comparison operators
     for op in ["==", "!=", "<", ">", "<=", ">="] {
@@ -136,6 +172,39 @@ fn remap_xyz(
     Ok(shape.remap_xyz(x, y, z))
 }
 
+fn powi(
+    ctx: NativeCallContext,
+    shape: rhai::Dynamic,
+    n: i64,
+) -> Result<Tree, Box<EvalAltResult>> {
+    let shape = Tree::from_dynamic(&ctx, shape, None)?;
+    Ok(shape.pow(n))
+}
+
+fn clamp(
+    ctx: NativeCallContext,
+    shape: rhai::Dynamic,
+    lo: rhai::Dynamic,
+    hi: rhai::Dynamic,
+) -> Result<Tree, Box<EvalAltResult>> {
+    let shape = Tree::from_dynamic(&ctx, shape, None)?;
+    let lo = Tree::from_dynamic(&ctx, lo, None)?;
+    let hi = Tree::from_dynamic(&ctx, hi, None)?;
+    Ok(shape.clamp(lo, hi))
+}
+
+fn if_nonzero_else(
+    ctx: NativeCallContext,
+    condition: rhai::Dynamic,
+    a: rhai::Dynamic,
+    b: rhai::Dynamic,
+) -> Result<Tree, Box<EvalAltResult>> {
+    let condition = Tree::from_dynamic(&ctx, condition, None)?;
+    let a = Tree::from_dynamic(&ctx, a, None)?;
+    let b = Tree::from_dynamic(&ctx, b, None)?;
+    Ok(condition.if_nonzero_else(a, b))
+}
+
 fn remap_xy(
     ctx: NativeCallContext,
     shape: rhai::Dynamic,
@@ -214,13 +283,22 @@ define_binary_fns!(div, Div);
 define_binary_fns!(min);
 define_binary_fns!(max);
 define_binary_fns!(compare);
+define_binary_fns!(less_than);
+define_binary_fns!(greater_than);
+define_binary_fns!(equal);
 define_binary_fns!(modulo);
+define_binary_fns!(intersection);
+define_binary_fns!(union);
+define_binary_fns!(rem);
 define_binary_fns!(and);
 define_binary_fns!(or);
 define_binary_fns!(atan2);
+define_binary_fns!(powf);
 define_unary_fns!(sqrt);
+define_unary_fns!(cbrt);
 define_unary_fns!(square);
 define_unary_fns!(neg);
+define_unary_fns!(complement);
 define_unary_fns!(sin);
 define_unary_fns!(cos);
 define_unary_fns!(tan);
@@ -229,8 +307,12 @@ define_unary_fns!(acos);
 define_unary_fns!(atan);
 define_unary_fns!(exp);
 define_unary_fns!(ln);
+define_unary_fns!(sinh);
+define_unary_fns!(cosh);
+define_unary_fns!(tanh);
 define_unary_fns!(not);
 define_unary_fns!(abs);
+define_unary_fns!(sign);
 define_unary_fns!(floor);
 define_unary_fns!(ceil);
 define_unary_fns!(round);
@@ -249,4 +331,84 @@ mod test {
             "Tree(..)"
         );
     }
+
+    #[test]
+    fn tree_coordinates() {
+        let mut e = rhai::Engine::new();
+        register(&mut e);
+        let t = e.eval::<Tree>("cylindrical().r - 1").unwrap();
+        let mut ctx = fidget_core::Context::new();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, 3.0, 4.0, 0.0).unwrap(), 4.0);
+
+        let t = e.eval::<Tree>("spherical().phi").unwrap();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, 0.0, 0.0, 1.0).unwrap(), 0.0);
+        assert_eq!(
+            e.eval::<String>("to_string(cylindrical().z)").unwrap(),
+            "z"
+        );
+    }
+
+    #[test]
+    fn tree_math_fns() {
+        let mut e = rhai::Engine::new();
+        register(&mut e);
+        let t = e.eval::<Tree>("powi(axes().x, 3)").unwrap();
+        let mut ctx = fidget_core::Context::new();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, 2.0, 0.0, 0.0).unwrap(), 8.0);
+
+        let t = e.eval::<Tree>("sign(axes().x)").unwrap();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, -2.0, 0.0, 0.0).unwrap(), -1.0);
+        assert_eq!(ctx.eval_xyz(n, 0.0, 0.0, 0.0).unwrap(), 0.0);
+        assert_eq!(ctx.eval_xyz(n, 3.0, 0.0, 0.0).unwrap(), 1.0);
+
+        let t = e.eval::<Tree>("clamp(axes().x, -1, 2.5)").unwrap();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, -2.0, 0.0, 0.0).unwrap(), -1.0);
+        assert_eq!(ctx.eval_xyz(n, 3.0, 0.0, 0.0).unwrap(), 2.5);
+
+        let t = e
+            .eval::<Tree>("less_than(axes().x, 1) + equal(axes().x, 2)")
+            .unwrap();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, 0.0, 0.0, 0.0).unwrap(), 1.0);
+        assert_eq!(ctx.eval_xyz(n, 2.0, 0.0, 0.0).unwrap(), 1.0);
+        assert_eq!(ctx.eval_xyz(n, 3.0, 0.0, 0.0).unwrap(), 0.0);
+
+        let t = e
+            .eval::<Tree>(
+                "let a = axes(); if_nonzero_else(less_than(a.x, 0), a.y, a.z)",
+            )
+            .unwrap();
+        let n = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(n, -1.0, 2.0, 3.0).unwrap(), 2.0);
+        assert_eq!(ctx.eval_xyz(n, 1.0, 2.0, 3.0).unwrap(), 3.0);
+
+        let t = e
+            .eval::<Tree>("let a = axes(); (a.x | a.y - 2) & !(a.y - 0.5)")
+            .unwrap();
+        let n = ctx.import(&t);
+        assert!(ctx.eval_xyz(n, -1.0, 1.0, 0.0).unwrap() < 0.0);
+        assert!(ctx.eval_xyz(n, -1.0, 0.0, 0.0).unwrap() > 0.0);
+        assert!(ctx.eval_xyz(n, 1.0, 1.0, 0.0).unwrap() < 0.0);
+        assert!(ctx.eval_xyz(n, 1.0, 3.0, 0.0).unwrap() > 0.0);
+
+        let t = e.eval::<Tree>("cbrt(axes().x)").unwrap();
+        let n = ctx.import(&t);
+        let v = ctx.eval_xyz(n, -8.0, 0.0, 0.0).unwrap();
+        assert!((v + 2.0).abs() < 1e-12);
+
+        let t = e.eval::<Tree>("tanh(axes().x)").unwrap();
+        let n = ctx.import(&t);
+        let v = ctx.eval_xyz(n, 0.5, 0.0, 0.0).unwrap();
+        assert!((v - 0.5f64.tanh()).abs() < 1e-12);
+
+        let t = e.eval::<Tree>("pow(axes().x, axes().y)").unwrap();
+        let n = ctx.import(&t);
+        let v = ctx.eval_xyz(n, 4.0, 0.5, 0.0).unwrap();
+        assert!((v - 2.0).abs() < 1e-12);
+    }
 }
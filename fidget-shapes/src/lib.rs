@@ -14,10 +14,14 @@
 //! For an example of binding shapes into a dynamic language, look at the
 //! implementation of `fidget_rhai::shapes` (specifically the internal
 //! `register_shape` function).
+//!
+//! When building shapes directly in Rust, the [`ops`] module provides
+//! functional wrappers for common operations (e.g. [`ops::union`]).
 #![warn(missing_docs)]
 use facet::Facet;
 use fidget_core::context::Tree;
 
+pub mod ops;
 pub mod types;
 use types::{Axis, Plane, Vec2, Vec3};
 
@@ -161,6 +165,186 @@ impl From<Blend> for Tree {
     }
 }
 
+/// Union of two shapes with a circular fillet at their intersection
+///
+/// This is `fOpUnionRound` from [hg_sdf](https://mercury.sexy/hg_sdf/): the
+/// seam is replaced by a quarter-circle of the given radius (exact when the
+/// surfaces meet at right angles).
+#[derive(Clone, Facet)]
+pub struct FilletUnion {
+    /// First shape input
+    pub a: Tree,
+    /// Second shape input
+    pub b: Tree,
+    /// Fillet radius
+    pub radius: f64,
+}
+
+impl From<FilletUnion> for Tree {
+    fn from(v: FilletUnion) -> Self {
+        if v.radius > 0.0 {
+            let ua = (v.radius - v.a.clone()).max(0.0);
+            let ub = (v.radius - v.b.clone()).max(0.0);
+            v.a.min(v.b).max(v.radius) - (ua.square() + ub.square()).sqrt()
+        } else {
+            v.a.min(v.b)
+        }
+    }
+}
+
+/// Intersection of two shapes with a circular fillet along their seam
+///
+/// This is `fOpIntersectionRound` from [hg_sdf](https://mercury.sexy/hg_sdf/)
+#[derive(Clone, Facet)]
+pub struct FilletIntersection {
+    /// First shape input
+    pub a: Tree,
+    /// Second shape input
+    pub b: Tree,
+    /// Fillet radius
+    pub radius: f64,
+}
+
+impl From<FilletIntersection> for Tree {
+    fn from(v: FilletIntersection) -> Self {
+        if v.radius > 0.0 {
+            let ua = (v.radius + v.a.clone()).max(0.0);
+            let ub = (v.radius + v.b.clone()).max(0.0);
+            v.a.max(v.b).min(-v.radius) + (ua.square() + ub.square()).sqrt()
+        } else {
+            v.a.max(v.b)
+        }
+    }
+}
+
+/// Difference of two shapes with a circular fillet along the cut
+#[derive(Clone, Facet)]
+pub struct FilletDifference {
+    /// Original shape
+    pub shape: Tree,
+    /// Shape to be subtracted from the original
+    pub cutout: Tree,
+    /// Fillet radius
+    pub radius: f64,
+}
+
+impl From<FilletDifference> for Tree {
+    fn from(v: FilletDifference) -> Self {
+        FilletIntersection {
+            a: v.shape,
+            b: -v.cutout,
+            radius: v.radius,
+        }
+        .into()
+    }
+}
+
+/// Union of two shapes with a 45° chamfer at their intersection
+///
+/// This is `fOpUnionChamfer` from [hg_sdf](https://mercury.sexy/hg_sdf/)
+#[derive(Clone, Facet)]
+pub struct ChamferUnion {
+    /// First shape input
+    pub a: Tree,
+    /// Second shape input
+    pub b: Tree,
+    /// Chamfer size
+    pub width: f64,
+}
+
+impl From<ChamferUnion> for Tree {
+    fn from(v: ChamferUnion) -> Self {
+        let d = (v.a.clone() + v.b.clone() - v.width) * 0.5f64.sqrt();
+        v.a.min(v.b).min(d)
+    }
+}
+
+/// Intersection of two shapes with a 45° chamfer along their seam
+///
+/// This is `fOpIntersectionChamfer` from
+/// [hg_sdf](https://mercury.sexy/hg_sdf/)
+#[derive(Clone, Facet)]
+pub struct ChamferIntersection {
+    /// First shape input
+    pub a: Tree,
+    /// Second shape input
+    pub b: Tree,
+    /// Chamfer size
+    pub width: f64,
+}
+
+impl From<ChamferIntersection> for Tree {
+    fn from(v: ChamferIntersection) -> Self {
+        let d = (v.a.clone() + v.b.clone() + v.width) * 0.5f64.sqrt();
+        v.a.max(v.b).max(d)
+    }
+}
+
+/// Difference of two shapes with a 45° chamfer along the cut
+#[derive(Clone, Facet)]
+pub struct ChamferDifference {
+    /// Original shape
+    pub shape: Tree,
+    /// Shape to be subtracted from the original
+    pub cutout: Tree,
+    /// Chamfer size
+    pub width: f64,
+}
+
+impl From<ChamferDifference> for Tree {
+    fn from(v: ChamferDifference) -> Self {
+        ChamferIntersection {
+            a: v.shape,
+            b: -v.cutout,
+            width: v.width,
+        }
+        .into()
+    }
+}
+
+/// Linear interpolation between two shapes
+///
+/// The result is `a` when `t = 0` and `b` when `t = 1`.  Because `t` is a
+/// [`Tree`], it may be a [`Var`](fidget_core::var::Var), in which case a
+/// single compiled tape can animate between shapes by rebinding that variable.
+#[derive(Clone, Facet)]
+pub struct Morph {
+    /// Shape at `t = 0`
+    pub a: Tree,
+    /// Shape at `t = 1`
+    pub b: Tree,
+    /// Interpolation parameter
+    pub t: Tree,
+}
+
+impl From<Morph> for Tree {
+    fn from(v: Morph) -> Self {
+        v.a.clone() + (v.b - v.a) * v.t
+    }
+}
+
+/// Smooth interpolation between two shapes
+///
+/// This is equivalent to [`Morph`], but `t` is clamped to `[0, 1]` and eased
+/// with a smoothstep curve (`3t² - 2t³`), so that animations ease in and out.
+#[derive(Clone, Facet)]
+pub struct SmoothMorph {
+    /// Shape at `t = 0`
+    pub a: Tree,
+    /// Shape at `t = 1`
+    pub b: Tree,
+    /// Interpolation parameter
+    pub t: Tree,
+}
+
+impl From<SmoothMorph> for Tree {
+    fn from(v: SmoothMorph) -> Self {
+        let t = v.t.max(0.0).min(1.0);
+        let t = t.square() * (3.0 - 2.0 * t);
+        Morph { a: v.a, b: v.b, t }.into()
+    }
+}
+
 /// Take the intersection of a set of shapes
 ///
 /// If the input is empty, returns a constant full tree (at -∞)
@@ -230,13 +414,7 @@ pub struct Move {
 
 impl From<Move> for Tree {
     fn from(v: Move) -> Self {
-        v.shape.remap_affine(nalgebra::convert(
-            nalgebra::Translation3::<f64>::new(
-                -v.offset.x,
-                -v.offset.y,
-                -v.offset.z,
-            ),
-        ))
+        v.shape.translate(v.offset.into())
     }
 }
 
@@ -252,12 +430,7 @@ pub struct Scale {
 
 impl From<Scale> for Tree {
     fn from(v: Scale) -> Self {
-        v.shape
-            .remap_affine(nalgebra::convert(nalgebra::Scale3::<f64>::new(
-                1.0 / v.scale.x,
-                1.0 / v.scale.y,
-                1.0 / v.scale.z,
-            )))
+        v.shape.scale(v.scale.into())
     }
 }
 
@@ -273,11 +446,7 @@ pub struct ScaleUniform {
 
 impl From<ScaleUniform> for Tree {
     fn from(v: ScaleUniform) -> Self {
-        let s = 1.0 / v.scale;
-        v.shape
-            .remap_affine(nalgebra::convert(nalgebra::Scale3::<f64>::new(
-                s, s, s,
-            )))
+        v.shape.scale(nalgebra::Vector3::repeat(v.scale))
     }
 }
 
@@ -382,6 +551,214 @@ impl From<ReflectZ> for Tree {
     }
 }
 
+/// Mirror symmetry about a plane
+///
+/// The part of the shape on the positive side of the plane (i.e. in the
+/// direction of the plane's axis) is mirrored onto the negative side; the
+/// original negative side is discarded.
+///
+/// For planes orthogonal to the X, Y, or Z axis, the mirrored coordinate is
+/// computed as `offset + abs(c - offset)`, which references the coordinate only
+/// once, so interval evaluation remains tight.  For other planes, each
+/// coordinate is shifted by `2 * max(-d, 0)` along the axis (where `d` is the
+/// signed distance to the plane); the shift itself is tight, but intervals of
+/// the shifted coordinates may be wider than their true range.
+#[derive(Clone, Facet)]
+pub struct Mirror {
+    /// Shape to mirror
+    pub shape: Tree,
+
+    /// Plane about which to mirror the shape
+    #[facet(default = Plane::YZ)]
+    pub plane: Plane,
+}
+
+impl From<Mirror> for Tree {
+    fn from(v: Mirror) -> Self {
+        let a = v.plane.axis.vec();
+        let (x, y, z) = Tree::axes();
+        let offset = v.plane.offset;
+        let axes = [a.x, a.y, a.z];
+        if let Some(i) = axes.iter().position(|c| c.abs() == 1.0) {
+            // Axis-aligned plane: fold a single coordinate, where `s * c` is
+            // the position along the axis
+            let s = axes[i];
+            let mut out = [x, y, z];
+            let c = out[i].clone();
+            out[i] = ((c * s - offset).abs() + offset) * s;
+            let [x, y, z] = out;
+            v.shape.remap_xyz(x, y, z)
+        } else {
+            let d =
+                a.x * x.clone() + a.y * y.clone() + a.z * z.clone() - offset;
+            let shift = (-d).max(0.0) * 2.0;
+            v.shape.remap_xyz(
+                x + shift.clone() * a.x,
+                y + shift.clone() * a.y,
+                z + shift * a.z,
+            )
+        }
+    }
+}
+
+/// Mirror symmetry about a plane orthogonal to the X axis
+///
+/// The `x >= offset` half of the shape is mirrored onto the other side.
+#[derive(Clone, Facet)]
+pub struct MirrorX {
+    /// Shape to mirror
+    pub shape: Tree,
+
+    /// Position of the mirror plane along the X axis
+    #[facet(default = 0.0)]
+    pub offset: f64,
+}
+
+impl From<MirrorX> for Tree {
+    fn from(v: MirrorX) -> Self {
+        Mirror {
+            shape: v.shape,
+            plane: Plane {
+                axis: Axis::X,
+                offset: v.offset,
+            },
+        }
+        .into()
+    }
+}
+
+/// Mirror symmetry about a plane orthogonal to the Y axis
+///
+/// The `y >= offset` half of the shape is mirrored onto the other side.
+#[derive(Clone, Facet)]
+pub struct MirrorY {
+    /// Shape to mirror
+    pub shape: Tree,
+
+    /// Position of the mirror plane along the Y axis
+    #[facet(default = 0.0)]
+    pub offset: f64,
+}
+
+impl From<MirrorY> for Tree {
+    fn from(v: MirrorY) -> Self {
+        Mirror {
+            shape: v.shape,
+            plane: Plane {
+                axis: Axis::Y,
+                offset: v.offset,
+            },
+        }
+        .into()
+    }
+}
+
+/// Mirror symmetry about a plane orthogonal to the Z axis
+///
+/// The `z >= offset` half of the shape is mirrored onto the other side.
+#[derive(Clone, Facet)]
+pub struct MirrorZ {
+    /// Shape to mirror
+    pub shape: Tree,
+
+    /// Position of the mirror plane along the Z axis
+    #[facet(default = 0.0)]
+    pub offset: f64,
+}
+
+impl From<MirrorZ> for Tree {
+    fn from(v: MirrorZ) -> Self {
+        Mirror {
+            shape: v.shape,
+            plane: Plane {
+                axis: Axis::Z,
+                offset: v.offset,
+            },
+        }
+        .into()
+    }
+}
+
+/// Radial symmetry about an axis through the origin
+///
+/// Space is divided into `count` equal sectors around the axis, and every
+/// sector is folded onto the one centered on the reference direction: +X for
+/// rotation about Z, +Y for rotation about X, and +Z for rotation about Y.
+/// The shape only needs to be defined within that sector.
+///
+/// `count` is rounded to the nearest integer, and must be at least 1.
+#[derive(Clone, Facet)]
+pub struct RadialSymmetry {
+    /// Shape to repeat
+    pub shape: Tree,
+
+    /// Number of sectors
+    #[facet(default = 1.0)]
+    pub count: f64,
+
+    /// Axis of symmetry
+    #[facet(default = Axis::Z)]
+    pub axis: Axis,
+}
+
+impl From<RadialSymmetry> for Tree {
+    fn from(v: RadialSymmetry) -> Self {
+        let frame = AxisFrame::new(&v.axis);
+        let (pu, pw, pa) = frame.coords();
+
+        // Fold the angle into the range [-sector / 2, sector / 2)
+        let sector = std::f64::consts::TAU / v.count.round().max(1.0);
+        let angle = (pw.clone().atan2(pu.clone()) + sector / 2.0)
+            .modulo(sector)
+            - sector / 2.0;
+        let r = (pu.square() + pw.square()).sqrt();
+        let pu = r.clone() * angle.clone().cos();
+        let pw = r * angle.sin();
+
+        frame.remap(v.shape, pu, pw, pa)
+    }
+}
+
+/// Right-handed orthonormal frame `(u, w, a)` built around an axis `a`
+///
+/// The reference direction `u` is picked by cycling the axis' largest
+/// component, so that the frame for Z is `(X, Y, Z)`, for X is `(Y, Z, X)`,
+/// and for Y is `(Z, X, Y)`.
+struct AxisFrame {
+    u: nalgebra::Vector3<f64>,
+    w: nalgebra::Vector3<f64>,
+    a: nalgebra::Vector3<f64>,
+}
+
+impl AxisFrame {
+    fn new(axis: &Axis) -> Self {
+        let a = nalgebra::Vector3::from(*axis.vec());
+        let helper = nalgebra::Vector3::ith((a.iamax() + 1) % 3, 1.0);
+        let u = (helper - a * helper.dot(&a)).normalize();
+        let w = a.cross(&u);
+        Self { u, w, a }
+    }
+
+    /// Returns the `(u, w, a)` coordinates of the current point
+    fn coords(&self) -> (Tree, Tree, Tree) {
+        let (x, y, z) = Tree::axes();
+        let dot = |v: &nalgebra::Vector3<f64>| {
+            v.x * x.clone() + v.y * y.clone() + v.z * z.clone()
+        };
+        (dot(&self.u), dot(&self.w), dot(&self.a))
+    }
+
+    /// Evaluates `shape` at the point with the given frame coordinates
+    fn remap(&self, shape: Tree, pu: Tree, pw: Tree, pa: Tree) -> Tree {
+        let coord = |i: usize| {
+            pu.clone() * self.u[i]
+                + pw.clone() * self.w[i]
+                + pa.clone() * self.a[i]
+        };
+        shape.remap_xyz(coord(0), coord(1), coord(2))
+    }
+}
+
 /// Rotates an object about an arbitrary axis and rotation center
 #[derive(Clone, Facet)]
 pub struct Rotate {
@@ -403,20 +780,10 @@ pub struct Rotate {
 
 impl From<Rotate> for Tree {
     fn from(v: Rotate) -> Self {
-        let shape = Tree::from(Move {
-            shape: v.shape,
-            offset: -v.center,
-        });
-        let d = -v.angle.to_radians();
-        let axis = v.axis.vec();
-        let shape = shape.remap_affine(nalgebra::convert(
-            nalgebra::Rotation3::<f64>::new(nalgebra::Vector3::from(d * *axis)),
-        ));
-        Move {
-            shape,
-            offset: v.center,
-        }
-        .into()
+        v.shape
+            .translate((-v.center).into())
+            .rotate_axis_angle((*v.axis.vec()).into(), v.angle.to_radians())
+            .translate(v.center.into())
     }
 }
 
@@ -501,6 +868,192 @@ impl From<RotateZ> for Tree {
     }
 }
 
+/// Stretches a shape along each axis, without distorting distances
+///
+/// The shape is split at the origin on each axis, and the two halves are
+/// pulled apart by `2 * extents`, with the gap filled by extruding the
+/// cross-section at the split.  This is implemented by folding each coordinate
+/// as `max(p - extents, 0) + min(p + extents, 0)` (equivalent to
+/// `p - clamp(p, -extents, extents)`), which references `p` once in each
+/// term, so interval evaluation remains tight.
+#[derive(Clone, Facet)]
+pub struct Elongate {
+    /// Shape to elongate
+    pub shape: Tree,
+    /// Half-length of the elongation on each axis
+    #[facet(default = Vec3::new(0.0, 0.0, 0.0))]
+    pub extents: Vec3,
+}
+
+impl From<Elongate> for Tree {
+    fn from(v: Elongate) -> Self {
+        let (x, y, z) = Tree::axes();
+        let fold = |t: Tree, h: f64| {
+            if h > 0.0 {
+                (t.clone() - h).max(0.0) + (t + h).min(0.0)
+            } else {
+                t
+            }
+        };
+        v.shape.remap_xyz(
+            fold(x, v.extents.x),
+            fold(y, v.extents.y),
+            fold(z, v.extents.z),
+        )
+    }
+}
+
+/// Turns a shape into a shell of the given thickness around its surface
+///
+/// The shell is centered on the original surface, so its walls extend by
+/// `thickness / 2` to each side.  Applying `Onion` repeatedly produces
+/// concentric shells.
+#[derive(Clone, Facet)]
+pub struct Onion {
+    /// Shape to hollow out
+    pub shape: Tree,
+    /// Thickness of the shell
+    #[facet(default = 0.1)]
+    pub thickness: f64,
+}
+
+impl From<Onion> for Tree {
+    fn from(v: Onion) -> Self {
+        v.shape.abs() - v.thickness / 2.0
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Warps
+//
+// Warps stretch space non-uniformly, so the warped field is no longer a
+// distance bound.  Each warp divides the result by an upper bound on the local
+// stretch (the Lipschitz factor of the coordinate mapping); this doesn't move
+// the surface, but keeps the field well-behaved for consumers that rely on
+// distances (e.g. raymarching or smooth blends).
+
+/// Twists a shape about an axis through the origin
+///
+/// Each cross-section orthogonal to the axis is rotated by `rate` degrees per
+/// unit of distance along the axis (following the right-hand rule).
+#[derive(Clone, Facet)]
+pub struct Twist {
+    /// Shape to twist
+    pub shape: Tree,
+    /// Axis about which to twist
+    #[facet(default = Axis::Z)]
+    pub axis: Axis,
+    /// Twist rate, in degrees per unit length
+    #[facet(default = 0.0)]
+    pub rate: f64,
+}
+
+impl From<Twist> for Tree {
+    fn from(v: Twist) -> Self {
+        if v.rate == 0.0 {
+            return v.shape;
+        }
+        let k = v.rate.to_radians();
+        let frame = AxisFrame::new(&v.axis);
+        let (pu, pw, pa) = frame.coords();
+
+        let angle = pa.clone() * -k;
+        let (c, s) = (angle.clone().cos(), angle.sin());
+        let r = (pu.square() + pw.square()).sqrt();
+        let u = pu.clone() * c.clone() - pw.clone() * s.clone();
+        let w = pu * s + pw * c;
+        frame.remap(v.shape, u, w, pa) / (1.0 + k.abs() * r)
+    }
+}
+
+/// Linearly scales the cross-section of a shape along an axis
+///
+/// At `base` (measured along the axis), the shape is unchanged; at
+/// `base + height`, its cross-section is scaled by `scale`.  The scale is
+/// extrapolated linearly beyond that range, and clamped to remain positive.
+#[derive(Clone, Facet)]
+pub struct Taper {
+    /// Shape to taper
+    pub shape: Tree,
+    /// Axis along which to taper
+    #[facet(default = Axis::Z)]
+    pub axis: Axis,
+    /// Position along the axis where the scale is 1
+    #[facet(default = 0.0)]
+    pub base: f64,
+    /// Distance along the axis over which the scale changes to `scale`
+    #[facet(default = 1.0)]
+    pub height: f64,
+    /// Scale at `base + height`
+    #[facet(default = 1.0)]
+    pub scale: f64,
+}
+
+impl From<Taper> for Tree {
+    fn from(v: Taper) -> Self {
+        if v.scale == 1.0 || v.height == 0.0 {
+            return v.shape;
+        }
+        let slope = (v.scale - 1.0) / v.height;
+        let frame = AxisFrame::new(&v.axis);
+        let (pu, pw, pa) = frame.coords();
+
+        let s = ((pa.clone() - v.base) * slope + 1.0).max(1e-6);
+        let r = (pu.square() + pw.square()).sqrt();
+        let out = frame.remap(v.shape, pu / s.clone(), pw / s.clone(), pa);
+
+        // The Jacobian of the mapping is `diag(1/s, 1/s, 1)`, plus a rank-one
+        // term of magnitude `r * |slope| / s²`
+        let stretch = s.recip().max(1.0) + r * slope.abs() / s.square();
+        out / stretch
+    }
+}
+
+/// Smallest distance correction applied by [`Bend`], near its center
+const BEND_MIN_SCALE: f64 = 1e-3;
+
+/// Bends a shape's X axis into a circular arc in the XY plane
+///
+/// The origin is fixed, and the X axis is wrapped around a circle of the given
+/// radius with its center at `(0, radius)`, so positive radii bend the shape
+/// towards +Y and negative radii bend it towards -Y.  Lengths along the X axis
+/// are preserved; parts of the shape further than `π * |radius|` from the
+/// origin along X overlap on the far side of the circle.
+///
+/// A radius of zero leaves the shape unchanged.
+#[derive(Clone, Facet)]
+pub struct Bend {
+    /// Shape to bend
+    pub shape: Tree,
+    /// Bend radius
+    #[facet(default = 0.0)]
+    pub radius: f64,
+}
+
+impl From<Bend> for Tree {
+    fn from(v: Bend) -> Self {
+        if v.radius == 0.0 {
+            return v.shape;
+        }
+        let (x, y, z) = Tree::axes();
+        let sign = v.radius.signum();
+        let dy = v.radius - y;
+        let rho = (x.square() + dy.square()).sqrt();
+        let angle = (x * sign).atan2(dy * sign);
+        let out = v.shape.remap_xyz(
+            angle * v.radius,
+            v.radius - rho.clone() * sign,
+            z,
+        );
+
+        // The mapping stretches arcs by `radius / rho` and preserves radial
+        // distances, so it's locally Lipschitz with factor `max(1, r / rho)`.
+        // That factor is unbounded at the bend's center, so the correction is
+        // clamped to keep the field nonzero (and its sign meaningful) there.
+        out * (rho / v.radius.abs()).max(BEND_MIN_SCALE).min(1.0)
+    }
+}
+
 // TODO figure out a generic Revolve?  The matrix math is a bit tricky!
 
 /// Revolve a shape about the Y axis, creating a 3D volume
@@ -600,6 +1153,11 @@ pub fn visit_shapes<V: ShapeVisitor>(visitor: &mut V) {
     visitor.visit::<ReflectX>();
     visitor.visit::<ReflectY>();
     visitor.visit::<ReflectZ>();
+    visitor.visit::<Mirror>();
+    visitor.visit::<MirrorX>();
+    visitor.visit::<MirrorY>();
+    visitor.visit::<MirrorZ>();
+    visitor.visit::<RadialSymmetry>();
     visitor.visit::<Rotate>();
     visitor.visit::<RotateX>();
     visitor.visit::<RotateY>();
@@ -607,9 +1165,22 @@ pub fn visit_shapes<V: ShapeVisitor>(visitor: &mut V) {
     visitor.visit::<RevolveY>();
     visitor.visit::<ExtrudeZ>();
     visitor.visit::<LoftZ>();
+    visitor.visit::<Elongate>();
+    visitor.visit::<Onion>();
+    visitor.visit::<Twist>();
+    visitor.visit::<Taper>();
+    visitor.visit::<Bend>();
 
     visitor.visit::<Union>();
     visitor.visit::<Blend>();
+    visitor.visit::<FilletUnion>();
+    visitor.visit::<FilletIntersection>();
+    visitor.visit::<FilletDifference>();
+    visitor.visit::<ChamferUnion>();
+    visitor.visit::<ChamferIntersection>();
+    visitor.visit::<ChamferDifference>();
+    visitor.visit::<Morph>();
+    visitor.visit::<SmoothMorph>();
     visitor.visit::<Intersection>();
     visitor.visit::<Difference>();
     visitor.visit::<Inverse>();
@@ -621,7 +1192,7 @@ pub fn visit_shapes<V: ShapeVisitor>(visitor: &mut V) {
 mod test {
     use super::*;
     use crate::types::eval_default_fn;
-    use fidget_core::Context;
+    use fidget_core::{Context, types::Interval};
 
     #[test]
     fn circle_docstring() {
@@ -654,6 +1225,359 @@ mod test {
         assert_eq!(ctx.eval_xyz(cr, 0.0, 1.0, 0.0).unwrap(), 2.0);
     }
 
+    #[test]
+    fn fillet_chamfer() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        let mut ctx = Context::new();
+
+        // Two half-spaces (x < 0 and y < 0), meeting at a right angle
+        let (x, y, _) = Tree::axes();
+        let mut eval = |t: Tree, px, py| {
+            let n = ctx.import(&t);
+            ctx.eval_xyz(n, px, py, 0.0).unwrap()
+        };
+
+        // Away from the seam, all operators match the sharp versions
+        let t: Tree = FilletUnion {
+            a: x.clone(),
+            b: y.clone(),
+            radius: 1.0,
+        }
+        .into();
+        assert_eq!(eval(t.clone(), -3.0, 5.0), -3.0);
+        // The fillet is a quarter circle centered at (1, 1)
+        let d = 1.0 - 0.5f64.sqrt();
+        assert!(close(eval(t.clone(), d, d), 0.0));
+        assert!(close(eval(t, 1.0, 1.0), 1.0));
+
+        let t: Tree = FilletIntersection {
+            a: x.clone(),
+            b: y.clone(),
+            radius: 1.0,
+        }
+        .into();
+        assert_eq!(eval(t.clone(), -5.0, 3.0), 3.0);
+        assert!(close(eval(t, -d, -d), 0.0));
+
+        let t: Tree = FilletDifference {
+            shape: x.clone(),
+            cutout: -y.clone(),
+            radius: 1.0,
+        }
+        .into();
+        assert!(close(eval(t, -d, -d), 0.0));
+
+        // The chamfer cuts the corner along the line x + y = 1
+        let t: Tree = ChamferUnion {
+            a: x.clone(),
+            b: y.clone(),
+            width: 1.0,
+        }
+        .into();
+        assert_eq!(eval(t.clone(), -3.0, 5.0), -3.0);
+        assert!(close(eval(t.clone(), 0.5, 0.5), 0.0));
+        assert!(close(eval(t, 1.0, 1.0), 0.5f64.sqrt()));
+
+        let t: Tree = ChamferIntersection {
+            a: x.clone(),
+            b: y.clone(),
+            width: 1.0,
+        }
+        .into();
+        assert_eq!(eval(t.clone(), -5.0, 3.0), 3.0);
+        assert!(close(eval(t, -0.5, -0.5), 0.0));
+
+        let t: Tree = ChamferDifference {
+            shape: x,
+            cutout: -y,
+            width: 1.0,
+        }
+        .into();
+        assert!(close(eval(t, -0.5, -0.5), 0.0));
+    }
+
+    #[test]
+    fn morph() {
+        use fidget_core::var::Var;
+        use std::collections::HashMap;
+
+        let t = Var::new();
+        let a = Tree::x() - 1.0;
+        let b = Tree::x() + 1.0;
+        let mut ctx = Context::new();
+        let linear = ctx.import(&Tree::from(Morph {
+            a: a.clone(),
+            b: b.clone(),
+            t: t.into(),
+        }));
+        let smooth = ctx.import(&Tree::from(SmoothMorph { a, b, t: t.into() }));
+
+        let eval = |n, tv| {
+            let vars = HashMap::from([(Var::X, 0.0), (t, tv)]);
+            ctx.eval(n, &vars).unwrap()
+        };
+        assert_eq!(eval(linear, 0.0), -1.0);
+        assert_eq!(eval(linear, 0.25), -0.5);
+        assert_eq!(eval(linear, 1.0), 1.0);
+        assert_eq!(eval(linear, 2.0), 3.0);
+
+        assert_eq!(eval(smooth, 0.0), -1.0);
+        assert_eq!(eval(smooth, 0.5), 0.0);
+        assert_eq!(eval(smooth, 1.0), 1.0);
+        assert_eq!(eval(smooth, 2.0), 1.0);
+        assert_eq!(eval(smooth, -1.0), -1.0);
+        assert!(eval(smooth, 0.25) < eval(linear, 0.25));
+    }
+
+    #[test]
+    fn elongate_onion() {
+        let mut ctx = Context::new();
+        let (x, y, z) = Tree::axes();
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+
+        let t: Tree = Elongate {
+            shape: sphere.clone(),
+            extents: Vec3::new(2.0, 0.0, 0.0),
+        }
+        .into();
+        let e = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(e, 0.0, 0.0, 0.0).unwrap(), -1.0);
+        assert_eq!(ctx.eval_xyz(e, 1.5, 0.0, 0.0).unwrap(), -1.0);
+        assert_eq!(ctx.eval_xyz(e, -3.0, 0.0, 0.0).unwrap(), 0.0);
+        assert_eq!(ctx.eval_xyz(e, 4.0, 0.0, 0.0).unwrap(), 1.0);
+        assert_eq!(ctx.eval_xyz(e, 1.0, 2.0, 0.0).unwrap(), 1.0);
+
+        // The folded coordinate's interval is tight
+        let t: Tree = Elongate {
+            shape: Tree::x(),
+            extents: Vec3::new(2.0, 0.0, 0.0),
+        }
+        .into();
+        let i = eval_interval(t, [-5.0, 5.0], [0.0; 2], [0.0; 2]);
+        assert_eq!(i, Interval::new(-3.0, 3.0));
+        let t: Tree = Elongate {
+            shape: Tree::x(),
+            extents: Vec3::new(2.0, 0.0, 0.0),
+        }
+        .into();
+        let i = eval_interval(t, [-1.0, 3.0], [0.0; 2], [0.0; 2]);
+        assert_eq!(i, Interval::new(0.0, 1.0));
+
+        let t: Tree = Onion {
+            shape: sphere,
+            thickness: 0.5,
+        }
+        .into();
+        let o = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(o, 1.0, 0.0, 0.0).unwrap(), -0.25);
+        assert_eq!(ctx.eval_xyz(o, 0.0, 0.0, 0.0).unwrap(), 0.75);
+        assert_eq!(ctx.eval_xyz(o, 0.0, 3.0, 0.0).unwrap(), 1.75);
+    }
+
+    #[test]
+    fn warps() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        let mut ctx = Context::new();
+
+        // A 90° twist over one unit of Z turns X into Y
+        let t: Tree = Twist {
+            shape: Tree::x(),
+            axis: Axis::Z,
+            rate: 90.0,
+        }
+        .into();
+        let n = ctx.import(&t);
+        let stretch = 1.0 + std::f64::consts::FRAC_PI_2;
+        assert!(close(
+            ctx.eval_xyz(n, 1.0, 0.0, 0.0).unwrap(),
+            1.0 / stretch
+        ));
+        assert!(close(ctx.eval_xyz(n, 1.0, 0.0, 1.0).unwrap(), 0.0));
+        assert!(close(
+            ctx.eval_xyz(n, 0.0, 1.0, 1.0).unwrap(),
+            1.0 / stretch
+        ));
+
+        // Doubling the cross-section of a unit cylinder over one unit of Z
+        let (x, y, _) = Tree::axes();
+        let t: Tree = Taper {
+            shape: (x.square() + y.square()).sqrt() - 1.0,
+            axis: Axis::Z,
+            base: 0.0,
+            height: 1.0,
+            scale: 2.0,
+        }
+        .into();
+        let n = ctx.import(&t);
+        assert!(close(ctx.eval_xyz(n, 1.0, 0.0, 0.0).unwrap(), 0.0));
+        assert!(close(ctx.eval_xyz(n, 2.0, 0.0, 1.0).unwrap(), 0.0));
+        assert!(close(ctx.eval_xyz(n, 0.0, 1.5, 0.5).unwrap(), 0.0));
+        assert!(ctx.eval_xyz(n, 0.0, 1.5, 0.0).unwrap() > 0.0);
+        assert!(ctx.eval_xyz(n, 0.0, 1.5, 1.0).unwrap() < 0.0);
+
+        // Bending a horizontal slab (|y| < 0.5) into a ring of radius 2
+        let t: Tree = Bend {
+            shape: Tree::y().abs() - 0.5,
+            radius: 2.0,
+        }
+        .into();
+        let n = ctx.import(&t);
+        assert!(close(ctx.eval_xyz(n, 0.0, 0.0, 0.0).unwrap(), -0.5));
+        assert!(close(ctx.eval_xyz(n, 2.0, 2.0, 0.0).unwrap(), -0.5));
+        assert!(close(ctx.eval_xyz(n, 0.0, 4.0, 0.0).unwrap(), -0.5));
+        assert!(close(ctx.eval_xyz(n, 2.5, 2.0, 0.0).unwrap(), 0.0));
+        // At the center, the field is scaled down but keeps its sign
+        let v = ctx.eval_xyz(n, 0.0, 2.0, 0.0).unwrap();
+        assert!(v > 0.0 && v < 1.5);
+        assert!(ctx.eval_xyz(n, 0.0, 1.0, 0.0).unwrap() > 0.0);
+
+        // A thick slab (|y| < 3) covers the center, which is inside
+        let t: Tree = Bend {
+            shape: Tree::y().abs() - 3.0,
+            radius: 2.0,
+        }
+        .into();
+        let n = ctx.import(&t);
+        assert!(ctx.eval_xyz(n, 0.0, 2.0, 0.0).unwrap() < 0.0);
+        assert!(ctx.eval_xyz(n, 0.0, 8.0, 0.0).unwrap() > 0.0);
+
+        let t: Tree = Bend {
+            shape: Tree::y().abs() - 0.5,
+            radius: -2.0,
+        }
+        .into();
+        let n = ctx.import(&t);
+        assert!(close(ctx.eval_xyz(n, 2.0, -2.0, 0.0).unwrap(), -0.5));
+        assert!(close(ctx.eval_xyz(n, 0.0, -4.0, 0.0).unwrap(), -0.5));
+    }
+
+    #[test]
+    fn mirror() {
+        let mut ctx = Context::new();
+        let t: Tree = MirrorX {
+            shape: Tree::x() + Tree::y(),
+            offset: 1.0,
+        }
+        .into();
+        let m = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(m, 3.0, 0.0, 0.0).unwrap(), 3.0);
+        assert_eq!(ctx.eval_xyz(m, -1.0, 0.0, 0.0).unwrap(), 3.0);
+        assert_eq!(ctx.eval_xyz(m, -1.0, 2.0, 0.0).unwrap(), 5.0);
+
+        let t: Tree = MirrorZ {
+            shape: Tree::z(),
+            offset: 0.0,
+        }
+        .into();
+        let m = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(m, 0.0, 0.0, -2.0).unwrap(), 2.0);
+        assert_eq!(ctx.eval_xyz(m, 0.0, 0.0, 2.0).unwrap(), 2.0);
+
+        // Mirroring about a plane with a negative axis keeps the negative side
+        let t: Tree = Mirror {
+            shape: Tree::x(),
+            plane: Plane {
+                axis: Axis::try_from(Vec3::new(-1.0, 0.0, 0.0)).unwrap(),
+                offset: 1.0,
+            },
+        }
+        .into();
+        let m = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(m, -3.0, 0.0, 0.0).unwrap(), -3.0);
+        assert_eq!(ctx.eval_xyz(m, 1.0, 0.0, 0.0).unwrap(), -3.0);
+
+        // Off-axis planes use the general form
+        let t: Tree = Mirror {
+            shape: Tree::x() * 2.0 + Tree::y(),
+            plane: Plane {
+                axis: Axis::try_from(Vec3::new(1.0, 1.0, 0.0)).unwrap(),
+                offset: 0.0,
+            },
+        }
+        .into();
+        let m = ctx.import(&t);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        assert!(close(ctx.eval_xyz(m, 1.0, 0.0, 0.0).unwrap(), 2.0));
+        assert!(close(ctx.eval_xyz(m, -1.0, 0.0, 0.0).unwrap(), 1.0));
+    }
+
+    /// Evaluates a tree over an interval region
+    fn eval_interval(
+        t: Tree,
+        x: [f32; 2],
+        y: [f32; 2],
+        z: [f32; 2],
+    ) -> Interval {
+        use fidget_core::{shape::EzShape, vm::VmShape};
+        let shape = VmShape::from(t);
+        let tape = shape.ez_interval_tape();
+        let mut eval = VmShape::new_interval_eval();
+        let (out, _) = eval
+            .eval(
+                &tape,
+                Interval::new(x[0], x[1]),
+                Interval::new(y[0], y[1]),
+                Interval::new(z[0], z[1]),
+            )
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn mirror_interval() {
+        // The mirrored coordinate ranges from 0 to 2
+        let t: Tree = MirrorX {
+            shape: Tree::x(),
+            offset: 0.0,
+        }
+        .into();
+        let i = eval_interval(t, [-2.0, 1.0], [0.0; 2], [0.0; 2]);
+        assert_eq!(i, Interval::new(0.0, 2.0));
+
+        let t: Tree = MirrorY {
+            shape: Tree::y(),
+            offset: 1.0,
+        }
+        .into();
+        let i = eval_interval(t, [0.0; 2], [0.5, 1.5], [0.0; 2]);
+        assert_eq!(i, Interval::new(1.0, 1.5));
+    }
+
+    #[test]
+    fn radial_symmetry() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        let mut ctx = Context::new();
+
+        // Distance to a point at (1, 0, 0), repeated four times about Z
+        let (x, y, z) = Tree::axes();
+        let point = ((x - 1.0).square() + y.square() + z.square()).sqrt();
+        let t: Tree = RadialSymmetry {
+            shape: point,
+            count: 4.0,
+            axis: Axis::Z,
+        }
+        .into();
+        let r = ctx.import(&t);
+        for (x, y) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+            assert!(close(ctx.eval_xyz(r, x, y, 0.0).unwrap(), 0.0));
+        }
+        assert!(close(ctx.eval_xyz(r, 0.0, 0.0, 0.0).unwrap(), 1.0));
+        let d = ctx.eval_xyz(r, 0.5f64.sqrt(), 0.5f64.sqrt(), 0.0).unwrap();
+        assert!(close(d, (2.0 - 2.0 * 0.5f64.sqrt()).sqrt()));
+
+        // About the X axis, the reference direction is +Y
+        let t: Tree = RadialSymmetry {
+            shape: Tree::y(),
+            count: 2.0,
+            axis: Axis::X,
+        }
+        .into();
+        let r = ctx.import(&t);
+        assert!(close(ctx.eval_xyz(r, 5.0, 2.0, 0.0).unwrap(), 2.0));
+        assert!(close(ctx.eval_xyz(r, 5.0, -2.0, 0.0).unwrap(), 2.0));
+        assert!(close(ctx.eval_xyz(r, 5.0, 0.0, 1.0).unwrap(), 0.0));
+    }
+
     #[test]
     fn scale_default_fn() {
         let facet::Type::User(facet::UserType::Struct(s)) = Scale::SHAPE.ty
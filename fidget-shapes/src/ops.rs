@@ -0,0 +1,344 @@
+//! Functional CSG combinators
+//!
+//! These are thin wrappers around the shapes in the [parent module](crate),
+//! for use when building models directly in Rust:
+//!
+//! ```
+//! use fidget_core::context::Tree;
+//! use fidget_shapes::ops;
+//!
+//! let (x, y, z) = Tree::axes();
+//! let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+//! let slab = z.abs() - 0.5;
+//! let shape = ops::difference(ops::smooth_union(sphere, slab, 0.1), x - 0.5);
+//! # let _ = shape;
+//! ```
+//!
+//! Each function returns a [`Tree`], which can be imported into a
+//! [`Context`](fidget_core::Context) with
+//! [`Context::import`](fidget_core::Context::import).
+use crate::{
+    Bend, Blend, ChamferDifference, ChamferIntersection, ChamferUnion,
+    Difference, Elongate, FilletDifference, FilletIntersection, FilletUnion,
+    Intersection, MirrorX, MirrorY, MirrorZ, Morph, Onion, RadialSymmetry,
+    SmoothMorph, Taper, Twist, Union,
+    types::{Axis, Vec3},
+};
+use fidget_core::context::Tree;
+
+/// Returns the union of two shapes
+pub fn union<A: Into<Tree>, B: Into<Tree>>(a: A, b: B) -> Tree {
+    Union {
+        input: vec![a.into(), b.into()],
+    }
+    .into()
+}
+
+/// Returns the intersection of two shapes
+pub fn intersection<A: Into<Tree>, B: Into<Tree>>(a: A, b: B) -> Tree {
+    Intersection {
+        input: vec![a.into(), b.into()],
+    }
+    .into()
+}
+
+/// Subtracts `cutout` from `shape`
+pub fn difference<A: Into<Tree>, B: Into<Tree>>(shape: A, cutout: B) -> Tree {
+    Difference {
+        shape: shape.into(),
+        cutout: cutout.into(),
+    }
+    .into()
+}
+
+/// Returns a smooth union of two shapes, with the given blending radius
+///
+/// See [`Blend`] for details; a radius of zero is equivalent to [`union`].
+pub fn smooth_union<A: Into<Tree>, B: Into<Tree>>(
+    a: A,
+    b: B,
+    radius: f64,
+) -> Tree {
+    Blend {
+        a: a.into(),
+        b: b.into(),
+        radius,
+    }
+    .into()
+}
+
+/// Returns the union of two shapes, with a circular fillet along the seam
+///
+/// See [`FilletUnion`] for details.
+pub fn fillet_union<A: Into<Tree>, B: Into<Tree>>(
+    a: A,
+    b: B,
+    radius: f64,
+) -> Tree {
+    FilletUnion {
+        a: a.into(),
+        b: b.into(),
+        radius,
+    }
+    .into()
+}
+
+/// Returns the intersection of two shapes, with a circular fillet
+pub fn fillet_intersection<A: Into<Tree>, B: Into<Tree>>(
+    a: A,
+    b: B,
+    radius: f64,
+) -> Tree {
+    FilletIntersection {
+        a: a.into(),
+        b: b.into(),
+        radius,
+    }
+    .into()
+}
+
+/// Subtracts `cutout` from `shape`, with a circular fillet along the cut
+pub fn fillet_difference<A: Into<Tree>, B: Into<Tree>>(
+    shape: A,
+    cutout: B,
+    radius: f64,
+) -> Tree {
+    FilletDifference {
+        shape: shape.into(),
+        cutout: cutout.into(),
+        radius,
+    }
+    .into()
+}
+
+/// Returns the union of two shapes, with a 45° chamfer along the seam
+///
+/// See [`ChamferUnion`] for details.
+pub fn chamfer_union<A: Into<Tree>, B: Into<Tree>>(
+    a: A,
+    b: B,
+    width: f64,
+) -> Tree {
+    ChamferUnion {
+        a: a.into(),
+        b: b.into(),
+        width,
+    }
+    .into()
+}
+
+/// Returns the intersection of two shapes, with a 45° chamfer
+pub fn chamfer_intersection<A: Into<Tree>, B: Into<Tree>>(
+    a: A,
+    b: B,
+    width: f64,
+) -> Tree {
+    ChamferIntersection {
+        a: a.into(),
+        b: b.into(),
+        width,
+    }
+    .into()
+}
+
+/// Subtracts `cutout` from `shape`, with a 45° chamfer along the cut
+pub fn chamfer_difference<A: Into<Tree>, B: Into<Tree>>(
+    shape: A,
+    cutout: B,
+    width: f64,
+) -> Tree {
+    ChamferDifference {
+        shape: shape.into(),
+        cutout: cutout.into(),
+        width,
+    }
+    .into()
+}
+
+/// Linearly interpolates between two shapes
+///
+/// This is an alias for [`morph`], which builds a [`Morph`] shape; see that
+/// type for details.
+pub fn blend<A: Into<Tree>, B: Into<Tree>, T: Into<Tree>>(
+    a: A,
+    b: B,
+    t: T,
+) -> Tree {
+    morph(a, b, t)
+}
+
+/// Linearly interpolates between two shapes
+///
+/// See [`Morph`] for details.
+pub fn morph<A: Into<Tree>, B: Into<Tree>, T: Into<Tree>>(
+    a: A,
+    b: B,
+    t: T,
+) -> Tree {
+    Morph {
+        a: a.into(),
+        b: b.into(),
+        t: t.into(),
+    }
+    .into()
+}
+
+/// Smoothly interpolates between two shapes, clamping `t` to `[0, 1]`
+///
+/// See [`SmoothMorph`] for details.
+pub fn smooth_morph<A: Into<Tree>, B: Into<Tree>, T: Into<Tree>>(
+    a: A,
+    b: B,
+    t: T,
+) -> Tree {
+    SmoothMorph {
+        a: a.into(),
+        b: b.into(),
+        t: t.into(),
+    }
+    .into()
+}
+
+/// Mirrors the `x >= offset` half of a shape onto the other side
+pub fn mirror_x<A: Into<Tree>>(shape: A, offset: f64) -> Tree {
+    MirrorX {
+        shape: shape.into(),
+        offset,
+    }
+    .into()
+}
+
+/// Mirrors the `y >= offset` half of a shape onto the other side
+pub fn mirror_y<A: Into<Tree>>(shape: A, offset: f64) -> Tree {
+    MirrorY {
+        shape: shape.into(),
+        offset,
+    }
+    .into()
+}
+
+/// Mirrors the `z >= offset` half of a shape onto the other side
+pub fn mirror_z<A: Into<Tree>>(shape: A, offset: f64) -> Tree {
+    MirrorZ {
+        shape: shape.into(),
+        offset,
+    }
+    .into()
+}
+
+/// Repeats one sector of a shape `n` times about an axis through the origin
+///
+/// See [`RadialSymmetry`] for which sector is used.
+pub fn radial_symmetry<A: Into<Tree>>(shape: A, n: usize, axis: Axis) -> Tree {
+    RadialSymmetry {
+        shape: shape.into(),
+        count: n as f64,
+        axis,
+    }
+    .into()
+}
+
+/// Stretches a shape by `extents` (half-lengths) along each axis
+///
+/// See [`Elongate`] for details.
+pub fn elongate<A: Into<Tree>>(shape: A, extents: Vec3) -> Tree {
+    Elongate {
+        shape: shape.into(),
+        extents,
+    }
+    .into()
+}
+
+/// Turns a shape into a shell of the given thickness
+///
+/// See [`Onion`] for details.
+pub fn onion<A: Into<Tree>>(shape: A, thickness: f64) -> Tree {
+    Onion {
+        shape: shape.into(),
+        thickness,
+    }
+    .into()
+}
+
+/// Twists a shape about an axis, at `rate` degrees per unit length
+///
+/// See [`Twist`] for details.
+pub fn twist<A: Into<Tree>>(shape: A, axis: Axis, rate: f64) -> Tree {
+    Twist {
+        shape: shape.into(),
+        axis,
+        rate,
+    }
+    .into()
+}
+
+/// Scales a shape's cross-section along the Z axis
+///
+/// The shape is unchanged at `z = 0`, and scaled by `scale` at `z = height`;
+/// see [`Taper`] for more options.
+pub fn taper<A: Into<Tree>>(shape: A, height: f64, scale: f64) -> Tree {
+    Taper {
+        shape: shape.into(),
+        axis: Axis::Z,
+        base: 0.0,
+        height,
+        scale,
+    }
+    .into()
+}
+
+/// Bends a shape's X axis into an arc of the given radius
+///
+/// See [`Bend`] for details.
+pub fn bend<A: Into<Tree>>(shape: A, radius: f64) -> Tree {
+    Bend {
+        shape: shape.into(),
+        radius,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fidget_core::Context;
+
+    fn eval(t: Tree, x: f64) -> f64 {
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        ctx.eval_xyz(root, x, 0.0, 0.0).unwrap()
+    }
+
+    #[test]
+    fn csg_ops() {
+        // Intervals [-1, 1] and [0, 2] along the X axis
+        let a = || Tree::x().abs() - 1.0;
+        let b = || (Tree::x() - 1.0).abs() - 1.0;
+
+        assert_eq!(eval(union(a(), b()), -0.5), -0.5);
+        assert_eq!(eval(union(a(), b()), 1.5), -0.5);
+        assert_eq!(eval(intersection(a(), b()), 0.5), -0.5);
+        assert_eq!(eval(intersection(a(), b()), -0.5), 0.5);
+        assert_eq!(eval(difference(a(), b()), -0.5), -0.5);
+        assert_eq!(eval(difference(a(), b()), 0.5), 0.5);
+
+        // Far from the seam, smooth union matches union
+        assert_eq!(eval(smooth_union(a(), b(), 0.1), -0.5), -0.5);
+        // Near the seam, it's strictly smaller
+        assert!(eval(smooth_union(a(), b(), 0.5), 0.5) < -0.5);
+        assert_eq!(eval(smooth_union(a(), b(), 0.0), 0.5), -0.5);
+
+        assert_eq!(eval(blend(a(), b(), 0.0), 0.5), -0.5);
+        assert_eq!(eval(blend(a(), b(), 1.0), 0.5), -0.5);
+        assert_eq!(eval(blend(a(), b(), 0.5), -0.5), 0.0);
+        assert_eq!(eval(blend(a(), b(), Tree::y()), -0.5), -0.5);
+        assert_eq!(
+            blend(a(), b(), Tree::y()),
+            Tree::from(Morph {
+                a: a(),
+                b: b(),
+                t: Tree::y()
+            })
+        );
+    }
+}
@@ -0,0 +1,38 @@
+// Shared helpers for generated shaders
+//
+// WGSL forbids NaN and infinite values in constant expressions, and lets
+// implementations assume that floating-point values are finite; special values
+// are therefore built at runtime from their bit patterns, and NaN checks are
+// done on bits rather than with comparisons.
+
+fn from_bits(b: u32) -> f32 {
+    return bitcast<f32>(b);
+}
+
+fn nan() -> f32 {
+    return from_bits(0x7fc00000u);
+}
+
+fn inf() -> f32 {
+    return from_bits(0x7f800000u);
+}
+
+fn is_nan(v: f32) -> bool {
+    return (bitcast<u32>(v) & 0x7fffffffu) > 0x7f800000u;
+}
+
+// Rounds half-way cases away from zero, like `f32::round`
+fn round_away(a: f32) -> f32 {
+    let t = trunc(a);
+    if abs(a - t) >= 0.5 {
+        return t + sign(a);
+    }
+    return t;
+}
+
+struct Params {
+    // Number of items to evaluate
+    count: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
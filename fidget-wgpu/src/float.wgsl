@@ -0,0 +1,82 @@
+// Float evaluation helpers, matching the semantics of `VmFloatSliceEval`
+
+fn f_recip(a: f32) -> f32 {
+    return 1.0 / a;
+}
+
+fn f_sqrt(a: f32) -> f32 {
+    if a < 0.0 || is_nan(a) {
+        return nan();
+    }
+    return sqrt(a);
+}
+
+fn f_square(a: f32) -> f32 {
+    return a * a;
+}
+
+fn f_asin(a: f32) -> f32 {
+    if a < -1.0 || a > 1.0 || is_nan(a) {
+        return nan();
+    }
+    return asin(a);
+}
+
+fn f_acos(a: f32) -> f32 {
+    if a < -1.0 || a > 1.0 || is_nan(a) {
+        return nan();
+    }
+    return acos(a);
+}
+
+fn f_ln(a: f32) -> f32 {
+    if a < 0.0 || is_nan(a) {
+        return nan();
+    } else if a == 0.0 {
+        return -inf();
+    }
+    return log(a);
+}
+
+fn f_not(a: f32) -> f32 {
+    return select(0.0, 1.0, a == 0.0 && !is_nan(a));
+}
+
+fn f_min(a: f32, b: f32) -> f32 {
+    if is_nan(a) || is_nan(b) {
+        return nan();
+    }
+    return min(a, b);
+}
+
+fn f_max(a: f32, b: f32) -> f32 {
+    if is_nan(a) || is_nan(b) {
+        return nan();
+    }
+    return max(a, b);
+}
+
+fn f_compare(a: f32, b: f32) -> f32 {
+    if is_nan(a) || is_nan(b) {
+        return nan();
+    } else if a < b {
+        return -1.0;
+    } else if a > b {
+        return 1.0;
+    }
+    return 0.0;
+}
+
+// Least non-negative remainder, like `f32::rem_euclid`
+fn f_mod(a: f32, b: f32) -> f32 {
+    let r = a % b;
+    return select(r, r + abs(b), r < 0.0);
+}
+
+fn f_and(a: f32, b: f32) -> f32 {
+    return select(b, a, a == 0.0 && !is_nan(a));
+}
+
+fn f_or(a: f32, b: f32) -> f32 {
+    return select(b, a, a != 0.0 || is_nan(a));
+}
@@ -0,0 +1,337 @@
+// Interval evaluation helpers, matching the semantics of `Interval`
+//
+// Intervals are stored as `vec2<f32>(lower, upper)`.  Basic arithmetic is not
+// outward-rounded (like `Interval` without the `outward-rounding` feature),
+// but transcendental functions are widened to cover the GPU's looser
+// precision requirements.
+
+// Choice values, matching `fidget_core::vm::Choice`
+const CHOICE_LEFT: u32 = 1u;
+const CHOICE_RIGHT: u32 = 2u;
+const CHOICE_BOTH: u32 = 3u;
+
+const PI: f32 = 3.14159265358979;
+const TAU: f32 = 6.28318530717959;
+
+// Widening for trigonometric functions (absolute error of 2^-11)
+const TRIG_ERR: f32 = 1e-3;
+
+// Widening for exponential and logarithmic functions
+const EXP_ERR: f32 = 1e-5;
+
+struct IChoice {
+    v: vec2<f32>,
+    c: u32,
+}
+
+fn i_imm(b: u32) -> vec2<f32> {
+    let v = from_bits(b);
+    return vec2<f32>(v, v);
+}
+
+fn i_nan() -> vec2<f32> {
+    return vec2<f32>(nan(), nan());
+}
+
+fn i_has_nan(a: vec2<f32>) -> bool {
+    return is_nan(a.x) || is_nan(a.y);
+}
+
+fn i_contains_zero(a: vec2<f32>) -> bool {
+    return a.x <= 0.0 && a.y >= 0.0;
+}
+
+// Widens the interval by `err`, relative to its bounds (but at least `err`)
+fn i_outward(a: vec2<f32>, err: f32) -> vec2<f32> {
+    if i_has_nan(a) {
+        return a;
+    }
+    return vec2<f32>(
+        a.x - err * max(1.0, abs(a.x)),
+        a.y + err * max(1.0, abs(a.y)),
+    );
+}
+
+fn i_neg(a: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(-a.y, -a.x);
+}
+
+fn i_abs(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) {
+        return i_nan();
+    } else if a.x < 0.0 {
+        if a.y > 0.0 {
+            return vec2<f32>(0.0, max(a.y, -a.x));
+        }
+        return vec2<f32>(-a.y, -a.x);
+    }
+    return a;
+}
+
+fn i_square(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) {
+        return i_nan();
+    } else if a.y < 0.0 {
+        return vec2<f32>(a.y * a.y, a.x * a.x);
+    } else if a.x > 0.0 {
+        return vec2<f32>(a.x * a.x, a.y * a.y);
+    }
+    let m = max(abs(a.x), abs(a.y));
+    return vec2<f32>(0.0, m * m);
+}
+
+fn i_recip(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || i_contains_zero(a) {
+        return i_nan();
+    }
+    return vec2<f32>(1.0 / a.y, 1.0 / a.x);
+}
+
+fn i_sqrt(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || a.x < 0.0 {
+        return i_nan();
+    }
+    return vec2<f32>(sqrt(a.x), sqrt(a.y));
+}
+
+fn i_floor(a: vec2<f32>) -> vec2<f32> {
+    return floor(a);
+}
+
+fn i_ceil(a: vec2<f32>) -> vec2<f32> {
+    return ceil(a);
+}
+
+fn i_round(a: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(round_away(a.x), round_away(a.y));
+}
+
+// Range of a periodic function with period TAU, given its values at the
+// endpoints of `a` and the phases at which it reaches its maximum and minimum
+fn i_periodic(
+    a: vec2<f32>,
+    lo: f32,
+    hi: f32,
+    max_phase: f32,
+    min_phase: f32,
+) -> vec2<f32> {
+    if i_has_nan(a) {
+        return i_nan();
+    } else if a.y - a.x >= TAU {
+        return vec2<f32>(-1.0, 1.0);
+    }
+    var out = vec2<f32>(min(lo, hi), max(lo, hi));
+    if max_phase + ceil((a.x - max_phase) / TAU) * TAU <= a.y {
+        out.y = 1.0;
+    }
+    if min_phase + ceil((a.x - min_phase) / TAU) * TAU <= a.y {
+        out.x = -1.0;
+    }
+    return clamp(i_outward(out, TRIG_ERR), vec2(-1.0), vec2(1.0));
+}
+
+fn i_sin(a: vec2<f32>) -> vec2<f32> {
+    return i_periodic(a, sin(a.x), sin(a.y), PI / 2.0, -PI / 2.0);
+}
+
+fn i_cos(a: vec2<f32>) -> vec2<f32> {
+    return i_periodic(a, cos(a.x), cos(a.y), 0.0, PI);
+}
+
+fn i_tan(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || a.y - a.x >= PI {
+        return i_nan();
+    }
+    let lo = tan(a.x);
+    let hi = tan(a.y);
+    if hi < lo {
+        // The interval spans an asymptote
+        return i_nan();
+    }
+    return i_outward(vec2<f32>(lo, hi), TRIG_ERR);
+}
+
+fn i_asin(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || a.x < -1.0 || a.y > 1.0 {
+        return i_nan();
+    }
+    return i_outward(vec2<f32>(asin(a.x), asin(a.y)), TRIG_ERR);
+}
+
+fn i_acos(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || a.x < -1.0 || a.y > 1.0 {
+        return i_nan();
+    }
+    let out = i_outward(vec2<f32>(acos(a.y), acos(a.x)), TRIG_ERR);
+    return vec2<f32>(max(out.x, 0.0), out.y);
+}
+
+fn i_atan(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) {
+        return i_nan();
+    }
+    return i_outward(vec2<f32>(atan(a.x), atan(a.y)), TRIG_ERR);
+}
+
+fn i_exp(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) {
+        return i_nan();
+    }
+    let out = i_outward(vec2<f32>(exp(a.x), exp(a.y)), EXP_ERR);
+    return vec2<f32>(max(out.x, 0.0), out.y);
+}
+
+fn i_ln(a: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || a.x <= 0.0 {
+        return i_nan();
+    }
+    return i_outward(vec2<f32>(log(a.x), log(a.y)), EXP_ERR);
+}
+
+fn i_not(a: vec2<f32>) -> vec2<f32> {
+    if !i_contains_zero(a) && !i_has_nan(a) {
+        return vec2<f32>(0.0);
+    } else if a.x == 0.0 && a.y == 0.0 {
+        return vec2<f32>(1.0);
+    }
+    return vec2<f32>(0.0, 1.0);
+}
+
+fn i_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return a + b;
+}
+
+fn i_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x - b.y, a.y - b.x);
+}
+
+fn i_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || i_has_nan(b) {
+        return i_nan();
+    }
+    let p = vec4<f32>(a.x * b.x, a.x * b.y, a.y * b.x, a.y * b.y);
+    return vec2<f32>(min(min(p.x, p.y), min(p.z, p.w)),
+                     max(max(p.x, p.y), max(p.z, p.w)));
+}
+
+fn i_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || i_has_nan(b) || i_contains_zero(b) {
+        return i_nan();
+    }
+    let p = vec4<f32>(a.x / b.x, a.x / b.y, a.y / b.x, a.y / b.y);
+    return vec2<f32>(min(min(p.x, p.y), min(p.z, p.w)),
+                     max(max(p.x, p.y), max(p.z, p.w)));
+}
+
+fn i_atan2(y: vec2<f32>, x: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(y) || i_has_nan(x) {
+        return i_nan();
+    }
+    // Check whether we span the branch cut
+    if i_contains_zero(y) && x.x < 0.0 {
+        return vec2<f32>(-PI, PI);
+    }
+    var a: f32;
+    var b: f32;
+    if y.x >= 0.0 {
+        if x.x >= 0.0 {
+            a = atan2(y.y, x.x);
+            b = atan2(y.x, x.y);
+        } else if x.y <= 0.0 {
+            a = atan2(y.x, x.x);
+            b = atan2(y.y, x.y);
+        } else {
+            a = atan2(y.x, x.x);
+            b = atan2(y.x, x.y);
+        }
+    } else if y.y <= 0.0 {
+        if x.x >= 0.0 {
+            a = atan2(y.x, x.x);
+            b = atan2(y.y, x.y);
+        } else if x.y <= 0.0 {
+            a = atan2(y.y, x.x);
+            b = atan2(y.x, x.y);
+        } else {
+            a = atan2(y.y, x.x);
+            b = atan2(y.y, x.y);
+        }
+    } else {
+        a = atan2(y.x, x.x);
+        b = atan2(y.y, x.x);
+    }
+    return i_outward(vec2<f32>(min(a, b), max(a, b)), TRIG_ERR);
+}
+
+fn i_min(a: vec2<f32>, b: vec2<f32>) -> IChoice {
+    if i_has_nan(a) || i_has_nan(b) {
+        return IChoice(i_nan(), CHOICE_BOTH);
+    }
+    var c = CHOICE_BOTH;
+    if a.y < b.x {
+        c = CHOICE_LEFT;
+    } else if b.y < a.x {
+        c = CHOICE_RIGHT;
+    }
+    return IChoice(min(a, b), c);
+}
+
+fn i_max(a: vec2<f32>, b: vec2<f32>) -> IChoice {
+    if i_has_nan(a) || i_has_nan(b) {
+        return IChoice(i_nan(), CHOICE_BOTH);
+    }
+    var c = CHOICE_BOTH;
+    if a.x > b.y {
+        c = CHOICE_LEFT;
+    } else if b.x > a.y {
+        c = CHOICE_RIGHT;
+    }
+    return IChoice(max(a, b), c);
+}
+
+fn i_and(a: vec2<f32>, b: vec2<f32>) -> IChoice {
+    if i_has_nan(a) || i_has_nan(b) {
+        return IChoice(i_nan(), CHOICE_BOTH);
+    } else if a.x == 0.0 && a.y == 0.0 {
+        return IChoice(vec2<f32>(0.0), CHOICE_LEFT);
+    } else if !i_contains_zero(a) {
+        return IChoice(b, CHOICE_RIGHT);
+    }
+    return IChoice(vec2<f32>(min(b.x, 0.0), max(b.y, 0.0)), CHOICE_BOTH);
+}
+
+fn i_or(a: vec2<f32>, b: vec2<f32>) -> IChoice {
+    if i_has_nan(a) || i_has_nan(b) {
+        return IChoice(i_nan(), CHOICE_BOTH);
+    } else if !i_contains_zero(a) {
+        return IChoice(a, CHOICE_LEFT);
+    } else if a.x == 0.0 && a.y == 0.0 {
+        return IChoice(b, CHOICE_RIGHT);
+    }
+    return IChoice(vec2<f32>(min(a.x, b.x), max(a.y, b.y)), CHOICE_BOTH);
+}
+
+fn i_compare(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || i_has_nan(b) {
+        return i_nan();
+    } else if a.y < b.x {
+        return vec2<f32>(-1.0);
+    } else if a.x > b.y {
+        return vec2<f32>(1.0);
+    }
+    return vec2<f32>(-1.0, 1.0);
+}
+
+// Least non-negative remainder, like `Interval::rem_euclid`
+fn i_mod(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    if i_has_nan(a) || i_has_nan(b) || i_contains_zero(b) {
+        return i_nan();
+    } else if b.x == b.y && b.x > 0.0 {
+        let p = a.x / b.x;
+        let q = a.y / b.x;
+        if p != floor(p) && floor(p) == floor(q) {
+            let r = vec2<f32>(a.x % b.x, a.y % b.x);
+            return select(r, r + b.x, r < vec2<f32>(0.0));
+        }
+    }
+    return vec2<f32>(0.0, max(abs(b.x), abs(b.y)));
+}
@@ -0,0 +1,753 @@
+//! GPU evaluation of math expressions, using [`wgpu`]
+//!
+//! Tapes are transpiled into WGSL compute shaders (see [`float_slice_shader`]
+//! and [`interval_shader`]), which evaluate one point or interval per
+//! invocation.  A [`WgpuEvaluator`] compiles those shaders into pipelines and
+//! dispatches them, which is useful for large, embarrassingly parallel
+//! workloads (e.g. evaluating millions of points during meshing).
+//!
+//! ```no_run
+//! use fidget_core::{context::Tree, vm::VmData};
+//! use fidget_wgpu::WgpuEvaluator;
+//!
+//! let (x, y, _) = Tree::axes();
+//! let circle = (x.square() + y.square()).sqrt() - 1.0;
+//! let mut ctx = fidget_core::Context::new();
+//! let root = ctx.import(&circle);
+//! let data = VmData::<255>::new(&ctx, &[root])?;
+//!
+//! let eval = WgpuEvaluator::new()?;
+//! let tape = eval.float_slice_tape(&data)?;
+//!
+//! // Inputs are ordered by the tape's variable map
+//! let vars = tape.vars();
+//! let mut args = vec![vec![]; 2];
+//! args[vars[&fidget_core::var::Var::X]] = vec![0.0, 1.0, 2.0];
+//! args[vars[&fidget_core::var::Var::Y]] = vec![0.0, 0.0, 0.0];
+//! let out = eval.eval_float_slice(&tape, &args)?;
+//! assert_eq!(out[0], [-1.0, 0.0, 1.0]);
+//! # Ok::<(), fidget_wgpu::Error>(())
+//! ```
+//!
+//! Interval evaluation also records a [`VmTrace`] for each interval, which
+//! can be used to simplify the original tape (e.g. with
+//! [`VmData::simplify`]).
+//!
+//! Basic arithmetic is not outward-rounded on the GPU, and transcendental
+//! functions are only widened by a fixed tolerance to account for the GPU's
+//! looser precision requirements, so interval results are not certified.
+#![warn(missing_docs)]
+
+mod shader;
+pub use shader::{WORKGROUP_SIZE, float_slice_shader, interval_shader};
+
+use fidget_core::{
+    types::Interval,
+    var::VarMap,
+    vm::{Choice, VmData, VmTrace},
+};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// Error type for GPU evaluation
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// No suitable GPU adapter was found
+    #[error("no suitable GPU adapter found")]
+    NoAdapter,
+
+    /// The GPU device could not be created
+    #[error("could not request device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+
+    /// A buffer could not be mapped for readback
+    #[error("could not map buffer: {0}")]
+    BufferMap(#[from] wgpu::BufferAsyncError),
+
+    /// The GPU reported an error (e.g. a shader compilation failure)
+    #[error("GPU error: {0}")]
+    Gpu(String),
+
+    /// Error from the core library (e.g. mismatched input slices)
+    #[error(transparent)]
+    Core(#[from] fidget_core::Error),
+
+    /// A single item's inputs, outputs, or choices exceed the GPU's buffer
+    /// size limit
+    #[error(
+        "one item needs {0} bytes, exceeding the buffer limit of {1} bytes"
+    )]
+    ItemTooLarge(u64, u64),
+}
+
+/// Trait for data types which can be evaluated on the GPU
+pub trait GpuData: Copy {
+    /// Number of `f32` values used to store one item
+    const WIDTH: usize;
+
+    /// Appends the item's `f32` values to the given buffer
+    fn pack(self, out: &mut Vec<f32>);
+
+    /// Builds an item from its `f32` values
+    fn unpack(v: &[f32]) -> Self;
+}
+
+impl GpuData for f32 {
+    const WIDTH: usize = 1;
+    fn pack(self, out: &mut Vec<f32>) {
+        out.push(self);
+    }
+    fn unpack(v: &[f32]) -> Self {
+        v[0]
+    }
+}
+
+impl GpuData for Interval {
+    const WIDTH: usize = 2;
+    fn pack(self, out: &mut Vec<f32>) {
+        out.extend([self.lower(), self.upper()]);
+    }
+    fn unpack(v: &[f32]) -> Self {
+        Interval::new(v[0], v[1])
+    }
+}
+
+/// A tape which has been compiled into a GPU compute pipeline
+///
+/// Tapes are built by [`WgpuEvaluator::float_slice_tape`] and
+/// [`WgpuEvaluator::interval_tape`], and are only valid for the evaluator
+/// which built them.
+pub struct WgpuTape<T> {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    vars: Arc<VarMap>,
+    output_count: usize,
+    choice_count: usize,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> WgpuTape<T> {
+    /// Returns the mapping from [`Var`](fidget_core::var::Var) to input index
+    pub fn vars(&self) -> &VarMap {
+        &self.vars
+    }
+
+    /// Returns the number of outputs written by this tape
+    pub fn output_count(&self) -> usize {
+        self.output_count
+    }
+}
+
+/// Evaluator which dispatches compute shaders on a GPU
+///
+/// Construction of the evaluator (and of each tape) is relatively expensive,
+/// so both should be reused where possible.
+///
+/// Large evaluations are split into several dispatches, so that no buffer
+/// exceeds the device's `max_buffer_size` or `max_storage_buffer_binding_size`
+/// limits.
+pub struct WgpuEvaluator {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+
+    /// Maximum size of a single buffer, in bytes
+    max_buffer_size: u64,
+}
+
+impl WgpuEvaluator {
+    /// Builds a new evaluator using the default GPU adapter
+    ///
+    /// This function blocks until the device is available.  Returns
+    /// [`Error::NoAdapter`] if there is no suitable GPU.
+    pub fn new() -> Result<Self, Error> {
+        pollster::block_on(async {
+            let instance =
+                wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions::default())
+                .await
+                .ok_or(Error::NoAdapter)?;
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor::default(), None)
+                .await?;
+            Ok(Self::from_device(device, queue))
+        })
+    }
+
+    /// Builds a new evaluator from an existing device and queue
+    pub fn from_device(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        let limits = device.limits();
+        let max_buffer_size = limits
+            .max_buffer_size
+            .min(limits.max_storage_buffer_binding_size.into());
+        Self {
+            device,
+            queue,
+            max_buffer_size,
+        }
+    }
+
+    /// Compiles a tape for float evaluation
+    ///
+    /// See [`float_slice_shader`] for details on the generated shader.
+    pub fn float_slice_tape<const N: usize>(
+        &self,
+        data: &VmData<N>,
+    ) -> Result<WgpuTape<f32>, Error> {
+        self.build_tape(data, &float_slice_shader(data), 0)
+    }
+
+    /// Compiles a tape for interval evaluation
+    ///
+    /// See [`interval_shader`] for details on the generated shader.
+    pub fn interval_tape<const N: usize>(
+        &self,
+        data: &VmData<N>,
+    ) -> Result<WgpuTape<Interval>, Error> {
+        self.build_tape(data, &interval_shader(data), data.choice_count())
+    }
+
+    fn build_tape<T: GpuData, const N: usize>(
+        &self,
+        data: &VmData<N>,
+        src: &str,
+        choice_count: usize,
+    ) -> Result<WgpuTape<T>, Error> {
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let entries = [
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            storage(1, true),
+            storage(2, false),
+            storage(3, false),
+        ];
+
+        // The float shader doesn't declare a choice array
+        let entries = if T::WIDTH == 1 {
+            &entries[..3]
+        } else {
+            &entries[..]
+        };
+
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module =
+            self.device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("fidget"),
+                    source: wgpu::ShaderSource::Wgsl(src.into()),
+                });
+        let layout = self.device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("fidget"),
+                entries,
+            },
+        );
+        let pipeline_layout = self.device.create_pipeline_layout(
+            &wgpu::PipelineLayoutDescriptor {
+                label: Some("fidget"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            },
+        );
+        let pipeline = self.device.create_compute_pipeline(
+            &wgpu::ComputePipelineDescriptor {
+                label: Some("fidget"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                cache: None,
+            },
+        );
+        if let Some(e) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(Error::Gpu(e.to_string()));
+        }
+
+        Ok(WgpuTape {
+            pipeline,
+            layout,
+            vars: data.vars.clone(),
+            output_count: data.output_count(),
+            choice_count,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Evaluates many points on the GPU
+    ///
+    /// `vars` is indexed by the tape's [`VarMap`], then by point; the result
+    /// is indexed by output, then by point.
+    pub fn eval_float_slice<V: std::ops::Deref<Target = [f32]>>(
+        &self,
+        tape: &WgpuTape<f32>,
+        vars: &[V],
+    ) -> Result<Vec<Vec<f32>>, Error> {
+        let (out, _) = self.eval(tape, vars)?;
+        Ok(out)
+    }
+
+    /// Evaluates many intervals on the GPU
+    ///
+    /// `vars` is indexed by the tape's [`VarMap`], then by interval.  Returns
+    /// output values (indexed by output, then by interval) and a trace for
+    /// each interval, which may be used to simplify the original tape.
+    pub fn eval_interval_slice<V: std::ops::Deref<Target = [Interval]>>(
+        &self,
+        tape: &WgpuTape<Interval>,
+        vars: &[V],
+    ) -> Result<(Vec<Vec<Interval>>, Vec<VmTrace>), Error> {
+        let (out, choices) = self.eval(tape, vars)?;
+        let n = out
+            .first()
+            .map(|o| o.len())
+            .unwrap_or_else(|| vars.first().map(|v| v.len()).unwrap_or(0));
+        let traces = (0..n)
+            .map(|i| {
+                (0..tape.choice_count)
+                    .map(|c| match choices[c * n + i] {
+                        1 => Choice::Left,
+                        2 => Choice::Right,
+                        3 => Choice::Both,
+                        _ => Choice::Unknown,
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect();
+        Ok((out, traces))
+    }
+
+    /// Evaluates a tape, returning outputs and (flattened) choices
+    ///
+    /// Choices are indexed by choice, then by item.  Items are evaluated in
+    /// chunks which fit within the device's buffer limits.
+    fn eval<T: GpuData, V: std::ops::Deref<Target = [T]>>(
+        &self,
+        tape: &WgpuTape<T>,
+        vars: &[V],
+    ) -> Result<(Vec<Vec<T>>, Vec<u32>), Error> {
+        tape.vars.check_bulk_arguments(vars)?;
+        let n = vars.first().map(|v| v.len()).unwrap_or(0);
+        let mut out = vec![Vec::with_capacity(n); tape.output_count];
+        let mut choices = vec![0; tape.choice_count * n];
+        if n == 0 {
+            return Ok((out, choices));
+        }
+
+        let item_size = [
+            vars.len() * T::WIDTH,
+            tape.output_count * T::WIDTH,
+            tape.choice_count,
+        ]
+        .into_iter()
+        .max()
+        .unwrap() as u64
+            * 4;
+        let max_groups = u64::from(
+            self.device.limits().max_compute_workgroups_per_dimension,
+        );
+        let chunk = chunk_size(
+            item_size,
+            self.max_buffer_size,
+            max_groups * max_groups * u64::from(WORKGROUP_SIZE),
+        )?;
+
+        for start in (0..n).step_by(chunk) {
+            let end = (start + chunk).min(n);
+            let args = vars.iter().map(|v| &v[start..end]).collect::<Vec<_>>();
+            let (o, c) = self.eval_chunk(tape, &args)?;
+            for (out, o) in out.iter_mut().zip(o) {
+                out.extend(o);
+            }
+            let m = end - start;
+            for (i, c) in c.chunks_exact(m).enumerate() {
+                choices[i * n + start..i * n + end].copy_from_slice(c);
+            }
+        }
+        Ok((out, choices))
+    }
+
+    /// Evaluates a single chunk, which must fit within buffer limits
+    fn eval_chunk<T: GpuData>(
+        &self,
+        tape: &WgpuTape<T>,
+        vars: &[&[T]],
+    ) -> Result<(Vec<Vec<T>>, Vec<u32>), Error> {
+        let n = vars[0].len();
+        let mut input = Vec::with_capacity(vars.len() * n * T::WIDTH);
+        for v in vars {
+            for x in v.iter() {
+                x.pack(&mut input);
+            }
+        }
+        // Storage buffers can't be empty
+        input.resize(input.len().max(4), 0.0);
+
+        let output_size = (tape.output_count * n * T::WIDTH * 4).max(16);
+        let choice_size = (tape.choice_count * n * 4).max(16);
+
+        let params =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("params"),
+                    contents: bytemuck::cast_slice(&[n as u32, 0, 0, 0]),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+        let inputs =
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("inputs"),
+                    contents: bytemuck::cast_slice(&input),
+                    usage: wgpu::BufferUsages::STORAGE,
+                });
+        let storage = |label, size| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let outputs = storage("outputs", output_size);
+        let choices = storage("choices", choice_size);
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: inputs.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: outputs.as_entire_binding(),
+            },
+        ];
+        if T::WIDTH == 2 {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 3,
+                resource: choices.as_entire_binding(),
+            });
+        }
+        let bind_group =
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("fidget"),
+                layout: &tape.layout,
+                entries: &entries,
+            });
+
+        // Split the dispatch into two dimensions if it's too large
+        let groups = (n as u32).div_ceil(WORKGROUP_SIZE);
+        let max = self.device.limits().max_compute_workgroups_per_dimension;
+        let (gx, gy) = (groups.min(max), groups.div_ceil(max));
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass =
+                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("fidget"),
+                    timestamp_writes: None,
+                });
+            pass.set_pipeline(&tape.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(gx, gy, 1);
+        }
+        let readback = |label, size| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage: wgpu::BufferUsages::MAP_READ
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let outputs_rb = readback("outputs readback", output_size);
+        encoder.copy_buffer_to_buffer(
+            &outputs,
+            0,
+            &outputs_rb,
+            0,
+            output_size as u64,
+        );
+        let choices_rb = readback("choices readback", choice_size);
+        encoder.copy_buffer_to_buffer(
+            &choices,
+            0,
+            &choices_rb,
+            0,
+            choice_size as u64,
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let out: Vec<f32> = self.read(&outputs_rb)?;
+        let out = (0..tape.output_count)
+            .map(|o| {
+                let start = o * n * T::WIDTH;
+                out[start..start + n * T::WIDTH]
+                    .chunks_exact(T::WIDTH)
+                    .map(T::unpack)
+                    .collect()
+            })
+            .collect();
+        let mut choices: Vec<u32> = self.read(&choices_rb)?;
+        choices.truncate(tape.choice_count * n);
+        Ok((out, choices))
+    }
+
+    /// Maps a readback buffer and copies out its contents
+    fn read<T: bytemuck::Pod>(
+        &self,
+        buf: &wgpu::Buffer,
+    ) -> Result<Vec<T>, Error> {
+        let slice = buf.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |r| {
+            let _ = tx.send(r);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv().map_err(|e| Error::Gpu(e.to_string()))??;
+        let out = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        buf.unmap();
+        Ok(out)
+    }
+}
+
+/// Returns the number of items which can be evaluated in one dispatch
+///
+/// `item_size` is the largest number of bytes used by a single item in any
+/// buffer, `max_buffer_size` is the device's buffer size limit, and
+/// `max_items` is the largest number of items that can be dispatched at once.
+fn chunk_size(
+    item_size: u64,
+    max_buffer_size: u64,
+    max_items: u64,
+) -> Result<usize, Error> {
+    let n = (max_buffer_size / item_size.max(1))
+        .min(max_items)
+        .min(u32::MAX.into());
+    if n == 0 {
+        Err(Error::ItemTooLarge(item_size, max_buffer_size))
+    } else {
+        Ok(n as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fidget_core::{
+        context::Context,
+        eval::{BulkEvaluator, Function, MathFunction, test::build_all_ops},
+        var::Var,
+        vm::{VmFunction, VmIntervalSliceEval},
+    };
+
+    fn validate(src: &str) {
+        let module = naga::front::wgsl::parse_str(src)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string(src)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{}", e.emit_to_string(src)));
+    }
+
+    #[test]
+    fn float_shader_is_valid() {
+        let (ctx, root) = build_all_ops();
+        let data = VmData::<255>::new(&ctx, &[root]).unwrap();
+        validate(&float_slice_shader(&data));
+    }
+
+    #[test]
+    fn interval_shader_is_valid() {
+        let (ctx, root) = build_all_ops();
+        let data = VmData::<255>::new(&ctx, &[root]).unwrap();
+        let src = interval_shader(&data);
+        assert!(src.contains("choices["));
+        validate(&src);
+    }
+
+    #[test]
+    fn constant_shader_is_valid() {
+        let mut ctx = Context::new();
+        let c = ctx.constant(f64::NAN);
+        let data = VmData::<255>::new(&ctx, &[c]).unwrap();
+        validate(&float_slice_shader(&data));
+        validate(&interval_shader(&data));
+    }
+
+    /// Returns an evaluator, or `None` if there's no GPU available
+    fn evaluator() -> Option<WgpuEvaluator> {
+        match WgpuEvaluator::new() {
+            Ok(e) => Some(e),
+            Err(Error::NoAdapter) => {
+                eprintln!("no GPU adapter available; skipping test");
+                None
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn gpu_float_slice() {
+        let Some(eval) = evaluator() else { return };
+        let mut ctx = Context::new();
+        let (x, y) = (ctx.x(), ctx.y());
+        let a = ctx.mul(x, y).unwrap();
+        let b = ctx.min(a, 3.0).unwrap();
+        let c = ctx.sub(b, y).unwrap();
+        let f = VmFunction::new(&ctx, &[c]).unwrap();
+        let tape = eval.float_slice_tape(f.data()).unwrap();
+
+        let n = 1000;
+        let mut args = vec![vec![]; 2];
+        args[tape.vars()[&Var::X]] = (0..n).map(|i| i as f32 / 100.0).collect();
+        args[tape.vars()[&Var::Y]] = (0..n).map(|i| (i % 7) as f32).collect();
+        let out = eval.eval_float_slice(&tape, &args).unwrap();
+
+        let vm_tape = f.float_slice_tape(Default::default());
+        let mut vm = VmFunction::new_float_slice_eval();
+        let expected = vm.eval(&vm_tape, &args).unwrap();
+        assert_eq!(out[0], &expected[0]);
+    }
+
+    #[test]
+    fn gpu_interval_slice() {
+        let Some(eval) = evaluator() else { return };
+        let mut ctx = Context::new();
+        let (x, y) = (ctx.x(), ctx.y());
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.max(a, -1.0).unwrap();
+        let f = VmFunction::new(&ctx, &[b]).unwrap();
+        let tape = eval.interval_tape(f.data()).unwrap();
+        assert_eq!(tape.vars().len(), 2);
+
+        let n = 300;
+        let mut args = vec![vec![]; 2];
+        args[tape.vars()[&Var::X]] = (0..n)
+            .map(|i| Interval::new(i as f32 / 100.0 - 1.5, i as f32 / 100.0))
+            .collect();
+        args[tape.vars()[&Var::Y]] = vec![Interval::new(0.5, 1.0); n];
+        let (out, traces) = eval.eval_interval_slice(&tape, &args).unwrap();
+
+        let vm_tape = f.interval_tape(Default::default());
+        let mut vm = VmIntervalSliceEval::new();
+        let expected = vm.eval(&vm_tape, &args).unwrap();
+        assert_eq!(out[0], &expected[0]);
+
+        let mut single = VmFunction::new_interval_eval();
+        for (i, t) in traces.iter().enumerate() {
+            let a = [args[0][i], args[1][i]];
+            let (_, trace) = fidget_core::eval::TracingEvaluator::eval(
+                &mut single,
+                &vm_tape,
+                &a,
+            )
+            .unwrap();
+            match trace {
+                Some(trace) => assert_eq!(t.as_ref(), trace.as_ref()),
+                None => assert!(t.as_ref().iter().all(|c| *c == Choice::Both)),
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_sizes() {
+        assert_eq!(chunk_size(8, 1024, u64::MAX).unwrap(), 128);
+        assert_eq!(chunk_size(8, 1024, 100).unwrap(), 100);
+        assert_eq!(
+            chunk_size(8, 1 << 40, u64::MAX).unwrap(),
+            u32::MAX as usize
+        );
+        assert!(matches!(
+            chunk_size(2048, 1024, u64::MAX),
+            Err(Error::ItemTooLarge(2048, 1024))
+        ));
+    }
+
+    #[test]
+    fn gpu_chunked() {
+        let Some(mut eval) = evaluator() else { return };
+        let mut ctx = Context::new();
+        let (x, y) = (ctx.x(), ctx.y());
+        let a = ctx.min(x, y).unwrap();
+        let b = ctx.max(a, -1.0).unwrap();
+        let f = VmFunction::new(&ctx, &[b, a]).unwrap();
+
+        // Force several chunks, including a partial one at the end
+        eval.max_buffer_size = 1024;
+        let n = 1000;
+
+        let float_tape = eval.float_slice_tape(f.data()).unwrap();
+        let vars = float_tape.vars();
+        let mut args = vec![vec![]; 2];
+        args[vars[&Var::X]] = (0..n).map(|i| i as f32 / 100.0).collect();
+        args[vars[&Var::Y]] = (0..n).map(|i| (i % 7) as f32).collect();
+        let out = eval.eval_float_slice(&float_tape, &args).unwrap();
+        let vm_tape = f.float_slice_tape(Default::default());
+        let mut vm = VmFunction::new_float_slice_eval();
+        let expected = vm.eval(&vm_tape, &args).unwrap();
+        assert_eq!(out[0], &expected[0]);
+        assert_eq!(out[1], &expected[1]);
+
+        let tape = eval.interval_tape(f.data()).unwrap();
+        let mut args = vec![vec![]; 2];
+        args[tape.vars()[&Var::X]] = (0..n)
+            .map(|i| Interval::new(i as f32 / 100.0 - 1.5, i as f32 / 100.0))
+            .collect();
+        args[tape.vars()[&Var::Y]] = vec![Interval::new(0.5, 1.0); n];
+        let (out, traces) = eval.eval_interval_slice(&tape, &args).unwrap();
+        let vm_tape = f.interval_tape(Default::default());
+        let mut vm = VmIntervalSliceEval::new();
+        let expected = vm.eval(&vm_tape, &args).unwrap();
+        assert_eq!(out[0], &expected[0]);
+        assert_eq!(out[1], &expected[1]);
+
+        let mut single = VmFunction::new_interval_eval();
+        for (i, t) in traces.iter().enumerate() {
+            let a = [args[0][i], args[1][i]];
+            let (_, trace) = fidget_core::eval::TracingEvaluator::eval(
+                &mut single,
+                &vm_tape,
+                &a,
+            )
+            .unwrap();
+            match trace {
+                Some(trace) => assert_eq!(t.as_ref(), trace.as_ref()),
+                None => assert!(t.as_ref().iter().all(|c| *c == Choice::Both)),
+            }
+        }
+
+        // A single item which doesn't fit is an error
+        eval.max_buffer_size = 4;
+        let args = [vec![1.0], vec![2.0]];
+        assert!(matches!(
+            eval.eval_float_slice(&float_tape, &args),
+            Err(Error::ItemTooLarge(8, 4))
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+//! WGSL code generation
+use fidget_core::{
+    compiler::{Clause, Operand},
+    context::{BinaryOpcode, UnaryOpcode},
+    vm::VmData,
+};
+use std::fmt::Write;
+
+const COMMON: &str = include_str!("common.wgsl");
+const FLOAT: &str = include_str!("float.wgsl");
+const INTERVAL: &str = include_str!("interval.wgsl");
+
+/// Number of invocations in each workgroup of a generated shader
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Data type used by a generated shader
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Kind {
+    Float,
+    Interval,
+}
+
+/// Generates a WGSL compute shader which evaluates the tape on arrays of points
+///
+/// The shader has the following bindings in group 0:
+///
+/// | Binding | Type                           | Value                         |
+/// |---------|--------------------------------|-------------------------------|
+/// | 0       | `uniform` struct with a `u32`  | Number of points (`n`)        |
+/// | 1       | `storage, read` `array<f32>`   | Inputs                        |
+/// | 2       | `storage, read_write` `array<f32>` | Outputs                   |
+///
+/// Inputs and outputs are stored in structure-of-arrays order, i.e. input
+/// variable `v` (per the tape's [`VarMap`](fidget_core::var::VarMap)) of point
+/// `i` is at index `v * n + i`, and output `o` is at `o * n + i`.
+///
+/// The entry point is `main`, with a workgroup size of [`WORKGROUP_SIZE`].
+/// The invocation index is `global_id.x + global_id.y * num_workgroups.x *
+/// WORKGROUP_SIZE`, so very large arrays can be dispatched in two dimensions.
+pub fn float_slice_shader<const N: usize>(data: &VmData<N>) -> String {
+    build(data, Kind::Float)
+}
+
+/// Generates a WGSL compute shader which evaluates the tape on arrays of
+/// intervals
+///
+/// The bindings and layout match [`float_slice_shader`], except that values
+/// are `vec2<f32>` (lower and upper bounds), and there's one more binding:
+///
+/// | Binding | Type                           | Value                         |
+/// |---------|--------------------------------|-------------------------------|
+/// | 3       | `storage, read_write` `array<u32>` | Choices                   |
+///
+/// Choice `c` of item `i` is written to index `c * n + i`, with values
+/// matching [`Choice`](fidget_core::vm::Choice).  Choice indices match those
+/// used by [`VmData::simplify`].
+pub fn interval_shader<const N: usize>(data: &VmData<N>) -> String {
+    build(data, Kind::Interval)
+}
+
+fn build<const N: usize>(data: &VmData<N>, kind: Kind) -> String {
+    let (ty, prelude) = match kind {
+        Kind::Float => ("f32", FLOAT),
+        Kind::Interval => ("vec2<f32>", INTERVAL),
+    };
+    let mut out = String::new();
+    out += COMMON;
+    out += "\n";
+    out += prelude;
+    writeln!(
+        out,
+        "
+@group(0) @binding(1) var<storage, read> inputs: array<{ty}>;
+@group(0) @binding(2) var<storage, read_write> outputs: array<{ty}>;"
+    )
+    .unwrap();
+    if kind == Kind::Interval {
+        out += "@group(0) @binding(3) var<storage, read_write> choices: \
+                array<u32>;\n";
+    }
+    writeln!(
+        out,
+        "
+@compute @workgroup_size({WORKGROUP_SIZE})
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {{
+    let i = global_id.x + global_id.y * num_workgroups.x * {WORKGROUP_SIZE}u;
+    let n = params.count;
+    if i >= n {{
+        return;
+    }}"
+    )
+    .unwrap();
+
+    let imm = |f: f32| match kind {
+        Kind::Float => format!("from_bits(0x{:08x}u)", f.to_bits()),
+        Kind::Interval => format!("i_imm(0x{:08x}u)", f.to_bits()),
+    };
+    let operand = |o: Operand| match o {
+        Operand::Reg(r) => format!("r{r}"),
+        Operand::Imm(f) => imm(f),
+        o => unimplemented!("unsupported operand {o:?}"),
+    };
+
+    let mut choice = 0;
+    for c in data.clauses() {
+        let line = match c {
+            Clause::Input { out, var } => {
+                format!("let r{out} = inputs[{var}u * n + i];")
+            }
+            Clause::Output { arg, index } => {
+                format!("outputs[{index}u * n + i] = r{arg};")
+            }
+            Clause::Copy { out, arg } => {
+                format!("let r{out} = {};", operand(arg))
+            }
+            Clause::Unary { op, out, arg } => {
+                let f = match kind {
+                    Kind::Float => float_unary(op),
+                    Kind::Interval => interval_unary(op),
+                };
+                format!("let r{out} = {f}(r{arg});")
+            }
+            Clause::Binary { op, out, lhs, rhs } => {
+                let (lhs, rhs) = (operand(lhs), operand(rhs));
+                match kind {
+                    Kind::Float => {
+                        format!(
+                            "let r{out} = {};",
+                            float_binary(op, &lhs, &rhs)
+                        )
+                    }
+                    Kind::Interval => {
+                        let f = interval_binary(op);
+                        if matches!(
+                            op,
+                            BinaryOpcode::Min
+                                | BinaryOpcode::Max
+                                | BinaryOpcode::And
+                                | BinaryOpcode::Or
+                        ) {
+                            let s = format!(
+                                "let c{choice} = {f}({lhs}, {rhs});\n    \
+                                 choices[{choice}u * n + i] = c{choice}.c;\n    \
+                                 let r{out} = c{choice}.v;"
+                            );
+                            choice += 1;
+                            s
+                        } else {
+                            format!("let r{out} = {f}({lhs}, {rhs});")
+                        }
+                    }
+                }
+            }
+            c => unimplemented!("unsupported clause {c:?}"),
+        };
+        writeln!(out, "    {line}").unwrap();
+    }
+    debug_assert!(kind == Kind::Float || choice == data.choice_count());
+    out += "}\n";
+    out
+}
+
+fn float_unary(op: UnaryOpcode) -> &'static str {
+    match op {
+        UnaryOpcode::Neg => "-",
+        UnaryOpcode::Abs => "abs",
+        UnaryOpcode::Recip => "f_recip",
+        UnaryOpcode::Sqrt => "f_sqrt",
+        UnaryOpcode::Square => "f_square",
+        UnaryOpcode::Floor => "floor",
+        UnaryOpcode::Ceil => "ceil",
+        UnaryOpcode::Round => "round_away",
+        UnaryOpcode::Sin => "sin",
+        UnaryOpcode::Cos => "cos",
+        UnaryOpcode::Tan => "tan",
+        UnaryOpcode::Asin => "f_asin",
+        UnaryOpcode::Acos => "f_acos",
+        UnaryOpcode::Atan => "atan",
+        UnaryOpcode::Exp => "exp",
+        UnaryOpcode::Ln => "f_ln",
+        UnaryOpcode::Not => "f_not",
+    }
+}
+
+fn float_binary(op: BinaryOpcode, lhs: &str, rhs: &str) -> String {
+    let f = match op {
+        BinaryOpcode::Add => return format!("{lhs} + {rhs}"),
+        BinaryOpcode::Sub => return format!("{lhs} - {rhs}"),
+        BinaryOpcode::Mul => return format!("{lhs} * {rhs}"),
+        BinaryOpcode::Div => return format!("{lhs} / {rhs}"),
+        BinaryOpcode::Atan => "atan2",
+        BinaryOpcode::Min => "f_min",
+        BinaryOpcode::Max => "f_max",
+        BinaryOpcode::Compare => "f_compare",
+        BinaryOpcode::Mod => "f_mod",
+        BinaryOpcode::And => "f_and",
+        BinaryOpcode::Or => "f_or",
+    };
+    format!("{f}({lhs}, {rhs})")
+}
+
+fn interval_unary(op: UnaryOpcode) -> &'static str {
+    match op {
+        UnaryOpcode::Neg => "i_neg",
+        UnaryOpcode::Abs => "i_abs",
+        UnaryOpcode::Recip => "i_recip",
+        UnaryOpcode::Sqrt => "i_sqrt",
+        UnaryOpcode::Square => "i_square",
+        UnaryOpcode::Floor => "i_floor",
+        UnaryOpcode::Ceil => "i_ceil",
+        UnaryOpcode::Round => "i_round",
+        UnaryOpcode::Sin => "i_sin",
+        UnaryOpcode::Cos => "i_cos",
+        UnaryOpcode::Tan => "i_tan",
+        UnaryOpcode::Asin => "i_asin",
+        UnaryOpcode::Acos => "i_acos",
+        UnaryOpcode::Atan => "i_atan",
+        UnaryOpcode::Exp => "i_exp",
+        UnaryOpcode::Ln => "i_ln",
+        UnaryOpcode::Not => "i_not",
+    }
+}
+
+fn interval_binary(op: BinaryOpcode) -> &'static str {
+    match op {
+        BinaryOpcode::Add => "i_add",
+        BinaryOpcode::Sub => "i_sub",
+        BinaryOpcode::Mul => "i_mul",
+        BinaryOpcode::Div => "i_div",
+        BinaryOpcode::Atan => "i_atan2",
+        BinaryOpcode::Min => "i_min",
+        BinaryOpcode::Max => "i_max",
+        BinaryOpcode::Compare => "i_compare",
+        BinaryOpcode::Mod => "i_mod",
+        BinaryOpcode::And => "i_and",
+        BinaryOpcode::Or => "i_or",
+    }
+}
@@ -8,12 +8,12 @@ use std::hint::black_box;
 
 pub fn run_bench<F: Function + MathFunction>(
     c: &mut Criterion,
-    ctx: Context,
+    ctx: &Context,
     node: Node,
     test_name: &'static str,
     name: &'static str,
 ) {
-    let shape_vm = &Shape::<F>::new(&ctx, node).unwrap();
+    let shape_vm = &Shape::<F>::new(ctx, node).unwrap();
 
     let mut eval = Shape::<F>::new_float_slice_eval();
     let tape = shape_vm.ez_float_slice_tape();
@@ -38,7 +38,7 @@ pub fn test_single_fn<F: Function + MathFunction>(
     let x = ctx.x();
     let f = ctx.sin(x).unwrap();
 
-    run_bench::<F>(c, ctx, f, "single function", name);
+    run_bench::<F>(c, &ctx, f, "single function", name);
 }
 
 pub fn test_many_fn<F: Function + MathFunction>(
@@ -56,7 +56,7 @@ pub fn test_many_fn<F: Function + MathFunction>(
     let out = ctx.add(f, g).unwrap();
     let out = ctx.add(out, h).unwrap();
 
-    run_bench::<F>(c, ctx, out, "many functions", name);
+    run_bench::<F>(c, &ctx, out, "many functions", name);
 }
 
 pub fn test_single_fns(c: &mut Criterion) {
@@ -71,5 +71,14 @@ pub fn test_many_fns(c: &mut Criterion) {
     test_many_fn::<fidget::jit::JitFunction>(c, "jit");
 }
 
-criterion_group!(benches, test_single_fns, test_many_fns);
+pub fn test_corpus_fns(c: &mut Criterion) {
+    for (name, f) in fidget::corpus::ALL {
+        let (ctx, root) = f();
+        run_bench::<fidget::vm::VmFunction>(c, &ctx, root, name, "vm");
+        #[cfg(feature = "jit")]
+        run_bench::<fidget::jit::JitFunction>(c, &ctx, root, name, "jit");
+    }
+}
+
+criterion_group!(benches, test_single_fns, test_many_fns, test_corpus_fns);
 criterion_main!(benches);
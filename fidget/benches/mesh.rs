@@ -1,10 +1,8 @@
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use std::hint::black_box;
 
-const COLONNADE: &str = include_str!("../../models/colonnade.vm");
-
 pub fn colonnade_octree_thread_sweep(c: &mut Criterion) {
-    let (ctx, root) = fidget::Context::from_text(COLONNADE.as_bytes()).unwrap();
+    let (ctx, root) = fidget::corpus::colonnade();
     let shape_vm = &fidget::vm::VmShape::new(&ctx, root).unwrap();
     #[cfg(feature = "jit")]
     let shape_jit = &fidget::jit::JitShape::new(&ctx, root).unwrap();
@@ -13,14 +11,8 @@ pub fn colonnade_octree_thread_sweep(c: &mut Criterion) {
         c.benchmark_group("speed vs threads (colonnade, octree) (depth 6)");
 
     for threads in [None, Some(1), Some(4), Some(8)] {
-        let pool = threads.map(|n| {
-            fidget::render::ThreadPool::Custom(
-                rayon::ThreadPoolBuilder::new()
-                    .num_threads(n)
-                    .build()
-                    .unwrap(),
-            )
-        });
+        let pool = threads
+            .map(|n| fidget::render::ThreadPool::with_threads(n).unwrap());
         let cfg = &fidget::mesh::Settings {
             depth: 6,
             threads: pool.as_ref(),
@@ -39,7 +31,7 @@ pub fn colonnade_octree_thread_sweep(c: &mut Criterion) {
 }
 
 pub fn colonnade_mesh(c: &mut Criterion) {
-    let (ctx, root) = fidget::Context::from_text(COLONNADE.as_bytes()).unwrap();
+    let (ctx, root) = fidget::corpus::colonnade();
     let shape_vm = &fidget::vm::VmShape::new(&ctx, root).unwrap();
     let cfg = fidget::mesh::Settings {
         depth: 8,
@@ -2,10 +2,8 @@ use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use fidget::render::{ImageSize, RenderHints, ThreadPool};
 use std::hint::black_box;
 
-const PROSPERO: &str = include_str!("../../models/prospero.vm");
-
 pub fn prospero_size_sweep(c: &mut Criterion) {
-    let (ctx, root) = fidget::Context::from_text(PROSPERO.as_bytes()).unwrap();
+    let (ctx, root) = fidget::corpus::prospero();
     let shape_vm = &fidget::vm::VmShape::new(&ctx, root).unwrap();
 
     #[cfg(feature = "jit")]
@@ -44,7 +42,7 @@ pub fn prospero_size_sweep(c: &mut Criterion) {
 }
 
 pub fn prospero_thread_sweep(c: &mut Criterion) {
-    let (ctx, root) = fidget::Context::from_text(PROSPERO.as_bytes()).unwrap();
+    let (ctx, root) = fidget::corpus::prospero();
     let shape_vm = &fidget::vm::VmShape::new(&ctx, root).unwrap();
 
     #[cfg(feature = "jit")]
@@ -52,14 +50,8 @@ pub fn prospero_thread_sweep(c: &mut Criterion) {
 
     let mut group =
         c.benchmark_group("speed vs threads (prospero, 2d) (1024 x 1024)");
-    let pools = [1, 2, 4, 8, 16].map(|i| {
-        Some(ThreadPool::Custom(
-            rayon::ThreadPoolBuilder::new()
-                .num_threads(i)
-                .build()
-                .unwrap(),
-        ))
-    });
+    let pools =
+        [1, 2, 4, 8, 16].map(|i| Some(ThreadPool::with_threads(i).unwrap()));
     for threads in [None, Some(ThreadPool::Global)].into_iter().chain(pools) {
         let threads = threads.as_ref();
         let name = match &threads {
@@ -0,0 +1,211 @@
+//! Standard models for benchmarks, tests, and examples
+//!
+//! Each function builds a model in a new [`Context`], returning the context
+//! and the model's root [`Node`].  Most models are loaded from the `models`
+//! directory of this crate (like [`Context::from_text`]); simple shapes are
+//! built directly.  Benchmarks, tests, and downstream users can use these to
+//! share identical non-trivial inputs:
+//!
+//! ```
+//! use fidget::{corpus, vm::VmShape};
+//!
+//! let (ctx, root) = corpus::bear();
+//! let shape = VmShape::new(&ctx, root)?;
+//! # Ok::<(), fidget::Error>(())
+//! ```
+//!
+//! Models written in Rhai ([`sponge`] and [`gyroid_sphere`]) require the
+//! `rhai` feature.
+use crate::context::{Context, Node, Tree};
+
+/// Function which loads a model, returning a context and its root node
+pub type Builder = fn() -> (Context, Node);
+
+/// Every model in the corpus, as `(name, builder)` tuples
+pub const ALL: &[(&str, Builder)] = &[
+    ("circle", circle),
+    ("ring_text", ring_text),
+    ("bear", bear),
+    ("colonnade", colonnade),
+    ("hi", hi),
+    ("prospero", prospero),
+    #[cfg(feature = "rhai")]
+    ("sponge", sponge),
+    #[cfg(feature = "rhai")]
+    ("gyroid_sphere", gyroid_sphere),
+];
+
+fn from_vm(text: &str) -> (Context, Node) {
+    Context::from_text(text.as_bytes()).expect("corpus models are valid")
+}
+
+fn from_tree(tree: Tree) -> (Context, Node) {
+    let mut ctx = Context::new();
+    let root = ctx.import(&tree);
+    (ctx, root)
+}
+
+#[cfg(feature = "rhai")]
+fn from_rhai(script: &str) -> (Context, Node) {
+    use std::sync::{Arc, Mutex};
+
+    let mut engine = fidget_rhai::engine();
+    let out = Arc::new(Mutex::new(None));
+    let out_ = out.clone();
+    engine.register_fn("draw", move |t: Tree| {
+        *out_.lock().unwrap() = Some(t);
+    });
+    engine.run(script).expect("corpus scripts are valid");
+    let tree = out.lock().unwrap().take().expect("corpus scripts draw");
+    from_tree(tree)
+}
+
+/// Unit circle, centered at the origin
+///
+/// This model is 2D, i.e. independent of `z`.
+pub fn circle() -> (Context, Node) {
+    let (x, y, _) = Tree::axes();
+    from_tree((x.square() + y.square()).sqrt() - 1.0)
+}
+
+/// Copies of [`hi`] arranged around a ring of radius 0.75
+///
+/// Each copy is rotated so that its baseline is tangent to the ring.  This
+/// model is 2D and has many similar branches, which are pruned by interval
+/// arithmetic in different parts of the image.
+pub fn ring_text() -> (Context, Node) {
+    const COUNT: usize = 12;
+    const RADIUS: f64 = 0.75;
+    const SCALE: f64 = 0.3;
+
+    let (ctx, root) = hi();
+    let text = ctx.export(root).expect("corpus models are valid");
+    let (x, y, z) = Tree::axes();
+    let mut out: Option<Tree> = None;
+    for i in 0..COUNT {
+        // Rotate into a frame where this copy is centered on the +X axis,
+        // then map that frame into the text's unit square
+        let angle = std::f64::consts::TAU * i as f64 / COUNT as f64;
+        let (s, c) = angle.sin_cos();
+        let radial = x.clone() * c + y.clone() * s;
+        let tangent = y.clone() * c - x.clone() * s;
+        let u = -tangent / SCALE + 0.5;
+        let v = (radial - RADIUS) / SCALE + 0.5;
+        let t = text.remap_xyz(u, v, z.clone()) * SCALE;
+        out = Some(match out {
+            Some(prev) => prev.min(t),
+            None => t,
+        });
+    }
+    from_tree(out.unwrap())
+}
+
+/// Bear head, built from CSG operations on spheres (`models/bear.vm`)
+pub fn bear() -> (Context, Node) {
+    from_vm(include_str!("../models/bear.vm"))
+}
+
+/// Row of columns with arches (`models/colonnade.vm`)
+pub fn colonnade() -> (Context, Node) {
+    from_vm(include_str!("../models/colonnade.vm"))
+}
+
+/// The letters "hi" in the upper-right quadrant (`models/hi.vm`)
+///
+/// This model is 2D, i.e. independent of `z`.
+pub fn hi() -> (Context, Node) {
+    from_vm(include_str!("../models/hi.vm"))
+}
+
+/// A page of text, from the Prospero Challenge (`models/prospero.vm`)
+///
+/// This model is 2D and has thousands of clauses, making it a good stress
+/// test for tape simplification.
+pub fn prospero() -> (Context, Node) {
+    from_vm(include_str!("../models/prospero.vm"))
+}
+
+/// Sphere-ified Menger sponge (`models/sponge.rhai`)
+#[cfg(feature = "rhai")]
+pub fn sponge() -> (Context, Node) {
+    from_rhai(include_str!("../models/sponge.rhai"))
+}
+
+/// Gyroid lattice, clipped to a sphere (`models/gyroid-sphere.rhai`)
+///
+/// This uses trigonometric functions, which are expensive to evaluate.
+#[cfg(feature = "rhai")]
+pub fn gyroid_sphere() -> (Context, Node) {
+    from_rhai(include_str!("../models/gyroid-sphere.rhai"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(f: Builder, x: f64, y: f64, z: f64) -> f64 {
+        let (ctx, root) = f();
+        ctx.eval_xyz(root, x, y, z).unwrap()
+    }
+
+    #[test]
+    fn corpus_all() {
+        for (name, f) in ALL {
+            let (ctx, root) = f();
+            crate::vm::VmShape::new(&ctx, root)
+                .unwrap_or_else(|e| panic!("could not build {name}: {e}"));
+        }
+    }
+
+    #[test]
+    fn corpus_hi() {
+        assert!(eval(hi, -0.5, -0.5, 0.0) > 0.0);
+        assert!(eval(hi, 0.8, 0.35, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn corpus_circle() {
+        assert!(eval(circle, 0.0, 0.0, 0.0) < 0.0);
+        assert!((eval(circle, 0.6, 0.8, 0.0)).abs() < 1e-9);
+        assert!(eval(circle, 1.0, 1.0, 0.0) > 0.0);
+    }
+
+    #[test]
+    fn corpus_ring_text() {
+        // The ring is empty in the middle and outside
+        assert!(eval(ring_text, 0.0, 0.0, 0.0) > 0.0);
+        assert!(eval(ring_text, 1.5, 0.0, 0.0) > 0.0);
+
+        // Every copy is filled somewhere near the ring
+        let n = 720;
+        let filled = (0..n)
+            .filter(|i| {
+                let a = std::f64::consts::TAU * *i as f64 / n as f64;
+                (60..90).any(|r| {
+                    let r = r as f64 / 100.0;
+                    eval(ring_text, r * a.cos(), r * a.sin(), 0.0) < 0.0
+                })
+            })
+            .count();
+        assert!(filled > n / 4, "only {filled} / {n} directions are filled");
+    }
+
+    #[test]
+    fn corpus_bear() {
+        assert!(eval(bear, 0.0, 0.0, 0.0) < 0.0);
+        assert!(eval(bear, 2.0, 2.0, 2.0) > 0.0);
+    }
+
+    #[cfg(feature = "rhai")]
+    #[test]
+    fn corpus_sponge() {
+        assert!(eval(sponge, 0.7, 0.1, 0.6) < 0.0);
+        assert!(eval(sponge, 0.9, 0.9, 0.0) > 0.0);
+    }
+
+    #[cfg(feature = "rhai")]
+    #[test]
+    fn corpus_gyroid_sphere() {
+        assert!(eval(gyroid_sphere, 0.9, 0.9, 0.9) > 0.0);
+    }
+}
@@ -271,6 +271,11 @@
 //! [the `getrandom` docs](https://docs.rs/getrandom/latest/getrandom/#webassembly-support)
 //! for more details on why this is necessary.
 //!
+//! The JIT isn't available on WebAssembly, so evaluation uses the interpreter
+//! ([`fidget::vm`](vm)).  If the `simd128` target feature is enabled (e.g.
+//! `RUSTFLAGS='-C target-feature=+simd128'`), the interpreter's float slice
+//! evaluator processes arithmetic operations four values at a time.
+//!
 //! # Crate organization
 //! The Fidget crate is a thin wrapper about multiple smaller crates, for
 //! improved compilation speed and modulatiry.
@@ -290,6 +295,8 @@
 
 pub use fidget_core::*;
 
+pub mod corpus;
+
 #[cfg(feature = "rhai")]
 pub use fidget_rhai as rhai;
 
@@ -313,3 +320,6 @@ pub use fidget_gui as gui;
 
 #[cfg(all(feature = "jit", not(target_arch = "wasm32")))]
 pub use fidget_jit as jit;
+
+#[cfg(all(feature = "wgpu", not(target_arch = "wasm32")))]
+pub use fidget_wgpu as wgpu;
@@ -0,0 +1,125 @@
+//! Checks that render and mesh outputs don't depend on thread count
+use fidget::{
+    context::Context,
+    eval::{Function, MathFunction},
+    mesh::{Octree, Settings},
+    raster::{ImageRenderConfig, VoxelRenderConfig},
+    render::{ImageSize, RenderHints, ThreadPool, VoxelSize},
+    shape::Shape,
+};
+
+const COLONNADE: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/models/colonnade.vm"));
+
+/// Returns a set of thread pools to test, including single-threaded evaluation
+fn pools() -> Vec<Option<ThreadPool>> {
+    let mut out = vec![None];
+    for n in [1, 2, 3, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .unwrap();
+        out.push(Some(ThreadPool::Custom(pool)));
+    }
+    out
+}
+
+fn shape<F: Function + MathFunction>() -> Shape<F> {
+    let (ctx, root) = Context::from_text(COLONNADE.as_bytes()).unwrap();
+    Shape::new(&ctx, root).unwrap()
+}
+
+fn check_render2d<F: Function + MathFunction>() {
+    let shape = shape::<F>();
+    let mut prev: Option<Vec<u32>> = None;
+    for pool in pools() {
+        let cfg = ImageRenderConfig {
+            image_size: ImageSize::new(200, 150),
+            threads: pool.as_ref(),
+            pixel_perfect: true,
+            ..Default::default()
+        };
+        let image = cfg.run(shape.clone()).unwrap();
+        assert!(image.iter().any(|p| p.inside()));
+        let bits: Vec<u32> = image
+            .iter()
+            .map(|p| p.distance().unwrap().to_bits())
+            .collect();
+        if let Some(prev) = &prev {
+            assert!(prev == &bits, "2D render differs across thread counts");
+        }
+        prev = Some(bits);
+    }
+}
+
+fn check_render3d<F: Function + MathFunction>() {
+    let shape = shape::<F>();
+    let mut prev: Option<Vec<[u32; 4]>> = None;
+    for pool in pools() {
+        let cfg = VoxelRenderConfig {
+            image_size: VoxelSize::new(130, 100, 120),
+            threads: pool.as_ref(),
+            ..Default::default()
+        };
+        let image = cfg.run(shape.clone()).unwrap();
+        assert!(image.iter().any(|p| p.depth > 0.0));
+        let bits: Vec<[u32; 4]> = image
+            .iter()
+            .map(|p| {
+                let [a, b, c] = p.normal;
+                [p.depth.to_bits(), a.to_bits(), b.to_bits(), c.to_bits()]
+            })
+            .collect();
+        if let Some(prev) = &prev {
+            assert!(prev == &bits, "3D render differs across thread counts");
+        }
+        prev = Some(bits);
+    }
+}
+
+fn check_mesh<F: Function + MathFunction + RenderHints + Clone>() {
+    let shape = shape::<F>();
+    let mut prev = None;
+    for pool in pools() {
+        let settings = Settings {
+            depth: 6,
+            threads: pool.as_ref(),
+            ..Default::default()
+        };
+        let mesh = Octree::build(&shape, &settings).unwrap().walk_dual();
+        assert!(!mesh.triangles.is_empty());
+        let verts: Vec<[u32; 3]> = mesh
+            .vertices
+            .iter()
+            .map(|v| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()])
+            .collect();
+        let next = (verts, mesh.triangles);
+        if let Some(prev) = &prev {
+            assert!(prev == &next, "mesh differs across thread counts");
+        }
+        prev = Some(next);
+    }
+}
+
+macro_rules! determinism_tests {
+    ($i:ident, $ty:ty) => {
+        mod $i {
+            #[test]
+            fn render2d() {
+                super::check_render2d::<$ty>();
+            }
+            #[test]
+            fn render3d() {
+                super::check_render3d::<$ty>();
+            }
+            #[test]
+            fn mesh() {
+                super::check_mesh::<$ty>();
+            }
+        }
+    };
+}
+
+determinism_tests!(vm, fidget::vm::VmFunction);
+#[cfg(feature = "jit")]
+determinism_tests!(jit, fidget::jit::JitFunction);
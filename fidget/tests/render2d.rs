@@ -11,9 +11,9 @@ use fidget::{
 use nalgebra::Point2;
 
 const HI: &str =
-    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../models/hi.vm"));
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/models/hi.vm"));
 const QUARTER: &str =
-    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../models/quarter.vm"));
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/models/quarter.vm"));
 
 #[derive(Default)]
 struct Cfg {
@@ -23,11 +23,11 @@ struct Cfg {
 }
 
 impl Cfg {
-    fn test<F: Function>(&self, shape: Shape<F>, expected: &'static str) {
+    fn test<F: MathFunction>(&self, shape: Shape<F>, expected: &'static str) {
         self.test_with_mat(shape, nalgebra::Matrix3::identity(), expected);
     }
 
-    fn test_with_mat<F: Function>(
+    fn test_with_mat<F: MathFunction>(
         &self,
         shape: Shape<F>,
         world_to_model: nalgebra::Matrix3<f32>,
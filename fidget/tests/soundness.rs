@@ -0,0 +1,68 @@
+//! Checks interval soundness of every evaluator backend on sample models
+use fidget::{
+    check::{SoundnessSettings, interval_soundness, interval_soundness_with},
+    context::{Context, Tree},
+    eval::{Function, MathFunction},
+    shape::{Shape, ShapeVars},
+    types::Interval,
+};
+
+const MODELS: [&str; 2] = [
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/models/hi.vm")),
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/models/colonnade.vm")),
+];
+
+fn check_models<F: Function + MathFunction>() {
+    for m in MODELS {
+        let (ctx, root) = Context::from_text(m.as_bytes()).unwrap();
+        let shape = Shape::<F>::new(&ctx, root).unwrap();
+        interval_soundness(&shape, 200).unwrap();
+    }
+}
+
+fn check_transcendental<F: Function + MathFunction>() {
+    let (x, y, z) = Tree::axes();
+    let t = (x.clone() * 3.0).sin() * y.clone().cos()
+        + (z.clone().exp() - 1.0).atan()
+        + (x.square() + y.square() + 1.0).ln().sqrt()
+        - z.clone().atan2(x.clone()) / (y.abs() + 0.5);
+    let shape = Shape::<F>::from(t);
+    let settings = SoundnessSettings {
+        boxes: 200,
+        bounds: [Interval::new(-4.0, 4.0); 3],
+        ..Default::default()
+    };
+    interval_soundness_with(&shape, &ShapeVars::new(), &settings).unwrap();
+}
+
+fn check_coordinates<F: Function + MathFunction>() {
+    let (r, theta, z) = Tree::cylindrical();
+    let (rho, _, phi) = Tree::spherical();
+    // Mixing in `theta` ensures that boxes cross the branch cut
+    let t = r * theta.cos() + z * phi.sin() - rho + theta;
+    let shape = Shape::<F>::from(t);
+    interval_soundness(&shape, 200).unwrap();
+}
+
+macro_rules! soundness_tests {
+    ($i:ident, $ty:ty) => {
+        mod $i {
+            #[test]
+            fn models() {
+                super::check_models::<$ty>();
+            }
+            #[test]
+            fn transcendental() {
+                super::check_transcendental::<$ty>();
+            }
+            #[test]
+            fn coordinates() {
+                super::check_coordinates::<$ty>();
+            }
+        }
+    };
+}
+
+soundness_tests!(vm, fidget::vm::VmFunction);
+#[cfg(feature = "jit")]
+soundness_tests!(jit, fidget::jit::JitFunction);